@@ -1,7 +1,9 @@
+use std::collections::HashMap;
 use std::fmt;
 use std::str::FromStr;
 
-use anyhow::{Result, anyhow};
+use anyhow::{Context, Result, anyhow};
+use trivia_core::{Memory, MemorySummary, Storage, TagCount, User};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum AccessLevel {
@@ -27,7 +29,66 @@ impl FromStr for AccessLevel {
             "none" => Ok(AccessLevel::None),
             "read" => Ok(AccessLevel::Read),
             "update" => Ok(AccessLevel::Update),
-            _ => Err(anyhow!("invalid access level: {s} (expected none, read, or update)")),
+            _ => Err(anyhow!(
+                "invalid access level: {s} (expected none, read, or update)"
+            )),
+        }
+    }
+}
+
+/// A compiled rule pattern. `*` matches any run of characters (including
+/// none, and including `/`), so `project-*`, `team/backend/*` and `@*`
+/// select tag families; a pattern without `*` matches one tag exactly.
+#[derive(Debug, Clone)]
+enum Pattern {
+    Exact(String),
+    /// The literal pieces between `*`s, e.g. `team/*/docs` → ["team/", "/docs"].
+    Glob(Vec<String>),
+}
+
+impl Pattern {
+    fn parse(pattern: &str) -> Result<Self> {
+        if pattern.is_empty() {
+            return Err(anyhow!("empty ACL pattern"));
+        }
+        if let Some(c) = pattern
+            .chars()
+            .find(|c| matches!(c, '?' | '[' | ']' | '{' | '}'))
+        {
+            return Err(anyhow!(
+                "invalid ACL pattern {pattern:?}: unsupported character {c:?} (only * is a wildcard)"
+            ));
+        }
+        if pattern.contains("**") {
+            return Err(anyhow!(
+                "invalid ACL pattern {pattern:?}: use a single * (it already matches across /)"
+            ));
+        }
+        if !pattern.contains('*') {
+            return Ok(Pattern::Exact(pattern.to_string()));
+        }
+        Ok(Pattern::Glob(
+            pattern.split('*').map(str::to_string).collect(),
+        ))
+    }
+
+    fn matches(&self, tag: &str) -> bool {
+        match self {
+            Pattern::Exact(name) => name == tag,
+            Pattern::Glob(parts) => {
+                let (first, rest) = parts.split_first().expect("split yields a part");
+                let (last, middle) = rest.split_last().expect("glob has a *");
+                let Some(mut remaining) = tag.strip_prefix(first.as_str()) else {
+                    return false;
+                };
+                for part in middle {
+                    match remaining.find(part.as_str()) {
+                        Some(i) => remaining = &remaining[i + part.len()..],
+                        None => return false,
+                    }
+                }
+                remaining.len() >= last.len() && remaining.ends_with(last.as_str())
+            }
         }
     }
 }
@@ -35,22 +96,114 @@ impl FromStr for AccessLevel {
 #[derive(Debug, Clone)]
 struct AclRule {
     pattern: String,
+    matcher: Pattern,
     level: AccessLevel,
+    /// Written `pattern:!level`. Instead of granting `level`, caps any
+    /// memory carrying a matching tag at `level`, whatever its other tags
+    /// grant.
+    deny: bool,
+}
+
+impl AclRule {
+    fn new(pattern: &str, level: AccessLevel) -> Result<Self> {
+        Ok(AclRule {
+            pattern: pattern.to_string(),
+            matcher: Pattern::parse(pattern)?,
+            level,
+            deny: false,
+        })
+    }
+}
+
+/// What an access check looks at: a memory's tags and, if a signed-in user
+/// created it, its owner.
+pub trait Protected {
+    fn tags(&self) -> &[String];
+    fn owner(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// Tags alone, for memories that don't exist yet or checks where ownership
+/// doesn't apply.
+impl Protected for [String] {
+    fn tags(&self) -> &[String] {
+        self
+    }
+}
+
+impl Protected for Vec<String> {
+    fn tags(&self) -> &[String] {
+        self
+    }
+}
+
+impl<const N: usize> Protected for [String; N] {
+    fn tags(&self) -> &[String] {
+        self
+    }
+}
+
+impl Protected for Memory {
+    fn tags(&self) -> &[String] {
+        &self.tags
+    }
+    fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+}
+
+impl Protected for MemorySummary {
+    fn tags(&self) -> &[String] {
+        &self.tags
+    }
+    fn owner(&self) -> Option<&str> {
+        self.owner.as_deref()
+    }
+}
+
+/// Tags paired with an owner, e.g. a memory's new tags before an update.
+pub struct Owned<'a> {
+    pub tags: &'a [String],
+    pub owner: Option<&'a str>,
+}
+
+impl Protected for Owned<'_> {
+    fn tags(&self) -> &[String] {
+        self.tags
+    }
+    fn owner(&self) -> Option<&str> {
+        self.owner
+    }
 }
 
+/// One or more rule sets. A single spec parses to one set; `Acl::union`
+/// (a user's ACL plus their groups') keeps each source's rules apart so
+/// first-match precedence stays within the spec it was written in.
+///
+/// The signed-in caller, if any, is carried alongside: they get update on
+/// memories they own, and with `owner_only` nobody else sees those.
 #[derive(Debug, Clone)]
 pub struct Acl {
-    rules: Vec<AclRule>,
+    sets: Vec<Vec<AclRule>>,
+    caller: Option<String>,
+    owner_only: bool,
 }
 
 impl fmt::Display for Acl {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let parts: Vec<String> = self
-            .rules
+        let sets: Vec<String> = self
+            .sets
             .iter()
-            .map(|r| format!("{}:{}", r.pattern, r.level))
+            .map(|rules| {
+                rules
+                    .iter()
+                    .map(|r| format!("{}:{}{}", r.pattern, if r.deny { "!" } else { "" }, r.level))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
             .collect();
-        write!(f, "{}", parts.join(","))
+        write!(f, "{}", sets.join(";"))
     }
 }
 
@@ -58,81 +211,240 @@ impl Acl {
     /// Everything allowed — for stdio MCP (local, trusted).
     pub fn open() -> Self {
         Acl {
-            rules: vec![AclRule {
-                pattern: "*".to_string(),
-                level: AccessLevel::Update,
-            }],
+            sets: vec![vec![
+                AclRule::new("*", AccessLevel::Update).expect("valid pattern"),
+            ]],
+            caller: None,
+            owner_only: false,
         }
     }
 
     /// Everything denied — default when `--share` is not provided.
     pub fn closed() -> Self {
         Acl {
-            rules: vec![AclRule {
-                pattern: "*".to_string(),
-                level: AccessLevel::None,
-            }],
+            sets: vec![vec![
+                AclRule::new("*", AccessLevel::None).expect("valid pattern"),
+            ]],
+            caller: None,
+            owner_only: false,
         }
     }
 
     pub fn is_open(&self) -> bool {
-        self.rules.len() == 1
-            && self.rules[0].pattern == "*"
-            && self.rules[0].level == AccessLevel::Update
+        let rules = || self.sets.iter().flatten();
+        rules().all(|rule| !rule.deny)
+            && self.sets.iter().any(|rules| {
+                rules.len() == 1 && rules[0].pattern == "*" && rules[0].level == AccessLevel::Update
+            })
     }
 
-    /// Parse a spec like "project:read,notes:update,*:none".
+    /// Parse a spec like "project:read,notes:update,team/*:read,*:none".
+    /// Patterns may contain `*` wildcards; see `Pattern`. A `!` before the
+    /// level makes the rule restrictive, e.g. "private:!none". Rule sets
+    /// separated by `;` are combined as by `Acl::union`.
     pub fn parse(spec: &str) -> Result<Self> {
-        let mut rules = Vec::new();
-        for part in spec.split(',') {
-            let part = part.trim();
-            if part.is_empty() {
-                continue;
+        let mut sets = Vec::new();
+        for set in spec.split(';') {
+            let mut rules = Vec::new();
+            for part in set.split(',') {
+                let part = part.trim();
+                if part.is_empty() {
+                    continue;
+                }
+                let (pattern, level_str) = part
+                    .rsplit_once(':')
+                    .ok_or_else(|| anyhow!("invalid ACL rule (expected pattern:level): {part}"))?;
+                let level_str = level_str.trim();
+                let (deny, level_str) = match level_str.strip_prefix('!') {
+                    Some(rest) => (true, rest.trim_start()),
+                    None => (false, level_str),
+                };
+                let level = level_str.parse::<AccessLevel>()?;
+                rules.push(AclRule {
+                    deny,
+                    ..AclRule::new(pattern.trim(), level)?
+                });
+            }
+            if !rules.is_empty() {
+                sets.push(rules);
             }
-            let (pattern, level_str) = part
-                .rsplit_once(':')
-                .ok_or_else(|| anyhow!("invalid ACL rule (expected pattern:level): {part}"))?;
-            let level = level_str.trim().parse::<AccessLevel>()?;
-            rules.push(AclRule {
-                pattern: pattern.trim().to_string(),
-                level,
-            });
-        }
-        if rules.is_empty() {
+        }
+        if sets.is_empty() {
             return Err(anyhow!("empty ACL spec"));
         }
-        Ok(Acl { rules })
+        Ok(Acl {
+            sets,
+            caller: None,
+            owner_only: false,
+        })
     }
 
-    /// Access level for a single tag. First-match-wins.
-    pub fn tag_level(&self, tag: &str) -> AccessLevel {
-        for rule in &self.rules {
-            if rule.pattern == "*" || rule.pattern == tag {
-                return rule.level;
-            }
+    /// Combine ACLs, e.g. a user's own with those of their groups. A tag
+    /// gets the highest level any of them grants, and every deny rule still
+    /// caps it, so a restriction in one group can't be lifted by another.
+    /// The union of nothing is closed.
+    pub fn union(acls: impl IntoIterator<Item = Acl>) -> Self {
+        let sets: Vec<Vec<AclRule>> = acls.into_iter().flat_map(|acl| acl.sets).collect();
+        if sets.is_empty() {
+            return Acl::closed();
         }
-        AccessLevel::None
+        Acl {
+            sets,
+            caller: None,
+            owner_only: false,
+        }
+    }
+
+    /// Cap every memory at `level`, including the caller's own, e.g. for a
+    /// token granted only read access.
+    pub fn capped(mut self, level: AccessLevel) -> Self {
+        if level < AccessLevel::Update {
+            self.sets.push(vec![AclRule {
+                deny: true,
+                ..AclRule::new("*", level).expect("valid pattern")
+            }]);
+        }
+        self
+    }
+
+    /// Apply this ACL on behalf of `caller`, who may edit memories they own.
+    /// With `owner_only`, memories owned by anyone else are hidden from
+    /// them unless the ACL is open.
+    pub fn for_caller(mut self, caller: Option<String>, owner_only: bool) -> Self {
+        self.caller = caller;
+        self.owner_only = owner_only;
+        self
+    }
+
+    /// Level granted to a tag by the allow rules. Within a set they are
+    /// tried in the order written and the first whose pattern matches wins,
+    /// so put specific rules before broader globs:
+    /// `project-secret:none,project-*:update`. Across sets the highest wins.
+    fn granted_level(&self, tag: &str) -> AccessLevel {
+        self.sets
+            .iter()
+            .map(|rules| {
+                rules
+                    .iter()
+                    .filter(|rule| !rule.deny)
+                    .find(|rule| rule.matcher.matches(tag))
+                    .map_or(AccessLevel::None, |rule| rule.level)
+            })
+            .max()
+            .unwrap_or(AccessLevel::None)
     }
 
-    /// Effective access level for a memory. Max across all its tags.
+    /// Lowest cap among the deny rules matching a tag, in any position and
+    /// any set.
+    fn cap(&self, tag: &str) -> Option<AccessLevel> {
+        self.sets
+            .iter()
+            .flatten()
+            .filter(|rule| rule.deny && rule.matcher.matches(tag))
+            .map(|rule| rule.level)
+            .min()
+    }
+
+    /// Access level for a single tag: what the allow rules grant it,
+    /// capped by any deny rule matching it.
+    pub fn tag_level(&self, tag: &str) -> AccessLevel {
+        let level = self.granted_level(tag);
+        self.cap(tag).map_or(level, |cap| level.min(cap))
+    }
+
+    /// Effective access level for a memory: the max granted across all its
+    /// tags, capped by the strictest deny rule matching any of them.
     /// Untagged memories match against `*`.
     pub fn memory_level(&self, tags: &[String]) -> AccessLevel {
         if tags.is_empty() {
             return self.tag_level("*");
         }
-        tags.iter()
-            .map(|t| self.tag_level(t))
+        let granted = tags
+            .iter()
+            .map(|t| self.granted_level(t))
             .max()
-            .unwrap_or(AccessLevel::None)
+            .unwrap_or(AccessLevel::None);
+        match tags.iter().filter_map(|t| self.cap(t)).min() {
+            Some(cap) => granted.min(cap),
+            None => granted,
+        }
     }
 
-    pub fn check_read(&self, tags: &[String]) -> bool {
-        self.memory_level(tags) >= AccessLevel::Read
+    /// Access level for a memory, taking ownership into account. Its owner
+    /// gets update, though deny rules still cap it; with `owner_only`, a
+    /// memory owned by someone else is out of reach.
+    pub fn level<T: Protected + ?Sized>(&self, memory: &T) -> AccessLevel {
+        let tags = memory.tags();
+        let Some(owner) = memory.owner() else {
+            return self.memory_level(tags);
+        };
+        if self.caller.as_deref() != Some(owner) {
+            if self.owner_only && !self.is_open() {
+                return AccessLevel::None;
+            }
+            return self.memory_level(tags);
+        }
+        let cap = if tags.is_empty() {
+            self.cap("*")
+        } else {
+            tags.iter().filter_map(|t| self.cap(t)).min()
+        };
+        cap.map_or(AccessLevel::Update, |cap| cap.min(AccessLevel::Update))
     }
 
-    pub fn check_update(&self, tags: &[String]) -> bool {
-        self.memory_level(tags) >= AccessLevel::Update
+    /// Tag counts as this ACL sees them: only readable memories are
+    /// counted and only readable tags listed, so a tag shared with a denied
+    /// memory doesn't reveal it. Tags on the caller's own memories are
+    /// listed too. Ordered like `Storage::list_tags`.
+    pub fn visible_tags<'a, T: Protected + ?Sized + 'a>(
+        &self,
+        memories: impl IntoIterator<Item = &'a T>,
+    ) -> Vec<TagCount> {
+        let mut counts: HashMap<&str, i64> = HashMap::new();
+        for memory in memories {
+            if !self.check_read(memory) {
+                continue;
+            }
+            let own = memory.owner().is_some() && memory.owner() == self.caller.as_deref();
+            for tag in memory.tags() {
+                if own || self.tag_level(tag) >= AccessLevel::Read {
+                    *counts.entry(tag).or_default() += 1;
+                }
+            }
+        }
+        let mut result: Vec<TagCount> = counts
+            .into_iter()
+            .map(|(tag, count)| TagCount {
+                tag: tag.to_string(),
+                count,
+            })
+            .collect();
+        result.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.tag.cmp(&b.tag)));
+        result
     }
+
+    pub fn check_read<T: Protected + ?Sized>(&self, memory: &T) -> bool {
+        self.level(memory) >= AccessLevel::Read
+    }
+
+    pub fn check_update<T: Protected + ?Sized>(&self, memory: &T) -> bool {
+        self.level(memory) >= AccessLevel::Update
+    }
+}
+
+/// What `user` may do: their own ACL unioned with those of every group they
+/// belong to.
+pub fn effective_acl(store: &dyn Storage, user: &User) -> Result<Acl> {
+    let mut acls = vec![
+        Acl::parse(&user.acl).with_context(|| format!("invalid ACL for user {}", user.username))?,
+    ];
+    for group in store.groups_for_user(user.id)? {
+        acls.push(
+            Acl::parse(&group.acl)
+                .with_context(|| format!("invalid ACL for group {}", group.name))?,
+        );
+    }
+    Ok(Acl::union(acls))
 }
 
 #[cfg(test)]
@@ -168,14 +480,8 @@ mod tests {
             acl.memory_level(&["notes".into(), "project".into()]),
             AccessLevel::Update
         );
-        assert_eq!(
-            acl.memory_level(&["notes".into()]),
-            AccessLevel::Read
-        );
-        assert_eq!(
-            acl.memory_level(&["other".into()]),
-            AccessLevel::None
-        );
+        assert_eq!(acl.memory_level(&["notes".into()]), AccessLevel::Read);
+        assert_eq!(acl.memory_level(&["other".into()]), AccessLevel::None);
     }
 
     #[test]
@@ -218,6 +524,243 @@ mod tests {
         assert!(Acl::parse("tag:invalid_level").is_err());
     }
 
+    #[test]
+    fn glob_patterns() {
+        let acl = Acl::parse("project-*:update,team/backend/*:read,@*:read,*:none").unwrap();
+        assert_eq!(acl.tag_level("project-alpha"), AccessLevel::Update);
+        assert_eq!(acl.tag_level("project-"), AccessLevel::Update);
+        assert_eq!(acl.tag_level("project"), AccessLevel::None);
+        assert_eq!(acl.tag_level("team/backend/api"), AccessLevel::Read);
+        assert_eq!(acl.tag_level("team/backend/api/v2"), AccessLevel::Read);
+        assert_eq!(acl.tag_level("team/frontend/ui"), AccessLevel::None);
+        assert_eq!(acl.tag_level("@alice"), AccessLevel::Read);
+        assert_eq!(acl.tag_level("alice"), AccessLevel::None);
+
+        let acl = Acl::parse("*-notes:read,team/*/docs:update").unwrap();
+        assert_eq!(acl.tag_level("design-notes"), AccessLevel::Read);
+        assert_eq!(acl.tag_level("notes"), AccessLevel::None);
+        assert_eq!(acl.tag_level("team/infra/docs"), AccessLevel::Update);
+        assert_eq!(acl.tag_level("team/docs"), AccessLevel::None);
+    }
+
+    #[test]
+    fn glob_precedence_is_rule_order() {
+        let acl = Acl::parse("project-secret:none,project-*:update,*:read").unwrap();
+        assert_eq!(acl.tag_level("project-secret"), AccessLevel::None);
+        assert_eq!(acl.tag_level("project-alpha"), AccessLevel::Update);
+
+        // A broad glob first shadows the exact rule after it
+        let acl = Acl::parse("project-*:read,project-alpha:update").unwrap();
+        assert_eq!(acl.tag_level("project-alpha"), AccessLevel::Read);
+    }
+
+    #[test]
+    fn glob_memory_level_max_across_tags() {
+        let acl = Acl::parse("project-*:read,@*:update,*:none").unwrap();
+        assert_eq!(
+            acl.memory_level(&["project-alpha".into(), "@alice".into()]),
+            AccessLevel::Update
+        );
+        assert_eq!(
+            acl.memory_level(&["project-alpha".into(), "other".into()]),
+            AccessLevel::Read
+        );
+        // Untagged memories match only rules that match the literal `*`
+        assert_eq!(acl.memory_level(&[]), AccessLevel::None);
+    }
+
+    #[test]
+    fn glob_parse_errors() {
+        assert!(Acl::parse("project-**:read").is_err());
+        assert!(Acl::parse("project-?:read").is_err());
+        assert!(Acl::parse("project-[ab]:read").is_err());
+        assert!(Acl::parse("{a,b}:read").is_err());
+        assert!(Acl::parse(":read").is_err());
+    }
+
+    #[test]
+    fn display_round_trips_globs() {
+        let spec = "project-*:update,@*:read,*:none";
+        assert_eq!(Acl::parse(spec).unwrap().to_string(), spec);
+    }
+
+    #[test]
+    fn deny_caps_memory_level() {
+        let acl = Acl::parse("project:update,private:!none,draft:!read,*:read").unwrap();
+        assert_eq!(acl.memory_level(&["project".into()]), AccessLevel::Update);
+        // Without the deny, project would grant update to this memory
+        assert_eq!(
+            acl.memory_level(&["project".into(), "private".into()]),
+            AccessLevel::None
+        );
+        assert_eq!(
+            acl.memory_level(&["project".into(), "draft".into()]),
+            AccessLevel::Read
+        );
+        assert_eq!(
+            acl.memory_level(&["draft".into(), "private".into()]),
+            AccessLevel::None
+        );
+        assert!(!acl.check_read(&["private".into(), "project".into()]));
+    }
+
+    #[test]
+    fn deny_applies_regardless_of_order() {
+        let acl = Acl::parse("*:update,secret-*:!none").unwrap();
+        assert_eq!(acl.tag_level("secret-plans"), AccessLevel::None);
+        assert_eq!(acl.tag_level("plans"), AccessLevel::Update);
+        assert_eq!(
+            acl.memory_level(&["plans".into(), "secret-plans".into()]),
+            AccessLevel::None
+        );
+        assert!(!acl.is_open());
+    }
+
+    #[test]
+    fn deny_never_raises_level() {
+        let acl = Acl::parse("project:read,project:!update,*:none").unwrap();
+        assert_eq!(acl.tag_level("project"), AccessLevel::Read);
+        assert_eq!(acl.tag_level("other"), AccessLevel::None);
+    }
+
+    #[test]
+    fn deny_round_trips_and_parse_errors() {
+        let spec = "project:update,private:!none,*:read";
+        assert_eq!(Acl::parse(spec).unwrap().to_string(), spec);
+        assert_eq!(
+            Acl::parse("private: ! none").unwrap().to_string(),
+            "private:!none"
+        );
+        assert!(Acl::parse("private:!").is_err());
+        assert!(Acl::parse("private:!!none").is_err());
+    }
+
+    #[test]
+    fn visible_tags_skip_denied_memories() {
+        let acl = Acl::parse("project:read,notes:read,private:!none,*:none").unwrap();
+        let memories: Vec<Vec<String>> = vec![
+            vec!["project".into()],
+            vec!["project".into(), "private".into()],
+            vec!["notes".into(), "project".into(), "other".into()],
+        ];
+        let tags = acl.visible_tags(memories.iter().map(|t| t.as_slice()));
+        let tags: Vec<(&str, i64)> = tags.iter().map(|t| (t.tag.as_str(), t.count)).collect();
+        assert_eq!(tags, [("project", 2), ("notes", 1)]);
+    }
+
+    #[test]
+    fn union_takes_highest_grant_per_set() {
+        let user = Acl::parse("@alice:update,*:none").unwrap();
+        let eng = Acl::parse("project-*:update,*:read").unwrap();
+        let readers = Acl::parse("project-secret:none,*:read").unwrap();
+        let acl = Acl::union([user, eng, readers]);
+        assert_eq!(acl.tag_level("@alice"), AccessLevel::Update);
+        // readers' first match doesn't shadow eng's grant
+        assert_eq!(acl.tag_level("project-secret"), AccessLevel::Update);
+        assert_eq!(acl.tag_level("other"), AccessLevel::Read);
+        assert!(!acl.is_open());
+
+        assert!(Acl::union([Acl::closed(), Acl::open()]).is_open());
+        assert!(!Acl::union([]).check_read(&[]));
+    }
+
+    #[test]
+    fn union_deny_beats_other_grants() {
+        let acl = Acl::union([
+            Acl::parse("*:update").unwrap(),
+            Acl::parse("hr:!none,*:read").unwrap(),
+        ]);
+        assert_eq!(acl.tag_level("hr"), AccessLevel::None);
+        assert_eq!(
+            acl.memory_level(&["project".into(), "hr".into()]),
+            AccessLevel::None
+        );
+        assert_eq!(acl.tag_level("project"), AccessLevel::Update);
+        assert!(!acl.is_open());
+    }
+
+    #[test]
+    fn union_round_trips_through_display() {
+        let acl = Acl::union([
+            Acl::parse("@bob:update,*:none").unwrap(),
+            Acl::parse("team/*:read,hr:!none").unwrap(),
+        ]);
+        let spec = acl.to_string();
+        assert_eq!(spec, "@bob:update,*:none;team/*:read,hr:!none");
+        let reparsed = Acl::parse(&spec).unwrap();
+        assert_eq!(reparsed.tag_level("team/x"), AccessLevel::Read);
+        assert_eq!(reparsed.tag_level("@bob"), AccessLevel::Update);
+        assert!(Acl::parse(";;").is_err());
+    }
+
+    #[test]
+    fn owner_gets_update_capped_by_deny() {
+        let acl = Acl::parse("hr:!read,*:none")
+            .unwrap()
+            .for_caller(Some("alice".into()), false);
+        let mine = Owned {
+            tags: &["project".into()],
+            owner: Some("alice"),
+        };
+        let theirs = Owned {
+            tags: &["project".into()],
+            owner: Some("bob"),
+        };
+        let mine_hr = Owned {
+            tags: &["hr".into()],
+            owner: Some("alice"),
+        };
+        assert_eq!(acl.level(&mine), AccessLevel::Update);
+        assert_eq!(acl.level(&theirs), AccessLevel::None);
+        assert_eq!(acl.level(&mine_hr), AccessLevel::Read);
+    }
+
+    #[test]
+    fn owner_only_hides_other_owners() {
+        let acl = Acl::parse("*:read")
+            .unwrap()
+            .for_caller(Some("alice".into()), true);
+        let theirs = Owned {
+            tags: &["project".into()],
+            owner: Some("bob"),
+        };
+        let unowned = Owned {
+            tags: &["project".into()],
+            owner: None,
+        };
+        assert_eq!(acl.level(&theirs), AccessLevel::None);
+        assert_eq!(acl.level(&unowned), AccessLevel::Read);
+        let open = Acl::open().for_caller(Some("alice".into()), true);
+        assert_eq!(open.level(&theirs), AccessLevel::Update);
+    }
+
+    #[test]
+    fn capped_limits_everything_and_round_trips() {
+        let acl = Acl::parse("project:update,*:read")
+            .unwrap()
+            .capped(AccessLevel::Read)
+            .for_caller(Some("alice".into()), false);
+        let mine = Owned {
+            tags: &["project".into()],
+            owner: Some("alice"),
+        };
+        assert_eq!(acl.tag_level("project"), AccessLevel::Read);
+        assert_eq!(acl.memory_level(&[]), AccessLevel::Read);
+        assert_eq!(acl.level(&mine), AccessLevel::Read);
+        assert!(!acl.is_open());
+        assert!(!Acl::open().capped(AccessLevel::Read).is_open());
+        assert!(Acl::open().capped(AccessLevel::Update).is_open());
+
+        // Claims carry the ACL as a string
+        let reparsed = Acl::parse(&acl.to_string()).unwrap();
+        assert_eq!(reparsed.tag_level("project"), AccessLevel::Read);
+        let none = Acl::open().capped(AccessLevel::None);
+        assert_eq!(
+            Acl::parse(&none.to_string()).unwrap().tag_level("x"),
+            AccessLevel::None
+        );
+    }
+
     #[test]
     fn whitespace_trimmed() {
         let acl = Acl::parse(" project : update , * : none ").unwrap();
@@ -0,0 +1,758 @@
+//! `/api/admin/*`: manage users, groups, providers, identities, sessions,
+//! tokens, OAuth clients and maintenance over HTTP, for servers where shelling in to run
+//! `trivia admin` isn't an option. Every route needs the admin role, and
+//! every change is written to the audit log.
+
+use std::sync::Arc;
+
+use axum::{
+    Extension, Router,
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{delete, get, put},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tower_mcp::oauth::token::TokenClaims;
+use trivia_core::{
+    AuditEntry, AuditFilter, OAuthClient, OAuthProvider, Storage, TriviaError, User,
+};
+
+use crate::acl::{self, Acl};
+use crate::auth_middleware::is_admin;
+use crate::maintenance::{self, MaintenanceRun, MaintenanceStatus, SharedStatus};
+use crate::oauth::SharedStore;
+use crate::providers::Provider;
+use crate::providers::oidc::OidcConfig;
+use crate::www::AppResult;
+
+struct AdminState {
+    store: SharedStore,
+    maintenance: SharedStatus,
+}
+
+pub fn admin_router(store: SharedStore, maintenance: SharedStatus) -> Router {
+    let state = Arc::new(AdminState { store, maintenance });
+    Router::new()
+        .route("/api/admin/users", get(list_users).post(create_user))
+        .route(
+            "/api/admin/users/{username}",
+            get(show_user).put(update_user).delete(delete_user),
+        )
+        .route(
+            "/api/admin/users/{username}/sessions",
+            delete(revoke_sessions),
+        )
+        .route("/api/admin/users/{username}/tokens", delete(revoke_tokens))
+        .route(
+            "/api/admin/users/{username}/identities",
+            axum::routing::post(link_identity),
+        )
+        .route(
+            "/api/admin/users/{username}/identities/{provider}",
+            delete(unlink_identity),
+        )
+        .route("/api/admin/groups", get(list_groups).post(create_group))
+        .route(
+            "/api/admin/groups/{name}",
+            get(show_group).put(update_group).delete(delete_group),
+        )
+        .route(
+            "/api/admin/groups/{name}/members/{username}",
+            put(add_member).delete(remove_member),
+        )
+        .route(
+            "/api/admin/providers",
+            get(list_providers).post(create_provider),
+        )
+        .route(
+            "/api/admin/providers/{name}",
+            put(update_provider).delete(delete_provider),
+        )
+        .route("/api/admin/clients", get(list_clients))
+        .route("/api/admin/clients/{client_id}", delete(delete_client))
+        .route("/api/admin/audit", get(list_audit))
+        .route(
+            "/api/admin/maintenance",
+            get(maintenance_status).post(run_maintenance),
+        )
+        .with_state(state)
+}
+
+fn require_admin(claims: &TokenClaims) -> Result<(), TriviaError> {
+    if is_admin(claims) {
+        Ok(())
+    } else {
+        Err(TriviaError::access_denied("admin role required"))
+    }
+}
+
+fn validate_acl(spec: &str) -> Result<(), TriviaError> {
+    Acl::parse(spec)
+        .map(|_| ())
+        .map_err(|e| TriviaError::validation(e.to_string()))
+}
+
+fn find_user(store: &dyn Storage, username: &str) -> Result<User, TriviaError> {
+    store
+        .get_user_by_username(username)?
+        .ok_or_else(|| TriviaError::not_found(format!("user not found: {username}")))
+}
+
+fn audit(
+    store: &dyn Storage,
+    claims: &TokenClaims,
+    action: &str,
+    target: &str,
+    detail: Option<String>,
+) -> Result<(), TriviaError> {
+    store.record_audit(
+        claims.sub.as_deref(),
+        action,
+        Some(target),
+        detail.as_deref(),
+    )
+}
+
+fn ok() -> axum::Json<serde_json::Value> {
+    axum::Json(serde_json::json!({"ok": true}))
+}
+
+// --- Users ---
+
+#[derive(Serialize)]
+struct UserView {
+    username: String,
+    acl: String,
+    is_admin: bool,
+    created_at: DateTime<Utc>,
+    last_seen_at: Option<DateTime<Utc>>,
+    groups: Vec<String>,
+    sessions: i64,
+    tokens: i64,
+}
+
+#[derive(Serialize)]
+struct IdentityView {
+    provider: String,
+    provider_username: String,
+    provider_user_id: String,
+    created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct UserDetail {
+    #[serde(flatten)]
+    user: UserView,
+    effective_acl: String,
+    identities: Vec<IdentityView>,
+}
+
+fn user_view(store: &dyn Storage, user: User) -> Result<UserView, TriviaError> {
+    let activity = store.user_activity(user.id)?;
+    let groups = store
+        .groups_for_user(user.id)?
+        .into_iter()
+        .map(|g| g.name)
+        .collect();
+    Ok(UserView {
+        username: user.username,
+        acl: user.acl,
+        is_admin: user.is_admin,
+        created_at: user.created_at,
+        last_seen_at: activity.last_seen_at,
+        groups,
+        sessions: activity.sessions,
+        tokens: activity.tokens,
+    })
+}
+
+async fn list_users(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    let store = state.store.lock().await;
+    let users = store
+        .list_users()?
+        .into_iter()
+        .map(|u| user_view(&**store, u))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(axum::Json(users))
+}
+
+async fn show_user(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+    Path(username): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    let store = state.store.lock().await;
+    let user = find_user(&**store, &username)?;
+    let effective_acl = acl::effective_acl(&**store, &user)?.to_string();
+    let providers = store.list_providers()?;
+    let identities = store
+        .list_identities_for_user(user.id)?
+        .into_iter()
+        .map(|i| IdentityView {
+            provider: providers
+                .iter()
+                .find(|p| p.id == i.provider_id)
+                .map_or_else(String::new, |p| p.name.clone()),
+            provider_username: i.provider_username,
+            provider_user_id: i.provider_user_id,
+            created_at: i.created_at,
+        })
+        .collect();
+    Ok(axum::Json(UserDetail {
+        user: user_view(&**store, user)?,
+        effective_acl,
+        identities,
+    }))
+}
+
+#[derive(Deserialize)]
+struct CreateUserReq {
+    username: String,
+    #[serde(default = "default_acl")]
+    acl: String,
+    #[serde(default)]
+    is_admin: bool,
+}
+
+fn default_acl() -> String {
+    "*:none".into()
+}
+
+async fn create_user(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+    axum::Json(body): axum::Json<CreateUserReq>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    validate_acl(&body.acl)?;
+    let store = state.store.lock().await;
+    store.create_user(&body.username, &body.acl)?;
+    if body.is_admin {
+        store.set_user_admin(&body.username, true)?;
+    }
+    let detail = format!("acl: {}, admin: {}", body.acl, body.is_admin);
+    audit(
+        &**store,
+        &claims,
+        "user.create",
+        &body.username,
+        Some(detail),
+    )?;
+    Ok((StatusCode::CREATED, ok()))
+}
+
+#[derive(Deserialize)]
+struct UpdateUserReq {
+    acl: Option<String>,
+    is_admin: Option<bool>,
+}
+
+async fn update_user(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+    Path(username): Path<String>,
+    axum::Json(body): axum::Json<UpdateUserReq>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    if body.is_admin == Some(false) && claims.sub.as_deref() == Some(username.as_str()) {
+        return Err(TriviaError::validation("you can't remove your own admin role").into());
+    }
+    let store = state.store.lock().await;
+    let user = find_user(&**store, &username)?;
+    if let Some(acl) = &body.acl {
+        validate_acl(acl)?;
+        store.update_user_acl(&username, acl)?;
+        let detail = format!("{} -> {acl}", user.acl);
+        audit(&**store, &claims, "user.set_acl", &username, Some(detail))?;
+    }
+    if let Some(admin) = body.is_admin {
+        store.set_user_admin(&username, admin)?;
+        let action = if admin {
+            "user.grant_admin"
+        } else {
+            "user.revoke_admin"
+        };
+        audit(&**store, &claims, action, &username, None)?;
+    }
+    Ok(ok())
+}
+
+async fn delete_user(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+    Path(username): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    if claims.sub.as_deref() == Some(username.as_str()) {
+        return Err(TriviaError::validation("you can't delete yourself").into());
+    }
+    let store = state.store.lock().await;
+    let user = find_user(&**store, &username)?;
+    // Tokens and sessions don't cascade, so clear them first
+    store.revoke_user_tokens(user.id)?;
+    store.revoke_user_sessions(user.id)?;
+    store.delete_user(&username)?;
+    audit(&**store, &claims, "user.delete", &username, None)?;
+    Ok(ok())
+}
+
+async fn revoke_sessions(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+    Path(username): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    let store = state.store.lock().await;
+    let user = find_user(&**store, &username)?;
+    let revoked = store.revoke_user_sessions(user.id)?;
+    audit(
+        &**store,
+        &claims,
+        "user.revoke_sessions",
+        &username,
+        Some(format!("{revoked} revoked")),
+    )?;
+    Ok(axum::Json(
+        serde_json::json!({"ok": true, "revoked": revoked}),
+    ))
+}
+
+async fn revoke_tokens(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+    Path(username): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    let store = state.store.lock().await;
+    let user = find_user(&**store, &username)?;
+    let revoked = store.revoke_user_tokens(user.id)?;
+    audit(
+        &**store,
+        &claims,
+        "user.revoke_tokens",
+        &username,
+        Some(format!("{revoked} revoked")),
+    )?;
+    Ok(axum::Json(
+        serde_json::json!({"ok": true, "revoked": revoked}),
+    ))
+}
+
+// --- Identities ---
+
+#[derive(Deserialize)]
+struct LinkIdentityReq {
+    provider: String,
+    provider_username: String,
+    /// Stable provider user ID. Defaults to `provider_username`.
+    provider_user_id: Option<String>,
+}
+
+async fn link_identity(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+    Path(username): Path<String>,
+    axum::Json(body): axum::Json<LinkIdentityReq>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    let store = state.store.lock().await;
+    let user = find_user(&**store, &username)?;
+    let provider = store
+        .get_provider_by_name(&body.provider)?
+        .ok_or_else(|| TriviaError::not_found(format!("provider not found: {}", body.provider)))?;
+    let puid = body
+        .provider_user_id
+        .as_deref()
+        .unwrap_or(&body.provider_username);
+    store.link_identity(user.id, provider.id, &body.provider_username, puid)?;
+    let detail = format!(
+        "{} as {} (id: {puid})",
+        body.provider, body.provider_username
+    );
+    audit(&**store, &claims, "identity.link", &username, Some(detail))?;
+    Ok((StatusCode::CREATED, ok()))
+}
+
+async fn unlink_identity(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+    Path((username, provider)): Path<(String, String)>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    let store = state.store.lock().await;
+    let user = find_user(&**store, &username)?;
+    let prov = store
+        .get_provider_by_name(&provider)?
+        .ok_or_else(|| TriviaError::not_found(format!("provider not found: {provider}")))?;
+    if !store.unlink_identity(user.id, prov.id)? {
+        return Err(
+            TriviaError::not_found(format!("{username} has no {provider} identity")).into(),
+        );
+    }
+    audit(
+        &**store,
+        &claims,
+        "identity.unlink",
+        &username,
+        Some(provider),
+    )?;
+    Ok(ok())
+}
+
+// --- Groups ---
+
+#[derive(Serialize)]
+struct GroupView {
+    name: String,
+    acl: String,
+    created_at: DateTime<Utc>,
+    members: Vec<String>,
+}
+
+fn group_view(store: &dyn Storage, group: trivia_core::Group) -> Result<GroupView, TriviaError> {
+    let members = store
+        .list_group_members(&group.name)?
+        .into_iter()
+        .map(|u| u.username)
+        .collect();
+    Ok(GroupView {
+        name: group.name,
+        acl: group.acl,
+        created_at: group.created_at,
+        members,
+    })
+}
+
+async fn list_groups(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    let store = state.store.lock().await;
+    let groups = store
+        .list_groups()?
+        .into_iter()
+        .map(|g| group_view(&**store, g))
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(axum::Json(groups))
+}
+
+async fn show_group(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+    Path(name): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    let store = state.store.lock().await;
+    let group = store
+        .get_group_by_name(&name)?
+        .ok_or_else(|| TriviaError::not_found(format!("group not found: {name}")))?;
+    Ok(axum::Json(group_view(&**store, group)?))
+}
+
+#[derive(Deserialize)]
+struct CreateGroupReq {
+    name: String,
+    acl: String,
+}
+
+async fn create_group(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+    axum::Json(body): axum::Json<CreateGroupReq>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    validate_acl(&body.acl)?;
+    let store = state.store.lock().await;
+    store.create_group(&body.name, &body.acl)?;
+    audit(
+        &**store,
+        &claims,
+        "group.create",
+        &body.name,
+        Some(format!("acl: {}", body.acl)),
+    )?;
+    Ok((StatusCode::CREATED, ok()))
+}
+
+#[derive(Deserialize)]
+struct UpdateGroupReq {
+    acl: String,
+}
+
+async fn update_group(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+    Path(name): Path<String>,
+    axum::Json(body): axum::Json<UpdateGroupReq>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    validate_acl(&body.acl)?;
+    let store = state.store.lock().await;
+    let group = store
+        .get_group_by_name(&name)?
+        .ok_or_else(|| TriviaError::not_found(format!("group not found: {name}")))?;
+    store.update_group_acl(&name, &body.acl)?;
+    let detail = format!("{} -> {}", group.acl, body.acl);
+    audit(&**store, &claims, "group.set_acl", &name, Some(detail))?;
+    Ok(ok())
+}
+
+async fn delete_group(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+    Path(name): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    let store = state.store.lock().await;
+    if !store.delete_group(&name)? {
+        return Err(TriviaError::not_found(format!("group not found: {name}")).into());
+    }
+    audit(&**store, &claims, "group.delete", &name, None)?;
+    Ok(ok())
+}
+
+async fn add_member(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+    Path((name, username)): Path<(String, String)>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    let store = state.store.lock().await;
+    store.add_group_member(&name, &username)?;
+    audit(&**store, &claims, "group.add_member", &name, Some(username))?;
+    Ok(ok())
+}
+
+async fn remove_member(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+    Path((name, username)): Path<(String, String)>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    let store = state.store.lock().await;
+    if !store.remove_group_member(&name, &username)? {
+        return Err(TriviaError::not_found(format!("{username} is not in group {name}")).into());
+    }
+    audit(
+        &**store,
+        &claims,
+        "group.remove_member",
+        &name,
+        Some(username),
+    )?;
+    Ok(ok())
+}
+
+// --- Providers ---
+
+/// A provider without its client secret.
+#[derive(Serialize)]
+struct ProviderView {
+    name: String,
+    provider_type: String,
+    client_id: String,
+    enabled: bool,
+    config: serde_json::Value,
+    created_at: DateTime<Utc>,
+}
+
+impl From<OAuthProvider> for ProviderView {
+    fn from(p: OAuthProvider) -> Self {
+        Self {
+            name: p.name,
+            provider_type: p.provider_type,
+            client_id: p.client_id,
+            enabled: p.enabled,
+            config: serde_json::from_str(&p.config).unwrap_or_default(),
+            created_at: p.created_at,
+        }
+    }
+}
+
+async fn list_providers(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    let store = state.store.lock().await;
+    let providers: Vec<ProviderView> = store
+        .list_providers()?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+    Ok(axum::Json(providers))
+}
+
+#[derive(Deserialize)]
+struct CreateProviderReq {
+    name: String,
+    #[serde(rename = "type")]
+    provider_type: String,
+    client_id: String,
+    client_secret: String,
+    /// Issuer, username claim and scopes for OpenID Connect providers.
+    #[serde(flatten)]
+    config: OidcConfig,
+}
+
+async fn create_provider(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+    axum::Json(body): axum::Json<CreateProviderReq>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    let config = serde_json::to_string(&body.config)?;
+    Provider::validate(&body.provider_type, &config)
+        .map_err(|e| TriviaError::validation(e.to_string()))?;
+    let store = state.store.lock().await;
+    store.create_provider(
+        &body.name,
+        &body.provider_type,
+        &body.client_id,
+        &body.client_secret,
+        &config,
+    )?;
+    let detail = format!(
+        "type: {}, client_id: {}",
+        body.provider_type, body.client_id
+    );
+    audit(
+        &**store,
+        &claims,
+        "provider.create",
+        &body.name,
+        Some(detail),
+    )?;
+    Ok((StatusCode::CREATED, ok()))
+}
+
+#[derive(Deserialize)]
+struct UpdateProviderReq {
+    enabled: bool,
+}
+
+async fn update_provider(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+    Path(name): Path<String>,
+    axum::Json(body): axum::Json<UpdateProviderReq>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    let store = state.store.lock().await;
+    store.set_provider_enabled(&name, body.enabled)?;
+    let action = if body.enabled {
+        "provider.enable"
+    } else {
+        "provider.disable"
+    };
+    audit(&**store, &claims, action, &name, None)?;
+    Ok(ok())
+}
+
+async fn delete_provider(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+    Path(name): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    let store = state.store.lock().await;
+    if !store.delete_provider(&name)? {
+        return Err(TriviaError::not_found(format!("provider not found: {name}")).into());
+    }
+    audit(&**store, &claims, "provider.delete", &name, None)?;
+    Ok(ok())
+}
+
+// --- OAuth clients ---
+
+async fn list_clients(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+) -> AppResult<axum::Json<Vec<OAuthClient>>> {
+    require_admin(&claims)?;
+    let store = state.store.lock().await;
+    Ok(axum::Json(store.list_clients()?))
+}
+
+async fn delete_client(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+    Path(client_id): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    require_admin(&claims)?;
+    let store = state.store.lock().await;
+    let client = store
+        .get_client(&client_id)?
+        .ok_or_else(|| TriviaError::not_found(format!("client not found: {client_id}")))?;
+    store.delete_client(&client_id)?;
+    audit(
+        &**store,
+        &claims,
+        "client.delete",
+        &client_id,
+        client.client_name,
+    )?;
+    Ok(ok())
+}
+
+// --- Audit ---
+
+#[derive(Deserialize)]
+struct AuditQuery {
+    #[serde(default = "default_audit_limit")]
+    limit: usize,
+}
+
+fn default_audit_limit() -> usize {
+    100
+}
+
+async fn list_audit(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+    Query(params): Query<AuditQuery>,
+) -> AppResult<axum::Json<Vec<AuditEntry>>> {
+    require_admin(&claims)?;
+    let store = state.store.lock().await;
+    Ok(axum::Json(
+        store.list_audit(&AuditFilter::latest(params.limit))?,
+    ))
+}
+
+// --- Maintenance ---
+
+async fn maintenance_status(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+) -> AppResult<axum::Json<MaintenanceStatus>> {
+    require_admin(&claims)?;
+    Ok(axum::Json(state.maintenance.lock().unwrap().clone()))
+}
+
+async fn run_maintenance(
+    State(state): State<Arc<AdminState>>,
+    Extension(claims): Extension<TokenClaims>,
+) -> AppResult<axum::Json<MaintenanceRun>> {
+    require_admin(&claims)?;
+    let run = maintenance::run_once(&state.store, &state.maintenance).await;
+    let detail = match (&run.report, &run.error) {
+        (Some(report), _) => format!("{} removed", report.removed()),
+        (None, error) => error.clone().unwrap_or_default(),
+    };
+    audit(
+        &**state.store.lock().await,
+        &claims,
+        "maintenance.run",
+        "database",
+        Some(detail),
+    )?;
+    Ok(axum::Json(run))
+}
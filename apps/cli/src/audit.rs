@@ -0,0 +1,140 @@
+//! Attributing changes to callers, and `/api/audit` for reading the log
+//! back. The shared store is locked through `lock_as` by handlers that
+//! change memories, so each change is recorded against the signed-in user
+//! and the transport it arrived on.
+
+use std::ops::{Deref, DerefMut};
+
+use axum::{
+    Extension, Router,
+    extract::{Query, State},
+    response::IntoResponse,
+    routing::get,
+};
+use serde::Deserialize;
+use tokio::sync::MutexGuard;
+use tower_mcp::oauth::token::TokenClaims;
+use trivia_core::{Actor, AuditFilter, Storage, Transport, TriviaError, parse_since};
+
+use crate::auth_middleware::is_admin;
+use crate::oauth::SharedStore;
+use crate::www::AppResult;
+
+/// The locked store with its actor swapped for the caller's. The previous
+/// actor is put back when the guard drops.
+pub struct ActingStore<'a> {
+    store: MutexGuard<'a, Box<dyn Storage>>,
+    previous: Option<Actor>,
+}
+
+impl Deref for ActingStore<'_> {
+    type Target = Box<dyn Storage>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.store
+    }
+}
+
+impl DerefMut for ActingStore<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.store
+    }
+}
+
+impl Drop for ActingStore<'_> {
+    fn drop(&mut self) {
+        if let Some(previous) = self.previous.take() {
+            self.store.set_actor(previous);
+        }
+    }
+}
+
+impl<'a> ActingStore<'a> {
+    /// Keep whatever actor the store already has.
+    pub fn unchanged(store: MutexGuard<'a, Box<dyn Storage>>) -> Self {
+        Self {
+            store,
+            previous: None,
+        }
+    }
+}
+
+/// Lock `store`, attributing changes made through the guard to `actor`.
+pub async fn lock_as(store: &SharedStore, actor: Actor) -> ActingStore<'_> {
+    let mut store = store.lock().await;
+    let previous = Some(store.set_actor(actor));
+    ActingStore { store, previous }
+}
+
+pub fn audit_router(store: SharedStore) -> Router {
+    Router::new()
+        .route("/api/audit", get(list_audit))
+        .with_state(store)
+}
+
+#[derive(Deserialize)]
+struct AuditQuery {
+    actor: Option<String>,
+    action: Option<String>,
+    target: Option<String>,
+    memory: Option<String>,
+    transport: Option<String>,
+    since: Option<String>,
+    #[serde(default = "default_limit")]
+    limit: usize,
+}
+
+fn default_limit() -> usize {
+    100
+}
+
+impl AuditQuery {
+    fn into_filter(self) -> Result<AuditFilter, TriviaError> {
+        let transport = self
+            .transport
+            .map(|t| t.parse::<Transport>())
+            .transpose()
+            .map_err(TriviaError::Validation)?;
+        let since = self
+            .since
+            .map(|s| parse_since(&s))
+            .transpose()
+            .map_err(TriviaError::Validation)?;
+        Ok(AuditFilter {
+            actor: self.actor,
+            action: self.action,
+            target: self.target,
+            memory: self.memory,
+            transport,
+            since,
+            limit: self.limit,
+        })
+    }
+}
+
+/// Admins see every entry; anyone else signed in sees only their own.
+async fn list_audit(
+    State(store): State<SharedStore>,
+    Extension(claims): Extension<TokenClaims>,
+    Query(params): Query<AuditQuery>,
+) -> AppResult<impl IntoResponse> {
+    let mut filter = params.into_filter()?;
+    if !is_admin(&claims) {
+        let username = claims
+            .sub
+            .clone()
+            .ok_or_else(|| TriviaError::access_denied("sign in to see your changes"))?;
+        if filter
+            .actor
+            .as_ref()
+            .is_some_and(|actor| *actor != username)
+        {
+            return Err(
+                TriviaError::access_denied("only admins can see other users' changes").into(),
+            );
+        }
+        filter.actor = Some(username);
+    }
+    let store = store.lock().await;
+    Ok(axum::Json(store.list_audit(&filter)?))
+}
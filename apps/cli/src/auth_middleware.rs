@@ -6,33 +6,41 @@ use axum::{
     middleware::Next,
     response::{IntoResponse, Response},
 };
-use tokio::sync::Mutex;
 use tower_mcp::oauth::token::TokenClaims;
-use trivia_core::MemoryStore;
+use trivia_core::User;
 
-use crate::acl::Acl;
-use crate::oauth;
+use crate::acl::{self, AccessLevel, Acl};
+use crate::oauth::{self, SharedStore};
 
 /// State needed by the auth middleware.
 #[derive(Clone)]
 pub struct AuthState {
-    pub store: Arc<Mutex<MemoryStore>>,
+    pub store: SharedStore,
     pub external_url: String,
     /// Fallback ACL when auth is disabled (from --share flag).
     pub fallback_acl: String,
     /// If true, auth is required on non-public routes.
     pub auth_enabled: bool,
+    /// Hide memories from everyone but their owner (`visibility = "owner-only"`).
+    pub owner_only: bool,
 }
 
 /// Build a default `TokenClaims` from an ACL (for stdio/test, no HTTP middleware).
 pub fn default_claims(acl: &Acl) -> TokenClaims {
-    make_claims(None, &acl.to_string())
+    make_claims(None, &acl.to_string(), false, false)
 }
 
 /// Build a `TokenClaims` carrying username and ACL for bridging into MCP.
-fn make_claims(username: Option<String>, acl: &str) -> TokenClaims {
+/// `admin` grants the `/api/admin` endpoints.
+fn make_claims(username: Option<String>, acl: &str, owner_only: bool, admin: bool) -> TokenClaims {
     let mut extra = std::collections::HashMap::new();
     extra.insert("acl".into(), serde_json::Value::String(acl.into()));
+    if owner_only {
+        extra.insert("owner_only".into(), serde_json::Value::Bool(true));
+    }
+    if admin {
+        extra.insert("admin".into(), serde_json::Value::Bool(true));
+    }
     TokenClaims {
         sub: username,
         iss: None,
@@ -44,14 +52,58 @@ fn make_claims(username: Option<String>, acl: &str) -> TokenClaims {
     }
 }
 
-/// Resolve ACL + username from `TokenClaims`.
+/// Claims for a signed-in user, carrying their effective ACL (own rules plus
+/// their groups'). A user or group ACL that fails to parse locks them out
+/// rather than granting anything. Also records the user as last seen now.
+///
+/// For bearer tokens, `scope` narrows that: without `memory:write` the ACL
+/// is capped at read, without `memory:read` too at none, and the admin role
+/// needs the `admin` scope. Web sessions (`None`) aren't narrowed.
+async fn user_claims(state: &AuthState, user: User, scope: Option<&str>) -> TokenClaims {
+    let store = state.store.lock().await;
+    // Best effort: a failed write shouldn't fail the request
+    let _ = store.touch_user(user.id);
+    let mut acl = acl::effective_acl(store.as_ref(), &user).unwrap_or_else(|_| Acl::closed());
+    let mut admin = user.is_admin;
+    if let Some(scope) = scope {
+        let level = if oauth::has_scope(scope, oauth::SCOPE_WRITE) {
+            AccessLevel::Update
+        } else if oauth::has_scope(scope, oauth::SCOPE_READ) {
+            AccessLevel::Read
+        } else {
+            AccessLevel::None
+        };
+        acl = acl.capped(level);
+        admin &= oauth::has_scope(scope, oauth::SCOPE_ADMIN);
+    }
+    let mut claims = make_claims(
+        Some(user.username),
+        &acl.to_string(),
+        state.owner_only,
+        admin,
+    );
+    claims.scope = scope.map(String::from);
+    claims
+}
+
+/// Whether the claims grant the admin role.
+pub fn is_admin(claims: &TokenClaims) -> bool {
+    claims.extra.get("admin").and_then(|v| v.as_bool()) == Some(true)
+}
+
+/// Resolve ACL + username from `TokenClaims`. The ACL is bound to the
+/// username so ownership is taken into account.
 pub fn acl_from_claims(claims: &TokenClaims, fallback: &Acl) -> (Arc<Acl>, Option<String>) {
     let username = claims.sub.clone();
-    let acl_str = claims.extra.get("acl").and_then(|v| v.as_str());
-    match acl_str {
-        Some(s) => (Arc::new(Acl::parse(s).unwrap_or_else(|_| Acl::closed())), username),
-        None => (Arc::new(fallback.clone()), username),
-    }
+    let owner_only = claims.extra.get("owner_only").and_then(|v| v.as_bool()) == Some(true);
+    let acl = match claims.extra.get("acl").and_then(|v| v.as_str()) {
+        Some(s) => Acl::parse(s).unwrap_or_else(|_| Acl::closed()),
+        None => fallback.clone(),
+    };
+    (
+        Arc::new(acl.for_caller(username.clone(), owner_only)),
+        username,
+    )
 }
 
 /// Middleware that enforces authentication when auth is enabled.
@@ -63,10 +115,14 @@ pub async fn require_auth(
     next: Next,
 ) -> Response {
     if !auth_state.auth_enabled {
-        // No auth — insert default claims with fallback ACL
-        request
-            .extensions_mut()
-            .insert(make_claims(None, &auth_state.fallback_acl));
+        // No auth — insert default claims with fallback ACL. Nobody is
+        // signed in, so nobody gets the admin role
+        request.extensions_mut().insert(make_claims(
+            None,
+            &auth_state.fallback_acl,
+            auth_state.owner_only,
+            false,
+        ));
         return next.run(request).await;
     }
 
@@ -77,10 +133,9 @@ pub async fn require_auth(
     };
 
     // Try Bearer token
-    if let Ok(Some(user)) = oauth::extract_bearer_user(&oauth_state, &headers).await {
-        request
-            .extensions_mut()
-            .insert(make_claims(Some(user.username), &user.acl));
+    if let Ok(Some(bearer)) = oauth::extract_bearer_user(&oauth_state, &headers).await {
+        let claims = user_claims(&auth_state, bearer.user, Some(&bearer.scope)).await;
+        request.extensions_mut().insert(claims);
         return next.run(request).await;
     }
 
@@ -89,9 +144,8 @@ pub async fn require_auth(
         let store = auth_state.store.lock().await;
         if let Ok(Some((_sess, user))) = store.get_session(&session_id) {
             drop(store);
-            request
-                .extensions_mut()
-                .insert(make_claims(Some(user.username), &user.acl));
+            let claims = user_claims(&auth_state, user, None).await;
+            request.extensions_mut().insert(claims);
             return next.run(request).await;
         }
     }
@@ -0,0 +1,79 @@
+//! `/api/clients`: the OAuth clients the signed-in user has approved on the
+//! consent page. Revoking one also revokes its tokens, and the client has
+//! to ask again next time.
+
+use axum::{
+    Extension, Router,
+    extract::{Path, State},
+    response::IntoResponse,
+    routing::{delete, get},
+};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tower_mcp::oauth::token::TokenClaims;
+use trivia_core::{ClientConsent, TriviaError};
+
+use crate::oauth::SharedStore;
+use crate::tokens::session_user;
+use crate::www::AppResult;
+
+pub fn clients_router(store: SharedStore) -> Router {
+    Router::new()
+        .route("/api/clients", get(list_clients))
+        .route("/api/clients/{client_id}", delete(revoke_client))
+        .with_state(store)
+}
+
+#[derive(Serialize)]
+pub struct ClientView {
+    pub client_id: String,
+    pub client_name: Option<String>,
+    pub redirect_uris: Vec<String>,
+    pub scope: String,
+    pub approved_at: DateTime<Utc>,
+}
+
+impl From<ClientConsent> for ClientView {
+    fn from(consent: ClientConsent) -> Self {
+        Self {
+            client_id: consent.client_id,
+            client_name: consent.client_name,
+            redirect_uris: consent.redirect_uris,
+            scope: consent.scope,
+            approved_at: consent.created_at,
+        }
+    }
+}
+
+async fn list_clients(
+    State(store): State<SharedStore>,
+    Extension(claims): Extension<TokenClaims>,
+) -> AppResult<impl IntoResponse> {
+    let store = store.lock().await;
+    let user = session_user(&**store, &claims)?;
+    let clients: Vec<ClientView> = store
+        .list_consents(user.id)?
+        .into_iter()
+        .map(ClientView::from)
+        .collect();
+    Ok(axum::Json(clients))
+}
+
+async fn revoke_client(
+    State(store): State<SharedStore>,
+    Extension(claims): Extension<TokenClaims>,
+    Path(client_id): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    let store = store.lock().await;
+    let user = session_user(&**store, &claims)?;
+    if !store.revoke_consent(user.id, &client_id)? {
+        return Err(TriviaError::not_found(format!("no approved client: {client_id}")).into());
+    }
+    store.record_audit(
+        Some(&user.username),
+        "client.revoke",
+        Some(&client_id),
+        None,
+    )?;
+    Ok(axum::Json(serde_json::json!({"ok": true})))
+}
@@ -1,6 +1,11 @@
 pub mod acl;
+pub mod admin;
+pub mod audit;
 pub mod auth_middleware;
+pub mod clients;
+pub mod maintenance;
 pub mod mcp;
 pub mod oauth;
 pub mod providers;
+pub mod tokens;
 pub mod www;
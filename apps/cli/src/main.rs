@@ -1,11 +1,17 @@
 use std::collections::HashSet;
 use std::io::{self, BufRead, IsTerminal, Write};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
-use trivia_core::{Embedder, MemoryStore, TriviaConfig};
+use trivia_core::{
+    Actor, AuditFilter, Embedder, ExportFormat, MemoryStore, ScoringConfig, SecretKey, Storage,
+    Transport, TriviaConfig, is_postgres_url, open_storage, parse_since, replace_scoring_section,
+    tune,
+};
 
+use trivia_cli::providers::Provider;
+use trivia_cli::providers::oidc::OidcConfig;
 use trivia_cli::{acl, mcp, www};
 
 #[derive(Parser)]
@@ -56,6 +62,33 @@ enum Command {
         /// Mnemonic to show links for
         mnemonic: String,
     },
+    /// Show who changed what, newest first
+    Audit {
+        /// Only changes by this user
+        #[arg(long)]
+        actor: Option<String>,
+        /// Only this action and those beneath it (e.g. 'memory' or 'memory.edit')
+        #[arg(long)]
+        action: Option<String>,
+        /// Only changes to this target (a username, or a memory's UUID)
+        #[arg(long)]
+        target: Option<String>,
+        /// Only changes to the memory with this title
+        #[arg(long)]
+        memory: Option<String>,
+        /// Only changes that came in over cli, stdio, http or web
+        #[arg(long)]
+        transport: Option<Transport>,
+        /// Only changes at or after this date (YYYY-MM-DD) or RFC 3339 time
+        #[arg(long, value_parser = parse_since)]
+        since: Option<chrono::DateTime<chrono::Utc>>,
+        /// Maximum number of entries
+        #[arg(long, short, default_value_t = 50)]
+        limit: usize,
+        /// Output as JSON
+        #[arg(long)]
+        json: bool,
+    },
     /// Merge two memories: keep absorbs discard
     Merge {
         /// Mnemonic of the memory to keep
@@ -73,25 +106,46 @@ enum Command {
         /// Mark as not useful
         #[arg(long, group = "rating")]
         not_useful: bool,
+        /// The query the memory was recalled for
+        #[arg(long)]
+        query: Option<String>,
+        /// Recall id printed by `trivia recall`, so the rating also applies to similar queries
+        #[arg(long)]
+        recall: Option<String>,
     },
-    /// Export memories to a directory as markdown files
+    /// Fit the scoring weights to the ratings given with recall ids
+    Tune {
+        /// Write the [scoring] block into trivia.toml instead of printing it
+        #[arg(long)]
+        write: bool,
+    },
+    /// Export memories as markdown files or a JSONL archive
     Export {
-        /// Target directory
-        directory: String,
+        /// Target directory (markdown) or file (jsonl)
+        path: String,
         /// Only export memories with these tags
         #[arg(long, short)]
         tag: Vec<String>,
+        /// `markdown` (readable, editable) or `jsonl` (lossless archive)
+        #[arg(long, default_value = "markdown")]
+        format: ExportFormat,
+        /// Include embeddings in a jsonl archive so import needn't re-embed
+        #[arg(long)]
+        vectors: bool,
     },
-    /// Import memories from a directory of markdown files
+    /// Import memories from markdown files or a JSONL archive
     Import {
-        /// Source directory
-        directory: String,
+        /// Source directory (markdown) or file (jsonl)
+        path: String,
+        /// `markdown` or `jsonl`
+        #[arg(long, default_value = "markdown")]
+        format: ExportFormat,
     },
     /// Start MCP server (stdin/stdout JSON-RPC)
     Mcp,
     /// Start web UI server
     Www {
-        /// Tag-based ACL for shared MCP access (e.g. 'project:update,*:read')
+        /// Tag-based ACL for shared MCP and web API access (e.g. 'project:update,*:read')
         #[arg(long)]
         share: Option<String>,
     },
@@ -129,6 +183,33 @@ enum Command {
         #[command(subcommand)]
         command: AdminCommand,
     },
+    /// Copy the database to a file (safe while `www` is running)
+    Backup {
+        /// Destination file; must not exist
+        file: PathBuf,
+    },
+    /// Replace the database with a backup, migrating it to the current schema
+    Restore {
+        /// Backup file written by `trivia backup`
+        file: PathBuf,
+    },
+    /// Inspect and apply database schema migrations
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum DbCommand {
+    /// Show the current schema version and any pending migrations
+    Status,
+    /// Apply pending migrations (backs up the database first)
+    Migrate {
+        /// List pending migrations without applying them
+        #[arg(long)]
+        dry_run: bool,
+    },
 }
 
 #[derive(Subcommand)]
@@ -140,6 +221,9 @@ enum AdminCommand {
         /// ACL spec (e.g. '*:update', 'project:read,*:none')
         #[arg(long, default_value = "*:none")]
         acl: String,
+        /// Allow the user to use the admin API and web page
+        #[arg(long)]
+        admin: bool,
     },
     /// Remove a user
     RemoveUser {
@@ -148,19 +232,86 @@ enum AdminCommand {
     },
     /// List all users
     ListUsers,
+    /// Replace a user's ACL
+    SetAcl {
+        /// Username
+        username: String,
+        /// ACL spec (e.g. '*:update', 'project:read,*:none')
+        acl: String,
+    },
+    /// Show a user's groups, identities, sessions, tokens and last activity
+    ShowUser {
+        /// Username
+        username: String,
+    },
+    /// Grant or revoke the admin role
+    SetAdmin {
+        /// Username
+        username: String,
+        /// Revoke the role instead of granting it
+        #[arg(long)]
+        revoke: bool,
+    },
+    /// Sign a user out of the web UI everywhere
+    RevokeSessions {
+        /// Username
+        username: String,
+    },
+    /// Revoke every OAuth and personal access token issued to a user
+    RevokeTokens {
+        /// Username
+        username: String,
+    },
+    /// Create a personal access token for scripts and CI; the token is
+    /// printed once
+    CreateToken {
+        /// Username
+        username: String,
+        /// Name to list and revoke the token by
+        #[arg(long)]
+        name: String,
+        /// Space-separated scopes (default: 'memory:read memory:write')
+        #[arg(long)]
+        scope: Option<String>,
+        /// Expire the token after this many days (default: never)
+        #[arg(long)]
+        expires_days: Option<u32>,
+    },
+    /// List a user's personal access tokens
+    ListTokens {
+        /// Username
+        username: String,
+    },
+    /// Revoke one of a user's personal access tokens
+    RevokeToken {
+        /// Username
+        username: String,
+        /// Token name
+        name: String,
+    },
     /// Add an OAuth provider
     AddProvider {
         /// Provider name (e.g. 'github')
         name: String,
-        /// Provider type
+        /// Provider type: github, oidc, gitlab or google
         #[arg(long = "type")]
         provider_type: String,
         /// OAuth client ID
         #[arg(long)]
         client_id: String,
-        /// OAuth client secret
+        /// File holding the OAuth client secret; read from stdin if omitted,
+        /// so the secret stays out of shell history and `ps`
+        #[arg(long)]
+        client_secret_file: Option<PathBuf>,
+        /// OIDC issuer URL (required for 'oidc'; overrides the gitlab and google defaults)
+        #[arg(long)]
+        issuer: Option<String>,
+        /// ID token claim to use as the username (OIDC types only)
         #[arg(long)]
-        client_secret: String,
+        username_claim: Option<String>,
+        /// Space-separated scopes to request (OIDC types only)
+        #[arg(long)]
+        scopes: Option<String>,
     },
     /// Remove an OAuth provider
     RemoveProvider {
@@ -169,6 +320,16 @@ enum AdminCommand {
     },
     /// List all OAuth providers
     ListProviders,
+    /// Allow sign-in through a provider again
+    EnableProvider {
+        /// Provider name
+        name: String,
+    },
+    /// Stop sign-in through a provider, keeping its settings and identities
+    DisableProvider {
+        /// Provider name
+        name: String,
+    },
     /// Link a user identity to an OAuth provider
     LinkIdentity {
         /// Username
@@ -179,53 +340,175 @@ enum AdminCommand {
         /// Provider username (e.g. GitHub login)
         #[arg(long)]
         provider_username: String,
-        /// Provider user ID (stable, numeric). If omitted, uses provider_username.
+        /// Stable provider user ID (GitHub numeric ID, OIDC `sub`). If omitted, uses provider_username.
         #[arg(long)]
         provider_user_id: Option<String>,
     },
+    /// Remove the link between a user and their account at a provider
+    UnlinkIdentity {
+        /// Username
+        username: String,
+        /// Provider name
+        #[arg(long)]
+        provider: String,
+    },
+    /// Give memories to another user, either all of one user's memories
+    /// or the ones named with --memory
+    #[command(group(clap::ArgGroup::new("source").required(true).args(["from", "memory"])))]
+    TransferOwnership {
+        /// Current owner whose memories are all transferred
+        #[arg(long)]
+        from: Option<String>,
+        /// Title of a memory to transfer (repeatable)
+        #[arg(long)]
+        memory: Vec<String>,
+        /// New owner
+        #[arg(long)]
+        to: String,
+    },
+    /// Manage groups; members get the union of their own and the group ACLs
+    Group {
+        #[command(subcommand)]
+        command: GroupCommand,
+    },
+    /// Encrypt provider client secrets under a newly generated key, written
+    /// to a new file. Point TRIVIA_SECRET_KEY_FILE at it afterwards
+    RotateSecretKey {
+        /// Where to write the new key; must not exist yet
+        #[arg(long)]
+        new_key_file: PathBuf,
+    },
+    /// Delete expired tokens, sessions and authorization codes and optimize
+    /// the database now (`trivia www` does this periodically)
+    Maintenance,
+}
+
+#[derive(Subcommand)]
+enum GroupCommand {
+    /// Add a group
+    Add {
+        /// Group name
+        name: String,
+        /// ACL spec granted to members (e.g. 'project-*:update,hr:!none')
+        #[arg(long)]
+        acl: String,
+    },
+    /// Remove a group (members keep their own ACL)
+    Remove {
+        /// Group name
+        name: String,
+    },
+    /// Add a user to a group
+    Assign {
+        /// Username
+        username: String,
+        /// Group name
+        group: String,
+        /// Take the user out of the group instead
+        #[arg(long)]
+        unassign: bool,
+    },
+    /// List groups, or show one group's members or a user's effective ACL
+    Show {
+        /// Group name
+        name: Option<String>,
+        /// Show this user's groups and effective ACL instead
+        #[arg(long, conflicts_with = "name")]
+        user: Option<String>,
+    },
 }
 
-fn db_path(config: &TriviaConfig) -> PathBuf {
-    if let Ok(path) = std::env::var("TRIVIA_DB") {
-        PathBuf::from(path)
+/// SQLite file path or `postgres://` URL, from TRIVIA_DB or the config.
+fn db_location(config: &TriviaConfig) -> String {
+    if let Ok(location) = std::env::var("TRIVIA_DB") {
+        location
     } else if let Some(ref db) = config.database {
-        PathBuf::from(db)
+        db.clone()
     } else {
         dirs::home_dir()
             .expect("could not determine home directory")
             .join(".claude")
             .join("trivia.db")
+            .to_string_lossy()
+            .into_owned()
     }
 }
 
-fn load_config() -> TriviaConfig {
+/// The OS user running the command, recorded as the actor of local changes.
+fn local_user() -> Option<String> {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .ok()
+        .filter(|user| !user.is_empty())
+}
+
+/// The nearest `trivia.toml`, and where it was found.
+fn load_config() -> (TriviaConfig, Option<PathBuf>) {
     let start = std::env::var("CLAUDE_PLUGIN_ROOT")
         .map(PathBuf::from)
         .unwrap_or_else(|_| std::env::current_dir().unwrap_or_default());
-    TriviaConfig::discover(&start)
-        .map(|(c, _)| c)
-        .unwrap_or_default()
+    TriviaConfig::discover(&start).unwrap_or_default()
+}
+
+/// Open the configured store, saying where the database was backed up to
+/// if it had to be migrated.
+fn open_store(config: &TriviaConfig) -> Result<Box<dyn Storage>> {
+    let (store, backup) = open_storage(&db_location(config))?;
+    if let Some(backup) = backup {
+        eprintln!(
+            "Backed up database to {} before migrating",
+            backup.display()
+        );
+    }
+    Ok(store)
 }
 
 fn main() -> Result<()> {
-    let config = load_config();
+    let (config, config_path) = load_config();
 
     // Auto-detect: if stdin is not a TTY and no args, run MCP server
     if !io::stdin().is_terminal() && std::env::args().count() == 1 {
-        let mut store = MemoryStore::new(&db_path(&config))?;
+        let mut store = open_store(&config)?;
         if !config.recall.tags.is_empty() {
             store.set_boost_tags(config.recall.tags.clone());
         }
+        store.set_scoring_weights(&config.scoring);
+        store.set_auth_config(config.auth.clone());
+        store.set_rating_config(config.ratings.clone());
+        store.set_secret_key(SecretKey::from_env()?);
+        store.set_actor(Actor::new(local_user().as_deref(), Transport::Stdio));
         let embedder = Embedder::new()?;
         let rt = tokio::runtime::Runtime::new()?;
         return rt.block_on(mcp::serve(store, embedder, config));
     }
 
     let cli = Cli::parse();
-    let mut store = MemoryStore::new(&db_path(&config))?;
+
+    // Schema commands must run before opening the store applies migrations
+    match &cli.command {
+        Command::Db { command } => return run_db(command, &db_location(&config)),
+        Command::Restore { file } => return run_restore(file, &db_location(&config)),
+        Command::Backup { file } => {
+            open_store(&config)?.backup_to(file)?;
+            eprintln!("Backed up database to {}", file.display());
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let mut store = open_store(&config)?;
     if !config.recall.tags.is_empty() {
         store.set_boost_tags(config.recall.tags.clone());
     }
+    store.set_scoring_weights(&config.scoring);
+    store.set_auth_config(config.auth.clone());
+    store.set_rating_config(config.ratings.clone());
+    store.set_secret_key(SecretKey::from_env()?);
+    let transport = match cli.command {
+        Command::Mcp => Transport::Stdio,
+        _ => Transport::Cli,
+    };
+    store.set_actor(Actor::new(local_user().as_deref(), transport));
     let embedder = Embedder::new()?;
 
     match cli.command {
@@ -251,7 +534,8 @@ fn main() -> Result<()> {
             } else {
                 Some(tag.as_slice())
             };
-            let memories = store.recall(&embedding, limit, tags, None, None)?;
+            let recall = store.recall(&embedding, limit, tags, None, None)?;
+            let memories = &recall.memories;
 
             if json {
                 println!("{}", serde_json::to_string_pretty(&memories)?);
@@ -259,12 +543,7 @@ fn main() -> Result<()> {
                 println!("No memories found.");
             } else {
                 for (i, mem) in memories.iter().enumerate() {
-                    println!(
-                        "{}. [{}] (score: {:.4})",
-                        i + 1,
-                        mem.mnemonic,
-                        mem.score,
-                    );
+                    println!("{}. [{}] (score: {:.4})", i + 1, mem.mnemonic, mem.score,);
                     println!(
                         "   created: {} | updated: {} | recalled: {} times",
                         mem.created_at.format("%Y-%m-%dT%H:%M:%SZ"),
@@ -275,7 +554,9 @@ fn main() -> Result<()> {
                         println!("   tags: {}", mem.tags.join(", "));
                     }
                     if mem.mnemonics.len() > 1 {
-                        let aliases: Vec<&str> = mem.mnemonics.iter()
+                        let aliases: Vec<&str> = mem
+                            .mnemonics
+                            .iter()
                             .filter(|m| m.as_str() != mem.mnemonic)
                             .map(|m| m.as_str())
                             .collect();
@@ -302,17 +583,66 @@ fn main() -> Result<()> {
                     println!("{}", mem.content);
                     println!();
                 }
+                println!("Recall id: {}", recall.id);
+            }
+        }
+        Command::Tune { write } => {
+            let mut current = ScoringConfig::default();
+            current.apply_weights(&config.scoring);
+            let report = tune(&store.rated_results()?, &current)?;
+            eprintln!(
+                "Fitted to {} rated results; {} held out.",
+                report.fitted, report.held_out
+            );
+            eprintln!(
+                "Held-out pairs ordered right: {:.1}% with the current weights, {:.1}% tuned.",
+                report.current_accuracy * 100.0,
+                report.tuned_accuracy * 100.0
+            );
+            if !report.improved() {
+                if write {
+                    anyhow::bail!("the tuned weights do no better; nothing written");
+                }
+                eprintln!("The tuned weights do no better; keeping the current ones.");
+                return Ok(());
+            }
+            // Weights the tuning left alone keep their configured value
+            let block = report.weights.merged_over(&config.scoring).to_toml();
+            if write {
+                let path = config_path.unwrap_or_else(|| PathBuf::from("trivia.toml"));
+                let contents = if path.is_file() {
+                    std::fs::read_to_string(&path)
+                        .with_context(|| format!("reading {}", path.display()))?
+                } else {
+                    String::new()
+                };
+                // Only write a file that still parses and reads back the block
+                let updated = replace_scoring_section(&contents, &block);
+                let scoring = |toml: &str| toml::from_str::<TriviaConfig>(toml).map(|c| c.scoring);
+                if scoring(&updated).ok() != Some(scoring(&block)?) {
+                    anyhow::bail!(
+                        "could not place [scoring] in {}; nothing written. Set it by hand:\n{block}",
+                        path.display()
+                    );
+                }
+                std::fs::write(&path, updated)
+                    .with_context(|| format!("writing {}", path.display()))?;
+                eprintln!("Wrote [scoring] to {}", path.display());
+            } else {
+                print!("{block}");
             }
         }
         Command::Rate {
             mnemonic,
             useful,
             not_useful,
+            query,
+            recall,
         } => {
             if !useful && !not_useful {
                 anyhow::bail!("specify --useful or --not-useful");
             }
-            store.rate(&mnemonic, useful)?;
+            store.rate(&mnemonic, useful, query.as_deref(), recall.as_deref())?;
             let label = if useful { "useful" } else { "not useful" };
             eprintln!("Rated {mnemonic} as {label}");
         }
@@ -342,20 +672,75 @@ fn main() -> Result<()> {
                 }
             }
         }
-        Command::Export { directory, tag } => {
-            let dir = std::path::Path::new(&directory);
+        Command::Audit {
+            actor,
+            action,
+            target,
+            memory,
+            transport,
+            since,
+            limit,
+            json,
+        } => {
+            let filter = AuditFilter {
+                actor,
+                action,
+                target,
+                memory,
+                transport,
+                since,
+                limit,
+            };
+            let entries = store.list_audit(&filter)?;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            } else if entries.is_empty() {
+                println!("No changes found.");
+            } else {
+                for e in &entries {
+                    println!(
+                        "{}  {} ({})  {}  {}{}",
+                        e.created_at.format("%Y-%m-%d %H:%M:%S"),
+                        e.actor.as_deref().unwrap_or("-"),
+                        e.transport.as_deref().unwrap_or("-"),
+                        e.action,
+                        e.target.as_deref().unwrap_or("-"),
+                        e.detail
+                            .as_deref()
+                            .map(|d| format!("  {d}"))
+                            .unwrap_or_default()
+                    );
+                }
+            }
+        }
+        Command::Export {
+            path,
+            tag,
+            format,
+            vectors,
+        } => {
+            let target = std::path::Path::new(&path);
             let merged = TriviaConfig::merge_tags(&config.export.tags, &tag);
             let tags = if merged.is_empty() {
                 None
             } else {
                 Some(merged.as_slice())
             };
-            store.export(dir, tags)?;
-            eprintln!("Exported to: {directory}");
+            match format {
+                ExportFormat::Markdown => store.export(target, tags)?,
+                ExportFormat::Jsonl => {
+                    let count = store.export_jsonl(target, tags, vectors, &|_, _| true)?;
+                    eprintln!("Wrote {count} records");
+                }
+            }
+            eprintln!("Exported to: {path}");
         }
-        Command::Import { directory } => {
-            let dir = std::path::Path::new(&directory);
-            let result = store.import(dir, &embedder)?;
+        Command::Import { path, format } => {
+            let source = std::path::Path::new(&path);
+            let result = match format {
+                ExportFormat::Markdown => store.import(source, &embedder)?,
+                ExportFormat::Jsonl => store.import_jsonl(source, Some(&embedder))?,
+            };
             eprintln!(
                 "Imported: {} created, {} updated, {} unchanged",
                 result.created, result.updated, result.unchanged
@@ -366,14 +751,11 @@ fn main() -> Result<()> {
             rt.block_on(mcp::serve(store, embedder, config))?;
         }
         Command::Www { share } => {
-            let bind_addr = std::env::var("BIND_ADDR")
-                .unwrap_or_else(|_| "127.0.0.1:3000".to_string());
-            let acl = match share {
-                Some(spec) => acl::Acl::parse(&spec)?,
-                None => acl::Acl::closed(),
-            };
+            let bind_addr =
+                std::env::var("BIND_ADDR").unwrap_or_else(|_| "127.0.0.1:3000".to_string());
+            let share = share.map(|spec| acl::Acl::parse(&spec)).transpose()?;
             let rt = tokio::runtime::Runtime::new()?;
-            rt.block_on(www::serve(store, embedder, &bind_addr, config, acl))?;
+            rt.block_on(www::serve(store, embedder, &bind_addr, config, share))?;
         }
         Command::ListTags { json } => {
             let tags = store.list_tags()?;
@@ -396,10 +778,7 @@ fn main() -> Result<()> {
             store.remove_mnemonic(&title, &alias)?;
             eprintln!("Removed mnemonic alias \"{alias}\" from \"{title}\"");
         }
-        Command::Automerge {
-            threshold,
-            dry_run,
-        } => {
+        Command::Automerge { threshold, dry_run } => {
             // ANSI codes
             const BOLD: &str = "\x1b[1m";
             const DIM: &str = "\x1b[2m";
@@ -482,18 +861,27 @@ fn main() -> Result<()> {
                         store.merge(&summary.mnemonic, &candidate.mnemonic, &emb)?;
                         discarded.insert(candidate.mnemonic.clone());
                         merged_count += 1;
-                        eprintln!("  {GREEN}Merged: {BOLD}{}{RESET}{GREEN} absorbed {}{RESET}", summary.mnemonic, candidate.mnemonic);
+                        eprintln!(
+                            "  {GREEN}Merged: {BOLD}{}{RESET}{GREEN} absorbed {}{RESET}",
+                            summary.mnemonic, candidate.mnemonic
+                        );
                     }
                     "s" | "swap" => {
                         let emb = embedder.embed(&candidate.mnemonic)?;
                         store.merge(&candidate.mnemonic, &summary.mnemonic, &emb)?;
                         discarded.insert(summary.mnemonic.clone());
                         merged_count += 1;
-                        eprintln!("  {GREEN}Merged: {BOLD}{}{RESET}{GREEN} absorbed {}{RESET}", candidate.mnemonic, summary.mnemonic);
+                        eprintln!(
+                            "  {GREEN}Merged: {BOLD}{}{RESET}{GREEN} absorbed {}{RESET}",
+                            candidate.mnemonic, summary.mnemonic
+                        );
                     }
                     "l" | "link" => {
                         store.link(&summary.mnemonic, &candidate.mnemonic, "related")?;
-                        eprintln!("  Linked: {} \u{2194} {}", summary.mnemonic, candidate.mnemonic);
+                        eprintln!(
+                            "  Linked: {} \u{2194} {}",
+                            summary.mnemonic, candidate.mnemonic
+                        );
                     }
                     "q" | "quit" => {
                         eprintln!("  {DIM}Quitting.{RESET}");
@@ -509,14 +897,25 @@ fn main() -> Result<()> {
         }
         Command::Admin { command: admin_cmd } => {
             match admin_cmd {
-                AdminCommand::AddUser { username, acl: acl_spec } => {
+                AdminCommand::AddUser {
+                    username,
+                    acl: acl_spec,
+                    admin,
+                } => {
                     // Validate the ACL spec parses
                     acl::Acl::parse(&acl_spec)?;
                     let user = store.create_user(&username, &acl_spec)?;
-                    eprintln!("Created user: {} (acl: {})", user.username, user.acl);
+                    if admin {
+                        store.set_user_admin(&username, true)?;
+                    }
+                    let detail = format!("acl: {acl_spec}, admin: {admin}");
+                    audit(store.as_ref(), "user.create", &username, Some(detail))?;
+                    let role = if admin { ", admin" } else { "" };
+                    eprintln!("Created user: {} (acl: {}{role})", user.username, user.acl);
                 }
                 AdminCommand::RemoveUser { username } => {
                     if store.delete_user(&username)? {
+                        audit(store.as_ref(), "user.delete", &username, None)?;
                         eprintln!("Removed user: {username}");
                     } else {
                         eprintln!("User not found: {username}");
@@ -528,21 +927,190 @@ fn main() -> Result<()> {
                         println!("No users.");
                     } else {
                         for u in &users {
-                            println!("{} (acl: {})", u.username, u.acl);
+                            let role = if u.is_admin { ", admin" } else { "" };
+                            println!("{} (acl: {}{role})", u.username, u.acl);
                         }
                     }
                 }
+                AdminCommand::SetAcl {
+                    username,
+                    acl: acl_spec,
+                } => {
+                    acl::Acl::parse(&acl_spec)?;
+                    let user = find_user(store.as_ref(), &username)?;
+                    store.update_user_acl(&username, &acl_spec)?;
+                    let detail = format!("{} -> {acl_spec}", user.acl);
+                    audit(store.as_ref(), "user.set_acl", &username, Some(detail))?;
+                    eprintln!("Updated {username} (acl: {acl_spec})");
+                }
+                AdminCommand::ShowUser { username } => show_user(&username, store.as_ref())?,
+                AdminCommand::SetAdmin { username, revoke } => {
+                    store.set_user_admin(&username, !revoke)?;
+                    let action = if revoke {
+                        "user.revoke_admin"
+                    } else {
+                        "user.grant_admin"
+                    };
+                    audit(store.as_ref(), action, &username, None)?;
+                    if revoke {
+                        eprintln!("Revoked admin role from {username}");
+                    } else {
+                        eprintln!("Granted admin role to {username}");
+                    }
+                }
+                AdminCommand::RevokeSessions { username } => {
+                    let user = find_user(store.as_ref(), &username)?;
+                    let count = store.revoke_user_sessions(user.id)?;
+                    audit(
+                        store.as_ref(),
+                        "user.revoke_sessions",
+                        &username,
+                        Some(format!("{count} revoked")),
+                    )?;
+                    eprintln!("Revoked {count} sessions for {username}");
+                }
+                AdminCommand::RevokeTokens { username } => {
+                    let user = find_user(store.as_ref(), &username)?;
+                    let count = store.revoke_user_tokens(user.id)?;
+                    audit(
+                        store.as_ref(),
+                        "user.revoke_tokens",
+                        &username,
+                        Some(format!("{count} revoked")),
+                    )?;
+                    eprintln!("Revoked {count} tokens for {username}");
+                }
+                AdminCommand::CreateToken {
+                    username,
+                    name,
+                    scope,
+                    expires_days,
+                } => {
+                    let user = find_user(store.as_ref(), &username)?;
+                    let (token, secret) = trivia_cli::tokens::issue_token(
+                        store.as_ref(),
+                        &user,
+                        &name,
+                        scope.as_deref(),
+                        expires_days,
+                    )?;
+                    let detail = format!("for {username}, scope: {}", token.scope);
+                    audit(store.as_ref(), "token.create", &token.name, Some(detail))?;
+                    eprintln!(
+                        "Created token {} for {username} (scope: {})",
+                        token.name, token.scope
+                    );
+                    println!("{secret}");
+                }
+                AdminCommand::ListTokens { username } => {
+                    let user = find_user(store.as_ref(), &username)?;
+                    let tokens = store.list_personal_tokens(user.id)?;
+                    if tokens.is_empty() {
+                        println!("No tokens.");
+                    }
+                    let when = |t: Option<chrono::DateTime<chrono::Utc>>| {
+                        t.map_or_else(
+                            || "never".into(),
+                            |t| t.format("%Y-%m-%d %H:%M UTC").to_string(),
+                        )
+                    };
+                    for t in &tokens {
+                        println!(
+                            "{} (scope: {}, expires: {}, last used: {})",
+                            t.name,
+                            t.scope,
+                            when(t.expires_at),
+                            when(t.last_used_at)
+                        );
+                    }
+                }
+                AdminCommand::RevokeToken { username, name } => {
+                    let user = find_user(store.as_ref(), &username)?;
+                    if store.delete_personal_token(user.id, &name)? {
+                        audit(
+                            store.as_ref(),
+                            "token.revoke",
+                            &name,
+                            Some(format!("for {username}")),
+                        )?;
+                        eprintln!("Revoked token {name} for {username}");
+                    } else {
+                        eprintln!("Token not found: {name}");
+                    }
+                }
+                AdminCommand::RotateSecretKey { new_key_file } => {
+                    let key = SecretKey::generate()?;
+                    // Saved first: if re-encrypting fails, nothing has changed
+                    key.write_new_file(&new_key_file)?;
+                    let count = store.reencrypt_provider_secrets(&key)?;
+                    let detail = format!("{count} re-encrypted");
+                    audit(
+                        store.as_ref(),
+                        "provider.rotate_secret_key",
+                        "providers",
+                        Some(detail),
+                    )?;
+                    eprintln!(
+                        "Encrypted {count} provider secrets with the key in {}.\n\
+                         Set {}={} (and unset {}) before starting trivia again.",
+                        new_key_file.display(),
+                        trivia_core::secrets::KEY_FILE_ENV,
+                        new_key_file.display(),
+                        trivia_core::secrets::KEY_ENV
+                    );
+                }
+                AdminCommand::Maintenance => {
+                    let report = trivia_core::run_maintenance(store.as_ref())?;
+                    let detail = format!("{} removed", report.removed());
+                    audit(store.as_ref(), "maintenance.run", "database", Some(detail))?;
+                    eprintln!(
+                        "Removed {} expired tokens, {} sessions, {} authorization codes and {} unrated recalls",
+                        report.expired_tokens,
+                        report.expired_sessions,
+                        report.expired_codes,
+                        report.stale_recalls
+                    );
+                }
                 AdminCommand::AddProvider {
                     name,
                     provider_type,
                     client_id,
-                    client_secret,
+                    client_secret_file,
+                    issuer,
+                    username_claim,
+                    scopes,
                 } => {
-                    let prov = store.create_provider(&name, &provider_type, &client_id, &client_secret)?;
-                    eprintln!("Created provider: {} (type: {})", prov.name, prov.provider_type);
+                    let config = serde_json::to_string(&OidcConfig {
+                        issuer,
+                        username_claim,
+                        scopes,
+                    })?;
+                    Provider::validate(&provider_type, &config)?;
+                    let client_secret = read_client_secret(client_secret_file.as_deref())?;
+                    if SecretKey::from_env()?.is_none() {
+                        eprintln!(
+                            "Warning: neither {} nor {} is set, so the client secret is stored unencrypted",
+                            trivia_core::secrets::KEY_ENV,
+                            trivia_core::secrets::KEY_FILE_ENV
+                        );
+                    }
+                    let prov = store.create_provider(
+                        &name,
+                        &provider_type,
+                        &client_id,
+                        &client_secret,
+                        &config,
+                    )?;
+                    let detail = format!("type: {provider_type}, client_id: {client_id}");
+                    audit(store.as_ref(), "provider.create", &name, Some(detail))?;
+                    eprintln!(
+                        "Created provider: {} (type: {})",
+                        prov.name, prov.provider_type
+                    );
                 }
                 AdminCommand::RemoveProvider { name } => {
                     if store.delete_provider(&name)? {
+                        audit(store.as_ref(), "provider.delete", &name, None)?;
                         eprintln!("Removed provider: {name}");
                     } else {
                         eprintln!("Provider not found: {name}");
@@ -555,26 +1123,322 @@ fn main() -> Result<()> {
                     } else {
                         for p in &providers {
                             let status = if p.enabled { "enabled" } else { "disabled" };
-                            println!("{} (type: {}, {})", p.name, p.provider_type, status);
+                            let issuer = serde_json::from_str::<OidcConfig>(&p.config)
+                                .ok()
+                                .and_then(|c| c.issuer)
+                                .map(|i| format!(", issuer: {i}"))
+                                .unwrap_or_default();
+                            println!("{} (type: {}{issuer}, {})", p.name, p.provider_type, status);
                         }
                     }
                 }
+                AdminCommand::EnableProvider { name } => {
+                    store.set_provider_enabled(&name, true)?;
+                    audit(store.as_ref(), "provider.enable", &name, None)?;
+                    eprintln!("Enabled provider: {name}");
+                }
+                AdminCommand::DisableProvider { name } => {
+                    store.set_provider_enabled(&name, false)?;
+                    audit(store.as_ref(), "provider.disable", &name, None)?;
+                    eprintln!("Disabled provider: {name}");
+                }
                 AdminCommand::LinkIdentity {
                     username,
                     provider,
                     provider_username,
                     provider_user_id,
                 } => {
-                    let user = store.get_user_by_username(&username)?
-                        .ok_or_else(|| anyhow::anyhow!("user not found: {username}"))?;
-                    let prov = store.get_provider_by_name(&provider)?
+                    let user = find_user(store.as_ref(), &username)?;
+                    let prov = store
+                        .get_provider_by_name(&provider)?
                         .ok_or_else(|| anyhow::anyhow!("provider not found: {provider}"))?;
                     let puid = provider_user_id.as_deref().unwrap_or(&provider_username);
                     store.link_identity(user.id, prov.id, &provider_username, puid)?;
+                    let detail = format!("{provider} as {provider_username} (id: {puid})");
+                    audit(store.as_ref(), "identity.link", &username, Some(detail))?;
                     eprintln!(
                         "Linked {username} to {provider} as {provider_username} (id: {puid})"
                     );
                 }
+                AdminCommand::UnlinkIdentity { username, provider } => {
+                    let user = find_user(store.as_ref(), &username)?;
+                    let prov = store
+                        .get_provider_by_name(&provider)?
+                        .ok_or_else(|| anyhow::anyhow!("provider not found: {provider}"))?;
+                    if store.unlink_identity(user.id, prov.id)? {
+                        audit(
+                            store.as_ref(),
+                            "identity.unlink",
+                            &username,
+                            Some(provider.clone()),
+                        )?;
+                        eprintln!("Unlinked {username} from {provider}");
+                    } else {
+                        eprintln!("{username} has no {provider} identity");
+                    }
+                }
+                AdminCommand::TransferOwnership { from, memory, to } => {
+                    let (count, source) = match from {
+                        Some(from) => (
+                            store.transfer_ownership(&from, &to)?,
+                            format!("from {from}"),
+                        ),
+                        None => {
+                            for title in &memory {
+                                store.set_owner(title, Some(&to))?;
+                            }
+                            (memory.len(), format!("{memory:?}"))
+                        }
+                    };
+                    let detail = format!("{count} memories {source}");
+                    audit(store.as_ref(), "user.transfer_ownership", &to, Some(detail))?;
+                    eprintln!("Transferred {count} memories to {to}");
+                }
+                AdminCommand::Group { command } => run_group(&command, store.as_ref())?,
+            }
+        }
+        Command::Db { .. } | Command::Backup { .. } | Command::Restore { .. } => {
+            unreachable!("handled before opening the store")
+        }
+    }
+
+    Ok(())
+}
+
+fn find_user(store: &dyn Storage, username: &str) -> Result<trivia_core::User> {
+    store
+        .get_user_by_username(username)?
+        .ok_or_else(|| anyhow::anyhow!("user not found: {username}"))
+}
+
+/// Record an admin change made from the command line, as the local user.
+fn audit(store: &dyn Storage, action: &str, target: &str, detail: Option<String>) -> Result<()> {
+    store.record_audit(
+        local_user().as_deref(),
+        action,
+        Some(target),
+        detail.as_deref(),
+    )?;
+    Ok(())
+}
+
+/// The client secret for `admin add-provider`, from `path` or stdin.
+fn read_client_secret(path: Option<&Path>) -> Result<String> {
+    let secret = match path {
+        Some(path) => {
+            std::fs::read_to_string(path).with_context(|| format!("reading {}", path.display()))?
+        }
+        // Not echoed when typed at a terminal
+        None if io::stdin().is_terminal() => rpassword::prompt_password("Client secret: ")?,
+        None => {
+            let mut line = String::new();
+            io::stdin().lock().read_line(&mut line)?;
+            line
+        }
+    };
+    let secret = secret.trim();
+    if secret.is_empty() {
+        anyhow::bail!("client secret is empty");
+    }
+    Ok(secret.to_string())
+}
+
+fn show_user(username: &str, store: &dyn Storage) -> Result<()> {
+    let user = find_user(store, username)?;
+    let activity = store.user_activity(user.id)?;
+    let last_seen = activity
+        .last_seen_at
+        .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+        .unwrap_or_else(|| "never".into());
+    let role = if user.is_admin { ", admin" } else { "" };
+    println!("{} (acl: {}{role})", user.username, user.acl);
+    println!(
+        "  created: {}",
+        user.created_at.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+    println!("  last seen: {last_seen}");
+    let groups = store.groups_for_user(user.id)?;
+    if !groups.is_empty() {
+        let names: Vec<&str> = groups.iter().map(|g| g.name.as_str()).collect();
+        println!("  groups: {}", names.join(", "));
+        println!("  effective acl: {}", acl::effective_acl(store, &user)?);
+    }
+    let providers = store.list_providers()?;
+    for identity in store.list_identities_for_user(user.id)? {
+        let provider = providers
+            .iter()
+            .find(|p| p.id == identity.provider_id)
+            .map_or("?", |p| p.name.as_str());
+        println!(
+            "  identity: {provider} as {} (id: {})",
+            identity.provider_username, identity.provider_user_id
+        );
+    }
+    println!("  active sessions: {}", activity.sessions);
+    println!("  tokens: {}", activity.tokens);
+    println!(
+        "  personal tokens: {}",
+        store.list_personal_tokens(user.id)?.len()
+    );
+    Ok(())
+}
+
+fn run_group(command: &GroupCommand, store: &dyn Storage) -> Result<()> {
+    match command {
+        GroupCommand::Add {
+            name,
+            acl: acl_spec,
+        } => {
+            acl::Acl::parse(acl_spec)?;
+            let group = store.create_group(name, acl_spec)?;
+            audit(
+                store,
+                "group.create",
+                name,
+                Some(format!("acl: {acl_spec}")),
+            )?;
+            eprintln!("Created group: {} (acl: {})", group.name, group.acl);
+        }
+        GroupCommand::Remove { name } => {
+            if store.delete_group(name)? {
+                audit(store, "group.delete", name, None)?;
+                eprintln!("Removed group: {name}");
+            } else {
+                eprintln!("Group not found: {name}");
+            }
+        }
+        GroupCommand::Assign {
+            username,
+            group,
+            unassign: false,
+        } => {
+            store.add_group_member(group, username)?;
+            audit(store, "group.add_member", group, Some(username.clone()))?;
+            eprintln!("Added {username} to {group}");
+        }
+        GroupCommand::Assign {
+            username,
+            group,
+            unassign: true,
+        } => {
+            if store.remove_group_member(group, username)? {
+                audit(store, "group.remove_member", group, Some(username.clone()))?;
+                eprintln!("Removed {username} from {group}");
+            } else {
+                eprintln!("{username} is not in group {group}");
+            }
+        }
+        GroupCommand::Show {
+            name: None,
+            user: Some(username),
+        } => {
+            let user = find_user(store, username)?;
+            println!("{} (acl: {})", user.username, user.acl);
+            for group in store.groups_for_user(user.id)? {
+                println!("  {} (acl: {})", group.name, group.acl);
+            }
+            println!("effective: {}", acl::effective_acl(store, &user)?);
+        }
+        GroupCommand::Show {
+            name: Some(name), ..
+        } => {
+            let group = store
+                .get_group_by_name(name)?
+                .ok_or_else(|| anyhow::anyhow!("group not found: {name}"))?;
+            println!("{} (acl: {})", group.name, group.acl);
+            for member in store.list_group_members(name)? {
+                println!("  {}", member.username);
+            }
+        }
+        GroupCommand::Show {
+            name: None,
+            user: None,
+        } => {
+            let groups = store.list_groups()?;
+            if groups.is_empty() {
+                println!("No groups.");
+            } else {
+                for g in &groups {
+                    let members = store.list_group_members(&g.name)?.len();
+                    println!("{} (acl: {}, members: {members})", g.name, g.acl);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn run_restore(file: &Path, location: &str) -> Result<()> {
+    if is_postgres_url(location) {
+        anyhow::bail!("`trivia restore` restores SQLite files; use pg_restore for Postgres");
+    }
+    let report = MemoryStore::restore(Path::new(location), file)?;
+    if let Some(previous) = &report.previous {
+        eprintln!("Saved previous database to {}", previous.display());
+    }
+    if !report.migrated.is_empty() {
+        eprintln!(
+            "Migrated backup from schema version {} to {}",
+            report.from_version,
+            trivia_core::migrations::latest_version()
+        );
+    }
+    eprintln!(
+        "Restored {} memories from {}",
+        report.memories,
+        file.display()
+    );
+    Ok(())
+}
+
+fn run_db(command: &DbCommand, location: &str) -> Result<()> {
+    if is_postgres_url(location) {
+        anyhow::bail!(
+            "`trivia db` manages SQLite files; Postgres migrations are applied on connect"
+        );
+    }
+    let path = Path::new(location);
+    let store = MemoryStore::open_unmigrated(path)?;
+    let pending = store.pending_migrations()?;
+
+    match command {
+        DbCommand::Status => {
+            println!("database: {}", path.display());
+            println!(
+                "schema version: {} (latest: {})",
+                store.schema_version()?,
+                trivia_core::migrations::latest_version()
+            );
+            if pending.is_empty() {
+                println!("Up to date.");
+            } else {
+                println!("pending:");
+                for m in &pending {
+                    println!("  {}: {}", m.version, m.description);
+                }
+            }
+        }
+        DbCommand::Migrate { dry_run } => {
+            if pending.is_empty() {
+                eprintln!(
+                    "Schema is up to date (version {}).",
+                    store.schema_version()?
+                );
+                return Ok(());
+            }
+            if *dry_run {
+                println!("Would apply:");
+                for m in &pending {
+                    println!("  {}: {}", m.version, m.description);
+                }
+                return Ok(());
+            }
+            if store.has_existing_schema()? {
+                let backup = store.backup_before_migrate(path)?;
+                eprintln!("Backed up database to {}", backup.display());
+            }
+            for m in store.migrate()? {
+                eprintln!("Applied {}: {}", m.version, m.description);
             }
         }
     }
@@ -0,0 +1,103 @@
+//! Housekeeping while `trivia www` runs: expired OAuth tokens, sessions and
+//! authorization codes are deleted and the database's indexes tidied every
+//! `[maintenance] interval_minutes`. The outcome of the last run is kept for
+//! `GET /api/admin/maintenance`.
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use trivia_core::config::MaintenanceConfig;
+use trivia_core::{MaintenanceReport, TriviaError, run_maintenance};
+
+use crate::oauth::SharedStore;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceRun {
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub report: Option<MaintenanceReport>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct MaintenanceStatus {
+    /// Minutes between scheduled runs; `None` when the task is off.
+    pub interval_minutes: Option<u64>,
+    pub last_run: Option<MaintenanceRun>,
+}
+
+pub type SharedStatus = Arc<Mutex<MaintenanceStatus>>;
+
+/// Run maintenance now, log the outcome and record it in `status`.
+pub async fn run_once(store: &SharedStore, status: &SharedStatus) -> MaintenanceRun {
+    let started_at = Utc::now();
+    let timer = Instant::now();
+    // Deletes and index merges can take a while; keep them off the async workers
+    let store = store.clone();
+    let result =
+        tokio::task::spawn_blocking(move || run_maintenance(store.blocking_lock().as_ref()))
+            .await
+            .unwrap_or_else(|e| {
+                Err(TriviaError::storage(format!(
+                    "maintenance task failed: {e}"
+                )))
+            });
+    let duration_ms = timer.elapsed().as_millis() as u64;
+
+    let run = match result {
+        Ok(report) => {
+            eprintln!(
+                "Maintenance: removed {} expired tokens, {} sessions, {} authorization codes and {} unrated recalls ({duration_ms}ms)",
+                report.expired_tokens,
+                report.expired_sessions,
+                report.expired_codes,
+                report.stale_recalls
+            );
+            MaintenanceRun {
+                started_at,
+                duration_ms,
+                report: Some(report),
+                error: None,
+            }
+        }
+        Err(e) => {
+            eprintln!("Maintenance failed: {e:#}");
+            MaintenanceRun {
+                started_at,
+                duration_ms,
+                report: None,
+                error: Some(e.to_string()),
+            }
+        }
+    };
+    status.lock().unwrap().last_run = Some(run.clone());
+    run
+}
+
+/// Start the maintenance task, first running shortly after startup so a
+/// backlog of dead rows is cleared without waiting a full interval.
+pub fn spawn(store: SharedStore, config: &MaintenanceConfig) -> SharedStatus {
+    let minutes = config.interval_minutes.unwrap_or(60);
+    let status = SharedStatus::default();
+    if minutes == 0 {
+        return status;
+    }
+    status.lock().unwrap().interval_minutes = Some(minutes);
+    eprintln!("Maintenance every {minutes}m");
+
+    let task_status = status.clone();
+    tokio::spawn(async move {
+        let period = Duration::from_secs(minutes * 60);
+        let start = tokio::time::Instant::now() + Duration::from_secs(60);
+        let mut ticker = tokio::time::interval_at(start, period);
+        loop {
+            ticker.tick().await;
+            run_once(&store, &task_status).await;
+        }
+    });
+    status
+}
@@ -1,24 +1,52 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
 use anyhow::Result;
 use schemars::JsonSchema;
 use serde::Deserialize;
 use tokio::sync::Mutex;
-use tower_mcp::error::ResultExt;
 use tower_mcp::extract::{Extension, Json, State};
 use tower_mcp::oauth::token::TokenClaims;
 use tower_mcp::transport::stdio::StdioTransport;
 use tower_mcp::{CallToolResult, McpRouter, ToolBuilder};
-use trivia_core::{Embedder, Memory, MemoryStore, MemorizeResult, TriviaConfig};
+use trivia_core::{
+    Actor, Embedder, ExportFormat, MemorizeResult, Memory, Storage, Transport, TriviaConfig,
+    TriviaError,
+};
 
-use crate::acl::Acl;
+use crate::acl::{Acl, Owned};
+use crate::audit::{ActingStore, lock_as};
 use crate::auth_middleware::acl_from_claims;
+use crate::oauth::SharedStore;
 
 struct AppState {
-    store: Arc<Mutex<MemoryStore>>,
+    store: SharedStore,
     embedder: Arc<Mutex<Embedder>>,
     config: TriviaConfig,
     acl: Arc<Acl>,
+    /// Transport to attribute changes to, per caller. `None` over stdio,
+    /// where the store keeps the actor it was opened with.
+    transport: Option<Transport>,
+}
+
+impl AppState {
+    /// Lock the store on behalf of `username`, who changes are attributed
+    /// to and recall is personalized for.
+    async fn store_for(&self, username: Option<String>) -> ActingStore<'_> {
+        match self.transport {
+            Some(transport) => {
+                lock_as(
+                    &self.store,
+                    Actor {
+                        username,
+                        transport,
+                    },
+                )
+                .await
+            }
+            None => ActingStore::unchanged(self.store.lock().await),
+        }
+    }
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -88,17 +116,32 @@ struct RenameTagInput {
 
 #[derive(Debug, Deserialize, JsonSchema)]
 struct ExportInput {
-    /// Directory to export memories to
+    /// Directory (markdown) or file (jsonl) to export memories to
     directory: String,
     /// Optional tag filter — only export memories with at least one matching tag
     #[serde(default)]
     tags: Option<Vec<String>>,
+    /// "markdown" (default) or "jsonl" for a lossless archive with stats and timestamps
+    #[serde(default)]
+    format: Option<String>,
+    /// Include embeddings in a jsonl archive
+    #[serde(default)]
+    vectors: bool,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
 struct ImportInput {
-    /// Directory to import memories from
+    /// Directory (markdown) or file (jsonl) to import memories from
     directory: String,
+    /// "markdown" (default) or "jsonl"
+    #[serde(default)]
+    format: Option<String>,
+}
+
+fn parse_format(format: Option<&str>) -> Result<ExportFormat, TriviaError> {
+    format
+        .map(|f| f.parse().map_err(TriviaError::Validation))
+        .unwrap_or(Ok(ExportFormat::Markdown))
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -109,6 +152,10 @@ struct RateInput {
     mnemonics: Option<Vec<String>>,
     /// Whether the memory was useful (true) or not useful (false)
     useful: bool,
+    /// The query the memories were recalled for, kept with the rating
+    query: Option<String>,
+    /// Recall id the memories were returned with, so the rating also applies to similar queries later
+    recall_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize, JsonSchema)]
@@ -186,7 +233,9 @@ fn format_memories(memories: &[Memory], truncate: Option<usize>) -> String {
             output.push_str(&format!("   tags: {}\n", mem.tags.join(", ")));
         }
         if mem.mnemonics.len() > 1 {
-            let aliases: Vec<&str> = mem.mnemonics.iter()
+            let aliases: Vec<&str> = mem
+                .mnemonics
+                .iter()
                 .filter(|m| m.as_str() != mem.mnemonic)
                 .map(|m| m.as_str())
                 .collect();
@@ -229,33 +278,82 @@ fn format_memories(memories: &[Memory], truncate: Option<usize>) -> String {
     output
 }
 
-/// Helper: look up a memory's tags by mnemonic. Returns None if not found.
-async fn memory_tags(store: &Arc<Mutex<MemoryStore>>, mnemonic: &str) -> Result<Option<Vec<String>>> {
-    let s = store.lock().await;
-    match s.get_memory_by_mnemonic(mnemonic)? {
-        Some(mem) => Ok(Some(mem.tags)),
-        None => Ok(None),
+/// A failed tool call: what the tool was doing and the error that stopped it.
+struct ToolFailure {
+    context: String,
+    error: TriviaError,
+}
+
+/// Like tower-mcp's `ResultExt::tool_context`, but keeps the typed error so
+/// `tool_result` can report its kind.
+trait ToolContext<T> {
+    fn tool_context(self, context: &str) -> Result<T, ToolFailure>;
+}
+
+impl<T, E: Into<TriviaError>> ToolContext<T> for Result<T, E> {
+    fn tool_context(self, context: &str) -> Result<T, ToolFailure> {
+        self.map_err(|e| ToolFailure {
+            context: context.to_string(),
+            error: e.into(),
+        })
     }
 }
 
+/// Run a tool body, turning a failure into an error result. Besides the
+/// message, the result's structured content tells clients the error kind and
+/// whether the call is worth retrying.
+async fn tool_result(
+    body: impl Future<Output = Result<CallToolResult, ToolFailure>>,
+) -> tower_mcp::Result<CallToolResult> {
+    Ok(body.await.unwrap_or_else(|failure| {
+        let mut result = CallToolResult::error(format!("{}: {}", failure.context, failure.error));
+        result.structured_content = Some(serde_json::json!({
+            "error": {
+                "kind": failure.error.kind(),
+                "retryable": failure.error.is_retryable(),
+                "message": failure.error.to_string(),
+            }
+        }));
+        result
+    }))
+}
+
+/// Helper: look up a memory by mnemonic for an access check. Returns None
+/// if not found.
+async fn find_memory(store: &SharedStore, mnemonic: &str) -> Result<Option<Memory>> {
+    let s = store.lock().await;
+    Ok(s.get_memory_by_mnemonic(mnemonic)?)
+}
+
 /// Build the MCP router with ACL enforcement.
 pub fn build_mcp_router(
-    store: Arc<Mutex<MemoryStore>>,
+    store: SharedStore,
     embedder: Arc<Mutex<Embedder>>,
     config: TriviaConfig,
     acl: Arc<Acl>,
 ) -> McpRouter {
-    let state = Arc::new(AppState { store, embedder, config, acl });
+    let state = Arc::new(AppState {
+        store,
+        embedder,
+        config,
+        acl,
+        transport: Some(Transport::Http),
+    });
     build_router(state)
 }
 
 /// Serve MCP over stdio (no ACL restrictions).
-pub async fn serve(store: MemoryStore, embedder: Embedder, config: TriviaConfig) -> Result<()> {
+pub async fn serve(
+    store: Box<dyn Storage>,
+    embedder: Embedder,
+    config: TriviaConfig,
+) -> Result<()> {
     let state = Arc::new(AppState {
         store: Arc::new(Mutex::new(store)),
         embedder: Arc::new(Mutex::new(embedder)),
         config,
         acl: Arc::new(Acl::open()),
+        transport: None,
     });
 
     let router = build_router(state);
@@ -271,7 +369,7 @@ fn build_router(state: Arc<AppState>) -> McpRouter {
             s,
             |State(app): State<Arc<AppState>>,
              Extension(claims): Extension<TokenClaims>,
-             Json(input): Json<MemorizeInput>| async move {
+             Json(input): Json<MemorizeInput>| tool_result(async move {
                 let (acl, username) = acl_from_claims(&claims, &app.acl);
                 let mut tags = TriviaConfig::merge_tags(&app.config.memorize.tags, &input.tags);
 
@@ -283,23 +381,36 @@ fn build_router(state: Arc<AppState>) -> McpRouter {
                     }
                 }
 
-                // ACL: at least one tag must grant update
-                if !acl.is_open() && !acl.check_update(&tags) {
-                    return Err(anyhow::anyhow!(
-                        "access denied: your permissions are [{}] which do not grant update access",
-                        acl
-                    ))
-                    .tool_context("memorize denied");
+                // ACL: at least one tag must grant update, or the caller must
+                // own the memory (new memories belong to their creator), and
+                // an existing memory under this mnemonic must grant update
+                if !acl.is_open() {
+                    let existing = find_memory(&app.store, &input.mnemonic).await
+                        .tool_context("memorize failed")?;
+                    let allowed = match &existing {
+                        Some(mem) => acl.check_update(mem)
+                            && acl.check_update(&Owned { tags: &tags, owner: mem.owner.as_deref() }),
+                        None => acl.check_update(&Owned { tags: &tags, owner: username.as_deref() }),
+                    };
+                    if !allowed {
+                        return Err(TriviaError::access_denied(format!(
+                            "access denied: your permissions are [{}] which do not grant update access",
+                            acl
+                        )))
+                        .tool_context("memorize denied");
+                    }
                 }
 
                 let skip_merge = !acl.is_open();
                 let embedding = app.embedder.lock().await.embed(&input.mnemonic)
                     .tool_context("embedding failed")?;
-                let result = app.store.lock().await
-                    .memorize_with_options(&input.mnemonic, &input.content, &tags, &embedding, skip_merge)
+                let store = app.store_for(username.clone()).await;
+                let result = store
+                    .memorize_with_options(&input.mnemonic, &input.content, &tags, &embedding, skip_merge, username.as_deref())
                     .tool_context("memorize failed")?;
+                drop(store);
                 Ok(CallToolResult::text(format_memorize_response(&input.mnemonic, &result)))
-            },
+            }),
         )
         .build();
 
@@ -310,22 +421,20 @@ fn build_router(state: Arc<AppState>) -> McpRouter {
             s,
             |State(app): State<Arc<AppState>>,
              Extension(claims): Extension<TokenClaims>,
-             Json(input): Json<RecallInput>| async move {
-                let (acl, _username) = acl_from_claims(&claims, &app.acl);
+             Json(input): Json<RecallInput>| tool_result(async move {
+                let (acl, username) = acl_from_claims(&claims, &app.acl);
                 let embedding = app.embedder.lock().await.embed(&input.query)
                     .tool_context("embedding failed")?;
                 let limit = input.limit.unwrap_or(5).clamp(1, 10);
                 let tags = input.tags.as_deref();
                 let fts = input.full_text_search.as_deref();
                 let exclude = input.exclude_tags.as_deref();
-                let mut memories = app.store.lock().await
-                    .recall(&embedding, limit, tags, fts, exclude)
+                // ACL: only memories the caller may read are ranked and logged
+                let readable = |m: &Memory| acl.is_open() || acl.check_read(m);
+                let recall = app.store_for(username).await
+                    .recall_filtered(&embedding, limit, tags, fts, exclude, &readable)
                     .tool_context("recall failed")?;
-
-                // ACL: post-filter by read access
-                if !acl.is_open() {
-                    memories.retain(|m| acl.check_read(&m.tags));
-                }
+                let mut memories = recall.memories;
 
                 // Apply min_score: param > config > 0.0
                 let min_score = input.min_score
@@ -338,50 +447,50 @@ fn build_router(state: Arc<AppState>) -> McpRouter {
                 }
 
                 let truncate = input.truncate.or(app.config.recall.body_max_chars);
-                Ok(CallToolResult::text(format_memories(&memories, truncate)))
-            },
+                let mut output = format_memories(&memories, truncate);
+                output.push_str(&format!("\nRecall id: {} (pass as recall_id when rating)\n", recall.id));
+                Ok(CallToolResult::text(output))
+            }),
         )
         .build();
 
     let s = state.clone();
     let rate = ToolBuilder::new("rate")
-        .description("Rate previously recalled memories as useful or not useful. Call this after using recalled memories to improve future ranking. Accepts a single mnemonic or a batch of mnemonics. Silent on complete success; reports only not-found mnemonics.")
+        .description("Rate previously recalled memories as useful or not useful. Call this after using recalled memories to improve future ranking, passing the recall_id from the recall (and its query) so the rating also applies to similar questions later. Only your latest rating of each memory counts. Accepts a single mnemonic or a batch of mnemonics. Silent on complete success; reports only not-found mnemonics.")
         .extractor_handler(
             s,
             |State(app): State<Arc<AppState>>,
              Extension(claims): Extension<TokenClaims>,
-             Json(input): Json<RateInput>| async move {
-                let (acl, _username) = acl_from_claims(&claims, &app.acl);
+             Json(input): Json<RateInput>| tool_result(async move {
+                let (acl, username) = acl_from_claims(&claims, &app.acl);
                 // Merge single + batch mnemonics
                 let mut all = input.mnemonics.unwrap_or_default();
-                if let Some(single) = input.mnemonic {
-                    if !all.contains(&single) {
+                if let Some(single) = input.mnemonic
+                    && !all.contains(&single) {
                         all.insert(0, single);
                     }
-                }
                 if all.is_empty() {
-                    return Err(anyhow::anyhow!("provide mnemonic or mnemonics"))
+                    return Err(TriviaError::validation("provide mnemonic or mnemonics"))
                         .tool_context("rate failed");
                 }
 
                 // ACL: each memory must grant update
                 if !acl.is_open() {
                     for mn in &all {
-                        if let Some(tags) = memory_tags(&app.store, mn).await
-                            .tool_context("rate failed")? {
-                            if !acl.check_update(&tags) {
-                                return Err(anyhow::anyhow!(
-                                    "access denied: your permissions are [{}] which do not grant update access", acl
-                                )).tool_context("rate denied");
+                        if let Some(mem) = find_memory(&app.store, mn).await
+                            .tool_context("rate failed")?
+                            && !acl.check_update(&mem) {
+                                return Err(TriviaError::access_denied(format!(
+                                    "access denied: your permissions are [{}] which do not grant update access",
+                                    acl
+                                ))).tool_context("rate denied");
                             }
-                        }
                     }
                 }
 
-                let not_found = app.store
-                    .lock()
+                let not_found = app.store_for(username)
                     .await
-                    .rate_batch(&all, input.useful)
+                    .rate_batch(&all, input.useful, input.query.as_deref(), input.recall_id.as_deref())
                     .tool_context("rate failed")?;
 
                 if not_found.is_empty() {
@@ -393,7 +502,7 @@ fn build_router(state: Arc<AppState>) -> McpRouter {
                         .join("\n");
                     Ok(CallToolResult::text(msg))
                 }
-            },
+            }),
         )
         .build();
 
@@ -404,24 +513,23 @@ fn build_router(state: Arc<AppState>) -> McpRouter {
             s,
             |State(app): State<Arc<AppState>>,
              Extension(claims): Extension<TokenClaims>,
-             Json(input): Json<LinkInput>| async move {
-                let (acl, _username) = acl_from_claims(&claims, &app.acl);
+             Json(input): Json<LinkInput>| tool_result(async move {
+                let (acl, username) = acl_from_claims(&claims, &app.acl);
                 // ACL: both memories must grant update
                 if !acl.is_open() {
                     for mn in [&input.source, &input.target] {
-                        if let Some(tags) = memory_tags(&app.store, mn).await
-                            .tool_context("link failed")? {
-                            if !acl.check_update(&tags) {
-                                return Err(anyhow::anyhow!(
-                                    "access denied: your permissions are [{}] which do not grant update access", acl
-                                )).tool_context("link denied");
+                        if let Some(mem) = find_memory(&app.store, mn).await
+                            .tool_context("link failed")?
+                            && !acl.check_update(&mem) {
+                                return Err(TriviaError::access_denied(format!(
+                                    "access denied: your permissions are [{}] which do not grant update access",
+                                    acl
+                                ))).tool_context("link denied");
                             }
-                        }
                     }
                 }
 
-                app.store
-                    .lock()
+                app.store_for(username)
                     .await
                     .link(&input.source, &input.target, &input.link_type)
                     .tool_context("link failed")?;
@@ -429,7 +537,7 @@ fn build_router(state: Arc<AppState>) -> McpRouter {
                     "Linked: {} --[{}]--> {}",
                     input.source, input.link_type, input.target
                 )))
-            },
+            }),
         )
         .build();
 
@@ -440,19 +548,19 @@ fn build_router(state: Arc<AppState>) -> McpRouter {
             s,
             |State(app): State<Arc<AppState>>,
              Extension(claims): Extension<TokenClaims>,
-             Json(input): Json<MergeInput>| async move {
-                let (acl, _username) = acl_from_claims(&claims, &app.acl);
+             Json(input): Json<MergeInput>| tool_result(async move {
+                let (acl, username) = acl_from_claims(&claims, &app.acl);
                 // ACL: both memories must grant update
                 if !acl.is_open() {
                     for mn in [&input.keep, &input.discard] {
-                        if let Some(tags) = memory_tags(&app.store, mn).await
-                            .tool_context("merge failed")? {
-                            if !acl.check_update(&tags) {
-                                return Err(anyhow::anyhow!(
-                                    "access denied: your permissions are [{}] which do not grant update access", acl
-                                )).tool_context("merge denied");
+                        if let Some(mem) = find_memory(&app.store, mn).await
+                            .tool_context("merge failed")?
+                            && !acl.check_update(&mem) {
+                                return Err(TriviaError::access_denied(format!(
+                                    "access denied: your permissions are [{}] which do not grant update access",
+                                    acl
+                                ))).tool_context("merge denied");
                             }
-                        }
                     }
                 }
 
@@ -462,8 +570,7 @@ fn build_router(state: Arc<AppState>) -> McpRouter {
                     .await
                     .embed(&input.keep)
                     .tool_context("embedding failed")?;
-                app.store
-                    .lock()
+                app.store_for(username)
                     .await
                     .merge(&input.keep, &input.discard, &embedding)
                     .tool_context("merge failed")?;
@@ -471,67 +578,82 @@ fn build_router(state: Arc<AppState>) -> McpRouter {
                     "Merged: {} absorbed {}",
                     input.keep, input.discard
                 )))
-            },
+            }),
         )
         .build();
 
     let s = state.clone();
     let export = ToolBuilder::new("export")
-        .description("Export memories to a directory as markdown files with YAML frontmatter. Optionally filter by tags.")
+        .description("Export memories to a directory as markdown files with YAML frontmatter, or with format \"jsonl\" to a single lossless archive file (timestamps, recall and rating counts, link metadata, optionally vectors). Optionally filter by tags.")
         .extractor_handler(
             s,
             |State(app): State<Arc<AppState>>,
              Extension(claims): Extension<TokenClaims>,
-             Json(input): Json<ExportInput>| async move {
+             Json(input): Json<ExportInput>| tool_result(async move {
                 let (acl, _username) = acl_from_claims(&claims, &app.acl);
                 let dir = std::path::Path::new(&input.directory);
                 let tags = input.tags.as_deref();
+                let format = parse_format(input.format.as_deref()).tool_context("export failed")?;
 
-                if acl.is_open() {
-                    app.store.lock().await
-                        .export(dir, tags)
-                        .tool_context("export failed")?;
+                // ACL: only export readable memories
+                let store = app.store.lock().await;
+                let owners: HashMap<String, String> = if acl.is_open() {
+                    HashMap::new()
                 } else {
-                    // ACL: only export readable memories
-                    let acl = acl.clone();
-                    app.store.lock().await
-                        .export_filtered(dir, tags, move |mem_tags| acl.check_read(mem_tags))
-                        .tool_context("export failed")?;
+                    store.list_all_summaries().tool_context("export failed")?
+                        .into_iter()
+                        .filter_map(|m| Some((m.mnemonic, m.owner?)))
+                        .collect()
+                };
+                let readable = |title: &str, tags: &[String]| {
+                    acl.is_open()
+                        || acl.check_read(&Owned { tags, owner: owners.get(title).map(String::as_str) })
+                };
+                match format {
+                    ExportFormat::Markdown => store
+                        .export_filtered(dir, tags, &readable)
+                        .tool_context("export failed")?,
+                    ExportFormat::Jsonl => {
+                        store
+                            .export_jsonl(dir, tags, input.vectors, &readable)
+                            .tool_context("export failed")?;
+                    }
                 }
 
                 Ok(CallToolResult::text(format!("Exported to: {}", input.directory)))
-            },
+            }),
         )
         .build();
 
     let s = state.clone();
     let import = ToolBuilder::new("import")
-        .description("Import memories from a directory of markdown files with YAML frontmatter.")
+        .description("Import memories from a directory of markdown files with YAML frontmatter, or with format \"jsonl\" from an archive written by export.")
         .extractor_handler(
             s,
             |State(app): State<Arc<AppState>>,
              Extension(claims): Extension<TokenClaims>,
-             Json(input): Json<ImportInput>| async move {
-                let (acl, _username) = acl_from_claims(&claims, &app.acl);
+             Json(input): Json<ImportInput>| tool_result(async move {
+                let (acl, username) = acl_from_claims(&claims, &app.acl);
                 // ACL: import is blocked in shared mode
                 if !acl.is_open() {
-                    return Err(anyhow::anyhow!("import is disabled in shared mode"))
+                    return Err(TriviaError::access_denied("import is disabled in shared mode"))
                         .tool_context("import denied");
                 }
 
                 let dir = std::path::Path::new(&input.directory);
+                let format = parse_format(input.format.as_deref()).tool_context("import failed")?;
                 let embedder = app.embedder.lock().await;
-                let result = app
-                    .store
-                    .lock()
-                    .await
-                    .import(dir, &embedder)
-                    .tool_context("import failed")?;
+                let store = app.store_for(username).await;
+                let result = match format {
+                    ExportFormat::Markdown => store.import(dir, &embedder),
+                    ExportFormat::Jsonl => store.import_jsonl(dir, Some(&embedder)),
+                }
+                .tool_context("import failed")?;
                 Ok(CallToolResult::text(format!(
                     "Imported: {} created, {} updated, {} unchanged",
                     result.created, result.updated, result.unchanged
                 )))
-            },
+            }),
         )
         .build();
 
@@ -540,34 +662,31 @@ fn build_router(state: Arc<AppState>) -> McpRouter {
         .description("List all unique tags with the number of memories using each tag.")
         .extractor_handler(
             s,
-            |State(app): State<Arc<AppState>>,
-             Extension(claims): Extension<TokenClaims>| async move {
-                let (acl, _username) = acl_from_claims(&claims, &app.acl);
-                let tags = app
-                    .store
-                    .lock()
-                    .await
-                    .list_tags()
-                    .tool_context("list-tags failed")?;
-
-                // ACL: post-filter by read access
-                let tags: Vec<_> = if acl.is_open() {
-                    tags
-                } else {
-                    tags.into_iter()
-                        .filter(|t| acl.tag_level(&t.tag) >= crate::acl::AccessLevel::Read)
-                        .collect()
-                };
+            |State(app): State<Arc<AppState>>, Extension(claims): Extension<TokenClaims>| {
+                tool_result(async move {
+                    let (acl, _username) = acl_from_claims(&claims, &app.acl);
+                    let store = app.store.lock().await;
+                    // ACL: count only readable memories and list only readable tags
+                    let tags = if acl.is_open() {
+                        store.list_tags().tool_context("list-tags failed")?
+                    } else {
+                        let memories = store
+                            .list_all_summaries()
+                            .tool_context("list-tags failed")?;
+                        acl.visible_tags(memories.iter())
+                    };
+                    drop(store);
 
-                if tags.is_empty() {
-                    return Ok(CallToolResult::text("No tags found."));
-                }
+                    if tags.is_empty() {
+                        return Ok(CallToolResult::text("No tags found."));
+                    }
 
-                let mut output = String::new();
-                for t in &tags {
-                    output.push_str(&format!("{} ({} memories)\n", t.tag, t.count));
-                }
-                Ok(CallToolResult::text(output))
+                    let mut output = String::new();
+                    for t in &tags {
+                        output.push_str(&format!("{} ({} memories)\n", t.tag, t.count));
+                    }
+                    Ok(CallToolResult::text(output))
+                })
             },
         )
         .build();
@@ -579,26 +698,24 @@ fn build_router(state: Arc<AppState>) -> McpRouter {
             s,
             |State(app): State<Arc<AppState>>,
              Extension(claims): Extension<TokenClaims>,
-             Json(input): Json<EditInput>| async move {
-                let (acl, _username) = acl_from_claims(&claims, &app.acl);
+             Json(input): Json<EditInput>| tool_result(async move {
+                let (acl, username) = acl_from_claims(&claims, &app.acl);
                 if input.new_mnemonic.is_none() && input.add_tags.is_empty() && input.remove_tags.is_empty()
                     && input.add_mnemonics.is_empty() && input.remove_mnemonics.is_empty() {
-                    return Err(anyhow::anyhow!("provide at least one of: new_mnemonic, add_tags, remove_tags, add_mnemonics, remove_mnemonics"))
+                    return Err(TriviaError::validation("provide at least one of: new_mnemonic, add_tags, remove_tags, add_mnemonics, remove_mnemonics"))
                         .tool_context("edit failed");
                 }
 
                 // ACL: memory's current tags must grant update
-                if !acl.is_open() {
-                    if let Some(tags) = memory_tags(&app.store, &input.mnemonic).await
-                        .tool_context("edit failed")? {
-                        if !acl.check_update(&tags) {
-                            return Err(anyhow::anyhow!(
+                if !acl.is_open()
+                    && let Some(mem) = find_memory(&app.store, &input.mnemonic).await
+                        .tool_context("edit failed")?
+                        && !acl.check_update(&mem) {
+                            return Err(TriviaError::access_denied(format!(
                                 "access denied: your permissions are [{}] which do not grant update access",
                                 acl
-                            )).tool_context("edit denied");
+                            ))).tool_context("edit denied");
                         }
-                    }
-                }
 
                 let embedder = app.embedder.lock().await;
                 let new_embedding = match &input.new_mnemonic {
@@ -616,7 +733,7 @@ fn build_router(state: Arc<AppState>) -> McpRouter {
                 }
                 drop(embedder);
 
-                let result = app.store.lock().await
+                let result = app.store_for(username).await
                     .edit_memory(
                         &input.mnemonic,
                         input.new_mnemonic.as_deref(),
@@ -641,7 +758,7 @@ fn build_router(state: Arc<AppState>) -> McpRouter {
                     output.push_str(&format!("\nMnemonics: [{}]", result.mnemonics.join(", ")));
                 }
                 Ok(CallToolResult::text(output))
-            },
+            }),
         )
         .build();
 
@@ -652,32 +769,32 @@ fn build_router(state: Arc<AppState>) -> McpRouter {
             s,
             |State(app): State<Arc<AppState>>,
              Extension(claims): Extension<TokenClaims>,
-             Json(input): Json<RenameTagInput>| async move {
-                let (acl, _username) = acl_from_claims(&claims, &app.acl);
+             Json(input): Json<RenameTagInput>| tool_result(async move {
+                let (acl, username) = acl_from_claims(&claims, &app.acl);
                 // ACL: both old and new tag must grant update
                 if !acl.is_open() {
                     if acl.tag_level(&input.old_tag) < crate::acl::AccessLevel::Update {
-                        return Err(anyhow::anyhow!(
+                        return Err(TriviaError::access_denied(format!(
                             "access denied: your permissions are [{}] which do not grant update access",
                             acl
-                        )).tool_context("rename-tag denied");
+                        ))).tool_context("rename-tag denied");
                     }
                     if acl.tag_level(&input.new_tag) < crate::acl::AccessLevel::Update {
-                        return Err(anyhow::anyhow!(
+                        return Err(TriviaError::access_denied(format!(
                             "access denied: your permissions are [{}] which do not grant update access",
                             acl
-                        )).tool_context("rename-tag denied");
+                        ))).tool_context("rename-tag denied");
                     }
                 }
 
-                let count = app.store.lock().await
+                let count = app.store_for(username).await
                     .rename_tag(&input.old_tag, &input.new_tag)
                     .tool_context("rename-tag failed")?;
                 Ok(CallToolResult::text(format!(
                     "Renamed tag \"{}\" -> \"{}\" across {count} memories",
                     input.old_tag, input.new_tag
                 )))
-            },
+            }),
         )
         .build();
 
@@ -703,6 +820,7 @@ fn build_router(state: Arc<AppState>) -> McpRouter {
 
 #[cfg(test)]
 mod tests {
+    use super::ToolContext;
     use schemars::schema_for;
     use tower_mcp::NoParams;
 
@@ -714,4 +832,19 @@ mod tests {
         // MCP requires "type": "object", not "type": "null"
         assert_ne!(value["type"], "null");
     }
+
+    #[tokio::test]
+    async fn failures_carry_error_kind() {
+        let result = super::tool_result(async {
+            Err(trivia_core::TriviaError::not_found("memory not found: x"))
+                .tool_context("rate failed")
+        })
+        .await
+        .unwrap();
+        assert!(result.is_error);
+        let error = &result.structured_content.unwrap()["error"];
+        assert_eq!(error["kind"], "not_found");
+        assert_eq!(error["retryable"], false);
+        assert_eq!(error["message"], "memory not found: x");
+    }
 }
@@ -5,17 +5,18 @@ use axum::{
     Router,
     extract::{Path, Query, State},
     http::StatusCode,
-    response::{IntoResponse, Redirect, Response},
+    response::{AppendHeaders, Html, IntoResponse, Redirect, Response},
     routing::{get, post},
 };
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use tokio::sync::Mutex;
-use trivia_core::MemoryStore;
+use trivia_core::auth_store::PERSONAL_TOKEN_PREFIX;
+use trivia_core::{OAuthClient, PendingAuthorization, Storage, TokenGrant, TriviaError, User};
 
 use crate::providers::Provider;
 
-pub type SharedStore = Arc<Mutex<MemoryStore>>;
+pub type SharedStore = Arc<Mutex<Box<dyn Storage>>>;
 
 #[derive(Clone)]
 pub struct OAuthState {
@@ -35,11 +36,15 @@ pub fn router() -> Router<OAuthState> {
         // Authorization flow
         .route("/oauth/authorize", get(authorize))
         .route("/oauth/callback/{provider}", get(oauth_callback))
+        .route("/oauth/consent", post(consent))
         .route("/oauth/token", post(token_exchange))
+        .route("/oauth/revoke", post(revoke_token))
+        .route("/oauth/introspect", post(introspect_token))
         // Web UI auth
         .route("/auth/login/{provider}", get(auth_login))
         .route("/auth/callback/{provider}", get(auth_callback))
         .route("/auth/logout", post(auth_logout))
+        .route("/auth/logout-all", post(auth_logout_all))
         .route("/auth/me", get(auth_me))
         .route("/auth/providers", get(list_providers))
 }
@@ -52,9 +57,12 @@ struct ServerMetadata {
     authorization_endpoint: String,
     token_endpoint: String,
     registration_endpoint: String,
+    revocation_endpoint: String,
+    introspection_endpoint: String,
     response_types_supported: Vec<String>,
     grant_types_supported: Vec<String>,
     code_challenge_methods_supported: Vec<String>,
+    scopes_supported: Vec<String>,
 }
 
 async fn server_metadata(State(state): State<OAuthState>) -> impl IntoResponse {
@@ -64,9 +72,12 @@ async fn server_metadata(State(state): State<OAuthState>) -> impl IntoResponse {
         authorization_endpoint: format!("{base}/oauth/authorize"),
         token_endpoint: format!("{base}/oauth/token"),
         registration_endpoint: format!("{base}/oauth/register"),
+        revocation_endpoint: format!("{base}/oauth/revoke"),
+        introspection_endpoint: format!("{base}/oauth/introspect"),
         response_types_supported: vec!["code".into()],
         grant_types_supported: vec!["authorization_code".into(), "refresh_token".into()],
         code_challenge_methods_supported: vec!["S256".into()],
+        scopes_supported: SUPPORTED_SCOPES.iter().map(|s| s.to_string()).collect(),
     })
 }
 
@@ -95,7 +106,8 @@ async fn register_client(
     }
 
     let store = state.store.lock().await;
-    let (client, secret) = store.register_client(&body.redirect_uris, body.client_name.as_deref())?;
+    let (client, secret) =
+        store.register_client(&body.redirect_uris, body.client_name.as_deref())?;
 
     Ok((
         StatusCode::CREATED,
@@ -118,6 +130,8 @@ struct AuthorizeParams {
     code_challenge: String,
     code_challenge_method: Option<String>,
     response_type: Option<String>,
+    /// Space-separated scopes (defaults to `DEFAULT_SCOPE`)
+    scope: Option<String>,
     /// Which provider to use (defaults to first available)
     provider: Option<String>,
 }
@@ -130,17 +144,14 @@ async fn authorize(
     if params.response_type.as_deref().unwrap_or("code") != "code" {
         return Err(AppError::bad_request("unsupported response_type"));
     }
-    if params
-        .code_challenge_method
-        .as_deref()
-        .unwrap_or("S256")
-        != "S256"
-    {
+    if params.code_challenge_method.as_deref().unwrap_or("S256") != "S256" {
         return Err(AppError::bad_request(
             "unsupported code_challenge_method (only S256)",
         ));
     }
 
+    let scope = normalize_scope(params.scope.as_deref())?;
+
     let store = state.store.lock().await;
 
     // Validate client
@@ -166,26 +177,27 @@ async fn authorize(
     };
 
     let provider = Provider::from_db(db_provider)?;
-    drop(store);
 
-    // Build OAuth state that encodes our pending authorization
-    // Format: <random>:<client_id>:<redirect_uri>:<code_challenge>:<original_state>
-    let oauth_state = format!(
-        "{}:{}:{}:{}:{}",
-        trivia_core::auth_store::sha256_hex(&format!("{}{}", params.client_id, params.state)),
-        params.client_id,
-        base64_encode(&params.redirect_uri),
-        params.code_challenge,
-        params.state,
-    );
+    // The client's request waits server-side under an opaque state, so
+    // nothing the browser carries back to the callback is trusted
+    let authorization = PendingAuthorization {
+        client_id: params.client_id,
+        redirect_uri: params.redirect_uri,
+        code_challenge: params.code_challenge,
+        scope,
+        client_state: params.state,
+    };
+    let secrets = provider.login_secrets();
+    let oauth_state =
+        store.create_pending_login(&db_provider.name, Some(&authorization), &secrets)?;
+    drop(store);
 
-    let callback_uri = format!(
-        "{}/oauth/callback/{}",
-        state.external_url, db_provider.name
-    );
-    let auth_url = provider.authorize_url(&oauth_state, &callback_uri);
+    let callback_uri = format!("{}/oauth/callback/{}", state.external_url, db_provider.name);
+    let auth_url = provider
+        .authorize_url(&oauth_state, &callback_uri, &secrets)
+        .await?;
 
-    Ok(Redirect::temporary(&auth_url).into_response())
+    Ok(login_redirect(&auth_url, &oauth_state))
 }
 
 #[derive(Deserialize)]
@@ -198,33 +210,32 @@ async fn oauth_callback(
     State(state): State<OAuthState>,
     Path(provider_name): Path<String>,
     Query(params): Query<CallbackParams>,
+    headers: axum::http::HeaderMap,
 ) -> Result<Response, AppError> {
-    // Parse compound state
-    let parts: Vec<&str> = params.state.splitn(5, ':').collect();
-    if parts.len() != 5 {
-        return Err(AppError::bad_request("invalid state parameter"));
-    }
-    let (_hash, client_id, redirect_uri_b64, code_challenge, original_state) =
-        (parts[0], parts[1], parts[2], parts[3], parts[4]);
-    let redirect_uri = base64_decode(redirect_uri_b64)
-        .map_err(|_| AppError::bad_request("invalid state encoding"))?;
-
     let store = state.store.lock().await;
 
+    // Taken before anything else, so a replayed or tampered state fails
+    // without reaching the provider
+    let login = store.take_pending_login(&params.state)?;
+    let authorization = login
+        .authorization
+        .filter(|_| login.provider == provider_name && started_here(&headers, &params.state))
+        .ok_or_else(|| AppError::bad_request("state does not belong to this sign-in"))?;
+
     // Load provider
     let db_provider = store
         .get_provider_by_name(&provider_name)?
-        .ok_or_else(|| AppError::bad_request("unknown provider"))?;
+        .filter(|p| p.enabled)
+        .ok_or_else(|| AppError::bad_request("unknown or disabled provider"))?;
     let provider = Provider::from_db(&db_provider)?;
 
-    let callback_uri = format!(
-        "{}/oauth/callback/{}",
-        state.external_url, provider_name
-    );
+    let callback_uri = format!("{}/oauth/callback/{}", state.external_url, provider_name);
     drop(store);
 
     // Exchange code with provider
-    let provider_token = provider.exchange_code(&params.code, &callback_uri).await?;
+    let provider_token = provider
+        .exchange_code(&params.code, &callback_uri, &login.secrets)
+        .await?;
     let provider_user = provider.get_user_info(&provider_token).await?;
 
     let store = state.store.lock().await;
@@ -242,17 +253,233 @@ async fn oauth_callback(
             )
         })?;
 
-    // Create auth code for the client
-    let auth_code =
-        store.create_auth_code(client_id, user.id, code_challenge, &redirect_uri)?;
+    let authorization = PendingAuthorization {
+        scope: grantable_scope(&user, authorization.scope.clone()),
+        ..authorization
+    };
+
+    // Clients the user hasn't approved for this much access yet get the
+    // consent page instead of a code
+    let approved = store
+        .get_consent(user.id, &authorization.client_id)?
+        .is_some_and(|consent| {
+            authorization
+                .scope
+                .split_whitespace()
+                .all(|s| has_scope(&consent.scope, s))
+        });
+    if !approved {
+        let client = store
+            .get_client(&authorization.client_id)?
+            .ok_or_else(|| AppError::bad_request("unknown client_id"))?;
+        let token = store.create_pending_consent(user.id, &authorization)?;
+        return Ok(consent_page(&client, &authorization, &token));
+    }
+
+    let auth_code = store.create_auth_code(
+        &authorization.client_id,
+        user.id,
+        &authorization.code_challenge,
+        &authorization.redirect_uri,
+        Some(&authorization.scope),
+    )?;
+    Ok(redirect_to_client(
+        &authorization,
+        &format!("code={auth_code}"),
+    ))
+}
 
-    // Redirect back to client with code
+/// Send the browser back to the client with `params` and its own state.
+fn redirect_to_client(authorization: &PendingAuthorization, params: &str) -> Response {
+    let redirect_uri = &authorization.redirect_uri;
     let sep = if redirect_uri.contains('?') { "&" } else { "?" };
     let redirect_target = format!(
-        "{redirect_uri}{sep}code={auth_code}&state={original_state}"
+        "{redirect_uri}{sep}{params}&state={}",
+        authorization.client_state
+    );
+
+    (
+        [(axum::http::header::SET_COOKIE, CLEAR_LOGIN_COOKIE)],
+        Redirect::temporary(&redirect_target),
+    )
+        .into_response()
+}
+
+// --- Consent ---
+
+/// What each scope lets a client do, as shown on the consent page.
+fn describe_scope(scope: &str) -> &'static str {
+    match scope {
+        SCOPE_READ => "Read the memories you can read",
+        SCOPE_WRITE => "Create, edit and delete memories on your behalf",
+        SCOPE_ADMIN => "Manage users, groups and sign-in providers",
+        _ => "Unknown access",
+    }
+}
+
+fn escape_html(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Ask the user whether `client` may have `authorization`'s scope. The page
+/// can't be framed, so the buttons can't be clickjacked.
+fn consent_page(
+    client: &OAuthClient,
+    authorization: &PendingAuthorization,
+    token: &str,
+) -> Response {
+    let name = client.client_name.as_deref().unwrap_or(&client.client_id);
+    let scopes: String = authorization
+        .scope
+        .split_whitespace()
+        .map(|s| format!("<li><code>{s}</code>: {}</li>", describe_scope(s)))
+        .collect();
+    let html = format!(
+        r#"<!doctype html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>Authorize {name} - trivia</title>
+<style>
+body {{ font-family: system-ui, sans-serif; max-width: 32rem; margin: 4rem auto; padding: 0 1rem; color: #111827; }}
+code {{ background: #f3f4f6; padding: 0 .25rem; border-radius: .25rem; }}
+.uri {{ word-break: break-all; }}
+button {{ padding: .5rem 1rem; margin-right: .5rem; border: 1px solid #d1d5db; border-radius: .375rem; background: #fff; cursor: pointer; }}
+button[value=approve] {{ background: #2563eb; border-color: #2563eb; color: #fff; }}
+</style>
+</head>
+<body>
+<h1>Authorize {name}?</h1>
+<p>This application wants access to your trivia account:</p>
+<ul>{scopes}</ul>
+<p>You will be sent back to <code class="uri">{redirect_uri}</code>.</p>
+<p>Only approve applications you started signing in to yourself.</p>
+<form method="post" action="/oauth/consent">
+<input type="hidden" name="token" value="{token}">
+<button type="submit" name="decision" value="approve">Approve</button>
+<button type="submit" name="decision" value="deny">Deny</button>
+</form>
+</body>
+</html>
+"#,
+        name = escape_html(name),
+        redirect_uri = escape_html(&authorization.redirect_uri),
+        token = escape_html(token),
     );
+    (
+        [
+            (axum::http::header::SET_COOKIE, CLEAR_LOGIN_COOKIE),
+            (axum::http::header::X_FRAME_OPTIONS, "DENY"),
+            (
+                axum::http::header::CONTENT_SECURITY_POLICY,
+                "frame-ancestors 'none'",
+            ),
+            (axum::http::header::CACHE_CONTROL, "no-store"),
+        ],
+        Html(html),
+    )
+        .into_response()
+}
 
-    Ok(Redirect::temporary(&redirect_target).into_response())
+#[derive(Deserialize)]
+struct ConsentForm {
+    token: String,
+    /// `approve` or `deny`.
+    decision: String,
+}
+
+/// The consent page's answer. Approval is remembered, so the user isn't
+/// asked again until the client wants more scope or they revoke it.
+async fn consent(
+    State(state): State<OAuthState>,
+    axum::Form(form): axum::Form<ConsentForm>,
+) -> Result<Response, AppError> {
+    let store = state.store.lock().await;
+    let pending = store.take_pending_consent(&form.token)?;
+    let authorization = pending.authorization;
+    if form.decision != "approve" {
+        return Ok(redirect_to_client(&authorization, "error=access_denied"));
+    }
+    let user = store
+        .get_user_by_id(pending.user_id)?
+        .ok_or_else(|| AppError::bad_request("user no longer exists"))?;
+
+    store.grant_consent(user.id, &authorization.client_id, &authorization.scope)?;
+    store.record_audit(
+        Some(&user.username),
+        "client.approve",
+        Some(&authorization.client_id),
+        Some(&format!("scope: {}", authorization.scope)),
+    )?;
+    let auth_code = store.create_auth_code(
+        &authorization.client_id,
+        user.id,
+        &authorization.code_challenge,
+        &authorization.redirect_uri,
+        Some(&authorization.scope),
+    )?;
+    Ok(redirect_to_client(
+        &authorization,
+        &format!("code={auth_code}"),
+    ))
+}
+
+// --- Scopes ---
+
+/// Read the memories the user's ACL allows.
+pub const SCOPE_READ: &str = "memory:read";
+/// Also create, edit and delete them.
+pub const SCOPE_WRITE: &str = "memory:write";
+/// Use the admin API, for users with the admin role.
+pub const SCOPE_ADMIN: &str = "admin";
+const SUPPORTED_SCOPES: [&str; 3] = [SCOPE_READ, SCOPE_WRITE, SCOPE_ADMIN];
+/// Granted when a client asks for no scope, and assumed for tokens issued
+/// before scopes were recorded.
+pub const DEFAULT_SCOPE: &str = "memory:read memory:write";
+
+pub fn has_scope(scope: &str, wanted: &str) -> bool {
+    scope.split_whitespace().any(|s| s == wanted)
+}
+
+/// Only admins can be granted the admin scope; drop it for anyone else.
+pub fn grantable_scope(user: &User, scope: String) -> String {
+    if user.is_admin {
+        return scope;
+    }
+    scope
+        .split_whitespace()
+        .filter(|s| *s != SCOPE_ADMIN)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Check a requested scope and put it in canonical order. Unknown scopes
+/// are refused rather than dropped, so a typo can't widen a token.
+pub fn normalize_scope(requested: Option<&str>) -> Result<String, TriviaError> {
+    let requested = requested.unwrap_or_default();
+    if requested.trim().is_empty() {
+        return Ok(DEFAULT_SCOPE.into());
+    }
+    if let Some(unknown) = requested
+        .split_whitespace()
+        .find(|s| !SUPPORTED_SCOPES.contains(s))
+    {
+        return Err(TriviaError::validation(format!(
+            "invalid_scope: unknown scope {unknown} (supported: {})",
+            SUPPORTED_SCOPES.join(", ")
+        )));
+    }
+    Ok(SUPPORTED_SCOPES
+        .iter()
+        .filter(|s| has_scope(requested, s))
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" "))
 }
 
 // --- Token Exchange ---
@@ -275,6 +502,20 @@ struct TokenResponse {
     token_type: String,
     expires_in: i64,
     refresh_token: String,
+    scope: String,
+}
+
+impl TokenResponse {
+    fn new(pair: trivia_core::TokenPair) -> Self {
+        let expires_in = (pair.expires_at - chrono::Utc::now()).num_seconds();
+        Self {
+            access_token: pair.access_token,
+            token_type: "Bearer".into(),
+            expires_in,
+            refresh_token: pair.refresh_token,
+            scope: pair.scope.unwrap_or_else(|| DEFAULT_SCOPE.into()),
+        }
+    }
 }
 
 async fn token_exchange(
@@ -302,21 +543,18 @@ async fn token_exchange(
             }
 
             // Verify redirect_uri matches
-            if let Some(uri) = &body.redirect_uri {
-                if *uri != auth_code.redirect_uri {
-                    return Err(AppError::bad_request("redirect_uri mismatch"));
-                }
+            if let Some(uri) = &body.redirect_uri
+                && *uri != auth_code.redirect_uri
+            {
+                return Err(AppError::bad_request("redirect_uri mismatch"));
             }
 
-            let pair = store.create_token_pair(&auth_code.client_id, auth_code.user_id)?;
-            let expires_in = (pair.expires_at - chrono::Utc::now()).num_seconds();
-
-            Ok(axum::Json(TokenResponse {
-                access_token: pair.access_token,
-                token_type: "Bearer".into(),
-                expires_in,
-                refresh_token: pair.refresh_token,
-            }))
+            let pair = store.create_token_pair(
+                &auth_code.client_id,
+                auth_code.user_id,
+                auth_code.scope.as_deref(),
+            )?;
+            Ok(axum::Json(TokenResponse::new(pair)))
         }
         "refresh_token" => {
             let refresh = body
@@ -324,24 +562,13 @@ async fn token_exchange(
                 .as_deref()
                 .ok_or_else(|| AppError::bad_request("missing refresh_token"))?;
 
+            // Rotate: the old pair is revoked and a new one issued with the
+            // same scope
             let store = state.store.lock().await;
-            let (user, client_id) = store
-                .get_user_by_refresh_token(refresh)?
+            let pair = store
+                .refresh_token_pair(refresh)?
                 .ok_or_else(|| AppError::bad_request("invalid refresh_token"))?;
-
-            // Revoke old token pair
-            store.revoke_refresh_token(refresh)?;
-
-            // Issue new pair
-            let pair = store.create_token_pair(&client_id, user.id)?;
-            let expires_in = (pair.expires_at - chrono::Utc::now()).num_seconds();
-
-            Ok(axum::Json(TokenResponse {
-                access_token: pair.access_token,
-                token_type: "Bearer".into(),
-                expires_in,
-                refresh_token: pair.refresh_token,
-            }))
+            Ok(axum::Json(TokenResponse::new(pair)))
         }
         other => Err(AppError::bad_request(&format!(
             "unsupported grant_type: {other}"
@@ -349,6 +576,154 @@ async fn token_exchange(
     }
 }
 
+// --- Revocation (RFC 7009) and Introspection (RFC 7662) ---
+
+#[derive(Deserialize)]
+struct TokenParams {
+    token: String,
+    token_type_hint: Option<String>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+}
+
+/// The client making the request, from HTTP Basic auth or the
+/// `client_id`/`client_secret` form fields. `Some((id, true))` when a secret
+/// was given and checked, `Some((id, false))` for a public client that only
+/// named itself.
+fn authenticate_client(
+    store: &dyn Storage,
+    headers: &axum::http::HeaderMap,
+    params: &TokenParams,
+) -> Result<Option<(String, bool)>, AppError> {
+    let basic = headers
+        .get("authorization")
+        .and_then(|h| h.to_str().ok())
+        .and_then(|h| h.strip_prefix("Basic "))
+        .and_then(|b| base64_decode(b).ok());
+    let (client_id, secret) = match &basic {
+        Some(pair) => {
+            let (id, secret) = pair
+                .split_once(':')
+                .ok_or_else(|| AppError::status(StatusCode::UNAUTHORIZED, "invalid_client"))?;
+            (Some(id), Some(secret))
+        }
+        None => (params.client_id.as_deref(), params.client_secret.as_deref()),
+    };
+    let Some(client_id) = client_id else {
+        return Ok(None);
+    };
+    match secret {
+        Some(secret) if !store.verify_client_secret(client_id, secret)? => Err(AppError::status(
+            StatusCode::UNAUTHORIZED,
+            "invalid_client: client authentication failed",
+        )),
+        Some(_) => Ok(Some((client_id.to_string(), true))),
+        None => Ok(Some((client_id.to_string(), false))),
+    }
+}
+
+/// Look `token` up as an access or refresh token, trying the hinted type
+/// first. The flag is true for access tokens.
+fn find_grant(store: &dyn Storage, params: &TokenParams) -> Result<Option<(TokenGrant, bool)>> {
+    let refresh_first = params.token_type_hint.as_deref() == Some("refresh_token");
+    for is_access in [!refresh_first, refresh_first] {
+        let grant = if is_access {
+            store.get_user_by_access_token(&params.token)?
+        } else {
+            store.get_user_by_refresh_token(&params.token)?
+        };
+        if let Some(grant) = grant {
+            return Ok(Some((grant, is_access)));
+        }
+    }
+    Ok(None)
+}
+
+/// Revoke an access or refresh token, along with the rest of its pair.
+/// Unknown tokens succeed too, as RFC 7009 requires.
+async fn revoke_token(
+    State(state): State<OAuthState>,
+    headers: axum::http::HeaderMap,
+    axum::Form(params): axum::Form<TokenParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let store = state.store.lock().await;
+    let (client_id, _) =
+        authenticate_client(store.as_ref(), &headers, &params)?.ok_or_else(|| {
+            AppError::status(
+                StatusCode::UNAUTHORIZED,
+                "invalid_client: client_id required",
+            )
+        })?;
+    if let Some((grant, _)) = find_grant(store.as_ref(), &params)? {
+        if grant.client_id != client_id {
+            return Err(AppError::bad_request(
+                "unauthorized_client: token was issued to another client",
+            ));
+        }
+        store.revoke_token(&params.token)?;
+    }
+    Ok(StatusCode::OK)
+}
+
+#[derive(Serialize, Default)]
+struct IntrospectionResponse {
+    active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scope: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    client_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    token_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    exp: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    iat: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sub: Option<String>,
+}
+
+/// Report whether a token is active and who it belongs to. Callers must
+/// authenticate with a client secret; any registered client may ask about
+/// any token.
+async fn introspect_token(
+    State(state): State<OAuthState>,
+    headers: axum::http::HeaderMap,
+    axum::Form(params): axum::Form<TokenParams>,
+) -> Result<impl IntoResponse, AppError> {
+    let store = state.store.lock().await;
+    match authenticate_client(store.as_ref(), &headers, &params)? {
+        Some((_, true)) => {}
+        _ => {
+            return Err(AppError::status(
+                StatusCode::UNAUTHORIZED,
+                "invalid_client: client authentication required",
+            ));
+        }
+    }
+    let Some((grant, is_access)) = find_grant(store.as_ref(), &params)? else {
+        return Ok(axum::Json(IntrospectionResponse::default()));
+    };
+    Ok(axum::Json(IntrospectionResponse {
+        active: true,
+        scope: Some(grant.scope.unwrap_or_else(|| DEFAULT_SCOPE.into())),
+        client_id: Some(grant.client_id),
+        username: Some(grant.user.username),
+        token_type: is_access.then(|| "Bearer".into()),
+        exp: Some(
+            if is_access {
+                grant.expires_at
+            } else {
+                grant.refresh_expires_at
+            }
+            .timestamp(),
+        ),
+        iat: Some(grant.issued_at.timestamp()),
+        sub: Some(grant.user.id.to_string()),
+    }))
+}
+
 // --- Web UI Auth ---
 
 async fn auth_login(
@@ -358,35 +733,50 @@ async fn auth_login(
     let store = state.store.lock().await;
     let db_provider = store
         .get_provider_by_name(&provider_name)?
-        .ok_or_else(|| AppError::bad_request("unknown provider"))?;
+        .filter(|p| p.enabled)
+        .ok_or_else(|| AppError::bad_request("unknown or disabled provider"))?;
     let provider = Provider::from_db(&db_provider)?;
-    drop(store);
 
-    // Generate a random state for CSRF protection
-    let csrf_state = trivia_core::auth_store::sha256_hex(
-        &format!("webui-{}", rand::random::<u64>()),
-    );
+    // Checked by the callback, so it only finishes sign-ins started here
+    let secrets = provider.login_secrets();
+    let csrf_state = store.create_pending_login(&db_provider.name, None, &secrets)?;
+    drop(store);
     let callback_uri = format!("{}/auth/callback/{}", state.external_url, provider_name);
-    let auth_url = provider.authorize_url(&csrf_state, &callback_uri);
+    let auth_url = provider
+        .authorize_url(&csrf_state, &callback_uri, &secrets)
+        .await?;
 
-    Ok(Redirect::temporary(&auth_url).into_response())
+    Ok(login_redirect(&auth_url, &csrf_state))
 }
 
 async fn auth_callback(
     State(state): State<OAuthState>,
     Path(provider_name): Path<String>,
     Query(params): Query<CallbackParams>,
+    headers: axum::http::HeaderMap,
 ) -> Result<Response, AppError> {
     let store = state.store.lock().await;
+    let login = store.take_pending_login(&params.state)?;
+    if login.provider != provider_name
+        || login.authorization.is_some()
+        || !started_here(&headers, &params.state)
+    {
+        return Err(AppError::bad_request(
+            "state does not belong to this sign-in",
+        ));
+    }
     let db_provider = store
         .get_provider_by_name(&provider_name)?
-        .ok_or_else(|| AppError::bad_request("unknown provider"))?;
+        .filter(|p| p.enabled)
+        .ok_or_else(|| AppError::bad_request("unknown or disabled provider"))?;
     let provider = Provider::from_db(&db_provider)?;
 
     let callback_uri = format!("{}/auth/callback/{}", state.external_url, provider_name);
     drop(store);
 
-    let provider_token = provider.exchange_code(&params.code, &callback_uri).await?;
+    let provider_token = provider
+        .exchange_code(&params.code, &callback_uri, &login.secrets)
+        .await?;
     let provider_user = provider.get_user_info(&provider_token).await?;
 
     let store = state.store.lock().await;
@@ -405,14 +795,19 @@ async fn auth_callback(
 
     let session = store.create_session(user.id)?;
 
-    // Set cookie and redirect to /
+    // Set cookie and redirect to /. The cookie lasts as long as the session
+    // could; the server ends it sooner if it goes idle.
+    let max_age = (session.max_expires_at - chrono::Utc::now()).num_seconds();
     let cookie = format!(
-        "trivia_session={}; HttpOnly; SameSite=Lax; Path=/; Max-Age=2592000",
+        "trivia_session={}; HttpOnly; SameSite=Lax; Path=/; Max-Age={max_age}",
         session.session_id
     );
 
     Ok((
-        [(axum::http::header::SET_COOKIE, cookie)],
+        AppendHeaders([
+            (axum::http::header::SET_COOKIE, cookie),
+            (axum::http::header::SET_COOKIE, CLEAR_LOGIN_COOKIE.into()),
+        ]),
         Redirect::temporary("/"),
     )
         .into_response())
@@ -432,10 +827,35 @@ async fn auth_logout(State(state): State<OAuthState>, headers: axum::http::Heade
         .into_response()
 }
 
+/// Sign the current user out of every web session, not just this one.
+async fn auth_logout_all(
+    State(state): State<OAuthState>,
+    headers: axum::http::HeaderMap,
+) -> Result<Response, AppError> {
+    let store = state.store.lock().await;
+    let (_, user) = extract_session_cookie(&headers)
+        .map(|id| store.get_session(&id))
+        .transpose()?
+        .flatten()
+        .ok_or_else(|| AppError::status(StatusCode::UNAUTHORIZED, "not authenticated"))?;
+    let revoked = store.revoke_user_sessions(user.id)?;
+
+    let clear = "trivia_session=; HttpOnly; SameSite=Lax; Path=/; Max-Age=0";
+    Ok((
+        [(axum::http::header::SET_COOKIE, clear)],
+        axum::Json(serde_json::json!({"ok": true, "sessions": revoked})),
+    )
+        .into_response())
+}
+
 #[derive(Serialize)]
 struct MeResponse {
     username: String,
     acl: String,
+    is_admin: bool,
+    /// Scope of the bearer token, if signed in with one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scope: Option<String>,
 }
 
 async fn auth_me(
@@ -443,10 +863,12 @@ async fn auth_me(
     headers: axum::http::HeaderMap,
 ) -> Result<Response, AppError> {
     // Try bearer token first
-    if let Some(user) = extract_bearer_user(&state, &headers).await? {
+    if let Some(bearer) = extract_bearer_user(&state, &headers).await? {
         return Ok(axum::Json(MeResponse {
-            username: user.username,
-            acl: user.acl,
+            username: bearer.user.username,
+            acl: bearer.user.acl,
+            is_admin: bearer.user.is_admin && has_scope(&bearer.scope, SCOPE_ADMIN),
+            scope: Some(bearer.scope),
         })
         .into_response());
     }
@@ -458,17 +880,20 @@ async fn auth_me(
             return Ok(axum::Json(MeResponse {
                 username: user.username,
                 acl: user.acl,
+                is_admin: user.is_admin,
+                scope: None,
             })
             .into_response());
         }
     }
 
-    Err(AppError::status(StatusCode::UNAUTHORIZED, "not authenticated"))
+    Err(AppError::status(
+        StatusCode::UNAUTHORIZED,
+        "not authenticated",
+    ))
 }
 
-async fn list_providers(
-    State(state): State<OAuthState>,
-) -> Result<impl IntoResponse, AppError> {
+async fn list_providers(State(state): State<OAuthState>) -> Result<impl IntoResponse, AppError> {
     let store = state.store.lock().await;
     let providers = store.list_enabled_providers()?;
     let names: Vec<&str> = providers.iter().map(|(name, _)| name.as_str()).collect();
@@ -477,23 +902,59 @@ async fn list_providers(
 
 // --- Helpers ---
 
-pub fn extract_session_cookie(headers: &axum::http::HeaderMap) -> Option<String> {
+/// Binds a sign-in to the browser that started it: holds the hash of its
+/// `state`, while everything else about the sign-in stays server-side.
+const LOGIN_COOKIE: &str = "trivia_login";
+const CLEAR_LOGIN_COOKIE: &str = "trivia_login=; HttpOnly; SameSite=Lax; Path=/; Max-Age=0";
+
+/// Redirect to the provider, binding the sign-in with `oauth_state` to
+/// this browser.
+fn login_redirect(auth_url: &str, oauth_state: &str) -> Response {
+    let state_hash = trivia_core::auth_store::sha256_hex(oauth_state);
+    let cookie =
+        format!("{LOGIN_COOKIE}={state_hash}; HttpOnly; SameSite=Lax; Path=/; Max-Age=600");
+    (
+        [(axum::http::header::SET_COOKIE, cookie)],
+        Redirect::temporary(auth_url),
+    )
+        .into_response()
+}
+
+/// Whether the sign-in with `oauth_state` was started by this browser.
+fn started_here(headers: &axum::http::HeaderMap, oauth_state: &str) -> bool {
+    extract_cookie(headers, LOGIN_COOKIE)
+        .is_some_and(|hash| hash == trivia_core::auth_store::sha256_hex(oauth_state))
+}
+
+fn extract_cookie(headers: &axum::http::HeaderMap, name: &str) -> Option<String> {
     let cookie_header = headers.get("cookie")?.to_str().ok()?;
     for part in cookie_header.split(';') {
-        let part = part.trim();
-        if let Some(value) = part.strip_prefix("trivia_session=") {
-            if !value.is_empty() {
-                return Some(value.to_string());
-            }
+        if let Some((key, value)) = part.trim().split_once('=')
+            && key == name
+            && !value.is_empty()
+        {
+            return Some(value.to_string());
         }
     }
     None
 }
 
+pub fn extract_session_cookie(headers: &axum::http::HeaderMap) -> Option<String> {
+    extract_cookie(headers, "trivia_session")
+}
+
+/// The user a bearer token authenticates, and the scope it carries.
+pub struct BearerUser {
+    pub user: User,
+    pub scope: String,
+}
+
+/// Resolve an `Authorization: Bearer` header, which may hold an OAuth
+/// access token or a personal access token.
 pub async fn extract_bearer_user(
     state: &OAuthState,
     headers: &axum::http::HeaderMap,
-) -> Result<Option<trivia_core::User>> {
+) -> Result<Option<BearerUser>> {
     let auth_header = match headers.get("authorization") {
         Some(h) => h.to_str().unwrap_or(""),
         None => return Ok(None),
@@ -503,17 +964,30 @@ pub async fn extract_bearer_user(
         None => return Ok(None),
     };
     let store = state.store.lock().await;
-    Ok(store.get_user_by_access_token(token)?)
+    if token.starts_with(PERSONAL_TOKEN_PREFIX) {
+        return Ok(store
+            .get_user_by_personal_token(token)?
+            .map(|(token, user)| BearerUser {
+                user,
+                scope: token.scope,
+            }));
+    }
+    Ok(store
+        .get_user_by_access_token(token)?
+        .map(|grant| BearerUser {
+            user: grant.user,
+            scope: grant.scope.unwrap_or_else(|| DEFAULT_SCOPE.into()),
+        }))
 }
 
-fn pkce_challenge(verifier: &str) -> String {
+pub(crate) fn pkce_challenge(verifier: &str) -> String {
     let mut hasher = Sha256::new();
     hasher.update(verifier.as_bytes());
     let hash = hasher.finalize();
     base64_url_encode(&hash)
 }
 
-fn base64_url_encode(data: &[u8]) -> String {
+pub(crate) fn base64_url_encode(data: &[u8]) -> String {
     let encoded = base64_encode_bytes(data);
     encoded
         .replace('+', "-")
@@ -522,10 +996,6 @@ fn base64_url_encode(data: &[u8]) -> String {
         .to_string()
 }
 
-fn base64_encode(s: &str) -> String {
-    base64_encode_bytes(s.as_bytes())
-}
-
 fn base64_encode_bytes(data: &[u8]) -> String {
     const CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
     let mut result = String::new();
@@ -579,7 +1049,7 @@ fn base64_decode(s: &str) -> Result<String> {
                 }
             })
             .collect();
-        if vals.iter().any(|&v| v == 255) {
+        if vals.contains(&255) {
             anyhow::bail!("invalid base64");
         }
         let n = (vals[0] as u32) << 18
@@ -600,6 +1070,18 @@ fn base64_decode(s: &str) -> Result<String> {
 
 // --- Error type ---
 
+/// HTTP status for an error from trivia-core. Storage and embedding failures
+/// are the server's fault and may be retried; the rest are the client's.
+pub fn error_status(err: &TriviaError) -> StatusCode {
+    match err {
+        TriviaError::NotFound(_) => StatusCode::NOT_FOUND,
+        TriviaError::Conflict(_) => StatusCode::CONFLICT,
+        TriviaError::Validation(_) => StatusCode::BAD_REQUEST,
+        TriviaError::AccessDenied(_) => StatusCode::FORBIDDEN,
+        TriviaError::Storage(_) | TriviaError::Embedding(_) => StatusCode::INTERNAL_SERVER_ERROR,
+    }
+}
+
 pub struct AppError {
     status: StatusCode,
     message: String,
@@ -631,10 +1113,19 @@ impl IntoResponse for AppError {
     }
 }
 
+impl From<TriviaError> for AppError {
+    fn from(err: TriviaError) -> Self {
+        Self {
+            status: error_status(&err),
+            message: err.to_string(),
+        }
+    }
+}
+
 impl From<anyhow::Error> for AppError {
     fn from(err: anyhow::Error) -> Self {
         Self {
-            status: StatusCode::INTERNAL_SERVER_ERROR,
+            status: TriviaError::find(&err).map_or(StatusCode::INTERNAL_SERVER_ERROR, error_status),
             message: err.to_string(),
         }
     }
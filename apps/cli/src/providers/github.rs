@@ -58,6 +58,7 @@ impl GitHubProvider {
         let token_resp: GitHubTokenResponse = resp.json().await?;
         Ok(ProviderToken {
             access_token: token_resp.access_token,
+            claims: None,
         })
     }
 
@@ -1,7 +1,10 @@
 pub mod github;
+pub mod oidc;
 
 use anyhow::Result;
 
+pub use trivia_core::LoginSecrets;
+
 pub struct ProviderUser {
     pub provider_user_id: String,
     pub username: String,
@@ -9,11 +12,14 @@ pub struct ProviderUser {
 
 pub struct ProviderToken {
     pub access_token: String,
+    /// Verified ID token claims, for OpenID Connect providers.
+    pub claims: Option<serde_json::Map<String, serde_json::Value>>,
 }
 
 /// Enum dispatch over supported OAuth providers.
 pub enum Provider {
     GitHub(github::GitHubProvider),
+    Oidc(oidc::OidcProvider),
 }
 
 impl Provider {
@@ -24,25 +30,66 @@ impl Provider {
                 db_provider.client_id.clone(),
                 db_provider.client_secret.clone(),
             ))),
+            t if oidc::is_oidc_type(t) => Ok(Provider::Oidc(oidc::OidcProvider::new(
+                t,
+                db_provider.client_id.clone(),
+                db_provider.client_secret.clone(),
+                &db_provider.config,
+            )?)),
             other => anyhow::bail!("unsupported provider type: {other}"),
         }
     }
 
-    pub fn authorize_url(&self, state: &str, redirect_uri: &str) -> String {
+    /// Check that a provider of `provider_type` can be built from `config`,
+    /// before it is saved.
+    pub fn validate(provider_type: &str, config: &str) -> Result<()> {
+        match provider_type {
+            "github" => Ok(()),
+            t if oidc::is_oidc_type(t) => {
+                oidc::OidcProvider::new(t, String::new(), String::new(), config).map(|_| ())
+            }
+            other => anyhow::bail!(
+                "unsupported provider type: {other} (expected github, oidc, gitlab or google)"
+            ),
+        }
+    }
+
+    /// Fresh secrets for a sign-in, to keep server-side until its callback.
+    pub fn login_secrets(&self) -> LoginSecrets {
+        match self {
+            Provider::GitHub(_) => LoginSecrets::default(),
+            Provider::Oidc(_) => oidc::OidcProvider::login_secrets(),
+        }
+    }
+
+    pub async fn authorize_url(
+        &self,
+        state: &str,
+        redirect_uri: &str,
+        secrets: &LoginSecrets,
+    ) -> Result<String> {
         match self {
-            Provider::GitHub(p) => p.authorize_url(state, redirect_uri),
+            Provider::GitHub(p) => Ok(p.authorize_url(state, redirect_uri)),
+            Provider::Oidc(p) => p.authorize_url(state, redirect_uri, secrets).await,
         }
     }
 
-    pub async fn exchange_code(&self, code: &str, redirect_uri: &str) -> Result<ProviderToken> {
+    pub async fn exchange_code(
+        &self,
+        code: &str,
+        redirect_uri: &str,
+        secrets: &LoginSecrets,
+    ) -> Result<ProviderToken> {
         match self {
             Provider::GitHub(p) => p.exchange_code(code, redirect_uri).await,
+            Provider::Oidc(p) => p.exchange_code(code, redirect_uri, secrets).await,
         }
     }
 
     pub async fn get_user_info(&self, token: &ProviderToken) -> Result<ProviderUser> {
         match self {
             Provider::GitHub(p) => p.get_user_info(token).await,
+            Provider::Oidc(p) => p.get_user_info(token).await,
         }
     }
 }
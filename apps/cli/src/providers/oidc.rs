@@ -0,0 +1,311 @@
+use anyhow::{Context, Result, anyhow, bail};
+use jsonwebtoken::jwk::JwkSet;
+use jsonwebtoken::{Algorithm, DecodingKey, Validation};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use trivia_core::TriviaError;
+
+use super::{LoginSecrets, ProviderToken, ProviderUser};
+use crate::oauth::{base64_url_encode, pkce_challenge};
+
+/// Signature algorithms accepted on ID tokens. HMAC algorithms are left out:
+/// they'd make the client secret the verification key.
+const ALLOWED_ALGORITHMS: &[Algorithm] = &[
+    Algorithm::RS256,
+    Algorithm::RS384,
+    Algorithm::RS512,
+    Algorithm::PS256,
+    Algorithm::PS384,
+    Algorithm::PS512,
+    Algorithm::ES256,
+    Algorithm::ES384,
+    Algorithm::EdDSA,
+];
+
+/// Provider settings kept in `oauth_providers.config`.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct OidcConfig {
+    /// Issuer URL. The discovery document is read from
+    /// `{issuer}/.well-known/openid-configuration`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub issuer: Option<String>,
+    /// Claim used as the trivia-side username (default: `preferred_username`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username_claim: Option<String>,
+    /// Space-separated scopes to request (default: `openid profile email`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scopes: Option<String>,
+}
+
+/// Defaults for a provider type: `(issuer, username_claim)`. Plain `oidc`
+/// has no default issuer.
+fn preset(provider_type: &str) -> Option<(Option<&'static str>, &'static str)> {
+    match provider_type {
+        "oidc" => Some((None, "preferred_username")),
+        "gitlab" => Some((Some("https://gitlab.com"), "nickname")),
+        "google" => Some((Some("https://accounts.google.com"), "email")),
+        _ => None,
+    }
+}
+
+/// Whether `provider_type` is handled by `OidcProvider`.
+pub fn is_oidc_type(provider_type: &str) -> bool {
+    preset(provider_type).is_some()
+}
+
+/// The subset of the discovery document we use.
+#[derive(Deserialize)]
+struct Discovery {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+    userinfo_endpoint: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    id_token: Option<String>,
+}
+
+pub struct OidcProvider {
+    client_id: String,
+    client_secret: String,
+    issuer: String,
+    username_claim: String,
+    scopes: String,
+}
+
+impl OidcProvider {
+    pub fn new(
+        provider_type: &str,
+        client_id: String,
+        client_secret: String,
+        config: &str,
+    ) -> Result<Self> {
+        let (default_issuer, default_claim) = preset(provider_type)
+            .ok_or_else(|| anyhow!("not an OIDC provider type: {provider_type}"))?;
+        let config: OidcConfig =
+            serde_json::from_str(if config.is_empty() { "{}" } else { config })
+                .context("invalid provider config")?;
+        let issuer = config
+            .issuer
+            .or_else(|| default_issuer.map(String::from))
+            .ok_or_else(|| anyhow!("{provider_type} providers need an issuer URL"))?;
+        Ok(Self {
+            client_id,
+            client_secret,
+            issuer: issuer.trim_end_matches('/').to_string(),
+            username_claim: config
+                .username_claim
+                .unwrap_or_else(|| default_claim.into()),
+            scopes: config
+                .scopes
+                .unwrap_or_else(|| "openid profile email".into()),
+        })
+    }
+
+    /// Fetch the discovery document. Not cached: logins are rare, and this
+    /// way endpoint and key changes at the provider are picked up at once.
+    async fn discover(&self) -> Result<Discovery> {
+        let url = format!("{}/.well-known/openid-configuration", self.issuer);
+        let resp = reqwest::get(&url).await?;
+        if !resp.status().is_success() {
+            bail!("OIDC discovery failed: {} returned {}", url, resp.status());
+        }
+        let discovery: Discovery = resp
+            .json()
+            .await
+            .context("invalid OIDC discovery document")?;
+        if discovery.issuer.trim_end_matches('/') != self.issuer {
+            bail!(
+                "OIDC discovery issuer mismatch: expected {}, got {}",
+                self.issuer,
+                discovery.issuer
+            );
+        }
+        Ok(discovery)
+    }
+
+    /// A fresh PKCE verifier and nonce.
+    pub fn login_secrets() -> LoginSecrets {
+        LoginSecrets {
+            code_verifier: Some(random_token()),
+            nonce: Some(random_token()),
+        }
+    }
+
+    pub async fn authorize_url(
+        &self,
+        state: &str,
+        redirect_uri: &str,
+        secrets: &LoginSecrets,
+    ) -> Result<String> {
+        let discovery = self.discover().await?;
+        let challenge = pkce_challenge(secrets.code_verifier.as_deref().unwrap_or_default());
+        let url = reqwest::Url::parse_with_params(
+            &discovery.authorization_endpoint,
+            &[
+                ("response_type", "code"),
+                ("client_id", self.client_id.as_str()),
+                ("redirect_uri", redirect_uri),
+                ("scope", self.scopes.as_str()),
+                ("state", state),
+                ("nonce", secrets.nonce.as_deref().unwrap_or_default()),
+                ("code_challenge", challenge.as_str()),
+                ("code_challenge_method", "S256"),
+            ],
+        )
+        .context("invalid authorization_endpoint")?;
+        Ok(url.into())
+    }
+
+    pub async fn exchange_code(
+        &self,
+        code: &str,
+        redirect_uri: &str,
+        secrets: &LoginSecrets,
+    ) -> Result<ProviderToken> {
+        let (Some(verifier), Some(nonce)) = (&secrets.code_verifier, &secrets.nonce) else {
+            return Err(
+                TriviaError::validation("login session expired, please sign in again").into(),
+            );
+        };
+        let discovery = self.discover().await?;
+        let resp = reqwest::Client::new()
+            .post(&discovery.token_endpoint)
+            .header("Accept", "application/json")
+            .form(&[
+                ("grant_type", "authorization_code"),
+                ("code", code),
+                ("redirect_uri", redirect_uri),
+                ("client_id", &self.client_id),
+                ("client_secret", &self.client_secret),
+                ("code_verifier", verifier),
+            ])
+            .send()
+            .await?;
+        if !resp.status().is_success() {
+            let text = resp.text().await.unwrap_or_default();
+            return Err(anyhow!("OIDC token exchange failed: {text}"));
+        }
+        let token: TokenResponse = resp.json().await?;
+        let id_token = token
+            .id_token
+            .ok_or_else(|| anyhow!("OIDC token response has no id_token"))?;
+        let claims = self.verify_id_token(&id_token, &discovery, nonce).await?;
+        Ok(ProviderToken {
+            access_token: token.access_token,
+            claims: Some(claims),
+        })
+    }
+
+    /// Check the ID token's signature against the provider's JWKS, plus its
+    /// issuer, audience, expiry and nonce.
+    async fn verify_id_token(
+        &self,
+        id_token: &str,
+        discovery: &Discovery,
+        nonce: &str,
+    ) -> Result<Map<String, Value>> {
+        let header = jsonwebtoken::decode_header(id_token).context("malformed ID token")?;
+        if !ALLOWED_ALGORITHMS.contains(&header.alg) {
+            bail!(
+                "ID token signed with unsupported algorithm {:?}",
+                header.alg
+            );
+        }
+        let jwks: JwkSet = reqwest::get(&discovery.jwks_uri)
+            .await?
+            .error_for_status()?
+            .json()
+            .await
+            .context("invalid JWKS")?;
+        let jwk = match &header.kid {
+            Some(kid) => jwks.find(kid),
+            // Without a kid, only an unambiguous key set will do
+            None if jwks.keys.len() == 1 => jwks.keys.first(),
+            None => None,
+        }
+        .ok_or_else(|| anyhow!("no matching key in the provider's JWKS"))?;
+        let key = DecodingKey::from_jwk(jwk).context("unusable key in JWKS")?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_audience(&[&self.client_id]);
+        validation.set_issuer(&[&discovery.issuer]);
+        validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+        let data = jsonwebtoken::decode::<Map<String, Value>>(id_token, &key, &validation)
+            .context("ID token validation failed")?;
+        if data.claims.get("nonce").and_then(Value::as_str) != Some(nonce) {
+            bail!("ID token nonce mismatch");
+        }
+        Ok(data.claims)
+    }
+
+    pub async fn get_user_info(&self, token: &ProviderToken) -> Result<ProviderUser> {
+        let claims = token
+            .claims
+            .as_ref()
+            .ok_or_else(|| anyhow!("missing ID token claims"))?;
+        let sub = claims
+            .get("sub")
+            .and_then(Value::as_str)
+            .ok_or_else(|| anyhow!("ID token has no sub claim"))?;
+
+        let username = match claims.get(&self.username_claim).and_then(Value::as_str) {
+            Some(name) => name.to_string(),
+            // Some providers only put profile claims in the userinfo response
+            None => self.userinfo_claim(&token.access_token, sub).await?,
+        };
+        Ok(ProviderUser {
+            provider_user_id: sub.to_string(),
+            username,
+        })
+    }
+
+    async fn userinfo_claim(&self, access_token: &str, sub: &str) -> Result<String> {
+        let claim_missing = || anyhow!("provider didn't return a {} claim", self.username_claim);
+        let discovery = self.discover().await?;
+        let endpoint = discovery.userinfo_endpoint.ok_or_else(claim_missing)?;
+        let info: Map<String, Value> = reqwest::Client::new()
+            .get(&endpoint)
+            .bearer_auth(access_token)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if info.get("sub").and_then(Value::as_str) != Some(sub) {
+            bail!("userinfo sub doesn't match the ID token");
+        }
+        info.get(&self.username_claim)
+            .and_then(Value::as_str)
+            .map(String::from)
+            .ok_or_else(claim_missing)
+    }
+}
+
+fn random_token() -> String {
+    base64_url_encode(&rand::random::<[u8; 32]>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_fill_in_defaults() {
+        let gitlab = OidcProvider::new("gitlab", "id".into(), "s".into(), "{}").unwrap();
+        assert_eq!(gitlab.issuer, "https://gitlab.com");
+        assert_eq!(gitlab.username_claim, "nickname");
+
+        let own = r#"{"issuer": "https://git.example.com/", "username_claim": "email"}"#;
+        let gitlab = OidcProvider::new("gitlab", "id".into(), "s".into(), own).unwrap();
+        assert_eq!(gitlab.issuer, "https://git.example.com");
+        assert_eq!(gitlab.username_claim, "email");
+
+        assert!(OidcProvider::new("oidc", "id".into(), "s".into(), "{}").is_err());
+        assert!(OidcProvider::new("github", "id".into(), "s".into(), "{}").is_err());
+    }
+}
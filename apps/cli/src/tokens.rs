@@ -0,0 +1,162 @@
+//! `/api/tokens`: personal access tokens for the signed-in user, so scripts
+//! and CI jobs can call the API and MCP endpoint without a browser. Tokens
+//! are managed from a web session only, so a token can't mint itself a
+//! wider one. `trivia admin create-token` does the same from the shell.
+
+use axum::{
+    Extension, Router,
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    routing::{delete, get},
+};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use tower_mcp::oauth::token::TokenClaims;
+use trivia_core::{PersonalToken, Storage, TriviaError, User};
+
+use crate::oauth::{self, SharedStore};
+use crate::www::AppResult;
+
+pub fn tokens_router(store: SharedStore) -> Router {
+    Router::new()
+        .route("/api/tokens", get(list_tokens).post(create_token))
+        .route("/api/tokens/{name}", delete(revoke_token))
+        .with_state(store)
+}
+
+#[derive(Serialize)]
+pub struct TokenView {
+    pub name: String,
+    pub scope: String,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl From<PersonalToken> for TokenView {
+    fn from(token: PersonalToken) -> Self {
+        Self {
+            name: token.name,
+            scope: token.scope,
+            expires_at: token.expires_at,
+            created_at: token.created_at,
+            last_used_at: token.last_used_at,
+        }
+    }
+}
+
+/// Create a token for `user`. The requested scope is checked as for OAuth
+/// clients, and `expires_in_days` of `None` means the token never expires.
+/// Returns the token and its secret.
+pub fn issue_token(
+    store: &dyn Storage,
+    user: &User,
+    name: &str,
+    scope: Option<&str>,
+    expires_in_days: Option<u32>,
+) -> Result<(PersonalToken, String), TriviaError> {
+    let scope = oauth::grantable_scope(user, oauth::normalize_scope(scope)?);
+    if scope.is_empty() {
+        return Err(TriviaError::validation("token would have no scope"));
+    }
+    let expires_at = match expires_in_days {
+        Some(0) => {
+            return Err(TriviaError::validation(
+                "expires_in_days must be at least 1",
+            ));
+        }
+        Some(days) => Some(Utc::now() + Duration::days(days.into())),
+        None => None,
+    };
+    store.create_personal_token(user.id, name, &scope, expires_at)
+}
+
+/// The user behind a web session. Bearer tokens carry a scope; sessions
+/// don't. Also used by `/api/clients`.
+pub(crate) fn session_user(store: &dyn Storage, claims: &TokenClaims) -> Result<User, TriviaError> {
+    let username = claims
+        .sub
+        .as_deref()
+        .ok_or_else(|| TriviaError::validation("this needs a signed-in user"))?;
+    if claims.scope.is_some() {
+        return Err(TriviaError::access_denied(
+            "personal access tokens and approved clients can only be managed from a web session",
+        ));
+    }
+    store
+        .get_user_by_username(username)?
+        .ok_or_else(|| TriviaError::not_found(format!("user not found: {username}")))
+}
+
+async fn list_tokens(
+    State(store): State<SharedStore>,
+    Extension(claims): Extension<TokenClaims>,
+) -> AppResult<impl IntoResponse> {
+    let store = store.lock().await;
+    let user = session_user(&**store, &claims)?;
+    let tokens: Vec<TokenView> = store
+        .list_personal_tokens(user.id)?
+        .into_iter()
+        .map(TokenView::from)
+        .collect();
+    Ok(axum::Json(tokens))
+}
+
+#[derive(Deserialize)]
+struct CreateTokenReq {
+    name: String,
+    scope: Option<String>,
+    expires_in_days: Option<u32>,
+}
+
+#[derive(Serialize)]
+struct CreatedToken {
+    #[serde(flatten)]
+    view: TokenView,
+    /// The secret, shown only this once.
+    token: String,
+}
+
+async fn create_token(
+    State(store): State<SharedStore>,
+    Extension(claims): Extension<TokenClaims>,
+    axum::Json(body): axum::Json<CreateTokenReq>,
+) -> AppResult<impl IntoResponse> {
+    let store = store.lock().await;
+    let user = session_user(&**store, &claims)?;
+    let (token, secret) = issue_token(
+        &**store,
+        &user,
+        &body.name,
+        body.scope.as_deref(),
+        body.expires_in_days,
+    )?;
+    store.record_audit(
+        Some(&user.username),
+        "token.create",
+        Some(&token.name),
+        Some(&format!("scope: {}", token.scope)),
+    )?;
+    Ok((
+        StatusCode::CREATED,
+        axum::Json(CreatedToken {
+            view: token.into(),
+            token: secret,
+        }),
+    ))
+}
+
+async fn revoke_token(
+    State(store): State<SharedStore>,
+    Extension(claims): Extension<TokenClaims>,
+    Path(name): Path<String>,
+) -> AppResult<impl IntoResponse> {
+    let store = store.lock().await;
+    let user = session_user(&**store, &claims)?;
+    if !store.delete_personal_token(user.id, &name)? {
+        return Err(TriviaError::not_found(format!("token not found: {name}")).into());
+    }
+    store.record_audit(Some(&user.username), "token.revoke", Some(&name), None)?;
+    Ok(axum::Json(serde_json::json!({"ok": true})))
+}
@@ -1,8 +1,11 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::Result;
 use axum::{
-    Router,
+    Extension, Router,
     extract::{Path, Query, State},
     http::{StatusCode, header},
     middleware,
@@ -13,27 +16,58 @@ use include_dir::{Dir, include_dir};
 use serde::{Deserialize, Serialize};
 use tokio::sync::Mutex;
 use tower_http::cors::CorsLayer;
+use tower_mcp::oauth::token::TokenClaims;
 use tower_mcp::transport::http::HttpTransport;
-use trivia_core::{Embedder, MemoryStore, TriviaConfig};
+use trivia_core::backup::{backup_database, rotate_backups, scheduled_backup_path};
+use trivia_core::config::{BackupConfig, Visibility};
+use trivia_core::{Actor, Embedder, Memory, Storage, Transport, TriviaConfig, TriviaError};
 
-use crate::acl::Acl;
-use crate::auth_middleware::{AuthState, require_auth};
-use crate::oauth::{self, OAuthState};
+use crate::acl::{AccessLevel, Acl, Owned};
+use crate::audit::{ActingStore, lock_as};
+use crate::auth_middleware::{AuthState, acl_from_claims, require_auth};
+use crate::oauth::{self, OAuthState, SharedStore};
 
 static WWW_DIR: Dir<'_> = include_dir!("$CARGO_MANIFEST_DIR/www/dist");
 
 struct AppState {
-    store: Arc<Mutex<MemoryStore>>,
+    store: SharedStore,
     embedder: Arc<Mutex<Embedder>>,
+    /// ACL for requests without per-user claims (the `--share` spec).
+    acl: Arc<Acl>,
+}
+
+impl AppState {
+    /// Lock the store on behalf of `username` using the web UI, who changes
+    /// are attributed to and recall is personalized for.
+    async fn store_as(&self, username: Option<String>) -> ActingStore<'_> {
+        lock_as(
+            &self.store,
+            Actor {
+                username,
+                transport: Transport::Web,
+            },
+        )
+        .await
+    }
 }
 
-type AppResult<T> = std::result::Result<T, AppError>;
+pub(crate) type AppResult<T> = std::result::Result<T, AppError>;
 
-struct AppError(anyhow::Error);
+/// Errors from trivia-core keep their kind: a missing memory is a 404, a
+/// duplicate title a 409 and so on. The JSON body tells clients whether
+/// retrying could help.
+pub(crate) struct AppError(anyhow::Error);
 
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
-        (StatusCode::INTERNAL_SERVER_ERROR, self.0.to_string()).into_response()
+        let typed = TriviaError::find(&self.0);
+        let status = typed.map_or(StatusCode::INTERNAL_SERVER_ERROR, oauth::error_status);
+        let body = serde_json::json!({
+            "error": self.0.to_string(),
+            "kind": typed.map_or("internal", TriviaError::kind),
+            "retryable": typed.is_none_or(TriviaError::is_retryable),
+        });
+        (status, axum::Json(body)).into_response()
     }
 }
 
@@ -44,12 +78,15 @@ impl<E: Into<anyhow::Error>> From<E> for AppError {
 }
 
 pub async fn serve(
-    store: MemoryStore,
+    store: Box<dyn Storage>,
     embedder: Embedder,
     bind_addr: &str,
     config: TriviaConfig,
-    acl: Acl,
+    share: Option<Acl>,
 ) -> Result<()> {
+    // Changes made outside a request, such as maintenance, belong to no user
+    let mut store = store;
+    store.set_actor(Actor::new(None, Transport::Web));
     let store = Arc::new(Mutex::new(store));
     let embedder = Arc::new(Mutex::new(embedder));
 
@@ -65,36 +102,18 @@ pub async fn serve(
         s.has_auth_providers().unwrap_or(false)
     };
 
-    let state = Arc::new(AppState {
-        store: store.clone(),
-        embedder: embedder.clone(),
-    });
+    // Without --share the web UI is for the local user and stays
+    // unrestricted, while MCP over HTTP is closed
+    let api_acl = Arc::new(share.clone().unwrap_or_else(Acl::open));
+    let acl = Arc::new(share.unwrap_or_else(Acl::closed));
+    spawn_scheduled_backups(store.lock().await.database_path(), &config.backup);
+    let maintenance = crate::maintenance::spawn(store.clone(), &config.maintenance);
 
-    let api = Router::new()
-        .route("/api/memories/merge", post(merge_memories))
-        .route("/api/memories/{mnemonic}/rate", post(rate_memory))
-        .route("/api/memories", get(list_memories).post(create_memory))
-        .route(
-            "/api/memories/{mnemonic}",
-            get(get_memory).put(update_memory).delete(delete_memory),
-        )
-        .route("/api/graph", get(get_graph))
-        .route("/api/search", get(search_memories))
-        .route("/api/tags", get(list_tags))
-        .route("/api/links", post(create_link).delete(remove_link))
-        .route(
-            "/api/memories/{mnemonic}/mnemonics",
-            post(add_mnemonic_handler).delete(remove_mnemonic_handler),
-        );
+    let api = api_router(store.clone(), embedder.clone(), api_acl.clone());
 
     // Mount MCP over HTTP at /mcp
-    let acl = Arc::new(acl);
-    let mcp_router = crate::mcp::build_mcp_router(
-        store.clone(),
-        embedder,
-        config.clone(),
-        acl.clone(),
-    );
+    let mcp_router =
+        crate::mcp::build_mcp_router(store.clone(), embedder, config.clone(), acl.clone());
     let mcp = HttpTransport::new(mcp_router)
         .disable_origin_validation()
         .into_router_at("/mcp");
@@ -105,8 +124,27 @@ pub async fn serve(
         external_url: external_url.clone(),
         fallback_acl: acl.to_string(),
         auth_enabled,
+        owner_only: config.access.visibility == Visibility::OwnerOnly,
+    };
+    let api_auth_state = AuthState {
+        fallback_acl: api_acl.to_string(),
+        ..auth_state.clone()
     };
 
+    // Administration, tokens, approved clients and the audit log need a
+    // signed-in user, so without auth providers they are left to
+    // `trivia admin`. They get no CORS headers: only the web UI calls them
+    let account = auth_enabled.then(|| {
+        crate::admin::admin_router(store.clone(), maintenance)
+            .merge(crate::tokens::tokens_router(store.clone()))
+            .merge(crate::clients::clients_router(store.clone()))
+            .merge(crate::audit::audit_router(store.clone()))
+            .layer(middleware::from_fn_with_state(
+                api_auth_state.clone(),
+                require_auth,
+            ))
+    });
+
     // OAuth routes (always public, no auth middleware)
     let oauth_state = OAuthState {
         store: store.clone(),
@@ -116,12 +154,11 @@ pub async fn serve(
 
     // Protected routes: API + MCP get auth middleware when auth is enabled
     let protected = api
-        .with_state(state)
-        .merge(mcp)
-        .layer(middleware::from_fn_with_state(
+        .layer(middleware::from_fn_with_state(api_auth_state, require_auth))
+        .merge(mcp.layer(middleware::from_fn_with_state(
             auth_state.clone(),
             require_auth,
-        ));
+        )));
 
     let acl_desc = if auth_enabled {
         "OAuth (per-user ACL)"
@@ -133,12 +170,17 @@ pub async fn serve(
     eprintln!("MCP endpoint at /mcp ({acl_desc})");
     if auth_enabled {
         eprintln!("Auth enabled — OAuth providers configured");
+    } else {
+        eprintln!("Admin API off — it needs an OAuth provider to sign in with");
     }
 
-    let app = protected
+    let mut app = protected
         .merge(oauth_routes)
         .fallback(get(static_handler))
         .layer(CorsLayer::permissive());
+    if let Some(account) = account {
+        app = app.merge(account);
+    }
 
     let listener = tokio::net::TcpListener::bind(bind_addr).await?;
     eprintln!("Listening on http://{bind_addr}");
@@ -146,11 +188,108 @@ pub async fn serve(
     Ok(())
 }
 
+/// The `/api/*` routes. Handlers resolve the caller's ACL from the
+/// `TokenClaims` that `require_auth` inserts, falling back to `acl`.
+pub fn api_router(store: SharedStore, embedder: Arc<Mutex<Embedder>>, acl: Arc<Acl>) -> Router {
+    let state = Arc::new(AppState {
+        store,
+        embedder,
+        acl,
+    });
+    Router::new()
+        .route("/api/memories/merge", post(merge_memories))
+        .route("/api/memories/{mnemonic}/rate", post(rate_memory))
+        .route("/api/memories", get(list_memories).post(create_memory))
+        .route(
+            "/api/memories/{mnemonic}",
+            get(get_memory).put(update_memory).delete(delete_memory),
+        )
+        .route("/api/graph", get(get_graph))
+        .route("/api/search", get(search_memories))
+        .route("/api/tags", get(list_tags))
+        .route("/api/links", post(create_link).delete(remove_link))
+        .route(
+            "/api/memories/{mnemonic}/mnemonics",
+            post(add_mnemonic_handler).delete(remove_mnemonic_handler),
+        )
+        .with_state(state)
+}
+
+/// Back up the database every `interval_hours` into `dir`, keeping the
+/// newest `keep` scheduled backups. No-op unless `[backup] dir` is set.
+/// Backups run on a blocking thread over their own connection to
+/// `db_path`, so requests carry on while one is taken.
+fn spawn_scheduled_backups(db_path: Option<PathBuf>, backup: &BackupConfig) {
+    let Some(dir) = backup.dir.as_ref().map(PathBuf::from) else {
+        return;
+    };
+    let Some(db_path) = db_path else {
+        eprintln!("Scheduled backups are only supported for SQLite; use pg_dump for Postgres");
+        return;
+    };
+    let hours = backup.interval_hours.unwrap_or(24).max(1);
+    let keep = backup.keep.unwrap_or(7).max(1);
+    eprintln!(
+        "Scheduled backups every {hours}h to {} (keeping {keep})",
+        dir.display()
+    );
+
+    tokio::spawn(async move {
+        let period = Duration::from_secs(hours * 3600);
+        let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        loop {
+            ticker.tick().await;
+            let dest = scheduled_backup_path(&dir);
+            let (db_path, dir, target) = (db_path.clone(), dir.clone(), dest.clone());
+            let result = tokio::task::spawn_blocking(move || {
+                backup_database(&db_path, &target)?;
+                rotate_backups(&dir, keep)
+            })
+            .await;
+            match result {
+                Ok(Ok(_)) => eprintln!("Backed up database to {}", dest.display()),
+                Ok(Err(e)) => eprintln!("Scheduled backup failed: {e:#}"),
+                Err(e) => eprintln!("Scheduled backup failed: {e}"),
+            }
+        }
+    });
+}
+
 // --- API handlers ---
 
-async fn list_memories(State(state): State<Arc<AppState>>) -> AppResult<impl IntoResponse> {
+/// Check the caller may access `mnemonic` at `level`, returning the memory.
+/// Memories the caller can't read are reported as missing, so their
+/// existence isn't revealed.
+fn require_access(
+    store: &dyn Storage,
+    acl: &Acl,
+    mnemonic: &str,
+    level: AccessLevel,
+) -> std::result::Result<Memory, TriviaError> {
+    let memory = store
+        .get_memory_by_mnemonic(mnemonic)?
+        .filter(|mem| acl.check_read(mem))
+        .ok_or_else(|| TriviaError::NotFound(format!("memory not found: {mnemonic}")))?;
+    if acl.level(&memory) < level {
+        return Err(denied(acl, level));
+    }
+    Ok(memory)
+}
+
+fn denied(acl: &Acl, level: AccessLevel) -> TriviaError {
+    TriviaError::AccessDenied(format!(
+        "access denied: your permissions are [{acl}] which do not grant {level} access"
+    ))
+}
+
+async fn list_memories(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<TokenClaims>,
+) -> AppResult<impl IntoResponse> {
+    let (acl, _username) = acl_from_claims(&claims, &state.acl);
     let store = state.store.lock().await;
-    let summaries = store.list_all_summaries()?;
+    let mut summaries = store.list_all_summaries()?;
+    summaries.retain(|s| acl.check_read(s));
     Ok(axum::Json(summaries))
 }
 
@@ -164,24 +303,63 @@ struct CreateMemoryReq {
 
 async fn create_memory(
     State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<TokenClaims>,
     axum::Json(body): axum::Json<CreateMemoryReq>,
 ) -> AppResult<impl IntoResponse> {
+    let (acl, username) = acl_from_claims(&claims, &state.acl);
+    let mut tags = body.tags;
+    if let Some(ref name) = username {
+        let user_tag = format!("@{name}");
+        if !tags.contains(&user_tag) {
+            tags.push(user_tag);
+        }
+    }
+    // Storing under an existing mnemonic overwrites that memory, which keeps
+    // its owner; a new memory will belong to the caller
+    let owner = {
+        let store = state.store.lock().await;
+        match store.get_memory_by_mnemonic(&body.mnemonic)? {
+            Some(_) => require_access(&**store, &acl, &body.mnemonic, AccessLevel::Update)?.owner,
+            None => username.clone(),
+        }
+    };
+    if !acl.check_update(&Owned {
+        tags: &tags,
+        owner: owner.as_deref(),
+    }) {
+        return Err(denied(&acl, AccessLevel::Update).into());
+    }
+
     let embedder = state.embedder.lock().await;
     let embedding = embedder.embed(&body.mnemonic)?;
     drop(embedder);
-    let store = state.store.lock().await;
-    store.memorize(&body.mnemonic, &body.content, &body.tags, &embedding)?;
-    Ok((StatusCode::CREATED, axum::Json(serde_json::json!({"ok": true}))))
+    let store = state.store_as(username.clone()).await;
+    // Merging could fold the new memory into one the caller can't see
+    let skip_merge = !acl.is_open();
+    store.memorize_with_options(
+        &body.mnemonic,
+        &body.content,
+        &tags,
+        &embedding,
+        skip_merge,
+        username.as_deref(),
+    )?;
+    Ok((
+        StatusCode::CREATED,
+        axum::Json(serde_json::json!({"ok": true})),
+    ))
 }
 
 async fn get_memory(
     State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<TokenClaims>,
     Path(mnemonic): Path<String>,
 ) -> AppResult<Response> {
+    let (acl, _username) = acl_from_claims(&claims, &state.acl);
     let store = state.store.lock().await;
     match store.get_memory_by_mnemonic(&mnemonic)? {
-        Some(mem) => Ok(axum::Json(mem).into_response()),
-        None => Ok(StatusCode::NOT_FOUND.into_response()),
+        Some(mem) if acl.check_read(&mem) => Ok(axum::Json(mem).into_response()),
+        _ => Ok(StatusCode::NOT_FOUND.into_response()),
     }
 }
 
@@ -196,9 +374,26 @@ struct UpdateMemoryReq {
 
 async fn update_memory(
     State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<TokenClaims>,
     Path(old_mnemonic): Path<String>,
     axum::Json(body): axum::Json<UpdateMemoryReq>,
 ) -> AppResult<Response> {
+    let (acl, username) = acl_from_claims(&claims, &state.acl);
+    let memory = require_access(
+        &**state.store.lock().await,
+        &acl,
+        &old_mnemonic,
+        AccessLevel::Update,
+    )?;
+    // The new tags must also grant update, or the memory could be moved
+    // somewhere the caller can't manage it
+    if !acl.check_update(&Owned {
+        tags: &body.tags,
+        owner: memory.owner.as_deref(),
+    }) {
+        return Err(denied(&acl, AccessLevel::Update).into());
+    }
+
     let new_mnemonic = body.mnemonic.as_deref().unwrap_or(&old_mnemonic);
     let renaming = new_mnemonic != old_mnemonic;
 
@@ -206,7 +401,7 @@ async fn update_memory(
     let embedding = embedder.embed(new_mnemonic)?;
     drop(embedder);
 
-    let store = state.store.lock().await;
+    let store = state.store_as(username).await;
     if renaming {
         store.rename_memory(&old_mnemonic, new_mnemonic, &embedding)?;
     }
@@ -221,9 +416,12 @@ async fn update_memory(
 
 async fn delete_memory(
     State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<TokenClaims>,
     Path(mnemonic): Path<String>,
 ) -> AppResult<impl IntoResponse> {
-    let store = state.store.lock().await;
+    let (acl, username) = acl_from_claims(&claims, &state.acl);
+    let store = state.store_as(username).await;
+    require_access(&**store, &acl, &mnemonic, AccessLevel::Update)?;
     let deleted = store.delete_memory(&mnemonic)?;
     if deleted {
         Ok(axum::Json(serde_json::json!({"ok": true})).into_response())
@@ -235,15 +433,27 @@ async fn delete_memory(
 #[derive(Deserialize)]
 struct RateReq {
     useful: bool,
+    /// The search the memory was found with, if any
+    query: Option<String>,
+    /// `X-Recall-Id` of that search, so the rating also applies to similar searches
+    recall_id: Option<String>,
 }
 
 async fn rate_memory(
     State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<TokenClaims>,
     Path(mnemonic): Path<String>,
     axum::Json(body): axum::Json<RateReq>,
 ) -> AppResult<impl IntoResponse> {
-    let store = state.store.lock().await;
-    store.rate(&mnemonic, body.useful)?;
+    let (acl, username) = acl_from_claims(&claims, &state.acl);
+    let store = state.store_as(username).await;
+    require_access(&**store, &acl, &mnemonic, AccessLevel::Update)?;
+    store.rate(
+        &mnemonic,
+        body.useful,
+        body.query.as_deref(),
+        body.recall_id.as_deref(),
+    )?;
     Ok(axum::Json(serde_json::json!({"ok": true})))
 }
 
@@ -272,13 +482,18 @@ struct GraphEdge {
     link_type: String,
 }
 
-async fn get_graph(State(state): State<Arc<AppState>>) -> AppResult<impl IntoResponse> {
+async fn get_graph(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<TokenClaims>,
+) -> AppResult<impl IntoResponse> {
+    let (acl, _username) = acl_from_claims(&claims, &state.acl);
     let store = state.store.lock().await;
     let summaries = store.list_all_summaries()?;
     let links = store.get_all_links()?;
 
     let nodes: Vec<GraphNode> = summaries
         .into_iter()
+        .filter(|s| acl.check_read(s))
         .map(|s| GraphNode {
             mnemonic: s.mnemonic,
             content: s.content,
@@ -290,8 +505,14 @@ async fn get_graph(State(state): State<Arc<AppState>>) -> AppResult<impl IntoRes
         })
         .collect();
 
+    // Only edges between visible nodes
+    let visible: HashSet<&str> = nodes.iter().map(|n| n.mnemonic.as_str()).collect();
     let edges: Vec<GraphEdge> = links
         .into_iter()
+        .filter(|l| {
+            visible.contains(l.source_mnemonic.as_str())
+                && visible.contains(l.target_mnemonic.as_str())
+        })
         .map(|l| GraphEdge {
             source: l.source_mnemonic,
             target: l.target_mnemonic,
@@ -318,8 +539,10 @@ fn default_limit() -> usize {
 
 async fn search_memories(
     State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<TokenClaims>,
     Query(params): Query<SearchQuery>,
 ) -> AppResult<impl IntoResponse> {
+    let (acl, username) = acl_from_claims(&claims, &state.acl);
     let embedder = state.embedder.lock().await;
     let embedding = embedder.embed(&params.q)?;
     drop(embedder);
@@ -327,14 +550,31 @@ async fn search_memories(
         .tags
         .filter(|s| !s.is_empty())
         .map(|s| s.split(',').map(|t| t.trim().to_string()).collect());
+    let store = state.store_as(username).await;
+    let readable = |m: &Memory| acl.check_read(m);
+    let recall = store.recall_filtered(
+        &embedding,
+        params.limit,
+        tag_list.as_deref(),
+        None,
+        None,
+        &readable,
+    )?;
+    Ok(([("x-recall-id", recall.id)], axum::Json(recall.memories)))
+}
+
+async fn list_tags(
+    State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<TokenClaims>,
+) -> AppResult<impl IntoResponse> {
+    let (acl, _username) = acl_from_claims(&claims, &state.acl);
     let store = state.store.lock().await;
-    let results = store.recall(&embedding, params.limit, tag_list.as_deref(), None, None)?;
-    Ok(axum::Json(results))
-}
-
-async fn list_tags(State(state): State<Arc<AppState>>) -> AppResult<impl IntoResponse> {
-    let store = state.store.lock().await;
-    let tags = store.list_tags()?;
+    let tags = if acl.is_open() {
+        store.list_tags()?
+    } else {
+        let memories = store.list_all_summaries()?;
+        acl.visible_tags(memories.iter())
+    };
     Ok(axum::Json(tags))
 }
 
@@ -346,12 +586,19 @@ struct MergeReq {
 
 async fn merge_memories(
     State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<TokenClaims>,
     axum::Json(body): axum::Json<MergeReq>,
 ) -> AppResult<impl IntoResponse> {
+    let (acl, username) = acl_from_claims(&claims, &state.acl);
+    {
+        let store = state.store.lock().await;
+        require_access(&**store, &acl, &body.keep, AccessLevel::Update)?;
+        require_access(&**store, &acl, &body.discard, AccessLevel::Update)?;
+    }
     let embedder = state.embedder.lock().await;
     let embedding = embedder.embed(&body.keep)?;
     drop(embedder);
-    let store = state.store.lock().await;
+    let store = state.store_as(username).await;
     store.merge(&body.keep, &body.discard, &embedding)?;
     Ok(axum::Json(serde_json::json!({"ok": true})))
 }
@@ -370,18 +617,29 @@ fn default_link_type() -> String {
 
 async fn create_link(
     State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<TokenClaims>,
     axum::Json(body): axum::Json<LinkReq>,
 ) -> AppResult<impl IntoResponse> {
-    let store = state.store.lock().await;
+    let (acl, username) = acl_from_claims(&claims, &state.acl);
+    let store = state.store_as(username).await;
+    require_access(&**store, &acl, &body.source, AccessLevel::Update)?;
+    require_access(&**store, &acl, &body.target, AccessLevel::Update)?;
     store.link(&body.source, &body.target, &body.link_type)?;
-    Ok((StatusCode::CREATED, axum::Json(serde_json::json!({"ok": true}))))
+    Ok((
+        StatusCode::CREATED,
+        axum::Json(serde_json::json!({"ok": true})),
+    ))
 }
 
 async fn remove_link(
     State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<TokenClaims>,
     axum::Json(body): axum::Json<LinkReq>,
 ) -> AppResult<impl IntoResponse> {
-    let store = state.store.lock().await;
+    let (acl, username) = acl_from_claims(&claims, &state.acl);
+    let store = state.store_as(username).await;
+    require_access(&**store, &acl, &body.source, AccessLevel::Update)?;
+    require_access(&**store, &acl, &body.target, AccessLevel::Update)?;
     store.unlink(&body.source, &body.target, &body.link_type)?;
     Ok(axum::Json(serde_json::json!({"ok": true})))
 }
@@ -393,23 +651,37 @@ struct MnemonicReq {
 
 async fn add_mnemonic_handler(
     State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<TokenClaims>,
     Path(title): Path<String>,
     axum::Json(body): axum::Json<MnemonicReq>,
 ) -> AppResult<impl IntoResponse> {
+    let (acl, username) = acl_from_claims(&claims, &state.acl);
+    require_access(
+        &**state.store.lock().await,
+        &acl,
+        &title,
+        AccessLevel::Update,
+    )?;
     let embedder = state.embedder.lock().await;
     let embedding = embedder.embed(&body.text)?;
     drop(embedder);
-    let store = state.store.lock().await;
+    let store = state.store_as(username).await;
     store.add_mnemonic(&title, &body.text, &embedding)?;
-    Ok((StatusCode::CREATED, axum::Json(serde_json::json!({"ok": true}))))
+    Ok((
+        StatusCode::CREATED,
+        axum::Json(serde_json::json!({"ok": true})),
+    ))
 }
 
 async fn remove_mnemonic_handler(
     State(state): State<Arc<AppState>>,
+    Extension(claims): Extension<TokenClaims>,
     Path(title): Path<String>,
     axum::Json(body): axum::Json<MnemonicReq>,
 ) -> AppResult<impl IntoResponse> {
-    let store = state.store.lock().await;
+    let (acl, username) = acl_from_claims(&claims, &state.acl);
+    let store = state.store_as(username).await;
+    require_access(&**store, &acl, &title, AccessLevel::Update)?;
     store.remove_mnemonic(&title, &body.text)?;
     Ok(axum::Json(serde_json::json!({"ok": true})))
 }
@@ -450,6 +722,10 @@ async fn static_handler(uri: axum::http::Uri) -> Response {
     // SPA fallback: serve index.html
     match WWW_DIR.get_file("index.html") {
         Some(file) => Html(std::str::from_utf8(file.contents()).unwrap_or("")).into_response(),
-        None => (StatusCode::NOT_FOUND, "frontend not built — run: cd apps/cli/www && npm run build").into_response(),
+        None => (
+            StatusCode::NOT_FOUND,
+            "frontend not built — run: cd apps/cli/www && npm run build",
+        )
+            .into_response(),
     }
 }
@@ -0,0 +1,257 @@
+mod common;
+
+use serde_json::{Value, json};
+
+use common::{ALL_SCOPES, Auth, scoped_token_for, send};
+use trivia_cli::acl::Acl;
+use trivia_cli::admin::admin_router;
+
+/// The admin API behind the auth middleware, with auth enabled.
+fn test_app() -> (axum::Router, common::Store) {
+    common::test_app(|store| admin_router(store, Default::default()))
+}
+
+#[tokio::test]
+async fn non_admin_is_forbidden() {
+    let (app, store) = test_app();
+    let token = scoped_token_for(&store, "bob", "*:read", false, Some(ALL_SCOPES)).await;
+
+    let (status, _) = send(&app, "GET", "/api/admin/users", &token, None).await;
+    assert_eq!(status, 403);
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/api/admin/users",
+        &token,
+        Some(json!({"username": "eve"})),
+    )
+    .await;
+    assert_eq!(status, 403);
+    assert!(
+        store
+            .lock()
+            .await
+            .get_user_by_username("eve")
+            .unwrap()
+            .is_none()
+    );
+
+    // Admins need the admin scope on their token too
+    let token = scoped_token_for(
+        &store,
+        "root",
+        "*:read",
+        true,
+        Some("memory:read memory:write"),
+    )
+    .await;
+    let (status, _) = send(&app, "GET", "/api/admin/users", &token, None).await;
+    assert_eq!(status, 403);
+}
+
+#[tokio::test]
+async fn no_admin_without_auth() {
+    // Even an open ACL doesn't make an anonymous caller an admin
+    let (app, store) = common::test_app_with(
+        |store| admin_router(store, Default::default()),
+        &Acl::open(),
+        false,
+        false,
+    );
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/api/admin/users",
+        Auth::Anonymous,
+        Some(json!({"username": "eve", "is_admin": true})),
+    )
+    .await;
+    assert_eq!(status, 403);
+    assert!(
+        store
+            .lock()
+            .await
+            .get_user_by_username("eve")
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn admin_manages_users_with_audit() {
+    let (app, store) = test_app();
+    let token = scoped_token_for(&store, "root", "*:read", true, Some(ALL_SCOPES)).await;
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/api/admin/users",
+        &token,
+        Some(json!({"username": "alice", "acl": "project:read"})),
+    )
+    .await;
+    assert_eq!(status, 201);
+
+    let (status, _) = send(
+        &app,
+        "PUT",
+        "/api/admin/users/alice",
+        &token,
+        Some(json!({"acl": "not an acl ::"})),
+    )
+    .await;
+    assert_eq!(status, 400);
+
+    let (status, _) = send(
+        &app,
+        "PUT",
+        "/api/admin/users/alice",
+        &token,
+        Some(json!({"acl": "project:update", "is_admin": true})),
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    let (status, user) = send(&app, "GET", "/api/admin/users/alice", &token, None).await;
+    assert_eq!(status, 200);
+    assert_eq!(user["acl"], "project:update");
+    assert_eq!(user["is_admin"], true);
+
+    // Admins can't lock themselves out
+    let (status, _) = send(&app, "DELETE", "/api/admin/users/root", &token, None).await;
+    assert_eq!(status, 400);
+    let (status, _) = send(
+        &app,
+        "PUT",
+        "/api/admin/users/root",
+        &token,
+        Some(json!({"is_admin": false})),
+    )
+    .await;
+    assert_eq!(status, 400);
+
+    let (_, audit) = send(&app, "GET", "/api/admin/audit", &token, None).await;
+    let actions: Vec<&str> = audit
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|e| e["action"].as_str().unwrap())
+        .collect();
+    assert_eq!(actions, ["user.grant_admin", "user.set_acl", "user.create"]);
+    assert_eq!(audit[0]["actor"], "root");
+    assert_eq!(audit[1]["target"], "alice");
+    assert_eq!(audit[1]["detail"], "project:read -> project:update");
+}
+
+#[tokio::test]
+async fn providers_hide_secret() {
+    let (app, store) = test_app();
+    let token = scoped_token_for(&store, "root", "*:read", true, Some(ALL_SCOPES)).await;
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/api/admin/providers",
+        &token,
+        Some(
+            json!({"name": "gh", "type": "github", "client_id": "id", "client_secret": "hunter2"}),
+        ),
+    )
+    .await;
+    assert_eq!(status, 201);
+    let (status, _) = send(
+        &app,
+        "PUT",
+        "/api/admin/providers/gh",
+        &token,
+        Some(json!({"enabled": false})),
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    let (_, providers) = send(&app, "GET", "/api/admin/providers", &token, None).await;
+    assert_eq!(providers[0]["name"], "gh");
+    assert_eq!(providers[0]["enabled"], false);
+    assert!(!providers.to_string().contains("hunter2"));
+
+    let (_, audit) = send(&app, "GET", "/api/admin/audit", &token, None).await;
+    assert!(!audit.to_string().contains("hunter2"));
+}
+
+#[tokio::test]
+async fn groups_and_sessions() {
+    let (app, store) = test_app();
+    let token = scoped_token_for(&store, "root", "*:read", true, Some(ALL_SCOPES)).await;
+    scoped_token_for(&store, "alice", "*:read", false, Some(ALL_SCOPES)).await;
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/api/admin/groups",
+        &token,
+        Some(json!({"name": "eng", "acl": "eng:update"})),
+    )
+    .await;
+    assert_eq!(status, 201);
+    let (status, _) = send(
+        &app,
+        "PUT",
+        "/api/admin/groups/eng/members/alice",
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, 200);
+
+    let (_, group) = send(&app, "GET", "/api/admin/groups/eng", &token, None).await;
+    assert_eq!(group["members"], json!(["alice"]));
+    let (_, user) = send(&app, "GET", "/api/admin/users/alice", &token, None).await;
+    assert_eq!(user["groups"], json!(["eng"]));
+    assert_eq!(user["tokens"], 1);
+
+    let (status, body) = send(
+        &app,
+        "DELETE",
+        "/api/admin/users/alice/tokens",
+        &token,
+        None,
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(body["revoked"], 1);
+
+    let (status, _) = send(&app, "DELETE", "/api/admin/groups/nope", &token, None).await;
+    assert_eq!(status, 404);
+}
+
+#[tokio::test]
+async fn maintenance_runs_on_demand() {
+    let (app, store) = test_app();
+    let token = scoped_token_for(&store, "root", "*:read", true, Some(ALL_SCOPES)).await;
+    {
+        let s = store.lock().await;
+        let user = s.get_user_by_username("root").unwrap().unwrap();
+        s.create_session(user.id).unwrap();
+    }
+
+    let (status, before) = send(&app, "GET", "/api/admin/maintenance", &token, None).await;
+    assert_eq!(status, 200);
+    assert_eq!(before["last_run"], Value::Null);
+
+    let (status, run) = send(&app, "POST", "/api/admin/maintenance", &token, None).await;
+    assert_eq!(status, 200);
+    assert_eq!(run["report"]["expired_sessions"], 0);
+    assert!(run.get("error").is_none());
+
+    let (_, after) = send(&app, "GET", "/api/admin/maintenance", &token, None).await;
+    assert_eq!(after["last_run"]["started_at"], run["started_at"]);
+    let (_, audit) = send(&app, "GET", "/api/admin/audit", &token, None).await;
+    assert_eq!(audit[0]["action"], "maintenance.run");
+
+    // Live credentials are left alone
+    let (status, _) = send(&app, "GET", "/api/admin/users", &token, None).await;
+    assert_eq!(status, 200);
+    let bob = scoped_token_for(&store, "bob", "*:read", false, Some(ALL_SCOPES)).await;
+    let (status, _) = send(&app, "POST", "/api/admin/maintenance", &bob, None).await;
+    assert_eq!(status, 403);
+}
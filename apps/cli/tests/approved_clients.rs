@@ -0,0 +1,62 @@
+//! Approved OAuth clients: listed and revoked from a web session, taking
+//! the client's tokens with them.
+
+mod common;
+
+use serde_json::Value;
+
+use common::{Auth, send};
+use trivia_cli::clients::clients_router;
+
+#[tokio::test]
+async fn session_lists_and_revokes_approved_clients() {
+    let (app, store) = common::test_app(clients_router);
+
+    let (session, client_id, pair) = {
+        let s = store.lock().await;
+        let user = s.create_user("alice", "*:read").unwrap();
+        let (client, _) = s
+            .register_client(&["http://localhost/cb".into()], Some("agent"))
+            .unwrap();
+        s.grant_consent(user.id, &client.client_id, "memory:read")
+            .unwrap();
+        let pair = s
+            .create_token_pair(&client.client_id, user.id, Some("memory:read"))
+            .unwrap();
+        (
+            s.create_session(user.id).unwrap().session_id,
+            client.client_id,
+            pair,
+        )
+    };
+    let session = Auth::Session(&session);
+
+    let (status, list) = send(&app, "GET", "/api/clients", session, None).await;
+    assert_eq!(status, 200);
+    assert_eq!(list[0]["client_name"], "agent");
+    assert_eq!(list[0]["scope"], "memory:read");
+
+    // The client's own token can't see or revoke approvals
+    assert_eq!(
+        send(&app, "GET", "/api/clients", &pair.access_token, None)
+            .await
+            .0,
+        403
+    );
+
+    let uri = format!("/api/clients/{client_id}");
+    assert_eq!(send(&app, "DELETE", &uri, session, None).await.0, 200);
+    assert_eq!(send(&app, "DELETE", &uri, session, None).await.0, 404);
+    assert_eq!(
+        send(&app, "GET", "/api/clients", session, None).await.1,
+        Value::Array(vec![])
+    );
+    assert!(
+        store
+            .lock()
+            .await
+            .get_user_by_access_token(&pair.access_token)
+            .unwrap()
+            .is_none()
+    );
+}
@@ -0,0 +1,148 @@
+mod common;
+
+use std::sync::Arc;
+
+use serde_json::{Value, json};
+
+use common::{ALL_SCOPES, EMBEDDER, scoped_token_for, send};
+use trivia_cli::acl::Acl;
+use trivia_cli::audit::audit_router;
+use trivia_cli::www::api_router;
+use trivia_core::AuditFilter;
+
+/// The memory and audit APIs behind the auth middleware, with auth enabled.
+fn test_app() -> (axum::Router, common::Store) {
+    common::test_app(|store| {
+        api_router(store.clone(), EMBEDDER.clone(), Arc::new(Acl::closed()))
+            .merge(audit_router(store))
+    })
+}
+
+/// `(actor, action)` of each entry in a `/api/audit` response.
+fn entries(list: &Value) -> Vec<(&str, &str)> {
+    list.as_array()
+        .unwrap_or_else(|| panic!("expected array: {list}"))
+        .iter()
+        .map(|e| {
+            (
+                e["actor"].as_str().unwrap_or("-"),
+                e["action"].as_str().unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn web_changes_are_attributed() {
+    let (app, store) = test_app();
+    let root = scoped_token_for(&store, "root", "*:update", true, Some(ALL_SCOPES)).await;
+    let alice = scoped_token_for(&store, "alice", "*:update", false, Some(ALL_SCOPES)).await;
+
+    let note = json!({"mnemonic": "alice note", "content": "first draft", "tags": ["notes"]});
+    let (status, _) = send(&app, "POST", "/api/memories", &alice, Some(note)).await;
+    assert_eq!(status, 201);
+    let edit = json!({"content": "second draft", "tags": ["notes"]});
+    let (status, _) = send(
+        &app,
+        "PUT",
+        "/api/memories/alice%20note",
+        &alice,
+        Some(edit),
+    )
+    .await;
+    assert_eq!(status, 200);
+    let (status, _) = send(&app, "DELETE", "/api/memories/alice%20note", &root, None).await;
+    assert_eq!(status, 200);
+
+    let (status, list) = send(&app, "GET", "/api/audit?action=memory", &root, None).await;
+    assert_eq!(status, 200);
+    assert_eq!(
+        entries(&list),
+        [
+            ("root", "memory.delete"),
+            ("alice", "memory.update"),
+            ("alice", "memory.set_owner"),
+            ("alice", "memory.create"),
+        ]
+    );
+    assert!(
+        list.as_array()
+            .unwrap()
+            .iter()
+            .all(|e| e["transport"] == "web")
+    );
+    assert!(
+        list[0]["detail"]
+            .as_str()
+            .unwrap()
+            .contains("\"alice note\"")
+    );
+
+    // The store goes back to its own actor once a request is done
+    store
+        .lock()
+        .await
+        .memorize("local note", "from the shell", &[], &[1.0; 384])
+        .unwrap();
+    let latest = store
+        .lock()
+        .await
+        .list_audit(&AuditFilter::latest(1))
+        .unwrap();
+    assert_eq!(latest[0].actor, None);
+    assert_eq!(latest[0].transport.as_deref(), Some("cli"));
+}
+
+#[tokio::test]
+async fn non_admins_see_only_their_own_changes() {
+    let (app, store) = test_app();
+    let root = scoped_token_for(&store, "root", "*:update", true, Some(ALL_SCOPES)).await;
+    let alice = scoped_token_for(&store, "alice", "*:update", false, Some(ALL_SCOPES)).await;
+
+    for (token, mnemonic) in [(&root, "root note"), (&alice, "alice note")] {
+        let body = json!({"mnemonic": mnemonic, "content": "x"});
+        let (status, _) = send(&app, "POST", "/api/memories", token, Some(body)).await;
+        assert_eq!(status, 201);
+    }
+
+    let (status, list) = send(&app, "GET", "/api/audit", &alice, None).await;
+    assert_eq!(status, 200);
+    assert_eq!(
+        entries(&list),
+        [("alice", "memory.set_owner"), ("alice", "memory.create")]
+    );
+    let (status, _) = send(&app, "GET", "/api/audit?actor=root", &alice, None).await;
+    assert_eq!(status, 403);
+
+    let (status, list) = send(
+        &app,
+        "GET",
+        "/api/audit?actor=root&action=memory",
+        &root,
+        None,
+    )
+    .await;
+    assert_eq!(status, 200);
+    assert_eq!(
+        entries(&list),
+        [("root", "memory.set_owner"), ("root", "memory.create")]
+    );
+    let (status, list) = send(&app, "GET", "/api/audit?memory=alice%20note", &root, None).await;
+    assert_eq!(status, 200);
+    assert_eq!(
+        entries(&list),
+        [("alice", "memory.set_owner"), ("alice", "memory.create")]
+    );
+
+    let (status, _) = send(
+        &app,
+        "GET",
+        "/api/audit?transport=carrier-pigeon",
+        &root,
+        None,
+    )
+    .await;
+    assert_eq!(status, 400);
+    let (status, _) = send(&app, "GET", "/api/audit?since=yesterday", &root, None).await;
+    assert_eq!(status, 400);
+}
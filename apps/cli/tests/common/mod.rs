@@ -0,0 +1,126 @@
+//! Helpers shared by the HTTP API tests: routes behind the auth middleware
+//! on an in-memory store, users with bearer tokens, and a request sender.
+
+// Each test binary uses a different subset of these.
+#![allow(dead_code)]
+
+use std::sync::{Arc, LazyLock};
+
+use axum::body::Body;
+use axum::middleware;
+use http_body_util::BodyExt;
+use serde_json::Value;
+use tokio::sync::Mutex;
+use tower::ServiceExt;
+
+use trivia_cli::acl::Acl;
+use trivia_cli::auth_middleware::{AuthState, require_auth};
+use trivia_core::{Embedder, MemoryStore, Storage};
+
+pub type Store = Arc<Mutex<Box<dyn Storage>>>;
+
+/// Every scope a token can carry.
+pub const ALL_SCOPES: &str = "memory:read memory:write admin";
+
+// Shared embedder — model loading is expensive, do it once across all tests.
+pub static EMBEDDER: LazyLock<Arc<Mutex<Embedder>>> =
+    LazyLock::new(|| Arc::new(Mutex::new(Embedder::new().unwrap())));
+
+/// The router built by `routes` behind the auth middleware, with auth
+/// enabled and nothing granted to unauthenticated callers.
+pub fn test_app(routes: impl FnOnce(Store) -> axum::Router) -> (axum::Router, Store) {
+    test_app_with(routes, &Acl::closed(), true, false)
+}
+
+/// `test_app` with `share` as the fallback ACL. With `auth_enabled` off it
+/// applies to everyone; `owner_only` turns on owner-only visibility.
+pub fn test_app_with(
+    routes: impl FnOnce(Store) -> axum::Router,
+    share: &Acl,
+    auth_enabled: bool,
+    owner_only: bool,
+) -> (axum::Router, Store) {
+    let store: Store = Arc::new(Mutex::new(Box::new(MemoryStore::in_memory().unwrap())));
+    let auth_state = AuthState {
+        store: store.clone(),
+        external_url: "http://localhost".into(),
+        fallback_acl: share.to_string(),
+        auth_enabled,
+        owner_only,
+    };
+    let router =
+        routes(store.clone()).layer(middleware::from_fn_with_state(auth_state, require_auth));
+    (router, store)
+}
+
+/// Create a user with `acl` and return a bearer token for them.
+pub async fn token_for(store: &Store, username: &str, acl: &str) -> String {
+    scoped_token_for(store, username, acl, false, None).await
+}
+
+/// Create a user with `acl`, an admin if `admin`, and return a bearer token
+/// for them limited to `scope`.
+pub async fn scoped_token_for(
+    store: &Store,
+    username: &str,
+    acl: &str,
+    admin: bool,
+    scope: Option<&str>,
+) -> String {
+    let s = store.lock().await;
+    let user = s.create_user(username, acl).unwrap();
+    s.set_user_admin(username, admin).unwrap();
+    let (client, _) = s
+        .register_client(&["http://localhost/callback".into()], Some("test"))
+        .unwrap();
+    s.create_token_pair(&client.client_id, user.id, scope)
+        .unwrap()
+        .access_token
+}
+
+/// How a request authenticates. Tokens convert into `Bearer`.
+#[derive(Clone, Copy)]
+pub enum Auth<'a> {
+    Anonymous,
+    Bearer(&'a str),
+    Session(&'a str),
+}
+
+impl<'a> From<&'a str> for Auth<'a> {
+    fn from(token: &'a str) -> Self {
+        Auth::Bearer(token)
+    }
+}
+
+impl<'a> From<&'a String> for Auth<'a> {
+    fn from(token: &'a String) -> Self {
+        Auth::Bearer(token)
+    }
+}
+
+/// Send a request, returning (status, parsed JSON body or Null).
+pub async fn send<'a>(
+    app: &axum::Router,
+    method: &str,
+    uri: &str,
+    auth: impl Into<Auth<'a>>,
+    body: Option<Value>,
+) -> (u16, Value) {
+    let req = axum::http::Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("Content-Type", "application/json");
+    let req = match auth.into() {
+        Auth::Anonymous => req,
+        Auth::Bearer(token) => req.header("Authorization", format!("Bearer {token}")),
+        Auth::Session(id) => req.header("Cookie", format!("trivia_session={id}")),
+    };
+    let body = body.map(|b| Body::from(b.to_string())).unwrap_or_default();
+    let resp = app.clone().oneshot(req.body(body).unwrap()).await.unwrap();
+    let status = resp.status().as_u16();
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    (
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    )
+}
@@ -9,16 +9,16 @@ use tower_mcp::transport::http::HttpTransport;
 
 use trivia_cli::acl::Acl;
 use trivia_cli::mcp::build_mcp_router;
-use trivia_core::{Embedder, MemoryStore, TriviaConfig};
+use trivia_core::{Embedder, MemoryStore, Storage, TriviaConfig};
 
 // Shared embedder — model loading is expensive, do it once across all tests.
-static EMBEDDER: LazyLock<Arc<Mutex<Embedder>>> = LazyLock::new(|| {
-    Arc::new(Mutex::new(Embedder::new().unwrap()))
-});
+static EMBEDDER: LazyLock<Arc<Mutex<Embedder>>> =
+    LazyLock::new(|| Arc::new(Mutex::new(Embedder::new().unwrap())));
 
 /// Build an MCP HTTP app with the given ACL.
-fn test_app(acl: Acl) -> (axum::Router, Arc<Mutex<MemoryStore>>) {
-    let store = Arc::new(Mutex::new(MemoryStore::in_memory().unwrap()));
+fn test_app(acl: Acl) -> (axum::Router, Arc<Mutex<Box<dyn Storage>>>) {
+    let store: Arc<Mutex<Box<dyn Storage>>> =
+        Arc::new(Mutex::new(Box::new(MemoryStore::in_memory().unwrap())));
     let mcp = build_mcp_router(
         store.clone(),
         EMBEDDER.clone(),
@@ -33,7 +33,7 @@ fn test_app(acl: Acl) -> (axum::Router, Arc<Mutex<MemoryStore>>) {
 
 /// Seed memories with distinct tags for ACL testing.
 /// Uses real embeddings so recall KNN actually works.
-async fn seed(store: &Arc<Mutex<MemoryStore>>) {
+async fn seed(store: &Arc<Mutex<Box<dyn Storage>>>) {
     let e = EMBEDDER.lock().await;
     let emb1 = e.embed("test fact").unwrap();
     let emb2 = e.embed("private fact").unwrap();
@@ -45,13 +45,8 @@ async fn seed(store: &Arc<Mutex<MemoryStore>>) {
         .unwrap();
     s.memorize("private fact", "secret stuff", &["private".into()], &emb2)
         .unwrap();
-    s.memorize(
-        "project fact",
-        "project data",
-        &["project".into()],
-        &emb3,
-    )
-    .unwrap();
+    s.memorize("project fact", "project data", &["project".into()], &emb3)
+        .unwrap();
 }
 
 /// POST a JSON-RPC request. Returns (parsed response, session ID).
@@ -81,16 +76,16 @@ async fn post(app: &axum::Router, session: Option<&str>, body: Value) -> (Value,
 
 /// Parse JSON from plain JSON or SSE-wrapped response.
 fn parse_response(text: &str) -> Value {
-    if text.trim_start().starts_with('{') {
-        if let Ok(v) = serde_json::from_str::<Value>(text) {
-            return v;
-        }
+    if text.trim_start().starts_with('{')
+        && let Ok(v) = serde_json::from_str::<Value>(text)
+    {
+        return v;
     }
     for line in text.lines() {
-        if let Some(data) = line.strip_prefix("data:") {
-            if let Ok(v) = serde_json::from_str::<Value>(data.trim()) {
-                return v;
-            }
+        if let Some(data) = line.strip_prefix("data:")
+            && let Ok(v) = serde_json::from_str::<Value>(data.trim())
+        {
+            return v;
         }
     }
     panic!("Could not parse MCP response:\n{text}");
@@ -372,3 +367,58 @@ async fn closed_acl_denies_everything() {
     assert!(!is_error(&resp));
     assert_eq!(result_text(&resp), "No tags found.");
 }
+
+#[tokio::test]
+async fn deny_tag_caps_access() {
+    let (app, store) = test_app(Acl::parse("project:update,private:!none,*:read").unwrap());
+    seed(&store).await;
+    {
+        let e = EMBEDDER.lock().await;
+        let emb = e.embed("leaked plans").unwrap();
+        drop(e);
+        store
+            .lock()
+            .await
+            .memorize(
+                "leaked plans",
+                "do not share",
+                &["project".into(), "private".into()],
+                &emb,
+            )
+            .unwrap();
+    }
+    let sid = init(&app).await;
+
+    let resp = call_tool(
+        &app,
+        &sid,
+        "recall",
+        json!({"query": "leaked plans", "limit": 10}),
+    )
+    .await;
+    assert!(!is_error(&resp));
+    assert!(
+        !result_text(&resp).contains("do not share"),
+        "private memory leaked through project:update"
+    );
+
+    let resp = call_tool(&app, &sid, "list-tags", json!({})).await;
+    let text = result_text(&resp);
+    assert!(!text.contains("private"), "private tag listed: {text}");
+    assert!(
+        text.contains("project (1 memories)"),
+        "denied memory counted: {text}"
+    );
+
+    let resp = call_tool(
+        &app,
+        &sid,
+        "memorize",
+        json!({"mnemonic": "another secret", "content": "x", "tags": ["project", "private"]}),
+    )
+    .await;
+    assert!(
+        is_error(&resp),
+        "memorize into a denied tag should fail: {resp}"
+    );
+}
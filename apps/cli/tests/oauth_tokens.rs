@@ -0,0 +1,253 @@
+//! Token revocation (RFC 7009), introspection (RFC 7662) and signing out of
+//! every web session.
+
+use std::sync::Arc;
+
+use axum::Router;
+use axum::body::Body;
+use axum::http::Request;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use http_body_util::BodyExt;
+use serde_json::Value;
+use tower::ServiceExt;
+
+use trivia_cli::oauth::{OAuthState, SharedStore, router};
+use trivia_core::{MemoryStore, Storage};
+
+fn test_app() -> (Router, SharedStore) {
+    let store: SharedStore = Arc::new(tokio::sync::Mutex::new(Box::new(
+        MemoryStore::in_memory().unwrap(),
+    ) as Box<dyn Storage>));
+    let app = router().with_state(OAuthState {
+        store: store.clone(),
+        external_url: "http://localhost".into(),
+    });
+    (app, store)
+}
+
+/// Register a client and return its id and secret.
+async fn client(store: &SharedStore) -> (String, String) {
+    let (client, secret) = store
+        .lock()
+        .await
+        .register_client(&["http://localhost/cb".into()], Some("agent"))
+        .unwrap();
+    (client.client_id, secret.unwrap())
+}
+
+async fn post_form(
+    app: &Router,
+    uri: &str,
+    form: &str,
+    basic: Option<(&str, &str)>,
+) -> (u16, Value) {
+    let mut req = Request::post(uri).header("content-type", "application/x-www-form-urlencoded");
+    if let Some((id, secret)) = basic {
+        req = req.header(
+            "authorization",
+            format!("Basic {}", STANDARD.encode(format!("{id}:{secret}"))),
+        );
+    }
+    let resp = app
+        .clone()
+        .oneshot(req.body(Body::from(form.to_string())).unwrap())
+        .await
+        .unwrap();
+    let status = resp.status().as_u16();
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    (
+        status,
+        serde_json::from_slice(&bytes).unwrap_or(Value::Null),
+    )
+}
+
+#[tokio::test]
+async fn metadata_advertises_endpoints() {
+    let (app, _) = test_app();
+    let req = Request::get("/.well-known/oauth-authorization-server")
+        .body(Body::empty())
+        .unwrap();
+    let bytes = app
+        .oneshot(req)
+        .await
+        .unwrap()
+        .into_body()
+        .collect()
+        .await
+        .unwrap()
+        .to_bytes();
+    let metadata: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(
+        metadata["revocation_endpoint"],
+        "http://localhost/oauth/revoke"
+    );
+    assert_eq!(
+        metadata["introspection_endpoint"],
+        "http://localhost/oauth/introspect"
+    );
+}
+
+#[tokio::test]
+async fn revoked_access_token_stops_working() {
+    let (app, store) = test_app();
+    let (client_id, _) = client(&store).await;
+    let (other_id, _) = client(&store).await;
+    let pair = {
+        let s = store.lock().await;
+        let user = s.create_user("ci", "*:read").unwrap();
+        s.create_token_pair(&client_id, user.id, None).unwrap()
+    };
+
+    // Only the client the token was issued to may revoke it
+    let form = format!("token={}&client_id={other_id}", pair.access_token);
+    assert_eq!(post_form(&app, "/oauth/revoke", &form, None).await.0, 400);
+    let form = format!("token={}", pair.access_token);
+    assert_eq!(post_form(&app, "/oauth/revoke", &form, None).await.0, 401);
+    let form = format!(
+        "token={}&client_id={client_id}&client_secret=wrong",
+        pair.access_token
+    );
+    assert_eq!(post_form(&app, "/oauth/revoke", &form, None).await.0, 401);
+
+    let form = format!("token={}&client_id={client_id}", pair.access_token);
+    assert_eq!(post_form(&app, "/oauth/revoke", &form, None).await.0, 200);
+    let s = store.lock().await;
+    assert!(
+        s.get_user_by_access_token(&pair.access_token)
+            .unwrap()
+            .is_none()
+    );
+    assert!(
+        s.get_user_by_refresh_token(&pair.refresh_token)
+            .unwrap()
+            .is_none()
+    );
+    drop(s);
+
+    // Revoking again, or an unknown token, still succeeds
+    assert_eq!(post_form(&app, "/oauth/revoke", &form, None).await.0, 200);
+    let form = format!("token=bogus&client_id={client_id}");
+    assert_eq!(post_form(&app, "/oauth/revoke", &form, None).await.0, 200);
+}
+
+#[tokio::test]
+async fn refresh_token_revocation_with_basic_auth() {
+    let (app, store) = test_app();
+    let (client_id, secret) = client(&store).await;
+    let pair = {
+        let s = store.lock().await;
+        let user = s.create_user("ci", "*:read").unwrap();
+        s.create_token_pair(&client_id, user.id, None).unwrap()
+    };
+
+    let form = format!("token={}&token_type_hint=refresh_token", pair.refresh_token);
+    assert_eq!(
+        post_form(&app, "/oauth/revoke", &form, Some((&client_id, &secret)))
+            .await
+            .0,
+        200
+    );
+    assert!(
+        store
+            .lock()
+            .await
+            .get_user_by_access_token(&pair.access_token)
+            .unwrap()
+            .is_none()
+    );
+}
+
+#[tokio::test]
+async fn introspection_reports_token_details() {
+    let (app, store) = test_app();
+    let (client_id, secret) = client(&store).await;
+    let pair = {
+        let s = store.lock().await;
+        let user = s.create_user("ci", "*:read").unwrap();
+        s.create_token_pair(&client_id, user.id, Some("memory:read"))
+            .unwrap()
+    };
+    let auth = Some((client_id.as_str(), secret.as_str()));
+
+    // Callers must authenticate with their client secret
+    let form = format!("token={}&client_id={client_id}", pair.access_token);
+    assert_eq!(
+        post_form(&app, "/oauth/introspect", &form, None).await.0,
+        401
+    );
+    assert_eq!(
+        post_form(
+            &app,
+            "/oauth/introspect",
+            &form,
+            Some((&client_id, "wrong"))
+        )
+        .await
+        .0,
+        401
+    );
+
+    let form = format!("token={}", pair.access_token);
+    let (status, info) = post_form(&app, "/oauth/introspect", &form, auth).await;
+    assert_eq!(status, 200);
+    assert_eq!(info["active"], true);
+    assert_eq!(info["username"], "ci");
+    assert_eq!(info["scope"], "memory:read");
+    assert_eq!(info["client_id"], client_id.as_str());
+    assert_eq!(info["token_type"], "Bearer");
+    assert_eq!(info["exp"].as_i64(), Some(pair.expires_at.timestamp()));
+
+    let form = format!(
+        "token={}&client_id={client_id}&client_secret={secret}",
+        pair.refresh_token
+    );
+    let (_, info) = post_form(&app, "/oauth/introspect", &form, None).await;
+    assert_eq!(info["active"], true);
+    // Refresh tokens outlive their access token
+    assert!(info["exp"].as_i64().unwrap() > pair.expires_at.timestamp());
+
+    store.lock().await.revoke_token(&pair.access_token).unwrap();
+    let form = format!("token={}", pair.access_token);
+    let (status, info) = post_form(&app, "/oauth/introspect", &form, auth).await;
+    assert_eq!(status, 200);
+    assert_eq!(info, serde_json::json!({"active": false}));
+}
+
+#[tokio::test]
+async fn logout_all_ends_every_session() {
+    let (app, store) = test_app();
+    let (current, other, bystander) = {
+        let s = store.lock().await;
+        let alice = s.create_user("alice", "*:read").unwrap();
+        let bob = s.create_user("bob", "*:read").unwrap();
+        (
+            s.create_session(alice.id).unwrap().session_id,
+            s.create_session(alice.id).unwrap().session_id,
+            s.create_session(bob.id).unwrap().session_id,
+        )
+    };
+
+    let req = Request::post("/auth/logout-all")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(app.clone().oneshot(req).await.unwrap().status(), 401);
+
+    let req = Request::post("/auth/logout-all")
+        .header("cookie", format!("trivia_session={current}"))
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    assert!(
+        resp.headers()["set-cookie"]
+            .to_str()
+            .unwrap()
+            .contains("Max-Age=0")
+    );
+
+    let s = store.lock().await;
+    assert!(s.get_session(&current).unwrap().is_none());
+    assert!(s.get_session(&other).unwrap().is_none());
+    assert!(s.get_session(&bystander).unwrap().is_some());
+}
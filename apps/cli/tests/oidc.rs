@@ -0,0 +1,523 @@
+//! Sign-in through an OpenID Connect provider, for the web UI and for MCP
+//! clients, against a mock provider serving discovery, JWKS, token and
+//! userinfo endpoints on a local port.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use axum::body::Body;
+use axum::extract::{Form, State};
+use axum::response::Response;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use base64::Engine;
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use http_body_util::BodyExt;
+use jsonwebtoken::jwk::{Jwk, JwkSet};
+use jsonwebtoken::{Algorithm, EncodingKey, Header};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use tower::ServiceExt;
+
+use trivia_cli::oauth::{OAuthState, router};
+use trivia_cli::providers::{LoginSecrets, Provider};
+use trivia_core::auth_store::sha256_hex;
+use trivia_core::{MemoryStore, OAuthProvider, Storage};
+
+const KEY_PEM: &[u8] = include_bytes!("fixtures/oidc_rsa_key.pem");
+const CLIENT_ID: &str = "trivia";
+const SUB: &str = "kc-123";
+
+/// What the mock provider expects and puts in the next ID token.
+#[derive(Default)]
+struct Mock {
+    issuer: String,
+    /// PKCE challenge and nonce from the authorization request.
+    challenge: String,
+    nonce: String,
+    /// Merged over the default ID token claims.
+    overrides: Value,
+    /// Sign with HS256 instead of the RSA key.
+    hmac: bool,
+}
+
+type MockState = Arc<Mutex<Mock>>;
+
+async fn discovery(State(mock): State<MockState>) -> Json<Value> {
+    let issuer = mock.lock().unwrap().issuer.clone();
+    Json(json!({
+        "issuer": issuer,
+        "authorization_endpoint": format!("{issuer}/authorize"),
+        "token_endpoint": format!("{issuer}/token"),
+        "jwks_uri": format!("{issuer}/jwks"),
+        "userinfo_endpoint": format!("{issuer}/userinfo"),
+    }))
+}
+
+async fn jwks() -> Json<JwkSet> {
+    let key = EncodingKey::from_rsa_pem(KEY_PEM).unwrap();
+    let mut jwk = Jwk::from_encoding_key(&key, Algorithm::RS256).unwrap();
+    jwk.common.key_id = Some("test-key".into());
+    Json(JwkSet { keys: vec![jwk] })
+}
+
+async fn token(
+    State(mock): State<MockState>,
+    Form(form): Form<HashMap<String, String>>,
+) -> Result<Json<Value>, (axum::http::StatusCode, &'static str)> {
+    let mock = mock.lock().unwrap();
+    let verifier = form
+        .get("code_verifier")
+        .map(String::as_str)
+        .unwrap_or_default();
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+    if form.get("code").map(String::as_str) != Some("good-code") || challenge != mock.challenge {
+        return Err((axum::http::StatusCode::BAD_REQUEST, "invalid_grant"));
+    }
+
+    let now = chrono::Utc::now().timestamp();
+    let mut claims = json!({
+        "iss": mock.issuer,
+        "aud": CLIENT_ID,
+        "sub": SUB,
+        "preferred_username": "alice",
+        "nonce": mock.nonce,
+        "iat": now,
+        "exp": now + 300,
+    });
+    if let Value::Object(overrides) = &mock.overrides {
+        for (k, v) in overrides {
+            claims[k] = v.clone();
+        }
+    }
+    let id_token = if mock.hmac {
+        let key = EncodingKey::from_secret(b"client-secret");
+        jsonwebtoken::encode(&Header::new(Algorithm::HS256), &claims, &key).unwrap()
+    } else {
+        let mut header = Header::new(Algorithm::RS256);
+        header.kid = Some("test-key".into());
+        jsonwebtoken::encode(
+            &header,
+            &claims,
+            &EncodingKey::from_rsa_pem(KEY_PEM).unwrap(),
+        )
+        .unwrap()
+    };
+    Ok(Json(json!({
+        "access_token": "provider-access-token",
+        "token_type": "Bearer",
+        "id_token": id_token,
+    })))
+}
+
+async fn userinfo() -> Json<Value> {
+    Json(json!({"sub": SUB, "email": "alice@example.com"}))
+}
+
+/// Start the mock provider, returning its issuer URL and state.
+async fn mock_provider() -> (String, MockState) {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let issuer = format!("http://{}", listener.local_addr().unwrap());
+    let mock: MockState = Arc::new(Mutex::new(Mock {
+        issuer: issuer.clone(),
+        ..Default::default()
+    }));
+    let app = Router::new()
+        .route("/.well-known/openid-configuration", get(discovery))
+        .route("/jwks", get(jwks))
+        .route("/token", post(token))
+        .route("/userinfo", get(userinfo))
+        .with_state(mock.clone());
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    (issuer, mock)
+}
+
+/// Send `authorize` and follow it through the mock provider's sign-in,
+/// returning the callback URI, the login cookie and the callback's response.
+async fn sign_in(app: &Router, mock: &MockState, authorize: String) -> (String, String, Response) {
+    let req = axum::http::Request::get(authorize)
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), 307);
+    let location = resp.headers()["location"].to_str().unwrap().to_string();
+    let cookie = resp.headers()["set-cookie"]
+        .to_str()
+        .unwrap()
+        .split(';')
+        .next()
+        .unwrap()
+        .to_string();
+    {
+        let mut mock = mock.lock().unwrap();
+        mock.challenge = query_param(&location, "code_challenge");
+        mock.nonce = query_param(&location, "nonce");
+    }
+    let mut callback = reqwest::Url::parse("http://localhost/oauth/callback/kc").unwrap();
+    callback
+        .query_pairs_mut()
+        .append_pair("code", "good-code")
+        .append_pair("state", &query_param(&location, "state"));
+    let callback = format!("{}?{}", callback.path(), callback.query().unwrap());
+    let req = axum::http::Request::get(&callback)
+        .header("cookie", &cookie)
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.clone().oneshot(req).await.unwrap();
+    (callback, cookie, resp)
+}
+
+async fn body_text(resp: Response) -> String {
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    String::from_utf8(bytes.to_vec()).unwrap()
+}
+
+fn db_provider(issuer: &str, config: Value) -> OAuthProvider {
+    let mut config = config;
+    config["issuer"] = json!(issuer);
+    OAuthProvider {
+        id: 1,
+        name: "kc".into(),
+        provider_type: "oidc".into(),
+        client_id: CLIENT_ID.into(),
+        client_secret: "client-secret".into(),
+        enabled: true,
+        config: config.to_string(),
+        created_at: chrono::Utc::now(),
+    }
+}
+
+fn query_param(url: &str, name: &str) -> String {
+    reqwest::Url::parse(url)
+        .unwrap()
+        .query_pairs()
+        .find(|(k, _)| k == name)
+        .map(|(_, v)| v.into_owned())
+        .unwrap_or_else(|| panic!("{name} missing from {url}"))
+}
+
+/// Start a login with `provider` and have the mock provider expect it.
+async fn begin_login(provider: &Provider, mock: &MockState) -> LoginSecrets {
+    let secrets = provider.login_secrets();
+    let url = provider
+        .authorize_url("some-state", "http://localhost/cb", &secrets)
+        .await
+        .unwrap();
+    let mut mock = mock.lock().unwrap();
+    mock.challenge = query_param(&url, "code_challenge");
+    mock.nonce = query_param(&url, "nonce");
+    secrets
+}
+
+#[tokio::test]
+async fn web_login_through_oidc_provider() {
+    let (issuer, mock) = mock_provider().await;
+    let store = MemoryStore::in_memory().unwrap();
+    let user = store.create_user("alice", "*:read").unwrap();
+    let prov = store
+        .create_provider(
+            "kc",
+            "oidc",
+            CLIENT_ID,
+            "client-secret",
+            &json!({"issuer": issuer}).to_string(),
+        )
+        .unwrap();
+    store.link_identity(user.id, prov.id, "alice", SUB).unwrap();
+    let app = router().with_state(OAuthState {
+        store: Arc::new(tokio::sync::Mutex::new(Box::new(store) as Box<dyn Storage>)),
+        external_url: "http://localhost".into(),
+    });
+
+    // Starts a sign-in, returning the callback URI and the login cookie
+    let start = || {
+        let app = app.clone();
+        let mock = mock.clone();
+        let issuer = issuer.clone();
+        async move {
+            let req = axum::http::Request::get("/auth/login/kc")
+                .body(Body::empty())
+                .unwrap();
+            let resp = app.oneshot(req).await.unwrap();
+            assert_eq!(resp.status(), 307);
+            let location = resp.headers()["location"].to_str().unwrap().to_string();
+            assert!(location.starts_with(&format!("{issuer}/authorize?")));
+            assert_eq!(query_param(&location, "client_id"), CLIENT_ID);
+            assert_eq!(query_param(&location, "code_challenge_method"), "S256");
+            assert_eq!(
+                query_param(&location, "redirect_uri"),
+                "http://localhost/auth/callback/kc"
+            );
+            let login_cookie = resp.headers()["set-cookie"].to_str().unwrap();
+            let login_cookie = login_cookie.split(';').next().unwrap().to_string();
+            {
+                let mut mock = mock.lock().unwrap();
+                mock.challenge = query_param(&location, "code_challenge");
+                mock.nonce = query_param(&location, "nonce");
+            }
+            let state = query_param(&location, "state");
+            // The verifier and nonce stay server-side; the cookie only
+            // binds the browser to the state
+            assert_eq!(login_cookie, format!("trivia_login={}", sha256_hex(&state)));
+            (
+                format!("/auth/callback/kc?code=good-code&state={state}"),
+                login_cookie,
+            )
+        }
+    };
+
+    // A state the server never issued is rejected
+    let req = axum::http::Request::get("/auth/callback/kc?code=good-code&state=forged")
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(app.clone().oneshot(req).await.unwrap().status(), 400);
+
+    // Without the login cookie the callback isn't accepted, and the state
+    // is spent either way
+    let (callback, login_cookie) = start().await;
+    let req = axum::http::Request::get(&callback)
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(app.clone().oneshot(req).await.unwrap().status(), 400);
+    let req = axum::http::Request::get(&callback)
+        .header("cookie", &login_cookie)
+        .body(Body::empty())
+        .unwrap();
+    assert_eq!(app.clone().oneshot(req).await.unwrap().status(), 400);
+
+    let (callback, login_cookie) = start().await;
+    let req = axum::http::Request::get(&callback)
+        .header("cookie", &login_cookie)
+        .body(Body::empty())
+        .unwrap();
+    let resp = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), 307);
+    let cookies: Vec<&str> = resp
+        .headers()
+        .get_all("set-cookie")
+        .iter()
+        .map(|v| v.to_str().unwrap())
+        .collect();
+    assert!(
+        cookies
+            .iter()
+            .any(|c| c.starts_with("trivia_session=") && !c.contains("Max-Age=0"))
+    );
+    assert!(cookies.iter().any(|c| c.starts_with("trivia_login=;")));
+}
+
+#[tokio::test]
+async fn username_claim_falls_back_to_userinfo() {
+    let (issuer, mock) = mock_provider().await;
+    let provider =
+        Provider::from_db(&db_provider(&issuer, json!({"username_claim": "email"}))).unwrap();
+    let secrets = begin_login(&provider, &mock).await;
+
+    let token = provider
+        .exchange_code("good-code", "http://localhost/cb", &secrets)
+        .await
+        .unwrap();
+    let user = provider.get_user_info(&token).await.unwrap();
+    assert_eq!(user.provider_user_id, SUB);
+    assert_eq!(user.username, "alice@example.com");
+}
+
+#[tokio::test]
+async fn rejects_invalid_id_tokens() {
+    let (issuer, mock) = mock_provider().await;
+    let provider = Provider::from_db(&db_provider(&issuer, json!({}))).unwrap();
+    let now = chrono::Utc::now().timestamp();
+
+    let cases = [
+        ("wrong nonce", json!({"nonce": "replayed"})),
+        ("wrong audience", json!({"aud": "someone-else"})),
+        ("wrong issuer", json!({"iss": "https://evil.example.com"})),
+        ("expired", json!({"iat": now - 7200, "exp": now - 3600})),
+    ];
+    for (case, overrides) in cases {
+        let secrets = begin_login(&provider, &mock).await;
+        mock.lock().unwrap().overrides = overrides;
+        let result = provider
+            .exchange_code("good-code", "http://localhost/cb", &secrets)
+            .await;
+        assert!(result.is_err(), "{case} was accepted");
+    }
+
+    // Signed with the client secret instead of a key from the JWKS
+    let secrets = begin_login(&provider, &mock).await;
+    {
+        let mut mock = mock.lock().unwrap();
+        mock.overrides = Value::Null;
+        mock.hmac = true;
+    }
+    let err = provider
+        .exchange_code("good-code", "http://localhost/cb", &secrets)
+        .await
+        .err()
+        .expect("HS256 token was accepted");
+    assert!(err.to_string().contains("unsupported algorithm"), "{err}");
+
+    // A bad PKCE verifier is refused by the provider
+    mock.lock().unwrap().hmac = false;
+    let mut secrets = begin_login(&provider, &mock).await;
+    secrets.code_verifier = Some("not-the-verifier".into());
+    assert!(
+        provider
+            .exchange_code("good-code", "http://localhost/cb", &secrets)
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn mcp_client_gets_requested_scope() {
+    let (issuer, mock) = mock_provider().await;
+    let store = MemoryStore::in_memory().unwrap();
+    let user = store.create_user("alice", "*:update").unwrap();
+    let prov = store
+        .create_provider(
+            "kc",
+            "oidc",
+            CLIENT_ID,
+            "client-secret",
+            &json!({"issuer": issuer}).to_string(),
+        )
+        .unwrap();
+    store.link_identity(user.id, prov.id, "alice", SUB).unwrap();
+    let (client, _) = store
+        .register_client(&["http://localhost:9999/cb".into()], Some("agent"))
+        .unwrap();
+    let app = router().with_state(OAuthState {
+        store: Arc::new(tokio::sync::Mutex::new(Box::new(store) as Box<dyn Storage>)),
+        external_url: "http://localhost".into(),
+    });
+    let get = |uri: String, cookie: Option<String>| {
+        let app = app.clone();
+        async move {
+            let mut req = axum::http::Request::get(uri);
+            if let Some(cookie) = cookie {
+                req = req.header("cookie", cookie);
+            }
+            app.oneshot(req.body(Body::empty()).unwrap()).await.unwrap()
+        }
+    };
+
+    let resp = get("/.well-known/oauth-authorization-server".into(), None).await;
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let metadata: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(
+        metadata["scopes_supported"],
+        json!(["memory:read", "memory:write", "admin"])
+    );
+
+    let verifier = "client-verifier-0123456789-0123456789-0123456789";
+    let challenge = URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()));
+    let authorize = |scope: &str| {
+        format!(
+            "/oauth/authorize?client_id={}&redirect_uri=http%3A%2F%2Flocalhost%3A9999%2Fcb&state=xyz&code_challenge={challenge}&provider=kc&scope={scope}",
+            client.client_id
+        )
+    };
+    assert_eq!(get(authorize("memory%3Adelete"), None).await.status(), 400);
+
+    // alice isn't an admin, so only memory:read is granted
+    let resp = get(authorize("admin%20memory%3Aread"), None).await;
+    assert_eq!(resp.status(), 307);
+    let location = resp.headers()["location"].to_str().unwrap().to_string();
+    let cookie = resp.headers()["set-cookie"]
+        .to_str()
+        .unwrap()
+        .split(';')
+        .next()
+        .unwrap()
+        .to_string();
+    {
+        let mut mock = mock.lock().unwrap();
+        mock.challenge = query_param(&location, "code_challenge");
+        mock.nonce = query_param(&location, "nonce");
+    }
+    let callback_with = |state: &str| {
+        let mut callback = reqwest::Url::parse("http://localhost/oauth/callback/kc").unwrap();
+        callback
+            .query_pairs_mut()
+            .append_pair("code", "good-code")
+            .append_pair("state", state);
+        format!("{}?{}", callback.path(), callback.query().unwrap())
+    };
+    let oauth_state = query_param(&location, "state");
+    // The state is opaque: nothing about the client's request rides along
+    assert!(!oauth_state.contains(&client.client_id) && !oauth_state.contains("xyz"));
+    let tampered = format!("{oauth_state}:{}:evil", client.client_id);
+    assert_eq!(
+        get(callback_with(&tampered), Some(cookie.clone()))
+            .await
+            .status(),
+        400
+    );
+    // The web UI callback doesn't accept a client authorization's state
+    let web_callback = callback_with(&oauth_state).replace("/oauth/", "/auth/");
+    assert_eq!(get(web_callback, Some(cookie.clone())).await.status(), 400);
+
+    // That spent the state, so start over. The client isn't approved yet,
+    // so alice is asked first
+    let (callback, cookie, resp) = sign_in(&app, &mock, authorize("admin%20memory%3Aread")).await;
+    assert_eq!(resp.status(), 200);
+    assert_eq!(resp.headers()["x-frame-options"], "DENY");
+    let page = body_text(resp).await;
+    assert!(page.contains("Authorize agent?") && page.contains("http://localhost:9999/cb"));
+    assert!(page.contains("<code>memory:read</code>") && !page.contains("<code>admin</code>"));
+    // Replaying the callback doesn't start a second authorization
+    assert_eq!(get(callback, Some(cookie)).await.status(), 400);
+
+    let answer = |page: &str, decision: &'static str| {
+        let app = app.clone();
+        let token = page.split(r#"name="token" value=""#).nth(1).unwrap();
+        let token = token.split('"').next().unwrap().to_string();
+        async move {
+            let req = axum::http::Request::post("/oauth/consent")
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(format!("token={token}&decision={decision}")))
+                .unwrap();
+            app.oneshot(req).await.unwrap()
+        }
+    };
+    let resp = answer(&page, "approve").await;
+    assert_eq!(resp.status(), 307);
+    let redirect = resp.headers()["location"].to_str().unwrap().to_string();
+    assert!(redirect.starts_with("http://localhost:9999/cb?"));
+    assert_eq!(query_param(&redirect, "state"), "xyz");
+    // The form can only be submitted once
+    assert_eq!(answer(&page, "approve").await.status(), 400);
+
+    let req = axum::http::Request::post("/oauth/token")
+        .header("content-type", "application/json")
+        .body(Body::from(
+            json!({
+                "grant_type": "authorization_code",
+                "code": query_param(&redirect, "code"),
+                "code_verifier": verifier,
+            })
+            .to_string(),
+        ))
+        .unwrap();
+    let resp = app.clone().oneshot(req).await.unwrap();
+    assert_eq!(resp.status(), 200);
+    let bytes = resp.into_body().collect().await.unwrap().to_bytes();
+    let token: Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(token["scope"], "memory:read");
+
+    // The approval is remembered for the scope it covered...
+    let (_, _, resp) = sign_in(&app, &mock, authorize("memory%3Aread")).await;
+    assert_eq!(resp.status(), 307);
+    let redirect = resp.headers()["location"].to_str().unwrap().to_string();
+    assert!(!query_param(&redirect, "code").is_empty());
+
+    // ...but asking for more needs consent again, which alice can refuse
+    let (_, _, resp) = sign_in(&app, &mock, authorize("memory%3Aread%20memory%3Awrite")).await;
+    assert_eq!(resp.status(), 200);
+    let resp = answer(&body_text(resp).await, "deny").await;
+    assert_eq!(resp.status(), 307);
+    let redirect = resp.headers()["location"].to_str().unwrap().to_string();
+    assert_eq!(query_param(&redirect, "error"), "access_denied");
+    assert_eq!(query_param(&redirect, "state"), "xyz");
+}
@@ -0,0 +1,161 @@
+//! Personal access tokens: created from a web session, accepted as bearer
+//! tokens with their scope applied, and useless once revoked.
+
+mod common;
+
+use serde_json::json;
+
+use common::{Auth, Store, send};
+use trivia_cli::admin::admin_router;
+use trivia_cli::tokens::tokens_router;
+use trivia_core::AuditFilter;
+
+fn test_app() -> (axum::Router, Store) {
+    common::test_app(|store| {
+        tokens_router(store.clone()).merge(admin_router(store, Default::default()))
+    })
+}
+
+/// Create a user and return a web session id for them.
+async fn session_for(store: &Store, username: &str, admin: bool) -> String {
+    let s = store.lock().await;
+    let user = s.create_user(username, "*:read").unwrap();
+    s.set_user_admin(username, admin).unwrap();
+    s.create_session(user.id).unwrap().session_id
+}
+
+/// Create a token from `session` and return its secret.
+async fn create_token(app: &axum::Router, session: &str, name: &str, scope: &str) -> String {
+    let body = json!({"name": name, "scope": scope});
+    let (status, created) = send(
+        app,
+        "POST",
+        "/api/tokens",
+        Auth::Session(session),
+        Some(body),
+    )
+    .await;
+    assert_eq!(status, 201);
+    created["token"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn session_manages_tokens() {
+    let (app, store) = test_app();
+    let session = session_for(&store, "ci", false).await;
+
+    let (status, created) = send(
+        &app,
+        "POST",
+        "/api/tokens",
+        Auth::Session(&session),
+        Some(json!({"name": "pipeline", "scope": "memory:read", "expires_in_days": 30})),
+    )
+    .await;
+    assert_eq!(status, 201);
+    assert_eq!(created["scope"], "memory:read");
+    assert!(created["expires_at"].is_string());
+    let secret = created["token"].as_str().unwrap().to_string();
+    assert!(secret.starts_with("trivia_pat_"));
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/api/tokens",
+        Auth::Session(&session),
+        Some(json!({"name": "pipeline"})),
+    )
+    .await;
+    assert_eq!(status, 409);
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/api/tokens",
+        Auth::Session(&session),
+        Some(json!({"name": "typo", "scope": "memory:raed"})),
+    )
+    .await;
+    assert_eq!(status, 400);
+
+    // The secret is never listed, and a token can't manage tokens
+    let (status, list) = send(&app, "GET", "/api/tokens", Auth::Session(&session), None).await;
+    assert_eq!(status, 200);
+    assert_eq!(list.as_array().unwrap().len(), 1);
+    assert!(list[0].get("token").is_none());
+    let (status, _) = send(&app, "GET", "/api/tokens", Auth::Bearer(&secret), None).await;
+    assert_eq!(status, 403);
+
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        "/api/tokens/pipeline",
+        Auth::Session(&session),
+        None,
+    )
+    .await;
+    assert_eq!(status, 200);
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        "/api/tokens/pipeline",
+        Auth::Session(&session),
+        None,
+    )
+    .await;
+    assert_eq!(status, 404);
+    let (status, _) = send(&app, "GET", "/api/tokens", Auth::Bearer(&secret), None).await;
+    assert_eq!(status, 401);
+
+    let audit = store
+        .lock()
+        .await
+        .list_audit(&AuditFilter::latest(10))
+        .unwrap();
+    let actions: Vec<&str> = audit.iter().map(|e| e.action.as_str()).collect();
+    assert!(actions.contains(&"token.create") && actions.contains(&"token.revoke"));
+}
+
+#[tokio::test]
+async fn token_scope_caps_access() {
+    let (app, store) = test_app();
+    let session = session_for(&store, "root", true).await;
+    let read_only = create_token(&app, &session, "read", "memory:read").await;
+    let admin = create_token(&app, &session, "admin", "memory:read admin").await;
+
+    let (status, _) = send(
+        &app,
+        "GET",
+        "/api/admin/users",
+        Auth::Bearer(&read_only),
+        None,
+    )
+    .await;
+    assert_eq!(status, 403);
+    let (status, users) = send(&app, "GET", "/api/admin/users", Auth::Bearer(&admin), None).await;
+    assert_eq!(status, 200);
+    assert_eq!(users[0]["username"], "root");
+
+    // Without the admin role, the admin scope is dropped
+    let session = session_for(&store, "bob", false).await;
+    let body = json!({"name": "sneaky", "scope": "memory:read admin"});
+    let (_, created) = send(
+        &app,
+        "POST",
+        "/api/tokens",
+        Auth::Session(&session),
+        Some(body),
+    )
+    .await;
+    assert_eq!(created["scope"], "memory:read");
+
+    // Revoking all of a user's tokens covers personal ones too
+    let user = store
+        .lock()
+        .await
+        .get_user_by_username("root")
+        .unwrap()
+        .unwrap();
+    assert_eq!(store.lock().await.revoke_user_tokens(user.id).unwrap(), 2);
+    let (status, _) = send(&app, "GET", "/api/admin/users", Auth::Bearer(&admin), None).await;
+    assert_eq!(status, 401);
+}
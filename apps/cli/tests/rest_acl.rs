@@ -0,0 +1,580 @@
+mod common;
+
+use std::sync::Arc;
+
+use serde_json::{Value, json};
+
+use common::{Auth, EMBEDDER, Store, scoped_token_for, send, token_for};
+use trivia_cli::acl::Acl;
+use trivia_cli::www::api_router;
+
+/// Build the REST API behind the auth middleware. With `auth_enabled`,
+/// requests need a bearer token; otherwise `share` applies to everyone.
+fn test_app(share: Acl, auth_enabled: bool) -> (axum::Router, Store) {
+    test_app_with(share, auth_enabled, false)
+}
+
+/// `test_app`, optionally in owner-only visibility mode.
+fn test_app_with(share: Acl, auth_enabled: bool, owner_only: bool) -> (axum::Router, Store) {
+    let routes = |store: Store| api_router(store, EMBEDDER.clone(), Arc::new(share.clone()));
+    common::test_app_with(routes, &share, auth_enabled, owner_only)
+}
+
+/// Unit vector along axis `i`. Seeded memories are orthogonal, so they are
+/// never auto-linked or merged with each other.
+fn axis(i: usize) -> Vec<f32> {
+    let mut v = vec![0.0; 384];
+    v[i] = 1.0;
+    v
+}
+
+/// Seed memories with distinct tags, plus links from the test memory to the
+/// other two.
+async fn seed(store: &Store) {
+    let s = store.lock().await;
+    s.memorize("test fact", "hello world", &["test".into()], &axis(0))
+        .unwrap();
+    s.memorize(
+        "private fact",
+        "secret stuff",
+        &["private".into()],
+        &axis(1),
+    )
+    .unwrap();
+    s.memorize(
+        "project fact",
+        "project data",
+        &["project".into()],
+        &axis(2),
+    )
+    .unwrap();
+    s.link("test fact", "private fact", "related").unwrap();
+    s.link("test fact", "project fact", "related").unwrap();
+}
+
+async fn get<'a>(app: &axum::Router, uri: &str, auth: impl Into<Auth<'a>>) -> (u16, Value) {
+    send(app, "GET", uri, auth, None).await
+}
+
+/// Mnemonics in a list response.
+fn mnemonics(list: &Value) -> Vec<&str> {
+    let mut names: Vec<&str> = list
+        .as_array()
+        .unwrap_or_else(|| panic!("expected array: {list}"))
+        .iter()
+        .map(|m| m["mnemonic"].as_str().unwrap())
+        .collect();
+    names.sort();
+    names
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[tokio::test]
+async fn open_acl_lists_and_edits_everything() {
+    let (app, store) = test_app(Acl::open(), false);
+    seed(&store).await;
+
+    let (status, list) = get(&app, "/api/memories", Auth::Anonymous).await;
+    assert_eq!(status, 200);
+    assert_eq!(
+        mnemonics(&list),
+        ["private fact", "project fact", "test fact"]
+    );
+
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        "/api/memories/private%20fact",
+        Auth::Anonymous,
+        None,
+    )
+    .await;
+    assert_eq!(status, 200);
+}
+
+#[tokio::test]
+async fn read_only_share_filters_listings() {
+    let (app, store) = test_app(Acl::parse("test:read,project:read,*:none").unwrap(), false);
+    seed(&store).await;
+
+    let (_, list) = get(&app, "/api/memories", Auth::Anonymous).await;
+    assert_eq!(mnemonics(&list), ["project fact", "test fact"]);
+
+    let (status, mem) = get(&app, "/api/memories/test%20fact", Auth::Anonymous).await;
+    assert_eq!(status, 200);
+    assert_eq!(mem["content"], "hello world");
+    let (status, _) = get(&app, "/api/memories/private%20fact", Auth::Anonymous).await;
+    assert_eq!(status, 404, "unreadable memories look missing");
+
+    let (_, tags) = get(&app, "/api/tags", Auth::Anonymous).await;
+    let tags: Vec<&str> = tags
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|t| t["tag"].as_str().unwrap())
+        .collect();
+    assert!(!tags.contains(&"private"), "private tag leaked: {tags:?}");
+
+    let (_, results) = get(&app, "/api/search?q=fact&limit=10", Auth::Anonymous).await;
+    assert!(
+        results
+            .as_array()
+            .unwrap()
+            .iter()
+            .all(|m| m["mnemonic"] != "private fact"),
+        "search leaked private memory: {results}"
+    );
+    let private = store
+        .lock()
+        .await
+        .get_memory_by_mnemonic("private fact")
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        private.recall_count, 0,
+        "unreadable memories aren't counted as recalled"
+    );
+
+    // The limit counts readable memories only
+    let (_, results) = get(
+        &app,
+        "/api/search?q=secret%20stuff&limit=1",
+        Auth::Anonymous,
+    )
+    .await;
+    assert_eq!(results.as_array().unwrap().len(), 1, "{results}");
+}
+
+#[tokio::test]
+async fn graph_hides_unreadable_nodes_and_edges() {
+    let (app, store) = test_app(Acl::parse("test:read,project:read,*:none").unwrap(), false);
+    seed(&store).await;
+
+    let (status, graph) = get(&app, "/api/graph", Auth::Anonymous).await;
+    assert_eq!(status, 200);
+    let nodes: Vec<&str> = graph["nodes"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|n| n["mnemonic"].as_str().unwrap())
+        .collect();
+    assert!(!nodes.contains(&"private fact"));
+    let edges = graph["edges"].as_array().unwrap();
+    assert_eq!(
+        edges.len(),
+        1,
+        "only the test→project edge is visible: {edges:?}"
+    );
+    assert_eq!(edges[0]["target"], "project fact");
+}
+
+#[tokio::test]
+async fn read_only_share_denies_mutations() {
+    let (app, store) = test_app(Acl::parse("test:read,*:none").unwrap(), false);
+    seed(&store).await;
+
+    let (status, body) = send(
+        &app,
+        "DELETE",
+        "/api/memories/test%20fact",
+        Auth::Anonymous,
+        None,
+    )
+    .await;
+    assert_eq!(status, 403);
+    assert_eq!(body["kind"], "access_denied");
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/api/memories/test%20fact/rate",
+        Auth::Anonymous,
+        Some(json!({"useful": true})),
+    )
+    .await;
+    assert_eq!(status, 403);
+
+    let (status, _) = send(
+        &app,
+        "PUT",
+        "/api/memories/test%20fact",
+        Auth::Anonymous,
+        Some(json!({"content": "changed", "tags": ["test"]})),
+    )
+    .await;
+    assert_eq!(status, 403);
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/api/memories",
+        Auth::Anonymous,
+        Some(json!({"mnemonic": "new fact", "content": "x", "tags": ["test"]})),
+    )
+    .await;
+    assert_eq!(status, 403);
+
+    // Unreadable memories are missing rather than forbidden
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        "/api/memories/private%20fact",
+        Auth::Anonymous,
+        None,
+    )
+    .await;
+    assert_eq!(status, 404);
+
+    let s = store.lock().await;
+    assert!(s.get_memory_by_mnemonic("test fact").unwrap().is_some());
+    assert!(s.get_memory_by_mnemonic("new fact").unwrap().is_none());
+}
+
+#[tokio::test]
+async fn update_cannot_move_memory_out_of_reach() {
+    let (app, store) = test_app(Acl::parse("test:update,*:read").unwrap(), false);
+    seed(&store).await;
+
+    let (status, _) = send(
+        &app,
+        "PUT",
+        "/api/memories/test%20fact",
+        Auth::Anonymous,
+        Some(json!({"content": "changed", "tags": ["private"]})),
+    )
+    .await;
+    assert_eq!(status, 403);
+
+    let (status, _) = send(
+        &app,
+        "PUT",
+        "/api/memories/test%20fact",
+        Auth::Anonymous,
+        Some(json!({"content": "changed", "tags": ["test"]})),
+    )
+    .await;
+    assert_eq!(status, 200);
+}
+
+#[tokio::test]
+async fn merge_and_link_need_update_on_both() {
+    let (app, store) = test_app(
+        Acl::parse("test:update,project:read,*:none").unwrap(),
+        false,
+    );
+    seed(&store).await;
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/api/memories/merge",
+        Auth::Anonymous,
+        Some(json!({"keep": "test fact", "discard": "project fact"})),
+    )
+    .await;
+    assert_eq!(status, 403);
+
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        "/api/links",
+        Auth::Anonymous,
+        Some(json!({"source": "test fact", "target": "project fact"})),
+    )
+    .await;
+    assert_eq!(status, 403);
+
+    let s = store.lock().await;
+    assert!(s.get_memory_by_mnemonic("project fact").unwrap().is_some());
+    assert_eq!(s.get_links("test fact").unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn auth_requires_token() {
+    let (app, _) = test_app(Acl::closed(), true);
+    let (status, _) = get(&app, "/api/memories", Auth::Anonymous).await;
+    assert_eq!(status, 401);
+}
+
+#[tokio::test]
+async fn user_acl_applies_and_create_auto_tags() {
+    let (app, store) = test_app(Acl::closed(), true);
+    seed(&store).await;
+    let token = token_for(&store, "alice", "test:read,@alice:update,*:none").await;
+
+    let (_, list) = get(&app, "/api/memories", &token).await;
+    assert_eq!(mnemonics(&list), ["test fact"]);
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/api/memories",
+        &token,
+        Some(json!({"mnemonic": "alice note", "content": "mine"})),
+    )
+    .await;
+    assert_eq!(status, 201);
+
+    let (status, mem) = get(&app, "/api/memories/alice%20note", &token).await;
+    assert_eq!(status, 200);
+    assert_eq!(mem["tags"], json!(["@alice"]));
+
+    // Another user can't see it
+    let bob = token_for(&store, "bob", "@bob:update,*:none").await;
+    let (status, _) = get(&app, "/api/memories/alice%20note", &bob).await;
+    assert_eq!(status, 404);
+}
+
+#[tokio::test]
+async fn deny_tag_caps_access() {
+    let (app, store) = test_app(
+        Acl::parse("project:update,private:!none,*:read").unwrap(),
+        false,
+    );
+    seed(&store).await;
+    store
+        .lock()
+        .await
+        .memorize(
+            "leaked plans",
+            "do not share",
+            &["project".into(), "private".into()],
+            &axis(3),
+        )
+        .unwrap();
+
+    let (_, list) = get(&app, "/api/memories", Auth::Anonymous).await;
+    assert!(!mnemonics(&list).contains(&"leaked plans"));
+    let (status, _) = get(&app, "/api/memories/leaked%20plans", Auth::Anonymous).await;
+    assert_eq!(status, 404);
+
+    let (_, tags) = get(&app, "/api/tags", Auth::Anonymous).await;
+    assert!(
+        tags.as_array()
+            .unwrap()
+            .iter()
+            .all(|t| t["tag"] != "private")
+    );
+    let project = tags
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|t| t["tag"] == "project")
+        .unwrap();
+    assert_eq!(project["count"], 1);
+
+    let (status, _) = send(
+        &app,
+        "DELETE",
+        "/api/memories/leaked%20plans",
+        Auth::Anonymous,
+        None,
+    )
+    .await;
+    assert_eq!(status, 404);
+}
+
+#[tokio::test]
+async fn group_acls_union_with_user_acl() {
+    let (app, store) = test_app(Acl::closed(), true);
+    seed(&store).await;
+    let token = token_for(&store, "carol", "@carol:update,*:none").await;
+    {
+        let s = store.lock().await;
+        s.create_group("eng", "project:update,*:read").unwrap();
+        s.create_group("compliance", "private:!none").unwrap();
+        s.add_group_member("eng", "carol").unwrap();
+        s.add_group_member("compliance", "carol").unwrap();
+    }
+
+    let (_, list) = get(&app, "/api/memories", &token).await;
+    assert_eq!(mnemonics(&list), ["project fact", "test fact"]);
+
+    let (status, _) = send(&app, "DELETE", "/api/memories/project%20fact", &token, None).await;
+    assert_eq!(status, 200);
+    let (status, _) = send(&app, "DELETE", "/api/memories/test%20fact", &token, None).await;
+    assert_eq!(status, 403);
+
+    // Leaving the group takes its grants away on the next request
+    store
+        .lock()
+        .await
+        .remove_group_member("eng", "carol")
+        .unwrap();
+    let (_, list) = get(&app, "/api/memories", &token).await;
+    assert!(mnemonics(&list).is_empty());
+}
+
+#[tokio::test]
+async fn owner_can_update_own_memories() {
+    let (app, store) = test_app(Acl::closed(), true);
+    seed(&store).await;
+    let dave = token_for(&store, "dave", "*:read").await;
+    token_for(&store, "erin", "*:read").await;
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/api/memories",
+        &dave,
+        Some(json!({"mnemonic": "dave plan", "content": "mine", "tags": ["project"]})),
+    )
+    .await;
+    assert_eq!(status, 201);
+    let (_, mem) = get(&app, "/api/memories/dave%20plan", &dave).await;
+    assert_eq!(mem["owner"], "dave");
+
+    let (status, _) = send(
+        &app,
+        "PUT",
+        "/api/memories/dave%20plan",
+        &dave,
+        Some(json!({"content": "still mine", "tags": ["project"]})),
+    )
+    .await;
+    assert_eq!(status, 200);
+    let (status, _) = send(&app, "DELETE", "/api/memories/test%20fact", &dave, None).await;
+    assert_eq!(status, 403);
+
+    // Ownership moves with a transfer
+    store
+        .lock()
+        .await
+        .transfer_ownership("dave", "erin")
+        .unwrap();
+    let (status, _) = send(&app, "DELETE", "/api/memories/dave%20plan", &dave, None).await;
+    assert_eq!(status, 403);
+}
+
+#[tokio::test]
+async fn overwrite_checks_new_tags_against_the_existing_owner() {
+    let (app, store) = test_app(Acl::closed(), true);
+    let alice = token_for(&store, "alice", "*:read").await;
+    let bob = token_for(&store, "bob", "test:update,*:read").await;
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/api/memories",
+        &alice,
+        Some(json!({"mnemonic": "alice plan", "content": "mine", "tags": ["test"]})),
+    )
+    .await;
+    assert_eq!(status, 201);
+
+    // Bob may update the memory through its tag, but not retag it into one
+    // he can only read by overwriting it
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/api/memories",
+        &bob,
+        Some(json!({"mnemonic": "alice plan", "content": "moved", "tags": ["private"]})),
+    )
+    .await;
+    assert_eq!(status, 403);
+    let (_, mem) = get(&app, "/api/memories/alice%20plan", &alice).await;
+    assert_eq!(mem["content"], "mine");
+    assert_eq!(mem["owner"], "alice");
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/api/memories",
+        &bob,
+        Some(json!({"mnemonic": "alice plan", "content": "edited", "tags": ["test"]})),
+    )
+    .await;
+    assert_eq!(status, 201);
+    let (_, mem) = get(&app, "/api/memories/alice%20plan", &alice).await;
+    assert_eq!(mem["content"], "edited");
+    assert_eq!(mem["owner"], "alice");
+}
+
+#[tokio::test]
+async fn owner_only_hides_other_users_memories() {
+    let (app, store) = test_app_with(Acl::closed(), true, true);
+    seed(&store).await;
+    let alice = token_for(&store, "alice", "*:read").await;
+    let bob = token_for(&store, "bob", "*:read").await;
+    let admin = token_for(&store, "root", "*:update").await;
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/api/memories",
+        &alice,
+        Some(json!({"mnemonic": "alice plan", "content": "mine", "tags": ["project"]})),
+    )
+    .await;
+    assert_eq!(status, 201);
+
+    let (_, list) = get(&app, "/api/memories", &alice).await;
+    assert!(mnemonics(&list).contains(&"alice plan"));
+    let (_, list) = get(&app, "/api/memories", &bob).await;
+    assert_eq!(
+        mnemonics(&list),
+        ["private fact", "project fact", "test fact"]
+    );
+    let (status, _) = get(&app, "/api/memories/alice%20plan", &bob).await;
+    assert_eq!(status, 404);
+
+    // An open ACL still sees everything
+    let (status, _) = get(&app, "/api/memories/alice%20plan", &admin).await;
+    assert_eq!(status, 200);
+}
+
+#[tokio::test]
+async fn revoked_tokens_stop_working() {
+    let (app, store) = test_app(Acl::closed(), true);
+    let token = token_for(&store, "frank", "*:read").await;
+    let id = store
+        .lock()
+        .await
+        .get_user_by_username("frank")
+        .unwrap()
+        .unwrap()
+        .id;
+
+    let (status, _) = get(&app, "/api/memories", &token).await;
+    assert_eq!(status, 200);
+    assert!(
+        store
+            .lock()
+            .await
+            .user_activity(id)
+            .unwrap()
+            .last_seen_at
+            .is_some()
+    );
+
+    assert_eq!(store.lock().await.revoke_user_tokens(id).unwrap(), 1);
+    let (status, _) = get(&app, "/api/memories", &token).await;
+    assert_eq!(status, 401);
+}
+
+#[tokio::test]
+async fn read_scope_caps_token_at_read() {
+    let (app, store) = test_app(Acl::closed(), true);
+    seed(&store).await;
+    let token = scoped_token_for(&store, "ci", "*:update", false, Some("memory:read")).await;
+
+    let (status, list) = get(&app, "/api/memories", &token).await;
+    assert_eq!(status, 200);
+    assert_eq!(mnemonics(&list).len(), 3);
+
+    let (status, _) = send(
+        &app,
+        "POST",
+        "/api/memories",
+        &token,
+        Some(json!({"mnemonic": "ci note", "content": "nope", "tags": ["test"]})),
+    )
+    .await;
+    assert_eq!(status, 403);
+    let (status, _) = send(&app, "DELETE", "/api/memories/test%20fact", &token, None).await;
+    assert_eq!(status, 403);
+}
@@ -3,6 +3,8 @@ import { useQuery, useQueryClient } from '@tanstack/react-query'
 import { MemoryList } from './pages/MemoryList'
 import { MemoryDetail } from './pages/MemoryDetail'
 import { GraphView } from './pages/GraphView'
+import { Admin } from './pages/Admin'
+import { Tokens } from './pages/Tokens'
 import { auth } from './api'
 
 function LoginPage() {
@@ -68,8 +70,8 @@ export default function App() {
     return <LoginPage />
   }
 
-  const handleLogout = async () => {
-    await auth.logout()
+  const handleLogout = async (everywhere: boolean) => {
+    await (everywhere ? auth.logoutAll() : auth.logout())
     queryClient.invalidateQueries({ queryKey: ['auth-me'] })
   }
 
@@ -83,15 +85,32 @@ export default function App() {
         <NavLink to="/graph" className={({ isActive }) => isActive ? 'text-blue-600 font-medium' : 'text-gray-500 hover:text-gray-800'}>
           Graph
         </NavLink>
+        {user && (
+          <NavLink to="/tokens" className={({ isActive }) => isActive ? 'text-blue-600 font-medium' : 'text-gray-500 hover:text-gray-800'}>
+            Tokens
+          </NavLink>
+        )}
+        {user?.is_admin && (
+          <NavLink to="/admin" className={({ isActive }) => isActive ? 'text-blue-600 font-medium' : 'text-gray-500 hover:text-gray-800'}>
+            Admin
+          </NavLink>
+        )}
         {user && (
           <div className="ml-auto flex items-center gap-3">
             <span className="text-sm text-gray-500">{user.username}</span>
             <button
-              onClick={handleLogout}
+              onClick={() => handleLogout(false)}
               className="text-sm text-gray-400 hover:text-gray-600"
             >
               Sign out
             </button>
+            <button
+              onClick={() => handleLogout(true)}
+              title="End all of your sessions on every device"
+              className="text-sm text-gray-400 hover:text-gray-600"
+            >
+              Sign out everywhere
+            </button>
           </div>
         )}
       </nav>
@@ -100,6 +119,8 @@ export default function App() {
           <Route path="/" element={<MemoryList />} />
           <Route path="/memory/:mnemonic" element={<MemoryDetail />} />
           <Route path="/graph" element={<GraphView />} />
+          <Route path="/admin" element={<Admin />} />
+          <Route path="/tokens" element={<Tokens />} />
         </Routes>
       </main>
     </div>
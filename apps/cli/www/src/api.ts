@@ -50,12 +50,89 @@ export interface TagCount {
   count: number
 }
 
+export interface AdminUser {
+  username: string
+  acl: string
+  is_admin: boolean
+  created_at: string
+  last_seen_at: string | null
+  groups: string[]
+  sessions: number
+  tokens: number
+}
+
+export interface AdminGroup {
+  name: string
+  acl: string
+  created_at: string
+  members: string[]
+}
+
+export interface AdminProvider {
+  name: string
+  provider_type: string
+  client_id: string
+  enabled: boolean
+  created_at: string
+}
+
+export interface AdminClient {
+  client_id: string
+  client_name: string | null
+  redirect_uris: string[]
+  created_at: string
+}
+
+export interface AuditEntry {
+  id: number
+  created_at: string
+  actor: string | null
+  action: string
+  target: string | null
+  detail: string | null
+  transport: string | null
+}
+
+export interface MaintenanceRun {
+  started_at: string
+  duration_ms: number
+  report?: { expired_tokens: number; expired_sessions: number; expired_codes: number; stale_recalls: number }
+  error?: string
+}
+
+export interface MaintenanceStatus {
+  interval_minutes: number | null
+  last_run: MaintenanceRun | null
+}
+
+export interface PersonalToken {
+  name: string
+  scope: string
+  expires_at: string | null
+  created_at: string
+  last_used_at: string | null
+}
+
+export interface ApprovedClient {
+  client_id: string
+  client_name: string | null
+  redirect_uris: string[]
+  scope: string
+  approved_at: string
+}
+
 const enc = (s: string) => encodeURIComponent(s)
 
 async function json<T>(res: Response): Promise<T> {
   if (!res.ok) {
     const text = await res.text()
-    throw new Error(`${res.status}: ${text}`)
+    let message = text
+    try {
+      message = JSON.parse(text).error ?? text
+    } catch {
+      // not a JSON error body
+    }
+    throw new Error(`${res.status}: ${message}`)
   }
   return res.json()
 }
@@ -63,6 +140,7 @@ async function json<T>(res: Response): Promise<T> {
 export interface AuthUser {
   username: string
   acl: string
+  is_admin: boolean
 }
 
 export interface AuthProviders {
@@ -81,6 +159,9 @@ export const auth = {
 
   logout: () =>
     fetch('/auth/logout', { method: 'POST' }),
+
+  logoutAll: () =>
+    fetch('/auth/logout-all', { method: 'POST' }),
 }
 
 export const api = {
@@ -155,3 +236,95 @@ export const api = {
       body: JSON.stringify({ text }),
     }).then(r => json<{ ok: boolean }>(r)),
 }
+
+const send = (method: string, url: string, body?: unknown) =>
+  fetch(url, {
+    method,
+    headers: { 'Content-Type': 'application/json' },
+    body: body === undefined ? undefined : JSON.stringify(body),
+  }).then(r => json<{ ok: boolean }>(r))
+
+export const tokens = {
+  list: () =>
+    fetch('/api/tokens').then(r => json<PersonalToken[]>(r)),
+
+  // The response's `token` is the secret, shown only this once
+  create: (name: string, scope: string, expires_in_days?: number) =>
+    fetch('/api/tokens', {
+      method: 'POST',
+      headers: { 'Content-Type': 'application/json' },
+      body: JSON.stringify({ name, scope, expires_in_days }),
+    }).then(r => json<PersonalToken & { token: string }>(r)),
+
+  revoke: (name: string) =>
+    send('DELETE', `/api/tokens/${enc(name)}`),
+}
+
+// OAuth clients the user approved on the consent page
+export const clients = {
+  list: () =>
+    fetch('/api/clients').then(r => json<ApprovedClient[]>(r)),
+
+  // Also revokes the client's tokens; it has to ask again next time
+  revoke: (clientId: string) =>
+    send('DELETE', `/api/clients/${enc(clientId)}`),
+}
+
+export const admin = {
+  listUsers: () =>
+    fetch('/api/admin/users').then(r => json<AdminUser[]>(r)),
+
+  createUser: (username: string, acl: string, is_admin: boolean) =>
+    send('POST', '/api/admin/users', { username, acl, is_admin }),
+
+  updateUser: (username: string, changes: { acl?: string; is_admin?: boolean }) =>
+    send('PUT', `/api/admin/users/${enc(username)}`, changes),
+
+  deleteUser: (username: string) =>
+    send('DELETE', `/api/admin/users/${enc(username)}`),
+
+  revokeSessions: (username: string) =>
+    send('DELETE', `/api/admin/users/${enc(username)}/sessions`),
+
+  revokeTokens: (username: string) =>
+    send('DELETE', `/api/admin/users/${enc(username)}/tokens`),
+
+  listGroups: () =>
+    fetch('/api/admin/groups').then(r => json<AdminGroup[]>(r)),
+
+  createGroup: (name: string, acl: string) =>
+    send('POST', '/api/admin/groups', { name, acl }),
+
+  updateGroup: (name: string, acl: string) =>
+    send('PUT', `/api/admin/groups/${enc(name)}`, { acl }),
+
+  deleteGroup: (name: string) =>
+    send('DELETE', `/api/admin/groups/${enc(name)}`),
+
+  addMember: (group: string, username: string) =>
+    send('PUT', `/api/admin/groups/${enc(group)}/members/${enc(username)}`),
+
+  removeMember: (group: string, username: string) =>
+    send('DELETE', `/api/admin/groups/${enc(group)}/members/${enc(username)}`),
+
+  listProviders: () =>
+    fetch('/api/admin/providers').then(r => json<AdminProvider[]>(r)),
+
+  setProviderEnabled: (name: string, enabled: boolean) =>
+    send('PUT', `/api/admin/providers/${enc(name)}`, { enabled }),
+
+  listClients: () =>
+    fetch('/api/admin/clients').then(r => json<AdminClient[]>(r)),
+
+  deleteClient: (clientId: string) =>
+    send('DELETE', `/api/admin/clients/${enc(clientId)}`),
+
+  audit: (limit = 50) =>
+    fetch(`/api/admin/audit?limit=${limit}`).then(r => json<AuditEntry[]>(r)),
+
+  maintenance: () =>
+    fetch('/api/admin/maintenance').then(r => json<MaintenanceStatus>(r)),
+
+  runMaintenance: () =>
+    send('POST', '/api/admin/maintenance'),
+}
@@ -0,0 +1,312 @@
+import { useState, type ReactNode } from 'react'
+import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query'
+import { admin } from '../api'
+
+const input = 'border rounded-md px-2 py-1 text-sm focus:outline-none focus:ring-2 focus:ring-blue-500'
+const button = 'px-3 py-1 text-sm border rounded-md hover:bg-gray-50'
+const danger = 'px-3 py-1 text-sm text-red-600 border border-red-200 rounded-md hover:bg-red-50'
+
+function Section({ title, children }: { title: string; children: ReactNode }) {
+  return (
+    <section className="bg-white border rounded-lg p-4 mb-6">
+      <h2 className="font-semibold mb-3">{title}</h2>
+      {children}
+    </section>
+  )
+}
+
+// Refetch everything on the page after a change; the lists are small.
+function useAdminMutation<T>(fn: (arg: T) => Promise<unknown>) {
+  const queryClient = useQueryClient()
+  return useMutation({
+    mutationFn: fn,
+    onSuccess: () => queryClient.invalidateQueries({ queryKey: ['admin'] }),
+    onError: (e: Error) => alert(e.message),
+  })
+}
+
+function Users() {
+  const { data: users = [] } = useQuery({ queryKey: ['admin', 'users'], queryFn: admin.listUsers })
+  const [username, setUsername] = useState('')
+  const [acl, setAcl] = useState('*:none')
+
+  const create = useAdminMutation(() => admin.createUser(username, acl, false))
+  const update = useAdminMutation(
+    ({ name, changes }: { name: string; changes: { acl?: string; is_admin?: boolean } }) =>
+      admin.updateUser(name, changes),
+  )
+  const remove = useAdminMutation(admin.deleteUser)
+  const revokeSessions = useAdminMutation(admin.revokeSessions)
+  const revokeTokens = useAdminMutation(admin.revokeTokens)
+
+  const editAcl = (name: string, current: string) => {
+    const next = prompt(`ACL for ${name}`, current)
+    if (next !== null && next !== current) update.mutate({ name, changes: { acl: next } })
+  }
+
+  return (
+    <Section title="Users">
+      <table className="w-full text-sm mb-3">
+        <thead className="text-left text-gray-500">
+          <tr>
+            <th className="py-1">User</th>
+            <th>ACL</th>
+            <th>Groups</th>
+            <th>Last seen</th>
+            <th>Sessions / tokens</th>
+            <th />
+          </tr>
+        </thead>
+        <tbody>
+          {users.map(u => (
+            <tr key={u.username} className="border-t">
+              <td className="py-2">
+                {u.username}
+                {u.is_admin && <span className="ml-2 text-xs text-blue-600">admin</span>}
+              </td>
+              <td>
+                <button onClick={() => editAcl(u.username, u.acl)} className="font-mono hover:underline">
+                  {u.acl}
+                </button>
+              </td>
+              <td>{u.groups.join(', ')}</td>
+              <td>{u.last_seen_at ? new Date(u.last_seen_at).toLocaleString() : 'never'}</td>
+              <td>{u.sessions} / {u.tokens}</td>
+              <td className="text-right space-x-2 whitespace-nowrap">
+                <button
+                  className={button}
+                  onClick={() => update.mutate({ name: u.username, changes: { is_admin: !u.is_admin } })}
+                >
+                  {u.is_admin ? 'Revoke admin' : 'Make admin'}
+                </button>
+                <button className={button} onClick={() => revokeSessions.mutate(u.username)}>
+                  Sign out
+                </button>
+                <button className={button} onClick={() => revokeTokens.mutate(u.username)}>
+                  Revoke tokens
+                </button>
+                <button
+                  className={danger}
+                  onClick={() => confirm(`Delete ${u.username}?`) && remove.mutate(u.username)}
+                >
+                  Delete
+                </button>
+              </td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+      <form
+        className="flex gap-2"
+        onSubmit={e => {
+          e.preventDefault()
+          create.mutate(undefined, { onSuccess: () => setUsername('') })
+        }}
+      >
+        <input className={input} placeholder="username" value={username} onChange={e => setUsername(e.target.value)} />
+        <input className={`${input} font-mono`} placeholder="acl" value={acl} onChange={e => setAcl(e.target.value)} />
+        <button className={button} disabled={!username}>Add user</button>
+      </form>
+    </Section>
+  )
+}
+
+function Groups() {
+  const { data: groups = [] } = useQuery({ queryKey: ['admin', 'groups'], queryFn: admin.listGroups })
+  const [name, setName] = useState('')
+  const [acl, setAcl] = useState('')
+
+  const create = useAdminMutation(() => admin.createGroup(name, acl))
+  const update = useAdminMutation(({ group, acl }: { group: string; acl: string }) => admin.updateGroup(group, acl))
+  const remove = useAdminMutation(admin.deleteGroup)
+  const addMember = useAdminMutation(({ group, user }: { group: string; user: string }) => admin.addMember(group, user))
+  const removeMember = useAdminMutation(({ group, user }: { group: string; user: string }) =>
+    admin.removeMember(group, user),
+  )
+
+  return (
+    <Section title="Groups">
+      <table className="w-full text-sm mb-3">
+        <tbody>
+          {groups.map(g => (
+            <tr key={g.name} className="border-t">
+              <td className="py-2">{g.name}</td>
+              <td>
+                <button
+                  className="font-mono hover:underline"
+                  onClick={() => {
+                    const next = prompt(`ACL for ${g.name}`, g.acl)
+                    if (next !== null && next !== g.acl) update.mutate({ group: g.name, acl: next })
+                  }}
+                >
+                  {g.acl}
+                </button>
+              </td>
+              <td className="space-x-1">
+                {g.members.map(m => (
+                  <button
+                    key={m}
+                    title="Remove from group"
+                    onClick={() => removeMember.mutate({ group: g.name, user: m })}
+                    className="px-2 py-0.5 bg-gray-100 rounded text-xs hover:bg-red-100"
+                  >
+                    {m} ×
+                  </button>
+                ))}
+              </td>
+              <td className="text-right space-x-2 whitespace-nowrap">
+                <button
+                  className={button}
+                  onClick={() => {
+                    const user = prompt(`Add user to ${g.name}`)
+                    if (user) addMember.mutate({ group: g.name, user })
+                  }}
+                >
+                  Add member
+                </button>
+                <button
+                  className={danger}
+                  onClick={() => confirm(`Delete group ${g.name}?`) && remove.mutate(g.name)}
+                >
+                  Delete
+                </button>
+              </td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+      <form
+        className="flex gap-2"
+        onSubmit={e => {
+          e.preventDefault()
+          create.mutate(undefined, { onSuccess: () => setName('') })
+        }}
+      >
+        <input className={input} placeholder="name" value={name} onChange={e => setName(e.target.value)} />
+        <input className={`${input} font-mono`} placeholder="acl" value={acl} onChange={e => setAcl(e.target.value)} />
+        <button className={button} disabled={!name || !acl}>Add group</button>
+      </form>
+    </Section>
+  )
+}
+
+function Providers() {
+  const { data: providers = [] } = useQuery({ queryKey: ['admin', 'providers'], queryFn: admin.listProviders })
+  const toggle = useAdminMutation(({ name, enabled }: { name: string; enabled: boolean }) =>
+    admin.setProviderEnabled(name, enabled),
+  )
+
+  return (
+    <Section title="Login providers">
+      {providers.length === 0 && <p className="text-sm text-gray-500">None configured.</p>}
+      <table className="w-full text-sm">
+        <tbody>
+          {providers.map(p => (
+            <tr key={p.name} className="border-t">
+              <td className="py-2">{p.name}</td>
+              <td>{p.provider_type}</td>
+              <td className="font-mono">{p.client_id}</td>
+              <td className="text-right">
+                <button className={button} onClick={() => toggle.mutate({ name: p.name, enabled: !p.enabled })}>
+                  {p.enabled ? 'Disable' : 'Enable'}
+                </button>
+              </td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+    </Section>
+  )
+}
+
+function Clients() {
+  const { data: clients = [] } = useQuery({ queryKey: ['admin', 'clients'], queryFn: admin.listClients })
+  const remove = useAdminMutation(admin.deleteClient)
+
+  return (
+    <Section title="OAuth clients">
+      {clients.length === 0 && <p className="text-sm text-gray-500">No registered clients.</p>}
+      <table className="w-full text-sm">
+        <tbody>
+          {clients.map(c => (
+            <tr key={c.client_id} className="border-t">
+              <td className="py-2">{c.client_name ?? <span className="text-gray-400">unnamed</span>}</td>
+              <td className="font-mono text-xs">{c.client_id}</td>
+              <td>{new Date(c.created_at).toLocaleDateString()}</td>
+              <td className="text-right">
+                <button
+                  className={danger}
+                  onClick={() => confirm('Delete this client and its tokens?') && remove.mutate(c.client_id)}
+                >
+                  Delete
+                </button>
+              </td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+    </Section>
+  )
+}
+
+function Maintenance() {
+  const { data: status } = useQuery({ queryKey: ['admin', 'maintenance'], queryFn: admin.maintenance })
+  const run = useAdminMutation(admin.runMaintenance)
+  const last = status?.last_run
+
+  return (
+    <Section title="Maintenance">
+      <div className="flex items-center gap-4 text-sm">
+        <span className="text-gray-500">
+          {status?.interval_minutes ? `Runs every ${status.interval_minutes} minutes.` : 'Scheduled runs are off.'}
+        </span>
+        {last && (
+          <span>
+            Last run {new Date(last.started_at).toLocaleString()}:{' '}
+            {last.report
+              ? `removed ${last.report.expired_tokens} tokens, ${last.report.expired_sessions} sessions, ${last.report.expired_codes} codes, ${last.report.stale_recalls} recalls`
+              : <span className="text-red-600">{last.error}</span>}
+          </span>
+        )}
+        <button className={`${button} ml-auto`} disabled={run.isPending} onClick={() => run.mutate(undefined)}>
+          Run now
+        </button>
+      </div>
+    </Section>
+  )
+}
+
+function AuditLog() {
+  const { data: entries = [] } = useQuery({ queryKey: ['admin', 'audit'], queryFn: () => admin.audit() })
+
+  return (
+    <Section title="Recent changes">
+      <table className="w-full text-sm">
+        <tbody>
+          {entries.map(e => (
+            <tr key={e.id} className="border-t">
+              <td className="py-1 text-gray-500 whitespace-nowrap">{new Date(e.created_at).toLocaleString()}</td>
+              <td>{e.actor ?? '-'}</td>
+              <td className="font-mono">{e.action}</td>
+              <td>{e.target}</td>
+              <td className="text-gray-500">{e.detail}</td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+    </Section>
+  )
+}
+
+export function Admin() {
+  return (
+    <div>
+      <Users />
+      <Groups />
+      <Providers />
+      <Clients />
+      <Maintenance />
+      <AuditLog />
+    </div>
+  )
+}
@@ -0,0 +1,157 @@
+import { useState } from 'react'
+import { useMutation, useQuery, useQueryClient } from '@tanstack/react-query'
+import { clients, tokens } from '../api'
+
+const input = 'border rounded-md px-2 py-1 text-sm focus:outline-none focus:ring-2 focus:ring-blue-500'
+const button = 'px-3 py-1 text-sm border rounded-md hover:bg-gray-50'
+const danger = 'px-3 py-1 text-sm text-red-600 border border-red-200 rounded-md hover:bg-red-50'
+
+const when = (t: string | null) => (t ? new Date(t).toLocaleString() : 'never')
+
+export function Tokens() {
+  return (
+    <div className="space-y-4">
+      <PersonalTokens />
+      <ConnectedApps />
+    </div>
+  )
+}
+
+function PersonalTokens() {
+  const queryClient = useQueryClient()
+  const { data: list = [] } = useQuery({ queryKey: ['tokens'], queryFn: tokens.list })
+  const [name, setName] = useState('')
+  const [scope, setScope] = useState('memory:read')
+  const [days, setDays] = useState('')
+  const [secret, setSecret] = useState<string | null>(null)
+
+  const onSuccess = () => queryClient.invalidateQueries({ queryKey: ['tokens'] })
+  const onError = (e: Error) => alert(e.message)
+  const create = useMutation({
+    mutationFn: () => tokens.create(name, scope, days ? Number(days) : undefined),
+    onSuccess: created => {
+      setSecret(created.token)
+      setName('')
+      onSuccess()
+    },
+    onError,
+  })
+  const revoke = useMutation({ mutationFn: tokens.revoke, onSuccess, onError })
+
+  return (
+    <section className="bg-white border rounded-lg p-4">
+      <h2 className="font-semibold mb-1">Personal access tokens</h2>
+      <p className="text-sm text-gray-500 mb-3">
+        For scripts and CI: send one as <code>Authorization: Bearer &lt;token&gt;</code> to the API or <code>/mcp</code>.
+      </p>
+      {secret && (
+        <div className="mb-3 p-3 text-sm bg-yellow-50 border border-yellow-200 rounded-md">
+          Copy this token now; it won't be shown again.
+          <div className="font-mono mt-1 break-all select-all">{secret}</div>
+        </div>
+      )}
+      <table className="w-full text-sm mb-3">
+        <thead className="text-left text-gray-500">
+          <tr>
+            <th className="py-1">Name</th>
+            <th>Scope</th>
+            <th>Expires</th>
+            <th>Last used</th>
+            <th />
+          </tr>
+        </thead>
+        <tbody>
+          {list.map(t => (
+            <tr key={t.name} className="border-t">
+              <td className="py-2">{t.name}</td>
+              <td className="font-mono">{t.scope}</td>
+              <td>{when(t.expires_at)}</td>
+              <td>{when(t.last_used_at)}</td>
+              <td className="text-right">
+                <button
+                  className={danger}
+                  onClick={() => confirm(`Revoke token ${t.name}?`) && revoke.mutate(t.name)}
+                >
+                  Revoke
+                </button>
+              </td>
+            </tr>
+          ))}
+        </tbody>
+      </table>
+      <div className="flex gap-2">
+        <input className={input} placeholder="name" value={name} onChange={e => setName(e.target.value)} />
+        <select className={input} value={scope} onChange={e => setScope(e.target.value)}>
+          <option value="memory:read">read</option>
+          <option value="memory:read memory:write">read and write</option>
+        </select>
+        <input
+          className={`${input} w-32`}
+          type="number"
+          min={1}
+          placeholder="expires (days)"
+          value={days}
+          onChange={e => setDays(e.target.value)}
+        />
+        <button className={button} disabled={!name || create.isPending} onClick={() => create.mutate()}>
+          Create token
+        </button>
+      </div>
+    </section>
+  )
+}
+
+function ConnectedApps() {
+  const queryClient = useQueryClient()
+  const { data: list = [] } = useQuery({ queryKey: ['clients'], queryFn: clients.list })
+  const revoke = useMutation({
+    mutationFn: clients.revoke,
+    onSuccess: () => queryClient.invalidateQueries({ queryKey: ['clients'] }),
+    onError: (e: Error) => alert(e.message),
+  })
+
+  return (
+    <section className="bg-white border rounded-lg p-4">
+      <h2 className="font-semibold mb-1">Connected apps</h2>
+      <p className="text-sm text-gray-500 mb-3">
+        Applications you approved to use your account. Revoking one signs it out; it will ask again next time.
+      </p>
+      {list.length === 0 ? (
+        <p className="text-sm text-gray-500">No apps approved yet.</p>
+      ) : (
+        <table className="w-full text-sm">
+          <thead className="text-left text-gray-500">
+            <tr>
+              <th className="py-1">App</th>
+              <th>Scope</th>
+              <th>Approved</th>
+              <th />
+            </tr>
+          </thead>
+          <tbody>
+            {list.map(c => (
+              <tr key={c.client_id} className="border-t">
+                <td className="py-2">
+                  {c.client_name ?? c.client_id}
+                  <div className="text-xs text-gray-500 break-all">{c.redirect_uris.join(', ')}</div>
+                </td>
+                <td className="font-mono">{c.scope}</td>
+                <td>{when(c.approved_at)}</td>
+                <td className="text-right">
+                  <button
+                    className={danger}
+                    onClick={() =>
+                      confirm(`Revoke access for ${c.client_name ?? c.client_id}?`) && revoke.mutate(c.client_id)
+                    }
+                  >
+                    Revoke
+                  </button>
+                </td>
+              </tr>
+            ))}
+          </tbody>
+        </table>
+      )}
+    </section>
+  )
+}
@@ -0,0 +1,652 @@
+use anyhow::Context;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::{DateTime, Utc};
+use rusqlite::{OptionalExtension, params};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, BufWriter, Write};
+use std::path::Path;
+use zerocopy::AsBytes;
+
+use crate::embedder::DIMENSIONS;
+use crate::error::{Result, TriviaError};
+use crate::export::ImportOutcome;
+use crate::store::{MemoryStore, format_sqlite_datetime, parse_sqlite_datetime};
+
+/// One line of a JSONL archive: every column of a memory, so that
+/// `export --format jsonl` followed by `import --format jsonl` loses nothing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveRecord {
+    pub uuid: String,
+    pub title: String,
+    pub content: String,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub recall_count: i64,
+    #[serde(default)]
+    pub last_recalled_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub useful_count: i64,
+    #[serde(default)]
+    pub not_useful_count: i64,
+    /// Counters from before ratings were attributed, included in the two
+    /// above.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub legacy_useful: i64,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub legacy_not_useful: i64,
+    /// Every mnemonic, the title included.
+    pub mnemonics: Vec<ArchiveMnemonic>,
+    /// Outgoing links, keyed by target UUID.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<ArchiveLink>,
+    /// Every rating, oldest first. Archives written before ratings were
+    /// attributed have none; their counters are imported as legacy counts.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ratings: Vec<ArchiveRating>,
+    /// Model that produced the mnemonic embeddings; absent when the archive
+    /// was written without vectors.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_model: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveMnemonic {
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+    /// Base64 of the little-endian f32 vector.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveLink {
+    pub target: String,
+    #[serde(rename = "type")]
+    pub link_type: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveRating {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rater: Option<String>,
+    pub useful: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub query: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+fn is_zero(n: &i64) -> bool {
+    *n == 0
+}
+
+impl ArchiveRecord {
+    /// The legacy counters to store for this record: its own, or for an
+    /// archive from before ratings were attributed, its counters.
+    pub(crate) fn legacy_counts(&self) -> (i64, i64) {
+        let predates_ratings =
+            self.ratings.is_empty() && self.legacy_useful == 0 && self.legacy_not_useful == 0;
+        if predates_ratings {
+            (self.useful_count, self.not_useful_count)
+        } else {
+            (self.legacy_useful, self.legacy_not_useful)
+        }
+    }
+
+    /// The record with vectors and links stripped and mnemonics sorted, for
+    /// deciding whether an import changes anything.
+    fn comparable(&self) -> Self {
+        let mut mnemonics: Vec<ArchiveMnemonic> = self
+            .mnemonics
+            .iter()
+            .map(|m| ArchiveMnemonic {
+                embedding: None,
+                ..m.clone()
+            })
+            .collect();
+        mnemonics.sort_by(|a, b| a.text.cmp(&b.text));
+        Self {
+            mnemonics,
+            links: Vec::new(),
+            embedding_model: None,
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn is_unchanged_from(&self, existing: &ArchiveRecord) -> bool {
+        self.comparable() == existing.comparable()
+    }
+
+    /// Whether every vector in `vectors` is already stored as given.
+    pub(crate) fn has_vectors(&self, vectors: &HashMap<String, Vec<f32>>) -> bool {
+        vectors.iter().all(|(text, vector)| {
+            self.mnemonics
+                .iter()
+                .any(|m| m.text == *text && m.embedding.as_deref() == Some(&encode_vector(vector)))
+        })
+    }
+}
+
+pub(crate) fn encode_vector(vector: &[f32]) -> String {
+    let bytes: Vec<u8> = vector.iter().flat_map(|f| f.to_le_bytes()).collect();
+    BASE64.encode(bytes)
+}
+
+pub(crate) fn decode_vector(encoded: &str) -> Result<Vec<f32>> {
+    let bytes = BASE64
+        .decode(encoded)
+        .map_err(|e| TriviaError::Validation(format!("invalid base64 embedding: {e}")))?;
+    if bytes.len() != DIMENSIONS * 4 {
+        return Err(TriviaError::Validation(format!(
+            "embedding has {} bytes, expected {}",
+            bytes.len(),
+            DIMENSIONS * 4
+        )));
+    }
+    Ok(bytes
+        .chunks_exact(4)
+        .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect())
+}
+
+pub(crate) fn write_jsonl(path: &Path, records: &[ArchiveRecord]) -> Result<()> {
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = std::fs::File::create(path)
+        .with_context(|| format!("creating archive: {}", path.display()))?;
+    let mut out = BufWriter::new(file);
+    for record in records {
+        serde_json::to_writer(&mut out, record)?;
+        out.write_all(b"\n")?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+pub(crate) fn read_jsonl(path: &Path) -> Result<Vec<ArchiveRecord>> {
+    let file = std::fs::File::open(path)
+        .with_context(|| format!("opening archive: {}", path.display()))?;
+    let mut records = Vec::new();
+    for (i, line) in std::io::BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str(&line).map_err(|e| {
+            TriviaError::Validation(format!(
+                "{}:{}: invalid archive record: {e}",
+                path.display(),
+                i + 1
+            ))
+        })?;
+        records.push(record);
+    }
+    Ok(records)
+}
+
+pub(crate) fn vector_from_blob(blob: &[u8]) -> Vec<f32> {
+    blob.chunks_exact(4)
+        .map(|c| f32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect()
+}
+
+impl MemoryStore {
+    /// Full records for every memory (optionally those carrying any of
+    /// `tags`), ordered by title. Vectors are included when asked for.
+    pub fn archive_records(
+        &self,
+        tags: Option<&[String]>,
+        with_vectors: bool,
+    ) -> Result<Vec<ArchiveRecord>> {
+        let records = self.load_archive_records(None, with_vectors)?;
+        Ok(match tags.filter(|t| !t.is_empty()) {
+            Some(filter) => records
+                .into_iter()
+                .filter(|r| r.tags.iter().any(|t| filter.contains(t)))
+                .collect(),
+            None => records,
+        })
+    }
+
+    fn load_archive_records(
+        &self,
+        uuid: Option<&str>,
+        with_vectors: bool,
+    ) -> Result<Vec<ArchiveRecord>> {
+        let mut stmt = self.conn().prepare(
+            "SELECT id, uuid, title, content, tags, created_at, updated_at, recall_count,
+                    last_recalled_at, useful_count, not_useful_count, legacy_useful, legacy_not_useful
+             FROM memories
+             WHERE ?1 IS NULL OR uuid = ?1
+             ORDER BY title",
+        )?;
+        let rows = stmt
+            .query_map(params![uuid], |row| {
+                let tags_json: String = row.get(4)?;
+                let created_at: Option<String> = row.get(5)?;
+                let updated_at: Option<String> = row.get(6)?;
+                let last_recalled_at: Option<String> = row.get(8)?;
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    ArchiveRecord {
+                        uuid: row.get(1)?,
+                        title: row.get(2)?,
+                        content: row.get(3)?,
+                        tags: serde_json::from_str(&tags_json).unwrap_or_default(),
+                        created_at: parse_sqlite_datetime(
+                            created_at.as_deref().unwrap_or_default(),
+                        ),
+                        updated_at: parse_sqlite_datetime(
+                            updated_at.as_deref().unwrap_or_default(),
+                        ),
+                        recall_count: row.get(7)?,
+                        last_recalled_at: last_recalled_at.as_deref().map(parse_sqlite_datetime),
+                        useful_count: row.get(9)?,
+                        not_useful_count: row.get(10)?,
+                        legacy_useful: row.get(11)?,
+                        legacy_not_useful: row.get(12)?,
+                        mnemonics: Vec::new(),
+                        links: Vec::new(),
+                        ratings: Vec::new(),
+                        embedding_model: with_vectors
+                            .then(|| crate::embedder::MODEL_ID.to_string()),
+                    },
+                ))
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let mut mn_stmt = self.conn().prepare(
+            "SELECT mn.text, mn.created_at, v.embedding
+             FROM mnemonics mn
+             LEFT JOIN mnemonic_vectors v ON v.mnemonic_id = mn.id AND ?2
+             WHERE mn.memory_id = ?1
+             ORDER BY mn.id",
+        )?;
+        let mut link_stmt = self.conn().prepare(
+            "SELECT t.uuid, ml.link_type, ml.created_at
+             FROM memory_links ml
+             JOIN memories t ON t.id = ml.target_id
+             WHERE ml.source_id = ?1
+             ORDER BY ml.id",
+        )?;
+        let mut rating_stmt = self.conn().prepare(
+            "SELECT rater, useful, query, created_at FROM ratings WHERE memory_id = ?1 ORDER BY id",
+        )?;
+
+        let mut records = Vec::with_capacity(rows.len());
+        for (memory_id, mut record) in rows {
+            record.mnemonics = mn_stmt
+                .query_map(params![memory_id, with_vectors], |row| {
+                    let created_at: Option<String> = row.get(1)?;
+                    let blob: Option<Vec<u8>> = row.get(2)?;
+                    Ok(ArchiveMnemonic {
+                        text: row.get(0)?,
+                        created_at: parse_sqlite_datetime(
+                            created_at.as_deref().unwrap_or_default(),
+                        ),
+                        embedding: blob.map(|b| encode_vector(&vector_from_blob(&b))),
+                    })
+                })?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            record.links = link_stmt
+                .query_map(params![memory_id], |row| {
+                    let created_at: Option<String> = row.get(2)?;
+                    Ok(ArchiveLink {
+                        target: row.get(0)?,
+                        link_type: row.get(1)?,
+                        created_at: parse_sqlite_datetime(
+                            created_at.as_deref().unwrap_or_default(),
+                        ),
+                    })
+                })?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            record.ratings = rating_stmt
+                .query_map(params![memory_id], |row| {
+                    let created_at: String = row.get(3)?;
+                    Ok(ArchiveRating {
+                        rater: row.get(0)?,
+                        useful: row.get(1)?,
+                        query: row.get(2)?,
+                        created_at: parse_sqlite_datetime(&created_at),
+                    })
+                })?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            records.push(record);
+        }
+        Ok(records)
+    }
+
+    /// Which of `texts` exist as mnemonics that already have a vector.
+    pub fn mnemonics_with_vectors(&self, texts: &[String]) -> Result<HashSet<String>> {
+        let mut stmt = self.conn().prepare(
+            "SELECT COUNT(*) FROM mnemonics mn
+             JOIN mnemonic_vectors v ON v.mnemonic_id = mn.id
+             WHERE mn.text = ?1",
+        )?;
+        let mut found = HashSet::new();
+        for text in texts {
+            let count: i64 = stmt.query_row(params![text], |row| row.get(0))?;
+            if count > 0 {
+                found.insert(text.clone());
+            }
+        }
+        Ok(found)
+    }
+
+    /// Create or overwrite the memory identified by `record.uuid` with every
+    /// column from the record. Its mnemonics become exactly the record's;
+    /// `vectors` (keyed by mnemonic text) replace any existing embeddings.
+    /// Links are applied separately via `import_link`.
+    pub fn import_record(
+        &self,
+        record: &ArchiveRecord,
+        vectors: &HashMap<String, Vec<f32>>,
+    ) -> Result<ImportOutcome> {
+        let existing = self.load_archive_records(Some(&record.uuid), true)?.pop();
+        let outcome = match &existing {
+            Some(old) if record.is_unchanged_from(old) && old.has_vectors(vectors) => {
+                return Ok(ImportOutcome::Unchanged);
+            }
+            Some(_) => ImportOutcome::Updated,
+            None => ImportOutcome::Created,
+        };
+
+        let before = self.snapshot_by_uuid(&record.uuid)?;
+        let tx = self.conn().unchecked_transaction()?;
+        let tags_json = serde_json::to_string(&record.tags)?;
+        let created_at = format_sqlite_datetime(&record.created_at);
+        let updated_at = format_sqlite_datetime(&record.updated_at);
+        let last_recalled_at = record.last_recalled_at.as_ref().map(format_sqlite_datetime);
+        let (legacy_useful, legacy_not_useful) = record.legacy_counts();
+
+        let memory_id: i64 = match existing {
+            Some(_) => {
+                let id: i64 = tx.query_row(
+                    "SELECT id FROM memories WHERE uuid = ?1",
+                    params![record.uuid],
+                    |row| row.get(0),
+                )?;
+                tx.execute(
+                    "UPDATE memories SET title = ?1, mnemonic = ?1, content = ?2, tags = ?3,
+                        created_at = ?4, updated_at = ?5, recall_count = ?6, last_recalled_at = ?7,
+                        legacy_useful = ?8, legacy_not_useful = ?9
+                     WHERE id = ?10",
+                    params![
+                        record.title,
+                        record.content,
+                        tags_json,
+                        created_at,
+                        updated_at,
+                        record.recall_count,
+                        last_recalled_at,
+                        legacy_useful,
+                        legacy_not_useful,
+                        id
+                    ],
+                )
+                .with_context(|| format!("updating {}", record.title))?;
+                id
+            }
+            None => {
+                tx.execute(
+                    "INSERT INTO memories (uuid, mnemonic, title, content, tags, created_at, updated_at,
+                        recall_count, last_recalled_at, legacy_useful, legacy_not_useful)
+                     VALUES (?1, ?2, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                    params![
+                        record.uuid, record.title, record.content, tags_json, created_at, updated_at,
+                        record.recall_count, last_recalled_at, legacy_useful, legacy_not_useful
+                    ],
+                )
+                .with_context(|| format!("inserting {}", record.title))?;
+                tx.last_insert_rowid()
+            }
+        };
+
+        // Drop mnemonics the record no longer has
+        let wanted: HashSet<&str> = record.mnemonics.iter().map(|m| m.text.as_str()).collect();
+        let current: Vec<(i64, String)> = tx
+            .prepare("SELECT id, text FROM mnemonics WHERE memory_id = ?1")?
+            .query_map(params![memory_id], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        for (mn_id, text) in current {
+            if !wanted.contains(text.as_str()) {
+                tx.execute(
+                    "DELETE FROM mnemonic_vectors WHERE mnemonic_id = ?1",
+                    params![mn_id],
+                )?;
+                tx.execute("DELETE FROM mnemonics WHERE id = ?1", params![mn_id])?;
+            }
+        }
+
+        for mn in &record.mnemonics {
+            // A mnemonic owned by another memory stays where it is
+            tx.execute(
+                "INSERT OR IGNORE INTO mnemonics (memory_id, text, created_at) VALUES (?1, ?2, ?3)",
+                params![memory_id, mn.text, format_sqlite_datetime(&mn.created_at)],
+            )?;
+            let mn_id: Option<i64> = tx
+                .query_row(
+                    "SELECT id FROM mnemonics WHERE text = ?1 AND memory_id = ?2",
+                    params![mn.text, memory_id],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            let Some(mn_id) = mn_id else { continue };
+            tx.execute(
+                "UPDATE mnemonics SET created_at = ?1 WHERE id = ?2",
+                params![format_sqlite_datetime(&mn.created_at), mn_id],
+            )?;
+            if let Some(vector) = vectors.get(&mn.text) {
+                tx.execute(
+                    "DELETE FROM mnemonic_vectors WHERE mnemonic_id = ?1",
+                    params![mn_id],
+                )?;
+                tx.execute(
+                    "INSERT INTO mnemonic_vectors (mnemonic_id, embedding) VALUES (?1, ?2)",
+                    params![mn_id, vector.as_bytes()],
+                )?;
+            }
+        }
+
+        // The record's ratings replace any already here
+        tx.execute(
+            "DELETE FROM ratings WHERE memory_id = ?1",
+            params![memory_id],
+        )?;
+        for rating in &record.ratings {
+            tx.execute(
+                "INSERT INTO ratings (memory_id, rater, useful, query, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![
+                    memory_id, rating.rater, rating.useful, rating.query,
+                    format_sqlite_datetime(&rating.created_at)
+                ],
+            )?;
+        }
+        self.refresh_rating_counts(memory_id)?;
+
+        self.audit_change("memory.import", memory_id, before)?;
+        tx.commit()?;
+        Ok(outcome)
+    }
+}
+
+/// Resolve the vectors an import should write for `record`: the archived ones
+/// when they came from this build's model, otherwise fresh embeddings for
+/// mnemonics that have none yet. `has_vector` reports mnemonics that already
+/// have one in the target store.
+pub(crate) fn vectors_for_import(
+    record: &ArchiveRecord,
+    has_vector: &HashSet<String>,
+    embed: &mut dyn FnMut(&str) -> Result<Vec<f32>>,
+) -> Result<HashMap<String, Vec<f32>>> {
+    let reusable = record.embedding_model.as_deref() == Some(crate::embedder::MODEL_ID);
+    let mut vectors = HashMap::new();
+    for mn in &record.mnemonics {
+        match (&mn.embedding, reusable) {
+            (Some(encoded), true) => {
+                let vector = decode_vector(encoded)
+                    .with_context(|| format!("mnemonic {:?} of {}", mn.text, record.uuid))?;
+                vectors.insert(mn.text.clone(), vector);
+            }
+            // Archived without a vector on purpose (e.g. absorbed by a merge)
+            (None, true) => {}
+            _ if has_vector.contains(&mn.text) => {}
+            _ => {
+                vectors.insert(mn.text.clone(), embed(&mn.text)?);
+            }
+        }
+    }
+    Ok(vectors)
+}
+
+pub(crate) fn no_embedder(_: &str) -> Result<Vec<f32>> {
+    Err(TriviaError::Embedding(anyhow::anyhow!(
+        "archive has no vectors for {}; an embedder is required",
+        crate::embedder::MODEL_ID
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+    use tempfile::TempDir;
+
+    fn emb(seed: f32) -> Vec<f32> {
+        (0..DIMENSIONS).map(|i| seed + i as f32 / 1000.0).collect()
+    }
+
+    fn store_with_history() -> Result<MemoryStore> {
+        let store = MemoryStore::in_memory()?;
+        store.memorize("project design", "layered", &["arch".into()], &emb(0.1))?;
+        store.memorize("api endpoints", "REST", &["api".into()], &emb(-0.9))?;
+        store.add_mnemonic("project design", "architecture", &emb(0.5))?;
+        store.link("project design", "api endpoints", "derived_from")?;
+        store.rate("project design", true, None, None)?;
+        store.rate("api endpoints", false, None, None)?;
+        store.recall(&emb(0.1), 1, None, None, None)?;
+        Ok(store)
+    }
+
+    #[test]
+    fn test_vector_roundtrip() -> Result<()> {
+        let v = emb(0.25);
+        assert_eq!(decode_vector(&encode_vector(&v))?, v);
+        assert!(decode_vector(&encode_vector(&v[..10])).is_err());
+        assert!(decode_vector("not base64!").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_jsonl_roundtrip_is_lossless() -> Result<()> {
+        let source = store_with_history()?;
+        let dir = TempDir::new()?;
+        let path = dir.path().join("archive.jsonl");
+        assert_eq!(source.export_jsonl(&path, None, true, &|_, _| true)?, 2);
+        assert_eq!(std::fs::read_to_string(&path)?.lines().count(), 2);
+
+        let target = MemoryStore::in_memory()?;
+        let result = target.import_jsonl(&path, None)?;
+        assert_eq!(result.created, 2);
+
+        assert_eq!(
+            target.archive_records(None, true)?,
+            source.archive_records(None, true)?
+        );
+        let mem = target.get_memory_by_mnemonic("project design")?.unwrap();
+        assert_eq!(mem.useful_count, 1);
+        assert_eq!(mem.recall_count, 1);
+        assert_eq!(mem.links[0].link_type, "derived_from");
+
+        let again = target.import_jsonl(&path, None)?;
+        assert_eq!((again.created, again.updated, again.unchanged), (0, 0, 2));
+
+        // Imported vectors are searchable without re-embedding
+        let found = target.recall(&emb(0.5), 1, None, None, None)?.memories;
+        assert_eq!(found[0].mnemonic, "project design");
+        Ok(())
+    }
+
+    #[test]
+    fn test_import_overwrites_stats_and_aliases() -> Result<()> {
+        let source = store_with_history()?;
+        let dir = TempDir::new()?;
+        let path = dir.path().join("archive.jsonl");
+        source.export_jsonl(&path, Some(&["arch".into()]), false, &|_, _| true)?;
+
+        let target = MemoryStore::in_memory()?;
+        target.import_jsonl(&path, None).unwrap_err();
+
+        // Without vectors, existing ones are kept and only new aliases need embedding
+        target.import_jsonl_with(&path, &mut |_| Ok(emb(0.3)))?;
+        target.add_mnemonic("project design", "stale alias", &emb(0.7))?;
+        target.rate("project design", true, None, None)?;
+
+        let result = target.import_jsonl(&path, None)?;
+        assert_eq!(result.updated, 1);
+        let mem = target.get_memory_by_mnemonic("project design")?.unwrap();
+        assert_eq!(mem.useful_count, 1);
+        assert_eq!(mem.mnemonics, vec!["project design", "architecture"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_legacy_counters_import_as_legacy_counts() -> Result<()> {
+        let source = store_with_history()?;
+        let mut records = source.archive_records(Some(&["arch".into()]), true)?;
+        assert_eq!(records[0].ratings.len(), 1);
+        records[0].ratings.clear();
+        records[0].useful_count = 4;
+        records[0].not_useful_count = 1;
+        let dir = TempDir::new()?;
+        let path = dir.path().join("archive.jsonl");
+        write_jsonl(&path, &records)?;
+
+        let target = MemoryStore::in_memory()?;
+        target.import_jsonl(&path, None)?;
+        let imported = target.archive_records(None, false)?;
+        assert!(imported[0].ratings.is_empty());
+        assert_eq!(
+            (imported[0].legacy_useful, imported[0].legacy_not_useful),
+            (4, 1)
+        );
+        assert_eq!(
+            (imported[0].useful_count, imported[0].not_useful_count),
+            (4, 1)
+        );
+
+        // And survive another round trip alongside attributed ratings
+        target.rate(&imported[0].title, false, None, None)?;
+        write_jsonl(&path, &target.archive_records(None, true)?)?;
+        let again = MemoryStore::in_memory()?;
+        again.import_jsonl(&path, None)?;
+        let record = &again.archive_records(None, false)?[0];
+        assert_eq!((record.legacy_useful, record.legacy_not_useful), (4, 1));
+        assert_eq!((record.useful_count, record.not_useful_count), (4, 2));
+        Ok(())
+    }
+
+    #[test]
+    fn test_foreign_model_vectors_are_ignored() -> Result<()> {
+        let source = store_with_history()?;
+        let mut records = source.archive_records(Some(&["api".into()]), true)?;
+        records[0].embedding_model = Some("some-other-model".into());
+        let dir = TempDir::new()?;
+        let path = dir.path().join("archive.jsonl");
+        write_jsonl(&path, &records)?;
+
+        let mut embedded = Vec::new();
+        MemoryStore::in_memory()?.import_jsonl_with(&path, &mut |text| {
+            embedded.push(text.to_string());
+            Ok(emb(0.0))
+        })?;
+        assert_eq!(embedded, vec!["api endpoints"]);
+        Ok(())
+    }
+}
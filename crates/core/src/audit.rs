@@ -0,0 +1,474 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{OptionalExtension, params, params_from_iter};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::auth_store::parse_dt;
+use crate::error::{Result, TriviaError};
+use crate::store::{MemoryStore, format_sqlite_datetime};
+
+/// One recorded change, e.g. `user.set_acl` on `alice` by `root`, or
+/// `memory.edit` on a memory's UUID.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    /// Who made the change; `None` when it wasn't made by a signed-in user.
+    pub actor: Option<String>,
+    pub action: String,
+    pub target: Option<String>,
+    pub detail: Option<String>,
+    /// How the change came in: `cli`, `stdio`, `http` or `web`. `None` for
+    /// entries recorded before transports were tracked.
+    #[serde(default)]
+    pub transport: Option<String>,
+}
+
+/// The way a change reached the store.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    /// The `trivia` command line.
+    #[default]
+    Cli,
+    /// The MCP server over stdio.
+    Stdio,
+    /// The MCP server over HTTP.
+    Http,
+    /// The web UI and its REST API.
+    Web,
+}
+
+impl Transport {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Cli => "cli",
+            Self::Stdio => "stdio",
+            Self::Http => "http",
+            Self::Web => "web",
+        }
+    }
+}
+
+impl fmt::Display for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for Transport {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "cli" => Ok(Self::Cli),
+            "stdio" => Ok(Self::Stdio),
+            "http" => Ok(Self::Http),
+            "web" => Ok(Self::Web),
+            other => Err(format!(
+                "unknown transport '{other}' (expected cli, stdio, http or web)"
+            )),
+        }
+    }
+}
+
+/// Who changes made through a store are attributed to. Set with
+/// `Storage::set_actor`; memory mutations record it in the audit log.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Actor {
+    pub username: Option<String>,
+    pub transport: Transport,
+}
+
+impl Actor {
+    pub fn new(username: Option<&str>, transport: Transport) -> Self {
+        Self {
+            username: username.map(str::to_string),
+            transport,
+        }
+    }
+}
+
+/// What the audit log records about a memory on either side of a change.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemorySnapshot {
+    pub uuid: String,
+    pub title: String,
+    pub tags: Vec<String>,
+    pub content_chars: usize,
+}
+
+impl MemorySnapshot {
+    pub(crate) fn new(uuid: String, title: String, tags: Vec<String>, content: &str) -> Self {
+        Self {
+            uuid,
+            title,
+            tags,
+            content_chars: content.chars().count(),
+        }
+    }
+}
+
+impl fmt::Display for MemorySnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} [{}] ({} chars)",
+            self.title,
+            self.tags.join(", "),
+            self.content_chars
+        )
+    }
+}
+
+/// `before: …; after: …`, leaving out whichever side is missing.
+pub(crate) fn change_detail(
+    before: Option<&MemorySnapshot>,
+    after: Option<&MemorySnapshot>,
+) -> Option<String> {
+    match (before, after) {
+        (Some(b), Some(a)) => Some(format!("before: {b}; after: {a}")),
+        (Some(b), None) => Some(format!("before: {b}")),
+        (None, Some(a)) => Some(format!("after: {a}")),
+        (None, None) => None,
+    }
+}
+
+/// Which entries `list_audit` returns. `action` matches itself and anything
+/// beneath it, so `memory` covers `memory.edit`, `memory.delete` and so on.
+/// `memory` selects the history of the memory currently titled so; a deleted
+/// memory's history is found by its UUID with `target`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    pub action: Option<String>,
+    pub target: Option<String>,
+    pub memory: Option<String>,
+    pub transport: Option<Transport>,
+    pub since: Option<DateTime<Utc>>,
+    pub limit: usize,
+}
+
+impl Default for AuditFilter {
+    fn default() -> Self {
+        Self::latest(100)
+    }
+}
+
+impl AuditFilter {
+    /// The most recent `limit` entries, unfiltered.
+    pub fn latest(limit: usize) -> Self {
+        Self {
+            actor: None,
+            action: None,
+            target: None,
+            memory: None,
+            transport: None,
+            since: None,
+            limit,
+        }
+    }
+}
+
+/// Parse a `since` bound: an RFC 3339 timestamp or a `YYYY-MM-DD` date,
+/// taken as midnight UTC.
+pub fn parse_since(s: &str) -> std::result::Result<DateTime<Utc>, String> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+    chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
+        .map(|date| date.and_time(chrono::NaiveTime::MIN).and_utc())
+        .map_err(|_| format!("invalid time '{s}' (expected YYYY-MM-DD or an RFC 3339 timestamp)"))
+}
+
+const AUDIT_COLUMNS: &str = "id, created_at, actor, action, target, detail, transport";
+
+impl MemoryStore {
+    /// Record `action` on `target`, attributed to `actor` and the store's
+    /// current transport.
+    pub fn record_audit(
+        &self,
+        actor: Option<&str>,
+        action: &str,
+        target: Option<&str>,
+        detail: Option<&str>,
+    ) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO audit_log (actor, action, target, detail, transport) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![actor, action, target, detail, self.actor.transport.as_str()],
+        )?;
+        Ok(())
+    }
+
+    /// Record a change to the memory `uuid` on behalf of the current actor.
+    pub(crate) fn audit_memory(
+        &self,
+        action: &str,
+        uuid: &str,
+        detail: Option<&str>,
+    ) -> Result<()> {
+        self.record_audit(self.actor.username.as_deref(), action, Some(uuid), detail)
+    }
+
+    /// Record `action` on memory `memory_id`, comparing its current state
+    /// with `before`.
+    pub(crate) fn audit_change(
+        &self,
+        action: &str,
+        memory_id: i64,
+        before: Option<MemorySnapshot>,
+    ) -> Result<()> {
+        let after = self.snapshot_by_id(memory_id)?;
+        if let Some(uuid) = after.as_ref().or(before.as_ref()).map(|m| m.uuid.clone()) {
+            let detail = change_detail(before.as_ref(), after.as_ref());
+            self.audit_memory(action, &uuid, detail.as_deref())?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn snapshot_by_id(&self, id: i64) -> Result<Option<MemorySnapshot>> {
+        self.snapshot_where("id = ?1", &id)
+    }
+
+    pub(crate) fn snapshot_by_title(&self, title: &str) -> Result<Option<MemorySnapshot>> {
+        self.snapshot_where("title = ?1", &title)
+    }
+
+    pub(crate) fn snapshot_by_uuid(&self, uuid: &str) -> Result<Option<MemorySnapshot>> {
+        self.snapshot_where("uuid = ?1", &uuid)
+    }
+
+    fn snapshot_where(
+        &self,
+        condition: &str,
+        value: &dyn rusqlite::ToSql,
+    ) -> Result<Option<MemorySnapshot>> {
+        let row: Option<(String, String, String, String)> = self
+            .conn()
+            .query_row(
+                &format!("SELECT uuid, title, tags, content FROM memories WHERE {condition}"),
+                [value],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            )
+            .optional()?;
+        Ok(row.map(|(uuid, title, tags_json, content)| {
+            let tags = serde_json::from_str(&tags_json).unwrap_or_default();
+            MemorySnapshot::new(uuid, title, tags, &content)
+        }))
+    }
+
+    /// Entries matching `filter`, newest first.
+    pub fn list_audit(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+        if filter.limit == 0 {
+            return Err(TriviaError::validation("limit must be at least 1"));
+        }
+        let mut conditions = Vec::new();
+        let mut args: Vec<String> = Vec::new();
+        if let Some(actor) = &filter.actor {
+            args.push(actor.clone());
+            conditions.push(format!("actor = ?{}", args.len()));
+        }
+        if let Some(action) = &filter.action {
+            args.push(action.clone());
+            args.push(format!("{action}."));
+            let (exact, prefix) = (args.len() - 1, args.len());
+            conditions.push(format!(
+                "(action = ?{exact} OR substr(action, 1, length(?{prefix})) = ?{prefix})"
+            ));
+        }
+        if let Some(target) = &filter.target {
+            args.push(target.clone());
+            conditions.push(format!("target = ?{}", args.len()));
+        }
+        if let Some(memory) = &filter.memory {
+            args.push(memory.clone());
+            conditions.push(format!(
+                "target = (SELECT uuid FROM memories WHERE title = ?{})",
+                args.len()
+            ));
+        }
+        if let Some(transport) = filter.transport {
+            args.push(transport.as_str().to_string());
+            conditions.push(format!("transport = ?{}", args.len()));
+        }
+        if let Some(since) = &filter.since {
+            args.push(format_sqlite_datetime(since));
+            conditions.push(format!("created_at >= ?{}", args.len()));
+        }
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {AUDIT_COLUMNS} FROM audit_log {where_clause} ORDER BY id DESC LIMIT {}",
+            filter.limit
+        ))?;
+        let entries = stmt
+            .query_map(params_from_iter(args.iter()), |row| {
+                Ok(AuditEntry {
+                    id: row.get(0)?,
+                    created_at: parse_dt(&row.get::<_, String>(1)?),
+                    actor: row.get(2)?,
+                    action: row.get(3)?,
+                    target: row.get(4)?,
+                    detail: row.get(5)?,
+                    transport: row.get(6)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn emb(seed: f32) -> Vec<f32> {
+        (0..384).map(|i| ((i as f32) * seed).sin()).collect()
+    }
+
+    #[test]
+    fn audit_newest_first() -> Result<()> {
+        let store = MemoryStore::in_memory()?;
+        store.record_audit(
+            Some("root"),
+            "user.create",
+            Some("alice"),
+            Some("acl: *:read"),
+        )?;
+        store.record_audit(None, "provider.disable", Some("github"), None)?;
+
+        let entries = store.list_audit(&AuditFilter::latest(10))?;
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].action, "provider.disable");
+        assert_eq!(entries[0].actor, None);
+        assert_eq!(entries[1].actor.as_deref(), Some("root"));
+        assert_eq!(entries[1].detail.as_deref(), Some("acl: *:read"));
+        assert_eq!(entries[1].transport.as_deref(), Some("cli"));
+        assert_eq!(store.list_audit(&AuditFilter::latest(1))?.len(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn memory_mutations_are_attributed() -> Result<()> {
+        let mut store = MemoryStore::in_memory()?;
+        store.set_actor(Actor::new(Some("alice"), Transport::Http));
+        store.memorize("rust errors", "use thiserror", &["rust".into()], &emb(1.0))?;
+        store.update_memory(
+            "rust errors",
+            "use thiserror and anyhow",
+            &["rust".into()],
+            &emb(1.0),
+        )?;
+        store.rate("rust errors", true, None, None)?;
+
+        let previous = store.set_actor(Actor::new(Some("bob"), Transport::Web));
+        assert_eq!(previous, Actor::new(Some("alice"), Transport::Http));
+        store.delete_memory("rust errors")?;
+
+        let entries = store.list_audit(&AuditFilter::latest(10))?;
+        let actions: Vec<&str> = entries.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(
+            actions,
+            [
+                "memory.delete",
+                "memory.rate",
+                "memory.update",
+                "memory.create"
+            ]
+        );
+
+        let uuid = entries[3].target.clone().unwrap();
+        assert!(
+            entries
+                .iter()
+                .all(|e| e.target.as_deref() == Some(uuid.as_str()))
+        );
+        assert_eq!(entries[3].actor.as_deref(), Some("alice"));
+        assert_eq!(entries[3].transport.as_deref(), Some("http"));
+        assert_eq!(
+            entries[2].detail.as_deref(),
+            Some(
+                "before: \"rust errors\" [rust] (13 chars); after: \"rust errors\" [rust] (24 chars)"
+            )
+        );
+        assert_eq!(entries[1].detail.as_deref(), Some("useful"));
+        assert_eq!(entries[0].actor.as_deref(), Some("bob"));
+        assert_eq!(entries[0].transport.as_deref(), Some("web"));
+        assert_eq!(
+            entries[0].detail.as_deref(),
+            Some("before: \"rust errors\" [rust] (24 chars)")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn audit_filters() -> Result<()> {
+        let mut store = MemoryStore::in_memory()?;
+        store.set_actor(Actor::new(Some("alice"), Transport::Stdio));
+        store.memorize("first", "one", &[], &emb(1.0))?;
+        store.memorize("second", "two", &[], &emb(5.0))?;
+        store.link("first", "second", "related")?;
+        store.set_actor(Actor::default());
+        store.record_audit(Some("root"), "memoryless.action", None, None)?;
+
+        let by_action = |action: &str| AuditFilter {
+            action: Some(action.into()),
+            ..AuditFilter::default()
+        };
+        assert_eq!(store.list_audit(&by_action("memory"))?.len(), 3);
+        assert_eq!(store.list_audit(&by_action("memory.link"))?.len(), 1);
+        assert_eq!(store.list_audit(&by_action("memoryless"))?.len(), 1);
+
+        let by_transport = AuditFilter {
+            transport: Some(Transport::Stdio),
+            ..AuditFilter::default()
+        };
+        assert_eq!(store.list_audit(&by_transport)?.len(), 3);
+        let by_actor = AuditFilter {
+            actor: Some("root".into()),
+            ..AuditFilter::default()
+        };
+        assert_eq!(store.list_audit(&by_actor)?.len(), 1);
+
+        let first = store.snapshot_by_title("first")?.unwrap();
+        let by_target = AuditFilter {
+            target: Some(first.uuid),
+            ..AuditFilter::default()
+        };
+        let history = store.list_audit(&by_target)?;
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].detail.as_deref(), Some("related -> \"second\""));
+        let by_memory = AuditFilter {
+            memory: Some("first".into()),
+            ..AuditFilter::default()
+        };
+        assert_eq!(store.list_audit(&by_memory)?.len(), 2);
+
+        let future = AuditFilter {
+            since: Some(Utc::now() + chrono::Duration::hours(1)),
+            ..AuditFilter::default()
+        };
+        assert!(store.list_audit(&future)?.is_empty());
+        assert!(store.list_audit(&AuditFilter::latest(0)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn since_accepts_dates_and_timestamps() {
+        assert_eq!(
+            parse_since("2026-03-01").unwrap().to_rfc3339(),
+            "2026-03-01T00:00:00+00:00"
+        );
+        assert_eq!(
+            parse_since("2026-03-01T12:30:00+02:00")
+                .unwrap()
+                .to_rfc3339(),
+            "2026-03-01T10:30:00+00:00"
+        );
+        assert!(parse_since("yesterday").is_err());
+    }
+}
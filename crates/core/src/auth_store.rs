@@ -1,11 +1,13 @@
-use anyhow::{Result, anyhow};
+use crate::error::{Result, TriviaError};
 use chrono::{DateTime, Duration, Utc};
 use rand::Rng;
-use rusqlite::params;
+use rusqlite::{OptionalExtension, params};
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
-use crate::store::MemoryStore;
+use crate::config::AuthConfig;
+use crate::secrets::{self, SecretKey};
+use crate::store::{MemoryStore, format_sqlite_datetime};
 
 // --- Data types ---
 
@@ -14,10 +16,33 @@ pub struct User {
     pub id: i64,
     pub username: String,
     pub acl: String,
+    /// May use the `/api/admin` endpoints.
+    pub is_admin: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// A named set of ACL rules. Members get the union of their own ACL and the
+/// ACLs of every group they belong to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Group {
+    pub id: i64,
+    pub name: String,
+    pub acl: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Sign-in state of a user, for `trivia admin show-user`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UserActivity {
+    /// Unexpired web sessions.
+    pub sessions: i64,
+    /// Issued OAuth token pairs. Refresh tokens don't expire, so these count
+    /// until revoked.
+    pub tokens: i64,
+    pub last_seen_at: Option<DateTime<Utc>>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OAuthProvider {
     pub id: i64,
@@ -56,6 +81,67 @@ pub struct OAuthCode {
     pub code_challenge: String,
     pub redirect_uri: String,
     pub expires_at: DateTime<Utc>,
+    /// Space-separated OAuth scopes granted, passed on to the tokens.
+    pub scope: Option<String>,
+}
+
+/// A sign-in sent to an upstream provider and not yet back, kept server-side
+/// under the random `state` the provider echoes to the callback. Each can be
+/// taken once, within `PENDING_LOGIN_MINUTES`.
+#[derive(Debug, Clone)]
+pub struct PendingLogin {
+    /// Name of the provider the user was sent to.
+    pub provider: String,
+    /// The client authorization the sign-in completes; `None` for web UI
+    /// sign-ins.
+    pub authorization: Option<PendingAuthorization>,
+    /// What the provider needs back when the code is exchanged.
+    pub secrets: LoginSecrets,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Per-login values generated along with the authorization URL and needed
+/// again at the callback. Empty for providers that don't use them.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct LoginSecrets {
+    /// The upstream PKCE verifier.
+    pub code_verifier: Option<String>,
+    /// The OIDC nonce the ID token must carry.
+    pub nonce: Option<String>,
+}
+
+/// An MCP client's `/oauth/authorize` request, resumed once the user has
+/// signed in upstream.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingAuthorization {
+    pub client_id: String,
+    pub redirect_uri: String,
+    pub code_challenge: String,
+    /// Space-separated OAuth scopes requested.
+    pub scope: String,
+    /// The client's own `state`, handed back with the code.
+    pub client_state: String,
+}
+
+/// An authorization waiting for its user to approve the client on the
+/// consent page, kept under the single-use token in the page's form.
+#[derive(Debug, Clone)]
+pub struct PendingConsent {
+    pub user_id: i64,
+    pub authorization: PendingAuthorization,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// A client a user has approved, remembered so they aren't asked again
+/// unless it wants more.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientConsent {
+    pub client_id: String,
+    pub client_name: Option<String>,
+    pub redirect_uris: Vec<String>,
+    /// Space-separated OAuth scopes approved.
+    pub scope: String,
+    pub created_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone)]
@@ -63,18 +149,60 @@ pub struct TokenPair {
     pub access_token: String,
     pub refresh_token: String,
     pub expires_at: DateTime<Utc>,
+    pub scope: Option<String>,
+}
+
+/// Who an access or refresh token was issued to, and for what.
+#[derive(Debug, Clone)]
+pub struct TokenGrant {
+    pub user: User,
+    pub client_id: String,
+    /// `None` for tokens issued before scopes were recorded.
+    pub scope: Option<String>,
+    /// When the access token expires.
+    pub expires_at: DateTime<Utc>,
+    pub refresh_expires_at: DateTime<Utc>,
+    pub issued_at: DateTime<Utc>,
 }
 
 #[derive(Debug, Clone)]
 pub struct Session {
     pub session_id: String,
     pub user_id: i64,
+    /// When the session ends unless used again; each use pushes this out by
+    /// the idle timeout, up to `max_expires_at`.
     pub expires_at: DateTime<Utc>,
+    /// When the session ends regardless of use.
+    pub max_expires_at: DateTime<Utc>,
+}
+
+/// A named, long-lived bearer token a user holds for scripts and CI jobs.
+/// Only a hash of the secret is stored; it is shown once, on creation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PersonalToken {
+    pub id: i64,
+    pub user_id: i64,
+    /// Unique per user; used to list and revoke the token.
+    pub name: String,
+    /// Space-separated OAuth scopes, capping the user's access as for
+    /// OAuth tokens.
+    pub scope: String,
+    /// `None` for tokens that last until revoked.
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub last_used_at: Option<DateTime<Utc>>,
 }
 
+/// How long a user has to complete a sign-in with the upstream provider.
+pub const PENDING_LOGIN_MINUTES: i64 = 10;
+
+/// Every personal access token starts with this, so bearer tokens can be
+/// told apart from OAuth access tokens without a lookup.
+pub const PERSONAL_TOKEN_PREFIX: &str = "trivia_pat_";
+
 // --- Helpers ---
 
-fn generate_random_string(len: usize) -> String {
+pub(crate) fn generate_random_string(len: usize) -> String {
     let mut rng = rand::thread_rng();
     (0..len)
         .map(|_| {
@@ -94,12 +222,132 @@ pub fn sha256_hex(input: &str) -> String {
     format!("{:x}", hasher.finalize())
 }
 
-fn parse_dt(s: &str) -> DateTime<Utc> {
+pub(crate) fn parse_dt(s: &str) -> DateTime<Utc> {
     chrono::NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
         .map(|ndt| ndt.and_utc())
         .unwrap_or_default()
 }
 
+const USER_COLUMNS: &str = "u.id, u.username, u.acl, u.is_admin, u.created_at, u.updated_at";
+const GRANT_COLUMNS: &str =
+    "t.client_id, t.scope, t.expires_at, t.refresh_expires_at, t.created_at";
+const PROVIDER_COLUMNS: &str =
+    "id, name, provider_type, client_id, client_secret, enabled, config, created_at";
+const CONSENT_COLUMNS: &str = "c.client_id, c.client_name, c.redirect_uris, o.scope, o.created_at";
+const PERSONAL_TOKEN_COLUMNS: &str =
+    "p.id, p.user_id, p.name, p.scope, p.expires_at, p.created_at, p.last_used_at";
+
+fn user_from_row(row: &rusqlite::Row, offset: usize) -> rusqlite::Result<User> {
+    Ok(User {
+        id: row.get(offset)?,
+        username: row.get(offset + 1)?,
+        acl: row.get(offset + 2)?,
+        is_admin: row.get(offset + 3)?,
+        created_at: parse_dt(&row.get::<_, String>(offset + 4)?),
+        updated_at: parse_dt(&row.get::<_, String>(offset + 5)?),
+    })
+}
+
+/// A `TokenGrant` from `{GRANT_COLUMNS}, {USER_COLUMNS}`.
+fn grant_from_row(row: &rusqlite::Row) -> rusqlite::Result<TokenGrant> {
+    Ok(TokenGrant {
+        client_id: row.get(0)?,
+        scope: row.get(1)?,
+        expires_at: parse_dt(&row.get::<_, String>(2)?),
+        refresh_expires_at: parse_dt(&row.get::<_, String>(3)?),
+        issued_at: parse_dt(&row.get::<_, String>(4)?),
+        user: user_from_row(row, 5)?,
+    })
+}
+
+/// Issue a token pair. `auth_time` is when the user authorized the client;
+/// refreshing never extends the pair past `refresh_token_max` from then.
+fn insert_token_pair(
+    conn: &rusqlite::Connection,
+    auth: &AuthConfig,
+    client_id: &str,
+    user_id: i64,
+    scope: Option<&str>,
+    auth_time: DateTime<Utc>,
+) -> Result<TokenPair> {
+    let access_token = generate_random_string(48);
+    let refresh_token = generate_random_string(48);
+    let now = Utc::now();
+    let refresh_expires_at =
+        (now + auth.refresh_token_ttl()).min(auth_time + auth.refresh_token_max());
+    let expires_at = (now + auth.access_token_ttl()).min(refresh_expires_at);
+
+    conn.execute(
+        "INSERT INTO oauth_tokens
+            (access_token_hash, refresh_token_hash, client_id, user_id, expires_at, scope,
+             refresh_expires_at, auth_time)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        params![
+            sha256_hex(&access_token),
+            sha256_hex(&refresh_token),
+            client_id,
+            user_id,
+            format_sqlite_datetime(&expires_at),
+            scope,
+            format_sqlite_datetime(&refresh_expires_at),
+            format_sqlite_datetime(&auth_time)
+        ],
+    )?;
+
+    Ok(TokenPair {
+        access_token,
+        refresh_token,
+        expires_at,
+        scope: scope.map(String::from),
+    })
+}
+
+/// An `OAuthProvider` as stored, its client secret possibly still sealed.
+fn provider_from_row(row: &rusqlite::Row) -> rusqlite::Result<OAuthProvider> {
+    Ok(OAuthProvider {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        provider_type: row.get(2)?,
+        client_id: row.get(3)?,
+        client_secret: row.get(4)?,
+        enabled: row.get::<_, i64>(5)? != 0,
+        config: row.get(6)?,
+        created_at: parse_dt(&row.get::<_, String>(7)?),
+    })
+}
+
+fn consent_from_row(row: &rusqlite::Row) -> rusqlite::Result<ClientConsent> {
+    let uris_json: String = row.get(2)?;
+    Ok(ClientConsent {
+        client_id: row.get(0)?,
+        client_name: row.get(1)?,
+        redirect_uris: serde_json::from_str(&uris_json).unwrap_or_default(),
+        scope: row.get(3)?,
+        created_at: parse_dt(&row.get::<_, String>(4)?),
+    })
+}
+
+fn personal_token_from_row(row: &rusqlite::Row) -> rusqlite::Result<PersonalToken> {
+    Ok(PersonalToken {
+        id: row.get(0)?,
+        user_id: row.get(1)?,
+        name: row.get(2)?,
+        scope: row.get(3)?,
+        expires_at: row.get::<_, Option<String>>(4)?.as_deref().map(parse_dt),
+        created_at: parse_dt(&row.get::<_, String>(5)?),
+        last_used_at: row.get::<_, Option<String>>(6)?.as_deref().map(parse_dt),
+    })
+}
+
+fn group_from_row(row: &rusqlite::Row) -> rusqlite::Result<Group> {
+    Ok(Group {
+        id: row.get(0)?,
+        name: row.get(1)?,
+        acl: row.get(2)?,
+        created_at: parse_dt(&row.get::<_, String>(3)?),
+    })
+}
+
 // --- MemoryStore auth methods ---
 
 impl MemoryStore {
@@ -111,42 +359,26 @@ impl MemoryStore {
             params![username, acl],
         )?;
         self.get_user_by_username(username)?
-            .ok_or_else(|| anyhow!("failed to create user"))
+            .ok_or_else(|| TriviaError::storage("failed to create user"))
     }
 
     pub fn get_user_by_username(&self, username: &str) -> Result<Option<User>> {
-        let mut stmt = self.conn().prepare(
-            "SELECT id, username, acl, created_at, updated_at FROM users WHERE username = ?1",
-        )?;
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {USER_COLUMNS} FROM users u WHERE u.username = ?1"
+        ))?;
         let user = stmt
-            .query_row(params![username], |row| {
-                Ok(User {
-                    id: row.get(0)?,
-                    username: row.get(1)?,
-                    acl: row.get(2)?,
-                    created_at: parse_dt(&row.get::<_, String>(3)?),
-                    updated_at: parse_dt(&row.get::<_, String>(4)?),
-                })
-            })
-            .ok();
+            .query_row(params![username], |row| user_from_row(row, 0))
+            .optional()?;
         Ok(user)
     }
 
     pub fn get_user_by_id(&self, id: i64) -> Result<Option<User>> {
-        let mut stmt = self.conn().prepare(
-            "SELECT id, username, acl, created_at, updated_at FROM users WHERE id = ?1",
-        )?;
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {USER_COLUMNS} FROM users u WHERE u.id = ?1"
+        ))?;
         let user = stmt
-            .query_row(params![id], |row| {
-                Ok(User {
-                    id: row.get(0)?,
-                    username: row.get(1)?,
-                    acl: row.get(2)?,
-                    created_at: parse_dt(&row.get::<_, String>(3)?),
-                    updated_at: parse_dt(&row.get::<_, String>(4)?),
-                })
-            })
-            .ok();
+            .query_row(params![id], |row| user_from_row(row, 0))
+            .optional()?;
         Ok(user)
     }
 
@@ -156,25 +388,20 @@ impl MemoryStore {
             params![acl, username],
         )?;
         if rows == 0 {
-            return Err(anyhow!("user not found: {}", username));
+            return Err(TriviaError::NotFound(format!(
+                "user not found: {}",
+                username
+            )));
         }
         Ok(())
     }
 
     pub fn list_users(&self) -> Result<Vec<User>> {
-        let mut stmt = self
-            .conn()
-            .prepare("SELECT id, username, acl, created_at, updated_at FROM users ORDER BY username")?;
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {USER_COLUMNS} FROM users u ORDER BY u.username"
+        ))?;
         let users = stmt
-            .query_map([], |row| {
-                Ok(User {
-                    id: row.get(0)?,
-                    username: row.get(1)?,
-                    acl: row.get(2)?,
-                    created_at: parse_dt(&row.get::<_, String>(3)?),
-                    updated_at: parse_dt(&row.get::<_, String>(4)?),
-                })
-            })?
+            .query_map([], |row| user_from_row(row, 0))?
             .collect::<std::result::Result<Vec<_>, _>>()?;
         Ok(users)
     }
@@ -186,6 +413,216 @@ impl MemoryStore {
         Ok(rows > 0)
     }
 
+    pub fn set_user_admin(&self, username: &str, is_admin: bool) -> Result<()> {
+        let rows = self.conn().execute(
+            "UPDATE users SET is_admin = ?1, updated_at = datetime('now') WHERE username = ?2",
+            params![is_admin, username],
+        )?;
+        if rows == 0 {
+            return Err(TriviaError::NotFound(format!(
+                "user not found: {}",
+                username
+            )));
+        }
+        Ok(())
+    }
+
+    /// Record that the user just made an authenticated request. Writes at
+    /// most once a minute per user.
+    pub fn touch_user(&self, user_id: i64) -> Result<()> {
+        self.conn().execute(
+            "UPDATE users SET last_seen_at = datetime('now')
+             WHERE id = ?1 AND (last_seen_at IS NULL OR last_seen_at < datetime('now', '-1 minute'))",
+            params![user_id],
+        )?;
+        Ok(())
+    }
+
+    pub fn user_activity(&self, user_id: i64) -> Result<UserActivity> {
+        let activity = self.conn().query_row(
+            "SELECT
+                (SELECT COUNT(*) FROM sessions WHERE user_id = ?1 AND expires_at > datetime('now')),
+                (SELECT COUNT(*) FROM oauth_tokens WHERE user_id = ?1),
+                (SELECT last_seen_at FROM users WHERE id = ?1)",
+            params![user_id],
+            |row| {
+                Ok(UserActivity {
+                    sessions: row.get(0)?,
+                    tokens: row.get(1)?,
+                    last_seen_at: row.get::<_, Option<String>>(2)?.as_deref().map(parse_dt),
+                })
+            },
+        )?;
+        Ok(activity)
+    }
+
+    // ========== Groups ==========
+
+    pub fn create_group(&self, name: &str, acl: &str) -> Result<Group> {
+        self.conn().execute(
+            "INSERT INTO groups (name, acl) VALUES (?1, ?2)",
+            params![name, acl],
+        )?;
+        self.get_group_by_name(name)?
+            .ok_or_else(|| TriviaError::storage("failed to create group"))
+    }
+
+    pub fn get_group_by_name(&self, name: &str) -> Result<Option<Group>> {
+        let mut stmt = self
+            .conn()
+            .prepare("SELECT id, name, acl, created_at FROM groups WHERE name = ?1")?;
+        let group = stmt.query_row(params![name], group_from_row).optional()?;
+        Ok(group)
+    }
+
+    pub fn list_groups(&self) -> Result<Vec<Group>> {
+        let mut stmt = self
+            .conn()
+            .prepare("SELECT id, name, acl, created_at FROM groups ORDER BY name")?;
+        let groups = stmt
+            .query_map([], group_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(groups)
+    }
+
+    pub fn update_group_acl(&self, name: &str, acl: &str) -> Result<()> {
+        let rows = self.conn().execute(
+            "UPDATE groups SET acl = ?1 WHERE name = ?2",
+            params![acl, name],
+        )?;
+        if rows == 0 {
+            return Err(TriviaError::NotFound(format!("group not found: {}", name)));
+        }
+        Ok(())
+    }
+
+    /// Delete a group. Its members keep their own ACL and other groups.
+    pub fn delete_group(&self, name: &str) -> Result<bool> {
+        let rows = self
+            .conn()
+            .execute("DELETE FROM groups WHERE name = ?1", params![name])?;
+        Ok(rows > 0)
+    }
+
+    /// Add `username` to `group`. Adding an existing member is a no-op.
+    pub fn add_group_member(&self, group: &str, username: &str) -> Result<()> {
+        let group = self
+            .get_group_by_name(group)?
+            .ok_or_else(|| TriviaError::NotFound(format!("group not found: {}", group)))?;
+        let user = self
+            .get_user_by_username(username)?
+            .ok_or_else(|| TriviaError::NotFound(format!("user not found: {}", username)))?;
+        self.conn().execute(
+            "INSERT OR IGNORE INTO user_groups (user_id, group_id) VALUES (?1, ?2)",
+            params![user.id, group.id],
+        )?;
+        Ok(())
+    }
+
+    /// Remove `username` from `group`. Returns false if they weren't a member.
+    pub fn remove_group_member(&self, group: &str, username: &str) -> Result<bool> {
+        let rows = self.conn().execute(
+            "DELETE FROM user_groups
+             WHERE group_id = (SELECT id FROM groups WHERE name = ?1)
+               AND user_id = (SELECT id FROM users WHERE username = ?2)",
+            params![group, username],
+        )?;
+        Ok(rows > 0)
+    }
+
+    pub fn list_group_members(&self, group: &str) -> Result<Vec<User>> {
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {USER_COLUMNS}
+                 FROM users u
+                 JOIN user_groups ug ON ug.user_id = u.id
+                 JOIN groups g ON g.id = ug.group_id
+                 WHERE g.name = ?1
+                 ORDER BY u.username"
+        ))?;
+        let users = stmt
+            .query_map(params![group], |row| user_from_row(row, 0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(users)
+    }
+
+    pub fn groups_for_user(&self, user_id: i64) -> Result<Vec<Group>> {
+        let mut stmt = self.conn().prepare(
+            "SELECT g.id, g.name, g.acl, g.created_at
+             FROM groups g
+             JOIN user_groups ug ON ug.group_id = g.id
+             WHERE ug.user_id = ?1
+             ORDER BY g.name",
+        )?;
+        let groups = stmt
+            .query_map(params![user_id], group_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(groups)
+    }
+
+    // ========== Ownership ==========
+
+    /// Make `username` the owner of the memory titled `title`, or clear its
+    /// owner with `None`.
+    pub fn set_owner(&self, title: &str, username: Option<&str>) -> Result<()> {
+        let owner_id = match username {
+            Some(name) => Some(
+                self.get_user_by_username(name)?
+                    .ok_or_else(|| TriviaError::NotFound(format!("user not found: {}", name)))?
+                    .id,
+            ),
+            None => None,
+        };
+        let previous: Option<(String, Option<String>)> = self
+            .conn()
+            .query_row(
+                "SELECT m.uuid, u.username FROM memories m LEFT JOIN users u ON u.id = m.owner_id
+                 WHERE m.title = ?1",
+                params![title],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((uuid, previous_owner)) = previous else {
+            return Err(TriviaError::NotFound(format!(
+                "memory not found: {}",
+                title
+            )));
+        };
+        self.conn().execute(
+            "UPDATE memories SET owner_id = ?1 WHERE title = ?2",
+            params![owner_id, title],
+        )?;
+        let detail = format!(
+            "owner: {} -> {}",
+            previous_owner.as_deref().unwrap_or("none"),
+            username.unwrap_or("none")
+        );
+        self.audit_memory("memory.set_owner", &uuid, Some(&detail))
+    }
+
+    /// Hand every memory owned by `from` to `to`. Returns how many moved.
+    pub fn transfer_ownership(&self, from: &str, to: &str) -> Result<usize> {
+        let from = self
+            .get_user_by_username(from)?
+            .ok_or_else(|| TriviaError::NotFound(format!("user not found: {}", from)))?;
+        let to = self
+            .get_user_by_username(to)?
+            .ok_or_else(|| TriviaError::NotFound(format!("user not found: {}", to)))?;
+        let uuids: Vec<String> = self
+            .conn()
+            .prepare("SELECT uuid FROM memories WHERE owner_id = ?1")?
+            .query_map(params![from.id], |row| row.get(0))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let rows = self.conn().execute(
+            "UPDATE memories SET owner_id = ?1 WHERE owner_id = ?2",
+            params![to.id, from.id],
+        )?;
+        let detail = format!("owner: {} -> {}", from.username, to.username);
+        for uuid in &uuids {
+            self.audit_memory("memory.set_owner", uuid, Some(&detail))?;
+        }
+        Ok(rows)
+    }
+
     // ========== OAuth Providers ==========
 
     pub fn create_provider(
@@ -194,57 +631,70 @@ impl MemoryStore {
         provider_type: &str,
         client_id: &str,
         client_secret: &str,
+        config: &str,
     ) -> Result<OAuthProvider> {
+        let stored_secret = secrets::seal(self.secret_key.as_ref(), name, client_secret)?;
         self.conn().execute(
-            "INSERT INTO oauth_providers (name, provider_type, client_id, client_secret) VALUES (?1, ?2, ?3, ?4)",
-            params![name, provider_type, client_id, client_secret],
+            "INSERT INTO oauth_providers (name, provider_type, client_id, client_secret, config)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![name, provider_type, client_id, stored_secret, config],
         )?;
         self.get_provider_by_name(name)?
-            .ok_or_else(|| anyhow!("failed to create provider"))
+            .ok_or_else(|| TriviaError::storage("failed to create provider"))
     }
 
-    pub fn get_provider_by_name(&self, name: &str) -> Result<Option<OAuthProvider>> {
-        let mut stmt = self.conn().prepare(
-            "SELECT id, name, provider_type, client_id, client_secret, enabled, config, created_at
-             FROM oauth_providers WHERE name = ?1",
+    /// A provider with its client secret decrypted.
+    fn reveal_provider(&self, mut provider: OAuthProvider) -> Result<OAuthProvider> {
+        provider.client_secret = secrets::reveal(
+            self.secret_key.as_ref(),
+            &provider.name,
+            &provider.client_secret,
         )?;
-        let provider = stmt
-            .query_row(params![name], |row| {
-                Ok(OAuthProvider {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    provider_type: row.get(2)?,
-                    client_id: row.get(3)?,
-                    client_secret: row.get(4)?,
-                    enabled: row.get::<_, i64>(5)? != 0,
-                    config: row.get(6)?,
-                    created_at: parse_dt(&row.get::<_, String>(7)?),
-                })
-            })
-            .ok();
         Ok(provider)
     }
 
+    pub fn get_provider_by_name(&self, name: &str) -> Result<Option<OAuthProvider>> {
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {PROVIDER_COLUMNS} FROM oauth_providers WHERE name = ?1"
+        ))?;
+        let provider = stmt
+            .query_row(params![name], provider_from_row)
+            .optional()?;
+        provider.map(|p| self.reveal_provider(p)).transpose()
+    }
+
     pub fn list_providers(&self) -> Result<Vec<OAuthProvider>> {
-        let mut stmt = self.conn().prepare(
-            "SELECT id, name, provider_type, client_id, client_secret, enabled, config, created_at
-             FROM oauth_providers ORDER BY name",
-        )?;
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {PROVIDER_COLUMNS} FROM oauth_providers ORDER BY name"
+        ))?;
         let providers = stmt
-            .query_map([], |row| {
-                Ok(OAuthProvider {
-                    id: row.get(0)?,
-                    name: row.get(1)?,
-                    provider_type: row.get(2)?,
-                    client_id: row.get(3)?,
-                    client_secret: row.get(4)?,
-                    enabled: row.get::<_, i64>(5)? != 0,
-                    config: row.get(6)?,
-                    created_at: parse_dt(&row.get::<_, String>(7)?),
-                })
-            })?
+            .query_map([], provider_from_row)?
             .collect::<std::result::Result<Vec<_>, _>>()?;
-        Ok(providers)
+        providers
+            .into_iter()
+            .map(|p| self.reveal_provider(p))
+            .collect()
+    }
+
+    /// Re-encrypt every provider client secret under `new_key`, including
+    /// any still stored in plaintext. The store's current key must be able
+    /// to read them. Returns how many secrets were rewritten.
+    pub fn reencrypt_provider_secrets(&self, new_key: &SecretKey) -> Result<usize> {
+        let tx = self.conn().unchecked_transaction()?;
+        let rows: Vec<(i64, String, String)> = {
+            let mut stmt = tx.prepare("SELECT id, name, client_secret FROM oauth_providers")?;
+            stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+                .collect::<std::result::Result<Vec<_>, _>>()?
+        };
+        for (id, name, stored) in &rows {
+            let plaintext = secrets::reveal(self.secret_key.as_ref(), name, stored)?;
+            tx.execute(
+                "UPDATE oauth_providers SET client_secret = ?1 WHERE id = ?2",
+                params![new_key.encrypt(name, &plaintext)?, id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(rows.len())
     }
 
     pub fn delete_provider(&self, name: &str) -> Result<bool> {
@@ -254,6 +704,22 @@ impl MemoryStore {
         Ok(rows > 0)
     }
 
+    /// Disabled providers are hidden from the login page and can't be used
+    /// to sign in; their linked identities are kept.
+    pub fn set_provider_enabled(&self, name: &str, enabled: bool) -> Result<()> {
+        let rows = self.conn().execute(
+            "UPDATE oauth_providers SET enabled = ?1 WHERE name = ?2",
+            params![enabled, name],
+        )?;
+        if rows == 0 {
+            return Err(TriviaError::NotFound(format!(
+                "provider not found: {}",
+                name
+            )));
+        }
+        Ok(())
+    }
+
     // ========== User Identities ==========
 
     pub fn link_identity(
@@ -276,23 +742,17 @@ impl MemoryStore {
         provider_id: i64,
         provider_user_id: &str,
     ) -> Result<Option<User>> {
-        let mut stmt = self.conn().prepare(
-            "SELECT u.id, u.username, u.acl, u.created_at, u.updated_at
-             FROM users u
-             JOIN user_identities ui ON u.id = ui.user_id
-             WHERE ui.provider_id = ?1 AND ui.provider_user_id = ?2",
-        )?;
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {USER_COLUMNS}
+                 FROM users u
+                 JOIN user_identities ui ON u.id = ui.user_id
+                 WHERE ui.provider_id = ?1 AND ui.provider_user_id = ?2"
+        ))?;
         let user = stmt
             .query_row(params![provider_id, provider_user_id], |row| {
-                Ok(User {
-                    id: row.get(0)?,
-                    username: row.get(1)?,
-                    acl: row.get(2)?,
-                    created_at: parse_dt(&row.get::<_, String>(3)?),
-                    updated_at: parse_dt(&row.get::<_, String>(4)?),
-                })
+                user_from_row(row, 0)
             })
-            .ok();
+            .optional()?;
         Ok(user)
     }
 
@@ -316,6 +776,14 @@ impl MemoryStore {
         Ok(identities)
     }
 
+    pub fn unlink_identity(&self, user_id: i64, provider_id: i64) -> Result<bool> {
+        let rows = self.conn().execute(
+            "DELETE FROM user_identities WHERE user_id = ?1 AND provider_id = ?2",
+            params![user_id, provider_id],
+        )?;
+        Ok(rows > 0)
+    }
+
     // ========== OAuth Clients (DCR) ==========
 
     pub fn register_client(
@@ -361,10 +829,48 @@ impl MemoryStore {
                     created_at: parse_dt(&row.get::<_, String>(3)?),
                 })
             })
-            .ok();
+            .optional()?;
         Ok(client)
     }
 
+    pub fn list_clients(&self) -> Result<Vec<OAuthClient>> {
+        let mut stmt = self.conn().prepare(
+            "SELECT client_id, redirect_uris, client_name, created_at
+             FROM oauth_clients ORDER BY created_at, client_id",
+        )?;
+        let clients = stmt
+            .query_map([], |row| {
+                let uris_json: String = row.get(1)?;
+                Ok(OAuthClient {
+                    client_id: row.get(0)?,
+                    redirect_uris: serde_json::from_str(&uris_json).unwrap_or_default(),
+                    client_name: row.get(2)?,
+                    created_at: parse_dt(&row.get::<_, String>(3)?),
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(clients)
+    }
+
+    /// Remove a registered client along with its codes and tokens.
+    pub fn delete_client(&self, client_id: &str) -> Result<bool> {
+        let tx = self.conn().unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM oauth_tokens WHERE client_id = ?1",
+            params![client_id],
+        )?;
+        tx.execute(
+            "DELETE FROM oauth_codes WHERE client_id = ?1",
+            params![client_id],
+        )?;
+        let rows = tx.execute(
+            "DELETE FROM oauth_clients WHERE client_id = ?1",
+            params![client_id],
+        )?;
+        tx.commit()?;
+        Ok(rows > 0)
+    }
+
     pub fn verify_client_secret(&self, client_id: &str, secret: &str) -> Result<bool> {
         let hash = sha256_hex(secret);
         let stored_hash: Option<String> = self
@@ -374,7 +880,7 @@ impl MemoryStore {
                 params![client_id],
                 |row| row.get(0),
             )
-            .ok();
+            .optional()?;
         Ok(stored_hash.as_deref() == Some(&hash))
     }
 
@@ -386,22 +892,22 @@ impl MemoryStore {
         user_id: i64,
         code_challenge: &str,
         redirect_uri: &str,
+        scope: Option<&str>,
     ) -> Result<String> {
         let code = generate_random_string(48);
-        let expires_at = Utc::now() + Duration::minutes(10);
-        let expires_str = expires_at.format("%Y-%m-%d %H:%M:%S").to_string();
+        let expires_str = format_sqlite_datetime(&(Utc::now() + self.auth.auth_code_ttl()));
 
         self.conn().execute(
-            "INSERT INTO oauth_codes (code, client_id, user_id, code_challenge, redirect_uri, expires_at)
-             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-            params![code, client_id, user_id, code_challenge, redirect_uri, expires_str],
+            "INSERT INTO oauth_codes (code, client_id, user_id, code_challenge, redirect_uri, expires_at, scope)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![code, client_id, user_id, code_challenge, redirect_uri, expires_str, scope],
         )?;
         Ok(code)
     }
 
     pub fn consume_auth_code(&self, code: &str) -> Result<OAuthCode> {
         let mut stmt = self.conn().prepare(
-            "SELECT code, client_id, user_id, code_challenge, redirect_uri, expires_at, used
+            "SELECT code, client_id, user_id, code_challenge, redirect_uri, expires_at, used, scope
              FROM oauth_codes WHERE code = ?1",
         )?;
         let row = stmt
@@ -414,18 +920,20 @@ impl MemoryStore {
                         code_challenge: row.get(3)?,
                         redirect_uri: row.get(4)?,
                         expires_at: parse_dt(&row.get::<_, String>(5)?),
+                        scope: row.get(7)?,
                     },
                     row.get::<_, i64>(6)?,
                 ))
             })
-            .map_err(|_| anyhow!("invalid authorization code"))?;
+            .optional()?
+            .ok_or_else(|| TriviaError::validation("invalid authorization code"))?;
 
         let (auth_code, used) = row;
         if used != 0 {
-            return Err(anyhow!("authorization code already used"));
+            return Err(TriviaError::validation("authorization code already used"));
         }
         if auth_code.expires_at < Utc::now() {
-            return Err(anyhow!("authorization code expired"));
+            return Err(TriviaError::validation("authorization code expired"));
         }
 
         self.conn().execute(
@@ -436,139 +944,491 @@ impl MemoryStore {
         Ok(auth_code)
     }
 
-    // ========== Tokens ==========
+    // ========== Pending sign-ins ==========
 
-    pub fn create_token_pair(&self, client_id: &str, user_id: i64) -> Result<TokenPair> {
-        let access_token = generate_random_string(48);
-        let refresh_token = generate_random_string(48);
-        let access_hash = sha256_hex(&access_token);
-        let refresh_hash = sha256_hex(&refresh_token);
-        let expires_at = Utc::now() + Duration::hours(24);
-        let expires_str = expires_at.format("%Y-%m-%d %H:%M:%S").to_string();
+    /// Record a sign-in about to be sent to `provider` with `secrets`,
+    /// returning the opaque `state` to send with it. Only its hash is stored.
+    pub fn create_pending_login(
+        &self,
+        provider: &str,
+        authorization: Option<&PendingAuthorization>,
+        secrets: &LoginSecrets,
+    ) -> Result<String> {
+        let state = generate_random_string(48);
+        let expires_str =
+            format_sqlite_datetime(&(Utc::now() + Duration::minutes(PENDING_LOGIN_MINUTES)));
+        let a = authorization;
+        self.conn().execute(
+            "INSERT INTO pending_logins (state_hash, provider, client_id, redirect_uri,
+                 code_challenge, scope, client_state, code_verifier, nonce, expires_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                sha256_hex(&state),
+                provider,
+                a.map(|a| &a.client_id),
+                a.map(|a| &a.redirect_uri),
+                a.map(|a| &a.code_challenge),
+                a.map(|a| &a.scope),
+                a.map(|a| &a.client_state),
+                secrets.code_verifier,
+                secrets.nonce,
+                expires_str
+            ],
+        )?;
+        Ok(state)
+    }
+
+    /// The sign-in recorded under `state`, removing it so the state can't
+    /// be replayed. Unknown, already used and expired states are errors.
+    pub fn take_pending_login(&self, state: &str) -> Result<PendingLogin> {
+        let login = self
+            .conn()
+            .query_row(
+                "DELETE FROM pending_logins WHERE state_hash = ?1
+                 RETURNING provider, client_id, redirect_uri, code_challenge, scope,
+                     client_state, code_verifier, nonce, expires_at",
+                params![sha256_hex(state)],
+                |row| {
+                    let client_id: Option<String> = row.get(1)?;
+                    let authorization = match client_id {
+                        Some(client_id) => Some(PendingAuthorization {
+                            client_id,
+                            redirect_uri: row.get(2)?,
+                            code_challenge: row.get(3)?,
+                            scope: row.get(4)?,
+                            client_state: row.get(5)?,
+                        }),
+                        None => None,
+                    };
+                    Ok(PendingLogin {
+                        provider: row.get(0)?,
+                        authorization,
+                        secrets: LoginSecrets {
+                            code_verifier: row.get(6)?,
+                            nonce: row.get(7)?,
+                        },
+                        expires_at: parse_dt(&row.get::<_, String>(8)?),
+                    })
+                },
+            )
+            .optional()?
+            .ok_or_else(|| TriviaError::validation("unknown or already used state"))?;
+        if login.expires_at < Utc::now() {
+            return Err(TriviaError::validation("sign-in expired; start again"));
+        }
+        Ok(login)
+    }
+
+    // ========== Consents ==========
 
+    /// Hold `authorization` until `user_id` answers the consent page,
+    /// returning the token for its form. Expires like a pending sign-in.
+    pub fn create_pending_consent(
+        &self,
+        user_id: i64,
+        authorization: &PendingAuthorization,
+    ) -> Result<String> {
+        let token = generate_random_string(48);
+        let expires_str =
+            format_sqlite_datetime(&(Utc::now() + Duration::minutes(PENDING_LOGIN_MINUTES)));
+        let a = authorization;
         self.conn().execute(
-            "INSERT INTO oauth_tokens (access_token_hash, refresh_token_hash, client_id, user_id, expires_at)
-             VALUES (?1, ?2, ?3, ?4, ?5)",
-            params![access_hash, refresh_hash, client_id, user_id, expires_str],
+            "INSERT INTO pending_consents (token_hash, user_id, client_id, redirect_uri,
+                 code_challenge, scope, client_state, expires_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                sha256_hex(&token),
+                user_id,
+                a.client_id,
+                a.redirect_uri,
+                a.code_challenge,
+                a.scope,
+                a.client_state,
+                expires_str
+            ],
         )?;
+        Ok(token)
+    }
 
-        Ok(TokenPair {
-            access_token,
-            refresh_token,
-            expires_at,
-        })
+    /// The authorization held under `token`, removing it so the form can't
+    /// be submitted twice.
+    pub fn take_pending_consent(&self, token: &str) -> Result<PendingConsent> {
+        let consent = self
+            .conn()
+            .query_row(
+                "DELETE FROM pending_consents WHERE token_hash = ?1
+                 RETURNING user_id, client_id, redirect_uri, code_challenge, scope,
+                     client_state, expires_at",
+                params![sha256_hex(token)],
+                |row| {
+                    Ok(PendingConsent {
+                        user_id: row.get(0)?,
+                        authorization: PendingAuthorization {
+                            client_id: row.get(1)?,
+                            redirect_uri: row.get(2)?,
+                            code_challenge: row.get(3)?,
+                            scope: row.get(4)?,
+                            client_state: row.get(5)?,
+                        },
+                        expires_at: parse_dt(&row.get::<_, String>(6)?),
+                    })
+                },
+            )
+            .optional()?
+            .ok_or_else(|| {
+                TriviaError::validation("unknown or already answered consent request")
+            })?;
+        if consent.expires_at < Utc::now() {
+            return Err(TriviaError::validation(
+                "consent request expired; start again",
+            ));
+        }
+        Ok(consent)
     }
 
-    pub fn get_user_by_access_token(&self, token: &str) -> Result<Option<User>> {
-        let hash = sha256_hex(token);
-        let mut stmt = self.conn().prepare(
-            "SELECT u.id, u.username, u.acl, u.created_at, u.updated_at
-             FROM users u
-             JOIN oauth_tokens t ON u.id = t.user_id
-             WHERE t.access_token_hash = ?1 AND t.expires_at > datetime('now')",
+    /// Remember that `user_id` approved `client_id` for `scope`, replacing
+    /// any earlier approval.
+    pub fn grant_consent(&self, user_id: i64, client_id: &str, scope: &str) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO oauth_consents (user_id, client_id, scope) VALUES (?1, ?2, ?3)
+             ON CONFLICT (user_id, client_id)
+             DO UPDATE SET scope = excluded.scope, created_at = datetime('now')",
+            params![user_id, client_id, scope],
         )?;
-        let user = stmt
-            .query_row(params![hash], |row| {
-                Ok(User {
-                    id: row.get(0)?,
-                    username: row.get(1)?,
-                    acl: row.get(2)?,
-                    created_at: parse_dt(&row.get::<_, String>(3)?),
-                    updated_at: parse_dt(&row.get::<_, String>(4)?),
-                })
-            })
-            .ok();
-        Ok(user)
+        Ok(())
     }
 
-    pub fn get_user_by_refresh_token(&self, token: &str) -> Result<Option<(User, String)>> {
-        let hash = sha256_hex(token);
-        let mut stmt = self.conn().prepare(
-            "SELECT u.id, u.username, u.acl, u.created_at, u.updated_at, t.client_id
-             FROM users u
-             JOIN oauth_tokens t ON u.id = t.user_id
-             WHERE t.refresh_token_hash = ?1",
+    pub fn get_consent(&self, user_id: i64, client_id: &str) -> Result<Option<ClientConsent>> {
+        let consent = self
+            .conn()
+            .query_row(
+                &format!(
+                    "SELECT {CONSENT_COLUMNS} FROM oauth_consents o
+                     JOIN oauth_clients c ON c.client_id = o.client_id
+                     WHERE o.user_id = ?1 AND o.client_id = ?2"
+                ),
+                params![user_id, client_id],
+                consent_from_row,
+            )
+            .optional()?;
+        Ok(consent)
+    }
+
+    pub fn list_consents(&self, user_id: i64) -> Result<Vec<ClientConsent>> {
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {CONSENT_COLUMNS} FROM oauth_consents o
+             JOIN oauth_clients c ON c.client_id = o.client_id
+             WHERE o.user_id = ?1 ORDER BY o.created_at, c.client_id"
+        ))?;
+        let consents = stmt
+            .query_map(params![user_id], consent_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(consents)
+    }
+
+    /// Withdraw `user_id`'s approval of `client_id` and revoke the tokens
+    /// it holds for them, so it has to ask again.
+    pub fn revoke_consent(&self, user_id: i64, client_id: &str) -> Result<bool> {
+        let tx = self.conn().unchecked_transaction()?;
+        tx.execute(
+            "DELETE FROM oauth_tokens WHERE user_id = ?1 AND client_id = ?2",
+            params![user_id, client_id],
         )?;
-        let result = stmt
-            .query_row(params![hash], |row| {
-                Ok((
-                    User {
-                        id: row.get(0)?,
-                        username: row.get(1)?,
-                        acl: row.get(2)?,
-                        created_at: parse_dt(&row.get::<_, String>(3)?),
-                        updated_at: parse_dt(&row.get::<_, String>(4)?),
-                    },
-                    row.get::<_, String>(5)?,
-                ))
-            })
-            .ok();
-        Ok(result)
+        let rows = tx.execute(
+            "DELETE FROM oauth_consents WHERE user_id = ?1 AND client_id = ?2",
+            params![user_id, client_id],
+        )?;
+        tx.commit()?;
+        Ok(rows > 0)
     }
 
-    pub fn revoke_refresh_token(&self, token: &str) -> Result<()> {
-        let hash = sha256_hex(token);
-        self.conn().execute(
+    // ========== Tokens ==========
+
+    pub fn create_token_pair(
+        &self,
+        client_id: &str,
+        user_id: i64,
+        scope: Option<&str>,
+    ) -> Result<TokenPair> {
+        insert_token_pair(
+            self.conn(),
+            &self.auth,
+            client_id,
+            user_id,
+            scope,
+            Utc::now(),
+        )
+    }
+
+    /// Exchange a refresh token for a new pair with the same client, user
+    /// and scope, revoking the old one. `None` if the refresh token is
+    /// unknown or expired.
+    pub fn refresh_token_pair(&self, refresh_token: &str) -> Result<Option<TokenPair>> {
+        let hash = sha256_hex(refresh_token);
+        let tx = self.conn().unchecked_transaction()?;
+        let old = tx
+            .query_row(
+                "SELECT client_id, user_id, scope, auth_time FROM oauth_tokens
+                 WHERE refresh_token_hash = ?1 AND refresh_expires_at > datetime('now')",
+                params![hash],
+                |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                        parse_dt(&row.get::<_, String>(3)?),
+                    ))
+                },
+            )
+            .optional()?;
+        let Some((client_id, user_id, scope, auth_time)) = old else {
+            return Ok(None);
+        };
+        tx.execute(
             "DELETE FROM oauth_tokens WHERE refresh_token_hash = ?1",
             params![hash],
         )?;
-        Ok(())
+        let pair = insert_token_pair(
+            &tx,
+            &self.auth,
+            &client_id,
+            user_id,
+            scope.as_deref(),
+            auth_time,
+        )?;
+        tx.commit()?;
+        Ok(Some(pair))
     }
 
-    pub fn cleanup_expired_tokens(&self) -> Result<usize> {
+    pub fn get_user_by_access_token(&self, token: &str) -> Result<Option<TokenGrant>> {
+        let hash = sha256_hex(token);
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {GRANT_COLUMNS}, {USER_COLUMNS}
+                 FROM users u
+                 JOIN oauth_tokens t ON u.id = t.user_id
+                 WHERE t.access_token_hash = ?1 AND t.expires_at > datetime('now')"
+        ))?;
+        let grant = stmt.query_row(params![hash], grant_from_row).optional()?;
+        Ok(grant)
+    }
+
+    pub fn get_user_by_refresh_token(&self, token: &str) -> Result<Option<TokenGrant>> {
+        let hash = sha256_hex(token);
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {GRANT_COLUMNS}, {USER_COLUMNS}
+                 FROM users u
+                 JOIN oauth_tokens t ON u.id = t.user_id
+                 WHERE t.refresh_token_hash = ?1 AND t.refresh_expires_at > datetime('now')"
+        ))?;
+        let grant = stmt.query_row(params![hash], grant_from_row).optional()?;
+        Ok(grant)
+    }
+
+    /// Revoke the token pair that `token` (access or refresh) belongs to.
+    /// Returns false if there was none.
+    pub fn revoke_token(&self, token: &str) -> Result<bool> {
+        let hash = sha256_hex(token);
         let rows = self.conn().execute(
-            "DELETE FROM oauth_tokens WHERE expires_at <= datetime('now')",
+            "DELETE FROM oauth_tokens WHERE access_token_hash = ?1 OR refresh_token_hash = ?1",
+            params![hash],
+        )?;
+        Ok(rows > 0)
+    }
+
+    /// Delete token pairs whose refresh token can no longer be used, and
+    /// expired personal access tokens.
+    pub fn cleanup_expired_tokens(&self) -> Result<usize> {
+        let pairs = self.conn().execute(
+            "DELETE FROM oauth_tokens
+             WHERE expires_at <= datetime('now') AND refresh_expires_at <= datetime('now')",
             [],
         )?;
-        Ok(rows)
+        let personal = self.conn().execute(
+            "DELETE FROM personal_tokens WHERE expires_at <= datetime('now')",
+            [],
+        )?;
+        Ok(pairs + personal)
+    }
+
+    /// Revoke every token issued to the user: OAuth access and refresh
+    /// tokens as well as personal access tokens.
+    pub fn revoke_user_tokens(&self, user_id: i64) -> Result<usize> {
+        let pairs = self.conn().execute(
+            "DELETE FROM oauth_tokens WHERE user_id = ?1",
+            params![user_id],
+        )?;
+        let personal = self.conn().execute(
+            "DELETE FROM personal_tokens WHERE user_id = ?1",
+            params![user_id],
+        )?;
+        Ok(pairs + personal)
+    }
+
+    // ========== Personal Access Tokens ==========
+
+    /// Create a personal access token, returning it with its secret. The
+    /// secret can't be recovered later.
+    pub fn create_personal_token(
+        &self,
+        user_id: i64,
+        name: &str,
+        scope: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(PersonalToken, String)> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(TriviaError::validation("token name must not be empty"));
+        }
+        let exists: bool = self.conn().query_row(
+            "SELECT EXISTS (SELECT 1 FROM personal_tokens WHERE user_id = ?1 AND name = ?2)",
+            params![user_id, name],
+            |row| row.get(0),
+        )?;
+        if exists {
+            return Err(TriviaError::conflict(format!(
+                "a token named '{name}' already exists"
+            )));
+        }
+
+        let secret = format!("{PERSONAL_TOKEN_PREFIX}{}", generate_random_string(40));
+        self.conn().execute(
+            "INSERT INTO personal_tokens (user_id, name, token_hash, scope, expires_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                user_id,
+                name,
+                sha256_hex(&secret),
+                scope,
+                expires_at.as_ref().map(format_sqlite_datetime)
+            ],
+        )?;
+        let token = self.conn().query_row(
+            &format!("SELECT {PERSONAL_TOKEN_COLUMNS} FROM personal_tokens p WHERE p.id = ?1"),
+            params![self.conn().last_insert_rowid()],
+            personal_token_from_row,
+        )?;
+        Ok((token, secret))
+    }
+
+    /// The user's personal access tokens, expired ones included, by name.
+    pub fn list_personal_tokens(&self, user_id: i64) -> Result<Vec<PersonalToken>> {
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {PERSONAL_TOKEN_COLUMNS} FROM personal_tokens p WHERE p.user_id = ?1 ORDER BY p.name"
+        ))?;
+        let tokens = stmt
+            .query_map(params![user_id], personal_token_from_row)?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(tokens)
+    }
+
+    /// Look up an unexpired personal access token and its user, recording
+    /// that it was used.
+    pub fn get_user_by_personal_token(&self, token: &str) -> Result<Option<(PersonalToken, User)>> {
+        let hash = sha256_hex(token);
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT {PERSONAL_TOKEN_COLUMNS}, {USER_COLUMNS}
+             FROM personal_tokens p
+             JOIN users u ON u.id = p.user_id
+             WHERE p.token_hash = ?1 AND (p.expires_at IS NULL OR p.expires_at > datetime('now'))"
+        ))?;
+        let mut found = stmt
+            .query_row(params![hash], |row| {
+                Ok((personal_token_from_row(row)?, user_from_row(row, 7)?))
+            })
+            .optional()?;
+        if let Some((token, _)) = &mut found {
+            let now = Utc::now();
+            self.conn().execute(
+                "UPDATE personal_tokens SET last_used_at = ?2 WHERE id = ?1",
+                params![token.id, format_sqlite_datetime(&now)],
+            )?;
+            token.last_used_at = Some(now);
+        }
+        Ok(found)
+    }
+
+    /// Revoke the user's personal access token called `name`. Returns false
+    /// if there was none.
+    pub fn delete_personal_token(&self, user_id: i64, name: &str) -> Result<bool> {
+        let rows = self.conn().execute(
+            "DELETE FROM personal_tokens WHERE user_id = ?1 AND name = ?2",
+            params![user_id, name],
+        )?;
+        Ok(rows > 0)
     }
 
     // ========== Sessions ==========
 
     pub fn create_session(&self, user_id: i64) -> Result<Session> {
         let session_id = generate_random_string(48);
-        let expires_at = Utc::now() + Duration::days(30);
-        let expires_str = expires_at.format("%Y-%m-%d %H:%M:%S").to_string();
+        let now = Utc::now();
+        let max_expires_at = now + self.auth.session_max();
+        let expires_at = (now + self.auth.session_idle()).min(max_expires_at);
 
         self.conn().execute(
-            "INSERT INTO sessions (session_id, user_id, expires_at) VALUES (?1, ?2, ?3)",
-            params![session_id, user_id, expires_str],
+            "INSERT INTO sessions (session_id, user_id, expires_at, created_at) VALUES (?1, ?2, ?3, ?4)",
+            params![
+                session_id,
+                user_id,
+                format_sqlite_datetime(&expires_at),
+                format_sqlite_datetime(&now)
+            ],
         )?;
 
         Ok(Session {
             session_id,
             user_id,
             expires_at,
+            max_expires_at,
         })
     }
 
+    /// Look up a live session and its user, extending the session by the
+    /// idle timeout (but not past its absolute limit).
     pub fn get_session(&self, session_id: &str) -> Result<Option<(Session, User)>> {
-        let mut stmt = self.conn().prepare(
-            "SELECT s.session_id, s.user_id, s.expires_at,
-                    u.id, u.username, u.acl, u.created_at, u.updated_at
-             FROM sessions s
-             JOIN users u ON s.user_id = u.id
-             WHERE s.session_id = ?1 AND s.expires_at > datetime('now')",
-        )?;
-        let result = stmt
-            .query_row(params![session_id], |row| {
-                Ok((
-                    Session {
-                        session_id: row.get(0)?,
-                        user_id: row.get(1)?,
-                        expires_at: parse_dt(&row.get::<_, String>(2)?),
-                    },
-                    User {
-                        id: row.get(3)?,
-                        username: row.get(4)?,
-                        acl: row.get(5)?,
-                        created_at: parse_dt(&row.get::<_, String>(6)?),
-                        updated_at: parse_dt(&row.get::<_, String>(7)?),
-                    },
-                ))
-            })
-            .ok();
-        Ok(result)
+        let now = Utc::now();
+        let max = self.auth.session_max();
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT s.session_id, s.user_id, s.expires_at, s.created_at, {USER_COLUMNS}
+                 FROM sessions s
+                 JOIN users u ON s.user_id = u.id
+                 WHERE s.session_id = ?1 AND s.expires_at > ?2 AND s.created_at > ?3"
+        ))?;
+        let found = stmt
+            .query_row(
+                params![
+                    session_id,
+                    format_sqlite_datetime(&now),
+                    format_sqlite_datetime(&(now - max))
+                ],
+                |row| {
+                    Ok((
+                        Session {
+                            session_id: row.get(0)?,
+                            user_id: row.get(1)?,
+                            expires_at: parse_dt(&row.get::<_, String>(2)?),
+                            max_expires_at: parse_dt(&row.get::<_, String>(3)?) + max,
+                        },
+                        user_from_row(row, 4)?,
+                    ))
+                },
+            )
+            .optional()?;
+        let Some((mut session, user)) = found else {
+            return Ok(None);
+        };
+
+        // Renewing on every request would mean a write per request; a
+        // minute's slack is plenty for idle timeouts measured in hours
+        let renewed = (now + self.auth.session_idle()).min(session.max_expires_at);
+        if renewed - session.expires_at > Duration::minutes(1) {
+            self.conn().execute(
+                "UPDATE sessions SET expires_at = ?2 WHERE session_id = ?1",
+                params![session_id, format_sqlite_datetime(&renewed)],
+            )?;
+            session.expires_at = renewed;
+        }
+        Ok(Some((session, user)))
     }
 
     pub fn delete_session(&self, session_id: &str) -> Result<()> {
@@ -580,13 +1440,22 @@ impl MemoryStore {
     }
 
     pub fn cleanup_expired_sessions(&self) -> Result<usize> {
+        let cutoff = format_sqlite_datetime(&(Utc::now() - self.auth.session_max()));
         let rows = self.conn().execute(
-            "DELETE FROM sessions WHERE expires_at <= datetime('now')",
-            [],
+            "DELETE FROM sessions WHERE expires_at <= datetime('now') OR created_at <= ?1",
+            params![cutoff],
         )?;
         Ok(rows)
     }
 
+    /// Sign the user out of the web UI everywhere.
+    pub fn revoke_user_sessions(&self, user_id: i64) -> Result<usize> {
+        let rows = self
+            .conn()
+            .execute("DELETE FROM sessions WHERE user_id = ?1", params![user_id])?;
+        Ok(rows)
+    }
+
     // ========== Auth status ==========
 
     /// Returns true if any OAuth providers are configured and enabled.
@@ -610,12 +1479,22 @@ impl MemoryStore {
         Ok(providers)
     }
 
+    /// Delete expired authorization codes, and sign-ins and consent requests
+    /// that were abandoned.
     pub fn cleanup_expired_codes(&self) -> Result<usize> {
-        let rows = self.conn().execute(
+        let codes = self.conn().execute(
             "DELETE FROM oauth_codes WHERE expires_at <= datetime('now')",
             [],
         )?;
-        Ok(rows)
+        let logins = self.conn().execute(
+            "DELETE FROM pending_logins WHERE expires_at <= datetime('now')",
+            [],
+        )?;
+        let consents = self.conn().execute(
+            "DELETE FROM pending_consents WHERE expires_at <= datetime('now')",
+            [],
+        )?;
+        Ok(codes + logins + consents)
     }
 }
 
@@ -640,9 +1519,16 @@ mod tests {
         let fetched = store.get_user_by_id(user.id).unwrap().unwrap();
         assert_eq!(fetched.username, "alice");
 
-        store.update_user_acl("alice", "project:read,*:none").unwrap();
+        store
+            .update_user_acl("alice", "project:read,*:none")
+            .unwrap();
         let updated = store.get_user_by_username("alice").unwrap().unwrap();
         assert_eq!(updated.acl, "project:read,*:none");
+        assert!(!updated.is_admin);
+
+        store.set_user_admin("alice", true).unwrap();
+        assert!(store.get_user_by_id(user.id).unwrap().unwrap().is_admin);
+        assert!(store.set_user_admin("nobody", true).is_err());
 
         let users = store.list_users().unwrap();
         assert_eq!(users.len(), 1);
@@ -651,11 +1537,149 @@ mod tests {
         assert!(store.get_user_by_username("alice").unwrap().is_none());
     }
 
+    #[test]
+    fn group_membership() {
+        let store = test_store();
+        let alice = store.create_user("alice", "@alice:update").unwrap();
+        store.create_user("bob", "*:none").unwrap();
+        store.create_group("eng", "project-*:update").unwrap();
+        store.create_group("readers", "*:read").unwrap();
+        assert!(store.create_group("eng", "*:none").is_err());
+        store.update_group_acl("readers", "docs:read").unwrap();
+        assert_eq!(
+            store.get_group_by_name("readers").unwrap().unwrap().acl,
+            "docs:read"
+        );
+        assert!(store.update_group_acl("nobody", "*:read").is_err());
+
+        store.add_group_member("eng", "alice").unwrap();
+        store.add_group_member("eng", "alice").unwrap();
+        store.add_group_member("readers", "alice").unwrap();
+        store.add_group_member("readers", "bob").unwrap();
+        assert!(matches!(
+            store.add_group_member("eng", "nobody"),
+            Err(TriviaError::NotFound(_))
+        ));
+
+        let names: Vec<String> = store
+            .groups_for_user(alice.id)
+            .unwrap()
+            .into_iter()
+            .map(|g| g.name)
+            .collect();
+        assert_eq!(names, ["eng", "readers"]);
+        let members: Vec<String> = store
+            .list_group_members("readers")
+            .unwrap()
+            .into_iter()
+            .map(|u| u.username)
+            .collect();
+        assert_eq!(members, ["alice", "bob"]);
+
+        assert!(store.remove_group_member("readers", "alice").unwrap());
+        assert!(!store.remove_group_member("readers", "alice").unwrap());
+        assert!(store.delete_group("eng").unwrap());
+        assert!(store.groups_for_user(alice.id).unwrap().is_empty());
+
+        // Deleting a user drops their memberships
+        store.delete_user("bob").unwrap();
+        assert!(store.list_group_members("readers").unwrap().is_empty());
+        assert_eq!(store.list_groups().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn memory_ownership() {
+        let store = test_store();
+        store.create_user("alice", "*:read").unwrap();
+        store.create_user("bob", "*:read").unwrap();
+        let emb = vec![0.1; 384];
+        let result = store.memorize("note", "mine", &[], &emb).unwrap();
+        assert!(result.created);
+        assert!(
+            !store
+                .memorize("note", "still mine", &[], &emb)
+                .unwrap()
+                .created
+        );
+        assert_eq!(
+            store.get_memory_by_mnemonic("note").unwrap().unwrap().owner,
+            None
+        );
+
+        store.set_owner("note", Some("alice")).unwrap();
+        assert_eq!(
+            store
+                .get_memory_by_mnemonic("note")
+                .unwrap()
+                .unwrap()
+                .owner
+                .as_deref(),
+            Some("alice")
+        );
+        assert_eq!(
+            store.list_all_summaries().unwrap()[0].owner.as_deref(),
+            Some("alice")
+        );
+        assert!(matches!(
+            store.set_owner("missing", Some("alice")),
+            Err(TriviaError::NotFound(_))
+        ));
+        assert!(matches!(
+            store.set_owner("note", Some("nobody")),
+            Err(TriviaError::NotFound(_))
+        ));
+
+        assert_eq!(store.transfer_ownership("alice", "bob").unwrap(), 1);
+        assert_eq!(store.transfer_ownership("alice", "bob").unwrap(), 0);
+        assert_eq!(
+            store
+                .get_memory_by_mnemonic("note")
+                .unwrap()
+                .unwrap()
+                .owner
+                .as_deref(),
+            Some("bob")
+        );
+
+        // Deleting the owner leaves the memory unowned
+        store.delete_user("bob").unwrap();
+        assert_eq!(
+            store.get_memory_by_mnemonic("note").unwrap().unwrap().owner,
+            None
+        );
+    }
+
+    #[test]
+    fn memorize_sets_owner_atomically() {
+        let store = test_store();
+        store.create_user("alice", "*:read").unwrap();
+        let emb = vec![0.1; 384];
+        store
+            .memorize_with_options("note", "mine", &[], &emb, true, Some("alice"))
+            .unwrap();
+        assert_eq!(
+            store
+                .get_memory_by_mnemonic("note")
+                .unwrap()
+                .unwrap()
+                .owner
+                .as_deref(),
+            Some("alice")
+        );
+
+        // An owner that can't be set leaves no unowned memory behind
+        assert!(matches!(
+            store.memorize_with_options("other", "orphan", &[], &emb, true, Some("nobody")),
+            Err(TriviaError::NotFound(_))
+        ));
+        assert!(store.get_memory_by_mnemonic("other").unwrap().is_none());
+    }
+
     #[test]
     fn provider_crud() {
         let store = test_store();
         let prov = store
-            .create_provider("github", "github", "id123", "secret456")
+            .create_provider("github", "github", "id123", "secret456", "{}")
             .unwrap();
         assert_eq!(prov.name, "github");
         assert_eq!(prov.provider_type, "github");
@@ -666,6 +1690,17 @@ mod tests {
         let providers = store.list_providers().unwrap();
         assert_eq!(providers.len(), 1);
 
+        store.set_provider_enabled("github", false).unwrap();
+        assert!(
+            !store
+                .get_provider_by_name("github")
+                .unwrap()
+                .unwrap()
+                .enabled
+        );
+        assert!(!store.has_auth_providers().unwrap());
+        assert!(store.set_provider_enabled("gitlab", true).is_err());
+
         assert!(store.delete_provider("github").unwrap());
         assert!(store.get_provider_by_name("github").unwrap().is_none());
     }
@@ -675,7 +1710,7 @@ mod tests {
         let store = test_store();
         let user = store.create_user("bob", "*:read").unwrap();
         let prov = store
-            .create_provider("github", "github", "id", "secret")
+            .create_provider("github", "github", "id", "secret", "{}")
             .unwrap();
         store
             .link_identity(user.id, prov.id, "bobgithub", "12345")
@@ -690,6 +1725,56 @@ mod tests {
         let identities = store.list_identities_for_user(user.id).unwrap();
         assert_eq!(identities.len(), 1);
         assert_eq!(identities[0].provider_username, "bobgithub");
+
+        assert!(store.unlink_identity(user.id, prov.id).unwrap());
+        assert!(!store.unlink_identity(user.id, prov.id).unwrap());
+        assert!(
+            store
+                .get_user_by_provider_identity(prov.id, "12345")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn revoke_user_credentials() {
+        let store = test_store();
+        let user = store.create_user("carol", "*:read").unwrap();
+        let other = store.create_user("dan", "*:read").unwrap();
+        let (client, _) = store
+            .register_client(&["http://localhost/cb".into()], None)
+            .unwrap();
+        let pair = store
+            .create_token_pair(&client.client_id, user.id, None)
+            .unwrap();
+        store
+            .create_token_pair(&client.client_id, other.id, None)
+            .unwrap();
+        let session = store.create_session(user.id).unwrap();
+        store.create_session(user.id).unwrap();
+
+        assert!(store.user_activity(user.id).unwrap().last_seen_at.is_none());
+        store.touch_user(user.id).unwrap();
+        let activity = store.user_activity(user.id).unwrap();
+        assert_eq!((activity.sessions, activity.tokens), (2, 1));
+        assert!(activity.last_seen_at.is_some());
+
+        assert_eq!(store.revoke_user_sessions(user.id).unwrap(), 2);
+        assert_eq!(store.revoke_user_tokens(user.id).unwrap(), 1);
+        assert!(store.get_session(&session.session_id).unwrap().is_none());
+        assert!(
+            store
+                .get_user_by_access_token(&pair.access_token)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            store
+                .get_user_by_refresh_token(&pair.refresh_token)
+                .unwrap()
+                .is_none()
+        );
+        assert_eq!(store.user_activity(other.id).unwrap().tokens, 1);
     }
 
     #[test]
@@ -705,8 +1790,31 @@ mod tests {
         assert_eq!(fetched.client_name.as_deref(), Some("Test App"));
         assert_eq!(fetched.redirect_uris, vec!["http://localhost/callback"]);
 
-        assert!(store.verify_client_secret(&client.client_id, &secret).unwrap());
-        assert!(!store.verify_client_secret(&client.client_id, "wrong").unwrap());
+        assert!(
+            store
+                .verify_client_secret(&client.client_id, &secret)
+                .unwrap()
+        );
+        assert!(
+            !store
+                .verify_client_secret(&client.client_id, "wrong")
+                .unwrap()
+        );
+
+        let user = store.create_user("erin", "*:read").unwrap();
+        let pair = store
+            .create_token_pair(&client.client_id, user.id, None)
+            .unwrap();
+        assert_eq!(store.list_clients().unwrap().len(), 1);
+        assert!(store.delete_client(&client.client_id).unwrap());
+        assert!(store.list_clients().unwrap().is_empty());
+        assert!(
+            store
+                .get_user_by_access_token(&pair.access_token)
+                .unwrap()
+                .is_none()
+        );
+        assert!(!store.delete_client(&client.client_id).unwrap());
     }
 
     #[test]
@@ -718,7 +1826,13 @@ mod tests {
             .unwrap();
 
         let code = store
-            .create_auth_code(&client.client_id, user.id, "challenge123", "http://localhost/cb")
+            .create_auth_code(
+                &client.client_id,
+                user.id,
+                "challenge123",
+                "http://localhost/cb",
+                None,
+            )
             .unwrap();
         assert!(!code.is_empty());
 
@@ -730,6 +1844,141 @@ mod tests {
         assert!(store.consume_auth_code(&code).is_err());
     }
 
+    #[test]
+    fn pending_logins_are_single_use() {
+        let store = test_store();
+        let authorization = PendingAuthorization {
+            client_id: "client".into(),
+            redirect_uri: "http://localhost/cb".into(),
+            code_challenge: "challenge".into(),
+            scope: "memory:read".into(),
+            client_state: "xyz".into(),
+        };
+        let secrets = LoginSecrets {
+            code_verifier: Some("verifier".into()),
+            nonce: Some("nonce".into()),
+        };
+        let state = store
+            .create_pending_login("kc", Some(&authorization), &secrets)
+            .unwrap();
+        let web = store
+            .create_pending_login("github", None, &LoginSecrets::default())
+            .unwrap();
+        assert_ne!(state, web);
+
+        // Tampering with the state makes it unknown
+        let mut tampered = state.clone();
+        tampered.push('x');
+        assert!(store.take_pending_login(&tampered).is_err());
+
+        let login = store.take_pending_login(&state).unwrap();
+        assert_eq!(login.provider, "kc");
+        assert_eq!(login.authorization, Some(authorization));
+        assert_eq!(login.secrets, secrets);
+        assert!(store.take_pending_login(&state).is_err(), "replayed state");
+        assert!(
+            store
+                .take_pending_login(&web)
+                .unwrap()
+                .authorization
+                .is_none()
+        );
+
+        let stale = store
+            .create_pending_login("github", None, &LoginSecrets::default())
+            .unwrap();
+        store
+            .conn()
+            .execute(
+                "UPDATE pending_logins SET expires_at = datetime('now', '-1 minute')",
+                [],
+            )
+            .unwrap();
+        assert!(store.take_pending_login(&stale).is_err());
+
+        store
+            .create_pending_login("github", None, &LoginSecrets::default())
+            .unwrap();
+        store
+            .conn()
+            .execute(
+                "UPDATE pending_logins SET expires_at = datetime('now', '-1 minute')",
+                [],
+            )
+            .unwrap();
+        assert_eq!(store.cleanup_expired_codes().unwrap(), 1);
+    }
+
+    #[test]
+    fn consents_are_remembered_until_revoked() {
+        let store = test_store();
+        let user = store.create_user("erin", "*:read").unwrap();
+        let (client, _) = store
+            .register_client(&["http://localhost/cb".into()], Some("agent"))
+            .unwrap();
+        let authorization = PendingAuthorization {
+            client_id: client.client_id.clone(),
+            redirect_uri: "http://localhost/cb".into(),
+            code_challenge: "challenge".into(),
+            scope: "memory:read".into(),
+            client_state: "xyz".into(),
+        };
+        let token = store
+            .create_pending_consent(user.id, &authorization)
+            .unwrap();
+        let pending = store.take_pending_consent(&token).unwrap();
+        assert_eq!(
+            (pending.user_id, pending.authorization),
+            (user.id, authorization)
+        );
+        assert!(
+            store.take_pending_consent(&token).is_err(),
+            "answered twice"
+        );
+
+        assert!(
+            store
+                .get_consent(user.id, &client.client_id)
+                .unwrap()
+                .is_none()
+        );
+        store
+            .grant_consent(user.id, &client.client_id, "memory:read")
+            .unwrap();
+        store
+            .grant_consent(user.id, &client.client_id, "memory:read memory:write")
+            .unwrap();
+        let consents = store.list_consents(user.id).unwrap();
+        assert_eq!(consents.len(), 1);
+        assert_eq!(consents[0].client_name.as_deref(), Some("agent"));
+        assert_eq!(consents[0].scope, "memory:read memory:write");
+
+        let pair = store
+            .create_token_pair(&client.client_id, user.id, None)
+            .unwrap();
+        assert!(store.revoke_consent(user.id, &client.client_id).unwrap());
+        assert!(
+            store
+                .get_consent(user.id, &client.client_id)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            store
+                .get_user_by_access_token(&pair.access_token)
+                .unwrap()
+                .is_none()
+        );
+        assert!(!store.revoke_consent(user.id, &client.client_id).unwrap());
+
+        // Deleting the client takes its consents with it
+        store
+            .grant_consent(user.id, &client.client_id, "memory:read")
+            .unwrap();
+        store.delete_client(&client.client_id).unwrap();
+        assert!(store.list_consents(user.id).unwrap().is_empty());
+    }
+
     #[test]
     fn token_flow() {
         let store = test_store();
@@ -738,19 +1987,265 @@ mod tests {
             .register_client(&["http://localhost/cb".into()], None)
             .unwrap();
 
-        let pair = store.create_token_pair(&client.client_id, user.id).unwrap();
+        let pair = store
+            .create_token_pair(&client.client_id, user.id, Some("memory:read"))
+            .unwrap();
         assert!(!pair.access_token.is_empty());
 
         let found = store
             .get_user_by_access_token(&pair.access_token)
             .unwrap()
             .unwrap();
-        assert_eq!(found.username, "dave");
+        assert_eq!(found.user.username, "dave");
+        assert_eq!(found.scope.as_deref(), Some("memory:read"));
+        assert!(found.expires_at > found.issued_at);
+
+        // Revoking either half of the pair revokes both
+        assert!(store.revoke_token(&pair.refresh_token).unwrap());
+        assert!(
+            store
+                .get_user_by_access_token(&pair.access_token)
+                .unwrap()
+                .is_none()
+        );
+        assert!(!store.revoke_token(&pair.refresh_token).unwrap());
+
+        assert!(
+            store
+                .get_user_by_access_token("nonexistent")
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn refresh_rotation_and_lifetimes() {
+        let mut store = test_store();
+        store.set_auth_config(AuthConfig {
+            access_token_minutes: Some(60),
+            refresh_token_days: Some(30),
+            refresh_token_max_days: Some(45),
+            ..Default::default()
+        });
+        let user = store.create_user("dave", "*:read").unwrap();
+        let (client, _) = store
+            .register_client(&["http://localhost/cb".into()], None)
+            .unwrap();
+
+        let pair = store
+            .create_token_pair(&client.client_id, user.id, Some("memory:read"))
+            .unwrap();
+        let ttl = pair.expires_at - Utc::now();
+        assert!(ttl <= Duration::minutes(60) && ttl > Duration::minutes(59));
+
+        let rotated = store
+            .refresh_token_pair(&pair.refresh_token)
+            .unwrap()
+            .unwrap();
+        assert_eq!(rotated.scope.as_deref(), Some("memory:read"));
+        assert!(
+            store
+                .get_user_by_access_token(&pair.access_token)
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            store
+                .refresh_token_pair(&pair.refresh_token)
+                .unwrap()
+                .is_none()
+        );
+
+        // Authorized 40 days ago: the new refresh token stops at the 45-day cap
+        store
+            .conn()
+            .execute(
+                "UPDATE oauth_tokens SET auth_time = datetime('now', '-40 days')",
+                [],
+            )
+            .unwrap();
+        let capped = store
+            .refresh_token_pair(&rotated.refresh_token)
+            .unwrap()
+            .unwrap();
+        let grant = store
+            .get_user_by_refresh_token(&capped.refresh_token)
+            .unwrap()
+            .unwrap();
+        let left = grant.refresh_expires_at - Utc::now();
+        assert!(left <= Duration::days(5) && left > Duration::days(5) - Duration::minutes(1));
+
+        store
+            .conn()
+            .execute(
+                "UPDATE oauth_tokens SET refresh_expires_at = datetime('now', '-1 second')",
+                [],
+            )
+            .unwrap();
+        assert!(
+            store
+                .refresh_token_pair(&capped.refresh_token)
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn sessions_slide_until_their_limit() {
+        let mut store = test_store();
+        store.set_auth_config(AuthConfig {
+            session_idle_hours: Some(2),
+            session_max_days: Some(1),
+            ..Default::default()
+        });
+        let user = store.create_user("eve", "*:read").unwrap();
+        let session = store.create_session(user.id).unwrap();
+        assert!(session.expires_at - Utc::now() <= Duration::hours(2));
+
+        // Used again an hour later: pushed back out to two hours from now
+        store
+            .conn()
+            .execute(
+                "UPDATE sessions SET expires_at = datetime('now', '+1 hour')",
+                [],
+            )
+            .unwrap();
+        let (renewed, _) = store.get_session(&session.session_id).unwrap().unwrap();
+        assert!(renewed.expires_at - Utc::now() > Duration::minutes(119));
+
+        // Near the absolute limit, renewal stops there
+        store
+            .conn()
+            .execute(
+                "UPDATE sessions SET created_at = datetime('now', '-23 hours'),
+                                     expires_at = datetime('now', '+10 minutes')",
+                [],
+            )
+            .unwrap();
+        let (capped, _) = store.get_session(&session.session_id).unwrap().unwrap();
+        assert!(capped.expires_at - Utc::now() <= Duration::hours(1));
+        assert_eq!(capped.expires_at, capped.max_expires_at);
+
+        store
+            .conn()
+            .execute(
+                "UPDATE sessions SET created_at = datetime('now', '-25 hours')",
+                [],
+            )
+            .unwrap();
+        assert!(store.get_session(&session.session_id).unwrap().is_none());
+        assert_eq!(store.cleanup_expired_sessions().unwrap(), 1);
+    }
 
-        assert!(store
-            .get_user_by_access_token("nonexistent")
+    #[test]
+    fn provider_secrets_are_encrypted_at_rest() {
+        let mut store = test_store();
+        let stored = |store: &MemoryStore, name: &str| -> String {
+            store
+                .conn()
+                .query_row(
+                    "SELECT client_secret FROM oauth_providers WHERE name = ?1",
+                    params![name],
+                    |row| row.get(0),
+                )
+                .unwrap()
+        };
+        // Added before there was a key
+        store
+            .create_provider("legacy", "github", "id", "old-secret", "{}")
+            .unwrap();
+        assert_eq!(stored(&store, "legacy"), "old-secret");
+
+        let key = SecretKey::generate().unwrap();
+        store.set_secret_key(Some(key.clone()));
+        let created = store
+            .create_provider("gh", "github", "id", "hunter2", "{}")
+            .unwrap();
+        assert_eq!(created.client_secret, "hunter2");
+        assert!(secrets::is_encrypted(&stored(&store, "gh")));
+        assert_eq!(
+            store
+                .get_provider_by_name("legacy")
+                .unwrap()
+                .unwrap()
+                .client_secret,
+            "old-secret"
+        );
+
+        // Rotation rewrites every secret, plaintext ones included
+        let next = SecretKey::generate().unwrap();
+        assert_eq!(store.reencrypt_provider_secrets(&next).unwrap(), 2);
+        assert!(secrets::is_encrypted(&stored(&store, "legacy")));
+        assert!(store.get_provider_by_name("gh").is_err());
+        store.set_secret_key(Some(next));
+        let secrets: Vec<String> = store
+            .list_providers()
             .unwrap()
-            .is_none());
+            .into_iter()
+            .map(|p| p.client_secret)
+            .collect();
+        assert_eq!(secrets, ["hunter2", "old-secret"]);
+
+        // A sealed secret moved onto another provider's row doesn't decrypt
+        store
+            .conn()
+            .execute(
+                "UPDATE oauth_providers SET client_secret = ?1 WHERE name = 'legacy'",
+                params![stored(&store, "gh")],
+            )
+            .unwrap();
+        assert!(store.get_provider_by_name("legacy").is_err());
+        assert_eq!(
+            store
+                .get_provider_by_name("gh")
+                .unwrap()
+                .unwrap()
+                .client_secret,
+            "hunter2"
+        );
+
+        store.set_secret_key(None);
+        assert!(store.list_providers().is_err());
+    }
+
+    #[test]
+    fn personal_tokens_work_until_revoked_or_expired() {
+        let store = test_store();
+        let user = store.create_user("ci", "*:read").unwrap();
+        let (token, secret) = store
+            .create_personal_token(user.id, "pipeline", "memory:read", None)
+            .unwrap();
+        assert!(secret.starts_with(PERSONAL_TOKEN_PREFIX));
+        assert!(token.last_used_at.is_none());
+
+        let (used, owner) = store.get_user_by_personal_token(&secret).unwrap().unwrap();
+        assert_eq!(
+            (owner.username.as_str(), used.scope.as_str()),
+            ("ci", "memory:read")
+        );
+        assert!(used.last_used_at.is_some());
+        assert!(
+            store
+                .get_user_by_personal_token("trivia_pat_bogus")
+                .unwrap()
+                .is_none()
+        );
+
+        // Names are unique per user
+        let dup = store.create_personal_token(user.id, "pipeline", "memory:read", None);
+        assert!(matches!(dup, Err(TriviaError::Conflict(_))));
+
+        let expired = Utc::now() - Duration::minutes(1);
+        let (_, stale) = store
+            .create_personal_token(user.id, "old", "memory:read", Some(expired))
+            .unwrap();
+        assert!(store.get_user_by_personal_token(&stale).unwrap().is_none());
+        assert_eq!(store.list_personal_tokens(user.id).unwrap().len(), 2);
+        assert_eq!(store.cleanup_expired_tokens().unwrap(), 1);
+
+        assert!(store.delete_personal_token(user.id, "pipeline").unwrap());
+        assert!(!store.delete_personal_token(user.id, "pipeline").unwrap());
+        assert!(store.get_user_by_personal_token(&secret).unwrap().is_none());
     }
 
     #[test]
@@ -775,7 +2270,7 @@ mod tests {
         assert!(!store.has_auth_providers().unwrap());
 
         store
-            .create_provider("github", "github", "id", "secret")
+            .create_provider("github", "github", "id", "secret", "{}")
             .unwrap();
         assert!(store.has_auth_providers().unwrap());
     }
@@ -0,0 +1,345 @@
+use anyhow::Context;
+use rusqlite::backup::Backup;
+use rusqlite::{Connection, OpenFlags};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::error::{Result, TriviaError};
+use crate::migrations::latest_version;
+use crate::store::{MemoryStore, register_sqlite_vec};
+
+/// Pages copied per backup step. Other connections can write between steps;
+/// SQLite restarts the copy if they do, so the result is always consistent.
+const PAGES_PER_STEP: i32 = 256;
+const STEP_PAUSE: Duration = Duration::from_millis(10);
+
+/// Prefix for files written by scheduled backups; rotation only touches these.
+const SCHEDULED_PREFIX: &str = "trivia-";
+
+#[derive(Debug)]
+pub struct RestoreReport {
+    /// Schema version the backup was taken at.
+    pub from_version: i64,
+    /// Migrations applied to bring the backup up to date.
+    pub migrated: Vec<i64>,
+    pub memories: i64,
+    /// Where the database's previous contents were saved, if there were any.
+    pub previous: Option<PathBuf>,
+}
+
+fn copy_database(from: &Connection, dest: &Path) -> Result<()> {
+    let mut to = Connection::open(dest)
+        .with_context(|| format!("opening backup target: {}", dest.display()))?;
+    Backup::new(from, &mut to)?.run_to_completion(PAGES_PER_STEP, STEP_PAUSE, None)?;
+    Ok(())
+}
+
+/// Run `PRAGMA integrity_check` on the file at `path` and make sure it holds
+/// a trivia database.
+fn check_backup(path: &Path) -> Result<()> {
+    register_sqlite_vec();
+    let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("opening backup: {}", path.display()))?;
+    let problems: Vec<String> = conn
+        .prepare("PRAGMA integrity_check")
+        .and_then(|mut stmt| {
+            stmt.query_map([], |row| row.get(0))?
+                .collect::<rusqlite::Result<_>>()
+        })
+        .with_context(|| format!("{} is not a SQLite database", path.display()))?;
+    if problems != ["ok"] {
+        return Err(TriviaError::Validation(format!(
+            "integrity check failed for {}: {}",
+            path.display(),
+            problems.join("; ")
+        )));
+    }
+    let has_memories: bool = conn.query_row(
+        "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'memories'",
+        [],
+        |row| row.get(0),
+    )?;
+    if !has_memories {
+        return Err(TriviaError::Validation(format!(
+            "{} is not a trivia database",
+            path.display()
+        )));
+    }
+    Ok(())
+}
+
+/// `trivia.db` + `pre-restore` → `trivia.db.pre-restore-20260101T120000.bak`
+fn stamped_path(db_path: &Path, label: &str) -> PathBuf {
+    let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%S");
+    let mut name = db_path
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_else(|| "trivia.db".into());
+    name.push(format!(".{label}-{stamp}.bak"));
+    db_path.with_file_name(name)
+}
+
+/// Refuse to overwrite `dest` and create its directory.
+fn prepare_backup_target(dest: &Path) -> Result<()> {
+    if dest.exists() {
+        return Err(TriviaError::Conflict(format!(
+            "{} already exists",
+            dest.display()
+        )));
+    }
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("creating backup directory: {}", parent.display()))?;
+    }
+    Ok(())
+}
+
+/// Copy the database at `db_path` to `dest` over a read-only connection of
+/// its own, so a long backup doesn't hold up the store's connection.
+pub fn backup_database(db_path: &Path, dest: &Path) -> Result<()> {
+    prepare_backup_target(dest)?;
+    register_sqlite_vec();
+    let from = Connection::open_with_flags(db_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("opening database: {}", db_path.display()))?;
+    copy_database(&from, dest)
+}
+
+impl MemoryStore {
+    /// Copy the database to `dest` using SQLite's online backup API. Safe to
+    /// run while other processes (e.g. `trivia www`) are using the database.
+    pub fn backup_to(&self, dest: &Path) -> Result<()> {
+        prepare_backup_target(dest)?;
+        copy_database(self.conn(), dest)
+    }
+
+    /// The file this store is kept in, or `None` for an in-memory store.
+    pub fn database_path(&self) -> Option<PathBuf> {
+        self.conn()
+            .path()
+            .filter(|p| !p.is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// Replace the database at `db_path` with the backup at `src`.
+    ///
+    /// The backup is integrity-checked and migrated to the current schema on
+    /// a scratch copy first, so a bad or newer backup leaves the database
+    /// untouched. The current contents are saved next to `db_path` before
+    /// being overwritten.
+    pub fn restore(db_path: &Path, src: &Path) -> Result<RestoreReport> {
+        if !src.is_file() {
+            return Err(TriviaError::NotFound(format!(
+                "backup not found: {}",
+                src.display()
+            )));
+        }
+        check_backup(src)?;
+
+        let scratch = stamped_path(db_path, "restoring");
+        let prepared = (|| -> Result<RestoreReport> {
+            let from = Connection::open_with_flags(src, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+            copy_database(&from, &scratch)?;
+            let staged = MemoryStore::open_unmigrated(&scratch)?;
+            let from_version = staged.schema_version()?;
+            if from_version > latest_version() {
+                return Err(TriviaError::Validation(format!(
+                    "backup is at schema version {from_version}, newer than this build supports ({})",
+                    latest_version()
+                )));
+            }
+            let migrated = staged.migrate()?.iter().map(|m| m.version).collect();
+            let memories = staged
+                .conn()
+                .query_row("SELECT COUNT(*) FROM memories", [], |row| row.get(0))?;
+            Ok(RestoreReport {
+                from_version,
+                migrated,
+                memories,
+                previous: None,
+            })
+        })();
+        let mut report = match prepared {
+            Ok(report) => report,
+            Err(e) => {
+                let _ = std::fs::remove_file(&scratch);
+                return Err(e);
+            }
+        };
+
+        let mut live = MemoryStore::open_unmigrated(db_path)?;
+        if live.has_existing_schema()? {
+            let previous = stamped_path(db_path, "pre-restore");
+            live.backup_to(&previous)?;
+            report.previous = Some(previous);
+        }
+        live.conn_mut()
+            .restore(rusqlite::DatabaseName::Main, &scratch, None::<fn(_)>)
+            .with_context(|| format!("restoring into {}", db_path.display()))?;
+        std::fs::remove_file(&scratch)?;
+        Ok(report)
+    }
+}
+
+/// Path for a new scheduled backup in `dir`.
+pub fn scheduled_backup_path(dir: &Path) -> PathBuf {
+    let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%S");
+    dir.join(format!("{SCHEDULED_PREFIX}{stamp}.db"))
+}
+
+/// Delete all but the newest `keep` scheduled backups in `dir`. Returns the
+/// removed paths. Files not written by scheduled backups are left alone.
+pub fn rotate_backups(dir: &Path, keep: usize) -> Result<Vec<PathBuf>> {
+    let mut backups: Vec<PathBuf> = std::fs::read_dir(dir)
+        .with_context(|| format!("reading backup directory: {}", dir.display()))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .is_some_and(|n| n.starts_with(SCHEDULED_PREFIX) && n.ends_with(".db"))
+        })
+        .collect();
+    // Timestamped names sort chronologically
+    backups.sort();
+    let excess = backups.len().saturating_sub(keep);
+    let removed: Vec<PathBuf> = backups.into_iter().take(excess).collect();
+    for path in &removed {
+        std::fs::remove_file(path)
+            .with_context(|| format!("removing old backup: {}", path.display()))?;
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn emb(seed: f32) -> Vec<f32> {
+        vec![seed; 384]
+    }
+
+    #[test]
+    fn test_backup_and_restore_keep_history() -> Result<()> {
+        let dir = TempDir::new()?;
+        let db = dir.path().join("trivia.db");
+        let backup = dir.path().join("snapshot.db");
+
+        let store = MemoryStore::new(&db)?;
+        store.memorize("ranked", "keep my ratings", &["t".into()], &emb(0.1))?;
+        store.rate("ranked", true, None, None)?;
+        store.create_user("alice", "*:read")?;
+        store.backup_to(&backup)?;
+        assert!(store.backup_to(&backup).is_err(), "refuses to overwrite");
+
+        store.delete_memory("ranked")?;
+        drop(store);
+
+        let report = MemoryStore::restore(&db, &backup)?;
+        assert_eq!(report.memories, 1);
+        assert!(report.migrated.is_empty());
+        assert!(report.previous.as_ref().is_some_and(|p| p.exists()));
+
+        let store = MemoryStore::new(&db)?;
+        let mem = store.get_memory_by_mnemonic("ranked")?.unwrap();
+        assert_eq!(mem.useful_count, 1);
+        assert!(store.get_user_by_username("alice")?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_backup_over_separate_connection() -> Result<()> {
+        let dir = TempDir::new()?;
+        let db = dir.path().join("trivia.db");
+        let backup = dir.path().join("nightly").join("snapshot.db");
+
+        let store = MemoryStore::new(&db)?;
+        store.memorize("kept", "copied while the store stays open", &[], &emb(0.1))?;
+        let db_path = store.database_path().unwrap();
+        backup_database(&db_path, &backup)?;
+        assert!(
+            backup_database(&db_path, &backup).is_err(),
+            "refuses to overwrite"
+        );
+        assert_eq!(MemoryStore::in_memory()?.database_path(), None);
+
+        let copy = MemoryStore::new(&backup)?;
+        assert!(copy.get_memory_by_mnemonic("kept")?.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_migrates_old_backup() -> Result<()> {
+        let dir = TempDir::new()?;
+        let db = dir.path().join("trivia.db");
+        let old = dir.path().join("old.db");
+        {
+            let store = MemoryStore::new(&old)?;
+            store.memorize("m", "c", &[], &emb(0.1))?;
+            store.conn().pragma_update(None, "user_version", 1)?;
+        }
+
+        let report = MemoryStore::restore(&db, &old)?;
+        assert_eq!(report.from_version, 1);
+        assert_eq!(report.migrated, (2..=latest_version()).collect::<Vec<_>>());
+        assert!(report.previous.is_none());
+        assert_eq!(MemoryStore::new(&db)?.schema_version()?, latest_version());
+        Ok(())
+    }
+
+    #[test]
+    fn test_restore_rejects_bad_backup() -> Result<()> {
+        let dir = TempDir::new()?;
+        let db = dir.path().join("trivia.db");
+        MemoryStore::new(&db)?.memorize("m", "c", &[], &emb(0.1))?;
+
+        let garbage = dir.path().join("garbage.db");
+        std::fs::write(&garbage, "definitely not sqlite")?;
+        assert!(MemoryStore::restore(&db, &garbage).is_err());
+
+        let foreign = dir.path().join("foreign.db");
+        Connection::open(&foreign)?.execute_batch("CREATE TABLE t (x)")?;
+        let err = MemoryStore::restore(&db, &foreign).unwrap_err();
+        assert!(err.to_string().contains("not a trivia database"));
+
+        let newer = dir.path().join("newer.db");
+        MemoryStore::new(&newer)?.conn().pragma_update(
+            None,
+            "user_version",
+            latest_version() + 1,
+        )?;
+        assert!(MemoryStore::restore(&db, &newer).is_err());
+
+        // Nothing was touched, and no scratch files are left behind
+        assert!(
+            MemoryStore::new(&db)?
+                .get_memory_by_mnemonic("m")?
+                .is_some()
+        );
+        let leftovers = std::fs::read_dir(dir.path())?
+            .filter(|e| {
+                e.as_ref()
+                    .unwrap()
+                    .file_name()
+                    .to_string_lossy()
+                    .contains("restoring")
+            })
+            .count();
+        assert_eq!(leftovers, 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_rotate_backups() -> Result<()> {
+        let dir = TempDir::new()?;
+        for stamp in ["20260101T000000", "20260102T000000", "20260103T000000"] {
+            std::fs::write(dir.path().join(format!("trivia-{stamp}.db")), "")?;
+        }
+        std::fs::write(dir.path().join("manual.db"), "")?;
+
+        let removed = rotate_backups(dir.path(), 2)?;
+        assert_eq!(removed, vec![dir.path().join("trivia-20260101T000000.db")]);
+        assert!(dir.path().join("manual.db").exists());
+        assert!(rotate_backups(dir.path(), 2)?.is_empty());
+        Ok(())
+    }
+}
@@ -1,4 +1,5 @@
 use anyhow::Result;
+use chrono::Duration;
 use serde::Deserialize;
 use std::path::{Path, PathBuf};
 
@@ -10,6 +11,18 @@ pub struct TriviaConfig {
     pub recall: RecallConfig,
     #[serde(default)]
     pub export: ExportConfig,
+    #[serde(default)]
+    pub backup: BackupConfig,
+    #[serde(default)]
+    pub access: AccessConfig,
+    #[serde(default)]
+    pub maintenance: MaintenanceConfig,
+    #[serde(default)]
+    pub auth: AuthConfig,
+    #[serde(default)]
+    pub ratings: RatingConfig,
+    #[serde(default)]
+    pub scoring: ScoringWeights,
     pub database: Option<String>,
     /// External URL for OAuth redirect URIs (e.g. "https://trivia.example.com")
     pub external_url: Option<String>,
@@ -35,6 +48,239 @@ pub struct ExportConfig {
     pub tags: Vec<String>,
 }
 
+/// Scheduled backups while `trivia www` is running. Disabled unless `dir` is set.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct BackupConfig {
+    pub dir: Option<String>,
+    /// Hours between backups (default 24)
+    pub interval_hours: Option<u64>,
+    /// Number of scheduled backups to keep (default 7)
+    pub keep: Option<usize>,
+}
+
+/// Periodic cleanup of expired auth rows and index upkeep while `trivia www`
+/// is running.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct MaintenanceConfig {
+    /// Minutes between runs (default 60; 0 turns the task off)
+    pub interval_minutes: Option<u64>,
+}
+
+/// Lifetimes of OAuth tokens, web sessions and authorization codes.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct AuthConfig {
+    /// Minutes an access token is valid (default 1440, one day)
+    pub access_token_minutes: Option<u64>,
+    /// Days a refresh token stays usable after it is issued (default 30).
+    /// Each refresh issues a new one.
+    pub refresh_token_days: Option<u64>,
+    /// Days after the user authorized a client beyond which its tokens can't
+    /// be refreshed, however often they were (default 90)
+    pub refresh_token_max_days: Option<u64>,
+    /// Hours without a request after which a web session ends (default 168)
+    pub session_idle_hours: Option<u64>,
+    /// Days after sign-in after which a web session ends regardless (default 30)
+    pub session_max_days: Option<u64>,
+    /// Seconds an authorization code can be exchanged for tokens (default 600)
+    pub auth_code_seconds: Option<u64>,
+}
+
+/// How ratings feed into recall scoring. Each rater's latest verdict on a
+/// memory counts once, weighted down as it ages.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct RatingConfig {
+    /// Days after which a rating counts half as much (default 90)
+    pub half_life_days: Option<f64>,
+    /// Extra weight of the caller's own ratings in their recalls, on top of
+    /// everyone's (default 0, off)
+    pub personal_weight: Option<f64>,
+    /// How similar (cosine, 0 to 1) a past recall's query must be to the
+    /// current one for ratings given in it to apply (default 0.75)
+    pub query_similarity: Option<f64>,
+    /// Weight of the counters a memory had before ratings were attributed,
+    /// relative to everyone's attributed ratings (default 0.5)
+    pub legacy_weight: Option<f64>,
+}
+
+impl RatingConfig {
+    pub fn half_life_days(&self) -> f64 {
+        self.half_life_days
+            .filter(|days| *days > 0.0)
+            .unwrap_or(90.0)
+    }
+
+    pub fn personal_weight(&self) -> f64 {
+        self.personal_weight.unwrap_or(0.0)
+    }
+
+    pub fn legacy_weight(&self) -> f64 {
+        self.legacy_weight
+            .filter(|weight| *weight >= 0.0)
+            .unwrap_or(0.5)
+    }
+
+    pub fn query_similarity(&self) -> f64 {
+        self.query_similarity
+            .filter(|similarity| (0.0..1.0).contains(similarity))
+            .unwrap_or(0.75)
+    }
+}
+
+/// Weights of the recall score's components, overriding the built-in
+/// defaults. `trivia tune` fits them to the ratings a store has collected.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize)]
+pub struct ScoringWeights {
+    pub similarity_weight: Option<f64>,
+    pub recency_weight: Option<f64>,
+    pub frequency_weight: Option<f64>,
+    pub link_weight: Option<f64>,
+    pub rating_weight: Option<f64>,
+    pub query_weight: Option<f64>,
+    pub tag_boost_weight: Option<f64>,
+    pub fts_weight: Option<f64>,
+}
+
+impl ScoringWeights {
+    fn entries(&self) -> [(&'static str, Option<f64>); 8] {
+        [
+            ("similarity_weight", self.similarity_weight),
+            ("recency_weight", self.recency_weight),
+            ("frequency_weight", self.frequency_weight),
+            ("link_weight", self.link_weight),
+            ("rating_weight", self.rating_weight),
+            ("query_weight", self.query_weight),
+            ("tag_boost_weight", self.tag_boost_weight),
+            ("fts_weight", self.fts_weight),
+        ]
+    }
+
+    /// These weights, with `existing` filling in the ones not set here.
+    pub fn merged_over(&self, existing: &ScoringWeights) -> ScoringWeights {
+        ScoringWeights {
+            similarity_weight: self.similarity_weight.or(existing.similarity_weight),
+            recency_weight: self.recency_weight.or(existing.recency_weight),
+            frequency_weight: self.frequency_weight.or(existing.frequency_weight),
+            link_weight: self.link_weight.or(existing.link_weight),
+            rating_weight: self.rating_weight.or(existing.rating_weight),
+            query_weight: self.query_weight.or(existing.query_weight),
+            tag_boost_weight: self.tag_boost_weight.or(existing.tag_boost_weight),
+            fts_weight: self.fts_weight.or(existing.fts_weight),
+        }
+    }
+
+    /// The weights that are set, as a `[scoring]` block for `trivia.toml`.
+    pub fn to_toml(&self) -> String {
+        let mut block = String::from("[scoring]\n");
+        for (key, value) in self.entries() {
+            if let Some(value) = value {
+                block.push_str(&format!("{key} = {value:.4}\n"));
+            }
+        }
+        block
+    }
+}
+
+/// `contents` with its `[scoring]` section, if any, replaced by `block`,
+/// or with `block` appended. Top-level `scoring.*` keys are dropped, as
+/// `block` sets them. Other sections and comments are kept as they are.
+pub fn replace_scoring_section(contents: &str, block: &str) -> String {
+    /// The line without its comment, if it is a table header.
+    fn header(line: &str) -> Option<&str> {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        line.starts_with('[').then_some(line)
+    }
+    let is_scoring = |header: &str| {
+        header
+            .strip_prefix('[')
+            .and_then(|h| h.strip_suffix(']'))
+            .is_some_and(|name| name.trim() == "scoring")
+    };
+    // A top-level `scoring.key = ...` or `scoring = { ... }`
+    let is_scoring_key = |line: &str| {
+        line.split_once('=')
+            .and_then(|(key, _)| key.split('.').next())
+            .is_some_and(|first| first.trim() == "scoring")
+    };
+    let mut lines = contents.lines().peekable();
+    let mut out = String::new();
+    let mut replaced = false;
+    let mut top_level = true;
+    while let Some(line) = lines.next() {
+        let table = header(line);
+        top_level &= table.is_none();
+        if table.is_some_and(is_scoring) && !replaced {
+            out.push_str(block);
+            while lines.peek().is_some_and(|next| header(next).is_none()) {
+                lines.next();
+            }
+            if lines.peek().is_some() {
+                out.push('\n');
+            }
+            replaced = true;
+        } else if !(top_level && is_scoring_key(line)) {
+            out.push_str(line);
+            out.push('\n');
+        }
+    }
+    if !replaced {
+        if !out.is_empty() && !out.ends_with("\n\n") {
+            out.push('\n');
+        }
+        out.push_str(block);
+    }
+    out
+}
+
+/// `value` (or `default`) in `unit`s, capped well below where chrono's
+/// constructors would panic.
+fn duration(value: Option<u64>, default: u64, unit: fn(i64) -> Duration) -> Duration {
+    unit(value.unwrap_or(default).clamp(1, 1_000_000) as i64)
+}
+
+impl AuthConfig {
+    pub fn access_token_ttl(&self) -> Duration {
+        duration(self.access_token_minutes, 24 * 60, Duration::minutes)
+    }
+
+    pub fn refresh_token_ttl(&self) -> Duration {
+        duration(self.refresh_token_days, 30, Duration::days)
+    }
+
+    pub fn refresh_token_max(&self) -> Duration {
+        duration(self.refresh_token_max_days, 90, Duration::days)
+    }
+
+    pub fn session_idle(&self) -> Duration {
+        duration(self.session_idle_hours, 7 * 24, Duration::hours)
+    }
+
+    pub fn session_max(&self) -> Duration {
+        duration(self.session_max_days, 30, Duration::days)
+    }
+
+    pub fn auth_code_ttl(&self) -> Duration {
+        duration(self.auth_code_seconds, 600, Duration::seconds)
+    }
+}
+
+/// Access control for `trivia www`, on top of the tag ACL.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct AccessConfig {
+    #[serde(default)]
+    pub visibility: Visibility,
+}
+
+/// Who can see a memory created by a signed-in user.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Visibility {
+    /// Whoever the ACL lets read it; the owner can always edit it.
+    #[default]
+    Shared,
+    /// Only the owner, whatever the ACL grants others.
+    OwnerOnly,
+}
+
 impl TriviaConfig {
     /// Walk up from `start_dir` looking for `trivia.toml`.
     /// Returns default config if not found.
@@ -134,4 +380,123 @@ tags = ["project-x", "backend"]
         assert_eq!(config.database.as_deref(), Some("/tmp/my.db"));
         Ok(())
     }
+
+    #[test]
+    fn test_backup_section() -> Result<()> {
+        let dir = TempDir::new()?;
+        let toml_path = dir.path().join("trivia.toml");
+        fs::write(
+            &toml_path,
+            "[backup]\ndir = \"/var/backups/trivia\"\nkeep = 3\n",
+        )?;
+
+        let config = TriviaConfig::load(&toml_path)?;
+        assert_eq!(config.backup.dir.as_deref(), Some("/var/backups/trivia"));
+        assert_eq!(config.backup.keep, Some(3));
+        assert_eq!(config.backup.interval_hours, None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_auth_section() -> Result<()> {
+        let dir = TempDir::new()?;
+        let toml_path = dir.path().join("trivia.toml");
+        fs::write(
+            &toml_path,
+            "[auth]\naccess_token_minutes = 60\nsession_idle_hours = 0\n",
+        )?;
+
+        let auth = TriviaConfig::load(&toml_path)?.auth;
+        assert_eq!(auth.access_token_ttl(), Duration::hours(1));
+        assert_eq!(auth.refresh_token_ttl(), Duration::days(30));
+        // Zero would end sessions immediately; it's treated as the minimum
+        assert_eq!(auth.session_idle(), Duration::hours(1));
+        assert_eq!(auth.auth_code_ttl(), Duration::minutes(10));
+        Ok(())
+    }
+
+    #[test]
+    fn test_ratings_section() -> Result<()> {
+        let dir = TempDir::new()?;
+        let toml_path = dir.path().join("trivia.toml");
+        fs::write(
+            &toml_path,
+            "[ratings]\nhalf_life_days = 0\npersonal_weight = 0.3\nquery_similarity = 1.5\nlegacy_weight = -1\n",
+        )?;
+
+        let ratings = TriviaConfig::load(&toml_path)?.ratings;
+        // A zero half-life would discount every rating to nothing
+        assert_eq!(ratings.half_life_days(), 90.0);
+        assert_eq!(ratings.personal_weight(), 0.3);
+        // No query is more than identical to another
+        assert_eq!(ratings.query_similarity(), 0.75);
+        assert_eq!(ratings.legacy_weight(), 0.5);
+        Ok(())
+    }
+
+    #[test]
+    fn test_scoring_section() -> Result<()> {
+        let dir = TempDir::new()?;
+        let toml_path = dir.path().join("trivia.toml");
+        let original = "# project memory\n[recall]\ntags = [\"rust\"]\n\n[scoring]\nrating_weight = 0.1\nfts_weight = 0.2\n\n[auth]\nsession_max_days = 7\n";
+        fs::write(&toml_path, original)?;
+        let existing = TriviaConfig::load(&toml_path)?.scoring;
+        let tuned = ScoringWeights {
+            similarity_weight: Some(1.0),
+            rating_weight: Some(0.4),
+            ..Default::default()
+        };
+        // Hand-set weights the tuning left alone are kept
+        let weights = tuned.merged_over(&existing);
+        let block = weights.to_toml();
+        assert_eq!(
+            block,
+            "[scoring]\nsimilarity_weight = 1.0000\nrating_weight = 0.4000\nfts_weight = 0.2000\n"
+        );
+
+        let updated = replace_scoring_section(original, &block);
+        assert_eq!(
+            updated,
+            "# project memory\n[recall]\ntags = [\"rust\"]\n\n[scoring]\nsimilarity_weight = 1.0000\nrating_weight = 0.4000\nfts_weight = 0.2000\n\n[auth]\nsession_max_days = 7\n"
+        );
+        fs::write(&toml_path, &updated)?;
+        let config = TriviaConfig::load(&toml_path)?;
+        assert_eq!(config.scoring, weights);
+        assert_eq!(config.auth.session_max_days, Some(7));
+
+        let appended = replace_scoring_section("[recall]\nmin_score = 0.2\n", &block);
+        assert_eq!(appended, format!("[recall]\nmin_score = 0.2\n\n{block}"));
+        assert_eq!(replace_scoring_section("", &block), block);
+
+        // Headers with comments or padding, and dotted keys, are found too
+        for original in [
+            "[recall]\nmin_score = 0.2\n\n[ scoring ]  # tuned\nfts_weight = 0.2\n",
+            "scoring.fts_weight = 0.2\n\n[recall]\nmin_score = 0.2\n",
+            "scoring = { fts_weight = 0.2 }\n[recall]\nmin_score = 0.2\n",
+        ] {
+            let updated = replace_scoring_section(original, &block);
+            let config: TriviaConfig = toml::from_str(&updated)?;
+            assert_eq!(config.scoring, weights, "{updated}");
+            assert_eq!(config.recall.min_score, Some(0.2));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_access_section() -> Result<()> {
+        let dir = TempDir::new()?;
+        let toml_path = dir.path().join("trivia.toml");
+        assert_eq!(
+            TriviaConfig::load(&toml_path)?.access.visibility,
+            Visibility::Shared
+        );
+
+        fs::write(&toml_path, "[access]\nvisibility = \"owner-only\"\n")?;
+        let config = TriviaConfig::load(&toml_path)?;
+        assert_eq!(config.access.visibility, Visibility::OwnerOnly);
+
+        fs::write(&toml_path, "[access]\nvisibility = \"private\"\n")?;
+        assert!(TriviaConfig::load(&toml_path).is_err());
+        Ok(())
+    }
 }
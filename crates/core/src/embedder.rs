@@ -1,8 +1,14 @@
 use std::path::PathBuf;
 
-use anyhow::Result;
 use fastembed::{EmbeddingModel, InitOptions, TextEmbedding};
 
+use crate::error::{Result, TriviaError};
+
+/// Identifies the embedding model in archives, so vectors are only reused
+/// by a build that would have produced the same ones.
+pub const MODEL_ID: &str = "sentence-transformers/all-MiniLM-L6-v2";
+pub const DIMENSIONS: usize = 384;
+
 pub struct Embedder {
     model: TextEmbedding,
 }
@@ -20,13 +26,20 @@ impl Embedder {
             InitOptions::new(EmbeddingModel::AllMiniLML6V2)
                 .with_cache_dir(cache_dir())
                 .with_show_download_progress(true),
-        )?;
+        )
+        .map_err(TriviaError::Embedding)?;
         Ok(Self { model })
     }
 
     pub fn embed(&self, text: &str) -> Result<Vec<f32>> {
-        let embeddings = self.model.embed(vec![text], None)?;
-        Ok(embeddings.into_iter().next().expect("single input should produce single output"))
+        let embeddings = self
+            .model
+            .embed(vec![text], None)
+            .map_err(TriviaError::Embedding)?;
+        Ok(embeddings
+            .into_iter()
+            .next()
+            .expect("single input should produce single output"))
     }
 }
 
@@ -0,0 +1,193 @@
+use std::fmt;
+
+/// Errors returned by `MemoryStore`, `PgStore` and `Embedder`.
+///
+/// The first four variants are caused by the request and won't go away on a
+/// retry; `Storage` and `Embedding` wrap failures of the database or the
+/// model and may be transient.
+#[derive(Debug)]
+pub enum TriviaError {
+    /// A memory, user, link or other entity doesn't exist.
+    NotFound(String),
+    /// The change clashes with existing data, e.g. a duplicate title.
+    Conflict(String),
+    /// The input is malformed or the operation isn't allowed on this data.
+    Validation(String),
+    AccessDenied(String),
+    Storage(anyhow::Error),
+    Embedding(anyhow::Error),
+}
+
+pub type Result<T, E = TriviaError> = std::result::Result<T, E>;
+
+impl TriviaError {
+    pub fn not_found(msg: impl Into<String>) -> Self {
+        Self::NotFound(msg.into())
+    }
+
+    pub fn conflict(msg: impl Into<String>) -> Self {
+        Self::Conflict(msg.into())
+    }
+
+    pub fn validation(msg: impl Into<String>) -> Self {
+        Self::Validation(msg.into())
+    }
+
+    pub fn access_denied(msg: impl Into<String>) -> Self {
+        Self::AccessDenied(msg.into())
+    }
+
+    pub fn storage(msg: impl fmt::Display + fmt::Debug + Send + Sync + 'static) -> Self {
+        Self::Storage(anyhow::Error::msg(msg))
+    }
+
+    /// Stable machine-readable name, used in REST and MCP error bodies.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "not_found",
+            Self::Conflict(_) => "conflict",
+            Self::Validation(_) => "validation",
+            Self::AccessDenied(_) => "access_denied",
+            Self::Storage(_) => "storage",
+            Self::Embedding(_) => "embedding",
+        }
+    }
+
+    /// Whether the same request might succeed if tried again later.
+    pub fn is_retryable(&self) -> bool {
+        matches!(self, Self::Storage(_) | Self::Embedding(_))
+    }
+
+    /// Find the `TriviaError` in an `anyhow` chain, if there is one.
+    pub fn find(err: &anyhow::Error) -> Option<&TriviaError> {
+        err.chain()
+            .find_map(|cause| cause.downcast_ref::<TriviaError>())
+    }
+}
+
+impl fmt::Display for TriviaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(msg)
+            | Self::Conflict(msg)
+            | Self::Validation(msg)
+            | Self::AccessDenied(msg) => f.write_str(msg),
+            Self::Storage(err) | Self::Embedding(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for TriviaError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Storage(err) | Self::Embedding(err) => err.source(),
+            _ => None,
+        }
+    }
+}
+
+fn is_constraint_violation(err: &rusqlite::Error) -> bool {
+    matches!(
+        err.sqlite_error_code(),
+        Some(rusqlite::ErrorCode::ConstraintViolation)
+    )
+}
+
+impl From<rusqlite::Error> for TriviaError {
+    fn from(err: rusqlite::Error) -> Self {
+        if is_constraint_violation(&err) {
+            Self::Conflict(err.to_string())
+        } else {
+            Self::Storage(err.into())
+        }
+    }
+}
+
+/// Errors that picked up context on the way keep their classification: a
+/// wrapped `TriviaError` is unwrapped, and a wrapped constraint violation is
+/// still a conflict.
+impl From<anyhow::Error> for TriviaError {
+    fn from(err: anyhow::Error) -> Self {
+        let err = match err.downcast::<TriviaError>() {
+            Ok(typed) => return typed,
+            Err(err) => err,
+        };
+        let conflict = err
+            .chain()
+            .filter_map(|cause| cause.downcast_ref::<rusqlite::Error>())
+            .any(is_constraint_violation);
+        if conflict {
+            Self::Conflict(format!("{err:#}"))
+        } else {
+            Self::Storage(err)
+        }
+    }
+}
+
+impl From<std::io::Error> for TriviaError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Storage(err.into())
+    }
+}
+
+impl From<serde_json::Error> for TriviaError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Storage(err.into())
+    }
+}
+
+impl From<serde_norway::Error> for TriviaError {
+    fn from(err: serde_norway::Error) -> Self {
+        Self::Validation(err.to_string())
+    }
+}
+
+#[cfg(feature = "postgres")]
+impl From<postgres::Error> for TriviaError {
+    fn from(err: postgres::Error) -> Self {
+        if err.code() == Some(&postgres::error::SqlState::UNIQUE_VIOLATION) {
+            Self::Conflict(err.to_string())
+        } else {
+            Self::Storage(err.into())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_constraint_violation_is_conflict() {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t (x TEXT UNIQUE); INSERT INTO t VALUES ('a');")
+            .unwrap();
+        let insert = || conn.execute("INSERT INTO t VALUES ('a')", []);
+
+        let err: TriviaError = insert().unwrap_err().into();
+        assert_eq!(err.kind(), "conflict");
+        assert!(!err.is_retryable());
+
+        let err: TriviaError = insert().context("saving t").unwrap_err().into();
+        assert_eq!(err.kind(), "conflict");
+    }
+
+    #[test]
+    fn test_anyhow_roundtrip_keeps_kind() {
+        let err = anyhow::Error::from(TriviaError::not_found("memory not found: x"))
+            .context("rate failed");
+        assert_eq!(
+            TriviaError::find(&err).map(TriviaError::kind),
+            Some("not_found")
+        );
+
+        let typed: TriviaError = anyhow::Error::from(TriviaError::validation("bad")).into();
+        assert_eq!(typed.kind(), "validation");
+
+        let other: TriviaError = anyhow::anyhow!("disk on fire").into();
+        assert_eq!(other.kind(), "storage");
+        assert!(other.is_retryable());
+        assert_eq!(other.to_string(), "disk on fire");
+    }
+}
@@ -1,10 +1,11 @@
-use anyhow::{Result, anyhow};
-use rusqlite::params;
+use chrono::{DateTime, Utc};
+use rusqlite::{OptionalExtension, params};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 
 use crate::embedder::Embedder;
-use crate::store::MemoryStore;
+use crate::error::{Result, TriviaError};
+use crate::store::{MemoryStore, format_sqlite_datetime, link_detail};
 
 #[derive(Debug, Serialize, Deserialize)]
 struct Frontmatter {
@@ -18,11 +19,58 @@ struct Frontmatter {
     links: Vec<ExportLink>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct ExportLink {
-    target: String, // UUID of target
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportLink {
+    pub target: String, // UUID of target
     #[serde(rename = "type")]
-    link_type: String,
+    pub link_type: String,
+}
+
+/// A memory in backend-neutral form: what an export file holds and what
+/// an import hands back to the store.
+#[derive(Debug, Clone)]
+pub struct ExportEntry {
+    pub uuid: String,
+    pub title: String,
+    pub content: String,
+    pub tags: Vec<String>,
+    /// Alias mnemonics, excluding the title itself.
+    pub mnemonics: Vec<String>,
+    /// Outgoing links, keyed by target UUID.
+    pub links: Vec<ExportLink>,
+}
+
+/// On-disk format for `export` / `import`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// One markdown file per memory with YAML frontmatter, for reading and
+    /// editing by hand.
+    #[default]
+    Markdown,
+    /// A single JSONL archive holding every column, for moving a database
+    /// without losing its history.
+    Jsonl,
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s {
+            "markdown" | "md" => Ok(Self::Markdown),
+            "jsonl" => Ok(Self::Jsonl),
+            other => Err(format!(
+                "unknown format '{other}' (expected markdown or jsonl)"
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportOutcome {
+    Created,
+    Updated,
+    Unchanged,
 }
 
 #[derive(Debug, Default)]
@@ -32,6 +80,16 @@ pub struct ImportResult {
     pub unchanged: usize,
 }
 
+impl ImportResult {
+    pub(crate) fn record(&mut self, outcome: ImportOutcome) {
+        match outcome {
+            ImportOutcome::Created => self.created += 1,
+            ImportOutcome::Updated => self.updated += 1,
+            ImportOutcome::Unchanged => self.unchanged += 1,
+        }
+    }
+}
+
 fn slugify(s: &str) -> String {
     let slug: String = s
         .to_lowercase()
@@ -59,146 +117,78 @@ fn slugify(s: &str) -> String {
     result
 }
 
-struct ExportRow {
-    memory_id: i64,
-    uuid: String,
-    title: String,
-    content: String,
-    tags_json: String,
-}
+/// Write one markdown file per entry. Links are only kept when both ends
+/// are part of the export.
+pub(crate) fn write_markdown(dir: &Path, entries: &[ExportEntry]) -> Result<()> {
+    std::fs::create_dir_all(dir)?;
 
-struct ExportLinkRow {
-    source_uuid: String,
-    target_uuid: String,
-    link_type: String,
-}
+    let exported_uuids: std::collections::HashSet<&str> =
+        entries.iter().map(|e| e.uuid.as_str()).collect();
 
-impl MemoryStore {
-    pub fn export(&self, dir: &Path, tags: Option<&[String]>) -> Result<()> {
-        std::fs::create_dir_all(dir)?;
+    for entry in entries {
+        let links: Vec<ExportLink> = entry
+            .links
+            .iter()
+            .filter(|l| exported_uuids.contains(l.target.as_str()))
+            .cloned()
+            .collect();
 
-        // Query memories, optionally filtered by tags
-        let rows: Vec<ExportRow> = match tags {
-            Some(filter_tags) if !filter_tags.is_empty() => {
-                let placeholders: Vec<String> =
-                    (1..=filter_tags.len()).map(|i| format!("?{i}")).collect();
-                let sql = format!(
-                    "SELECT DISTINCT m.id, m.uuid, m.title, m.content, m.tags
-                     FROM memories m, json_each(m.tags) je
-                     WHERE je.value IN ({})
-                     ORDER BY m.title",
-                    placeholders.join(", ")
-                );
-                let mut stmt = self.conn().prepare(&sql)?;
-                let params: Vec<&dyn rusqlite::types::ToSql> = filter_tags
-                    .iter()
-                    .map(|t| t as &dyn rusqlite::types::ToSql)
-                    .collect();
-                stmt.query_map(params.as_slice(), |row| {
-                    Ok(ExportRow {
-                        memory_id: row.get(0)?,
-                        uuid: row.get(1)?,
-                        title: row.get(2)?,
-                        content: row.get(3)?,
-                        tags_json: row.get(4)?,
-                    })
-                })?
-                .collect::<std::result::Result<Vec<_>, _>>()?
-            }
-            _ => {
-                let mut stmt = self.conn().prepare(
-                    "SELECT id, uuid, title, content, tags FROM memories ORDER BY title",
-                )?;
-                stmt.query_map([], |row| {
-                    Ok(ExportRow {
-                        memory_id: row.get(0)?,
-                        uuid: row.get(1)?,
-                        title: row.get(2)?,
-                        content: row.get(3)?,
-                        tags_json: row.get(4)?,
-                    })
-                })?
-                .collect::<std::result::Result<Vec<_>, _>>()?
-            }
+        let fm = Frontmatter {
+            uuid: entry.uuid.clone(),
+            mnemonic: entry.title.clone(),
+            mnemonics: entry.mnemonics.clone(),
+            tags: entry.tags.clone(),
+            links,
         };
 
-        // Query links — only include links where both ends are in the exported set
-        let exported_uuids: std::collections::HashSet<&str> =
-            rows.iter().map(|r| r.uuid.as_str()).collect();
+        let yaml = serde_norway::to_string(&fm)?;
+        let file_content = format!("---\n{yaml}---\n\n{}", entry.content);
 
-        let mut link_stmt = self.conn().prepare(
-            "SELECT s_mem.uuid, t_mem.uuid, ml.link_type
-             FROM memory_links ml
-             JOIN memories s_mem ON s_mem.id = ml.source_id
-             JOIN memories t_mem ON t_mem.id = ml.target_id",
-        )?;
-        let link_rows: Vec<ExportLinkRow> = link_stmt
-            .query_map([], |row| {
-                Ok(ExportLinkRow {
-                    source_uuid: row.get(0)?,
-                    target_uuid: row.get(1)?,
-                    link_type: row.get(2)?,
-                })
-            })?
-            .collect::<std::result::Result<Vec<_>, _>>()?
-            .into_iter()
-            .filter(|l| {
-                exported_uuids.contains(l.source_uuid.as_str())
-                    && exported_uuids.contains(l.target_uuid.as_str())
-            })
-            .collect();
+        let filename = format!("{}.md", slugify(&entry.title));
+        std::fs::write(dir.join(&filename), file_content)?;
+    }
 
-        for row in &rows {
-            let tags: Vec<String> = serde_json::from_str(&row.tags_json).unwrap_or_default();
-
-            // Get additional mnemonics (excluding title itself)
-            let mnemonics: Vec<String> = MemoryStore::get_mnemonics_for_memory(self.conn(), row.memory_id)
-                .unwrap_or_default()
-                .into_iter()
-                .filter(|m| m != &row.title)
-                .collect();
-
-            // Collect links where this memory is the source
-            let links: Vec<ExportLink> = link_rows
-                .iter()
-                .filter(|l| l.source_uuid == row.uuid)
-                .map(|l| ExportLink {
-                    target: l.target_uuid.clone(),
-                    link_type: l.link_type.clone(),
-                })
-                .collect();
-
-            let fm = Frontmatter {
-                uuid: row.uuid.clone(),
-                mnemonic: row.title.clone(),
-                mnemonics,
-                tags,
-                links,
-            };
-
-            let yaml = serde_norway::to_string(&fm)?;
-            let file_content = format!("---\n{yaml}---\n\n{}", row.content);
-
-            let filename = format!("{}.md", slugify(&row.title));
-            let path = dir.join(&filename);
-            std::fs::write(&path, file_content)?;
-        }
+    Ok(())
+}
 
-        Ok(())
+/// Read every `.md` file in `dir`, in filename order.
+pub(crate) fn read_markdown(dir: &Path) -> Result<Vec<ExportEntry>> {
+    if !dir.is_dir() {
+        return Err(TriviaError::NotFound(format!(
+            "not a directory: {}",
+            dir.display()
+        )));
     }
 
-    /// Like `export`, but applies an additional filter predicate on each memory's tags.
-    /// Only memories for which `filter(&tags)` returns true are exported.
-    pub fn export_filtered(
-        &self,
-        dir: &Path,
-        tags: Option<&[String]>,
-        filter: impl Fn(&[String]) -> bool,
-    ) -> Result<()> {
-        std::fs::create_dir_all(dir)?;
+    let mut paths: Vec<_> = std::fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|ext| ext == "md"))
+        .collect();
+    paths.sort();
+
+    let mut entries = Vec::with_capacity(paths.len());
+    for path in &paths {
+        let raw = std::fs::read_to_string(path)?;
+        let (fm, content) = parse_frontmatter(&raw).ok_or_else(|| {
+            TriviaError::Validation(format!("invalid frontmatter in {}", path.display()))
+        })?;
+        entries.push(ExportEntry {
+            uuid: fm.uuid,
+            title: fm.mnemonic,
+            content,
+            tags: fm.tags,
+            mnemonics: fm.mnemonics,
+            links: fm.links,
+        });
+    }
+    Ok(entries)
+}
 
-        // Query memories (optionally pre-filtered by tags)
-        let rows: Vec<ExportRow> = match tags {
+impl MemoryStore {
+    /// All memories (optionally those carrying any of `tags`), ordered by title.
+    pub fn export_entries(&self, tags: Option<&[String]>) -> Result<Vec<ExportEntry>> {
+        let rows: Vec<(i64, String, String, String, String)> = match tags {
             Some(filter_tags) if !filter_tags.is_empty() => {
                 let placeholders: Vec<String> =
                     (1..=filter_tags.len()).map(|i| format!("?{i}")).collect();
@@ -215,13 +205,13 @@ impl MemoryStore {
                     .map(|t| t as &dyn rusqlite::types::ToSql)
                     .collect();
                 stmt.query_map(params.as_slice(), |row| {
-                    Ok(ExportRow {
-                        memory_id: row.get(0)?,
-                        uuid: row.get(1)?,
-                        title: row.get(2)?,
-                        content: row.get(3)?,
-                        tags_json: row.get(4)?,
-                    })
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                    ))
                 })?
                 .collect::<std::result::Result<Vec<_>, _>>()?
             }
@@ -230,259 +220,211 @@ impl MemoryStore {
                     "SELECT id, uuid, title, content, tags FROM memories ORDER BY title",
                 )?;
                 stmt.query_map([], |row| {
-                    Ok(ExportRow {
-                        memory_id: row.get(0)?,
-                        uuid: row.get(1)?,
-                        title: row.get(2)?,
-                        content: row.get(3)?,
-                        tags_json: row.get(4)?,
-                    })
+                    Ok((
+                        row.get(0)?,
+                        row.get(1)?,
+                        row.get(2)?,
+                        row.get(3)?,
+                        row.get(4)?,
+                    ))
                 })?
                 .collect::<std::result::Result<Vec<_>, _>>()?
             }
         };
 
-        // Apply ACL filter
-        let rows: Vec<ExportRow> = rows
-            .into_iter()
-            .filter(|row| {
-                let mem_tags: Vec<String> =
-                    serde_json::from_str(&row.tags_json).unwrap_or_default();
-                filter(&mem_tags)
-            })
-            .collect();
-
-        let exported_uuids: std::collections::HashSet<&str> =
-            rows.iter().map(|r| r.uuid.as_str()).collect();
-
         let mut link_stmt = self.conn().prepare(
             "SELECT s_mem.uuid, t_mem.uuid, ml.link_type
              FROM memory_links ml
              JOIN memories s_mem ON s_mem.id = ml.source_id
              JOIN memories t_mem ON t_mem.id = ml.target_id",
         )?;
-        let link_rows: Vec<ExportLinkRow> = link_stmt
-            .query_map([], |row| {
-                Ok(ExportLinkRow {
-                    source_uuid: row.get(0)?,
-                    target_uuid: row.get(1)?,
-                    link_type: row.get(2)?,
-                })
-            })?
-            .collect::<std::result::Result<Vec<_>, _>>()?
+        let link_rows: Vec<(String, String, String)> = link_stmt
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let entries = rows
             .into_iter()
-            .filter(|l| {
-                exported_uuids.contains(l.source_uuid.as_str())
-                    && exported_uuids.contains(l.target_uuid.as_str())
+            .map(|(memory_id, uuid, title, content, tags_json)| {
+                let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+                // Additional mnemonics (excluding title itself)
+                let mnemonics: Vec<String> =
+                    MemoryStore::get_mnemonics_for_memory(self.conn(), memory_id)
+                        .unwrap_or_default()
+                        .into_iter()
+                        .filter(|m| m != &title)
+                        .collect();
+                let links: Vec<ExportLink> = link_rows
+                    .iter()
+                    .filter(|(source, _, _)| *source == uuid)
+                    .map(|(_, target, link_type)| ExportLink {
+                        target: target.clone(),
+                        link_type: link_type.clone(),
+                    })
+                    .collect();
+                ExportEntry {
+                    uuid,
+                    title,
+                    content,
+                    tags,
+                    mnemonics,
+                    links,
+                }
             })
             .collect();
 
-        for row in &rows {
-            let tags: Vec<String> = serde_json::from_str(&row.tags_json).unwrap_or_default();
-            let mnemonics: Vec<String> =
-                MemoryStore::get_mnemonics_for_memory(self.conn(), row.memory_id)
-                    .unwrap_or_default()
-                    .into_iter()
-                    .filter(|m| m != &row.title)
-                    .collect();
-            let links: Vec<ExportLink> = link_rows
-                .iter()
-                .filter(|l| l.source_uuid == row.uuid)
-                .map(|l| ExportLink {
-                    target: l.target_uuid.clone(),
-                    link_type: l.link_type.clone(),
-                })
-                .collect();
-
-            let fm = Frontmatter {
-                uuid: row.uuid.clone(),
-                mnemonic: row.title.clone(),
-                mnemonics,
-                tags,
-                links,
-            };
-            let yaml = serde_norway::to_string(&fm)?;
-            let file_content = format!("---\n{yaml}---\n\n{}", row.content);
-            let filename = format!("{}.md", slugify(&row.title));
-            std::fs::write(dir.join(&filename), file_content)?;
-        }
-
-        Ok(())
+        Ok(entries)
     }
 
-    pub fn import(&self, dir: &Path, embedder: &Embedder) -> Result<ImportResult> {
-        if !dir.is_dir() {
-            return Err(anyhow!("not a directory: {}", dir.display()));
-        }
-
-        let mut result = ImportResult::default();
-
-        // Read all .md files
-        let mut entries: Vec<_> = std::fs::read_dir(dir)?
-            .filter_map(|e| e.ok())
-            .filter(|e| e.path().extension().is_some_and(|ext| ext == "md"))
-            .collect();
-        entries.sort_by_key(|e| e.path());
-
-        for entry in &entries {
-            let path = entry.path();
-            let raw = std::fs::read_to_string(&path)?;
-
-            let (fm, content) = parse_frontmatter(&raw)
-                .ok_or_else(|| anyhow!("invalid frontmatter in {}", path.display()))?;
+    /// Create or update the memory identified by `entry.uuid`, embedding the
+    /// title and any alias that has no vector yet. Links are applied
+    /// separately via `import_link` once every entry exists.
+    pub fn import_entry(&self, entry: &ExportEntry, embedder: &Embedder) -> Result<ImportOutcome> {
+        let existing: Option<(i64, String)> = self
+            .conn()
+            .query_row(
+                "SELECT id, content FROM memories WHERE uuid = ?1",
+                params![entry.uuid],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let before = match &existing {
+            Some((id, _)) => self.snapshot_by_id(*id)?,
+            None => None,
+        };
 
-            // Check if this UUID already exists
-            let existing: Option<(i64, String)> = self
-                .conn()
-                .query_row(
-                    "SELECT id, content FROM memories WHERE uuid = ?1",
-                    params![fm.uuid],
-                    |row| Ok((row.get(0)?, row.get(1)?)),
-                )
-                .ok();
-
-            let memory_id: i64 = match existing {
-                Some((id, old_content)) => {
-                    if old_content == content {
-                        result.unchanged += 1;
-                    } else {
-                        let tags_json = serde_json::to_string(&fm.tags)?;
-                        let embedding = embedder.embed(&fm.mnemonic)?;
-                        self.conn().execute(
-                            "UPDATE memories SET content = ?1, tags = ?2, title = ?3, mnemonic = ?3, updated_at = datetime('now') WHERE id = ?4",
-                            params![content, tags_json, fm.mnemonic, id],
-                        )?;
-                        // Update primary mnemonic in mnemonics table
-                        self.conn().execute(
-                            "INSERT OR IGNORE INTO mnemonics (memory_id, text) VALUES (?1, ?2)",
-                            params![id, fm.mnemonic],
-                        )?;
-                        let mn_id: i64 = self.conn().query_row(
-                            "SELECT id FROM mnemonics WHERE text = ?1",
-                            params![fm.mnemonic],
-                            |row| row.get(0),
-                        )?;
-                        // Update mnemonic vector
-                        self.conn().execute(
-                            "DELETE FROM mnemonic_vectors WHERE mnemonic_id = ?1",
-                            params![mn_id],
-                        )?;
-                        self.conn().execute(
-                            "INSERT INTO mnemonic_vectors (mnemonic_id, embedding) VALUES (?1, ?2)",
-                            params![mn_id, zerocopy::AsBytes::as_bytes(embedding.as_slice())],
-                        )?;
-                        // Also update legacy memory_vectors
-                        self.conn().execute(
-                            "DELETE FROM memory_vectors WHERE memory_id = ?1",
-                            params![id],
-                        )?;
-                        self.conn().execute(
-                            "INSERT INTO memory_vectors (memory_id, embedding) VALUES (?1, ?2)",
-                            params![id, zerocopy::AsBytes::as_bytes(embedding.as_slice())],
-                        )?;
-                        result.updated += 1;
-                    }
-                    id
-                }
-                None => {
-                    let tags_json = serde_json::to_string(&fm.tags)?;
-                    let embedding = embedder.embed(&fm.mnemonic)?;
+        let (memory_id, outcome) = match existing {
+            Some((id, old_content)) => {
+                if old_content == entry.content {
+                    (id, ImportOutcome::Unchanged)
+                } else {
+                    let tags_json = serde_json::to_string(&entry.tags)?;
+                    let embedding = embedder.embed(&entry.title)?;
                     self.conn().execute(
-                        "INSERT INTO memories (uuid, mnemonic, title, content, tags) VALUES (?1, ?2, ?3, ?4, ?5)",
-                        params![fm.uuid, fm.mnemonic, fm.mnemonic, content, tags_json],
+                        "UPDATE memories SET content = ?1, tags = ?2, title = ?3, mnemonic = ?3, updated_at = datetime('now') WHERE id = ?4",
+                        params![entry.content, tags_json, entry.title, id],
                     )?;
-                    let id: i64 = self.conn().query_row(
-                        "SELECT id FROM memories WHERE uuid = ?1",
-                        params![fm.uuid],
-                        |row| row.get(0),
-                    )?;
-                    // Insert primary mnemonic
+                    // Update primary mnemonic in mnemonics table
                     self.conn().execute(
                         "INSERT OR IGNORE INTO mnemonics (memory_id, text) VALUES (?1, ?2)",
-                        params![id, fm.mnemonic],
+                        params![id, entry.title],
                     )?;
                     let mn_id: i64 = self.conn().query_row(
                         "SELECT id FROM mnemonics WHERE text = ?1",
-                        params![fm.mnemonic],
+                        params![entry.title],
                         |row| row.get(0),
                     )?;
+                    // Update mnemonic vector
                     self.conn().execute(
-                        "INSERT INTO mnemonic_vectors (mnemonic_id, embedding) VALUES (?1, ?2)",
-                        params![mn_id, zerocopy::AsBytes::as_bytes(embedding.as_slice())],
+                        "DELETE FROM mnemonic_vectors WHERE mnemonic_id = ?1",
+                        params![mn_id],
                     )?;
-                    // Also insert legacy memory_vectors
                     self.conn().execute(
-                        "INSERT INTO memory_vectors (memory_id, embedding) VALUES (?1, ?2)",
-                        params![id, zerocopy::AsBytes::as_bytes(embedding.as_slice())],
+                        "INSERT INTO mnemonic_vectors (mnemonic_id, embedding) VALUES (?1, ?2)",
+                        params![mn_id, zerocopy::AsBytes::as_bytes(embedding.as_slice())],
                     )?;
-                    result.created += 1;
-                    id
+                    (id, ImportOutcome::Updated)
                 }
-            };
-
-            // Import additional mnemonics
-            for mn_text in &fm.mnemonics {
+            }
+            None => {
+                let tags_json = serde_json::to_string(&entry.tags)?;
+                let embedding = embedder.embed(&entry.title)?;
+                self.conn().execute(
+                    "INSERT INTO memories (uuid, mnemonic, title, content, tags) VALUES (?1, ?2, ?3, ?4, ?5)",
+                    params![entry.uuid, entry.title, entry.title, entry.content, tags_json],
+                )?;
+                let id: i64 = self.conn().query_row(
+                    "SELECT id FROM memories WHERE uuid = ?1",
+                    params![entry.uuid],
+                    |row| row.get(0),
+                )?;
+                // Insert primary mnemonic
                 self.conn().execute(
                     "INSERT OR IGNORE INTO mnemonics (memory_id, text) VALUES (?1, ?2)",
-                    params![memory_id, mn_text],
+                    params![id, entry.title],
                 )?;
-                let mn_id: Option<i64> = self.conn().query_row(
+                let mn_id: i64 = self.conn().query_row(
                     "SELECT id FROM mnemonics WHERE text = ?1",
-                    params![mn_text],
+                    params![entry.title],
                     |row| row.get(0),
-                ).ok();
-                if let Some(mn_id) = mn_id {
-                    // Check if already has vector
-                    let has_vec: bool = self.conn().query_row(
-                        "SELECT COUNT(*) FROM mnemonic_vectors WHERE mnemonic_id = ?1",
-                        params![mn_id],
-                        |row| row.get::<_, i64>(0),
-                    ).map(|c| c > 0)?;
-                    if !has_vec {
-                        let emb = embedder.embed(mn_text)?;
-                        self.conn().execute(
-                            "INSERT INTO mnemonic_vectors (mnemonic_id, embedding) VALUES (?1, ?2)",
-                            params![mn_id, zerocopy::AsBytes::as_bytes(emb.as_slice())],
-                        )?;
-                    }
-                }
+                )?;
+                self.conn().execute(
+                    "INSERT INTO mnemonic_vectors (mnemonic_id, embedding) VALUES (?1, ?2)",
+                    params![mn_id, zerocopy::AsBytes::as_bytes(embedding.as_slice())],
+                )?;
+                (id, ImportOutcome::Created)
             }
-        }
-
-        // Recreate links from UUID references (second pass)
-        for entry in &entries {
-            let path = entry.path();
-            let raw = std::fs::read_to_string(&path)?;
-            let (fm, _) = parse_frontmatter(&raw).unwrap();
+        };
 
-            for link in &fm.links {
-                let source_id: Option<i64> = self
-                    .conn()
-                    .query_row(
-                        "SELECT id FROM memories WHERE uuid = ?1",
-                        params![fm.uuid],
-                        |row| row.get(0),
-                    )
-                    .ok();
-                let target_id: Option<i64> = self
+        // Import additional mnemonics
+        for mn_text in &entry.mnemonics {
+            self.conn().execute(
+                "INSERT OR IGNORE INTO mnemonics (memory_id, text) VALUES (?1, ?2)",
+                params![memory_id, mn_text],
+            )?;
+            let mn_id: Option<i64> = self
+                .conn()
+                .query_row(
+                    "SELECT id FROM mnemonics WHERE text = ?1",
+                    params![mn_text],
+                    |row| row.get(0),
+                )
+                .optional()?;
+            if let Some(mn_id) = mn_id {
+                // Check if already has vector
+                let has_vec: bool = self
                     .conn()
                     .query_row(
-                        "SELECT id FROM memories WHERE uuid = ?1",
-                        params![link.target],
-                        |row| row.get(0),
+                        "SELECT COUNT(*) FROM mnemonic_vectors WHERE mnemonic_id = ?1",
+                        params![mn_id],
+                        |row| row.get::<_, i64>(0),
                     )
-                    .ok();
-
-                if let (Some(sid), Some(tid)) = (source_id, target_id) {
+                    .map(|c| c > 0)?;
+                if !has_vec {
+                    let emb = embedder.embed(mn_text)?;
                     self.conn().execute(
-                        "INSERT OR IGNORE INTO memory_links (source_id, target_id, link_type) VALUES (?1, ?2, ?3)",
-                        params![sid, tid, link.link_type],
+                        "INSERT INTO mnemonic_vectors (mnemonic_id, embedding) VALUES (?1, ?2)",
+                        params![mn_id, zerocopy::AsBytes::as_bytes(emb.as_slice())],
                     )?;
                 }
             }
         }
 
-        Ok(result)
+        if outcome != ImportOutcome::Unchanged {
+            self.audit_change("memory.import", memory_id, before)?;
+        }
+        Ok(outcome)
+    }
+
+    /// Recreate a link between two memories identified by UUID, stamped with
+    /// `created_at` when known. Missing endpoints are ignored.
+    pub fn import_link(
+        &self,
+        source_uuid: &str,
+        target_uuid: &str,
+        link_type: &str,
+        created_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        let inserted = self.conn().execute(
+            "INSERT OR IGNORE INTO memory_links (source_id, target_id, link_type, created_at)
+             SELECT s.id, t.id, ?3, COALESCE(?4, datetime('now')) FROM memories s, memories t
+             WHERE s.uuid = ?1 AND t.uuid = ?2",
+            params![
+                source_uuid,
+                target_uuid,
+                link_type,
+                created_at.as_ref().map(format_sqlite_datetime)
+            ],
+        )?;
+        if inserted > 0
+            && let Some(target) = self.snapshot_by_uuid(target_uuid)?
+        {
+            self.audit_memory(
+                "memory.link",
+                source_uuid,
+                Some(&link_detail(link_type, &target.title)),
+            )?;
+        }
+        Ok(())
     }
 }
 
@@ -498,6 +440,7 @@ fn parse_frontmatter(raw: &str) -> Option<(Frontmatter, String)> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::storage::Storage;
     use crate::store::MemoryStore;
     use tempfile::TempDir;
 
@@ -523,6 +466,13 @@ mod tests {
         Ok(store)
     }
 
+    #[test]
+    fn test_export_format_from_str() {
+        assert_eq!("jsonl".parse::<ExportFormat>(), Ok(ExportFormat::Jsonl));
+        assert_eq!("md".parse::<ExportFormat>(), Ok(ExportFormat::Markdown));
+        assert!("csv".parse::<ExportFormat>().is_err());
+    }
+
     #[test]
     fn test_slugify() {
         assert_eq!(slugify("project design"), "project-design");
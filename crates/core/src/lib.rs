@@ -1,14 +1,39 @@
+pub mod archive;
+pub mod audit;
 pub mod auth_store;
+pub mod backup;
 pub mod config;
 pub mod embedder;
+pub mod error;
 pub mod export;
+pub mod maintenance;
+pub mod migrations;
+#[cfg(feature = "postgres")]
+pub mod postgres;
+pub mod ratings;
+pub mod secrets;
+pub mod storage;
 pub mod store;
+pub mod tune;
 
-pub use auth_store::{OAuthClient, OAuthProvider, Session, TokenPair, User, UserIdentity};
-pub use config::TriviaConfig;
+pub use archive::{ArchiveLink, ArchiveMnemonic, ArchiveRating, ArchiveRecord};
+pub use audit::{Actor, AuditEntry, AuditFilter, Transport, parse_since};
+pub use auth_store::{
+    ClientConsent, Group, LoginSecrets, OAuthClient, OAuthProvider, PendingAuthorization,
+    PendingConsent, PendingLogin, PersonalToken, Session, TokenGrant, TokenPair, User,
+    UserActivity, UserIdentity,
+};
+pub use backup::RestoreReport;
+pub use config::{RatingConfig, ScoringWeights, TriviaConfig, replace_scoring_section};
 pub use embedder::Embedder;
-pub use export::ImportResult;
+pub use error::{Result, TriviaError};
+pub use export::{ExportEntry, ExportFormat, ExportLink, ImportOutcome, ImportResult};
+pub use maintenance::{MaintenanceReport, run_maintenance};
+pub use migrations::Migration;
+pub use secrets::SecretKey;
+pub use storage::{Storage, is_postgres_url, open_storage};
 pub use store::{
-    EditResult, Memory, MemoryLink, MemoryStore, MemorizeNeighbor, MemorizeResult,
-    MergeCandidate, MemorySummary, ScoringConfig, TagCount,
+    EditResult, MemorizeNeighbor, MemorizeResult, Memory, MemoryLink, MemoryStore, MemorySummary,
+    MergeCandidate, Recall, ScoreFeatures, ScoringConfig, TagCount,
 };
+pub use tune::{RatedResult, TuneReport, tune};
@@ -0,0 +1,114 @@
+use serde::Serialize;
+
+use crate::error::Result;
+use crate::storage::Storage;
+use crate::store::MemoryStore;
+
+/// What one maintenance pass removed.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct MaintenanceReport {
+    /// OAuth token pairs and personal access tokens.
+    pub expired_tokens: usize,
+    pub expired_sessions: usize,
+    /// Authorization codes, and abandoned sign-ins and consent requests.
+    pub expired_codes: usize,
+    /// Old recalls nobody rated.
+    pub stale_recalls: usize,
+}
+
+impl MaintenanceReport {
+    pub fn removed(&self) -> usize {
+        self.expired_tokens + self.expired_sessions + self.expired_codes + self.stale_recalls
+    }
+}
+
+/// Delete expired OAuth and personal access tokens, web sessions,
+/// authorization codes and unrated recalls, then let the database tidy its
+/// indexes.
+pub fn run_maintenance(store: &dyn Storage) -> Result<MaintenanceReport> {
+    let report = MaintenanceReport {
+        expired_tokens: store.cleanup_expired_tokens()?,
+        expired_sessions: store.cleanup_expired_sessions()?,
+        expired_codes: store.cleanup_expired_codes()?,
+        stale_recalls: store.cleanup_unrated_recalls()?,
+    };
+    store.optimize()?;
+    Ok(report)
+}
+
+impl MemoryStore {
+    /// Merge the full-text index's segments and refresh the query planner's
+    /// statistics. Cheap when there is nothing to do.
+    pub fn optimize(&self) -> Result<()> {
+        self.conn().execute_batch(
+            "INSERT INTO memory_fts(memory_fts) VALUES('optimize'); PRAGMA optimize;",
+        )?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn removes_only_expired_rows() {
+        let store = MemoryStore::in_memory().unwrap();
+        let user = store.create_user("alice", "*:read").unwrap();
+        let (client, _) = store
+            .register_client(&["http://localhost/cb".into()], None)
+            .unwrap();
+        let live = store
+            .create_token_pair(&client.client_id, user.id, None)
+            .unwrap();
+        let refreshable = store
+            .create_token_pair(&client.client_id, user.id, None)
+            .unwrap();
+        let dead = store
+            .create_token_pair(&client.client_id, user.id, None)
+            .unwrap();
+        let session = store.create_session(user.id).unwrap();
+        let stale = store.create_session(user.id).unwrap();
+        store
+            .conn()
+            .execute_batch(&format!(
+                "UPDATE oauth_tokens SET expires_at = datetime('now', '-1 day')
+                   WHERE access_token_hash = '{}';
+                 UPDATE oauth_tokens SET expires_at = datetime('now', '-60 days'),
+                     refresh_expires_at = datetime('now', '-30 days')
+                   WHERE access_token_hash = '{}';
+                 UPDATE sessions SET expires_at = datetime('now', '-1 hour')
+                   WHERE session_id = '{}';",
+                crate::auth_store::sha256_hex(&refreshable.access_token),
+                crate::auth_store::sha256_hex(&dead.access_token),
+                stale.session_id,
+            ))
+            .unwrap();
+
+        let report = run_maintenance(&store).unwrap();
+        assert_eq!((report.expired_tokens, report.expired_sessions), (1, 1));
+        assert!(
+            store
+                .get_user_by_access_token(&live.access_token)
+                .unwrap()
+                .is_some()
+        );
+        // Past its access token's expiry, but the refresh token still works
+        assert!(
+            store
+                .get_user_by_refresh_token(&refreshable.refresh_token)
+                .unwrap()
+                .is_some()
+        );
+        assert!(
+            store
+                .get_user_by_refresh_token(&dead.refresh_token)
+                .unwrap()
+                .is_none()
+        );
+        assert!(store.get_session(&session.session_id).unwrap().is_some());
+
+        let again = run_maintenance(&store).unwrap();
+        assert_eq!(again.removed(), 0);
+    }
+}
@@ -0,0 +1,824 @@
+use anyhow::Context;
+use rusqlite::{Connection, Transaction, params};
+use std::path::{Path, PathBuf};
+
+use crate::error::Result;
+use crate::store::MemoryStore;
+
+/// A numbered schema migration. The database's `PRAGMA user_version` records
+/// the highest version applied; each migration runs in its own transaction.
+pub struct Migration {
+    pub version: i64,
+    pub description: &'static str,
+    up: fn(&Transaction) -> Result<()>,
+}
+
+/// All migrations, in order. Append new entries; never edit or reorder
+/// existing ones once released.
+static MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "baseline schema: memories, mnemonics, vectors, links, FTS, auth",
+        up: baseline,
+    },
+    Migration {
+        version: 2,
+        description: "drop legacy memory_vectors table",
+        up: drop_memory_vectors,
+    },
+    Migration {
+        version: 3,
+        description: "user groups",
+        up: user_groups,
+    },
+    Migration {
+        version: 4,
+        description: "memory owners",
+        up: memory_owners,
+    },
+    Migration {
+        version: 5,
+        description: "user last seen",
+        up: user_last_seen,
+    },
+    Migration {
+        version: 6,
+        description: "admin role and audit log",
+        up: admin_audit,
+    },
+    Migration {
+        version: 7,
+        description: "oauth token scopes",
+        up: token_scopes,
+    },
+    Migration {
+        version: 8,
+        description: "refresh token lifetimes",
+        up: refresh_lifetimes,
+    },
+    Migration {
+        version: 9,
+        description: "personal access tokens",
+        up: personal_tokens,
+    },
+    Migration {
+        version: 10,
+        description: "pending sign-ins",
+        up: pending_logins,
+    },
+    Migration {
+        version: 11,
+        description: "client consents",
+        up: client_consents,
+    },
+    Migration {
+        version: 12,
+        description: "audit transport",
+        up: audit_transport,
+    },
+    Migration {
+        version: 13,
+        description: "per-user ratings",
+        up: per_user_ratings,
+    },
+    Migration {
+        version: 14,
+        description: "recall log",
+        up: recall_log,
+    },
+    Migration {
+        version: 15,
+        description: "recall score components",
+        up: recall_features,
+    },
+];
+
+/// Columns of `recall_results` holding a result's score components, in the
+/// order of `ScoreFeatures::to_array`.
+pub(crate) const RECALL_FEATURE_COLUMNS: [&str; 8] = [
+    "similarity",
+    "recency",
+    "frequency",
+    "link",
+    "rating",
+    "query",
+    "tag_boost",
+    "fts",
+];
+
+/// The schema version a fully migrated database reports.
+pub fn latest_version() -> i64 {
+    MIGRATIONS.last().map(|m| m.version).unwrap_or(0)
+}
+
+fn has_table(conn: &Connection, name: &str) -> Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = ?1",
+        params![name],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM pragma_table_info(?1) WHERE name = ?2",
+        params![table, column],
+        |row| row.get(0),
+    )?;
+    Ok(count > 0)
+}
+
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+    if !has_column(conn, table, column)? {
+        conn.execute_batch(&format!("ALTER TABLE {table} ADD COLUMN {column} {decl};"))?;
+    }
+    Ok(())
+}
+
+/// Migration 1: the schema as it stood before versioning was introduced.
+///
+/// Databases created before `user_version` was tracked report version 0 and
+/// may be at any intermediate shape, so this step inspects the schema rather
+/// than assuming a fresh database. It only ever runs once per database.
+fn baseline(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS memories (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            mnemonic TEXT NOT NULL UNIQUE,
+            content TEXT NOT NULL,
+            tags TEXT DEFAULT '[]',
+            created_at TEXT DEFAULT (datetime('now')),
+            updated_at TEXT DEFAULT (datetime('now')),
+            recall_count INTEGER NOT NULL DEFAULT 0,
+            last_recalled_at TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS memory_links (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            source_id INTEGER NOT NULL REFERENCES memories(id) ON DELETE CASCADE,
+            target_id INTEGER NOT NULL REFERENCES memories(id) ON DELETE CASCADE,
+            link_type TEXT NOT NULL CHECK(link_type IN ('related', 'supersedes', 'derived_from')),
+            created_at TEXT DEFAULT (datetime('now')),
+            UNIQUE(source_id, target_id, link_type)
+        );",
+    )?;
+
+    add_column_if_missing(tx, "memories", "recall_count", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(tx, "memories", "last_recalled_at", "TEXT")?;
+    add_column_if_missing(tx, "memories", "uuid", "TEXT")?;
+    add_column_if_missing(tx, "memories", "useful_count", "INTEGER NOT NULL DEFAULT 0")?;
+    add_column_if_missing(
+        tx,
+        "memories",
+        "not_useful_count",
+        "INTEGER NOT NULL DEFAULT 0",
+    )?;
+    add_column_if_missing(tx, "memories", "title", "TEXT")?;
+
+    // Backfill UUIDs and titles for rows created before those columns existed
+    tx.execute_batch(
+        "UPDATE memories SET uuid = lower(hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' || substr(hex(randomblob(2)),2) || '-' || substr('89ab', abs(random()) % 4 + 1, 1) || substr(hex(randomblob(2)),2) || '-' || hex(randomblob(6))) WHERE uuid IS NULL;
+         UPDATE memories SET title = mnemonic WHERE title IS NULL;
+
+         CREATE UNIQUE INDEX IF NOT EXISTS idx_memories_uuid ON memories(uuid);
+         CREATE UNIQUE INDEX IF NOT EXISTS idx_memories_title ON memories(title);",
+    )?;
+
+    // Mnemonics: one row per alias, seeded with each memory's original mnemonic
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS mnemonics (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            memory_id INTEGER NOT NULL REFERENCES memories(id) ON DELETE CASCADE,
+            text TEXT NOT NULL UNIQUE,
+            created_at TEXT DEFAULT (datetime('now'))
+        );
+
+        INSERT OR IGNORE INTO mnemonics (memory_id, text, created_at)
+        SELECT id, mnemonic, created_at FROM memories;
+
+        CREATE VIRTUAL TABLE IF NOT EXISTS mnemonic_vectors USING vec0(
+            mnemonic_id INTEGER PRIMARY KEY,
+            embedding float[384]
+        );",
+    )?;
+
+    // Carry per-memory vectors over to the memory's primary mnemonic
+    if has_table(tx, "memory_vectors")? {
+        let mv_count: i64 = tx.query_row("SELECT COUNT(*) FROM mnemonic_vectors", [], |row| {
+            row.get(0)
+        })?;
+        if mv_count == 0 {
+            let rows: Vec<(i64, Vec<u8>)> = {
+                let mut stmt = tx.prepare(
+                    "SELECT mn.id, mv.embedding
+                     FROM memory_vectors mv
+                     JOIN memories m ON m.id = mv.memory_id
+                     JOIN mnemonics mn ON mn.memory_id = m.id AND mn.text = m.title",
+                )?;
+                stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+                    .collect::<std::result::Result<Vec<_>, _>>()?
+            };
+            for (mnemonic_id, embedding) in &rows {
+                tx.execute(
+                    "INSERT INTO mnemonic_vectors (mnemonic_id, embedding) VALUES (?1, ?2)",
+                    params![mnemonic_id, embedding],
+                )?;
+            }
+        }
+    }
+
+    // Full-text index over title + content. Older databases indexed the
+    // mnemonic column instead; rebuilding unconditionally covers both shapes.
+    tx.execute_batch(
+        "DROP TRIGGER IF EXISTS memory_fts_ai;
+         DROP TRIGGER IF EXISTS memory_fts_ad;
+         DROP TRIGGER IF EXISTS memory_fts_au;
+         DROP TABLE IF EXISTS memory_fts;
+
+         CREATE VIRTUAL TABLE memory_fts USING fts5(
+             title,
+             content,
+             content='memories',
+             content_rowid='id',
+             tokenize='porter unicode61'
+         );
+
+         CREATE TRIGGER memory_fts_ai AFTER INSERT ON memories BEGIN
+             INSERT INTO memory_fts(rowid, title, content)
+             VALUES (new.id, new.title, new.content);
+         END;
+
+         CREATE TRIGGER memory_fts_ad AFTER DELETE ON memories BEGIN
+             INSERT INTO memory_fts(memory_fts, rowid, title, content)
+             VALUES ('delete', old.id, old.title, old.content);
+         END;
+
+         CREATE TRIGGER memory_fts_au AFTER UPDATE ON memories BEGIN
+             INSERT INTO memory_fts(memory_fts, rowid, title, content)
+             VALUES ('delete', old.id, old.title, old.content);
+             INSERT INTO memory_fts(rowid, title, content)
+             VALUES (new.id, new.title, new.content);
+         END;
+
+         INSERT INTO memory_fts(memory_fts) VALUES ('rebuild');",
+    )?;
+
+    // Auth tables
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS users (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            username TEXT UNIQUE NOT NULL,
+            acl TEXT NOT NULL DEFAULT '*:none',
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS oauth_providers (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT UNIQUE NOT NULL,
+            provider_type TEXT NOT NULL,
+            client_id TEXT NOT NULL,
+            client_secret TEXT NOT NULL,
+            enabled INTEGER NOT NULL DEFAULT 1,
+            config TEXT NOT NULL DEFAULT '{}',
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS user_identities (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            provider_id INTEGER NOT NULL REFERENCES oauth_providers(id) ON DELETE CASCADE,
+            provider_username TEXT NOT NULL,
+            provider_user_id TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            UNIQUE(provider_id, provider_user_id)
+        );
+
+        CREATE TABLE IF NOT EXISTS oauth_clients (
+            client_id TEXT PRIMARY KEY,
+            client_secret_hash TEXT,
+            redirect_uris TEXT NOT NULL DEFAULT '[]',
+            client_name TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS oauth_codes (
+            code TEXT PRIMARY KEY,
+            client_id TEXT NOT NULL,
+            user_id INTEGER NOT NULL REFERENCES users(id),
+            code_challenge TEXT NOT NULL,
+            redirect_uri TEXT NOT NULL,
+            expires_at TEXT NOT NULL,
+            used INTEGER NOT NULL DEFAULT 0
+        );
+
+        CREATE TABLE IF NOT EXISTS oauth_tokens (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            access_token_hash TEXT UNIQUE NOT NULL,
+            refresh_token_hash TEXT UNIQUE,
+            client_id TEXT NOT NULL,
+            user_id INTEGER NOT NULL REFERENCES users(id),
+            expires_at TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS sessions (
+            session_id TEXT PRIMARY KEY,
+            user_id INTEGER NOT NULL REFERENCES users(id),
+            expires_at TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );",
+    )?;
+
+    Ok(())
+}
+
+/// Migration 2: vectors live in `mnemonic_vectors` (one per alias) now;
+/// the per-memory table was only kept in sync for backward compatibility.
+fn drop_memory_vectors(tx: &Transaction) -> Result<()> {
+    tx.execute_batch("DROP TABLE IF EXISTS memory_vectors;")?;
+    Ok(())
+}
+
+/// Migration 3: named groups with their own ACL spec, and user membership.
+fn user_groups(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS groups (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            name TEXT UNIQUE NOT NULL,
+            acl TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+
+        CREATE TABLE IF NOT EXISTS user_groups (
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            group_id INTEGER NOT NULL REFERENCES groups(id) ON DELETE CASCADE,
+            PRIMARY KEY (user_id, group_id)
+        );",
+    )?;
+    Ok(())
+}
+
+/// Migration 4: the user who created a memory. Existing memories stay
+/// unowned; deleting a user leaves their memories unowned too.
+fn memory_owners(tx: &Transaction) -> Result<()> {
+    add_column_if_missing(
+        tx,
+        "memories",
+        "owner_id",
+        "INTEGER REFERENCES users(id) ON DELETE SET NULL",
+    )?;
+    Ok(())
+}
+
+/// Migration 5: when a user last made an authenticated request.
+fn user_last_seen(tx: &Transaction) -> Result<()> {
+    add_column_if_missing(tx, "users", "last_seen_at", "TEXT")?;
+    Ok(())
+}
+
+/// Migration 6: an admin flag on users, and a log of changes made through
+/// the admin API.
+fn admin_audit(tx: &Transaction) -> Result<()> {
+    add_column_if_missing(tx, "users", "is_admin", "INTEGER NOT NULL DEFAULT 0")?;
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS audit_log (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            actor TEXT,
+            action TEXT NOT NULL,
+            target TEXT,
+            detail TEXT
+        );
+        CREATE INDEX IF NOT EXISTS audit_log_created_at ON audit_log (created_at);",
+    )?;
+    Ok(())
+}
+
+/// Migration 7: the scope granted with an authorization code and carried
+/// over to the tokens issued for it. NULL on rows from before scopes.
+fn token_scopes(tx: &Transaction) -> Result<()> {
+    add_column_if_missing(tx, "oauth_codes", "scope", "TEXT")?;
+    add_column_if_missing(tx, "oauth_tokens", "scope", "TEXT")?;
+    Ok(())
+}
+
+/// Migration 8: when a token pair's refresh token expires, and when the
+/// user originally authorized the client, which caps how long refreshing
+/// can go on. Existing pairs keep the 30 days after access token expiry
+/// they had before.
+fn refresh_lifetimes(tx: &Transaction) -> Result<()> {
+    add_column_if_missing(tx, "oauth_tokens", "refresh_expires_at", "TEXT")?;
+    add_column_if_missing(tx, "oauth_tokens", "auth_time", "TEXT")?;
+    tx.execute_batch(
+        "UPDATE oauth_tokens SET refresh_expires_at = datetime(expires_at, '+30 days')
+           WHERE refresh_expires_at IS NULL;
+         UPDATE oauth_tokens SET auth_time = created_at WHERE auth_time IS NULL;",
+    )?;
+    Ok(())
+}
+
+/// Migration 9: named bearer tokens users create for themselves, stored
+/// hashed like OAuth tokens. They go when their user does.
+fn personal_tokens(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS personal_tokens (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            name TEXT NOT NULL,
+            token_hash TEXT UNIQUE NOT NULL,
+            scope TEXT NOT NULL,
+            expires_at TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            last_used_at TEXT,
+            UNIQUE (user_id, name)
+        );",
+    )?;
+    Ok(())
+}
+
+/// Migration 10: sign-ins waiting on the upstream provider, keyed by a hash
+/// of the `state` sent to it, with the PKCE verifier and nonce the provider
+/// needs back. The client columns are NULL for web UI sign-ins.
+fn pending_logins(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS pending_logins (
+            state_hash TEXT PRIMARY KEY,
+            provider TEXT NOT NULL,
+            client_id TEXT,
+            redirect_uri TEXT,
+            code_challenge TEXT,
+            scope TEXT,
+            client_state TEXT,
+            code_verifier TEXT,
+            nonce TEXT,
+            expires_at TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );",
+    )?;
+    Ok(())
+}
+
+/// Migration 11: the clients each user has approved and for which scope,
+/// and authorizations waiting on the consent page, keyed by a hash of the
+/// token in its form.
+fn client_consents(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS oauth_consents (
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            client_id TEXT NOT NULL REFERENCES oauth_clients(client_id) ON DELETE CASCADE,
+            scope TEXT NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now')),
+            PRIMARY KEY (user_id, client_id)
+        );
+
+        CREATE TABLE IF NOT EXISTS pending_consents (
+            token_hash TEXT PRIMARY KEY,
+            user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            client_id TEXT NOT NULL,
+            redirect_uri TEXT NOT NULL,
+            code_challenge TEXT NOT NULL,
+            scope TEXT NOT NULL,
+            client_state TEXT NOT NULL,
+            expires_at TEXT NOT NULL
+        );",
+    )?;
+    Ok(())
+}
+
+/// Migration 12: which transport (cli, stdio, http, web) each audited
+/// change came through, and an index for looking up a memory's history.
+fn audit_transport(tx: &Transaction) -> Result<()> {
+    add_column_if_missing(tx, "audit_log", "transport", "TEXT")?;
+    tx.execute_batch("CREATE INDEX IF NOT EXISTS audit_log_target ON audit_log (target);")?;
+    Ok(())
+}
+
+/// Migration 13: one row per rating, attributed to its rater. The old
+/// counters can't be told apart by rater, so they are kept as each memory's
+/// legacy counts, which scoring weighs as a signal of their own.
+fn per_user_ratings(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS ratings (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            memory_id INTEGER NOT NULL REFERENCES memories(id) ON DELETE CASCADE,
+            rater TEXT,
+            useful INTEGER NOT NULL,
+            query TEXT,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE INDEX IF NOT EXISTS ratings_memory_rater ON ratings (memory_id, rater);",
+    )?;
+    if !has_column(tx, "memories", "legacy_useful")? {
+        add_column_if_missing(
+            tx,
+            "memories",
+            "legacy_useful",
+            "INTEGER NOT NULL DEFAULT 0",
+        )?;
+        add_column_if_missing(
+            tx,
+            "memories",
+            "legacy_not_useful",
+            "INTEGER NOT NULL DEFAULT 0",
+        )?;
+        tx.execute_batch(
+            "UPDATE memories SET legacy_useful = useful_count, legacy_not_useful = not_useful_count;",
+        )?;
+    }
+    Ok(())
+}
+
+/// Migration 14: each recall's query embedding and the memories it
+/// returned, so a rating can name the recall it was given for.
+fn recall_log(tx: &Transaction) -> Result<()> {
+    tx.execute_batch(
+        "CREATE TABLE IF NOT EXISTS recalls (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            uuid TEXT NOT NULL UNIQUE,
+            username TEXT,
+            embedding BLOB NOT NULL,
+            created_at TEXT NOT NULL DEFAULT (datetime('now'))
+        );
+        CREATE TABLE IF NOT EXISTS recall_results (
+            recall_id INTEGER NOT NULL REFERENCES recalls(id) ON DELETE CASCADE,
+            memory_id INTEGER NOT NULL REFERENCES memories(id) ON DELETE CASCADE,
+            rank INTEGER NOT NULL,
+            PRIMARY KEY (recall_id, memory_id)
+        );
+        CREATE INDEX IF NOT EXISTS recalls_created_at ON recalls (created_at);",
+    )?;
+    add_column_if_missing(
+        tx,
+        "ratings",
+        "recall_id",
+        "INTEGER REFERENCES recalls(id) ON DELETE SET NULL",
+    )?;
+    tx.execute_batch("CREATE INDEX IF NOT EXISTS ratings_recall ON ratings (recall_id);")?;
+    Ok(())
+}
+
+/// Migration 15: the unweighted score components of each recall result,
+/// which `trivia tune` fits weights to. Results logged before have none.
+fn recall_features(tx: &Transaction) -> Result<()> {
+    for column in RECALL_FEATURE_COLUMNS {
+        add_column_if_missing(tx, "recall_results", column, "REAL")?;
+    }
+    Ok(())
+}
+
+impl MemoryStore {
+    /// Current schema version (`PRAGMA user_version`).
+    pub fn schema_version(&self) -> Result<i64> {
+        Ok(self
+            .conn()
+            .query_row("PRAGMA user_version", [], |row| row.get(0))?)
+    }
+
+    /// Migrations that have not yet been applied to this database.
+    pub fn pending_migrations(&self) -> Result<Vec<&'static Migration>> {
+        let current = self.schema_version()?;
+        Ok(MIGRATIONS.iter().filter(|m| m.version > current).collect())
+    }
+
+    pub fn has_pending_migrations(&self) -> Result<bool> {
+        Ok(!self.pending_migrations()?.is_empty())
+    }
+
+    /// True if the database holds any tables, i.e. it is not freshly created.
+    pub fn has_existing_schema(&self) -> Result<bool> {
+        let count: i64 = self.conn().query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table'",
+            [],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
+    }
+
+    /// Apply all pending migrations in order, each in its own transaction.
+    /// Returns the migrations that were applied.
+    pub fn migrate(&self) -> Result<Vec<&'static Migration>> {
+        let pending = self.pending_migrations()?;
+        for migration in &pending {
+            let tx = self.conn().unchecked_transaction()?;
+            (migration.up)(&tx).with_context(|| {
+                format!(
+                    "migration {} ({}) failed",
+                    migration.version, migration.description
+                )
+            })?;
+            tx.pragma_update(None, "user_version", migration.version)?;
+            tx.commit()?;
+        }
+        Ok(pending)
+    }
+
+    /// Copy the database to a timestamped file next to `db_path` so a failed
+    /// or unwanted migration can be rolled back by hand.
+    pub fn backup_before_migrate(&self, db_path: &Path) -> Result<PathBuf> {
+        let backup = backup_path(db_path, self.schema_version()?);
+        self.conn()
+            .execute("VACUUM INTO ?1", params![backup.to_string_lossy()])
+            .with_context(|| format!("backing up database to {}", backup.display()))?;
+        Ok(backup)
+    }
+}
+
+/// `trivia.db` at version 1 → `trivia.db.v1-20260101T120000.bak`
+fn backup_path(db_path: &Path, version: i64) -> PathBuf {
+    let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%S");
+    let mut name = db_path
+        .file_name()
+        .map(|n| n.to_os_string())
+        .unwrap_or_else(|| "trivia.db".into());
+    name.push(format!(".v{version}-{stamp}.bak"));
+    db_path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{Actor, Transport};
+    use crate::store::register_sqlite_vec;
+    use tempfile::TempDir;
+    use zerocopy::AsBytes;
+
+    /// Create a database in the shape produced by releases that predate
+    /// versioned migrations: no title/uuid columns, FTS over the mnemonic,
+    /// and vectors keyed by memory id.
+    fn create_legacy_db(path: &Path) -> Result<()> {
+        register_sqlite_vec();
+        let conn = Connection::open(path)?;
+        let emb: Vec<f32> = vec![0.1; 384];
+        conn.execute_batch(
+            "CREATE TABLE memories (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                mnemonic TEXT NOT NULL UNIQUE,
+                content TEXT NOT NULL,
+                tags TEXT DEFAULT '[]',
+                created_at TEXT DEFAULT (datetime('now')),
+                updated_at TEXT DEFAULT (datetime('now'))
+            );
+            CREATE VIRTUAL TABLE memory_vectors USING vec0(
+                memory_id INTEGER PRIMARY KEY,
+                embedding float[384]
+            );
+            CREATE VIRTUAL TABLE memory_fts USING fts5(
+                mnemonic, content, content='memories', content_rowid='id'
+            );
+            CREATE TRIGGER memory_fts_ai AFTER INSERT ON memories BEGIN
+                INSERT INTO memory_fts(rowid, mnemonic, content)
+                VALUES (new.id, new.mnemonic, new.content);
+            END;
+            INSERT INTO memories (mnemonic, content, tags)
+            VALUES ('legacy fact', 'kept across upgrades', '[\"old\"]');",
+        )?;
+        conn.execute(
+            "INSERT INTO memory_vectors (memory_id, embedding) VALUES (1, ?1)",
+            params![emb.as_bytes()],
+        )?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_fresh_store_is_at_latest_version() -> Result<()> {
+        let store = MemoryStore::in_memory()?;
+        assert_eq!(store.schema_version()?, latest_version());
+        assert!(store.pending_migrations()?.is_empty());
+        assert!(!has_table(store.conn(), "memory_vectors")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_is_noop_when_current() -> Result<()> {
+        let store = MemoryStore::in_memory()?;
+        assert!(store.migrate()?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_legacy_db_upgrade() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("trivia.db");
+        create_legacy_db(&path)?;
+
+        let store = MemoryStore::open_unmigrated(&path)?;
+        assert_eq!(store.schema_version()?, 0);
+        assert_eq!(store.pending_migrations()?.len(), MIGRATIONS.len());
+        drop(store);
+
+        let store = MemoryStore::new(&path)?;
+        assert_eq!(store.schema_version()?, latest_version());
+
+        let mem = store.get_memory_by_mnemonic("legacy fact")?.unwrap();
+        assert_eq!(mem.content, "kept across upgrades");
+        assert_eq!(mem.tags, vec!["old"]);
+        assert_eq!(mem.mnemonics, vec!["legacy fact"]);
+
+        // Vector carried over to the primary mnemonic; legacy table gone
+        let vectors: i64 =
+            store
+                .conn()
+                .query_row("SELECT COUNT(*) FROM mnemonic_vectors", [], |row| {
+                    row.get(0)
+                })?;
+        assert_eq!(vectors, 1);
+        assert!(!has_table(store.conn(), "memory_vectors")?);
+
+        // FTS was rebuilt over title + content
+        let hits: i64 = store.conn().query_row(
+            "SELECT COUNT(*) FROM memory_fts WHERE memory_fts MATCH 'upgrades'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(hits, 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ratings_keep_legacy_counts() -> Result<()> {
+        let mut store = MemoryStore::in_memory()?;
+        for (i, (title, useful, not_useful)) in
+            [("lopsided", 40, 3), ("tied", 2, 2), ("unrated", 0, 0)]
+                .into_iter()
+                .enumerate()
+        {
+            let mut emb = vec![0.0_f32; 384];
+            emb[i] = 1.0;
+            store.memorize(title, "counted before ratings were attributed", &[], &emb)?;
+            store.conn().execute(
+                "UPDATE memories SET useful_count = ?1, not_useful_count = ?2 WHERE title = ?3",
+                params![useful, not_useful, title],
+            )?;
+        }
+        store.conn().execute_batch(
+            "DROP TABLE ratings;
+             ALTER TABLE memories DROP COLUMN legacy_useful;
+             ALTER TABLE memories DROP COLUMN legacy_not_useful;",
+        )?;
+
+        let tx = store.conn().unchecked_transaction()?;
+        per_user_ratings(&tx)?;
+        // Ratings gained their recall id in a later migration
+        recall_log(&tx)?;
+        tx.commit()?;
+
+        let counts = |store: &MemoryStore, title: &str| -> Result<(i64, i64)> {
+            let mem = store.get_memory_by_mnemonic(title)?.unwrap();
+            Ok((mem.useful_count, mem.not_useful_count))
+        };
+        assert_eq!(counts(&store, "lopsided")?, (40, 3));
+        assert_eq!(counts(&store, "tied")?, (2, 2));
+        let ratings: i64 = store
+            .conn()
+            .query_row("SELECT COUNT(*) FROM ratings", [], |row| row.get(0))?;
+        assert_eq!(ratings, 0);
+
+        let signals = store.rating_signals(&[1, 2, 3])?;
+        assert!(signals["lopsided"] > 0.0);
+        assert_eq!(signals["tied"], 0.0);
+        assert!(!signals.contains_key("unrated"));
+
+        // New verdicts add to the legacy counts rather than replacing them
+        store.set_actor(Actor::new(Some("alice"), Transport::Cli));
+        store.rate("tied", false, None, None)?;
+        assert_eq!(counts(&store, "tied")?, (2, 3));
+        assert!(store.rating_signals(&[2])?["tied"] < 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_backup_created_before_migrating() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("trivia.db");
+        create_legacy_db(&path)?;
+
+        let (_, reported) = MemoryStore::open_with_migration_report(&path)?;
+
+        let backups: Vec<_> = std::fs::read_dir(dir.path())?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_name().to_string_lossy().ends_with(".bak"))
+            .collect();
+        assert_eq!(backups.len(), 1);
+        assert_eq!(reported, Some(backups[0].path()));
+
+        // The backup is the untouched legacy database
+        let backup = MemoryStore::open_unmigrated(&backups[0].path())?;
+        assert_eq!(backup.schema_version()?, 0);
+        assert!(has_table(backup.conn(), "memory_vectors")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_no_backup_for_new_database() -> Result<()> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("trivia.db");
+        assert_eq!(MemoryStore::open_with_migration_report(&path)?.1, None);
+        assert_eq!(MemoryStore::open_with_migration_report(&path)?.1, None);
+
+        let files = std::fs::read_dir(dir.path())?.count();
+        assert_eq!(files, 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,3903 @@
+//! Postgres + pgvector storage backend, enabled by the `postgres` feature.
+//!
+//! Mirrors `MemoryStore` table for table, with two simplifications that
+//! Postgres allows: each mnemonic's embedding lives in a `vector(384)` column
+//! on the `mnemonics` row (so it cascades with it), and tags are a `TEXT[]`.
+
+use chrono::{DateTime, Duration, Utc};
+use postgres::types::ToSql;
+use postgres::{Client, GenericClient, Row};
+use postgres_native_tls::MakeTlsConnector;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tokio::runtime::{Handle, RuntimeFlavor};
+use uuid::Uuid;
+
+use crate::archive::{ArchiveLink, ArchiveMnemonic, ArchiveRating, ArchiveRecord, encode_vector};
+use crate::audit::{Actor, AuditEntry, AuditFilter, MemorySnapshot, change_detail};
+use crate::auth_store::{
+    ClientConsent, Group, LoginSecrets, OAuthClient, OAuthCode, OAuthProvider,
+    PENDING_LOGIN_MINUTES, PERSONAL_TOKEN_PREFIX, PendingAuthorization, PendingConsent,
+    PendingLogin, PersonalToken, Session, TokenGrant, TokenPair, User, UserActivity, UserIdentity,
+    generate_random_string, sha256_hex,
+};
+use crate::config::{AuthConfig, RatingConfig, ScoringWeights};
+use crate::embedder::Embedder;
+use crate::error::{Result, TriviaError};
+use crate::export::{ExportEntry, ExportLink, ImportOutcome};
+use crate::migrations::RECALL_FEATURE_COLUMNS;
+use crate::ratings::{
+    LatestRating, LegacyCounts, RecallVerdict, UNRATED_RECALL_DAYS, query_signals_from,
+    require_recaller, signals_from_latest,
+};
+use crate::secrets::{self, SecretKey};
+use crate::storage::Storage;
+use crate::store::{
+    AUTO_LINK_MAX_NEIGHBORS, EditResult, MemorizeResult, Memory, MemoryLink, MemorySummary,
+    MergeCandidate, Neighbor, Recall, ScoreFeatures, ScoringConfig, TagCount, edited_tags,
+    gather_candidates, link_detail, merged_content, passes_tag_filters, plan_neighbors, union_tags,
+};
+use crate::tune::RatedResult;
+
+struct PgMigration {
+    version: i64,
+    description: &'static str,
+    sql: &'static str,
+}
+
+/// Applied in order and recorded in `schema_migrations`. Append only.
+static PG_MIGRATIONS: &[PgMigration] = &[PgMigration {
+    version: 1,
+    description: "baseline schema: memories, mnemonics, vectors, links, FTS, auth",
+    sql: "
+        CREATE EXTENSION IF NOT EXISTS vector;
+
+        CREATE TABLE memories (
+            id BIGSERIAL PRIMARY KEY,
+            uuid TEXT NOT NULL UNIQUE,
+            title TEXT NOT NULL UNIQUE,
+            content TEXT NOT NULL,
+            tags TEXT[] NOT NULL DEFAULT '{}',
+            created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+            recall_count BIGINT NOT NULL DEFAULT 0,
+            last_recalled_at TIMESTAMPTZ,
+            useful_count BIGINT NOT NULL DEFAULT 0,
+            not_useful_count BIGINT NOT NULL DEFAULT 0,
+            fts TSVECTOR GENERATED ALWAYS AS (to_tsvector('english', title || ' ' || content)) STORED
+        );
+        CREATE INDEX memories_fts_idx ON memories USING GIN (fts);
+        CREATE INDEX memories_tags_idx ON memories USING GIN (tags);
+
+        CREATE TABLE mnemonics (
+            id BIGSERIAL PRIMARY KEY,
+            memory_id BIGINT NOT NULL REFERENCES memories(id) ON DELETE CASCADE,
+            text TEXT NOT NULL UNIQUE,
+            embedding vector(384),
+            created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        );
+        CREATE INDEX mnemonics_memory_id_idx ON mnemonics (memory_id);
+
+        -- HNSW needs pgvector >= 0.5; older installs fall back to a scan
+        DO $$
+        BEGIN
+            IF EXISTS (SELECT 1 FROM pg_am WHERE amname = 'hnsw') THEN
+                CREATE INDEX mnemonics_embedding_idx ON mnemonics USING hnsw (embedding vector_l2_ops);
+            END IF;
+        END
+        $$;
+
+        CREATE TABLE memory_links (
+            id BIGSERIAL PRIMARY KEY,
+            source_id BIGINT NOT NULL REFERENCES memories(id) ON DELETE CASCADE,
+            target_id BIGINT NOT NULL REFERENCES memories(id) ON DELETE CASCADE,
+            link_type TEXT NOT NULL CHECK (link_type IN ('related', 'supersedes', 'derived_from')),
+            created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+            UNIQUE (source_id, target_id, link_type)
+        );
+
+        CREATE TABLE users (
+            id BIGSERIAL PRIMARY KEY,
+            username TEXT UNIQUE NOT NULL,
+            acl TEXT NOT NULL DEFAULT '*:none',
+            created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+            updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        );
+
+        CREATE TABLE oauth_providers (
+            id BIGSERIAL PRIMARY KEY,
+            name TEXT UNIQUE NOT NULL,
+            provider_type TEXT NOT NULL,
+            client_id TEXT NOT NULL,
+            client_secret TEXT NOT NULL,
+            enabled BOOLEAN NOT NULL DEFAULT TRUE,
+            config TEXT NOT NULL DEFAULT '{}',
+            created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        );
+
+        CREATE TABLE user_identities (
+            id BIGSERIAL PRIMARY KEY,
+            user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            provider_id BIGINT NOT NULL REFERENCES oauth_providers(id) ON DELETE CASCADE,
+            provider_username TEXT NOT NULL,
+            provider_user_id TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+            UNIQUE (provider_id, provider_user_id)
+        );
+
+        CREATE TABLE oauth_clients (
+            client_id TEXT PRIMARY KEY,
+            client_secret_hash TEXT,
+            redirect_uris TEXT[] NOT NULL DEFAULT '{}',
+            client_name TEXT,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        );
+
+        CREATE TABLE oauth_codes (
+            code TEXT PRIMARY KEY,
+            client_id TEXT NOT NULL,
+            user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            code_challenge TEXT NOT NULL,
+            redirect_uri TEXT NOT NULL,
+            expires_at TIMESTAMPTZ NOT NULL,
+            used BOOLEAN NOT NULL DEFAULT FALSE
+        );
+
+        CREATE TABLE oauth_tokens (
+            id BIGSERIAL PRIMARY KEY,
+            access_token_hash TEXT UNIQUE NOT NULL,
+            refresh_token_hash TEXT UNIQUE,
+            client_id TEXT NOT NULL,
+            user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            expires_at TIMESTAMPTZ NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        );
+
+        CREATE TABLE sessions (
+            session_id TEXT PRIMARY KEY,
+            user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            expires_at TIMESTAMPTZ NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        );
+    ",
+}, PgMigration {
+    version: 2,
+    description: "user groups",
+    sql: "
+        CREATE TABLE groups (
+            id BIGSERIAL PRIMARY KEY,
+            name TEXT UNIQUE NOT NULL,
+            acl TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        );
+
+        CREATE TABLE user_groups (
+            user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            group_id BIGINT NOT NULL REFERENCES groups(id) ON DELETE CASCADE,
+            PRIMARY KEY (user_id, group_id)
+        );
+    ",
+}, PgMigration {
+    version: 3,
+    description: "memory owners",
+    sql: "
+        ALTER TABLE memories ADD COLUMN owner_id BIGINT REFERENCES users(id) ON DELETE SET NULL;
+    ",
+}, PgMigration {
+    version: 4,
+    description: "user last seen",
+    sql: "
+        ALTER TABLE users ADD COLUMN last_seen_at TIMESTAMPTZ;
+    ",
+}, PgMigration {
+    version: 5,
+    description: "admin role and audit log",
+    sql: "
+        ALTER TABLE users ADD COLUMN is_admin BOOLEAN NOT NULL DEFAULT FALSE;
+
+        CREATE TABLE audit_log (
+            id BIGSERIAL PRIMARY KEY,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+            actor TEXT,
+            action TEXT NOT NULL,
+            target TEXT,
+            detail TEXT
+        );
+        CREATE INDEX audit_log_created_at ON audit_log (created_at);
+    ",
+}, PgMigration {
+    version: 6,
+    description: "oauth token scopes",
+    sql: "
+        ALTER TABLE oauth_codes ADD COLUMN scope TEXT;
+        ALTER TABLE oauth_tokens ADD COLUMN scope TEXT;
+    ",
+}, PgMigration {
+    version: 7,
+    description: "refresh token lifetimes",
+    sql: "
+        ALTER TABLE oauth_tokens ADD COLUMN refresh_expires_at TIMESTAMPTZ;
+        ALTER TABLE oauth_tokens ADD COLUMN auth_time TIMESTAMPTZ;
+        UPDATE oauth_tokens SET refresh_expires_at = expires_at + interval '30 days', auth_time = created_at;
+    ",
+}, PgMigration {
+    version: 8,
+    description: "personal access tokens",
+    sql: "
+        CREATE TABLE personal_tokens (
+            id BIGSERIAL PRIMARY KEY,
+            user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            name TEXT NOT NULL,
+            token_hash TEXT UNIQUE NOT NULL,
+            scope TEXT NOT NULL,
+            expires_at TIMESTAMPTZ,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+            last_used_at TIMESTAMPTZ,
+            UNIQUE (user_id, name)
+        );
+    ",
+}, PgMigration {
+    version: 9,
+    description: "pending sign-ins",
+    sql: "
+        CREATE TABLE pending_logins (
+            state_hash TEXT PRIMARY KEY,
+            provider TEXT NOT NULL,
+            client_id TEXT,
+            redirect_uri TEXT,
+            code_challenge TEXT,
+            scope TEXT,
+            client_state TEXT,
+            code_verifier TEXT,
+            nonce TEXT,
+            expires_at TIMESTAMPTZ NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        );
+    ",
+}, PgMigration {
+    version: 10,
+    description: "client consents",
+    sql: "
+        CREATE TABLE oauth_consents (
+            user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            client_id TEXT NOT NULL REFERENCES oauth_clients(client_id) ON DELETE CASCADE,
+            scope TEXT NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+            PRIMARY KEY (user_id, client_id)
+        );
+        CREATE TABLE pending_consents (
+            token_hash TEXT PRIMARY KEY,
+            user_id BIGINT NOT NULL REFERENCES users(id) ON DELETE CASCADE,
+            client_id TEXT NOT NULL,
+            redirect_uri TEXT NOT NULL,
+            code_challenge TEXT NOT NULL,
+            scope TEXT NOT NULL,
+            client_state TEXT NOT NULL,
+            expires_at TIMESTAMPTZ NOT NULL
+        );
+    ",
+}, PgMigration {
+    version: 11,
+    description: "audit transport",
+    sql: "
+        ALTER TABLE audit_log ADD COLUMN transport TEXT;
+        CREATE INDEX audit_log_target ON audit_log (target);
+    ",
+}, PgMigration {
+    version: 12,
+    description: "per-user ratings",
+    sql: "
+        CREATE TABLE ratings (
+            id BIGSERIAL PRIMARY KEY,
+            memory_id BIGINT NOT NULL REFERENCES memories(id) ON DELETE CASCADE,
+            rater TEXT,
+            useful BOOLEAN NOT NULL,
+            query TEXT,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        );
+        CREATE INDEX ratings_memory_rater ON ratings (memory_id, rater);
+
+        ALTER TABLE memories
+            ADD COLUMN legacy_useful BIGINT NOT NULL DEFAULT 0,
+            ADD COLUMN legacy_not_useful BIGINT NOT NULL DEFAULT 0;
+        UPDATE memories SET legacy_useful = useful_count, legacy_not_useful = not_useful_count;
+    ",
+}, PgMigration {
+    version: 13,
+    description: "recall log",
+    sql: "
+        CREATE TABLE recalls (
+            id BIGSERIAL PRIMARY KEY,
+            uuid TEXT NOT NULL UNIQUE,
+            username TEXT,
+            embedding REAL[] NOT NULL,
+            created_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        );
+        CREATE TABLE recall_results (
+            recall_id BIGINT NOT NULL REFERENCES recalls(id) ON DELETE CASCADE,
+            memory_id BIGINT NOT NULL REFERENCES memories(id) ON DELETE CASCADE,
+            rank INTEGER NOT NULL,
+            PRIMARY KEY (recall_id, memory_id)
+        );
+        CREATE INDEX recalls_created_at ON recalls (created_at);
+        ALTER TABLE ratings ADD COLUMN recall_id BIGINT REFERENCES recalls(id) ON DELETE SET NULL;
+        CREATE INDEX ratings_recall ON ratings (recall_id);
+    ",
+}, PgMigration {
+    version: 14,
+    description: "recall score components",
+    sql: "
+        ALTER TABLE recall_results
+            ADD COLUMN similarity DOUBLE PRECISION,
+            ADD COLUMN recency DOUBLE PRECISION,
+            ADD COLUMN frequency DOUBLE PRECISION,
+            ADD COLUMN link DOUBLE PRECISION,
+            ADD COLUMN rating DOUBLE PRECISION,
+            ADD COLUMN query DOUBLE PRECISION,
+            ADD COLUMN tag_boost DOUBLE PRECISION,
+            ADD COLUMN fts DOUBLE PRECISION;
+    ",
+}];
+
+/// The synchronous `postgres` client drives its own Tokio runtime, which
+/// panics when entered from inside another one (axum handlers, the MCP
+/// server). Inside a multi-threaded runtime each call is made through
+/// `block_in_place`, which moves the worker's other tasks to the rest of
+/// the pool while this one waits on the database.
+fn off_runtime<T>(f: impl FnOnce() -> Result<T>) -> Result<T> {
+    match Handle::try_current() {
+        Err(_) => f(),
+        Ok(handle) if handle.runtime_flavor() == RuntimeFlavor::MultiThread => {
+            tokio::task::block_in_place(f)
+        }
+        Ok(_) => Err(TriviaError::storage(
+            "the Postgres backend needs a multi-threaded Tokio runtime",
+        )),
+    }
+}
+
+pub struct PgStore {
+    client: Mutex<Client>,
+    scoring: ScoringConfig,
+    auth: AuthConfig,
+    secret_key: Option<SecretKey>,
+    actor: Actor,
+    ratings: RatingConfig,
+}
+
+impl PgStore {
+    /// Connect to `url` (a libpq-style `postgres://` URL) and apply any
+    /// pending migrations. TLS is negotiated according to `sslmode`.
+    pub fn connect(url: &str) -> Result<Self> {
+        let client = off_runtime(|| {
+            let tls = MakeTlsConnector::new(
+                native_tls::TlsConnector::new().map_err(|e| TriviaError::Storage(e.into()))?,
+            );
+            Ok(Client::connect(url, tls)?)
+        })?;
+        let store = Self {
+            client: Mutex::new(client),
+            scoring: ScoringConfig::default(),
+            auth: AuthConfig::default(),
+            secret_key: None,
+            actor: Actor::default(),
+            ratings: RatingConfig::default(),
+        };
+        store.migrate()?;
+        Ok(store)
+    }
+
+    fn with_client<T>(&self, f: impl FnOnce(&mut Client) -> Result<T>) -> Result<T> {
+        let mut guard = self
+            .client
+            .lock()
+            .map_err(|_| TriviaError::storage("postgres connection lock poisoned"))?;
+        off_runtime(|| f(&mut guard))
+    }
+
+    /// See `MemoryStore::reveal_provider`.
+    fn reveal_provider(&self, mut provider: OAuthProvider) -> Result<OAuthProvider> {
+        provider.client_secret = secrets::reveal(
+            self.secret_key.as_ref(),
+            &provider.name,
+            &provider.client_secret,
+        )?;
+        Ok(provider)
+    }
+
+    /// Highest migration version applied to this database.
+    pub fn schema_version(&self) -> Result<i64> {
+        self.with_client(|c| {
+            c.batch_execute(
+                "CREATE TABLE IF NOT EXISTS schema_migrations (
+                    version BIGINT PRIMARY KEY,
+                    description TEXT NOT NULL,
+                    applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+                )",
+            )?;
+            let row = c.query_one(
+                "SELECT COALESCE(MAX(version), 0) FROM schema_migrations",
+                &[],
+            )?;
+            Ok(row.get(0))
+        })
+    }
+
+    /// Apply pending migrations, each in its own transaction. Returns the
+    /// versions applied.
+    pub fn migrate(&self) -> Result<Vec<i64>> {
+        let current = self.schema_version()?;
+        self.with_client(|c| {
+            let mut applied = Vec::new();
+            for migration in PG_MIGRATIONS.iter().filter(|m| m.version > current) {
+                let mut tx = c.transaction()?;
+                tx.batch_execute(migration.sql).map_err(|e| {
+                    TriviaError::Storage(anyhow::anyhow!(
+                        "migration {} ({}) failed: {e}",
+                        migration.version,
+                        migration.description
+                    ))
+                })?;
+                tx.execute(
+                    "INSERT INTO schema_migrations (version, description) VALUES ($1, $2)",
+                    &[&migration.version, &migration.description],
+                )?;
+                tx.commit()?;
+                applied.push(migration.version);
+            }
+            Ok(applied)
+        })
+    }
+
+    fn memorize_inner(
+        &self,
+        mnemonic: &str,
+        content: &str,
+        tags: &[String],
+        embedding: &[f32],
+        skip_merge: bool,
+        owner: Option<&str>,
+    ) -> Result<MemorizeResult> {
+        self.with_client(|c| {
+            let mut tx = c.transaction()?;
+
+            let existing: Option<i64> = tx
+                .query_opt("SELECT memory_id FROM mnemonics WHERE text = $1", &[&mnemonic])?
+                .map(|row| row.get(0));
+            let before = match existing {
+                Some(mid) => snapshot_by_id(&mut tx, mid)?,
+                None => None,
+            };
+
+            let memory_id: i64 = match existing {
+                Some(mid) => {
+                    tx.execute(
+                        "UPDATE memories SET content = $1, tags = $2, updated_at = now() WHERE id = $3",
+                        &[&content, &tags, &mid],
+                    )?;
+                    mid
+                }
+                None => {
+                    let new_uuid = Uuid::new_v4().to_string();
+                    let mid: i64 = tx
+                        .query_one(
+                            "INSERT INTO memories (uuid, title, content, tags)
+                             VALUES ($1, $2, $3, $4)
+                             ON CONFLICT (title) DO UPDATE SET
+                                content = EXCLUDED.content,
+                                tags = EXCLUDED.tags,
+                                updated_at = now()
+                             RETURNING id",
+                            &[&new_uuid, &mnemonic, &content, &tags],
+                        )?
+                        .get(0);
+                    tx.execute(
+                        "INSERT INTO mnemonics (memory_id, text) VALUES ($1, $2)
+                         ON CONFLICT (text) DO NOTHING",
+                        &[&mid, &mnemonic],
+                    )?;
+                    mid
+                }
+            };
+
+            tx.execute(
+                "UPDATE mnemonics SET embedding = $1::real[]::vector WHERE text = $2",
+                &[&embedding, &mnemonic],
+            )?;
+
+            // Find nearby memories
+            let fetch = ((AUTO_LINK_MAX_NEIGHBORS + 1) * 3) as i64;
+            let neighbors = tx.query(
+                "SELECT mn.memory_id, mn.embedding <-> $1::real[]::vector AS distance, m.title, m.tags
+                 FROM mnemonics mn
+                 JOIN memories m ON m.id = mn.memory_id
+                 WHERE mn.embedding IS NOT NULL
+                 ORDER BY distance
+                 LIMIT $2",
+                &[&embedding, &fetch],
+            )?;
+            let plan = plan_neighbors(
+                neighbors.into_iter().map(|row| Neighbor {
+                    memory_id: row.get(0),
+                    distance: row.get(1),
+                    title: row.get(2),
+                    tags: row.get(3),
+                }),
+                memory_id,
+                skip_merge,
+            );
+
+            let mut absorbed = None;
+            let merged_with = if let Some(old) = &plan.merge {
+                absorbed = snapshot_by_id(&mut tx, old.memory_id)?;
+                let old_content: String = tx
+                    .query_one("SELECT content FROM memories WHERE id = $1", &[&old.memory_id])?
+                    .get(0);
+                tx.execute(
+                    "UPDATE memories SET content = $1, tags = $2, updated_at = now() WHERE id = $3",
+                    &[&merged_content(content, &old_content), &union_tags(tags, &old.tags), &memory_id],
+                )?;
+
+                // The absorbed memory's aliases keep their text but lose their
+                // vectors, matching the SQLite backend.
+                tx.execute(
+                    "UPDATE mnemonics SET memory_id = $1, embedding = NULL WHERE memory_id = $2",
+                    &[&memory_id, &old.memory_id],
+                )?;
+                absorb_links(&mut tx, memory_id, old.memory_id)?;
+                tx.execute("DELETE FROM memories WHERE id = $1", &[&old.memory_id])?;
+
+                Some(old.title.clone())
+            } else {
+                for neighbor_mid in &plan.link {
+                    tx.execute(
+                        "INSERT INTO memory_links (source_id, target_id, link_type)
+                         VALUES ($1, $2, 'related')
+                         ON CONFLICT DO NOTHING",
+                        &[&memory_id, neighbor_mid],
+                    )?;
+                }
+                None
+            };
+
+            if let Some(after) = snapshot_by_id(&mut tx, memory_id)? {
+                let action = if before.is_some() { "memory.update" } else { "memory.create" };
+                let detail = change_detail(before.as_ref(), Some(&after));
+                audit_memory(&mut tx, &self.actor, action, &after.uuid, detail.as_deref())?;
+                if let Some(absorbed) = &absorbed {
+                    let detail = format!("absorbed {absorbed} ({})", absorbed.uuid);
+                    audit_memory(&mut tx, &self.actor, "memory.merge", &after.uuid, Some(&detail))?;
+                }
+            }
+
+            let created = existing.is_none() && merged_with.is_none();
+            if created && owner.is_some() {
+                set_owner_in(&mut tx, &self.actor, mnemonic, owner)?;
+            }
+
+            tx.commit()?;
+            Ok(MemorizeResult {
+                created,
+                merged_with,
+                neighbors: plan.reported,
+            })
+        })
+    }
+}
+
+/// Move every link touching `discard_id` onto `keep_id`, drop self-links,
+/// and record that `keep_id` supersedes `discard_id`.
+fn absorb_links(c: &mut impl GenericClient, keep_id: i64, discard_id: i64) -> Result<()> {
+    c.execute(
+        "INSERT INTO memory_links (source_id, target_id, link_type, created_at)
+         SELECT $1, target_id, link_type, created_at FROM memory_links WHERE source_id = $2
+         ON CONFLICT DO NOTHING",
+        &[&keep_id, &discard_id],
+    )?;
+    c.execute(
+        "INSERT INTO memory_links (source_id, target_id, link_type, created_at)
+         SELECT source_id, $1, link_type, created_at FROM memory_links WHERE target_id = $2
+         ON CONFLICT DO NOTHING",
+        &[&keep_id, &discard_id],
+    )?;
+    c.execute("DELETE FROM memory_links WHERE source_id = target_id", &[])?;
+    c.execute(
+        "INSERT INTO memory_links (source_id, target_id, link_type) VALUES ($1, $2, 'supersedes')
+         ON CONFLICT DO NOTHING",
+        &[&keep_id, &discard_id],
+    )?;
+    Ok(())
+}
+
+/// Full archive records, optionally for a single UUID or restricted to
+/// memories carrying any of `tags`, ordered by title.
+/// See `MemoryStore::snapshot_by_id` and friends.
+fn snapshot_where(
+    c: &mut impl GenericClient,
+    condition: &str,
+    value: &(dyn ToSql + Sync),
+) -> Result<Option<MemorySnapshot>> {
+    Ok(c.query_opt(
+        &format!("SELECT uuid, title, tags, content FROM memories WHERE {condition}"),
+        &[value],
+    )?
+    .map(|row| MemorySnapshot::new(row.get(0), row.get(1), row.get(2), &row.get::<_, String>(3))))
+}
+
+fn snapshot_by_id(c: &mut impl GenericClient, id: i64) -> Result<Option<MemorySnapshot>> {
+    snapshot_where(c, "id = $1", &id)
+}
+
+fn snapshot_by_title(c: &mut impl GenericClient, title: &str) -> Result<Option<MemorySnapshot>> {
+    snapshot_where(c, "title = $1", &title)
+}
+
+fn insert_audit(
+    c: &mut impl GenericClient,
+    actor: Option<&str>,
+    transport: &str,
+    action: &str,
+    target: Option<&str>,
+    detail: Option<&str>,
+) -> Result<()> {
+    c.execute(
+        "INSERT INTO audit_log (actor, action, target, detail, transport) VALUES ($1, $2, $3, $4, $5)",
+        &[&actor, &action, &target, &detail, &transport],
+    )?;
+    Ok(())
+}
+
+/// See `MemoryStore::audit_memory`.
+fn audit_memory(
+    c: &mut impl GenericClient,
+    actor: &Actor,
+    action: &str,
+    uuid: &str,
+    detail: Option<&str>,
+) -> Result<()> {
+    insert_audit(
+        c,
+        actor.username.as_deref(),
+        actor.transport.as_str(),
+        action,
+        Some(uuid),
+        detail,
+    )
+}
+
+fn set_owner_in(
+    c: &mut impl GenericClient,
+    actor: &Actor,
+    title: &str,
+    username: Option<&str>,
+) -> Result<()> {
+    let owner_id: Option<i64> = match username {
+        Some(name) => Some(
+            c.query_opt("SELECT id FROM users WHERE username = $1", &[&name])?
+                .ok_or_else(|| TriviaError::NotFound(format!("user not found: {}", name)))?
+                .get(0),
+        ),
+        None => None,
+    };
+    let row = c
+        .query_opt(
+            "SELECT m.uuid, u.username FROM memories m LEFT JOIN users u ON u.id = m.owner_id
+             WHERE m.title = $1",
+            &[&title],
+        )?
+        .ok_or_else(|| TriviaError::NotFound(format!("memory not found: {}", title)))?;
+    let uuid: String = row.get(0);
+    let previous_owner: Option<String> = row.get(1);
+    c.execute(
+        "UPDATE memories SET owner_id = $1 WHERE title = $2",
+        &[&owner_id, &title],
+    )?;
+    let detail = format!(
+        "owner: {} -> {}",
+        previous_owner.as_deref().unwrap_or("none"),
+        username.unwrap_or("none")
+    );
+    audit_memory(c, actor, "memory.set_owner", &uuid, Some(&detail))
+}
+
+/// See `MemoryStore::audit_change`.
+fn audit_change(
+    c: &mut impl GenericClient,
+    actor: &Actor,
+    action: &str,
+    memory_id: i64,
+    before: Option<MemorySnapshot>,
+) -> Result<()> {
+    let after = snapshot_by_id(c, memory_id)?;
+    if let Some(uuid) = after.as_ref().or(before.as_ref()).map(|m| m.uuid.clone()) {
+        let detail = change_detail(before.as_ref(), after.as_ref());
+        audit_memory(c, actor, action, &uuid, detail.as_deref())?;
+    }
+    Ok(())
+}
+
+/// See `ratings::LATEST_PER_RATER`.
+const LATEST_PER_RATER: &str = "r.recall_id IS NULL AND r.id = (SELECT MAX(l.id) FROM ratings l
+    WHERE l.memory_id = r.memory_id AND l.rater IS NOT DISTINCT FROM r.rater AND l.recall_id IS NULL)";
+
+/// See `ratings::LATEST_PER_RATER_AND_RECALL`.
+const LATEST_PER_RATER_AND_RECALL: &str = "r.id = (SELECT MAX(l.id) FROM ratings l
+    WHERE l.memory_id = r.memory_id AND l.rater IS NOT DISTINCT FROM r.rater
+    AND l.recall_id = r.recall_id)";
+
+/// See `MemoryStore::record_rating`.
+fn record_rating(
+    c: &mut impl GenericClient,
+    actor: &Actor,
+    memory_id: i64,
+    useful: bool,
+    query: Option<&str>,
+    recall: Option<i64>,
+) -> Result<()> {
+    c.execute(
+        "INSERT INTO ratings (memory_id, rater, useful, query, recall_id) VALUES ($1, $2, $3, $4, $5)",
+        &[&memory_id, &actor.username, &useful, &query, &recall],
+    )?;
+    refresh_rating_counts(c, memory_id)
+}
+
+/// See `MemoryStore::log_recall`.
+fn log_recall(
+    c: &mut impl GenericClient,
+    actor: &Actor,
+    query_embedding: &[f32],
+    titles: &[&str],
+    features: &[ScoreFeatures],
+) -> Result<String> {
+    let uuid = Uuid::new_v4().to_string();
+    let recall: i64 = c
+        .query_one(
+            "INSERT INTO recalls (uuid, username, embedding) VALUES ($1, $2, $3) RETURNING id",
+            &[&uuid, &actor.username, &query_embedding],
+        )?
+        .get(0);
+    let sql = format!(
+        "INSERT INTO recall_results (recall_id, memory_id, rank, {})
+         SELECT $1, id, $2, $4, $5, $6, $7, $8, $9, $10, $11 FROM memories WHERE title = $3
+         ON CONFLICT DO NOTHING",
+        RECALL_FEATURE_COLUMNS.join(", ")
+    );
+    for (rank, (title, features)) in titles.iter().zip(features).enumerate() {
+        c.execute(
+            &sql,
+            &[
+                &recall,
+                &(rank as i32),
+                title,
+                &features.similarity,
+                &features.recency,
+                &features.frequency,
+                &features.link,
+                &features.rating,
+                &features.query,
+                &features.tag_boost,
+                &features.fts,
+            ],
+        )?;
+    }
+    Ok(uuid)
+}
+
+/// See `MemoryStore::recall_row_id`.
+fn recall_row_id(c: &mut impl GenericClient, actor: &Actor, uuid: &str) -> Result<i64> {
+    let row = c
+        .query_opt("SELECT id, username FROM recalls WHERE uuid = $1", &[&uuid])?
+        .ok_or_else(|| TriviaError::not_found(format!("recall not found: {uuid}")))?;
+    let username: Option<String> = row.get(1);
+    require_recaller(uuid, username.as_deref(), actor.username.as_deref())?;
+    Ok(row.get(0))
+}
+
+/// See `MemoryStore::require_recalled`.
+fn require_recalled(
+    c: &mut impl GenericClient,
+    recall: i64,
+    memory_id: i64,
+    title: &str,
+) -> Result<()> {
+    let returned: bool = c
+        .query_one(
+            "SELECT EXISTS (SELECT 1 FROM recall_results WHERE recall_id = $1 AND memory_id = $2)",
+            &[&recall, &memory_id],
+        )?
+        .get(0);
+    if returned {
+        Ok(())
+    } else {
+        Err(TriviaError::validation(format!(
+            "{title} was not returned by that recall"
+        )))
+    }
+}
+
+/// See `MemoryStore::query_signals`.
+fn query_signals(
+    c: &mut impl GenericClient,
+    memory_ids: &[i64],
+    query_embedding: &[f32],
+    config: &RatingConfig,
+) -> Result<HashMap<String, f64>> {
+    let verdicts: Vec<RecallVerdict> = c
+        .query(
+            &format!(
+                "SELECT m.title, r.rater, r.useful, rc.embedding, r.created_at
+                 FROM ratings r
+                 JOIN recalls rc ON rc.id = r.recall_id
+                 JOIN memories m ON m.id = r.memory_id
+                 WHERE r.memory_id = ANY($1) AND {LATEST_PER_RATER_AND_RECALL}"
+            ),
+            &[&memory_ids],
+        )?
+        .into_iter()
+        .map(|row| RecallVerdict {
+            title: row.get(0),
+            rater: row.get(1),
+            useful: row.get(2),
+            embedding: row.get(3),
+            created_at: row.get(4),
+        })
+        .collect();
+    Ok(query_signals_from(
+        &verdicts,
+        query_embedding,
+        config,
+        Utc::now(),
+    ))
+}
+
+/// See `MemoryStore::refresh_rating_counts`.
+fn refresh_rating_counts(c: &mut impl GenericClient, memory_id: i64) -> Result<()> {
+    c.execute(
+        &format!(
+            "UPDATE memories SET
+                useful_count = legacy_useful + (SELECT COUNT(*) FROM ratings r
+                    WHERE r.memory_id = $1 AND r.useful AND {LATEST_PER_RATER}),
+                not_useful_count = legacy_not_useful + (SELECT COUNT(*) FROM ratings r
+                    WHERE r.memory_id = $1 AND NOT r.useful AND {LATEST_PER_RATER})
+             WHERE id = $1"
+        ),
+        &[&memory_id],
+    )?;
+    Ok(())
+}
+
+/// See `MemoryStore::rating_signals`.
+fn rating_signals(
+    c: &mut impl GenericClient,
+    memory_ids: &[i64],
+    actor: &Actor,
+    config: &RatingConfig,
+) -> Result<HashMap<String, f64>> {
+    let latest: Vec<LatestRating> = c
+        .query(
+            &format!(
+                "SELECT m.title, r.rater, r.useful, r.created_at
+                 FROM ratings r
+                 JOIN memories m ON m.id = r.memory_id
+                 WHERE r.memory_id = ANY($1) AND {LATEST_PER_RATER}"
+            ),
+            &[&memory_ids],
+        )?
+        .into_iter()
+        .map(|row| LatestRating {
+            title: row.get(0),
+            rater: row.get(1),
+            useful: row.get(2),
+            created_at: row.get(3),
+        })
+        .collect();
+    let legacy: Vec<LegacyCounts> = c
+        .query(
+            "SELECT title, legacy_useful, legacy_not_useful
+             FROM memories
+             WHERE id = ANY($1) AND (legacy_useful <> 0 OR legacy_not_useful <> 0)",
+            &[&memory_ids],
+        )?
+        .into_iter()
+        .map(|row| LegacyCounts {
+            title: row.get(0),
+            useful: row.get(1),
+            not_useful: row.get(2),
+        })
+        .collect();
+    Ok(signals_from_latest(
+        &latest,
+        &legacy,
+        actor.username.as_deref(),
+        config,
+        Utc::now(),
+    ))
+}
+
+fn load_archive_records(
+    c: &mut impl GenericClient,
+    uuid: Option<&str>,
+    tags: Option<&[String]>,
+    with_vectors: bool,
+) -> Result<Vec<ArchiveRecord>> {
+    let rows = c.query(
+        "SELECT id, uuid, title, content, tags, created_at, updated_at, recall_count,
+                last_recalled_at, useful_count, not_useful_count, legacy_useful, legacy_not_useful
+         FROM memories
+         WHERE ($1::text IS NULL OR uuid = $1) AND ($2::text[] IS NULL OR tags && $2)
+         ORDER BY title",
+        &[&uuid, &tags],
+    )?;
+    let mut records = Vec::with_capacity(rows.len());
+    for row in rows {
+        let memory_id: i64 = row.get(0);
+        let mnemonics = c
+            .query(
+                "SELECT text, created_at, CASE WHEN $2 THEN embedding::real[] END
+                 FROM mnemonics WHERE memory_id = $1 ORDER BY id",
+                &[&memory_id, &with_vectors],
+            )?
+            .into_iter()
+            .map(|mn| ArchiveMnemonic {
+                text: mn.get(0),
+                created_at: mn.get(1),
+                embedding: mn.get::<_, Option<Vec<f32>>>(2).map(|v| encode_vector(&v)),
+            })
+            .collect();
+        let links = c
+            .query(
+                "SELECT t.uuid, ml.link_type, ml.created_at
+                 FROM memory_links ml
+                 JOIN memories t ON t.id = ml.target_id
+                 WHERE ml.source_id = $1
+                 ORDER BY ml.id",
+                &[&memory_id],
+            )?
+            .into_iter()
+            .map(|link| ArchiveLink {
+                target: link.get(0),
+                link_type: link.get(1),
+                created_at: link.get(2),
+            })
+            .collect();
+        let ratings = c
+            .query(
+                "SELECT rater, useful, query, created_at FROM ratings WHERE memory_id = $1 ORDER BY id",
+                &[&memory_id],
+            )?
+            .into_iter()
+            .map(|rating| ArchiveRating {
+                rater: rating.get(0),
+                useful: rating.get(1),
+                query: rating.get(2),
+                created_at: rating.get(3),
+            })
+            .collect();
+        records.push(ArchiveRecord {
+            uuid: row.get(1),
+            title: row.get(2),
+            content: row.get(3),
+            tags: row.get(4),
+            created_at: row.get(5),
+            updated_at: row.get(6),
+            recall_count: row.get(7),
+            last_recalled_at: row.get(8),
+            useful_count: row.get(9),
+            not_useful_count: row.get(10),
+            legacy_useful: row.get(11),
+            legacy_not_useful: row.get(12),
+            mnemonics,
+            links,
+            ratings,
+            embedding_model: with_vectors.then(|| crate::embedder::MODEL_ID.to_string()),
+        });
+    }
+    Ok(records)
+}
+
+fn find_memory_id(c: &mut impl GenericClient, title: &str) -> Result<Option<i64>> {
+    Ok(
+        c.query_opt("SELECT id FROM memories WHERE title = $1", &[&title])?
+            .map(|row| row.get(0)),
+    )
+}
+
+fn memory_id_by_title(c: &mut impl GenericClient, title: &str) -> Result<i64> {
+    find_memory_id(c, title)?
+        .ok_or_else(|| TriviaError::NotFound(format!("memory not found: {}", title)))
+}
+
+fn mnemonics_for_memory(c: &mut impl GenericClient, memory_id: i64) -> Result<Vec<String>> {
+    Ok(c.query(
+        "SELECT text FROM mnemonics WHERE memory_id = $1 ORDER BY id",
+        &[&memory_id],
+    )?
+    .into_iter()
+    .map(|row| row.get(0))
+    .collect())
+}
+
+fn links_for(c: &mut impl GenericClient, title: &str) -> Result<Vec<MemoryLink>> {
+    Ok(c.query(
+        "SELECT s.title, t.title, ml.link_type, ml.created_at
+             FROM memory_links ml
+             JOIN memories s ON s.id = ml.source_id
+             JOIN memories t ON t.id = ml.target_id
+             WHERE s.title = $1 OR t.title = $1",
+        &[&title],
+    )?
+    .iter()
+    .map(link_from_row)
+    .collect())
+}
+
+fn link_from_row(row: &Row) -> MemoryLink {
+    MemoryLink {
+        source_mnemonic: row.get(0),
+        target_mnemonic: row.get(1),
+        link_type: row.get(2),
+        created_at: row.get(3),
+    }
+}
+
+/// Re-point the mnemonic row for `text` at `embedding`.
+fn set_embedding(c: &mut impl GenericClient, text: &str, embedding: &[f32]) -> Result<()> {
+    c.execute(
+        "UPDATE mnemonics SET embedding = $1::real[]::vector WHERE text = $2",
+        &[&embedding, &text],
+    )?;
+    Ok(())
+}
+
+const USER_COLUMNS: &str = "u.id, u.username, u.acl, u.is_admin, u.created_at, u.updated_at";
+const GRANT_COLUMNS: &str =
+    "t.client_id, t.scope, t.expires_at, t.refresh_expires_at, t.created_at";
+
+fn user_from_row(row: &Row, offset: usize) -> User {
+    User {
+        id: row.get(offset),
+        username: row.get(offset + 1),
+        acl: row.get(offset + 2),
+        is_admin: row.get(offset + 3),
+        created_at: row.get(offset + 4),
+        updated_at: row.get(offset + 5),
+    }
+}
+
+const GROUP_COLUMNS: &str = "g.id, g.name, g.acl, g.created_at";
+
+fn group_from_row(row: &Row) -> Group {
+    Group {
+        id: row.get(0),
+        name: row.get(1),
+        acl: row.get(2),
+        created_at: row.get(3),
+    }
+}
+
+const PROVIDER_COLUMNS: &str =
+    "id, name, provider_type, client_id, client_secret, enabled, config, created_at";
+
+/// A `TokenGrant` from `t.client_id, t.scope, {USER_COLUMNS}`.
+fn grant_from_row(row: &Row) -> TokenGrant {
+    TokenGrant {
+        client_id: row.get(0),
+        scope: row.get(1),
+        expires_at: row.get(2),
+        refresh_expires_at: row.get(3),
+        issued_at: row.get(4),
+        user: user_from_row(row, 5),
+    }
+}
+
+const CONSENT_COLUMNS: &str = "c.client_id, c.client_name, c.redirect_uris, o.scope, o.created_at";
+
+fn consent_from_row(row: &Row) -> ClientConsent {
+    ClientConsent {
+        client_id: row.get(0),
+        client_name: row.get(1),
+        redirect_uris: row.get(2),
+        scope: row.get(3),
+        created_at: row.get(4),
+    }
+}
+
+const PERSONAL_TOKEN_COLUMNS: &str =
+    "p.id, p.user_id, p.name, p.scope, p.expires_at, p.created_at, p.last_used_at";
+
+fn personal_token_from_row(row: &Row) -> PersonalToken {
+    PersonalToken {
+        id: row.get(0),
+        user_id: row.get(1),
+        name: row.get(2),
+        scope: row.get(3),
+        expires_at: row.get(4),
+        created_at: row.get(5),
+        last_used_at: row.get(6),
+    }
+}
+
+/// See `auth_store::insert_token_pair`.
+fn insert_token_pair(
+    c: &mut impl GenericClient,
+    auth: &AuthConfig,
+    client_id: &str,
+    user_id: i64,
+    scope: Option<&str>,
+    auth_time: DateTime<Utc>,
+) -> Result<TokenPair> {
+    let access_token = generate_random_string(48);
+    let refresh_token = generate_random_string(48);
+    let now = Utc::now();
+    let refresh_expires_at =
+        (now + auth.refresh_token_ttl()).min(auth_time + auth.refresh_token_max());
+    let expires_at = (now + auth.access_token_ttl()).min(refresh_expires_at);
+    c.execute(
+        "INSERT INTO oauth_tokens
+            (access_token_hash, refresh_token_hash, client_id, user_id, expires_at, scope,
+             refresh_expires_at, auth_time)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        &[
+            &sha256_hex(&access_token),
+            &sha256_hex(&refresh_token),
+            &client_id,
+            &user_id,
+            &expires_at,
+            &scope,
+            &refresh_expires_at,
+            &auth_time,
+        ],
+    )?;
+    Ok(TokenPair {
+        access_token,
+        refresh_token,
+        expires_at,
+        scope: scope.map(String::from),
+    })
+}
+
+fn provider_from_row(row: &Row) -> OAuthProvider {
+    OAuthProvider {
+        id: row.get(0),
+        name: row.get(1),
+        provider_type: row.get(2),
+        client_id: row.get(3),
+        client_secret: row.get(4),
+        enabled: row.get(5),
+        config: row.get(6),
+        created_at: row.get(7),
+    }
+}
+
+impl Storage for PgStore {
+    fn set_boost_tags(&mut self, tags: Vec<String>) {
+        self.scoring.boost_tags = tags;
+    }
+
+    fn set_scoring_weights(&mut self, weights: &ScoringWeights) {
+        self.scoring.apply_weights(weights);
+    }
+
+    fn set_auth_config(&mut self, auth: AuthConfig) {
+        self.auth = auth;
+    }
+
+    fn set_rating_config(&mut self, ratings: RatingConfig) {
+        self.ratings = ratings;
+    }
+
+    fn set_secret_key(&mut self, key: Option<SecretKey>) {
+        self.secret_key = key;
+    }
+
+    fn set_actor(&mut self, actor: Actor) -> Actor {
+        std::mem::replace(&mut self.actor, actor)
+    }
+
+    fn backup_to(&self, _dest: &Path) -> Result<()> {
+        Err(TriviaError::validation(
+            "file backups are only supported for SQLite; use pg_dump for Postgres",
+        ))
+    }
+
+    fn database_path(&self) -> Option<PathBuf> {
+        None
+    }
+
+    fn optimize(&self) -> Result<()> {
+        // Autovacuum keeps statistics and indexes in shape on Postgres
+        Ok(())
+    }
+
+    fn rated_results(&self) -> Result<Vec<RatedResult>> {
+        let columns: Vec<String> = RECALL_FEATURE_COLUMNS
+            .iter()
+            .map(|c| format!("rr.{c}"))
+            .collect();
+        self.with_client(|c| {
+            Ok(c.query(
+                &format!(
+                    "SELECT rr.recall_id, r.useful, {}
+                         FROM ratings r
+                         JOIN recall_results rr
+                           ON rr.recall_id = r.recall_id AND rr.memory_id = r.memory_id
+                         WHERE rr.similarity IS NOT NULL AND {LATEST_PER_RATER_AND_RECALL}
+                         ORDER BY r.id",
+                    columns.join(", ")
+                ),
+                &[],
+            )?
+            .into_iter()
+            .map(|row| RatedResult {
+                recall_id: row.get(0),
+                useful: row.get(1),
+                features: ScoreFeatures::from_array(std::array::from_fn(|j| row.get(j + 2))),
+            })
+            .collect())
+        })
+    }
+
+    fn cleanup_unrated_recalls(&self) -> Result<usize> {
+        self.with_client(|c| {
+            Ok(c.execute(
+                "DELETE FROM recalls
+                 WHERE created_at < now() - make_interval(days => $1)
+                 AND NOT EXISTS (SELECT 1 FROM ratings WHERE ratings.recall_id = recalls.id)",
+                &[&(UNRATED_RECALL_DAYS as i32)],
+            )? as usize)
+        })
+    }
+
+    fn memorize(
+        &self,
+        mnemonic: &str,
+        content: &str,
+        tags: &[String],
+        embedding: &[f32],
+    ) -> Result<MemorizeResult> {
+        self.memorize_inner(mnemonic, content, tags, embedding, false, None)
+    }
+
+    fn memorize_with_options(
+        &self,
+        mnemonic: &str,
+        content: &str,
+        tags: &[String],
+        embedding: &[f32],
+        skip_merge: bool,
+        owner: Option<&str>,
+    ) -> Result<MemorizeResult> {
+        self.memorize_inner(mnemonic, content, tags, embedding, skip_merge, owner)
+    }
+
+    fn recall_filtered(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        tags: Option<&[String]>,
+        fts_query: Option<&str>,
+        exclude_tags: Option<&[String]>,
+        readable: &dyn Fn(&Memory) -> bool,
+    ) -> Result<Recall> {
+        self.with_client(|c| {
+            let candidates = gather_candidates(limit, tags.is_some(), |fetch_limit| {
+                let rows = c.query(
+                    "SELECT mn.memory_id, m.title, m.content, m.tags,
+                            mn.embedding <-> $1::real[]::vector AS distance,
+                            m.created_at, m.updated_at, m.recall_count, m.last_recalled_at,
+                            m.useful_count, m.not_useful_count,
+                            ARRAY(SELECT x.text FROM mnemonics x WHERE x.memory_id = m.id ORDER BY x.id),
+                            u.username
+                     FROM mnemonics mn
+                     JOIN memories m ON m.id = mn.memory_id
+                     LEFT JOIN users u ON u.id = m.owner_id
+                     WHERE mn.embedding IS NOT NULL
+                     ORDER BY distance
+                     LIMIT $2",
+                    &[&query_embedding, &(fetch_limit as i64)],
+                )?;
+                let fetched = rows.len();
+
+                // Deduplicate by memory_id, keeping the best distance
+                let mut seen = HashSet::new();
+                let memories: Vec<(i64, Memory)> = rows
+                    .into_iter()
+                    .filter(|row| seen.insert(row.get::<_, i64>(0)))
+                    .map(|row| {
+                        let mem = Memory {
+                            mnemonic: row.get(1),
+                            content: row.get(2),
+                            tags: row.get(3),
+                            mnemonics: row.get(11),
+                            distance: row.get(4),
+                            score: 0.0,
+                            created_at: row.get(5),
+                            updated_at: row.get(6),
+                            recall_count: row.get(7),
+                            last_recalled_at: row.get(8),
+                            useful_count: row.get(9),
+                            not_useful_count: row.get(10),
+                            links: Vec::new(),
+                            owner: row.get(12),
+                        };
+                        (row.get(0), mem)
+                    })
+                    .filter(|(_, mem)| passes_tag_filters(mem, tags, exclude_tags) && readable(mem))
+                    .collect();
+                Ok((fetched, memories))
+            })?;
+            let (memory_ids, mut memories): (Vec<i64>, Vec<Memory>) = candidates.into_iter().unzip();
+
+            let fts_matches: HashSet<String> = match fts_query {
+                Some(q) if !q.is_empty() => c
+                    .query(
+                        "SELECT title FROM memories WHERE fts @@ phraseto_tsquery('english', $1)",
+                        &[&q],
+                    )?
+                    .into_iter()
+                    .map(|row| row.get(0))
+                    .collect(),
+                _ => HashSet::new(),
+            };
+
+            for mem in &mut memories {
+                mem.links = links_for(c, &mem.mnemonic)?;
+            }
+
+            let ratings = rating_signals(c, &memory_ids, &self.actor, &self.ratings)?;
+            let query_feedback = query_signals(c, &memory_ids, query_embedding, &self.ratings)?;
+            let features = self
+                .scoring
+                .rank(&mut memories, &fts_matches, &ratings, &query_feedback, limit);
+
+            let titles: Vec<&str> = memories.iter().map(|m| m.mnemonic.as_str()).collect();
+            if !titles.is_empty() {
+                c.execute(
+                    "UPDATE memories SET recall_count = recall_count + 1, last_recalled_at = now()
+                     WHERE title = ANY($1)",
+                    &[&titles],
+                )?;
+            }
+
+            let id = log_recall(c, &self.actor, query_embedding, &titles, &features)?;
+            Ok(Recall { id, memories })
+        })
+    }
+
+    fn get_memory_by_mnemonic(&self, title: &str) -> Result<Option<Memory>> {
+        self.with_client(|c| {
+            let Some(row) = c.query_opt(
+                "SELECT m.id, m.title, m.content, m.tags, m.created_at, m.updated_at,
+                        m.recall_count, m.last_recalled_at, m.useful_count, m.not_useful_count,
+                        u.username
+                 FROM memories m LEFT JOIN users u ON u.id = m.owner_id
+                 WHERE m.title = $1",
+                &[&title],
+            )?
+            else {
+                return Ok(None);
+            };
+            let mnemonics = mnemonics_for_memory(c, row.get(0))?;
+            let links = links_for(c, title)?;
+            Ok(Some(Memory {
+                mnemonic: row.get(1),
+                content: row.get(2),
+                tags: row.get(3),
+                mnemonics,
+                distance: 0.0,
+                score: 0.0,
+                created_at: row.get(4),
+                updated_at: row.get(5),
+                recall_count: row.get(6),
+                last_recalled_at: row.get(7),
+                useful_count: row.get(8),
+                not_useful_count: row.get(9),
+                links,
+                owner: row.get(10),
+            }))
+        })
+    }
+
+    fn list_all_summaries(&self) -> Result<Vec<MemorySummary>> {
+        self.with_client(|c| {
+            Ok(c
+                .query(
+                    "SELECT m.title, m.content, m.tags, m.recall_count, m.useful_count, m.not_useful_count,
+                            ARRAY(SELECT x.text FROM mnemonics x WHERE x.memory_id = m.id ORDER BY x.id),
+                            u.username
+                     FROM memories m
+                     LEFT JOIN users u ON u.id = m.owner_id
+                     ORDER BY m.recall_count DESC, m.updated_at DESC",
+                    &[],
+                )?
+                .into_iter()
+                .map(|row| MemorySummary {
+                    mnemonic: row.get(0),
+                    content: row.get(1),
+                    tags: row.get(2),
+                    mnemonics: row.get(6),
+                    recall_count: row.get(3),
+                    useful_count: row.get(4),
+                    not_useful_count: row.get(5),
+                    owner: row.get(7),
+                })
+                .collect())
+        })
+    }
+
+    fn update_memory(
+        &self,
+        title: &str,
+        content: &str,
+        tags: &[String],
+        embedding: &[f32],
+    ) -> Result<()> {
+        self.with_client(|c| {
+            let mut tx = c.transaction()?;
+            let memory_id = memory_id_by_title(&mut tx, title)?;
+            let before = snapshot_by_id(&mut tx, memory_id)?;
+            tx.execute(
+                "UPDATE memories SET content = $1, tags = $2, updated_at = now() WHERE id = $3",
+                &[&content, &tags, &memory_id],
+            )?;
+            // Update primary mnemonic vector (the one matching title)
+            set_embedding(&mut tx, title, embedding)?;
+            audit_change(&mut tx, &self.actor, "memory.update", memory_id, before)?;
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    fn rename_memory(&self, old_title: &str, new_title: &str, embedding: &[f32]) -> Result<()> {
+        self.with_client(|c| {
+            let mut tx = c.transaction()?;
+            let memory_id = memory_id_by_title(&mut tx, old_title)?;
+            let conflict: i64 = tx
+                .query_one(
+                    "SELECT COUNT(*) FROM memories WHERE title = $1 AND id != $2",
+                    &[&new_title, &memory_id],
+                )?
+                .get(0);
+            if conflict > 0 {
+                return Err(TriviaError::Conflict(format!(
+                    "title already exists: {}",
+                    new_title
+                )));
+            }
+            let before = snapshot_by_id(&mut tx, memory_id)?;
+            tx.execute(
+                "UPDATE memories SET title = $1, updated_at = now() WHERE id = $2",
+                &[&new_title, &memory_id],
+            )?;
+            tx.execute(
+                "UPDATE mnemonics SET text = $1 WHERE memory_id = $2 AND text = $3",
+                &[&new_title, &memory_id, &old_title],
+            )?;
+            set_embedding(&mut tx, new_title, embedding)?;
+            audit_change(&mut tx, &self.actor, "memory.rename", memory_id, before)?;
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    fn edit_memory(
+        &self,
+        title: &str,
+        new_title: Option<&str>,
+        add_tags: &[String],
+        remove_tags: &[String],
+        new_embedding: Option<&[f32]>,
+        add_mnemonics: &[String],
+        remove_mnemonics: &[String],
+        mnemonic_embeddings: &[Vec<f32>],
+    ) -> Result<EditResult> {
+        self.with_client(|c| {
+            let mut tx = c.transaction()?;
+
+            let row = tx
+                .query_opt("SELECT id, tags FROM memories WHERE title = $1", &[&title])?
+                .ok_or_else(|| TriviaError::NotFound(format!("memory not found: {}", title)))?;
+            let memory_id: i64 = row.get(0);
+            let tags = edited_tags(&row.get::<_, Vec<String>>(1), add_tags, remove_tags);
+            let before = snapshot_by_id(&mut tx, memory_id)?;
+            tx.execute(
+                "UPDATE memories SET tags = $1, updated_at = now() WHERE id = $2",
+                &[&tags, &memory_id],
+            )?;
+
+            let final_title = if let Some(new_t) = new_title {
+                let embedding = new_embedding
+                    .ok_or_else(|| TriviaError::validation("new_embedding required when changing title"))?;
+                let conflict: i64 = tx
+                    .query_one(
+                        "SELECT COUNT(*) FROM memories WHERE title = $1 AND id != $2",
+                        &[&new_t, &memory_id],
+                    )?
+                    .get(0);
+                if conflict > 0 {
+                    return Err(TriviaError::Conflict(format!("title already exists: {}", new_t)));
+                }
+                tx.execute(
+                    "UPDATE memories SET title = $1, updated_at = now() WHERE id = $2",
+                    &[&new_t, &memory_id],
+                )?;
+                tx.execute(
+                    "UPDATE mnemonics SET text = $1 WHERE memory_id = $2 AND text = $3",
+                    &[&new_t, &memory_id, &title],
+                )?;
+                set_embedding(&mut tx, new_t, embedding)?;
+                new_t.to_string()
+            } else {
+                title.to_string()
+            };
+
+            for (i, mn_text) in add_mnemonics.iter().enumerate() {
+                tx.execute(
+                    "INSERT INTO mnemonics (memory_id, text) VALUES ($1, $2) ON CONFLICT (text) DO NOTHING",
+                    &[&memory_id, mn_text],
+                )?;
+                if let Some(emb) = mnemonic_embeddings.get(i) {
+                    set_embedding(&mut tx, mn_text, emb)?;
+                }
+            }
+
+            for mn_text in remove_mnemonics {
+                // Guard: cannot remove last mnemonic
+                let count: i64 = tx
+                    .query_one("SELECT COUNT(*) FROM mnemonics WHERE memory_id = $1", &[&memory_id])?
+                    .get(0);
+                if count <= 1 {
+                    return Err(TriviaError::validation("cannot remove the last mnemonic"));
+                }
+                tx.execute(
+                    "DELETE FROM mnemonics WHERE memory_id = $1 AND text = $2",
+                    &[&memory_id, mn_text],
+                )?;
+            }
+
+            let mnemonics = mnemonics_for_memory(&mut tx, memory_id)?;
+            audit_change(&mut tx, &self.actor, "memory.edit", memory_id, before)?;
+            tx.commit()?;
+            Ok(EditResult {
+                old_mnemonic: title.to_string(),
+                new_mnemonic: final_title,
+                tags,
+                mnemonics,
+                re_embedded: new_title.is_some(),
+            })
+        })
+    }
+
+    fn delete_memory(&self, title: &str) -> Result<bool> {
+        self.with_client(|c| {
+            let mut tx = c.transaction()?;
+            let Some(before) = snapshot_by_title(&mut tx, title)? else {
+                return Ok(false);
+            };
+            tx.execute("DELETE FROM memories WHERE uuid = $1", &[&before.uuid])?;
+            let detail = change_detail(Some(&before), None);
+            audit_memory(
+                &mut tx,
+                &self.actor,
+                "memory.delete",
+                &before.uuid,
+                detail.as_deref(),
+            )?;
+            tx.commit()?;
+            Ok(true)
+        })
+    }
+
+    fn merge(&self, keep: &str, discard: &str, embedding: &[f32]) -> Result<()> {
+        self.with_client(|c| {
+            let mut tx = c.transaction()?;
+            let keep_row = tx
+                .query_opt(
+                    "SELECT id, content, tags FROM memories WHERE title = $1",
+                    &[&keep],
+                )?
+                .ok_or_else(|| TriviaError::NotFound(format!("memory not found: {}", keep)))?;
+            let discard_row = tx
+                .query_opt(
+                    "SELECT id, content, tags FROM memories WHERE title = $1",
+                    &[&discard],
+                )?
+                .ok_or_else(|| TriviaError::NotFound(format!("memory not found: {}", discard)))?;
+            let keep_id: i64 = keep_row.get(0);
+            let discard_id: i64 = discard_row.get(0);
+            let absorbed = snapshot_by_id(&mut tx, discard_id)?;
+
+            let content = merged_content(keep_row.get(1), discard_row.get(1));
+            let tags = union_tags(
+                &keep_row.get::<_, Vec<String>>(2),
+                &discard_row.get::<_, Vec<String>>(2),
+            );
+            tx.execute(
+                "UPDATE memories SET content = $1, tags = $2, updated_at = now() WHERE id = $3",
+                &[&content, &tags, &keep_id],
+            )?;
+
+            // Re-embed keep's primary mnemonic; discard's aliases keep their vectors
+            set_embedding(&mut tx, keep, embedding)?;
+            tx.execute(
+                "UPDATE mnemonics SET memory_id = $1 WHERE memory_id = $2",
+                &[&keep_id, &discard_id],
+            )?;
+            absorb_links(&mut tx, keep_id, discard_id)?;
+            tx.execute("DELETE FROM memories WHERE id = $1", &[&discard_id])?;
+
+            if let (Some(absorbed), Some(after)) = (absorbed, snapshot_by_id(&mut tx, keep_id)?) {
+                let detail = format!("absorbed {absorbed} ({}); after: {after}", absorbed.uuid);
+                audit_memory(
+                    &mut tx,
+                    &self.actor,
+                    "memory.merge",
+                    &after.uuid,
+                    Some(&detail),
+                )?;
+            }
+
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    fn rate(
+        &self,
+        title: &str,
+        useful: bool,
+        query: Option<&str>,
+        recall_id: Option<&str>,
+    ) -> Result<()> {
+        if self
+            .rate_batch(&[title.to_string()], useful, query, recall_id)?
+            .is_empty()
+        {
+            Ok(())
+        } else {
+            Err(TriviaError::NotFound(format!(
+                "memory not found: {}",
+                title
+            )))
+        }
+    }
+
+    fn rate_batch(
+        &self,
+        titles: &[String],
+        useful: bool,
+        query: Option<&str>,
+        recall_id: Option<&str>,
+    ) -> Result<Vec<String>> {
+        let verdict = if useful { "useful" } else { "not useful" };
+        let detail = match query {
+            Some(query) => format!("{verdict} for {query:?}"),
+            None => verdict.to_string(),
+        };
+        self.with_client(|c| {
+            let mut tx = c.transaction()?;
+            let recall = recall_id
+                .map(|uuid| recall_row_id(&mut tx, &self.actor, uuid))
+                .transpose()?;
+            let mut not_found = Vec::new();
+            for title in titles {
+                match tx.query_opt("SELECT id, uuid FROM memories WHERE title = $1", &[title])? {
+                    Some(row) => {
+                        let memory_id: i64 = row.get(0);
+                        let uuid: String = row.get(1);
+                        if let Some(recall) = recall {
+                            require_recalled(&mut tx, recall, memory_id, title)?;
+                        }
+                        record_rating(&mut tx, &self.actor, memory_id, useful, query, recall)?;
+                        audit_memory(&mut tx, &self.actor, "memory.rate", &uuid, Some(&detail))?;
+                    }
+                    None => not_found.push(title.clone()),
+                }
+            }
+            tx.commit()?;
+            Ok(not_found)
+        })
+    }
+
+    fn list_tags(&self) -> Result<Vec<TagCount>> {
+        self.with_client(|c| {
+            Ok(c.query(
+                "SELECT tag, COUNT(*) AS count
+                     FROM memories, unnest(tags) AS tag
+                     GROUP BY tag
+                     ORDER BY count DESC, tag ASC",
+                &[],
+            )?
+            .into_iter()
+            .map(|row| TagCount {
+                tag: row.get(0),
+                count: row.get(1),
+            })
+            .collect())
+        })
+    }
+
+    fn rename_tag(&self, old_tag: &str, new_tag: &str) -> Result<usize> {
+        self.with_client(|c| {
+            let mut tx = c.transaction()?;
+            let ids: Vec<i64> = tx
+                .query("SELECT id FROM memories WHERE $1 = ANY(tags)", &[&old_tag])?
+                .into_iter()
+                .map(|row| row.get(0))
+                .collect();
+            for id in &ids {
+                let before = snapshot_by_id(&mut tx, *id)?;
+                tx.execute(
+                    "UPDATE memories SET
+                        tags = CASE WHEN $2 = ANY(tags) THEN array_remove(tags, $1)
+                                    ELSE array_append(array_remove(tags, $1), $2) END,
+                        updated_at = now()
+                     WHERE id = $3",
+                    &[&old_tag, &new_tag, id],
+                )?;
+                audit_change(&mut tx, &self.actor, "memory.rename_tag", *id, before)?;
+            }
+            tx.commit()?;
+            Ok(ids.len())
+        })
+    }
+
+    fn find_nearest(
+        &self,
+        embedding: &[f32],
+        threshold: f64,
+        exclude_title: &str,
+    ) -> Result<Vec<(String, f64)>> {
+        let fetch = ((AUTO_LINK_MAX_NEIGHBORS + 1) * 3) as i64;
+        self.with_client(|c| {
+            let mut seen = HashSet::new();
+            Ok(c.query(
+                "SELECT m.title, mn.embedding <-> $1::real[]::vector AS distance
+                     FROM mnemonics mn
+                     JOIN memories m ON m.id = mn.memory_id
+                     WHERE mn.embedding IS NOT NULL
+                     ORDER BY distance
+                     LIMIT $2",
+                &[&embedding, &fetch],
+            )?
+            .into_iter()
+            .map(|row| (row.get::<_, String>(0), row.get::<_, f64>(1)))
+            .filter(|(title, distance)| {
+                title != exclude_title && *distance < threshold && seen.insert(title.clone())
+            })
+            .collect())
+        })
+    }
+
+    fn find_merge_candidates(
+        &self,
+        embedding: &[f32],
+        threshold: f64,
+        exclude: &HashSet<String>,
+        limit: usize,
+    ) -> Result<Vec<MergeCandidate>> {
+        let fetch = ((limit + exclude.len() + 1) * 3) as i64;
+        self.with_client(|c| {
+            let mut seen = HashSet::new();
+            Ok(c.query(
+                "SELECT mn.memory_id, m.title, m.content, m.tags,
+                            mn.embedding <-> $1::real[]::vector AS distance, m.recall_count
+                     FROM mnemonics mn
+                     JOIN memories m ON m.id = mn.memory_id
+                     WHERE mn.embedding IS NOT NULL
+                     ORDER BY distance
+                     LIMIT $2",
+                &[&embedding, &fetch],
+            )?
+            .into_iter()
+            .filter(|row| {
+                !exclude.contains(&row.get::<_, String>(1))
+                    && row.get::<_, f64>(4) < threshold
+                    && seen.insert(row.get::<_, i64>(0))
+            })
+            .take(limit)
+            .map(|row| MergeCandidate {
+                mnemonic: row.get(1),
+                content: row.get(2),
+                tags: row.get(3),
+                distance: row.get(4),
+                recall_count: row.get(5),
+            })
+            .collect())
+        })
+    }
+
+    fn add_mnemonic(&self, title: &str, text: &str, embedding: &[f32]) -> Result<()> {
+        self.with_client(|c| {
+            let memory_id = memory_id_by_title(c, title)?;
+            c.execute(
+                "INSERT INTO mnemonics (memory_id, text, embedding) VALUES ($1, $2, $3::real[]::vector)",
+                &[&memory_id, &text, &embedding],
+            )?;
+            if let Some(memory) = snapshot_by_id(c, memory_id)? {
+                audit_memory(c, &self.actor, "memory.add_mnemonic", &memory.uuid, Some(&format!("{text:?}")))?;
+            }
+            Ok(())
+        })
+    }
+
+    fn remove_mnemonic(&self, title: &str, text: &str) -> Result<()> {
+        self.with_client(|c| {
+            let mut tx = c.transaction()?;
+            let memory_id = memory_id_by_title(&mut tx, title)?;
+            let count: i64 = tx
+                .query_one(
+                    "SELECT COUNT(*) FROM mnemonics WHERE memory_id = $1",
+                    &[&memory_id],
+                )?
+                .get(0);
+            if count <= 1 {
+                return Err(TriviaError::validation("cannot remove the last mnemonic"));
+            }
+            let removed = tx.execute(
+                "DELETE FROM mnemonics WHERE memory_id = $1 AND text = $2",
+                &[&memory_id, &text],
+            )?;
+            if removed > 0
+                && let Some(memory) = snapshot_by_id(&mut tx, memory_id)?
+            {
+                audit_memory(
+                    &mut tx,
+                    &self.actor,
+                    "memory.remove_mnemonic",
+                    &memory.uuid,
+                    Some(&format!("{text:?}")),
+                )?;
+            }
+            tx.commit()?;
+            Ok(())
+        })
+    }
+
+    fn link(&self, source_title: &str, target_title: &str, link_type: &str) -> Result<()> {
+        self.with_client(|c| {
+            let source_id = find_memory_id(c, source_title)?.ok_or_else(|| {
+                TriviaError::NotFound(format!("source not found: {}", source_title))
+            })?;
+            let target_id = find_memory_id(c, target_title)?.ok_or_else(|| {
+                TriviaError::NotFound(format!("target not found: {}", target_title))
+            })?;
+            let inserted = c.execute(
+                "INSERT INTO memory_links (source_id, target_id, link_type) VALUES ($1, $2, $3)
+                 ON CONFLICT DO NOTHING",
+                &[&source_id, &target_id, &link_type],
+            )?;
+            if inserted > 0
+                && let Some(source) = snapshot_by_id(c, source_id)?
+            {
+                let detail = link_detail(link_type, target_title);
+                audit_memory(c, &self.actor, "memory.link", &source.uuid, Some(&detail))?;
+            }
+            Ok(())
+        })
+    }
+
+    fn unlink(&self, source_title: &str, target_title: &str, link_type: &str) -> Result<()> {
+        self.with_client(|c| {
+            let removed = c.execute(
+                "DELETE FROM memory_links
+                 WHERE source_id = (SELECT id FROM memories WHERE title = $1)
+                 AND target_id = (SELECT id FROM memories WHERE title = $2)
+                 AND link_type = $3",
+                &[&source_title, &target_title, &link_type],
+            )?;
+            if removed > 0
+                && let Some(source) = snapshot_by_title(c, source_title)?
+            {
+                let detail = link_detail(link_type, target_title);
+                audit_memory(c, &self.actor, "memory.unlink", &source.uuid, Some(&detail))?;
+            }
+            Ok(())
+        })
+    }
+
+    fn get_links(&self, title: &str) -> Result<Vec<MemoryLink>> {
+        self.with_client(|c| links_for(c, title))
+    }
+
+    fn get_all_links(&self) -> Result<Vec<MemoryLink>> {
+        self.with_client(|c| {
+            Ok(c.query(
+                "SELECT s.title, t.title, ml.link_type, ml.created_at
+                     FROM memory_links ml
+                     JOIN memories s ON s.id = ml.source_id
+                     JOIN memories t ON t.id = ml.target_id",
+                &[],
+            )?
+            .iter()
+            .map(link_from_row)
+            .collect())
+        })
+    }
+
+    fn export_entries(&self, tags: Option<&[String]>) -> Result<Vec<ExportEntry>> {
+        let filter: Option<&[String]> = tags.filter(|t| !t.is_empty());
+        self.with_client(|c| {
+            let link_rows: Vec<(String, String, String)> = c
+                .query(
+                    "SELECT s.uuid, t.uuid, ml.link_type
+                     FROM memory_links ml
+                     JOIN memories s ON s.id = ml.source_id
+                     JOIN memories t ON t.id = ml.target_id",
+                    &[],
+                )?
+                .into_iter()
+                .map(|row| (row.get(0), row.get(1), row.get(2)))
+                .collect();
+
+            Ok(c.query(
+                "SELECT m.uuid, m.title, m.content, m.tags,
+                            ARRAY(SELECT x.text FROM mnemonics x
+                                  WHERE x.memory_id = m.id AND x.text <> m.title ORDER BY x.id)
+                     FROM memories m
+                     WHERE $1::text[] IS NULL OR m.tags && $1::text[]
+                     ORDER BY m.title",
+                &[&filter],
+            )?
+            .into_iter()
+            .map(|row| {
+                let uuid: String = row.get(0);
+                let links = link_rows
+                    .iter()
+                    .filter(|(source, _, _)| *source == uuid)
+                    .map(|(_, target, link_type)| ExportLink {
+                        target: target.clone(),
+                        link_type: link_type.clone(),
+                    })
+                    .collect();
+                ExportEntry {
+                    uuid,
+                    title: row.get(1),
+                    content: row.get(2),
+                    tags: row.get(3),
+                    mnemonics: row.get(4),
+                    links,
+                }
+            })
+            .collect())
+        })
+    }
+
+    fn import_entry(&self, entry: &ExportEntry, embedder: &Embedder) -> Result<ImportOutcome> {
+        // Work out what needs embedding first; the embedder stays on this thread
+        let (existing, missing_aliases) = self.with_client(|c| {
+            let existing: Option<(i64, String)> = c
+                .query_opt(
+                    "SELECT id, content FROM memories WHERE uuid = $1",
+                    &[&entry.uuid],
+                )?
+                .map(|row| (row.get(0), row.get(1)));
+            let with_vectors: HashSet<String> = c
+                .query(
+                    "SELECT text FROM mnemonics WHERE text = ANY($1) AND embedding IS NOT NULL",
+                    &[&entry.mnemonics],
+                )?
+                .into_iter()
+                .map(|row| row.get(0))
+                .collect();
+            let missing: Vec<String> = entry
+                .mnemonics
+                .iter()
+                .filter(|m| !with_vectors.contains(*m))
+                .cloned()
+                .collect();
+            Ok((existing, missing))
+        })?;
+
+        let outcome = match &existing {
+            Some((_, old_content)) if *old_content == entry.content => ImportOutcome::Unchanged,
+            Some(_) => ImportOutcome::Updated,
+            None => ImportOutcome::Created,
+        };
+        let title_embedding = match outcome {
+            ImportOutcome::Unchanged => None,
+            _ => Some(embedder.embed(&entry.title)?),
+        };
+        let alias_embeddings: Vec<(String, Vec<f32>)> = missing_aliases
+            .into_iter()
+            .map(|text| {
+                let emb = embedder.embed(&text)?;
+                Ok((text, emb))
+            })
+            .collect::<Result<_>>()?;
+
+        self.with_client(|c| {
+            let mut tx = c.transaction()?;
+            let before = match existing {
+                Some((id, _)) => snapshot_by_id(&mut tx, id)?,
+                None => None,
+            };
+            let memory_id: i64 = match existing {
+                Some((id, _)) => {
+                    if outcome == ImportOutcome::Updated {
+                        tx.execute(
+                            "UPDATE memories SET content = $1, tags = $2, title = $3, updated_at = now() WHERE id = $4",
+                            &[&entry.content, &entry.tags, &entry.title, &id],
+                        )?;
+                    }
+                    id
+                }
+                None => tx
+                    .query_one(
+                        "INSERT INTO memories (uuid, title, content, tags) VALUES ($1, $2, $3, $4) RETURNING id",
+                        &[&entry.uuid, &entry.title, &entry.content, &entry.tags],
+                    )?
+                    .get(0),
+            };
+            if let Some(embedding) = &title_embedding {
+                tx.execute(
+                    "INSERT INTO mnemonics (memory_id, text) VALUES ($1, $2) ON CONFLICT (text) DO NOTHING",
+                    &[&memory_id, &entry.title],
+                )?;
+                set_embedding(&mut tx, &entry.title, embedding)?;
+            }
+            for text in &entry.mnemonics {
+                tx.execute(
+                    "INSERT INTO mnemonics (memory_id, text) VALUES ($1, $2) ON CONFLICT (text) DO NOTHING",
+                    &[&memory_id, text],
+                )?;
+            }
+            for (text, embedding) in &alias_embeddings {
+                set_embedding(&mut tx, text, embedding)?;
+            }
+            if outcome != ImportOutcome::Unchanged {
+                audit_change(&mut tx, &self.actor, "memory.import", memory_id, before)?;
+            }
+            tx.commit()?;
+            Ok(outcome)
+        })
+    }
+
+    fn import_link(
+        &self,
+        source_uuid: &str,
+        target_uuid: &str,
+        link_type: &str,
+        created_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        self.with_client(|c| {
+            let inserted = c.execute(
+                "INSERT INTO memory_links (source_id, target_id, link_type, created_at)
+                 SELECT s.id, t.id, $3, COALESCE($4, now()) FROM memories s, memories t
+                 WHERE s.uuid = $1 AND t.uuid = $2
+                 ON CONFLICT DO NOTHING",
+                &[&source_uuid, &target_uuid, &link_type, &created_at],
+            )?;
+            if inserted > 0
+                && let Some(target) = snapshot_where(c, "uuid = $1", &target_uuid)?
+            {
+                let detail = link_detail(link_type, &target.title);
+                audit_memory(c, &self.actor, "memory.link", source_uuid, Some(&detail))?;
+            }
+            Ok(())
+        })
+    }
+
+    fn archive_records(
+        &self,
+        tags: Option<&[String]>,
+        with_vectors: bool,
+    ) -> Result<Vec<ArchiveRecord>> {
+        let filter: Option<&[String]> = tags.filter(|t| !t.is_empty());
+        self.with_client(|c| load_archive_records(c, None, filter, with_vectors))
+    }
+
+    fn mnemonics_with_vectors(&self, texts: &[String]) -> Result<HashSet<String>> {
+        self.with_client(|c| {
+            Ok(c.query(
+                "SELECT text FROM mnemonics WHERE text = ANY($1) AND embedding IS NOT NULL",
+                &[&texts],
+            )?
+            .into_iter()
+            .map(|row| row.get(0))
+            .collect())
+        })
+    }
+
+    fn import_record(
+        &self,
+        record: &ArchiveRecord,
+        vectors: &HashMap<String, Vec<f32>>,
+    ) -> Result<ImportOutcome> {
+        self.with_client(|c| {
+            let mut tx = c.transaction()?;
+            let existing = load_archive_records(&mut tx, Some(&record.uuid), None, true)?.pop();
+            let outcome = match &existing {
+                Some(old) if record.is_unchanged_from(old) && old.has_vectors(vectors) => {
+                    return Ok(ImportOutcome::Unchanged);
+                }
+                Some(_) => ImportOutcome::Updated,
+                None => ImportOutcome::Created,
+            };
+            let before = snapshot_where(&mut tx, "uuid = $1", &record.uuid)?;
+            let (legacy_useful, legacy_not_useful) = record.legacy_counts();
+
+            let memory_id: i64 = tx
+                .query_one(
+                    "INSERT INTO memories (uuid, title, content, tags, created_at, updated_at,
+                        recall_count, last_recalled_at, legacy_useful, legacy_not_useful)
+                     VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                     ON CONFLICT (uuid) DO UPDATE SET
+                        title = EXCLUDED.title, content = EXCLUDED.content, tags = EXCLUDED.tags,
+                        created_at = EXCLUDED.created_at, updated_at = EXCLUDED.updated_at,
+                        recall_count = EXCLUDED.recall_count,
+                        last_recalled_at = EXCLUDED.last_recalled_at,
+                        legacy_useful = EXCLUDED.legacy_useful,
+                        legacy_not_useful = EXCLUDED.legacy_not_useful
+                     RETURNING id",
+                    &[
+                        &record.uuid,
+                        &record.title,
+                        &record.content,
+                        &record.tags,
+                        &record.created_at,
+                        &record.updated_at,
+                        &record.recall_count,
+                        &record.last_recalled_at,
+                        &legacy_useful,
+                        &legacy_not_useful,
+                    ],
+                )
+                .map_err(|e| {
+                    TriviaError::Storage(anyhow::anyhow!("importing {}: {e}", record.title))
+                })?
+                .get(0);
+
+            // Drop mnemonics the record no longer has
+            let wanted: Vec<&str> = record.mnemonics.iter().map(|m| m.text.as_str()).collect();
+            tx.execute(
+                "DELETE FROM mnemonics WHERE memory_id = $1 AND NOT (text = ANY($2))",
+                &[&memory_id, &wanted],
+            )?;
+
+            for mn in &record.mnemonics {
+                // A mnemonic owned by another memory stays where it is
+                tx.execute(
+                    "INSERT INTO mnemonics (memory_id, text, created_at) VALUES ($1, $2, $3)
+                     ON CONFLICT (text) DO UPDATE SET created_at = EXCLUDED.created_at
+                     WHERE mnemonics.memory_id = EXCLUDED.memory_id",
+                    &[&memory_id, &mn.text, &mn.created_at],
+                )?;
+                if let Some(vector) = vectors.get(&mn.text) {
+                    tx.execute(
+                        "UPDATE mnemonics SET embedding = $1::real[]::vector
+                         WHERE text = $2 AND memory_id = $3",
+                        &[vector, &mn.text, &memory_id],
+                    )?;
+                }
+            }
+
+            // The record's ratings replace any already here
+            tx.execute("DELETE FROM ratings WHERE memory_id = $1", &[&memory_id])?;
+            for rating in &record.ratings {
+                tx.execute(
+                    "INSERT INTO ratings (memory_id, rater, useful, query, created_at)
+                     VALUES ($1, $2, $3, $4, $5)",
+                    &[
+                        &memory_id,
+                        &rating.rater,
+                        &rating.useful,
+                        &rating.query,
+                        &rating.created_at,
+                    ],
+                )?;
+            }
+            refresh_rating_counts(&mut tx, memory_id)?;
+
+            audit_change(&mut tx, &self.actor, "memory.import", memory_id, before)?;
+            tx.commit()?;
+            Ok(outcome)
+        })
+    }
+
+    // ========== Users ==========
+
+    fn create_user(&self, username: &str, acl: &str) -> Result<User> {
+        self.with_client(|c| {
+            let row = c.query_one(
+                &format!(
+                    "INSERT INTO users AS u (username, acl) VALUES ($1, $2)
+                     RETURNING {USER_COLUMNS}"
+                ),
+                &[&username, &acl],
+            )?;
+            Ok(user_from_row(&row, 0))
+        })
+    }
+
+    fn get_user_by_username(&self, username: &str) -> Result<Option<User>> {
+        self.with_client(|c| {
+            Ok(c.query_opt(
+                &format!("SELECT {USER_COLUMNS} FROM users u WHERE u.username = $1"),
+                &[&username],
+            )?
+            .map(|row| user_from_row(&row, 0)))
+        })
+    }
+
+    fn get_user_by_id(&self, id: i64) -> Result<Option<User>> {
+        self.with_client(|c| {
+            Ok(c.query_opt(
+                &format!("SELECT {USER_COLUMNS} FROM users u WHERE u.id = $1"),
+                &[&id],
+            )?
+            .map(|row| user_from_row(&row, 0)))
+        })
+    }
+
+    fn set_user_admin(&self, username: &str, is_admin: bool) -> Result<()> {
+        self.with_client(|c| {
+            let rows = c.execute(
+                "UPDATE users SET is_admin = $1, updated_at = now() WHERE username = $2",
+                &[&is_admin, &username],
+            )?;
+            if rows == 0 {
+                return Err(TriviaError::NotFound(format!(
+                    "user not found: {}",
+                    username
+                )));
+            }
+            Ok(())
+        })
+    }
+
+    fn update_user_acl(&self, username: &str, acl: &str) -> Result<()> {
+        self.with_client(|c| {
+            let rows = c.execute(
+                "UPDATE users SET acl = $1, updated_at = now() WHERE username = $2",
+                &[&acl, &username],
+            )?;
+            if rows == 0 {
+                return Err(TriviaError::NotFound(format!(
+                    "user not found: {}",
+                    username
+                )));
+            }
+            Ok(())
+        })
+    }
+
+    fn list_users(&self) -> Result<Vec<User>> {
+        self.with_client(|c| {
+            Ok(c.query(
+                &format!("SELECT {USER_COLUMNS} FROM users u ORDER BY u.username"),
+                &[],
+            )?
+            .iter()
+            .map(|row| user_from_row(row, 0))
+            .collect())
+        })
+    }
+
+    fn delete_user(&self, username: &str) -> Result<bool> {
+        self.with_client(|c| {
+            Ok(c.execute("DELETE FROM users WHERE username = $1", &[&username])? > 0)
+        })
+    }
+
+    fn touch_user(&self, user_id: i64) -> Result<()> {
+        self.with_client(|c| {
+            c.execute(
+                "UPDATE users SET last_seen_at = now()
+                 WHERE id = $1 AND (last_seen_at IS NULL OR last_seen_at < now() - interval '1 minute')",
+                &[&user_id],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn user_activity(&self, user_id: i64) -> Result<UserActivity> {
+        self.with_client(|c| {
+            let row = c.query_one(
+                "SELECT
+                    (SELECT COUNT(*) FROM sessions WHERE user_id = $1 AND expires_at > now()),
+                    (SELECT COUNT(*) FROM oauth_tokens WHERE user_id = $1),
+                    (SELECT last_seen_at FROM users WHERE id = $1)",
+                &[&user_id],
+            )?;
+            Ok(UserActivity {
+                sessions: row.get(0),
+                tokens: row.get(1),
+                last_seen_at: row.get(2),
+            })
+        })
+    }
+
+    // ========== Ownership ==========
+
+    fn set_owner(&self, title: &str, username: Option<&str>) -> Result<()> {
+        self.with_client(|c| set_owner_in(c, &self.actor, title, username))
+    }
+
+    fn transfer_ownership(&self, from: &str, to: &str) -> Result<usize> {
+        self.with_client(|c| {
+            let mut ids = Vec::new();
+            for name in [from, to] {
+                let id: i64 = c
+                    .query_opt("SELECT id FROM users WHERE username = $1", &[&name])?
+                    .ok_or_else(|| TriviaError::NotFound(format!("user not found: {}", name)))?
+                    .get(0);
+                ids.push(id);
+            }
+            let uuids: Vec<String> = c
+                .query(
+                    "UPDATE memories SET owner_id = $1 WHERE owner_id = $2 RETURNING uuid",
+                    &[&ids[1], &ids[0]],
+                )?
+                .into_iter()
+                .map(|row| row.get(0))
+                .collect();
+            let detail = format!("owner: {from} -> {to}");
+            for uuid in &uuids {
+                audit_memory(c, &self.actor, "memory.set_owner", uuid, Some(&detail))?;
+            }
+            Ok(uuids.len())
+        })
+    }
+
+    // ========== Groups ==========
+
+    fn create_group(&self, name: &str, acl: &str) -> Result<Group> {
+        self.with_client(|c| {
+            let row = c.query_one(
+                &format!(
+                    "INSERT INTO groups AS g (name, acl) VALUES ($1, $2) RETURNING {GROUP_COLUMNS}"
+                ),
+                &[&name, &acl],
+            )?;
+            Ok(group_from_row(&row))
+        })
+    }
+
+    fn get_group_by_name(&self, name: &str) -> Result<Option<Group>> {
+        self.with_client(|c| {
+            Ok(c.query_opt(
+                &format!("SELECT {GROUP_COLUMNS} FROM groups g WHERE g.name = $1"),
+                &[&name],
+            )?
+            .map(|row| group_from_row(&row)))
+        })
+    }
+
+    fn list_groups(&self) -> Result<Vec<Group>> {
+        self.with_client(|c| {
+            Ok(c.query(
+                &format!("SELECT {GROUP_COLUMNS} FROM groups g ORDER BY g.name"),
+                &[],
+            )?
+            .iter()
+            .map(group_from_row)
+            .collect())
+        })
+    }
+
+    fn update_group_acl(&self, name: &str, acl: &str) -> Result<()> {
+        self.with_client(|c| {
+            let rows = c.execute("UPDATE groups SET acl = $1 WHERE name = $2", &[&acl, &name])?;
+            if rows == 0 {
+                return Err(TriviaError::NotFound(format!("group not found: {}", name)));
+            }
+            Ok(())
+        })
+    }
+
+    fn delete_group(&self, name: &str) -> Result<bool> {
+        self.with_client(|c| Ok(c.execute("DELETE FROM groups WHERE name = $1", &[&name])? > 0))
+    }
+
+    fn add_group_member(&self, group: &str, username: &str) -> Result<()> {
+        self.with_client(|c| {
+            let group_id: i64 = c
+                .query_opt("SELECT id FROM groups WHERE name = $1", &[&group])?
+                .ok_or_else(|| TriviaError::NotFound(format!("group not found: {}", group)))?
+                .get(0);
+            let user_id: i64 = c
+                .query_opt("SELECT id FROM users WHERE username = $1", &[&username])?
+                .ok_or_else(|| TriviaError::NotFound(format!("user not found: {}", username)))?
+                .get(0);
+            c.execute(
+                "INSERT INTO user_groups (user_id, group_id) VALUES ($1, $2) ON CONFLICT DO NOTHING",
+                &[&user_id, &group_id],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn remove_group_member(&self, group: &str, username: &str) -> Result<bool> {
+        self.with_client(|c| {
+            let rows = c.execute(
+                "DELETE FROM user_groups
+                 WHERE group_id = (SELECT id FROM groups WHERE name = $1)
+                   AND user_id = (SELECT id FROM users WHERE username = $2)",
+                &[&group, &username],
+            )?;
+            Ok(rows > 0)
+        })
+    }
+
+    fn list_group_members(&self, group: &str) -> Result<Vec<User>> {
+        self.with_client(|c| {
+            Ok(c.query(
+                &format!(
+                    "SELECT {USER_COLUMNS} FROM users u
+                         JOIN user_groups ug ON ug.user_id = u.id
+                         JOIN groups g ON g.id = ug.group_id
+                         WHERE g.name = $1 ORDER BY u.username"
+                ),
+                &[&group],
+            )?
+            .iter()
+            .map(|row| user_from_row(row, 0))
+            .collect())
+        })
+    }
+
+    fn groups_for_user(&self, user_id: i64) -> Result<Vec<Group>> {
+        self.with_client(|c| {
+            Ok(c.query(
+                &format!(
+                    "SELECT {GROUP_COLUMNS} FROM groups g
+                         JOIN user_groups ug ON ug.group_id = g.id
+                         WHERE ug.user_id = $1 ORDER BY g.name"
+                ),
+                &[&user_id],
+            )?
+            .iter()
+            .map(group_from_row)
+            .collect())
+        })
+    }
+
+    // ========== OAuth providers and identities ==========
+
+    fn create_provider(
+        &self,
+        name: &str,
+        provider_type: &str,
+        client_id: &str,
+        client_secret: &str,
+        config: &str,
+    ) -> Result<OAuthProvider> {
+        let stored_secret = secrets::seal(self.secret_key.as_ref(), name, client_secret)?;
+        let provider = self.with_client(|c| {
+            let row = c.query_one(
+                &format!(
+                    "INSERT INTO oauth_providers (name, provider_type, client_id, client_secret, config)
+                     VALUES ($1, $2, $3, $4, $5) RETURNING {PROVIDER_COLUMNS}"
+                ),
+                &[&name, &provider_type, &client_id, &stored_secret, &config],
+            )?;
+            Ok(provider_from_row(&row))
+        })?;
+        self.reveal_provider(provider)
+    }
+
+    fn get_provider_by_name(&self, name: &str) -> Result<Option<OAuthProvider>> {
+        let provider = self.with_client(|c| {
+            Ok(c.query_opt(
+                &format!("SELECT {PROVIDER_COLUMNS} FROM oauth_providers WHERE name = $1"),
+                &[&name],
+            )?
+            .map(|row| provider_from_row(&row)))
+        })?;
+        provider.map(|p| self.reveal_provider(p)).transpose()
+    }
+
+    fn list_providers(&self) -> Result<Vec<OAuthProvider>> {
+        let providers = self.with_client(|c| {
+            Ok(c.query(
+                &format!("SELECT {PROVIDER_COLUMNS} FROM oauth_providers ORDER BY name"),
+                &[],
+            )?
+            .iter()
+            .map(provider_from_row)
+            .collect::<Vec<_>>())
+        })?;
+        providers
+            .into_iter()
+            .map(|p| self.reveal_provider(p))
+            .collect()
+    }
+
+    fn reencrypt_provider_secrets(&self, new_key: &SecretKey) -> Result<usize> {
+        self.with_client(|c| {
+            let mut tx = c.transaction()?;
+            let rows = tx.query(
+                "SELECT id, name, client_secret FROM oauth_providers FOR UPDATE",
+                &[],
+            )?;
+            for row in &rows {
+                let name: String = row.get(1);
+                let stored: String = row.get(2);
+                let plaintext = secrets::reveal(self.secret_key.as_ref(), &name, &stored)?;
+                tx.execute(
+                    "UPDATE oauth_providers SET client_secret = $1 WHERE id = $2",
+                    &[&new_key.encrypt(&name, &plaintext)?, &row.get::<_, i64>(0)],
+                )?;
+            }
+            tx.commit()?;
+            Ok(rows.len())
+        })
+    }
+
+    fn delete_provider(&self, name: &str) -> Result<bool> {
+        self.with_client(|c| {
+            Ok(c.execute("DELETE FROM oauth_providers WHERE name = $1", &[&name])? > 0)
+        })
+    }
+
+    fn set_provider_enabled(&self, name: &str, enabled: bool) -> Result<()> {
+        self.with_client(|c| {
+            let rows = c.execute(
+                "UPDATE oauth_providers SET enabled = $1 WHERE name = $2",
+                &[&enabled, &name],
+            )?;
+            if rows == 0 {
+                return Err(TriviaError::NotFound(format!(
+                    "provider not found: {}",
+                    name
+                )));
+            }
+            Ok(())
+        })
+    }
+
+    fn has_auth_providers(&self) -> Result<bool> {
+        self.with_client(|c| {
+            let count: i64 = c
+                .query_one("SELECT COUNT(*) FROM oauth_providers WHERE enabled", &[])?
+                .get(0);
+            Ok(count > 0)
+        })
+    }
+
+    fn list_enabled_providers(&self) -> Result<Vec<(String, String)>> {
+        self.with_client(|c| {
+            Ok(c.query(
+                "SELECT name, provider_type FROM oauth_providers WHERE enabled ORDER BY name",
+                &[],
+            )?
+            .into_iter()
+            .map(|row| (row.get(0), row.get(1)))
+            .collect())
+        })
+    }
+
+    fn link_identity(
+        &self,
+        user_id: i64,
+        provider_id: i64,
+        provider_username: &str,
+        provider_user_id: &str,
+    ) -> Result<()> {
+        self.with_client(|c| {
+            c.execute(
+                "INSERT INTO user_identities (user_id, provider_id, provider_username, provider_user_id)
+                 VALUES ($1, $2, $3, $4)",
+                &[&user_id, &provider_id, &provider_username, &provider_user_id],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn get_user_by_provider_identity(
+        &self,
+        provider_id: i64,
+        provider_user_id: &str,
+    ) -> Result<Option<User>> {
+        self.with_client(|c| {
+            Ok(c.query_opt(
+                &format!(
+                    "SELECT {USER_COLUMNS} FROM users u
+                         JOIN user_identities ui ON u.id = ui.user_id
+                         WHERE ui.provider_id = $1 AND ui.provider_user_id = $2"
+                ),
+                &[&provider_id, &provider_user_id],
+            )?
+            .map(|row| user_from_row(&row, 0)))
+        })
+    }
+
+    fn list_identities_for_user(&self, user_id: i64) -> Result<Vec<UserIdentity>> {
+        self.with_client(|c| {
+            Ok(c.query(
+                "SELECT id, user_id, provider_id, provider_username, provider_user_id, created_at
+                     FROM user_identities WHERE user_id = $1",
+                &[&user_id],
+            )?
+            .into_iter()
+            .map(|row| UserIdentity {
+                id: row.get(0),
+                user_id: row.get(1),
+                provider_id: row.get(2),
+                provider_username: row.get(3),
+                provider_user_id: row.get(4),
+                created_at: row.get(5),
+            })
+            .collect())
+        })
+    }
+
+    fn unlink_identity(&self, user_id: i64, provider_id: i64) -> Result<bool> {
+        self.with_client(|c| {
+            Ok(c.execute(
+                "DELETE FROM user_identities WHERE user_id = $1 AND provider_id = $2",
+                &[&user_id, &provider_id],
+            )? > 0)
+        })
+    }
+
+    // ========== OAuth clients, codes, tokens, sessions ==========
+
+    fn register_client(
+        &self,
+        redirect_uris: &[String],
+        client_name: Option<&str>,
+    ) -> Result<(OAuthClient, Option<String>)> {
+        let client_id = generate_random_string(32);
+        let secret = generate_random_string(48);
+        let secret_hash = sha256_hex(&secret);
+        self.with_client(|c| {
+            let created_at: DateTime<Utc> = c
+                .query_one(
+                    "INSERT INTO oauth_clients (client_id, client_secret_hash, redirect_uris, client_name)
+                     VALUES ($1, $2, $3, $4) RETURNING created_at",
+                    &[&client_id, &secret_hash, &redirect_uris, &client_name],
+                )?
+                .get(0);
+            Ok((
+                OAuthClient {
+                    client_id: client_id.clone(),
+                    redirect_uris: redirect_uris.to_vec(),
+                    client_name: client_name.map(String::from),
+                    created_at,
+                },
+                Some(secret.clone()),
+            ))
+        })
+    }
+
+    fn get_client(&self, client_id: &str) -> Result<Option<OAuthClient>> {
+        self.with_client(|c| {
+            Ok(c.query_opt(
+                "SELECT client_id, redirect_uris, client_name, created_at
+                     FROM oauth_clients WHERE client_id = $1",
+                &[&client_id],
+            )?
+            .map(|row| OAuthClient {
+                client_id: row.get(0),
+                redirect_uris: row.get(1),
+                client_name: row.get(2),
+                created_at: row.get(3),
+            }))
+        })
+    }
+
+    fn list_clients(&self) -> Result<Vec<OAuthClient>> {
+        self.with_client(|c| {
+            Ok(c.query(
+                "SELECT client_id, redirect_uris, client_name, created_at
+                     FROM oauth_clients ORDER BY created_at, client_id",
+                &[],
+            )?
+            .into_iter()
+            .map(|row| OAuthClient {
+                client_id: row.get(0),
+                redirect_uris: row.get(1),
+                client_name: row.get(2),
+                created_at: row.get(3),
+            })
+            .collect())
+        })
+    }
+
+    fn delete_client(&self, client_id: &str) -> Result<bool> {
+        self.with_client(|c| {
+            let mut tx = c.transaction()?;
+            tx.execute(
+                "DELETE FROM oauth_tokens WHERE client_id = $1",
+                &[&client_id],
+            )?;
+            tx.execute(
+                "DELETE FROM oauth_codes WHERE client_id = $1",
+                &[&client_id],
+            )?;
+            let rows = tx.execute(
+                "DELETE FROM oauth_clients WHERE client_id = $1",
+                &[&client_id],
+            )?;
+            tx.commit()?;
+            Ok(rows > 0)
+        })
+    }
+
+    fn verify_client_secret(&self, client_id: &str, secret: &str) -> Result<bool> {
+        let hash = sha256_hex(secret);
+        self.with_client(|c| {
+            let stored: Option<Option<String>> = c
+                .query_opt(
+                    "SELECT client_secret_hash FROM oauth_clients WHERE client_id = $1",
+                    &[&client_id],
+                )?
+                .map(|row| row.get(0));
+            Ok(stored.flatten().as_deref() == Some(&hash))
+        })
+    }
+
+    fn create_auth_code(
+        &self,
+        client_id: &str,
+        user_id: i64,
+        code_challenge: &str,
+        redirect_uri: &str,
+        scope: Option<&str>,
+    ) -> Result<String> {
+        let code = generate_random_string(48);
+        let expires_at = Utc::now() + self.auth.auth_code_ttl();
+        self.with_client(|c| {
+            c.execute(
+                "INSERT INTO oauth_codes (code, client_id, user_id, code_challenge, redirect_uri, expires_at, scope)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                &[&code, &client_id, &user_id, &code_challenge, &redirect_uri, &expires_at, &scope],
+            )?;
+            Ok(code.clone())
+        })
+    }
+
+    fn consume_auth_code(&self, code: &str) -> Result<OAuthCode> {
+        self.with_client(|c| {
+            let mut tx = c.transaction()?;
+            let row = tx
+                .query_opt(
+                    "SELECT code, client_id, user_id, code_challenge, redirect_uri, expires_at, used, scope
+                     FROM oauth_codes WHERE code = $1 FOR UPDATE",
+                    &[&code],
+                )?
+                .ok_or_else(|| TriviaError::validation("invalid authorization code"))?;
+            let auth_code = OAuthCode {
+                code: row.get(0),
+                client_id: row.get(1),
+                user_id: row.get(2),
+                code_challenge: row.get(3),
+                redirect_uri: row.get(4),
+                expires_at: row.get(5),
+                scope: row.get(7),
+            };
+            if row.get::<_, bool>(6) {
+                return Err(TriviaError::validation("authorization code already used"));
+            }
+            if auth_code.expires_at < Utc::now() {
+                return Err(TriviaError::validation("authorization code expired"));
+            }
+            tx.execute("UPDATE oauth_codes SET used = TRUE WHERE code = $1", &[&code])?;
+            tx.commit()?;
+            Ok(auth_code)
+        })
+    }
+
+    fn cleanup_expired_codes(&self) -> Result<usize> {
+        self.with_client(|c| {
+            let codes = c.execute("DELETE FROM oauth_codes WHERE expires_at <= now()", &[])?;
+            let logins = c.execute("DELETE FROM pending_logins WHERE expires_at <= now()", &[])?;
+            let consents = c.execute(
+                "DELETE FROM pending_consents WHERE expires_at <= now()",
+                &[],
+            )?;
+            Ok((codes + logins + consents) as usize)
+        })
+    }
+
+    fn create_pending_login(
+        &self,
+        provider: &str,
+        authorization: Option<&PendingAuthorization>,
+        secrets: &LoginSecrets,
+    ) -> Result<String> {
+        let state = generate_random_string(48);
+        let expires_at = Utc::now() + Duration::minutes(PENDING_LOGIN_MINUTES);
+        let a = authorization;
+        self.with_client(|c| {
+            c.execute(
+                "INSERT INTO pending_logins (state_hash, provider, client_id, redirect_uri,
+                     code_challenge, scope, client_state, code_verifier, nonce, expires_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)",
+                &[
+                    &sha256_hex(&state),
+                    &provider,
+                    &a.map(|a| &a.client_id),
+                    &a.map(|a| &a.redirect_uri),
+                    &a.map(|a| &a.code_challenge),
+                    &a.map(|a| &a.scope),
+                    &a.map(|a| &a.client_state),
+                    &secrets.code_verifier,
+                    &secrets.nonce,
+                    &expires_at,
+                ],
+            )?;
+            Ok(state.clone())
+        })
+    }
+
+    fn take_pending_login(&self, state: &str) -> Result<PendingLogin> {
+        let hash = sha256_hex(state);
+        let login = self.with_client(|c| {
+            let row = c
+                .query_opt(
+                    "DELETE FROM pending_logins WHERE state_hash = $1
+                     RETURNING provider, client_id, redirect_uri, code_challenge, scope,
+                         client_state, code_verifier, nonce, expires_at",
+                    &[&hash],
+                )?
+                .ok_or_else(|| TriviaError::validation("unknown or already used state"))?;
+            let authorization =
+                row.get::<_, Option<String>>(1)
+                    .map(|client_id| PendingAuthorization {
+                        client_id,
+                        redirect_uri: row.get(2),
+                        code_challenge: row.get(3),
+                        scope: row.get(4),
+                        client_state: row.get(5),
+                    });
+            Ok(PendingLogin {
+                provider: row.get(0),
+                authorization,
+                secrets: LoginSecrets {
+                    code_verifier: row.get(6),
+                    nonce: row.get(7),
+                },
+                expires_at: row.get(8),
+            })
+        })?;
+        if login.expires_at < Utc::now() {
+            return Err(TriviaError::validation("sign-in expired; start again"));
+        }
+        Ok(login)
+    }
+
+    fn create_pending_consent(
+        &self,
+        user_id: i64,
+        authorization: &PendingAuthorization,
+    ) -> Result<String> {
+        let token = generate_random_string(48);
+        let expires_at = Utc::now() + Duration::minutes(PENDING_LOGIN_MINUTES);
+        let a = authorization;
+        self.with_client(|c| {
+            c.execute(
+                "INSERT INTO pending_consents (token_hash, user_id, client_id, redirect_uri,
+                     code_challenge, scope, client_state, expires_at)
+                 VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+                &[
+                    &sha256_hex(&token),
+                    &user_id,
+                    &a.client_id,
+                    &a.redirect_uri,
+                    &a.code_challenge,
+                    &a.scope,
+                    &a.client_state,
+                    &expires_at,
+                ],
+            )?;
+            Ok(token.clone())
+        })
+    }
+
+    fn take_pending_consent(&self, token: &str) -> Result<PendingConsent> {
+        let hash = sha256_hex(token);
+        let consent = self.with_client(|c| {
+            let row = c
+                .query_opt(
+                    "DELETE FROM pending_consents WHERE token_hash = $1
+                     RETURNING user_id, client_id, redirect_uri, code_challenge, scope,
+                         client_state, expires_at",
+                    &[&hash],
+                )?
+                .ok_or_else(|| {
+                    TriviaError::validation("unknown or already answered consent request")
+                })?;
+            Ok(PendingConsent {
+                user_id: row.get(0),
+                authorization: PendingAuthorization {
+                    client_id: row.get(1),
+                    redirect_uri: row.get(2),
+                    code_challenge: row.get(3),
+                    scope: row.get(4),
+                    client_state: row.get(5),
+                },
+                expires_at: row.get(6),
+            })
+        })?;
+        if consent.expires_at < Utc::now() {
+            return Err(TriviaError::validation(
+                "consent request expired; start again",
+            ));
+        }
+        Ok(consent)
+    }
+
+    fn grant_consent(&self, user_id: i64, client_id: &str, scope: &str) -> Result<()> {
+        self.with_client(|c| {
+            c.execute(
+                "INSERT INTO oauth_consents (user_id, client_id, scope) VALUES ($1, $2, $3)
+                 ON CONFLICT (user_id, client_id)
+                 DO UPDATE SET scope = excluded.scope, created_at = now()",
+                &[&user_id, &client_id, &scope],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn get_consent(&self, user_id: i64, client_id: &str) -> Result<Option<ClientConsent>> {
+        self.with_client(|c| {
+            Ok(c.query_opt(
+                &format!(
+                    "SELECT {CONSENT_COLUMNS} FROM oauth_consents o
+                         JOIN oauth_clients c ON c.client_id = o.client_id
+                         WHERE o.user_id = $1 AND o.client_id = $2"
+                ),
+                &[&user_id, &client_id],
+            )?
+            .map(|row| consent_from_row(&row)))
+        })
+    }
+
+    fn list_consents(&self, user_id: i64) -> Result<Vec<ClientConsent>> {
+        self.with_client(|c| {
+            Ok(c.query(
+                &format!(
+                    "SELECT {CONSENT_COLUMNS} FROM oauth_consents o
+                         JOIN oauth_clients c ON c.client_id = o.client_id
+                         WHERE o.user_id = $1 ORDER BY o.created_at, c.client_id"
+                ),
+                &[&user_id],
+            )?
+            .iter()
+            .map(consent_from_row)
+            .collect())
+        })
+    }
+
+    fn revoke_consent(&self, user_id: i64, client_id: &str) -> Result<bool> {
+        self.with_client(|c| {
+            let mut tx = c.transaction()?;
+            tx.execute(
+                "DELETE FROM oauth_tokens WHERE user_id = $1 AND client_id = $2",
+                &[&user_id, &client_id],
+            )?;
+            let rows = tx.execute(
+                "DELETE FROM oauth_consents WHERE user_id = $1 AND client_id = $2",
+                &[&user_id, &client_id],
+            )?;
+            tx.commit()?;
+            Ok(rows > 0)
+        })
+    }
+
+    fn create_token_pair(
+        &self,
+        client_id: &str,
+        user_id: i64,
+        scope: Option<&str>,
+    ) -> Result<TokenPair> {
+        self.with_client(|c| {
+            insert_token_pair(c, &self.auth, client_id, user_id, scope, Utc::now())
+        })
+    }
+
+    fn refresh_token_pair(&self, refresh_token: &str) -> Result<Option<TokenPair>> {
+        let hash = sha256_hex(refresh_token);
+        self.with_client(|c| {
+            let mut tx = c.transaction()?;
+            let Some(row) = tx.query_opt(
+                "DELETE FROM oauth_tokens
+                 WHERE refresh_token_hash = $1 AND refresh_expires_at > now()
+                 RETURNING client_id, user_id, scope, auth_time",
+                &[&hash],
+            )?
+            else {
+                return Ok(None);
+            };
+            let client_id: String = row.get(0);
+            let scope: Option<String> = row.get(2);
+            let pair = insert_token_pair(
+                &mut tx,
+                &self.auth,
+                &client_id,
+                row.get(1),
+                scope.as_deref(),
+                row.get(3),
+            )?;
+            tx.commit()?;
+            Ok(Some(pair))
+        })
+    }
+
+    fn get_user_by_access_token(&self, token: &str) -> Result<Option<TokenGrant>> {
+        let hash = sha256_hex(token);
+        self.with_client(|c| {
+            Ok(c.query_opt(
+                &format!(
+                    "SELECT {GRANT_COLUMNS}, {USER_COLUMNS} FROM users u
+                         JOIN oauth_tokens t ON u.id = t.user_id
+                         WHERE t.access_token_hash = $1 AND t.expires_at > now()"
+                ),
+                &[&hash],
+            )?
+            .map(|row| grant_from_row(&row)))
+        })
+    }
+
+    fn get_user_by_refresh_token(&self, token: &str) -> Result<Option<TokenGrant>> {
+        let hash = sha256_hex(token);
+        self.with_client(|c| {
+            Ok(c.query_opt(
+                &format!(
+                    "SELECT {GRANT_COLUMNS}, {USER_COLUMNS} FROM users u
+                         JOIN oauth_tokens t ON u.id = t.user_id
+                         WHERE t.refresh_token_hash = $1 AND t.refresh_expires_at > now()"
+                ),
+                &[&hash],
+            )?
+            .map(|row| grant_from_row(&row)))
+        })
+    }
+
+    fn revoke_token(&self, token: &str) -> Result<bool> {
+        let hash = sha256_hex(token);
+        self.with_client(|c| {
+            Ok(c.execute(
+                "DELETE FROM oauth_tokens WHERE access_token_hash = $1 OR refresh_token_hash = $1",
+                &[&hash],
+            )? > 0)
+        })
+    }
+
+    fn cleanup_expired_tokens(&self) -> Result<usize> {
+        self.with_client(|c| {
+            let pairs = c.execute(
+                "DELETE FROM oauth_tokens WHERE expires_at <= now() AND refresh_expires_at <= now()",
+                &[],
+            )?;
+            let personal = c.execute("DELETE FROM personal_tokens WHERE expires_at <= now()", &[])?;
+            Ok((pairs + personal) as usize)
+        })
+    }
+
+    fn revoke_user_tokens(&self, user_id: i64) -> Result<usize> {
+        self.with_client(|c| {
+            let pairs = c.execute("DELETE FROM oauth_tokens WHERE user_id = $1", &[&user_id])?;
+            let personal = c.execute(
+                "DELETE FROM personal_tokens WHERE user_id = $1",
+                &[&user_id],
+            )?;
+            Ok((pairs + personal) as usize)
+        })
+    }
+
+    fn create_personal_token(
+        &self,
+        user_id: i64,
+        name: &str,
+        scope: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(PersonalToken, String)> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(TriviaError::validation("token name must not be empty"));
+        }
+        let secret = format!("{PERSONAL_TOKEN_PREFIX}{}", generate_random_string(40));
+        let hash = sha256_hex(&secret);
+        self.with_client(|c| {
+            let row = c
+                .query_opt(
+                    &format!(
+                        "INSERT INTO personal_tokens AS p (user_id, name, token_hash, scope, expires_at)
+                         VALUES ($1, $2, $3, $4, $5)
+                         ON CONFLICT (user_id, name) DO NOTHING
+                         RETURNING {PERSONAL_TOKEN_COLUMNS}"
+                    ),
+                    &[&user_id, &name, &hash, &scope, &expires_at],
+                )?
+                .ok_or_else(|| TriviaError::conflict(format!("a token named '{name}' already exists")))?;
+            Ok((personal_token_from_row(&row), secret.clone()))
+        })
+    }
+
+    fn list_personal_tokens(&self, user_id: i64) -> Result<Vec<PersonalToken>> {
+        self.with_client(|c| {
+            Ok(c.query(
+                &format!(
+                    "SELECT {PERSONAL_TOKEN_COLUMNS} FROM personal_tokens p
+                         WHERE p.user_id = $1 ORDER BY p.name"
+                ),
+                &[&user_id],
+            )?
+            .iter()
+            .map(personal_token_from_row)
+            .collect())
+        })
+    }
+
+    fn get_user_by_personal_token(&self, token: &str) -> Result<Option<(PersonalToken, User)>> {
+        let hash = sha256_hex(token);
+        self.with_client(|c| {
+            Ok(c.query_opt(
+                &format!(
+                    "UPDATE personal_tokens p SET last_used_at = now()
+                         FROM users u
+                         WHERE u.id = p.user_id AND p.token_hash = $1
+                           AND (p.expires_at IS NULL OR p.expires_at > now())
+                         RETURNING {PERSONAL_TOKEN_COLUMNS}, {USER_COLUMNS}"
+                ),
+                &[&hash],
+            )?
+            .map(|row| (personal_token_from_row(&row), user_from_row(&row, 7))))
+        })
+    }
+
+    fn delete_personal_token(&self, user_id: i64, name: &str) -> Result<bool> {
+        self.with_client(|c| {
+            Ok(c.execute(
+                "DELETE FROM personal_tokens WHERE user_id = $1 AND name = $2",
+                &[&user_id, &name],
+            )? > 0)
+        })
+    }
+
+    fn create_session(&self, user_id: i64) -> Result<Session> {
+        let session_id = generate_random_string(48);
+        let now = Utc::now();
+        let max_expires_at = now + self.auth.session_max();
+        let expires_at = (now + self.auth.session_idle()).min(max_expires_at);
+        self.with_client(|c| {
+            c.execute(
+                "INSERT INTO sessions (session_id, user_id, expires_at, created_at) VALUES ($1, $2, $3, $4)",
+                &[&session_id, &user_id, &expires_at, &now],
+            )?;
+            Ok(())
+        })?;
+        Ok(Session {
+            session_id,
+            user_id,
+            expires_at,
+            max_expires_at,
+        })
+    }
+
+    fn get_session(&self, session_id: &str) -> Result<Option<(Session, User)>> {
+        let now = Utc::now();
+        let max = self.auth.session_max();
+        let oldest = now - max;
+        let found = self.with_client(|c| {
+            Ok(c.query_opt(
+                &format!(
+                    "SELECT s.session_id, s.user_id, s.expires_at, s.created_at, {USER_COLUMNS}
+                         FROM sessions s
+                         JOIN users u ON s.user_id = u.id
+                         WHERE s.session_id = $1 AND s.expires_at > $2 AND s.created_at > $3"
+                ),
+                &[&session_id, &now, &oldest],
+            )?
+            .map(|row| {
+                (
+                    Session {
+                        session_id: row.get(0),
+                        user_id: row.get(1),
+                        expires_at: row.get(2),
+                        max_expires_at: row.get::<_, DateTime<Utc>>(3) + max,
+                    },
+                    user_from_row(&row, 4),
+                )
+            }))
+        })?;
+        let Some((mut session, user)) = found else {
+            return Ok(None);
+        };
+
+        // See MemoryStore::get_session
+        let renewed = (now + self.auth.session_idle()).min(session.max_expires_at);
+        if renewed - session.expires_at > Duration::minutes(1) {
+            self.with_client(|c| {
+                c.execute(
+                    "UPDATE sessions SET expires_at = $2 WHERE session_id = $1",
+                    &[&session_id, &renewed],
+                )?;
+                Ok(())
+            })?;
+            session.expires_at = renewed;
+        }
+        Ok(Some((session, user)))
+    }
+
+    fn delete_session(&self, session_id: &str) -> Result<()> {
+        self.with_client(|c| {
+            c.execute("DELETE FROM sessions WHERE session_id = $1", &[&session_id])?;
+            Ok(())
+        })
+    }
+
+    fn cleanup_expired_sessions(&self) -> Result<usize> {
+        let oldest = Utc::now() - self.auth.session_max();
+        self.with_client(|c| {
+            Ok(c.execute(
+                "DELETE FROM sessions WHERE expires_at <= now() OR created_at <= $1",
+                &[&oldest],
+            )? as usize)
+        })
+    }
+
+    fn revoke_user_sessions(&self, user_id: i64) -> Result<usize> {
+        self.with_client(|c| {
+            Ok(c.execute("DELETE FROM sessions WHERE user_id = $1", &[&user_id])? as usize)
+        })
+    }
+
+    // ========== Audit ==========
+
+    fn record_audit(
+        &self,
+        actor: Option<&str>,
+        action: &str,
+        target: Option<&str>,
+        detail: Option<&str>,
+    ) -> Result<()> {
+        let transport = self.actor.transport.as_str();
+        self.with_client(|c| insert_audit(c, actor, transport, action, target, detail))
+    }
+
+    fn list_audit(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+        if filter.limit == 0 {
+            return Err(TriviaError::validation("limit must be at least 1"));
+        }
+        let action_prefix = filter.action.as_ref().map(|action| format!("{action}."));
+        let transport = filter.transport.map(|t| t.as_str());
+        let mut conditions = Vec::new();
+        let mut args: Vec<&(dyn ToSql + Sync)> = Vec::new();
+        if let Some(actor) = &filter.actor {
+            args.push(actor);
+            conditions.push(format!("actor = ${}", args.len()));
+        }
+        if let (Some(action), Some(prefix)) = (&filter.action, &action_prefix) {
+            args.push(action);
+            args.push(prefix);
+            conditions.push(format!(
+                "(action = ${} OR starts_with(action, ${}))",
+                args.len() - 1,
+                args.len()
+            ));
+        }
+        if let Some(target) = &filter.target {
+            args.push(target);
+            conditions.push(format!("target = ${}", args.len()));
+        }
+        if let Some(memory) = &filter.memory {
+            args.push(memory);
+            conditions.push(format!(
+                "target = (SELECT uuid FROM memories WHERE title = ${})",
+                args.len()
+            ));
+        }
+        if let Some(transport) = &transport {
+            args.push(transport);
+            conditions.push(format!("transport = ${}", args.len()));
+        }
+        if let Some(since) = &filter.since {
+            args.push(since);
+            conditions.push(format!("created_at >= ${}", args.len()));
+        }
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+        let limit = filter.limit as i64;
+        args.push(&limit);
+        let sql = format!(
+            "SELECT id, created_at, actor, action, target, detail, transport
+             FROM audit_log {where_clause} ORDER BY id DESC LIMIT ${}",
+            args.len()
+        );
+        self.with_client(|c| {
+            Ok(c.query(&sql, &args)?
+                .into_iter()
+                .map(|row| AuditEntry {
+                    id: row.get(0),
+                    created_at: row.get(1),
+                    actor: row.get(2),
+                    action: row.get(3),
+                    target: row.get(4),
+                    detail: row.get(5),
+                    transport: row.get(6),
+                })
+                .collect())
+        })
+    }
+}
+
+/// These run against a real server and are skipped unless
+/// `TRIVIA_TEST_POSTGRES_URL` points at a database with pgvector available,
+/// e.g. `postgres://postgres@localhost/trivia_test`. Each test gets its own
+/// schema so they can run in parallel.
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    static EXTENSION_LOCK: Mutex<()> = Mutex::new(());
+
+    /// A store confined to a throwaway schema, dropped with the store.
+    struct TestStore {
+        store: PgStore,
+        schema: String,
+    }
+
+    impl std::ops::Deref for TestStore {
+        type Target = PgStore;
+        fn deref(&self) -> &PgStore {
+            &self.store
+        }
+    }
+
+    impl std::ops::DerefMut for TestStore {
+        fn deref_mut(&mut self) -> &mut PgStore {
+            &mut self.store
+        }
+    }
+
+    impl Drop for TestStore {
+        fn drop(&mut self) {
+            let sql = format!("DROP SCHEMA IF EXISTS {} CASCADE", self.schema);
+            let _ = self.store.with_client(|c| Ok(c.batch_execute(&sql)?));
+        }
+    }
+
+    fn test_store() -> Option<TestStore> {
+        let url = std::env::var("TRIVIA_TEST_POSTGRES_URL").ok()?;
+        let schema = format!("trivia_test_{}", Uuid::new_v4().simple());
+        let client = off_runtime(|| {
+            let tls = MakeTlsConnector::new(
+                native_tls::TlsConnector::new().map_err(|e| TriviaError::Storage(e.into()))?,
+            );
+            let mut client = Client::connect(&url, tls)?;
+            {
+                // CREATE EXTENSION IF NOT EXISTS races when run concurrently
+                let _guard = EXTENSION_LOCK.lock().unwrap();
+                client.batch_execute("CREATE EXTENSION IF NOT EXISTS vector WITH SCHEMA public")?;
+            }
+            client.batch_execute(&format!(
+                "CREATE SCHEMA {schema}; SET search_path TO {schema}, public;"
+            ))?;
+            Ok(client)
+        })
+        .unwrap();
+        let store = PgStore {
+            client: Mutex::new(client),
+            scoring: ScoringConfig::default(),
+            auth: AuthConfig::default(),
+            secret_key: None,
+            actor: Actor::default(),
+            ratings: RatingConfig::default(),
+        };
+        store.migrate().unwrap();
+        Some(TestStore { store, schema })
+    }
+
+    fn emb(seed: f32) -> Vec<f32> {
+        vec![seed; 384]
+    }
+
+    #[test]
+    fn test_migrate_idempotent() -> Result<()> {
+        let Some(store) = test_store() else {
+            return Ok(());
+        };
+        assert_eq!(
+            store.schema_version()?,
+            PG_MIGRATIONS.last().unwrap().version
+        );
+        assert!(store.migrate()?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_memorize_and_recall() -> Result<()> {
+        let Some(store) = test_store() else {
+            return Ok(());
+        };
+        store.memorize("rust tips", "use clippy", &["rust".into()], &emb(0.1))?;
+        store.memorize("python tips", "use ruff", &["python".into()], &emb(-0.5))?;
+
+        let results = store.recall(&emb(0.1), 5, None, None, None)?.memories;
+        assert_eq!(results[0].mnemonic, "rust tips");
+        assert_eq!(results[0].tags, vec!["rust"]);
+        assert_eq!(results[0].mnemonics, vec!["rust tips"]);
+
+        let filtered = store
+            .recall(&emb(0.1), 5, Some(&["python".into()]), None, None)?
+            .memories;
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].mnemonic, "python tips");
+
+        let mem = store.get_memory_by_mnemonic("rust tips")?.unwrap();
+        assert_eq!(mem.recall_count, 1);
+        assert!(mem.last_recalled_at.is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_fts_boost() -> Result<()> {
+        let Some(store) = test_store() else {
+            return Ok(());
+        };
+        // Equidistant from the query, so only the FTS match separates them
+        store.memorize("alpha", "the deployment pipeline", &[], &emb(0.2))?;
+        store.memorize("beta", "unrelated text", &[], &emb(0.0))?;
+
+        let results = store
+            .recall(&emb(0.1), 5, None, Some("deployment"), None)?
+            .memories;
+        assert_eq!(results[0].mnemonic, "alpha");
+        assert!(results[0].score > results[1].score);
+        Ok(())
+    }
+
+    #[test]
+    fn test_auto_merge_and_links() -> Result<()> {
+        let Some(store) = test_store() else {
+            return Ok(());
+        };
+        store.memorize("first", "one", &["a".into()], &emb(0.1))?;
+        let result = store.memorize("second", "two", &["b".into()], &emb(0.1))?;
+        assert_eq!(result.merged_with.as_deref(), Some("first"));
+
+        let mem = store.get_memory_by_mnemonic("second")?.unwrap();
+        assert_eq!(mem.content, "two\n\none");
+        assert_eq!(mem.tags, vec!["b", "a"]);
+        assert!(mem.mnemonics.contains(&"first".to_string()));
+        assert!(store.get_memory_by_mnemonic("first")?.is_none());
+
+        store.memorize("third", "three", &[], &emb(0.5))?;
+        store.link("second", "third", "derived_from")?;
+        assert_eq!(store.get_links("third")?.len(), 1);
+        store.unlink("second", "third", "derived_from")?;
+        assert!(store.get_links("third")?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_edit_rename_delete() -> Result<()> {
+        let Some(store) = test_store() else {
+            return Ok(());
+        };
+        store.memorize("old name", "body", &["x".into()], &emb(0.1))?;
+        let result = store.edit_memory(
+            "old name",
+            Some("new name"),
+            &["y".into()],
+            &["x".into()],
+            Some(&emb(0.2)),
+            &["alias".into()],
+            &[],
+            &[emb(0.3)],
+        )?;
+        assert_eq!(result.new_mnemonic, "new name");
+        assert_eq!(result.tags, vec!["y"]);
+        assert_eq!(result.mnemonics, vec!["new name", "alias"]);
+
+        let found = store.recall(&emb(0.3), 1, None, None, None)?.memories;
+        assert_eq!(found[0].mnemonic, "new name");
+
+        store.remove_mnemonic("new name", "alias")?;
+        assert!(store.remove_mnemonic("new name", "new name").is_err());
+
+        assert_eq!(store.rename_tag("y", "z")?, 1);
+        assert_eq!(store.list_tags()?[0].tag, "z");
+
+        store.rate("new name", true, None, None)?;
+        assert!(store.rate("missing", true, None, None).is_err());
+        assert_eq!(store.list_all_summaries()?[0].useful_count, 1);
+
+        assert!(store.delete_memory("new name")?);
+        assert!(store.list_all_summaries()?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_manual_merge() -> Result<()> {
+        let Some(store) = test_store() else {
+            return Ok(());
+        };
+        store.memorize("keep", "kept", &["a".into()], &emb(0.1))?;
+        store.memorize("discard", "absorbed", &["b".into()], &emb(-0.9))?;
+        store.memorize("other", "x", &[], &emb(0.9))?;
+        store.link("discard", "other", "related")?;
+
+        store.merge("keep", "discard", &emb(0.1))?;
+        let mem = store.get_memory_by_mnemonic("keep")?.unwrap();
+        assert_eq!(mem.content, "kept\n\nabsorbed");
+        assert_eq!(mem.mnemonics, vec!["keep", "discard"]);
+        assert!(mem.links.iter().any(|l| l.target_mnemonic == "other"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_jsonl_archive_roundtrip() -> Result<()> {
+        let Some(source) = test_store() else {
+            return Ok(());
+        };
+        let Some(target) = test_store() else {
+            return Ok(());
+        };
+        source.memorize("keep", "kept", &["a".into()], &emb(0.1))?;
+        source.memorize("other", "x", &[], &emb(-0.9))?;
+        source.link("keep", "other", "derived_from")?;
+        source.rate("keep", true, None, None)?;
+
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("archive.jsonl");
+        assert_eq!(source.export_jsonl(&path, None, true, &|_, _| true)?, 2);
+        assert_eq!(target.import_jsonl(&path, None)?.created, 2);
+        assert_eq!(
+            target.archive_records(None, true)?,
+            source.archive_records(None, true)?
+        );
+        assert_eq!(target.import_jsonl(&path, None)?.unchanged, 2);
+
+        let found = target.recall(&emb(0.1), 1, None, None, None)?.memories;
+        assert_eq!(
+            (found[0].mnemonic.as_str(), found[0].useful_count),
+            ("keep", 1)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_auth_entities() -> Result<()> {
+        let Some(mut store) = test_store() else {
+            return Ok(());
+        };
+        let user = store.create_user("alice", "*:read")?;
+        store.update_user_acl("alice", "*:update")?;
+        assert_eq!(store.get_user_by_id(user.id)?.unwrap().acl, "*:update");
+
+        store.set_secret_key(Some(SecretKey::generate()?));
+        let prov = store.create_provider("github", "github", "id", "secret", "{}")?;
+        let stored: String = store.with_client(|c| {
+            Ok(
+                c.query_one("SELECT client_secret FROM oauth_providers", &[])?
+                    .get(0),
+            )
+        })?;
+        assert!(secrets::is_encrypted(&stored));
+        let next = SecretKey::generate()?;
+        assert_eq!(store.reencrypt_provider_secrets(&next)?, 1);
+        store.set_secret_key(Some(next));
+        assert_eq!(
+            store.get_provider_by_name("github")?.unwrap().client_secret,
+            "secret"
+        );
+        assert!(store.has_auth_providers()?);
+        store.link_identity(user.id, prov.id, "alicegh", "42")?;
+        assert_eq!(
+            store
+                .get_user_by_provider_identity(prov.id, "42")?
+                .unwrap()
+                .username,
+            "alice"
+        );
+
+        let (client, secret) =
+            store.register_client(&["http://localhost/cb".into()], Some("App"))?;
+        assert!(store.verify_client_secret(&client.client_id, &secret.unwrap())?);
+        assert_eq!(
+            store.get_client(&client.client_id)?.unwrap().redirect_uris,
+            vec!["http://localhost/cb"]
+        );
+
+        let code = store.create_auth_code(
+            &client.client_id,
+            user.id,
+            "chal",
+            "http://localhost/cb",
+            Some("memory:read"),
+        )?;
+        let consumed = store.consume_auth_code(&code)?;
+        assert_eq!(
+            (consumed.user_id, consumed.scope.as_deref()),
+            (user.id, Some("memory:read"))
+        );
+        assert!(store.consume_auth_code(&code).is_err());
+
+        let state = store.create_pending_login("github", None, &LoginSecrets::default())?;
+        let login = store.take_pending_login(&state)?;
+        assert!(login.provider == "github" && login.authorization.is_none());
+        assert!(store.take_pending_login(&state).is_err());
+
+        let authorization = PendingAuthorization {
+            client_id: client.client_id.clone(),
+            redirect_uri: "http://localhost/cb".into(),
+            code_challenge: "chal".into(),
+            scope: "memory:read".into(),
+            client_state: "xyz".into(),
+        };
+        let token = store.create_pending_consent(user.id, &authorization)?;
+        assert_eq!(
+            store.take_pending_consent(&token)?.authorization,
+            authorization
+        );
+        assert!(store.take_pending_consent(&token).is_err());
+        store.grant_consent(user.id, &client.client_id, "memory:read")?;
+        assert_eq!(
+            store
+                .get_consent(user.id, &client.client_id)?
+                .unwrap()
+                .scope,
+            "memory:read"
+        );
+        assert_eq!(store.list_consents(user.id)?.len(), 1);
+
+        let pair = store.create_token_pair(&client.client_id, user.id, Some("memory:read"))?;
+        let grant = store.get_user_by_access_token(&pair.access_token)?.unwrap();
+        assert_eq!(
+            (grant.user.id, grant.scope.as_deref()),
+            (user.id, Some("memory:read"))
+        );
+        let refreshed = store
+            .get_user_by_refresh_token(&pair.refresh_token)?
+            .unwrap();
+        assert_eq!(
+            (refreshed.user.id, refreshed.client_id),
+            (user.id, client.client_id.clone())
+        );
+        let rotated = store.refresh_token_pair(&pair.refresh_token)?.unwrap();
+        assert_eq!(rotated.scope.as_deref(), Some("memory:read"));
+        assert!(
+            store
+                .get_user_by_access_token(&pair.access_token)?
+                .is_none()
+        );
+        assert!(store.refresh_token_pair(&pair.refresh_token)?.is_none());
+        store.revoke_token(&rotated.access_token)?;
+        let pair = store.create_token_pair(&client.client_id, user.id, None)?;
+        assert!(store.revoke_token(&pair.access_token)?);
+        assert!(
+            store
+                .get_user_by_refresh_token(&pair.refresh_token)?
+                .is_none()
+        );
+        assert!(!store.revoke_token(&pair.access_token)?);
+        let live = store.create_token_pair(&client.client_id, user.id, None)?;
+        crate::run_maintenance(&*store)?;
+        assert!(
+            store
+                .get_user_by_refresh_token(&live.refresh_token)?
+                .is_some()
+        );
+        store.revoke_token(&live.access_token)?;
+
+        let session = store.create_session(user.id)?;
+        let (_, found) = store.get_session(&session.session_id)?.unwrap();
+        assert_eq!(found.username, "alice");
+        store.delete_session(&session.session_id)?;
+        assert!(store.get_session(&session.session_id)?.is_none());
+
+        store.create_session(user.id)?;
+        store.create_token_pair(&client.client_id, user.id, None)?;
+        store.touch_user(user.id)?;
+        let activity = store.user_activity(user.id)?;
+        assert_eq!((activity.sessions, activity.tokens), (1, 1));
+        assert!(activity.last_seen_at.is_some());
+        assert_eq!(store.revoke_user_sessions(user.id)?, 1);
+        assert_eq!(store.revoke_user_tokens(user.id)?, 1);
+
+        let (_, secret) = store.create_personal_token(user.id, "ci", "memory:read", None)?;
+        let (token, owner) = store.get_user_by_personal_token(&secret)?.unwrap();
+        assert_eq!((owner.id, token.scope.as_str()), (user.id, "memory:read"));
+        assert!(token.last_used_at.is_some());
+        assert!(
+            store
+                .create_personal_token(user.id, "ci", "memory:read", None)
+                .is_err()
+        );
+        assert_eq!(store.list_personal_tokens(user.id)?.len(), 1);
+        assert!(store.delete_personal_token(user.id, "ci")?);
+        assert!(store.get_user_by_personal_token(&secret)?.is_none());
+        assert!(store.unlink_identity(user.id, prov.id)?);
+        store.set_provider_enabled("github", false)?;
+        assert!(!store.has_auth_providers()?);
+
+        store.set_user_admin("alice", true)?;
+        assert!(store.get_user_by_username("alice")?.unwrap().is_admin);
+        assert_eq!(store.list_clients()?.len(), 1);
+        store.record_audit(
+            Some("alice"),
+            "client.delete",
+            Some(&client.client_id),
+            None,
+        )?;
+        assert!(store.delete_client(&client.client_id)?);
+        assert_eq!(
+            store.list_audit(&AuditFilter::latest(10))?[0].action,
+            "client.delete"
+        );
+
+        store.memorize("owned", "by alice", &[], &emb(0.1))?;
+        store.set_owner("owned", Some("alice"))?;
+        assert_eq!(
+            store.list_all_summaries()?[0].owner.as_deref(),
+            Some("alice")
+        );
+        store.create_user("bob", "*:read")?;
+        assert_eq!(store.transfer_ownership("alice", "bob")?, 1);
+        assert_eq!(
+            store
+                .get_memory_by_mnemonic("owned")?
+                .unwrap()
+                .owner
+                .as_deref(),
+            Some("bob")
+        );
+        store.delete_user("bob")?;
+        assert_eq!(store.get_memory_by_mnemonic("owned")?.unwrap().owner, None);
+
+        store.create_group("eng", "project:update")?;
+        store.add_group_member("eng", "alice")?;
+        store.add_group_member("eng", "alice")?;
+        store.update_group_acl("eng", "project:update")?;
+        assert_eq!(store.groups_for_user(user.id)?[0].acl, "project:update");
+        assert_eq!(store.list_group_members("eng")?.len(), 1);
+        assert!(store.remove_group_member("eng", "alice")?);
+        assert!(store.delete_group("eng")?);
+
+        assert!(store.delete_user("alice")?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_audit() -> Result<()> {
+        let Some(mut store) = test_store() else {
+            return Ok(());
+        };
+        store.set_actor(Actor::new(Some("alice"), crate::audit::Transport::Http));
+        store.memorize("first", "one", &["a".into()], &emb(0.1))?;
+        store.memorize("second", "two", &[], &emb(0.9))?;
+        store.link("first", "second", "derived_from")?;
+        store.rate("first", false, None, None)?;
+        store.delete_memory("second")?;
+
+        let memory = AuditFilter {
+            action: Some("memory".into()),
+            ..AuditFilter::default()
+        };
+        let entries = store.list_audit(&memory)?;
+        let actions: Vec<&str> = entries.iter().map(|e| e.action.as_str()).collect();
+        assert_eq!(
+            actions,
+            [
+                "memory.delete",
+                "memory.rate",
+                "memory.link",
+                "memory.create",
+                "memory.create"
+            ]
+        );
+        assert!(entries.iter().all(|e| e.actor.as_deref() == Some("alice")));
+        assert!(
+            entries
+                .iter()
+                .all(|e| e.transport.as_deref() == Some("http"))
+        );
+        assert_eq!(
+            entries[2].detail.as_deref(),
+            Some("derived_from -> \"second\"")
+        );
+
+        let history = AuditFilter {
+            target: entries[1].target.clone(),
+            ..AuditFilter::default()
+        };
+        assert_eq!(store.list_audit(&history)?.len(), 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_ratings_per_rater() -> Result<()> {
+        let Some(mut store) = test_store() else {
+            return Ok(());
+        };
+        store.memorize("tip", "useful to some", &[], &emb(0.1))?;
+        for (rater, useful) in [
+            ("agent", true),
+            ("agent", true),
+            ("alice", false),
+            ("bob", false),
+        ] {
+            store.set_actor(Actor::new(Some(rater), crate::audit::Transport::Http));
+            store.rate("tip", useful, Some("how do I deploy"), None)?;
+        }
+        let mem = store.get_memory_by_mnemonic("tip")?.unwrap();
+        assert_eq!((mem.useful_count, mem.not_useful_count), (1, 2));
+        assert!(
+            store.with_client(|c| rating_signals(
+                c,
+                &[1],
+                &Actor::default(),
+                &RatingConfig::default()
+            ))?["tip"]
+                < 0.0
+        );
+
+        let records = store.archive_records(None, false)?;
+        assert_eq!(records[0].ratings.len(), 4);
+        assert_eq!(
+            records[0].ratings[0].query.as_deref(),
+            Some("how do I deploy")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_ratings_name_their_recall() -> Result<()> {
+        let Some(store) = test_store() else {
+            return Ok(());
+        };
+        store.memorize("tip", "noise for this question", &[], &emb(0.1))?;
+        let recall = store.recall(&emb(0.1), 1, None, None, None)?;
+        assert!(matches!(
+            store.rate("tip", false, None, Some("nope")),
+            Err(TriviaError::NotFound(_))
+        ));
+        store.rate("tip", false, None, Some(&recall.id))?;
+
+        let feedback =
+            store.with_client(|c| query_signals(c, &[1], &emb(0.1), &RatingConfig::default()))?;
+        assert!(feedback["tip"] < 0.0);
+        // Only for queries like that one
+        assert!(
+            store
+                .with_client(|c| rating_signals(
+                    c,
+                    &[1],
+                    &Actor::default(),
+                    &RatingConfig::default()
+                ))?
+                .is_empty()
+        );
+        assert_eq!(store.cleanup_unrated_recalls()?, 0);
+
+        let rated = store.rated_results()?;
+        assert_eq!(rated.len(), 1);
+        assert!(!rated[0].useful);
+        assert_eq!(
+            ScoringConfig::default().score(&rated[0].features),
+            recall.memories[0].score
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_works_inside_tokio_runtime() -> Result<()> {
+        let Some(store) = test_store() else {
+            return Ok(());
+        };
+        let rt = tokio::runtime::Builder::new_multi_thread().build()?;
+        rt.block_on(async {
+            store.memorize("async", "called from a runtime", &[], &emb(0.1))?;
+            assert!(store.get_memory_by_mnemonic("async")?.is_some());
+            Ok::<_, TriviaError>(())
+        })?;
+
+        // A single-threaded runtime has no other worker to hand tasks to
+        let rt = tokio::runtime::Builder::new_current_thread().build()?;
+        let err = rt
+            .block_on(async { store.get_memory_by_mnemonic("async") })
+            .unwrap_err();
+        assert!(matches!(err, TriviaError::Storage(_)), "{err}");
+        Ok(())
+    }
+}
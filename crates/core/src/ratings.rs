@@ -0,0 +1,685 @@
+//! Ratings are stored one row per verdict, attributed to whoever gave it and
+//! the query it was given for. For scoring, only each rater's latest verdict
+//! on a memory counts, and it counts for less as it ages, so no single rater
+//! can outweigh everyone else by rating over and over. Counters from before
+//! ratings were attributed are kept per memory and count as one more signal.
+//!
+//! Recalls are logged with their query embedding and results. A rating that
+//! names the recall it was given for also applies to later recalls whose
+//! queries are similar, so a memory that is noise for one kind of question
+//! can be pushed down for it without losing ground elsewhere.
+
+use chrono::{DateTime, Utc};
+use rusqlite::{OptionalExtension, params, params_from_iter};
+use std::collections::HashMap;
+use uuid::Uuid;
+use zerocopy::AsBytes;
+
+use crate::archive::vector_from_blob;
+use crate::config::RatingConfig;
+use crate::error::{Result, TriviaError};
+use crate::migrations::RECALL_FEATURE_COLUMNS;
+use crate::store::{MemoryStore, ScoreFeatures, days_between, parse_sqlite_datetime};
+
+/// Recalls nobody rated are kept this long before maintenance removes them.
+pub(crate) const UNRATED_RECALL_DAYS: i64 = 30;
+
+/// Keeps rating `r` only if it is its rater's latest on the memory outside
+/// any recall. Ratings given for a recall only apply to similar queries.
+/// Unattributed ratings count as one rater between them.
+const LATEST_PER_RATER: &str = "r.recall_id IS NULL AND r.id = (SELECT MAX(l.id) FROM ratings l WHERE l.memory_id = r.memory_id AND l.rater IS r.rater AND l.recall_id IS NULL)";
+
+/// Keeps rating `r` only if it is its rater's latest on the memory in its
+/// recall.
+pub(crate) const LATEST_PER_RATER_AND_RECALL: &str = "r.id = (SELECT MAX(l.id) FROM ratings l WHERE l.memory_id = r.memory_id AND l.rater IS r.rater AND l.recall_id = r.recall_id)";
+
+/// One rater's latest verdict on a memory.
+#[derive(Debug, Clone)]
+pub(crate) struct LatestRating {
+    pub title: String,
+    pub rater: Option<String>,
+    pub useful: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Counters a memory had before ratings were attributed.
+#[derive(Debug, Clone)]
+pub(crate) struct LegacyCounts {
+    pub title: String,
+    pub useful: i64,
+    pub not_useful: i64,
+}
+
+/// Net share of useful verdicts, scaled by confidence in how many there are.
+fn net_share(useful: f64, not_useful: f64) -> f64 {
+    let total = useful + not_useful;
+    if total > 0.0 {
+        let ratio = (useful - not_useful) / total;
+        let confidence = total.sqrt() / (total.sqrt() + 1.0);
+        ratio * confidence
+    } else {
+        0.0
+    }
+}
+
+#[derive(Debug, Default)]
+struct Tally {
+    useful: f64,
+    not_useful: f64,
+    /// The caller's own verdict: its weight, negated if not useful.
+    own: f64,
+    /// Net share of the legacy counters.
+    legacy: f64,
+}
+
+impl Tally {
+    /// Everyone's verdicts, plus the legacy counters at `legacy_weight` and
+    /// the caller's own verdict at `personal_weight`.
+    fn signal(&self, personal_weight: f64, legacy_weight: f64) -> f64 {
+        net_share(self.useful, self.not_useful)
+            + legacy_weight * self.legacy
+            + personal_weight * self.own
+    }
+}
+
+/// Rating signal per title from each rater's latest verdict and the legacy
+/// counters. A verdict's weight halves every `half_life_days`; `caller`'s own
+/// verdicts also count at the configured personal weight.
+pub(crate) fn signals_from_latest(
+    latest: &[LatestRating],
+    legacy: &[LegacyCounts],
+    caller: Option<&str>,
+    config: &RatingConfig,
+    now: DateTime<Utc>,
+) -> HashMap<String, f64> {
+    let half_life = config.half_life_days();
+    let mut tallies: HashMap<&str, Tally> = HashMap::new();
+    for rating in latest {
+        let weight = 0.5_f64.powf(days_between(rating.created_at, now) / half_life);
+        let tally = tallies.entry(rating.title.as_str()).or_default();
+        if rating.useful {
+            tally.useful += weight;
+        } else {
+            tally.not_useful += weight;
+        }
+        if caller.is_some() && rating.rater.as_deref() == caller {
+            tally.own = if rating.useful { weight } else { -weight };
+        }
+    }
+    for counts in legacy {
+        tallies.entry(counts.title.as_str()).or_default().legacy =
+            net_share(counts.useful as f64, counts.not_useful as f64);
+    }
+    let personal_weight = config.personal_weight();
+    let legacy_weight = config.legacy_weight();
+    tallies
+        .into_iter()
+        .map(|(title, tally)| {
+            (
+                title.to_string(),
+                tally.signal(personal_weight, legacy_weight),
+            )
+        })
+        .collect()
+}
+
+/// One rater's latest verdict on a memory in one recall, with the query
+/// embedding of that recall.
+#[derive(Debug, Clone)]
+pub(crate) struct RecallVerdict {
+    pub title: String,
+    pub rater: Option<String>,
+    pub useful: bool,
+    pub embedding: Vec<f32>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Fail unless `rater` is the `recaller` who ran the recall `uuid`; only
+/// they can rate its results against its query.
+pub(crate) fn require_recaller(
+    uuid: &str,
+    recaller: Option<&str>,
+    rater: Option<&str>,
+) -> Result<()> {
+    if recaller == rater {
+        Ok(())
+    } else {
+        Err(TriviaError::access_denied(format!(
+            "recall {uuid} was run by someone else"
+        )))
+    }
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f64 {
+    let dot: f64 = a
+        .iter()
+        .zip(b)
+        .map(|(x, y)| f64::from(*x) * f64::from(*y))
+        .sum();
+    let norm = |v: &[f32]| v.iter().map(|x| f64::from(*x).powi(2)).sum::<f64>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms > 0.0 { dot / norms } else { 0.0 }
+}
+
+/// Query feedback signal per title from verdicts given in recalls of
+/// queries at least `query_similarity` like `query_embedding`. Each rater
+/// counts once per memory, with their verdict from the most similar recall;
+/// a verdict weighs more the closer its query and the newer it is.
+pub(crate) fn query_signals_from(
+    verdicts: &[RecallVerdict],
+    query_embedding: &[f32],
+    config: &RatingConfig,
+    now: DateTime<Utc>,
+) -> HashMap<String, f64> {
+    let threshold = config.query_similarity();
+    let half_life = config.half_life_days();
+    let mut closest: HashMap<(&str, Option<&str>), (f64, &RecallVerdict)> = HashMap::new();
+    for verdict in verdicts {
+        let similarity = cosine_similarity(query_embedding, &verdict.embedding);
+        if similarity < threshold {
+            continue;
+        }
+        let key = (verdict.title.as_str(), verdict.rater.as_deref());
+        if closest.get(&key).is_none_or(|(best, _)| similarity > *best) {
+            closest.insert(key, (similarity, verdict));
+        }
+    }
+
+    // Net verdict over total weight, shrunk towards zero while there is
+    // little of it
+    let mut sums: HashMap<&str, (f64, f64)> = HashMap::new();
+    for ((title, _), (similarity, verdict)) in closest {
+        let closeness = (similarity - threshold) / (1.0 - threshold);
+        let age = 0.5_f64.powf(days_between(verdict.created_at, now) / half_life);
+        let weight = closeness * age;
+        let (net, total) = sums.entry(title).or_default();
+        *net += if verdict.useful { weight } else { -weight };
+        *total += weight;
+    }
+    sums.into_iter()
+        .map(|(title, (net, total))| (title.to_string(), net / (total + 1.0)))
+        .collect()
+}
+
+impl MemoryStore {
+    /// Record the current actor's verdict on `memory_id`, with the query and
+    /// recall it was rated for, and refresh the memory's counts.
+    pub(crate) fn record_rating(
+        &self,
+        memory_id: i64,
+        useful: bool,
+        query: Option<&str>,
+        recall: Option<i64>,
+    ) -> Result<()> {
+        self.conn().execute(
+            "INSERT INTO ratings (memory_id, rater, useful, query, recall_id)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![memory_id, self.actor.username, useful, query, recall],
+        )?;
+        self.refresh_rating_counts(memory_id)
+    }
+
+    /// Log a recall of `query_embedding` by the current actor that returned
+    /// `titles`, best first, with their score components, and return its id.
+    pub(crate) fn log_recall(
+        &self,
+        query_embedding: &[f32],
+        titles: &[&str],
+        features: &[ScoreFeatures],
+    ) -> Result<String> {
+        let uuid = Uuid::new_v4().to_string();
+        let tx = self.conn().unchecked_transaction()?;
+        tx.execute(
+            "INSERT INTO recalls (uuid, username, embedding) VALUES (?1, ?2, ?3)",
+            params![uuid, self.actor.username, query_embedding.as_bytes()],
+        )?;
+        let recall = tx.last_insert_rowid();
+        let sql = format!(
+            "INSERT OR IGNORE INTO recall_results (recall_id, memory_id, rank, {})
+             SELECT ?1, id, ?2, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11 FROM memories WHERE title = ?3",
+            RECALL_FEATURE_COLUMNS.join(", ")
+        );
+        for (rank, (title, features)) in titles.iter().zip(features).enumerate() {
+            tx.execute(
+                &sql,
+                params![
+                    recall,
+                    rank as i64,
+                    title,
+                    features.similarity,
+                    features.recency,
+                    features.frequency,
+                    features.link,
+                    features.rating,
+                    features.query,
+                    features.tag_boost,
+                    features.fts,
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(uuid)
+    }
+
+    /// Row id of the recall with id `uuid`, which the actor must have run.
+    pub(crate) fn recall_row_id(&self, uuid: &str) -> Result<i64> {
+        let (id, username): (i64, Option<String>) = self
+            .conn()
+            .query_row(
+                "SELECT id, username FROM recalls WHERE uuid = ?1",
+                params![uuid],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?
+            .ok_or_else(|| TriviaError::not_found(format!("recall not found: {uuid}")))?;
+        require_recaller(uuid, username.as_deref(), self.actor.username.as_deref())?;
+        Ok(id)
+    }
+
+    /// Fail unless `memory_id` was among the results of `recall`.
+    pub(crate) fn require_recalled(&self, recall: i64, memory_id: i64, title: &str) -> Result<()> {
+        let returned: bool = self.conn().query_row(
+            "SELECT EXISTS (SELECT 1 FROM recall_results WHERE recall_id = ?1 AND memory_id = ?2)",
+            params![recall, memory_id],
+            |row| row.get(0),
+        )?;
+        if returned {
+            Ok(())
+        } else {
+            Err(TriviaError::validation(format!(
+                "{title} was not returned by that recall"
+            )))
+        }
+    }
+
+    /// Delete recalls older than `UNRATED_RECALL_DAYS` that no rating names.
+    pub fn cleanup_unrated_recalls(&self) -> Result<usize> {
+        Ok(self.conn().execute(
+            "DELETE FROM recalls
+             WHERE created_at < datetime('now', ?1)
+             AND NOT EXISTS (SELECT 1 FROM ratings WHERE ratings.recall_id = recalls.id)",
+            params![format!("-{UNRATED_RECALL_DAYS} days")],
+        )?)
+    }
+
+    /// Set a memory's `useful_count` and `not_useful_count` to its legacy
+    /// counters plus the number of raters whose latest verdict outside any
+    /// recall is either.
+    pub(crate) fn refresh_rating_counts(&self, memory_id: i64) -> Result<()> {
+        self.conn().execute(
+            &format!(
+                "UPDATE memories SET
+                    useful_count = legacy_useful + (SELECT COUNT(*) FROM ratings r
+                        WHERE r.memory_id = ?1 AND r.useful = 1 AND {LATEST_PER_RATER}),
+                    not_useful_count = legacy_not_useful + (SELECT COUNT(*) FROM ratings r
+                        WHERE r.memory_id = ?1 AND r.useful = 0 AND {LATEST_PER_RATER})
+                 WHERE id = ?1"
+            ),
+            params![memory_id],
+        )?;
+        Ok(())
+    }
+
+    /// Rating signals of those of `memory_ids` that have been rated, keyed by
+    /// title and personalized for the current actor.
+    pub(crate) fn rating_signals(&self, memory_ids: &[i64]) -> Result<HashMap<String, f64>> {
+        if memory_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let placeholders: Vec<String> = (1..=memory_ids.len()).map(|i| format!("?{i}")).collect();
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT m.title, r.rater, r.useful, r.created_at
+             FROM ratings r
+             JOIN memories m ON m.id = r.memory_id
+             WHERE r.memory_id IN ({}) AND {LATEST_PER_RATER}",
+            placeholders.join(", ")
+        ))?;
+        let latest = stmt
+            .query_map(params_from_iter(memory_ids), |row| {
+                Ok(LatestRating {
+                    title: row.get(0)?,
+                    rater: row.get(1)?,
+                    useful: row.get(2)?,
+                    created_at: parse_sqlite_datetime(&row.get::<_, String>(3)?),
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT title, legacy_useful, legacy_not_useful
+             FROM memories
+             WHERE id IN ({}) AND (legacy_useful != 0 OR legacy_not_useful != 0)",
+            placeholders.join(", ")
+        ))?;
+        let legacy = stmt
+            .query_map(params_from_iter(memory_ids), |row| {
+                Ok(LegacyCounts {
+                    title: row.get(0)?,
+                    useful: row.get(1)?,
+                    not_useful: row.get(2)?,
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(signals_from_latest(
+            &latest,
+            &legacy,
+            self.actor.username.as_deref(),
+            &self.ratings,
+            Utc::now(),
+        ))
+    }
+
+    /// Query feedback signals of those of `memory_ids` rated in recalls of
+    /// queries like `query_embedding`, keyed by title.
+    pub(crate) fn query_signals(
+        &self,
+        memory_ids: &[i64],
+        query_embedding: &[f32],
+    ) -> Result<HashMap<String, f64>> {
+        if memory_ids.is_empty() {
+            return Ok(HashMap::new());
+        }
+        let placeholders: Vec<String> = (1..=memory_ids.len()).map(|i| format!("?{i}")).collect();
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT m.title, r.rater, r.useful, rc.embedding, r.created_at
+             FROM ratings r
+             JOIN recalls rc ON rc.id = r.recall_id
+             JOIN memories m ON m.id = r.memory_id
+             WHERE r.memory_id IN ({}) AND {LATEST_PER_RATER_AND_RECALL}",
+            placeholders.join(", ")
+        ))?;
+        let verdicts = stmt
+            .query_map(params_from_iter(memory_ids), |row| {
+                Ok(RecallVerdict {
+                    title: row.get(0)?,
+                    rater: row.get(1)?,
+                    useful: row.get(2)?,
+                    embedding: vector_from_blob(&row.get::<_, Vec<u8>>(3)?),
+                    created_at: parse_sqlite_datetime(&row.get::<_, String>(4)?),
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(query_signals_from(
+            &verdicts,
+            query_embedding,
+            &self.ratings,
+            Utc::now(),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{Actor, Transport};
+
+    fn rated(title: &str, rater: Option<&str>, useful: bool, at: DateTime<Utc>) -> LatestRating {
+        LatestRating {
+            title: title.into(),
+            rater: rater.map(str::to_string),
+            useful,
+            created_at: at,
+        }
+    }
+
+    fn as_user(store: &mut MemoryStore, username: &str) {
+        store.set_actor(Actor::new(Some(username), Transport::Http));
+    }
+
+    #[test]
+    fn each_rater_counts_once() -> Result<()> {
+        let mut store = MemoryStore::in_memory()?;
+        store.memorize("flaky test", "retry twice", &[], &[0.1; 384])?;
+
+        as_user(&mut store, "agent");
+        for _ in 0..20 {
+            store.rate("flaky test", true, None, None)?;
+        }
+        as_user(&mut store, "alice");
+        store.rate("flaky test", false, Some("why does ci fail"), None)?;
+        as_user(&mut store, "bob");
+        store.rate("flaky test", false, None, None)?;
+
+        let mem = store.get_memory_by_mnemonic("flaky test")?.unwrap();
+        assert_eq!((mem.useful_count, mem.not_useful_count), (1, 2));
+        assert!(store.rating_signals(&[1])?["flaky test"] < 0.0);
+
+        // A rater changing their mind replaces their earlier verdict
+        store.rate("flaky test", true, None, None)?;
+        let mem = store.get_memory_by_mnemonic("flaky test")?.unwrap();
+        assert_eq!((mem.useful_count, mem.not_useful_count), (2, 1));
+
+        let rows: i64 = store
+            .conn()
+            .query_row("SELECT COUNT(*) FROM ratings", [], |row| row.get(0))?;
+        assert_eq!(rows, 23);
+        let query: Option<String> = store.conn().query_row(
+            "SELECT query FROM ratings WHERE rater = 'alice'",
+            [],
+            |row| row.get(0),
+        )?;
+        assert_eq!(query.as_deref(), Some("why does ci fail"));
+        Ok(())
+    }
+
+    #[test]
+    fn old_ratings_count_for_less() {
+        let now = Utc::now();
+        let days_ago = |days| now - chrono::Duration::days(days);
+        let signals = signals_from_latest(
+            &[
+                rated("fresh", Some("alice"), true, now),
+                rated("stale", Some("alice"), true, days_ago(180)),
+                rated("mixed", Some("alice"), true, now),
+                rated("mixed", Some("bob"), false, days_ago(90)),
+            ],
+            &[],
+            None,
+            &RatingConfig::default(),
+            now,
+        );
+        assert!(signals["fresh"] > signals["stale"]);
+        assert!(signals["stale"] > 0.0);
+        // Bob's verdict is one half-life old, so counts half as much as Alice's
+        let expected = (1.0 - 0.5) / 1.5 * 1.5_f64.sqrt() / (1.5_f64.sqrt() + 1.0);
+        assert!((signals["mixed"] - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn own_ratings_are_personal() -> Result<()> {
+        let now = Utc::now();
+        let latest = [
+            rated("tip", Some("alice"), false, now),
+            rated("tip", Some("bob"), true, now),
+        ];
+        let config = RatingConfig {
+            personal_weight: Some(1.0),
+            ..Default::default()
+        };
+        assert_eq!(
+            signals_from_latest(&latest, &[], None, &config, now)["tip"],
+            0.0
+        );
+        assert!(signals_from_latest(&latest, &[], Some("alice"), &config, now)["tip"] < 0.0);
+        assert!(signals_from_latest(&latest, &[], Some("bob"), &config, now)["tip"] > 0.0);
+        assert_eq!(
+            signals_from_latest(&latest, &[], Some("bob"), &RatingConfig::default(), now)["tip"],
+            0.0
+        );
+
+        // Recall ranks by the caller's own verdicts
+        let mut store = MemoryStore::in_memory()?;
+        store.set_rating_config(config);
+        let emb1: Vec<f32> = (0..384).map(|i| (i as f32) / 384.0).collect();
+        let emb2: Vec<f32> = (0..384).map(|i| (i as f32) / 384.0 + 0.01).collect();
+        let mid: Vec<f32> = (0..384).map(|i| (i as f32) / 384.0 + 0.005).collect();
+        store.memorize("plain", "unrated", &[], &emb1)?;
+        store.memorize("tip", "contested", &[], &emb2)?;
+        as_user(&mut store, "alice");
+        store.rate("tip", false, None, None)?;
+        assert_eq!(
+            store.recall(&mid, 2, None, None, None)?.memories[0].mnemonic,
+            "plain"
+        );
+        as_user(&mut store, "bob");
+        store.rate("tip", true, None, None)?;
+        assert_eq!(
+            store.recall(&mid, 2, None, None, None)?.memories[0].mnemonic,
+            "tip"
+        );
+        Ok(())
+    }
+
+    /// A ramp, and the ramp reversed: a query about half as similar.
+    fn ramp(offset: f32) -> Vec<f32> {
+        (0..384).map(|i| (i as f32) / 384.0 + offset).collect()
+    }
+
+    fn reversed() -> Vec<f32> {
+        (0..384).map(|i| ((383 - i) as f32) / 384.0).collect()
+    }
+
+    #[test]
+    fn feedback_applies_to_similar_queries() {
+        let now = Utc::now();
+        let verdict = |rater: &str, useful, embedding: Vec<f32>| RecallVerdict {
+            title: "tip".into(),
+            rater: Some(rater.into()),
+            useful,
+            embedding,
+            created_at: now,
+        };
+        let config = RatingConfig::default();
+
+        let downvoted = [verdict("alice", false, ramp(0.0))];
+        let signals = query_signals_from(&downvoted, &ramp(0.0), &config, now);
+        assert!((signals["tip"] + 0.5).abs() < 1e-6);
+        assert!(query_signals_from(&downvoted, &reversed(), &config, now).is_empty());
+
+        // Each rater counts once, with their verdict from the closest query
+        let verdicts = [
+            verdict("alice", true, reversed()),
+            verdict("alice", false, ramp(0.0)),
+            verdict("alice", true, ramp(0.5)),
+        ];
+        let signals = query_signals_from(&verdicts, &ramp(0.0), &config, now);
+        assert!((signals["tip"] + 0.5).abs() < 1e-6);
+    }
+
+    #[test]
+    fn ratings_name_their_recall() -> Result<()> {
+        let mut store = MemoryStore::in_memory()?;
+        store.memorize("plain", "unrated", &[], &ramp(0.0))?;
+        store.memorize("tip", "noise for this question", &[], &ramp(0.01))?;
+        store.memorize("elsewhere", "never recalled", &[], &reversed())?;
+        as_user(&mut store, "alice");
+
+        let recall = store.recall(&ramp(0.005), 2, None, None, None)?;
+        assert_eq!(recall.memories.len(), 2);
+        let err = store
+            .rate("elsewhere", false, None, Some(&recall.id))
+            .unwrap_err();
+        assert!(matches!(err, TriviaError::Validation(_)), "{err}");
+        let err = store
+            .rate("tip", false, None, Some("no-such-recall"))
+            .unwrap_err();
+        assert!(matches!(err, TriviaError::NotFound(_)), "{err}");
+        as_user(&mut store, "bob");
+        let err = store
+            .rate("tip", false, None, Some(&recall.id))
+            .unwrap_err();
+        assert!(matches!(err, TriviaError::AccessDenied(_)), "{err}");
+        as_user(&mut store, "alice");
+        store.rate("tip", false, Some("why is ci slow"), Some(&recall.id))?;
+
+        // Pushed down for queries like the one it was rated in, not others
+        let ids = [1, 2];
+        assert!(store.query_signals(&ids, &ramp(0.005))?["tip"] < 0.0);
+        assert!(store.query_signals(&ids, &reversed())?.is_empty());
+        assert_eq!(
+            store.recall(&ramp(0.005), 2, None, None, None)?.memories[0].mnemonic,
+            "plain"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn recall_ratings_stay_with_their_query() -> Result<()> {
+        let mut store = MemoryStore::in_memory()?;
+        store.memorize("plain", "unrated", &[], &ramp(0.0))?;
+        store.memorize("tip", "noise for one question", &[], &ramp(0.01))?;
+        as_user(&mut store, "alice");
+        let recall = store.recall(&ramp(0.005), 2, None, None, None)?;
+        store.rate("tip", false, None, Some(&recall.id))?;
+
+        // The rating and query feedback components `tip` was scored with
+        let components = |store: &MemoryStore, query: &[f32]| -> Result<(f64, f64)> {
+            let recall = store.recall(query, 2, None, None, None)?;
+            Ok(store.conn().query_row(
+                "SELECT rr.rating, rr.query FROM recall_results rr
+                 JOIN memories m ON m.id = rr.memory_id
+                 WHERE rr.recall_id = ?1 AND m.title = 'tip'",
+                params![store.recall_row_id(&recall.id)?],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )?)
+        };
+        // A downvote for query A pushes `tip` down for A but not for B
+        let (rating, query) = components(&store, &ramp(0.005))?;
+        assert_eq!(rating, 0.0);
+        assert!(query < 0.0);
+        assert_eq!(components(&store, &reversed())?, (0.0, 0.0));
+
+        assert!(store.rating_signals(&[2])?.is_empty());
+        let mem = store.get_memory_by_mnemonic("tip")?.unwrap();
+        assert_eq!((mem.useful_count, mem.not_useful_count), (0, 0));
+        Ok(())
+    }
+
+    #[test]
+    fn unrated_recalls_expire() -> Result<()> {
+        let store = MemoryStore::in_memory()?;
+        store.memorize("tip", "kept", &[], &ramp(0.0))?;
+        let rated = store.recall(&ramp(0.0), 1, None, None, None)?;
+        store.recall(&ramp(0.0), 1, None, None, None)?;
+        store.rate("tip", true, None, Some(&rated.id))?;
+        store.conn().execute(
+            "UPDATE recalls SET created_at = datetime('now', '-31 days')",
+            [],
+        )?;
+        store.recall(&ramp(0.0), 1, None, None, None)?;
+
+        assert_eq!(store.cleanup_unrated_recalls()?, 1);
+        let left: i64 = store
+            .conn()
+            .query_row("SELECT COUNT(*) FROM recalls", [], |row| row.get(0))?;
+        assert_eq!(left, 2);
+        assert!(store.recall_row_id(&rated.id).is_ok());
+        Ok(())
+    }
+
+    #[test]
+    fn legacy_counts_are_one_signal() {
+        let now = Utc::now();
+        let legacy = |title: &str, useful, not_useful| LegacyCounts {
+            title: title.into(),
+            useful,
+            not_useful,
+        };
+        let config = RatingConfig::default();
+        let signals = signals_from_latest(
+            &[rated("lopsided", Some("alice"), false, now)],
+            &[
+                legacy("lopsided", 40, 3),
+                legacy("tied", 2, 2),
+                legacy("liked", 40, 3),
+            ],
+            None,
+            &config,
+            now,
+        );
+        assert_eq!(signals["tied"], 0.0);
+        // However many votes, the counters weigh no more than `legacy_weight`
+        assert!(signals["liked"] > 0.0 && signals["liked"] < config.legacy_weight());
+        // One rater's downvote is not drowned out by forty old upvotes
+        assert!(signals["lopsided"] < 0.0);
+    }
+}
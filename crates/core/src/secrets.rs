@@ -0,0 +1,207 @@
+//! Encryption of secrets stored in the database, currently OAuth provider
+//! client secrets. Values are sealed with AES-256-GCM under a key kept
+//! outside the database (`TRIVIA_SECRET_KEY` or `TRIVIA_SECRET_KEY_FILE`),
+//! so a copied database or backup doesn't give the secrets away.
+//!
+//! Sealed values look like `enc:v1:<base64 nonce + ciphertext>`; anything
+//! else is a plaintext value from before a key was configured. Each value is
+//! bound to a context, the provider name, so a sealed secret copied onto
+//! another provider's row fails to decrypt instead of being used there.
+
+use std::path::Path;
+
+use anyhow::Context;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use ring::aead::{AES_256_GCM, Aad, LessSafeKey, NONCE_LEN, Nonce, UnboundKey};
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::error::{Result, TriviaError};
+
+/// Holds the key itself, base64-encoded.
+pub const KEY_ENV: &str = "TRIVIA_SECRET_KEY";
+/// Names a file holding the key, base64-encoded.
+pub const KEY_FILE_ENV: &str = "TRIVIA_SECRET_KEY_FILE";
+
+const PREFIX: &str = "enc:v1:";
+const KEY_LEN: usize = 32;
+
+/// A 256-bit key for sealing stored secrets.
+#[derive(Clone)]
+pub struct SecretKey([u8; KEY_LEN]);
+
+impl std::fmt::Debug for SecretKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SecretKey(..)")
+    }
+}
+
+impl SecretKey {
+    pub fn generate() -> Result<Self> {
+        let mut key = [0u8; KEY_LEN];
+        SystemRandom::new()
+            .fill(&mut key)
+            .map_err(|_| TriviaError::storage("no secure random source available"))?;
+        Ok(Self(key))
+    }
+
+    pub fn from_base64(encoded: &str) -> Result<Self> {
+        let bytes = BASE64
+            .decode(encoded.trim())
+            .map_err(|e| TriviaError::validation(format!("secret key is not valid base64: {e}")))?;
+        let key = bytes
+            .try_into()
+            .map_err(|_| TriviaError::validation(format!("secret key must be {KEY_LEN} bytes")))?;
+        Ok(Self(key))
+    }
+
+    pub fn to_base64(&self) -> String {
+        BASE64.encode(self.0)
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let encoded = std::fs::read_to_string(path)
+            .with_context(|| format!("reading secret key from {}", path.display()))?;
+        Self::from_base64(&encoded)
+    }
+
+    /// The key from `TRIVIA_SECRET_KEY` or, failing that, the file named by
+    /// `TRIVIA_SECRET_KEY_FILE`. `None` if neither is set.
+    pub fn from_env() -> Result<Option<Self>> {
+        if let Ok(encoded) = std::env::var(KEY_ENV) {
+            return Self::from_base64(&encoded).map(Some);
+        }
+        match std::env::var(KEY_FILE_ENV) {
+            Ok(path) => Self::from_file(Path::new(&path)).map(Some),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Write the key to a new file readable only by its owner. Refuses to
+    /// overwrite an existing file, which may hold a key still in use.
+    pub fn write_new_file(&self, path: &Path) -> Result<()> {
+        use std::io::Write;
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options
+            .open(path)
+            .with_context(|| format!("creating key file {}", path.display()))?;
+        writeln!(file, "{}", self.to_base64())?;
+        Ok(())
+    }
+
+    fn cipher(&self) -> LessSafeKey {
+        LessSafeKey::new(
+            UnboundKey::new(&AES_256_GCM, &self.0).expect("key has the AES-256 length"),
+        )
+    }
+
+    pub fn encrypt(&self, context: &str, plaintext: &str) -> Result<String> {
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| TriviaError::storage("no secure random source available"))?;
+        let mut sealed = plaintext.as_bytes().to_vec();
+        self.cipher()
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::from(context.as_bytes()),
+                &mut sealed,
+            )
+            .map_err(|_| TriviaError::storage("encryption failed"))?;
+        let mut out = nonce.to_vec();
+        out.extend_from_slice(&sealed);
+        Ok(format!("{PREFIX}{}", BASE64.encode(out)))
+    }
+
+    pub fn decrypt(&self, context: &str, stored: &str) -> Result<String> {
+        let encoded = stored
+            .strip_prefix(PREFIX)
+            .ok_or_else(|| TriviaError::validation("value is not encrypted"))?;
+        let mut bytes = BASE64
+            .decode(encoded)
+            .map_err(|_| TriviaError::validation("encrypted value is corrupt"))?;
+        if bytes.len() < NONCE_LEN {
+            return Err(TriviaError::validation("encrypted value is corrupt"));
+        }
+        let mut sealed = bytes.split_off(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(&bytes)
+            .map_err(|_| TriviaError::validation("encrypted value is corrupt"))?;
+        let plaintext = self
+            .cipher()
+            .open_in_place(nonce, Aad::from(context.as_bytes()), &mut sealed)
+            .map_err(|_| {
+                TriviaError::validation(format!(
+                    "cannot decrypt stored secret for {context}: wrong secret key"
+                ))
+            })?;
+        String::from_utf8(plaintext.to_vec())
+            .map_err(|_| TriviaError::validation("encrypted value is corrupt"))
+    }
+}
+
+pub fn is_encrypted(stored: &str) -> bool {
+    stored.starts_with(PREFIX)
+}
+
+/// The form to store `plaintext` in: encrypted if there is a key.
+pub fn seal(key: Option<&SecretKey>, context: &str, plaintext: &str) -> Result<String> {
+    match key {
+        Some(key) => key.encrypt(context, plaintext),
+        None => Ok(plaintext.to_string()),
+    }
+}
+
+/// The plaintext of a stored value, which may predate encryption.
+pub fn reveal(key: Option<&SecretKey>, context: &str, stored: &str) -> Result<String> {
+    if !is_encrypted(stored) {
+        return Ok(stored.to_string());
+    }
+    match key {
+        Some(key) => key.decrypt(context, stored),
+        None => Err(TriviaError::validation(format!(
+            "stored secrets are encrypted; set {KEY_ENV} or {KEY_FILE_ENV}"
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_and_wrong_key() {
+        let key = SecretKey::generate().unwrap();
+        let sealed = key.encrypt("github", "hunter2").unwrap();
+        assert!(is_encrypted(&sealed));
+        assert!(!sealed.contains("hunter2"));
+        // A fresh nonce each time
+        assert_ne!(sealed, key.encrypt("github", "hunter2").unwrap());
+        assert_eq!(key.decrypt("github", &sealed).unwrap(), "hunter2");
+        // Bound to the provider it was sealed for
+        assert!(key.decrypt("google", &sealed).is_err());
+
+        let other = SecretKey::generate().unwrap();
+        assert!(other.decrypt("github", &sealed).is_err());
+        assert!(reveal(None, "github", &sealed).is_err());
+        assert_eq!(reveal(None, "github", "plain").unwrap(), "plain");
+        assert_eq!(reveal(Some(&key), "github", "plain").unwrap(), "plain");
+    }
+
+    #[test]
+    fn key_encoding() {
+        let key = SecretKey::generate().unwrap();
+        let decoded = SecretKey::from_base64(&format!("{}\n", key.to_base64())).unwrap();
+        assert_eq!(decoded.0, key.0);
+        assert!(SecretKey::from_base64("c2hvcnQ=").is_err());
+        assert!(SecretKey::from_base64("not base64!").is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("key");
+        key.write_new_file(&path).unwrap();
+        assert_eq!(SecretKey::from_file(&path).unwrap().0, key.0);
+        assert!(key.write_new_file(&path).is_err());
+    }
+}
@@ -0,0 +1,1047 @@
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::archive::{self, ArchiveRecord};
+use crate::audit::{Actor, AuditEntry, AuditFilter};
+use crate::auth_store::{
+    ClientConsent, Group, LoginSecrets, OAuthClient, OAuthCode, OAuthProvider,
+    PendingAuthorization, PendingConsent, PendingLogin, PersonalToken, Session, TokenGrant,
+    TokenPair, User, UserActivity, UserIdentity,
+};
+use crate::config::{AuthConfig, RatingConfig, ScoringWeights};
+use crate::embedder::Embedder;
+use crate::error::Result;
+use crate::export::{self, ExportEntry, ImportOutcome, ImportResult};
+use crate::secrets::SecretKey;
+use crate::store::{
+    EditResult, MemorizeResult, Memory, MemoryLink, MemoryStore, MemorySummary, MergeCandidate,
+    Recall, TagCount,
+};
+use crate::tune::RatedResult;
+
+/// Everything the CLI, MCP server and web UI need from a database:
+/// memories, mnemonics and their vectors, links, full-text search and the
+/// auth entities. `MemoryStore` (SQLite + sqlite-vec) is the default
+/// implementation; `PgStore` (Postgres + pgvector) is available behind the
+/// `postgres` feature.
+///
+/// Methods mirror `MemoryStore`'s inherent API; see there for semantics.
+pub trait Storage: Send {
+    fn set_boost_tags(&mut self, tags: Vec<String>);
+    fn set_scoring_weights(&mut self, weights: &ScoringWeights);
+    fn set_auth_config(&mut self, auth: AuthConfig);
+    fn set_rating_config(&mut self, ratings: RatingConfig);
+    fn set_secret_key(&mut self, key: Option<SecretKey>);
+
+    /// Attribute subsequent changes to `actor` in the audit log, returning
+    /// the previous one so callers can restore it.
+    fn set_actor(&mut self, actor: Actor) -> Actor;
+
+    /// Write a consistent copy of the whole database to `dest` without
+    /// blocking other users of it.
+    fn backup_to(&self, dest: &Path) -> Result<()>;
+    /// The SQLite file behind this store, so long-running work such as
+    /// scheduled backups can open a connection of its own. `None` for
+    /// Postgres and in-memory stores.
+    fn database_path(&self) -> Option<PathBuf>;
+
+    /// Routine index and statistics upkeep, run by `run_maintenance`.
+    fn optimize(&self) -> Result<()>;
+    /// Delete old recalls that no rating names.
+    fn cleanup_unrated_recalls(&self) -> Result<usize>;
+    /// Ratings given with a recall id, with the rated result's score
+    /// components, for `trivia tune`.
+    fn rated_results(&self) -> Result<Vec<RatedResult>>;
+
+    // ========== Memories ==========
+
+    fn memorize(
+        &self,
+        mnemonic: &str,
+        content: &str,
+        tags: &[String],
+        embedding: &[f32],
+    ) -> Result<MemorizeResult>;
+
+    fn memorize_with_options(
+        &self,
+        mnemonic: &str,
+        content: &str,
+        tags: &[String],
+        embedding: &[f32],
+        skip_merge: bool,
+        owner: Option<&str>,
+    ) -> Result<MemorizeResult>;
+
+    fn recall(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        tags: Option<&[String]>,
+        fts_query: Option<&str>,
+        exclude_tags: Option<&[String]>,
+    ) -> Result<Recall> {
+        self.recall_filtered(
+            query_embedding,
+            limit,
+            tags,
+            fts_query,
+            exclude_tags,
+            &|_| true,
+        )
+    }
+
+    /// Like `recall`, considering only memories `readable` accepts, e.g.
+    /// those a caller's ACL lets them read. Others are skipped before the
+    /// limit applies and are neither counted as recalled nor logged.
+    fn recall_filtered(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        tags: Option<&[String]>,
+        fts_query: Option<&str>,
+        exclude_tags: Option<&[String]>,
+        readable: &dyn Fn(&Memory) -> bool,
+    ) -> Result<Recall>;
+
+    fn get_memory_by_mnemonic(&self, title: &str) -> Result<Option<Memory>>;
+    fn list_all_summaries(&self) -> Result<Vec<MemorySummary>>;
+    fn update_memory(
+        &self,
+        title: &str,
+        content: &str,
+        tags: &[String],
+        embedding: &[f32],
+    ) -> Result<()>;
+    fn rename_memory(&self, old_title: &str, new_title: &str, embedding: &[f32]) -> Result<()>;
+
+    #[allow(clippy::too_many_arguments)]
+    fn edit_memory(
+        &self,
+        title: &str,
+        new_title: Option<&str>,
+        add_tags: &[String],
+        remove_tags: &[String],
+        new_embedding: Option<&[f32]>,
+        add_mnemonics: &[String],
+        remove_mnemonics: &[String],
+        mnemonic_embeddings: &[Vec<f32>],
+    ) -> Result<EditResult>;
+
+    fn delete_memory(&self, title: &str) -> Result<bool>;
+    fn merge(&self, keep: &str, discard: &str, embedding: &[f32]) -> Result<()>;
+    fn rate(
+        &self,
+        title: &str,
+        useful: bool,
+        query: Option<&str>,
+        recall_id: Option<&str>,
+    ) -> Result<()>;
+    fn rate_batch(
+        &self,
+        titles: &[String],
+        useful: bool,
+        query: Option<&str>,
+        recall_id: Option<&str>,
+    ) -> Result<Vec<String>>;
+    fn list_tags(&self) -> Result<Vec<TagCount>>;
+    fn rename_tag(&self, old_tag: &str, new_tag: &str) -> Result<usize>;
+
+    fn find_nearest(
+        &self,
+        embedding: &[f32],
+        threshold: f64,
+        exclude_title: &str,
+    ) -> Result<Vec<(String, f64)>>;
+    fn find_merge_candidates(
+        &self,
+        embedding: &[f32],
+        threshold: f64,
+        exclude: &HashSet<String>,
+        limit: usize,
+    ) -> Result<Vec<MergeCandidate>>;
+
+    // ========== Mnemonics ==========
+
+    fn add_mnemonic(&self, title: &str, text: &str, embedding: &[f32]) -> Result<()>;
+    fn remove_mnemonic(&self, title: &str, text: &str) -> Result<()>;
+
+    // ========== Links ==========
+
+    fn link(&self, source_title: &str, target_title: &str, link_type: &str) -> Result<()>;
+    fn unlink(&self, source_title: &str, target_title: &str, link_type: &str) -> Result<()>;
+    fn get_links(&self, title: &str) -> Result<Vec<MemoryLink>>;
+    fn get_all_links(&self) -> Result<Vec<MemoryLink>>;
+
+    // ========== Export / import ==========
+
+    fn export_entries(&self, tags: Option<&[String]>) -> Result<Vec<ExportEntry>>;
+    fn import_entry(&self, entry: &ExportEntry, embedder: &Embedder) -> Result<ImportOutcome>;
+    fn import_link(
+        &self,
+        source_uuid: &str,
+        target_uuid: &str,
+        link_type: &str,
+        created_at: Option<DateTime<Utc>>,
+    ) -> Result<()>;
+    fn archive_records(
+        &self,
+        tags: Option<&[String]>,
+        with_vectors: bool,
+    ) -> Result<Vec<ArchiveRecord>>;
+    fn mnemonics_with_vectors(&self, texts: &[String]) -> Result<HashSet<String>>;
+    fn import_record(
+        &self,
+        record: &ArchiveRecord,
+        vectors: &HashMap<String, Vec<f32>>,
+    ) -> Result<ImportOutcome>;
+
+    /// Export memories to `dir` as markdown files with YAML frontmatter.
+    fn export(&self, dir: &Path, tags: Option<&[String]>) -> Result<()> {
+        self.export_filtered(dir, tags, &|_, _| true)
+    }
+
+    /// Like `export`, but only memories for which `filter(title, tags)`
+    /// returns true are written.
+    fn export_filtered(
+        &self,
+        dir: &Path,
+        tags: Option<&[String]>,
+        filter: &dyn Fn(&str, &[String]) -> bool,
+    ) -> Result<()> {
+        let entries: Vec<ExportEntry> = self
+            .export_entries(tags)?
+            .into_iter()
+            .filter(|e| filter(&e.title, &e.tags))
+            .collect();
+        export::write_markdown(dir, &entries)
+    }
+
+    /// Import a directory written by `export`, matching memories by UUID.
+    fn import(&self, dir: &Path, embedder: &Embedder) -> Result<ImportResult> {
+        let entries = export::read_markdown(dir)?;
+        let mut result = ImportResult::default();
+        for entry in &entries {
+            result.record(self.import_entry(entry, embedder)?);
+        }
+        // Links need both ends to exist, so they go in a second pass
+        for entry in &entries {
+            for link in &entry.links {
+                self.import_link(&entry.uuid, &link.target, &link.link_type, None)?;
+            }
+        }
+        Ok(result)
+    }
+
+    /// Write a JSONL archive to `path`: one full record per memory, keeping
+    /// timestamps, recall and rating counters, aliases and link metadata.
+    /// With `with_vectors`, embeddings are included so an import can skip
+    /// re-embedding. Returns the number of records written.
+    fn export_jsonl(
+        &self,
+        path: &Path,
+        tags: Option<&[String]>,
+        with_vectors: bool,
+        filter: &dyn Fn(&str, &[String]) -> bool,
+    ) -> Result<usize> {
+        let records: Vec<ArchiveRecord> = self
+            .archive_records(tags, with_vectors)?
+            .into_iter()
+            .filter(|r| filter(&r.title, &r.tags))
+            .collect();
+        archive::write_jsonl(path, &records)?;
+        Ok(records.len())
+    }
+
+    /// Import a JSONL archive, overwriting memories with the same UUID.
+    /// `embedder` is only needed when the archive lacks vectors for this
+    /// build's model.
+    fn import_jsonl(&self, path: &Path, embedder: Option<&Embedder>) -> Result<ImportResult> {
+        match embedder {
+            Some(embedder) => self.import_jsonl_with(path, &mut |text| embedder.embed(text)),
+            None => self.import_jsonl_with(path, &mut archive::no_embedder),
+        }
+    }
+
+    /// `import_jsonl` with a caller-supplied embedding function.
+    fn import_jsonl_with(
+        &self,
+        path: &Path,
+        embed: &mut dyn FnMut(&str) -> Result<Vec<f32>>,
+    ) -> Result<ImportResult> {
+        let records = archive::read_jsonl(path)?;
+        let mut result = ImportResult::default();
+        for record in &records {
+            let texts: Vec<String> = record.mnemonics.iter().map(|m| m.text.clone()).collect();
+            let has_vector = self.mnemonics_with_vectors(&texts)?;
+            let vectors = archive::vectors_for_import(record, &has_vector, embed)?;
+            result.record(self.import_record(record, &vectors)?);
+        }
+        for record in &records {
+            for link in &record.links {
+                self.import_link(
+                    &record.uuid,
+                    &link.target,
+                    &link.link_type,
+                    Some(link.created_at),
+                )?;
+            }
+        }
+        Ok(result)
+    }
+
+    // ========== Users ==========
+
+    fn create_user(&self, username: &str, acl: &str) -> Result<User>;
+    fn get_user_by_username(&self, username: &str) -> Result<Option<User>>;
+    fn get_user_by_id(&self, id: i64) -> Result<Option<User>>;
+    fn update_user_acl(&self, username: &str, acl: &str) -> Result<()>;
+    fn set_user_admin(&self, username: &str, is_admin: bool) -> Result<()>;
+    fn list_users(&self) -> Result<Vec<User>>;
+    fn delete_user(&self, username: &str) -> Result<bool>;
+    fn touch_user(&self, user_id: i64) -> Result<()>;
+    fn user_activity(&self, user_id: i64) -> Result<UserActivity>;
+
+    // ========== Ownership ==========
+
+    fn set_owner(&self, title: &str, username: Option<&str>) -> Result<()>;
+    fn transfer_ownership(&self, from: &str, to: &str) -> Result<usize>;
+
+    // ========== Groups ==========
+
+    fn create_group(&self, name: &str, acl: &str) -> Result<Group>;
+    fn get_group_by_name(&self, name: &str) -> Result<Option<Group>>;
+    fn list_groups(&self) -> Result<Vec<Group>>;
+    fn update_group_acl(&self, name: &str, acl: &str) -> Result<()>;
+    fn delete_group(&self, name: &str) -> Result<bool>;
+    fn add_group_member(&self, group: &str, username: &str) -> Result<()>;
+    fn remove_group_member(&self, group: &str, username: &str) -> Result<bool>;
+    fn list_group_members(&self, group: &str) -> Result<Vec<User>>;
+    fn groups_for_user(&self, user_id: i64) -> Result<Vec<Group>>;
+
+    // ========== OAuth providers and identities ==========
+
+    fn create_provider(
+        &self,
+        name: &str,
+        provider_type: &str,
+        client_id: &str,
+        client_secret: &str,
+        config: &str,
+    ) -> Result<OAuthProvider>;
+    fn get_provider_by_name(&self, name: &str) -> Result<Option<OAuthProvider>>;
+    fn list_providers(&self) -> Result<Vec<OAuthProvider>>;
+    fn reencrypt_provider_secrets(&self, new_key: &SecretKey) -> Result<usize>;
+    fn delete_provider(&self, name: &str) -> Result<bool>;
+    fn set_provider_enabled(&self, name: &str, enabled: bool) -> Result<()>;
+    fn has_auth_providers(&self) -> Result<bool>;
+    fn list_enabled_providers(&self) -> Result<Vec<(String, String)>>;
+
+    fn link_identity(
+        &self,
+        user_id: i64,
+        provider_id: i64,
+        provider_username: &str,
+        provider_user_id: &str,
+    ) -> Result<()>;
+    fn get_user_by_provider_identity(
+        &self,
+        provider_id: i64,
+        provider_user_id: &str,
+    ) -> Result<Option<User>>;
+    fn list_identities_for_user(&self, user_id: i64) -> Result<Vec<UserIdentity>>;
+    fn unlink_identity(&self, user_id: i64, provider_id: i64) -> Result<bool>;
+
+    // ========== OAuth clients, codes, tokens, sessions ==========
+
+    fn register_client(
+        &self,
+        redirect_uris: &[String],
+        client_name: Option<&str>,
+    ) -> Result<(OAuthClient, Option<String>)>;
+    fn get_client(&self, client_id: &str) -> Result<Option<OAuthClient>>;
+    fn list_clients(&self) -> Result<Vec<OAuthClient>>;
+    fn delete_client(&self, client_id: &str) -> Result<bool>;
+    fn verify_client_secret(&self, client_id: &str, secret: &str) -> Result<bool>;
+
+    fn create_auth_code(
+        &self,
+        client_id: &str,
+        user_id: i64,
+        code_challenge: &str,
+        redirect_uri: &str,
+        scope: Option<&str>,
+    ) -> Result<String>;
+    fn consume_auth_code(&self, code: &str) -> Result<OAuthCode>;
+    fn cleanup_expired_codes(&self) -> Result<usize>;
+
+    fn create_pending_login(
+        &self,
+        provider: &str,
+        authorization: Option<&PendingAuthorization>,
+        secrets: &LoginSecrets,
+    ) -> Result<String>;
+    fn take_pending_login(&self, state: &str) -> Result<PendingLogin>;
+
+    fn create_pending_consent(
+        &self,
+        user_id: i64,
+        authorization: &PendingAuthorization,
+    ) -> Result<String>;
+    fn take_pending_consent(&self, token: &str) -> Result<PendingConsent>;
+    fn grant_consent(&self, user_id: i64, client_id: &str, scope: &str) -> Result<()>;
+    fn get_consent(&self, user_id: i64, client_id: &str) -> Result<Option<ClientConsent>>;
+    fn list_consents(&self, user_id: i64) -> Result<Vec<ClientConsent>>;
+    fn revoke_consent(&self, user_id: i64, client_id: &str) -> Result<bool>;
+
+    fn create_token_pair(
+        &self,
+        client_id: &str,
+        user_id: i64,
+        scope: Option<&str>,
+    ) -> Result<TokenPair>;
+    fn refresh_token_pair(&self, refresh_token: &str) -> Result<Option<TokenPair>>;
+    fn get_user_by_access_token(&self, token: &str) -> Result<Option<TokenGrant>>;
+    fn get_user_by_refresh_token(&self, token: &str) -> Result<Option<TokenGrant>>;
+    fn revoke_token(&self, token: &str) -> Result<bool>;
+    fn cleanup_expired_tokens(&self) -> Result<usize>;
+    fn revoke_user_tokens(&self, user_id: i64) -> Result<usize>;
+
+    fn create_personal_token(
+        &self,
+        user_id: i64,
+        name: &str,
+        scope: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(PersonalToken, String)>;
+    fn list_personal_tokens(&self, user_id: i64) -> Result<Vec<PersonalToken>>;
+    fn get_user_by_personal_token(&self, token: &str) -> Result<Option<(PersonalToken, User)>>;
+    fn delete_personal_token(&self, user_id: i64, name: &str) -> Result<bool>;
+
+    fn create_session(&self, user_id: i64) -> Result<Session>;
+    fn get_session(&self, session_id: &str) -> Result<Option<(Session, User)>>;
+    fn delete_session(&self, session_id: &str) -> Result<()>;
+    fn cleanup_expired_sessions(&self) -> Result<usize>;
+    fn revoke_user_sessions(&self, user_id: i64) -> Result<usize>;
+
+    // ========== Audit ==========
+
+    fn record_audit(
+        &self,
+        actor: Option<&str>,
+        action: &str,
+        target: Option<&str>,
+        detail: Option<&str>,
+    ) -> Result<()>;
+    fn list_audit(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>>;
+}
+
+/// Whether `location` names a Postgres database rather than a SQLite file.
+pub fn is_postgres_url(location: &str) -> bool {
+    location.starts_with("postgres://") || location.starts_with("postgresql://")
+}
+
+/// Open the store named by `location`: a `postgres://` / `postgresql://`
+/// URL selects the Postgres backend, anything else is a SQLite file path.
+/// Also returns where a SQLite database was backed up to before migrating.
+pub fn open_storage(location: &str) -> Result<(Box<dyn Storage>, Option<PathBuf>)> {
+    if is_postgres_url(location) {
+        #[cfg(feature = "postgres")]
+        {
+            return Ok((Box::new(crate::postgres::PgStore::connect(location)?), None));
+        }
+        #[cfg(not(feature = "postgres"))]
+        {
+            return Err(crate::error::TriviaError::validation(
+                "Postgres support is not compiled in (rebuild with --features postgres)",
+            ));
+        }
+    }
+    let (store, backup) = MemoryStore::open_with_migration_report(Path::new(location))?;
+    Ok((Box::new(store), backup))
+}
+
+impl Storage for MemoryStore {
+    fn set_boost_tags(&mut self, tags: Vec<String>) {
+        MemoryStore::set_boost_tags(self, tags)
+    }
+
+    fn set_scoring_weights(&mut self, weights: &ScoringWeights) {
+        MemoryStore::set_scoring_weights(self, weights)
+    }
+
+    fn set_auth_config(&mut self, auth: AuthConfig) {
+        MemoryStore::set_auth_config(self, auth)
+    }
+
+    fn set_rating_config(&mut self, ratings: RatingConfig) {
+        MemoryStore::set_rating_config(self, ratings)
+    }
+
+    fn set_secret_key(&mut self, key: Option<SecretKey>) {
+        MemoryStore::set_secret_key(self, key)
+    }
+
+    fn set_actor(&mut self, actor: Actor) -> Actor {
+        MemoryStore::set_actor(self, actor)
+    }
+
+    fn backup_to(&self, dest: &Path) -> Result<()> {
+        MemoryStore::backup_to(self, dest)
+    }
+
+    fn database_path(&self) -> Option<PathBuf> {
+        MemoryStore::database_path(self)
+    }
+
+    fn optimize(&self) -> Result<()> {
+        MemoryStore::optimize(self)
+    }
+
+    fn cleanup_unrated_recalls(&self) -> Result<usize> {
+        MemoryStore::cleanup_unrated_recalls(self)
+    }
+
+    fn rated_results(&self) -> Result<Vec<RatedResult>> {
+        MemoryStore::rated_results(self)
+    }
+
+    fn memorize(
+        &self,
+        mnemonic: &str,
+        content: &str,
+        tags: &[String],
+        embedding: &[f32],
+    ) -> Result<MemorizeResult> {
+        MemoryStore::memorize(self, mnemonic, content, tags, embedding)
+    }
+
+    fn memorize_with_options(
+        &self,
+        mnemonic: &str,
+        content: &str,
+        tags: &[String],
+        embedding: &[f32],
+        skip_merge: bool,
+        owner: Option<&str>,
+    ) -> Result<MemorizeResult> {
+        MemoryStore::memorize_with_options(
+            self, mnemonic, content, tags, embedding, skip_merge, owner,
+        )
+    }
+
+    fn recall_filtered(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        tags: Option<&[String]>,
+        fts_query: Option<&str>,
+        exclude_tags: Option<&[String]>,
+        readable: &dyn Fn(&Memory) -> bool,
+    ) -> Result<Recall> {
+        MemoryStore::recall_filtered(
+            self,
+            query_embedding,
+            limit,
+            tags,
+            fts_query,
+            exclude_tags,
+            readable,
+        )
+    }
+
+    fn get_memory_by_mnemonic(&self, title: &str) -> Result<Option<Memory>> {
+        MemoryStore::get_memory_by_mnemonic(self, title)
+    }
+
+    fn list_all_summaries(&self) -> Result<Vec<MemorySummary>> {
+        MemoryStore::list_all_summaries(self)
+    }
+
+    fn update_memory(
+        &self,
+        title: &str,
+        content: &str,
+        tags: &[String],
+        embedding: &[f32],
+    ) -> Result<()> {
+        MemoryStore::update_memory(self, title, content, tags, embedding)
+    }
+
+    fn rename_memory(&self, old_title: &str, new_title: &str, embedding: &[f32]) -> Result<()> {
+        MemoryStore::rename_memory(self, old_title, new_title, embedding)
+    }
+
+    fn edit_memory(
+        &self,
+        title: &str,
+        new_title: Option<&str>,
+        add_tags: &[String],
+        remove_tags: &[String],
+        new_embedding: Option<&[f32]>,
+        add_mnemonics: &[String],
+        remove_mnemonics: &[String],
+        mnemonic_embeddings: &[Vec<f32>],
+    ) -> Result<EditResult> {
+        MemoryStore::edit_memory(
+            self,
+            title,
+            new_title,
+            add_tags,
+            remove_tags,
+            new_embedding,
+            add_mnemonics,
+            remove_mnemonics,
+            mnemonic_embeddings,
+        )
+    }
+
+    fn delete_memory(&self, title: &str) -> Result<bool> {
+        MemoryStore::delete_memory(self, title)
+    }
+
+    fn merge(&self, keep: &str, discard: &str, embedding: &[f32]) -> Result<()> {
+        MemoryStore::merge(self, keep, discard, embedding)
+    }
+
+    fn rate(
+        &self,
+        title: &str,
+        useful: bool,
+        query: Option<&str>,
+        recall_id: Option<&str>,
+    ) -> Result<()> {
+        MemoryStore::rate(self, title, useful, query, recall_id)
+    }
+
+    fn rate_batch(
+        &self,
+        titles: &[String],
+        useful: bool,
+        query: Option<&str>,
+        recall_id: Option<&str>,
+    ) -> Result<Vec<String>> {
+        MemoryStore::rate_batch(self, titles, useful, query, recall_id)
+    }
+
+    fn list_tags(&self) -> Result<Vec<TagCount>> {
+        MemoryStore::list_tags(self)
+    }
+
+    fn rename_tag(&self, old_tag: &str, new_tag: &str) -> Result<usize> {
+        MemoryStore::rename_tag(self, old_tag, new_tag)
+    }
+
+    fn find_nearest(
+        &self,
+        embedding: &[f32],
+        threshold: f64,
+        exclude_title: &str,
+    ) -> Result<Vec<(String, f64)>> {
+        MemoryStore::find_nearest(self, embedding, threshold, exclude_title)
+    }
+
+    fn find_merge_candidates(
+        &self,
+        embedding: &[f32],
+        threshold: f64,
+        exclude: &HashSet<String>,
+        limit: usize,
+    ) -> Result<Vec<MergeCandidate>> {
+        MemoryStore::find_merge_candidates(self, embedding, threshold, exclude, limit)
+    }
+
+    fn add_mnemonic(&self, title: &str, text: &str, embedding: &[f32]) -> Result<()> {
+        MemoryStore::add_mnemonic(self, title, text, embedding)
+    }
+
+    fn remove_mnemonic(&self, title: &str, text: &str) -> Result<()> {
+        MemoryStore::remove_mnemonic(self, title, text)
+    }
+
+    fn link(&self, source_title: &str, target_title: &str, link_type: &str) -> Result<()> {
+        MemoryStore::link(self, source_title, target_title, link_type)
+    }
+
+    fn unlink(&self, source_title: &str, target_title: &str, link_type: &str) -> Result<()> {
+        MemoryStore::unlink(self, source_title, target_title, link_type)
+    }
+
+    fn get_links(&self, title: &str) -> Result<Vec<MemoryLink>> {
+        MemoryStore::get_links(self, title)
+    }
+
+    fn get_all_links(&self) -> Result<Vec<MemoryLink>> {
+        MemoryStore::get_all_links(self)
+    }
+
+    fn export_entries(&self, tags: Option<&[String]>) -> Result<Vec<ExportEntry>> {
+        MemoryStore::export_entries(self, tags)
+    }
+
+    fn import_entry(&self, entry: &ExportEntry, embedder: &Embedder) -> Result<ImportOutcome> {
+        MemoryStore::import_entry(self, entry, embedder)
+    }
+
+    fn import_link(
+        &self,
+        source_uuid: &str,
+        target_uuid: &str,
+        link_type: &str,
+        created_at: Option<DateTime<Utc>>,
+    ) -> Result<()> {
+        MemoryStore::import_link(self, source_uuid, target_uuid, link_type, created_at)
+    }
+
+    fn archive_records(
+        &self,
+        tags: Option<&[String]>,
+        with_vectors: bool,
+    ) -> Result<Vec<ArchiveRecord>> {
+        MemoryStore::archive_records(self, tags, with_vectors)
+    }
+
+    fn mnemonics_with_vectors(&self, texts: &[String]) -> Result<HashSet<String>> {
+        MemoryStore::mnemonics_with_vectors(self, texts)
+    }
+
+    fn import_record(
+        &self,
+        record: &ArchiveRecord,
+        vectors: &HashMap<String, Vec<f32>>,
+    ) -> Result<ImportOutcome> {
+        MemoryStore::import_record(self, record, vectors)
+    }
+
+    fn create_user(&self, username: &str, acl: &str) -> Result<User> {
+        MemoryStore::create_user(self, username, acl)
+    }
+
+    fn get_user_by_username(&self, username: &str) -> Result<Option<User>> {
+        MemoryStore::get_user_by_username(self, username)
+    }
+
+    fn get_user_by_id(&self, id: i64) -> Result<Option<User>> {
+        MemoryStore::get_user_by_id(self, id)
+    }
+
+    fn update_user_acl(&self, username: &str, acl: &str) -> Result<()> {
+        MemoryStore::update_user_acl(self, username, acl)
+    }
+
+    fn set_user_admin(&self, username: &str, is_admin: bool) -> Result<()> {
+        MemoryStore::set_user_admin(self, username, is_admin)
+    }
+
+    fn list_users(&self) -> Result<Vec<User>> {
+        MemoryStore::list_users(self)
+    }
+
+    fn delete_user(&self, username: &str) -> Result<bool> {
+        MemoryStore::delete_user(self, username)
+    }
+
+    fn touch_user(&self, user_id: i64) -> Result<()> {
+        MemoryStore::touch_user(self, user_id)
+    }
+
+    fn user_activity(&self, user_id: i64) -> Result<UserActivity> {
+        MemoryStore::user_activity(self, user_id)
+    }
+
+    fn set_owner(&self, title: &str, username: Option<&str>) -> Result<()> {
+        MemoryStore::set_owner(self, title, username)
+    }
+
+    fn transfer_ownership(&self, from: &str, to: &str) -> Result<usize> {
+        MemoryStore::transfer_ownership(self, from, to)
+    }
+
+    fn create_group(&self, name: &str, acl: &str) -> Result<Group> {
+        MemoryStore::create_group(self, name, acl)
+    }
+
+    fn get_group_by_name(&self, name: &str) -> Result<Option<Group>> {
+        MemoryStore::get_group_by_name(self, name)
+    }
+
+    fn list_groups(&self) -> Result<Vec<Group>> {
+        MemoryStore::list_groups(self)
+    }
+
+    fn update_group_acl(&self, name: &str, acl: &str) -> Result<()> {
+        MemoryStore::update_group_acl(self, name, acl)
+    }
+
+    fn delete_group(&self, name: &str) -> Result<bool> {
+        MemoryStore::delete_group(self, name)
+    }
+
+    fn add_group_member(&self, group: &str, username: &str) -> Result<()> {
+        MemoryStore::add_group_member(self, group, username)
+    }
+
+    fn remove_group_member(&self, group: &str, username: &str) -> Result<bool> {
+        MemoryStore::remove_group_member(self, group, username)
+    }
+
+    fn list_group_members(&self, group: &str) -> Result<Vec<User>> {
+        MemoryStore::list_group_members(self, group)
+    }
+
+    fn groups_for_user(&self, user_id: i64) -> Result<Vec<Group>> {
+        MemoryStore::groups_for_user(self, user_id)
+    }
+
+    fn create_provider(
+        &self,
+        name: &str,
+        provider_type: &str,
+        client_id: &str,
+        client_secret: &str,
+        config: &str,
+    ) -> Result<OAuthProvider> {
+        MemoryStore::create_provider(self, name, provider_type, client_id, client_secret, config)
+    }
+
+    fn get_provider_by_name(&self, name: &str) -> Result<Option<OAuthProvider>> {
+        MemoryStore::get_provider_by_name(self, name)
+    }
+
+    fn list_providers(&self) -> Result<Vec<OAuthProvider>> {
+        MemoryStore::list_providers(self)
+    }
+
+    fn reencrypt_provider_secrets(&self, new_key: &SecretKey) -> Result<usize> {
+        MemoryStore::reencrypt_provider_secrets(self, new_key)
+    }
+
+    fn delete_provider(&self, name: &str) -> Result<bool> {
+        MemoryStore::delete_provider(self, name)
+    }
+
+    fn set_provider_enabled(&self, name: &str, enabled: bool) -> Result<()> {
+        MemoryStore::set_provider_enabled(self, name, enabled)
+    }
+
+    fn has_auth_providers(&self) -> Result<bool> {
+        MemoryStore::has_auth_providers(self)
+    }
+
+    fn list_enabled_providers(&self) -> Result<Vec<(String, String)>> {
+        MemoryStore::list_enabled_providers(self)
+    }
+
+    fn link_identity(
+        &self,
+        user_id: i64,
+        provider_id: i64,
+        provider_username: &str,
+        provider_user_id: &str,
+    ) -> Result<()> {
+        MemoryStore::link_identity(
+            self,
+            user_id,
+            provider_id,
+            provider_username,
+            provider_user_id,
+        )
+    }
+
+    fn get_user_by_provider_identity(
+        &self,
+        provider_id: i64,
+        provider_user_id: &str,
+    ) -> Result<Option<User>> {
+        MemoryStore::get_user_by_provider_identity(self, provider_id, provider_user_id)
+    }
+
+    fn list_identities_for_user(&self, user_id: i64) -> Result<Vec<UserIdentity>> {
+        MemoryStore::list_identities_for_user(self, user_id)
+    }
+
+    fn unlink_identity(&self, user_id: i64, provider_id: i64) -> Result<bool> {
+        MemoryStore::unlink_identity(self, user_id, provider_id)
+    }
+
+    fn register_client(
+        &self,
+        redirect_uris: &[String],
+        client_name: Option<&str>,
+    ) -> Result<(OAuthClient, Option<String>)> {
+        MemoryStore::register_client(self, redirect_uris, client_name)
+    }
+
+    fn get_client(&self, client_id: &str) -> Result<Option<OAuthClient>> {
+        MemoryStore::get_client(self, client_id)
+    }
+
+    fn list_clients(&self) -> Result<Vec<OAuthClient>> {
+        MemoryStore::list_clients(self)
+    }
+
+    fn delete_client(&self, client_id: &str) -> Result<bool> {
+        MemoryStore::delete_client(self, client_id)
+    }
+
+    fn verify_client_secret(&self, client_id: &str, secret: &str) -> Result<bool> {
+        MemoryStore::verify_client_secret(self, client_id, secret)
+    }
+
+    fn create_auth_code(
+        &self,
+        client_id: &str,
+        user_id: i64,
+        code_challenge: &str,
+        redirect_uri: &str,
+        scope: Option<&str>,
+    ) -> Result<String> {
+        MemoryStore::create_auth_code(
+            self,
+            client_id,
+            user_id,
+            code_challenge,
+            redirect_uri,
+            scope,
+        )
+    }
+
+    fn consume_auth_code(&self, code: &str) -> Result<OAuthCode> {
+        MemoryStore::consume_auth_code(self, code)
+    }
+
+    fn cleanup_expired_codes(&self) -> Result<usize> {
+        MemoryStore::cleanup_expired_codes(self)
+    }
+
+    fn create_pending_login(
+        &self,
+        provider: &str,
+        authorization: Option<&PendingAuthorization>,
+        secrets: &LoginSecrets,
+    ) -> Result<String> {
+        MemoryStore::create_pending_login(self, provider, authorization, secrets)
+    }
+
+    fn take_pending_login(&self, state: &str) -> Result<PendingLogin> {
+        MemoryStore::take_pending_login(self, state)
+    }
+
+    fn create_pending_consent(
+        &self,
+        user_id: i64,
+        authorization: &PendingAuthorization,
+    ) -> Result<String> {
+        MemoryStore::create_pending_consent(self, user_id, authorization)
+    }
+
+    fn take_pending_consent(&self, token: &str) -> Result<PendingConsent> {
+        MemoryStore::take_pending_consent(self, token)
+    }
+
+    fn grant_consent(&self, user_id: i64, client_id: &str, scope: &str) -> Result<()> {
+        MemoryStore::grant_consent(self, user_id, client_id, scope)
+    }
+
+    fn get_consent(&self, user_id: i64, client_id: &str) -> Result<Option<ClientConsent>> {
+        MemoryStore::get_consent(self, user_id, client_id)
+    }
+
+    fn list_consents(&self, user_id: i64) -> Result<Vec<ClientConsent>> {
+        MemoryStore::list_consents(self, user_id)
+    }
+
+    fn revoke_consent(&self, user_id: i64, client_id: &str) -> Result<bool> {
+        MemoryStore::revoke_consent(self, user_id, client_id)
+    }
+
+    fn create_token_pair(
+        &self,
+        client_id: &str,
+        user_id: i64,
+        scope: Option<&str>,
+    ) -> Result<TokenPair> {
+        MemoryStore::create_token_pair(self, client_id, user_id, scope)
+    }
+
+    fn refresh_token_pair(&self, refresh_token: &str) -> Result<Option<TokenPair>> {
+        MemoryStore::refresh_token_pair(self, refresh_token)
+    }
+
+    fn get_user_by_access_token(&self, token: &str) -> Result<Option<TokenGrant>> {
+        MemoryStore::get_user_by_access_token(self, token)
+    }
+
+    fn get_user_by_refresh_token(&self, token: &str) -> Result<Option<TokenGrant>> {
+        MemoryStore::get_user_by_refresh_token(self, token)
+    }
+
+    fn revoke_token(&self, token: &str) -> Result<bool> {
+        MemoryStore::revoke_token(self, token)
+    }
+
+    fn cleanup_expired_tokens(&self) -> Result<usize> {
+        MemoryStore::cleanup_expired_tokens(self)
+    }
+
+    fn revoke_user_tokens(&self, user_id: i64) -> Result<usize> {
+        MemoryStore::revoke_user_tokens(self, user_id)
+    }
+
+    fn create_personal_token(
+        &self,
+        user_id: i64,
+        name: &str,
+        scope: &str,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(PersonalToken, String)> {
+        MemoryStore::create_personal_token(self, user_id, name, scope, expires_at)
+    }
+
+    fn list_personal_tokens(&self, user_id: i64) -> Result<Vec<PersonalToken>> {
+        MemoryStore::list_personal_tokens(self, user_id)
+    }
+
+    fn get_user_by_personal_token(&self, token: &str) -> Result<Option<(PersonalToken, User)>> {
+        MemoryStore::get_user_by_personal_token(self, token)
+    }
+
+    fn delete_personal_token(&self, user_id: i64, name: &str) -> Result<bool> {
+        MemoryStore::delete_personal_token(self, user_id, name)
+    }
+
+    fn create_session(&self, user_id: i64) -> Result<Session> {
+        MemoryStore::create_session(self, user_id)
+    }
+
+    fn get_session(&self, session_id: &str) -> Result<Option<(Session, User)>> {
+        MemoryStore::get_session(self, session_id)
+    }
+
+    fn delete_session(&self, session_id: &str) -> Result<()> {
+        MemoryStore::delete_session(self, session_id)
+    }
+
+    fn cleanup_expired_sessions(&self) -> Result<usize> {
+        MemoryStore::cleanup_expired_sessions(self)
+    }
+
+    fn revoke_user_sessions(&self, user_id: i64) -> Result<usize> {
+        MemoryStore::revoke_user_sessions(self, user_id)
+    }
+
+    fn record_audit(
+        &self,
+        actor: Option<&str>,
+        action: &str,
+        target: Option<&str>,
+        detail: Option<&str>,
+    ) -> Result<()> {
+        MemoryStore::record_audit(self, actor, action, target, detail)
+    }
+
+    fn list_audit(&self, filter: &AuditFilter) -> Result<Vec<AuditEntry>> {
+        MemoryStore::list_audit(self, filter)
+    }
+}
@@ -1,17 +1,22 @@
-use anyhow::{Context, Result, anyhow};
+use anyhow::Context;
 use chrono::{DateTime, NaiveDateTime, Utc};
-use rusqlite::{Connection, ffi::sqlite3_auto_extension, params};
+use rusqlite::{Connection, OptionalExtension, ffi::sqlite3_auto_extension, params};
 use serde::{Deserialize, Serialize};
 use sqlite_vec::sqlite3_vec_init;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Once;
 use uuid::Uuid;
 use zerocopy::AsBytes;
 
+use crate::audit::{Actor, change_detail};
+use crate::config::{AuthConfig, RatingConfig, ScoringWeights};
+use crate::error::{Result, TriviaError};
+use crate::secrets::SecretKey;
+
 static VEC_INIT: Once = Once::new();
 
 const AUTO_LINK_THRESHOLD: f64 = 0.3;
-const AUTO_LINK_MAX_NEIGHBORS: usize = 5;
+pub(crate) const AUTO_LINK_MAX_NEIGHBORS: usize = 5;
 const AUTO_MERGE_THRESHOLD: f64 = 0.15;
 
 #[derive(Debug, Clone)]
@@ -21,6 +26,8 @@ pub struct ScoringConfig {
     pub frequency_weight: f64,
     pub link_weight: f64,
     pub rating_weight: f64,
+    /// Weight of ratings given in recalls with queries like the current one.
+    pub query_weight: f64,
     pub half_life_days: f64,
     pub tag_boost_weight: f64,
     pub fts_weight: f64,
@@ -35,6 +42,7 @@ impl Default for ScoringConfig {
             frequency_weight: 0.05,
             link_weight: 0.1,
             rating_weight: 0.15,
+            query_weight: 0.3,
             half_life_days: 7.0,
             tag_boost_weight: 0.2,
             fts_weight: 0.5,
@@ -43,7 +51,196 @@ impl Default for ScoringConfig {
     }
 }
 
-fn register_sqlite_vec() {
+/// The components of a memory's recall score, before weighting.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ScoreFeatures {
+    pub similarity: f64,
+    pub recency: f64,
+    pub frequency: f64,
+    pub link: f64,
+    pub rating: f64,
+    pub query: f64,
+    pub tag_boost: f64,
+    pub fts: f64,
+}
+
+impl ScoreFeatures {
+    pub fn to_array(&self) -> [f64; 8] {
+        [
+            self.similarity,
+            self.recency,
+            self.frequency,
+            self.link,
+            self.rating,
+            self.query,
+            self.tag_boost,
+            self.fts,
+        ]
+    }
+
+    pub fn from_array(values: [f64; 8]) -> Self {
+        let [
+            similarity,
+            recency,
+            frequency,
+            link,
+            rating,
+            query,
+            tag_boost,
+            fts,
+        ] = values;
+        Self {
+            similarity,
+            recency,
+            frequency,
+            link,
+            rating,
+            query,
+            tag_boost,
+            fts,
+        }
+    }
+}
+
+impl ScoringConfig {
+    /// The weights, in the order of `ScoreFeatures::to_array`.
+    pub fn weights(&self) -> [f64; 8] {
+        [
+            self.similarity_weight,
+            self.recency_weight,
+            self.frequency_weight,
+            self.link_weight,
+            self.rating_weight,
+            self.query_weight,
+            self.tag_boost_weight,
+            self.fts_weight,
+        ]
+    }
+
+    /// Override the weights that `weights` sets.
+    pub fn apply_weights(&mut self, weights: &ScoringWeights) {
+        let overrides = [
+            (&mut self.similarity_weight, weights.similarity_weight),
+            (&mut self.recency_weight, weights.recency_weight),
+            (&mut self.frequency_weight, weights.frequency_weight),
+            (&mut self.link_weight, weights.link_weight),
+            (&mut self.rating_weight, weights.rating_weight),
+            (&mut self.query_weight, weights.query_weight),
+            (&mut self.tag_boost_weight, weights.tag_boost_weight),
+            (&mut self.fts_weight, weights.fts_weight),
+        ];
+        for (weight, value) in overrides {
+            if let Some(value) = value {
+                *weight = value;
+            }
+        }
+    }
+
+    /// Composite score of a memory with these components.
+    pub fn score(&self, features: &ScoreFeatures) -> f64 {
+        self.weights()
+            .iter()
+            .zip(features.to_array())
+            .map(|(weight, value)| weight * value)
+            .sum()
+    }
+
+    /// Compute composite scores for recall candidates, sort best-first and
+    /// keep the top `limit`. `memories` must already carry their links;
+    /// `ratings` holds the rating signal of those that have been rated, and
+    /// `query_feedback` that of those rated in recalls of similar queries.
+    /// Returns the score components of the memories kept, in order.
+    pub(crate) fn rank(
+        &self,
+        memories: &mut Vec<Memory>,
+        fts_matches: &std::collections::HashSet<String>,
+        ratings: &std::collections::HashMap<String, f64>,
+        query_feedback: &std::collections::HashMap<String, f64>,
+        limit: usize,
+    ) -> Vec<ScoreFeatures> {
+        let similarity_map: std::collections::HashMap<String, f64> = memories
+            .iter()
+            .map(|m| (m.mnemonic.clone(), 1.0 - m.distance))
+            .collect();
+        let lambda = (2.0_f64).ln() / self.half_life_days;
+        let now = Utc::now();
+
+        let mut scored: Vec<(Memory, ScoreFeatures)> = Vec::with_capacity(memories.len());
+        for mut mem in memories.drain(..) {
+            let similarity = 1.0 - mem.distance;
+
+            let recency = match mem.last_recalled_at {
+                Some(ts) => {
+                    let days = days_between(ts, now);
+                    (-lambda * days).exp()
+                }
+                None => 0.0,
+            };
+
+            let frequency = (1.0 + mem.recall_count as f64).ln();
+
+            let link: f64 = mem
+                .links
+                .iter()
+                .filter_map(|l| {
+                    let other = if l.source_mnemonic == mem.mnemonic {
+                        &l.target_mnemonic
+                    } else {
+                        &l.source_mnemonic
+                    };
+                    similarity_map.get(other).copied()
+                })
+                .take(3)
+                .sum();
+
+            let rating = ratings.get(&mem.mnemonic).copied().unwrap_or(0.0);
+            let query = query_feedback.get(&mem.mnemonic).copied().unwrap_or(0.0);
+
+            let tag_boost = if !self.boost_tags.is_empty() {
+                let matches = mem
+                    .tags
+                    .iter()
+                    .filter(|t| self.boost_tags.contains(t))
+                    .count();
+                (matches as f64) / (self.boost_tags.len() as f64)
+            } else {
+                0.0
+            };
+
+            let fts = if fts_matches.contains(&mem.mnemonic) {
+                1.0
+            } else {
+                0.0
+            };
+
+            let features = ScoreFeatures {
+                similarity,
+                recency,
+                frequency,
+                link,
+                rating,
+                query,
+                tag_boost,
+                fts,
+            };
+            mem.score = self.score(&features);
+            scored.push((mem, features));
+        }
+
+        // Sort by score descending, take limit
+        scored.sort_by(|(a, _), (b, _)| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        scored.truncate(limit);
+        let (kept, features): (Vec<Memory>, Vec<ScoreFeatures>) = scored.into_iter().unzip();
+        *memories = kept;
+        features
+    }
+}
+
+pub(crate) fn register_sqlite_vec() {
     VEC_INIT.call_once(|| unsafe {
         #[allow(clippy::missing_transmute_annotations)]
         sqlite3_auto_extension(Some(std::mem::transmute(sqlite3_vec_init as *const ())));
@@ -63,9 +260,22 @@ pub struct Memory {
     pub updated_at: DateTime<Utc>,
     pub recall_count: i64,
     pub last_recalled_at: Option<DateTime<Utc>>,
+    /// Raters whose latest verdict is useful.
     pub useful_count: i64,
+    /// Raters whose latest verdict is not useful.
     pub not_useful_count: i64,
     pub links: Vec<MemoryLink>,
+    /// Username of the signed-in user who created the memory, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
+}
+
+/// The memories a recall returned, best first, and the id to rate them
+/// against.
+#[derive(Debug, Clone, Serialize)]
+pub struct Recall {
+    pub id: String,
+    pub memories: Vec<Memory>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -79,6 +289,10 @@ pub struct MemoryLink {
 pub struct MemoryStore {
     conn: Connection,
     scoring: ScoringConfig,
+    pub(crate) auth: AuthConfig,
+    pub(crate) secret_key: Option<SecretKey>,
+    pub(crate) actor: Actor,
+    pub(crate) ratings: RatingConfig,
 }
 
 fn open_connection(conn: &Connection) -> Result<()> {
@@ -87,7 +301,28 @@ fn open_connection(conn: &Connection) -> Result<()> {
 }
 
 impl MemoryStore {
+    /// Open (or create) the database at `db_path` and bring its schema up to date.
+    /// Existing databases are backed up alongside the original before any
+    /// pending migration runs.
     pub fn new(db_path: &Path) -> Result<Self> {
+        Ok(Self::open_with_migration_report(db_path)?.0)
+    }
+
+    /// Like [`MemoryStore::new`], also returning where the existing database
+    /// was backed up to if migrations had to run.
+    pub fn open_with_migration_report(db_path: &Path) -> Result<(Self, Option<PathBuf>)> {
+        let store = Self::open_unmigrated(db_path)?;
+        let mut backup = None;
+        if store.has_pending_migrations()? && store.has_existing_schema()? {
+            backup = Some(store.backup_before_migrate(db_path)?);
+        }
+        store.migrate()?;
+        Ok((store, backup))
+    }
+
+    /// Open the database at `db_path` without applying migrations.
+    /// Used by `trivia db` to inspect and migrate explicitly.
+    pub fn open_unmigrated(db_path: &Path) -> Result<Self> {
         register_sqlite_vec();
 
         if let Some(parent) = db_path.parent() {
@@ -98,12 +333,14 @@ impl MemoryStore {
         let conn = Connection::open(db_path)
             .with_context(|| format!("opening database: {}", db_path.display()))?;
         open_connection(&conn)?;
-        let store = Self {
+        Ok(Self {
             conn,
             scoring: ScoringConfig::default(),
-        };
-        store.migrate()?;
-        Ok(store)
+            auth: AuthConfig::default(),
+            secret_key: None,
+            actor: Actor::default(),
+            ratings: RatingConfig::default(),
+        })
     }
 
     pub fn in_memory() -> Result<Self> {
@@ -113,6 +350,10 @@ impl MemoryStore {
         let store = Self {
             conn,
             scoring: ScoringConfig::default(),
+            auth: AuthConfig::default(),
+            secret_key: None,
+            actor: Actor::default(),
+            ratings: RatingConfig::default(),
         };
         store.migrate()?;
         Ok(store)
@@ -122,324 +363,62 @@ impl MemoryStore {
         self.scoring.boost_tags = tags;
     }
 
-    pub(crate) fn conn(&self) -> &Connection {
-        &self.conn
+    /// Score weights from `[scoring]`, over the built-in defaults.
+    pub fn set_scoring_weights(&mut self, weights: &ScoringWeights) {
+        self.scoring.apply_weights(weights);
     }
 
-    fn migrate(&self) -> Result<()> {
-        self.conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS memories (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                mnemonic TEXT NOT NULL UNIQUE,
-                content TEXT NOT NULL,
-                tags TEXT DEFAULT '[]',
-                created_at TEXT DEFAULT (datetime('now')),
-                updated_at TEXT DEFAULT (datetime('now')),
-                recall_count INTEGER NOT NULL DEFAULT 0,
-                last_recalled_at TEXT
-            );
-
-            CREATE VIRTUAL TABLE IF NOT EXISTS memory_vectors USING vec0(
-                memory_id INTEGER PRIMARY KEY,
-                embedding float[384]
-            );
-
-            CREATE TABLE IF NOT EXISTS memory_links (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                source_id INTEGER NOT NULL REFERENCES memories(id) ON DELETE CASCADE,
-                target_id INTEGER NOT NULL REFERENCES memories(id) ON DELETE CASCADE,
-                link_type TEXT NOT NULL CHECK(link_type IN ('related', 'supersedes', 'derived_from')),
-                created_at TEXT DEFAULT (datetime('now')),
-                UNIQUE(source_id, target_id, link_type)
-            );",
-        )?;
-
-        // Handle existing DBs that lack the new columns
-        let add_column = |sql: &str| -> Result<()> {
-            match self.conn.execute_batch(sql) {
-                Ok(_) => Ok(()),
-                Err(e) if e.to_string().contains("duplicate column") => Ok(()),
-                Err(e) => Err(e.into()),
-            }
-        };
-        add_column("ALTER TABLE memories ADD COLUMN recall_count INTEGER NOT NULL DEFAULT 0;")?;
-        add_column("ALTER TABLE memories ADD COLUMN last_recalled_at TEXT;")?;
-        add_column("ALTER TABLE memories ADD COLUMN uuid TEXT;")?;
-        add_column("ALTER TABLE memories ADD COLUMN useful_count INTEGER NOT NULL DEFAULT 0;")?;
-        add_column("ALTER TABLE memories ADD COLUMN not_useful_count INTEGER NOT NULL DEFAULT 0;")?;
-
-        // Backfill UUIDs for existing rows
-        self.conn.execute_batch(
-            "UPDATE memories SET uuid = lower(hex(randomblob(4)) || '-' || hex(randomblob(2)) || '-4' || substr(hex(randomblob(2)),2) || '-' || substr('89ab', abs(random()) % 4 + 1, 1) || substr(hex(randomblob(2)),2) || '-' || hex(randomblob(6))) WHERE uuid IS NULL;"
-        )?;
-
-        self.conn.execute_batch(
-            "CREATE UNIQUE INDEX IF NOT EXISTS idx_memories_uuid ON memories(uuid);"
-        )?;
-
-        // --- Multiple mnemonics migration ---
-        // 1. Add title column to memories
-        add_column("ALTER TABLE memories ADD COLUMN title TEXT;")?;
+    /// Token, session and authorization code lifetimes from `[auth]`.
+    pub fn set_auth_config(&mut self, auth: AuthConfig) {
+        self.auth = auth;
+    }
 
-        // 2. Backfill title from mnemonic
-        self.conn.execute_batch(
-            "UPDATE memories SET title = mnemonic WHERE title IS NULL;"
-        )?;
+    /// Key for encrypting provider client secrets. Without one they are
+    /// stored as given.
+    pub fn set_secret_key(&mut self, key: Option<SecretKey>) {
+        self.secret_key = key;
+    }
 
-        // 3. Unique index on title
-        self.conn.execute_batch(
-            "CREATE UNIQUE INDEX IF NOT EXISTS idx_memories_title ON memories(title);"
-        )?;
+    /// Rating decay and personalization from `[ratings]`.
+    pub fn set_rating_config(&mut self, ratings: RatingConfig) {
+        self.ratings = ratings;
+    }
 
-        // 4. Create mnemonics table + backfill
-        self.conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS mnemonics (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                memory_id INTEGER NOT NULL REFERENCES memories(id) ON DELETE CASCADE,
-                text TEXT NOT NULL UNIQUE,
-                created_at TEXT DEFAULT (datetime('now'))
-            );"
-        )?;
-        self.conn.execute_batch(
-            "INSERT OR IGNORE INTO mnemonics (memory_id, text, created_at)
-             SELECT id, mnemonic, created_at FROM memories;"
-        )?;
+    /// Attribute subsequent changes to `actor`, returning the previous one.
+    pub fn set_actor(&mut self, actor: Actor) -> Actor {
+        std::mem::replace(&mut self.actor, actor)
+    }
 
-        // 5. Create mnemonic_vectors virtual table
-        self.conn.execute_batch(
-            "CREATE VIRTUAL TABLE IF NOT EXISTS mnemonic_vectors USING vec0(
-                mnemonic_id INTEGER PRIMARY KEY,
-                embedding float[384]
-            );"
-        )?;
+    pub(crate) fn conn(&self) -> &Connection {
+        &self.conn
+    }
 
-        // 6. Vector migration: copy from memory_vectors to mnemonic_vectors if needed
-        let mv_count: i64 = self.conn.query_row(
-            "SELECT COUNT(*) FROM mnemonic_vectors", [], |row| row.get(0),
-        )?;
-        let old_mv_count: i64 = self.conn.query_row(
-            "SELECT COUNT(*) FROM memory_vectors", [], |row| row.get(0),
-        )?;
-        if mv_count == 0 && old_mv_count > 0 {
-            let mut stmt = self.conn.prepare(
-                "SELECT mv.memory_id, mv.embedding FROM memory_vectors mv"
-            )?;
-            let rows: Vec<(i64, Vec<u8>)> = stmt.query_map([], |row| {
-                Ok((row.get::<_, i64>(0)?, row.get::<_, Vec<u8>>(1)?))
-            })?.collect::<std::result::Result<Vec<_>, _>>()?;
-
-            for (memory_id, embedding_blob) in &rows {
-                let mnemonic_id: Option<i64> = self.conn.query_row(
-                    "SELECT id FROM mnemonics WHERE memory_id = ?1 LIMIT 1",
-                    params![memory_id],
-                    |row| row.get(0),
-                ).ok();
-                if let Some(mn_id) = mnemonic_id {
-                    self.conn.execute(
-                        "INSERT OR IGNORE INTO mnemonic_vectors (mnemonic_id, embedding) VALUES (?1, ?2)",
-                        params![mn_id, embedding_blob],
-                    )?;
-                }
-            }
-        }
+    pub(crate) fn conn_mut(&mut self) -> &mut Connection {
+        &mut self.conn
+    }
 
-        // 7. FTS rebuild: detect old-style triggers (referencing new.mnemonic)
-        //    and rebuild to use title+content
-        let needs_fts_rebuild = {
-            let trigger_sql: Option<String> = self.conn.query_row(
-                "SELECT sql FROM sqlite_master WHERE type='trigger' AND name='memory_fts_ai'",
-                [],
+    /// Look up memory id by title (the stable display name).
+    fn find_memory_id(conn: &Connection, title: &str) -> Result<Option<i64>> {
+        Ok(conn
+            .query_row(
+                "SELECT id FROM memories WHERE title = ?1",
+                params![title],
                 |row| row.get(0),
-            ).ok();
-            match trigger_sql {
-                Some(sql) => sql.contains("new.mnemonic"),
-                None => false, // no trigger yet, create fresh
-            }
-        };
-
-        let fts_exists: bool = self.conn.query_row(
-            "SELECT COUNT(*) FROM sqlite_master WHERE type='table' AND name='memory_fts'",
-            [],
-            |row| row.get::<_, i64>(0),
-        ).map(|c| c > 0)?;
-
-        if needs_fts_rebuild {
-            // Drop old triggers and FTS table, recreate with title+content
-            self.conn.execute_batch(
-                "DROP TRIGGER IF EXISTS memory_fts_ai;
-                 DROP TRIGGER IF EXISTS memory_fts_ad;
-                 DROP TRIGGER IF EXISTS memory_fts_au;
-                 DROP TABLE IF EXISTS memory_fts;"
-            )?;
-        }
-
-        if needs_fts_rebuild || !fts_exists {
-            self.conn.execute_batch(
-                "CREATE VIRTUAL TABLE IF NOT EXISTS memory_fts USING fts5(
-                    title,
-                    content,
-                    content='memories',
-                    content_rowid='id',
-                    tokenize='porter unicode61'
-                );
-
-                CREATE TRIGGER IF NOT EXISTS memory_fts_ai AFTER INSERT ON memories BEGIN
-                    INSERT INTO memory_fts(rowid, title, content)
-                    VALUES (new.id, new.title, new.content);
-                END;
-
-                CREATE TRIGGER IF NOT EXISTS memory_fts_ad AFTER DELETE ON memories BEGIN
-                    INSERT INTO memory_fts(memory_fts, rowid, title, content)
-                    VALUES ('delete', old.id, old.title, old.content);
-                END;
-
-                CREATE TRIGGER IF NOT EXISTS memory_fts_au AFTER UPDATE ON memories BEGIN
-                    INSERT INTO memory_fts(memory_fts, rowid, title, content)
-                    VALUES ('delete', old.id, old.title, old.content);
-                    INSERT INTO memory_fts(rowid, title, content)
-                    VALUES (new.id, new.title, new.content);
-                END;"
-            )?;
-
-            // Backfill FTS
-            let mem_count: i64 = self.conn.query_row(
-                "SELECT COUNT(*) FROM memories", [], |row| row.get(0),
-            )?;
-            if mem_count > 0 {
-                self.conn.execute_batch(
-                    "INSERT INTO memory_fts(rowid, title, content)
-                     SELECT id, title, content FROM memories;"
-                )?;
-            }
-        } else {
-            // Fresh DB or already migrated — ensure FTS and triggers exist
-            self.conn.execute_batch(
-                "CREATE VIRTUAL TABLE IF NOT EXISTS memory_fts USING fts5(
-                    title,
-                    content,
-                    content='memories',
-                    content_rowid='id',
-                    tokenize='porter unicode61'
-                );
-
-                CREATE TRIGGER IF NOT EXISTS memory_fts_ai AFTER INSERT ON memories BEGIN
-                    INSERT INTO memory_fts(rowid, title, content)
-                    VALUES (new.id, new.title, new.content);
-                END;
-
-                CREATE TRIGGER IF NOT EXISTS memory_fts_ad AFTER DELETE ON memories BEGIN
-                    INSERT INTO memory_fts(memory_fts, rowid, title, content)
-                    VALUES ('delete', old.id, old.title, old.content);
-                END;
-
-                CREATE TRIGGER IF NOT EXISTS memory_fts_au AFTER UPDATE ON memories BEGIN
-                    INSERT INTO memory_fts(memory_fts, rowid, title, content)
-                    VALUES ('delete', old.id, old.title, old.content);
-                    INSERT INTO memory_fts(rowid, title, content)
-                    VALUES (new.id, new.title, new.content);
-                END;"
-            )?;
-
-            // Backfill FTS if empty (first run on existing DB)
-            let fts_count: i64 = self.conn.query_row(
-                "SELECT COUNT(*) FROM memory_fts", [], |row| row.get(0),
-            )?;
-            let mem_count: i64 = self.conn.query_row(
-                "SELECT COUNT(*) FROM memories", [], |row| row.get(0),
-            )?;
-            if fts_count == 0 && mem_count > 0 {
-                self.conn.execute_batch(
-                    "INSERT INTO memory_fts(rowid, title, content)
-                     SELECT id, title, content FROM memories;"
-                )?;
-            }
-        }
-
-        // --- Auth tables migration ---
-        self.conn.execute_batch(
-            "CREATE TABLE IF NOT EXISTS users (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                username TEXT UNIQUE NOT NULL,
-                acl TEXT NOT NULL DEFAULT '*:none',
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                updated_at TEXT NOT NULL DEFAULT (datetime('now'))
-            );
-
-            CREATE TABLE IF NOT EXISTS oauth_providers (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                name TEXT UNIQUE NOT NULL,
-                provider_type TEXT NOT NULL,
-                client_id TEXT NOT NULL,
-                client_secret TEXT NOT NULL,
-                enabled INTEGER NOT NULL DEFAULT 1,
-                config TEXT NOT NULL DEFAULT '{}',
-                created_at TEXT NOT NULL DEFAULT (datetime('now'))
-            );
-
-            CREATE TABLE IF NOT EXISTS user_identities (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                user_id INTEGER NOT NULL REFERENCES users(id) ON DELETE CASCADE,
-                provider_id INTEGER NOT NULL REFERENCES oauth_providers(id) ON DELETE CASCADE,
-                provider_username TEXT NOT NULL,
-                provider_user_id TEXT NOT NULL,
-                created_at TEXT NOT NULL DEFAULT (datetime('now')),
-                UNIQUE(provider_id, provider_user_id)
-            );
-
-            CREATE TABLE IF NOT EXISTS oauth_clients (
-                client_id TEXT PRIMARY KEY,
-                client_secret_hash TEXT,
-                redirect_uris TEXT NOT NULL DEFAULT '[]',
-                client_name TEXT,
-                created_at TEXT NOT NULL DEFAULT (datetime('now'))
-            );
-
-            CREATE TABLE IF NOT EXISTS oauth_codes (
-                code TEXT PRIMARY KEY,
-                client_id TEXT NOT NULL,
-                user_id INTEGER NOT NULL REFERENCES users(id),
-                code_challenge TEXT NOT NULL,
-                redirect_uri TEXT NOT NULL,
-                expires_at TEXT NOT NULL,
-                used INTEGER NOT NULL DEFAULT 0
-            );
-
-            CREATE TABLE IF NOT EXISTS oauth_tokens (
-                id INTEGER PRIMARY KEY AUTOINCREMENT,
-                access_token_hash TEXT UNIQUE NOT NULL,
-                refresh_token_hash TEXT UNIQUE,
-                client_id TEXT NOT NULL,
-                user_id INTEGER NOT NULL REFERENCES users(id),
-                expires_at TEXT NOT NULL,
-                created_at TEXT NOT NULL DEFAULT (datetime('now'))
-            );
-
-            CREATE TABLE IF NOT EXISTS sessions (
-                session_id TEXT PRIMARY KEY,
-                user_id INTEGER NOT NULL REFERENCES users(id),
-                expires_at TEXT NOT NULL,
-                created_at TEXT NOT NULL DEFAULT (datetime('now'))
-            );"
-        )?;
-
-        Ok(())
+            )
+            .optional()?)
     }
 
-    /// Look up memory id by title (the stable display name).
     fn memory_id_by_title(conn: &Connection, title: &str) -> Result<i64> {
-        conn.query_row(
-            "SELECT id FROM memories WHERE title = ?1",
-            params![title],
-            |row| row.get(0),
-        ).map_err(|_| anyhow!("memory not found: {}", title))
+        Self::find_memory_id(conn, title)?
+            .ok_or_else(|| TriviaError::NotFound(format!("memory not found: {}", title)))
     }
 
     /// Get all mnemonic texts for a memory.
     pub fn get_mnemonics_for_memory(conn: &Connection, memory_id: i64) -> Result<Vec<String>> {
-        let mut stmt = conn.prepare(
-            "SELECT text FROM mnemonics WHERE memory_id = ?1 ORDER BY id"
-        )?;
-        let results = stmt.query_map(params![memory_id], |row| row.get(0))?
+        let mut stmt =
+            conn.prepare("SELECT text FROM mnemonics WHERE memory_id = ?1 ORDER BY id")?;
+        let results = stmt
+            .query_map(params![memory_id], |row| row.get(0))?
             .collect::<std::result::Result<Vec<String>, _>>()?;
         Ok(results)
     }
@@ -460,37 +439,52 @@ impl MemoryStore {
             "INSERT INTO mnemonic_vectors (mnemonic_id, embedding) VALUES (?1, ?2)",
             params![mnemonic_id, embedding.as_bytes()],
         )?;
+        if let Some(memory) = self.snapshot_by_id(memory_id)? {
+            self.audit_memory(
+                "memory.add_mnemonic",
+                &memory.uuid,
+                Some(&format!("{text:?}")),
+            )?;
+        }
         tx.commit()?;
         Ok(())
     }
 
     pub fn remove_mnemonic(&self, title: &str, text: &str) -> Result<()> {
         let memory_id = Self::memory_id_by_title(&self.conn, title)?;
+        let tx = self.conn.unchecked_transaction()?;
         // Guard: cannot remove last mnemonic
-        let count: i64 = self.conn.query_row(
+        let count: i64 = tx.query_row(
             "SELECT COUNT(*) FROM mnemonics WHERE memory_id = ?1",
             params![memory_id],
             |row| row.get(0),
         )?;
         if count <= 1 {
-            return Err(anyhow!("cannot remove the last mnemonic"));
+            return Err(TriviaError::validation("cannot remove the last mnemonic"));
         }
-        let mnemonic_id: Option<i64> = self.conn.query_row(
-            "SELECT id FROM mnemonics WHERE memory_id = ?1 AND text = ?2",
-            params![memory_id, text],
-            |row| row.get(0),
-        ).ok();
+        let mnemonic_id: Option<i64> = tx
+            .query_row(
+                "SELECT id FROM mnemonics WHERE memory_id = ?1 AND text = ?2",
+                params![memory_id, text],
+                |row| row.get(0),
+            )
+            .optional()?;
         if let Some(mn_id) = mnemonic_id {
             // vec0 doesn't support FK CASCADE, delete manually
-            self.conn.execute(
+            tx.execute(
                 "DELETE FROM mnemonic_vectors WHERE mnemonic_id = ?1",
                 params![mn_id],
             )?;
-            self.conn.execute(
-                "DELETE FROM mnemonics WHERE id = ?1",
-                params![mn_id],
-            )?;
+            tx.execute("DELETE FROM mnemonics WHERE id = ?1", params![mn_id])?;
+            if let Some(memory) = self.snapshot_by_id(memory_id)? {
+                self.audit_memory(
+                    "memory.remove_mnemonic",
+                    &memory.uuid,
+                    Some(&format!("{text:?}")),
+                )?;
+            }
         }
+        tx.commit()?;
         Ok(())
     }
 
@@ -501,11 +495,12 @@ impl MemoryStore {
         tags: &[String],
         embedding: &[f32],
     ) -> Result<MemorizeResult> {
-        self.memorize_inner(mnemonic, content, tags, embedding, false)
+        self.memorize_inner(mnemonic, content, tags, embedding, false, None)
     }
 
     /// Like `memorize`, but when `skip_merge` is true, suppresses auto-merge
-    /// and neighbor distance info (for shared/ACL-gated access).
+    /// and neighbor distance info (for shared/ACL-gated access). A newly
+    /// created memory is given `owner` in the same transaction.
     pub fn memorize_with_options(
         &self,
         mnemonic: &str,
@@ -513,8 +508,9 @@ impl MemoryStore {
         tags: &[String],
         embedding: &[f32],
         skip_merge: bool,
+        owner: Option<&str>,
     ) -> Result<MemorizeResult> {
-        self.memorize_inner(mnemonic, content, tags, embedding, skip_merge)
+        self.memorize_inner(mnemonic, content, tags, embedding, skip_merge, owner)
     }
 
     fn memorize_inner(
@@ -524,17 +520,25 @@ impl MemoryStore {
         tags: &[String],
         embedding: &[f32],
         skip_merge: bool,
+        owner: Option<&str>,
     ) -> Result<MemorizeResult> {
         let tags_json = serde_json::to_string(tags)?;
 
         let tx = self.conn.unchecked_transaction()?;
 
         // Look up existing mnemonic in mnemonics table first
-        let existing_via_mnemonic: Option<i64> = tx.query_row(
-            "SELECT memory_id FROM mnemonics WHERE text = ?1",
-            params![mnemonic],
-            |row| row.get(0),
-        ).ok();
+        let existing_via_mnemonic: Option<i64> = tx
+            .query_row(
+                "SELECT memory_id FROM mnemonics WHERE text = ?1",
+                params![mnemonic],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        let before = match existing_via_mnemonic {
+            Some(mid) => self.snapshot_by_id(mid)?,
+            None => None,
+        };
 
         let memory_id: i64 = if let Some(mid) = existing_via_mnemonic {
             // Update existing memory's content/tags
@@ -585,16 +589,6 @@ impl MemoryStore {
             params![mnemonic_row_id, embedding.as_bytes()],
         )?;
 
-        // Also update legacy memory_vectors for backward compat
-        tx.execute(
-            "DELETE FROM memory_vectors WHERE memory_id = ?1",
-            params![memory_id],
-        )?;
-        tx.execute(
-            "INSERT INTO memory_vectors (memory_id, embedding) VALUES (?1, ?2)",
-            params![memory_id, embedding.as_bytes()],
-        )?;
-
         // Find nearby memories via mnemonic_vectors, dedup by memory_id
         let neighbors: Vec<(i64, i64, f64, String, String)> = {
             let mut stmt = tx.prepare(
@@ -608,195 +602,170 @@ impl MemoryStore {
             )?;
             stmt.query_map(
                 params![embedding.as_bytes(), (AUTO_LINK_MAX_NEIGHBORS + 1) * 3],
-                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, f64>(2)?, row.get::<_, String>(3)?, row.get::<_, String>(4)?)),
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, f64>(2)?,
+                        row.get::<_, String>(3)?,
+                        row.get::<_, String>(4)?,
+                    ))
+                },
             )?
             .collect::<std::result::Result<Vec<_>, _>>()?
         };
 
-        // Dedup by memory_id, keep best (lowest) distance, exclude self
-        let mut seen = std::collections::HashSet::new();
-        let deduped: Vec<(i64, f64, String, String)> = neighbors
-            .into_iter()
-            .filter(|(mid, _, _, _, _)| *mid != memory_id && seen.insert(*mid))
-            .map(|(mid, _, dist, title, tags)| (mid, dist, title, tags))
-            .collect();
+        let plan = plan_neighbors(
+            neighbors
+                .into_iter()
+                .map(|(mid, _, distance, title, tags_json)| Neighbor {
+                    memory_id: mid,
+                    distance,
+                    title,
+                    tags: serde_json::from_str(&tags_json).unwrap_or_default(),
+                }),
+            memory_id,
+            skip_merge,
+        );
 
-        // When skip_merge is set (shared/ACL mode), suppress neighbor info and
-        // skip auto-merge to prevent probing via distance values.
-        let result_neighbors: Vec<MemorizeNeighbor> = if skip_merge {
-            Vec::new()
-        } else {
-            deduped
-                .iter()
-                .filter(|(_, dist, _, _)| *dist < AUTO_LINK_THRESHOLD)
-                .map(|(_, dist, title, tags_json)| {
-                    let tags: Vec<String> = serde_json::from_str(tags_json).unwrap_or_default();
-                    MemorizeNeighbor {
-                        mnemonic: title.clone(),
-                        distance: *dist,
-                        tags,
-                    }
-                })
-                .collect()
-        };
+        let mut absorbed = None;
+        let merged_with = if let Some(old) = &plan.merge {
+            let old_id = old.memory_id;
+            absorbed = self.snapshot_by_id(old_id)?;
+            let old_content: String = tx.query_row(
+                "SELECT content FROM memories WHERE id = ?1",
+                params![old_id],
+                |row| row.get(0),
+            )?;
+            let merged_tags_json = serde_json::to_string(&union_tags(tags, &old.tags))?;
 
-        let merged_with = if skip_merge {
-            // Skip auto-merge, but still auto-link
-            for (neighbor_mid, dist, _, _) in &deduped {
-                if *dist < AUTO_LINK_THRESHOLD {
-                    tx.execute(
-                        "INSERT OR IGNORE INTO memory_links (source_id, target_id, link_type)
-                         VALUES (?1, ?2, 'related')",
-                        params![memory_id, neighbor_mid],
-                    )?;
-                }
-            }
-            None
-        } else {
-            // Check for auto-merge candidate (closest neighbor below merge threshold)
-            let merge_candidate: Option<(i64, String, String, String)> = deduped
-                .iter()
-                .filter(|(_, dist, _, _)| *dist < AUTO_MERGE_THRESHOLD)
-                .next()
-                .map(|(mid, _, _, _)| {
-                    tx.query_row(
-                        "SELECT id, title, content, tags FROM memories WHERE id = ?1",
-                        params![mid],
-                        |row| {
-                            Ok((
-                                row.get::<_, i64>(0)?,
-                                row.get::<_, String>(1)?,
-                                row.get::<_, String>(2)?,
-                                row.get::<_, String>(3)?,
-                            ))
-                        },
-                    )
-                })
-                .transpose()?;
-
-            if let Some((old_id, ref old_title, old_content, old_tags_json)) = merge_candidate {
-                // Concatenate content: new + old
-                let merged_content = format!("{content}\n\n{old_content}");
-                // Union tags
-                let old_tags: Vec<String> =
-                    serde_json::from_str(&old_tags_json).unwrap_or_default();
-                let mut merged_tags: Vec<String> = tags.to_vec();
-                for t in old_tags {
-                    if !merged_tags.contains(&t) {
-                        merged_tags.push(t);
-                    }
-                }
-                let merged_tags_json = serde_json::to_string(&merged_tags)?;
+            // Update the new memory with merged content and tags
+            tx.execute(
+                "UPDATE memories SET content = ?1, tags = ?2, updated_at = datetime('now') WHERE id = ?3",
+                params![merged_content(content, &old_content), merged_tags_json, memory_id],
+            )?;
 
-                // Update the new memory with merged content and tags
-                tx.execute(
-                    "UPDATE memories SET content = ?1, tags = ?2, updated_at = datetime('now') WHERE id = ?3",
-                    params![merged_content, merged_tags_json, memory_id],
-                )?;
+            // Transfer mnemonics from old to new
+            // First delete mnemonic_vectors for old memory's mnemonics
+            tx.execute(
+                "DELETE FROM mnemonic_vectors WHERE mnemonic_id IN (SELECT id FROM mnemonics WHERE memory_id = ?1)",
+                params![old_id],
+            )?;
+            // Transfer mnemonics rows
+            tx.execute(
+                "UPDATE OR IGNORE mnemonics SET memory_id = ?1 WHERE memory_id = ?2",
+                params![memory_id, old_id],
+            )?;
+            // Delete any orphaned mnemonics that conflicted
+            tx.execute(
+                "DELETE FROM mnemonics WHERE memory_id = ?1",
+                params![old_id],
+            )?;
 
-                // Transfer mnemonics from old to new
-                // First delete mnemonic_vectors for old memory's mnemonics
-                tx.execute(
-                    "DELETE FROM mnemonic_vectors WHERE mnemonic_id IN (SELECT id FROM mnemonics WHERE memory_id = ?1)",
-                    params![old_id],
-                )?;
-                // Transfer mnemonics rows
-                tx.execute(
-                    "UPDATE OR IGNORE mnemonics SET memory_id = ?1 WHERE memory_id = ?2",
-                    params![memory_id, old_id],
-                )?;
-                // Delete any orphaned mnemonics that conflicted
-                tx.execute(
-                    "DELETE FROM mnemonics WHERE memory_id = ?1",
-                    params![old_id],
-                )?;
+            // Transfer links from old to new
+            tx.execute(
+                "UPDATE OR IGNORE memory_links SET source_id = ?1 WHERE source_id = ?2",
+                params![memory_id, old_id],
+            )?;
+            tx.execute(
+                "UPDATE OR IGNORE memory_links SET target_id = ?1 WHERE target_id = ?2",
+                params![memory_id, old_id],
+            )?;
+            // Clean up any self-links created by transfer
+            tx.execute("DELETE FROM memory_links WHERE source_id = target_id", [])?;
 
-                // Transfer links from old to new
-                tx.execute(
-                    "UPDATE OR IGNORE memory_links SET source_id = ?1 WHERE source_id = ?2",
-                    params![memory_id, old_id],
-                )?;
-                tx.execute(
-                    "UPDATE OR IGNORE memory_links SET target_id = ?1 WHERE target_id = ?2",
-                    params![memory_id, old_id],
-                )?;
-                // Clean up any self-links created by transfer
-                tx.execute(
-                    "DELETE FROM memory_links WHERE source_id = target_id",
-                    [],
-                )?;
+            // Create supersedes link
+            tx.execute(
+                "INSERT OR IGNORE INTO memory_links (source_id, target_id, link_type) VALUES (?1, ?2, 'supersedes')",
+                params![memory_id, old_id],
+            )?;
 
-                // Create supersedes link
-                tx.execute(
-                    "INSERT OR IGNORE INTO memory_links (source_id, target_id, link_type) VALUES (?1, ?2, 'supersedes')",
-                    params![memory_id, old_id],
-                )?;
+            // Delete old memory (CASCADE handles remaining)
+            tx.execute("DELETE FROM memories WHERE id = ?1", params![old_id])?;
 
-                // Delete old memory_vectors
+            Some(old.title.clone())
+        } else {
+            for neighbor_mid in &plan.link {
                 tx.execute(
-                    "DELETE FROM memory_vectors WHERE memory_id = ?1",
-                    params![old_id],
+                    "INSERT OR IGNORE INTO memory_links (source_id, target_id, link_type)
+                     VALUES (?1, ?2, 'related')",
+                    params![memory_id, neighbor_mid],
                 )?;
+            }
+            None
+        };
 
-                // Delete old memory (CASCADE handles remaining)
-                tx.execute("DELETE FROM memories WHERE id = ?1", params![old_id])?;
-
-                Some(old_title.clone())
+        if let Some(after) = self.snapshot_by_id(memory_id)? {
+            let action = if before.is_some() {
+                "memory.update"
             } else {
-                // No merge — just auto-link
-                for (neighbor_mid, dist, _, _) in &deduped {
-                    if *dist < AUTO_LINK_THRESHOLD {
-                        tx.execute(
-                            "INSERT OR IGNORE INTO memory_links (source_id, target_id, link_type)
-                             VALUES (?1, ?2, 'related')",
-                            params![memory_id, neighbor_mid],
-                        )?;
-                    }
-                }
-                None
+                "memory.create"
+            };
+            let detail = change_detail(before.as_ref(), Some(&after));
+            self.audit_memory(action, &after.uuid, detail.as_deref())?;
+            if let Some(absorbed) = &absorbed {
+                self.audit_memory(
+                    "memory.merge",
+                    &after.uuid,
+                    Some(&format!("absorbed {absorbed} ({})", absorbed.uuid)),
+                )?;
             }
-        };
+        }
+
+        let created = existing_via_mnemonic.is_none() && merged_with.is_none();
+        if created && owner.is_some() {
+            self.set_owner(mnemonic, owner)?;
+        }
 
         tx.commit()?;
         Ok(MemorizeResult {
+            created,
             merged_with,
-            neighbors: result_neighbors,
+            neighbors: plan.reported,
         })
     }
 
-    pub fn link(
-        &self,
-        source_title: &str,
-        target_title: &str,
-        link_type: &str,
-    ) -> Result<()> {
-        let source_id = Self::memory_id_by_title(&self.conn, source_title)
-            .map_err(|_| anyhow!("source not found: {}", source_title))?;
-        let target_id = Self::memory_id_by_title(&self.conn, target_title)
-            .map_err(|_| anyhow!("target not found: {}", target_title))?;
+    pub fn link(&self, source_title: &str, target_title: &str, link_type: &str) -> Result<()> {
+        let source_id = Self::find_memory_id(&self.conn, source_title)?
+            .ok_or_else(|| TriviaError::NotFound(format!("source not found: {}", source_title)))?;
+        let target_id = Self::find_memory_id(&self.conn, target_title)?
+            .ok_or_else(|| TriviaError::NotFound(format!("target not found: {}", target_title)))?;
 
-        self.conn.execute(
+        let inserted = self.conn.execute(
             "INSERT OR IGNORE INTO memory_links (source_id, target_id, link_type)
              VALUES (?1, ?2, ?3)",
             params![source_id, target_id, link_type],
         )?;
+        if inserted > 0
+            && let Some(source) = self.snapshot_by_id(source_id)?
+        {
+            self.audit_memory(
+                "memory.link",
+                &source.uuid,
+                Some(&link_detail(link_type, target_title)),
+            )?;
+        }
 
         Ok(())
     }
 
-    pub fn unlink(
-        &self,
-        source_title: &str,
-        target_title: &str,
-        link_type: &str,
-    ) -> Result<()> {
-        self.conn.execute(
+    pub fn unlink(&self, source_title: &str, target_title: &str, link_type: &str) -> Result<()> {
+        let removed = self.conn.execute(
             "DELETE FROM memory_links
              WHERE source_id = (SELECT id FROM memories WHERE title = ?1)
              AND target_id = (SELECT id FROM memories WHERE title = ?2)
              AND link_type = ?3",
             params![source_title, target_title, link_type],
         )?;
+        if removed > 0
+            && let Some(source) = self.snapshot_by_title(source_title)?
+        {
+            self.audit_memory(
+                "memory.unlink",
+                &source.uuid,
+                Some(&link_detail(link_type, target_title)),
+            )?;
+        }
         Ok(())
     }
 
@@ -848,7 +817,9 @@ impl MemoryStore {
             )?
             .collect::<std::result::Result<Vec<_>, _>>()?
             .into_iter()
-            .filter(|(title, distance)| title != exclude_title && *distance < threshold && seen.insert(title.clone()))
+            .filter(|(title, distance)| {
+                title != exclude_title && *distance < threshold && seen.insert(title.clone())
+            })
             .collect();
 
         Ok(results)
@@ -865,7 +836,8 @@ impl MemoryStore {
                 params![keep],
                 |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
             )
-            .map_err(|_| anyhow!("memory not found: {}", keep))?;
+            .optional()?
+            .ok_or_else(|| TriviaError::NotFound(format!("memory not found: {}", keep)))?;
 
         let (discard_id, discard_content, discard_tags_json): (i64, String, String) = tx
             .query_row(
@@ -873,35 +845,30 @@ impl MemoryStore {
                 params![discard],
                 |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
             )
-            .map_err(|_| anyhow!("memory not found: {}", discard))?;
+            .optional()?
+            .ok_or_else(|| TriviaError::NotFound(format!("memory not found: {}", discard)))?;
 
-        // Concatenate content
-        let merged_content = format!("{keep_content}\n\n{discard_content}");
+        let absorbed = self.snapshot_by_id(discard_id)?;
 
-        // Union tags
         let keep_tags: Vec<String> = serde_json::from_str(&keep_tags_json).unwrap_or_default();
         let discard_tags: Vec<String> =
             serde_json::from_str(&discard_tags_json).unwrap_or_default();
-        let mut merged_tags = keep_tags;
-        for t in discard_tags {
-            if !merged_tags.contains(&t) {
-                merged_tags.push(t);
-            }
-        }
-        let merged_tags_json = serde_json::to_string(&merged_tags)?;
+        let merged_tags_json = serde_json::to_string(&union_tags(&keep_tags, &discard_tags))?;
 
         // Update keep with merged content/tags
         tx.execute(
             "UPDATE memories SET content = ?1, tags = ?2, updated_at = datetime('now') WHERE id = ?3",
-            params![merged_content, merged_tags_json, keep_id],
+            params![merged_content(&keep_content, &discard_content), merged_tags_json, keep_id],
         )?;
 
         // Re-embed keep's primary mnemonic
-        let keep_primary_mn_id: Option<i64> = tx.query_row(
-            "SELECT id FROM mnemonics WHERE text = ?1",
-            params![keep],
-            |row| row.get(0),
-        ).ok();
+        let keep_primary_mn_id: Option<i64> = tx
+            .query_row(
+                "SELECT id FROM mnemonics WHERE text = ?1",
+                params![keep],
+                |row| row.get(0),
+            )
+            .optional()?;
         if let Some(mn_id) = keep_primary_mn_id {
             tx.execute(
                 "DELETE FROM mnemonic_vectors WHERE mnemonic_id = ?1",
@@ -913,16 +880,6 @@ impl MemoryStore {
             )?;
         }
 
-        // Update legacy memory_vectors
-        tx.execute(
-            "DELETE FROM memory_vectors WHERE memory_id = ?1",
-            params![keep_id],
-        )?;
-        tx.execute(
-            "INSERT INTO memory_vectors (memory_id, embedding) VALUES (?1, ?2)",
-            params![keep_id, embedding.as_bytes()],
-        )?;
-
         // Transfer mnemonics from discard to keep (vectors stay valid, keyed by mnemonic_id)
         tx.execute(
             "UPDATE OR IGNORE mnemonics SET memory_id = ?1 WHERE memory_id = ?2",
@@ -943,10 +900,7 @@ impl MemoryStore {
             "UPDATE OR IGNORE memory_links SET target_id = ?1 WHERE target_id = ?2",
             params![keep_id, discard_id],
         )?;
-        tx.execute(
-            "DELETE FROM memory_links WHERE source_id = target_id",
-            [],
-        )?;
+        tx.execute("DELETE FROM memory_links WHERE source_id = target_id", [])?;
 
         // Create supersedes link
         tx.execute(
@@ -954,15 +908,14 @@ impl MemoryStore {
             params![keep_id, discard_id],
         )?;
 
-        // Delete discard's legacy memory_vectors
-        tx.execute(
-            "DELETE FROM memory_vectors WHERE memory_id = ?1",
-            params![discard_id],
-        )?;
-
         // Delete discard memory
         tx.execute("DELETE FROM memories WHERE id = ?1", params![discard_id])?;
 
+        if let (Some(absorbed), Some(after)) = (absorbed, self.snapshot_by_id(keep_id)?) {
+            let detail = format!("absorbed {absorbed} ({}); after: {after}", absorbed.uuid);
+            self.audit_memory("memory.merge", &after.uuid, Some(&detail))?;
+        }
+
         tx.commit()?;
         Ok(())
     }
@@ -974,47 +927,90 @@ impl MemoryStore {
         tags: Option<&[String]>,
         fts_query: Option<&str>,
         exclude_tags: Option<&[String]>,
-    ) -> Result<Vec<Memory>> {
-        // Overfetch 5x for composite scoring reranking (extra to compensate for dedup)
-        let base_fetch = limit * 5;
-        let fetch_limit = match tags {
-            Some(_) => base_fetch * 4,
-            None => base_fetch,
-        };
+    ) -> Result<Recall> {
+        self.recall_filtered(
+            query_embedding,
+            limit,
+            tags,
+            fts_query,
+            exclude_tags,
+            &|_| true,
+        )
+    }
 
-        let query = "SELECT mn.memory_id, m.title, m.content, m.tags, v.distance, m.created_at, m.updated_at, m.recall_count, m.last_recalled_at, m.useful_count, m.not_useful_count
+    /// Like `recall`, but only memories `readable` accepts are considered.
+    /// They are filtered before ranking, so up to `limit` readable memories
+    /// come back, and the rest are neither counted as recalled nor logged.
+    pub fn recall_filtered(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        tags: Option<&[String]>,
+        fts_query: Option<&str>,
+        exclude_tags: Option<&[String]>,
+        readable: &dyn Fn(&Memory) -> bool,
+    ) -> Result<Recall> {
+        let query = "SELECT mn.memory_id, m.title, m.content, m.tags, v.distance, m.created_at, m.updated_at, m.recall_count, m.last_recalled_at, m.useful_count, m.not_useful_count, u.username
              FROM mnemonic_vectors v
              JOIN mnemonics mn ON mn.id = v.mnemonic_id
              JOIN memories m ON m.id = mn.memory_id
+             LEFT JOIN users u ON u.id = m.owner_id
              WHERE v.embedding MATCH ?1
              AND v.k = ?2
              ORDER BY v.distance";
 
-        let mut stmt = self.conn.prepare(query)?;
-
-        let rows = stmt
-            .query_map(params![query_embedding.as_bytes(), fetch_limit], |row| {
-                Ok(MemoryRow {
-                    memory_id: row.get(0)?,
-                    mnemonic: row.get(1)?,
-                    content: row.get(2)?,
-                    tags_json: row.get(3)?,
-                    distance: row.get(4)?,
-                    created_at: row.get(5)?,
-                    updated_at: row.get(6)?,
-                    recall_count: row.get(7)?,
-                    last_recalled_at: row.get(8)?,
-                    useful_count: row.get(9)?,
-                    not_useful_count: row.get(10)?,
-                })
-            })?
-            .collect::<std::result::Result<Vec<_>, _>>()?;
-
-        // Deduplicate by memory_id, keep best (lowest) distance per memory
-        let mut seen = std::collections::HashSet::new();
-        let deduped: Vec<MemoryRow> = rows.into_iter()
-            .filter(|row| seen.insert(row.memory_id))
-            .collect();
+        let candidates = gather_candidates(limit, tags.is_some(), |fetch_limit| {
+            let mut stmt = self.conn.prepare(query)?;
+            let rows = stmt
+                .query_map(params![query_embedding.as_bytes(), fetch_limit], |row| {
+                    Ok(MemoryRow {
+                        memory_id: row.get(0)?,
+                        mnemonic: row.get(1)?,
+                        content: row.get(2)?,
+                        tags_json: row.get(3)?,
+                        distance: row.get(4)?,
+                        created_at: row.get(5)?,
+                        updated_at: row.get(6)?,
+                        recall_count: row.get(7)?,
+                        last_recalled_at: row.get(8)?,
+                        useful_count: row.get(9)?,
+                        not_useful_count: row.get(10)?,
+                        owner: row.get(11)?,
+                    })
+                })?
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            let fetched = rows.len();
+
+            // Deduplicate by memory_id, keep best (lowest) distance per memory
+            let mut seen = std::collections::HashSet::new();
+            let mut memories = Vec::new();
+            for row in rows.into_iter().filter(|row| seen.insert(row.memory_id)) {
+                let row_tags: Vec<String> =
+                    serde_json::from_str(&row.tags_json).unwrap_or_default();
+                let mnemonics = Self::get_mnemonics_for_memory(&self.conn, row.memory_id)?;
+                let mem = Memory {
+                    mnemonic: row.mnemonic,
+                    content: row.content,
+                    tags: row_tags,
+                    mnemonics,
+                    distance: row.distance,
+                    score: 0.0,
+                    created_at: parse_sqlite_datetime(&row.created_at),
+                    updated_at: parse_sqlite_datetime(&row.updated_at),
+                    recall_count: row.recall_count,
+                    last_recalled_at: row.last_recalled_at.as_deref().map(parse_sqlite_datetime),
+                    useful_count: row.useful_count,
+                    not_useful_count: row.not_useful_count,
+                    links: Vec::new(),
+                    owner: row.owner,
+                };
+                if passes_tag_filters(&mem, tags, exclude_tags) && readable(&mem) {
+                    memories.push((row.memory_id, mem));
+                }
+            }
+            Ok((fetched, memories))
+        })?;
+        let (memory_ids, mut memories): (Vec<i64>, Vec<Memory>) = candidates.into_iter().unzip();
 
         // Build FTS match set if query provided (now uses title column)
         let fts_matches: std::collections::HashSet<String> = match fts_query {
@@ -1034,124 +1030,25 @@ impl MemoryStore {
             _ => std::collections::HashSet::new(),
         };
 
-        let mut memories: Vec<Memory> = deduped
-            .into_iter()
-            .map(|row| {
-                let row_tags: Vec<String> =
-                    serde_json::from_str(&row.tags_json).unwrap_or_default();
-                let mnemonics = Self::get_mnemonics_for_memory(&self.conn, row.memory_id).unwrap_or_default();
-                Memory {
-                    mnemonic: row.mnemonic,
-                    content: row.content,
-                    tags: row_tags,
-                    mnemonics,
-                    distance: row.distance,
-                    score: 0.0,
-                    created_at: parse_sqlite_datetime(&row.created_at),
-                    updated_at: parse_sqlite_datetime(&row.updated_at),
-                    recall_count: row.recall_count,
-                    last_recalled_at: row.last_recalled_at.as_deref().map(parse_sqlite_datetime),
-                    useful_count: row.useful_count,
-                    not_useful_count: row.not_useful_count,
-                    links: Vec::new(),
-                }
-            })
-            .filter(|mem| match tags {
-                Some(filter_tags) => filter_tags.iter().any(|t| mem.tags.contains(t)),
-                None => true,
-            })
-            .filter(|mem| match exclude_tags {
-                Some(excl) => !excl.iter().any(|t| mem.tags.contains(t)),
-                None => true,
-            })
-            .collect();
-
         // Populate links for each candidate
         for mem in &mut memories {
             mem.links = self.get_links(&mem.mnemonic)?;
         }
 
-        // Compute composite scores
-        let similarity_map: std::collections::HashMap<String, f64> = memories
-            .iter()
-            .map(|m| (m.mnemonic.clone(), 1.0 - m.distance))
-            .collect();
-        let lambda = (2.0_f64).ln() / self.scoring.half_life_days;
-        let now = Utc::now();
-
-        for mem in &mut memories {
-            let similarity = 1.0 - mem.distance;
-
-            let recency = match mem.last_recalled_at {
-                Some(ts) => {
-                    let days = days_between(ts, now);
-                    (-lambda * days).exp()
-                }
-                None => 0.0,
-            };
-
-            let frequency = (1.0 + mem.recall_count as f64).ln();
-
-            let link_boost: f64 = mem
-                .links
-                .iter()
-                .filter_map(|l| {
-                    let other = if l.source_mnemonic == mem.mnemonic {
-                        &l.target_mnemonic
-                    } else {
-                        &l.source_mnemonic
-                    };
-                    similarity_map.get(other).copied()
-                })
-                .take(3)
-                .sum();
-
-            let rating_signal = {
-                let total = (mem.useful_count + mem.not_useful_count) as f64;
-                if total > 0.0 {
-                    let ratio = (mem.useful_count - mem.not_useful_count) as f64 / total;
-                    let confidence = total.sqrt() / (total.sqrt() + 1.0);
-                    ratio * confidence
-                } else {
-                    0.0
-                }
-            };
-
-            let tag_boost = if !self.scoring.boost_tags.is_empty() {
-                let matches = mem
-                    .tags
-                    .iter()
-                    .filter(|t| self.scoring.boost_tags.contains(t))
-                    .count();
-                (matches as f64) / (self.scoring.boost_tags.len() as f64)
-            } else {
-                0.0
-            };
-
-            let fts_boost = if fts_matches.contains(&mem.mnemonic) {
-                1.0
-            } else {
-                0.0
-            };
-
-            mem.score = self.scoring.similarity_weight * similarity
-                + self.scoring.recency_weight * recency
-                + self.scoring.frequency_weight * frequency
-                + self.scoring.link_weight * link_boost
-                + self.scoring.rating_weight * rating_signal
-                + self.scoring.tag_boost_weight * tag_boost
-                + self.scoring.fts_weight * fts_boost;
-        }
-
-        // Sort by score descending, take limit
-        memories.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
-        memories.truncate(limit);
+        let ratings = self.rating_signals(&memory_ids)?;
+        let query_feedback = self.query_signals(&memory_ids, query_embedding)?;
+        let features = self.scoring.rank(
+            &mut memories,
+            &fts_matches,
+            &ratings,
+            &query_feedback,
+            limit,
+        );
 
         // Update recall stats for all returned memories (by title)
         let titles: Vec<&str> = memories.iter().map(|m| m.mnemonic.as_str()).collect();
         if !titles.is_empty() {
-            let placeholders: Vec<String> =
-                (1..=titles.len()).map(|i| format!("?{i}")).collect();
+            let placeholders: Vec<String> = (1..=titles.len()).map(|i| format!("?{i}")).collect();
             let sql = format!(
                 "UPDATE memories SET recall_count = recall_count + 1, last_recalled_at = datetime('now') WHERE title IN ({})",
                 placeholders.join(", ")
@@ -1163,14 +1060,16 @@ impl MemoryStore {
             self.conn.execute(&sql, params.as_slice())?;
         }
 
-        Ok(memories)
+        let id = self.log_recall(query_embedding, &titles, &features)?;
+        Ok(Recall { id, memories })
     }
 
     pub fn list_all_summaries(&self) -> Result<Vec<MemorySummary>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, title, content, tags, recall_count, useful_count, not_useful_count
-             FROM memories
-             ORDER BY recall_count DESC, updated_at DESC",
+            "SELECT m.id, m.title, m.content, m.tags, m.recall_count, m.useful_count, m.not_useful_count, u.username
+             FROM memories m
+             LEFT JOIN users u ON u.id = m.owner_id
+             ORDER BY m.recall_count DESC, m.updated_at DESC",
         )?;
 
         let results = stmt
@@ -1183,23 +1082,37 @@ impl MemoryStore {
                     row.get::<_, i64>(4)?,
                     row.get::<_, i64>(5)?,
                     row.get::<_, i64>(6)?,
+                    row.get::<_, Option<String>>(7)?,
                 ))
             })?
             .collect::<std::result::Result<Vec<_>, _>>()?
             .into_iter()
-            .map(|(id, title, content, tags_json, recall_count, useful_count, not_useful_count)| {
-                let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
-                let mnemonics = Self::get_mnemonics_for_memory(&self.conn, id).unwrap_or_default();
-                MemorySummary {
-                    mnemonic: title,
+            .map(
+                |(
+                    id,
+                    title,
                     content,
-                    tags,
-                    mnemonics,
+                    tags_json,
                     recall_count,
                     useful_count,
                     not_useful_count,
-                }
-            })
+                    owner,
+                )| {
+                    let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
+                    let mnemonics =
+                        Self::get_mnemonics_for_memory(&self.conn, id).unwrap_or_default();
+                    MemorySummary {
+                        mnemonic: title,
+                        content,
+                        tags,
+                        mnemonics,
+                        recall_count,
+                        useful_count,
+                        not_useful_count,
+                        owner,
+                    }
+                },
+            )
             .collect();
 
         Ok(results)
@@ -1258,8 +1171,9 @@ impl MemoryStore {
 
     pub fn get_memory_by_mnemonic(&self, title: &str) -> Result<Option<Memory>> {
         let row = self.conn.query_row(
-            "SELECT m.id, m.title, m.content, m.tags, m.created_at, m.updated_at, m.recall_count, m.last_recalled_at, m.useful_count, m.not_useful_count
+            "SELECT m.id, m.title, m.content, m.tags, m.created_at, m.updated_at, m.recall_count, m.last_recalled_at, m.useful_count, m.not_useful_count, u.username
              FROM memories m
+             LEFT JOIN users u ON u.id = m.owner_id
              WHERE m.title = ?1",
             params![title],
             |row| {
@@ -1274,12 +1188,25 @@ impl MemoryStore {
                     row.get::<_, Option<String>>(7)?,
                     row.get::<_, i64>(8)?,
                     row.get::<_, i64>(9)?,
+                    row.get::<_, Option<String>>(10)?,
                 ))
             },
         );
 
         match row {
-            Ok((id, title, content, tags_json, created_at, updated_at, recall_count, last_recalled_at, useful_count, not_useful_count)) => {
+            Ok((
+                id,
+                title,
+                content,
+                tags_json,
+                created_at,
+                updated_at,
+                recall_count,
+                last_recalled_at,
+                useful_count,
+                not_useful_count,
+                owner,
+            )) => {
                 let tags: Vec<String> = serde_json::from_str(&tags_json).unwrap_or_default();
                 let mnemonics = Self::get_mnemonics_for_memory(&self.conn, id)?;
                 let links = self.get_links(&title)?;
@@ -1297,6 +1224,7 @@ impl MemoryStore {
                     useful_count,
                     not_useful_count,
                     links,
+                    owner,
                 }))
             }
             Err(rusqlite::Error::QueryReturnedNoRows) => Ok(None),
@@ -1304,43 +1232,61 @@ impl MemoryStore {
         }
     }
 
-    pub fn rate(&self, title: &str, useful: bool) -> Result<()> {
-        let column = if useful {
-            "useful_count"
+    /// Record the current actor's verdict on a memory, optionally with the
+    /// query it was (or wasn't) useful for. Only a rater's latest verdict
+    /// counts towards ranking.
+    pub fn rate(
+        &self,
+        title: &str,
+        useful: bool,
+        query: Option<&str>,
+        recall_id: Option<&str>,
+    ) -> Result<()> {
+        if self
+            .rate_batch(&[title.to_string()], useful, query, recall_id)?
+            .is_empty()
+        {
+            Ok(())
         } else {
-            "not_useful_count"
-        };
-        let rows = self.conn.execute(
-            &format!(
-                "UPDATE memories SET {column} = {column} + 1 WHERE title = ?1"
-            ),
-            params![title],
-        )?;
-        if rows == 0 {
-            return Err(anyhow!("memory not found: {}", title));
+            Err(TriviaError::NotFound(format!(
+                "memory not found: {}",
+                title
+            )))
         }
-        Ok(())
     }
 
     /// Rate multiple memories at once. Returns the list of titles that were NOT found.
-    pub fn rate_batch(&self, titles: &[String], useful: bool) -> Result<Vec<String>> {
-        let column = if useful {
-            "useful_count"
-        } else {
-            "not_useful_count"
+    /// With `recall_id`, every memory must have been returned by that recall,
+    /// and the ratings also apply to later recalls with similar queries.
+    pub fn rate_batch(
+        &self,
+        titles: &[String],
+        useful: bool,
+        query: Option<&str>,
+        recall_id: Option<&str>,
+    ) -> Result<Vec<String>> {
+        let verdict = if useful { "useful" } else { "not useful" };
+        let detail = match query {
+            Some(query) => format!("{verdict} for {query:?}"),
+            None => verdict.to_string(),
         };
         let mut not_found = Vec::new();
+        let tx = self.conn.unchecked_transaction()?;
+        let recall = recall_id.map(|uuid| self.recall_row_id(uuid)).transpose()?;
         for title in titles {
-            let rows = self.conn.execute(
-                &format!(
-                    "UPDATE memories SET {column} = {column} + 1 WHERE title = ?1"
-                ),
-                params![title],
-            )?;
-            if rows == 0 {
+            let Some(memory_id) = Self::find_memory_id(&self.conn, title)? else {
                 not_found.push(title.clone());
+                continue;
+            };
+            if let Some(recall) = recall {
+                self.require_recalled(recall, memory_id, title)?;
+            }
+            self.record_rating(memory_id, useful, query, recall)?;
+            if let Some(memory) = self.snapshot_by_id(memory_id)? {
+                self.audit_memory("memory.rate", &memory.uuid, Some(&detail))?;
             }
         }
+        tx.commit()?;
         Ok(not_found)
     }
 
@@ -1360,7 +1306,9 @@ impl MemoryStore {
                 params![title],
                 |row| row.get(0),
             )
-            .map_err(|_| anyhow!("memory not found: {}", title))?;
+            .optional()?
+            .ok_or_else(|| TriviaError::NotFound(format!("memory not found: {}", title)))?;
+        let before = self.snapshot_by_id(memory_id)?;
 
         tx.execute(
             "UPDATE memories SET content = ?1, tags = ?2, updated_at = datetime('now') WHERE id = ?3",
@@ -1368,11 +1316,13 @@ impl MemoryStore {
         )?;
 
         // Update primary mnemonic vector (the one matching title)
-        let primary_mn_id: Option<i64> = tx.query_row(
-            "SELECT id FROM mnemonics WHERE text = ?1",
-            params![title],
-            |row| row.get(0),
-        ).ok();
+        let primary_mn_id: Option<i64> = tx
+            .query_row(
+                "SELECT id FROM mnemonics WHERE text = ?1",
+                params![title],
+                |row| row.get(0),
+            )
+            .optional()?;
         if let Some(mn_id) = primary_mn_id {
             tx.execute(
                 "DELETE FROM mnemonic_vectors WHERE mnemonic_id = ?1",
@@ -1384,26 +1334,12 @@ impl MemoryStore {
             )?;
         }
 
-        // Also update legacy memory_vectors
-        tx.execute(
-            "DELETE FROM memory_vectors WHERE memory_id = ?1",
-            params![memory_id],
-        )?;
-        tx.execute(
-            "INSERT INTO memory_vectors (memory_id, embedding) VALUES (?1, ?2)",
-            params![memory_id, embedding.as_bytes()],
-        )?;
-
+        self.audit_change("memory.update", memory_id, before)?;
         tx.commit()?;
         Ok(())
     }
 
-    pub fn rename_memory(
-        &self,
-        old_title: &str,
-        new_title: &str,
-        embedding: &[f32],
-    ) -> Result<()> {
+    pub fn rename_memory(&self, old_title: &str, new_title: &str, embedding: &[f32]) -> Result<()> {
         let tx = self.conn.unchecked_transaction()?;
 
         let memory_id: i64 = tx
@@ -1412,7 +1348,8 @@ impl MemoryStore {
                 params![old_title],
                 |row| row.get(0),
             )
-            .map_err(|_| anyhow!("memory not found: {}", old_title))?;
+            .optional()?
+            .ok_or_else(|| TriviaError::NotFound(format!("memory not found: {}", old_title)))?;
 
         // Check for conflict
         let conflict: bool = tx
@@ -1424,11 +1361,12 @@ impl MemoryStore {
             .map(|c| c > 0)?;
 
         if conflict {
-            return Err(anyhow!(
+            return Err(TriviaError::Conflict(format!(
                 "title already exists: {}",
                 new_title
-            ));
+            )));
         }
+        let before = self.snapshot_by_id(memory_id)?;
 
         // Update title + keep mnemonic synced
         tx.execute(
@@ -1443,11 +1381,13 @@ impl MemoryStore {
         )?;
 
         // Re-embed the primary mnemonic
-        let primary_mn_id: Option<i64> = tx.query_row(
-            "SELECT id FROM mnemonics WHERE text = ?1",
-            params![new_title],
-            |row| row.get(0),
-        ).ok();
+        let primary_mn_id: Option<i64> = tx
+            .query_row(
+                "SELECT id FROM mnemonics WHERE text = ?1",
+                params![new_title],
+                |row| row.get(0),
+            )
+            .optional()?;
         if let Some(mn_id) = primary_mn_id {
             tx.execute(
                 "DELETE FROM mnemonic_vectors WHERE mnemonic_id = ?1",
@@ -1459,20 +1399,12 @@ impl MemoryStore {
             )?;
         }
 
-        // Also update legacy memory_vectors
-        tx.execute(
-            "DELETE FROM memory_vectors WHERE memory_id = ?1",
-            params![memory_id],
-        )?;
-        tx.execute(
-            "INSERT INTO memory_vectors (memory_id, embedding) VALUES (?1, ?2)",
-            params![memory_id, embedding.as_bytes()],
-        )?;
-
+        self.audit_change("memory.rename", memory_id, before)?;
         tx.commit()?;
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn edit_memory(
         &self,
         title: &str,
@@ -1492,16 +1424,14 @@ impl MemoryStore {
                 params![title],
                 |row| Ok((row.get(0)?, row.get(1)?)),
             )
-            .map_err(|_| anyhow!("memory not found: {}", title))?;
+            .optional()?
+            .ok_or_else(|| TriviaError::NotFound(format!("memory not found: {}", title)))?;
+        let before = self.snapshot_by_id(memory_id)?;
 
         // Update tags
-        let mut tags: Vec<String> = serde_json::from_str(&current_tags_json).unwrap_or_default();
-        for t in add_tags {
-            if !tags.contains(t) {
-                tags.push(t.clone());
-            }
-        }
-        tags.retain(|t| !remove_tags.contains(t));
+        let current_tags: Vec<String> =
+            serde_json::from_str(&current_tags_json).unwrap_or_default();
+        let tags = edited_tags(&current_tags, add_tags, remove_tags);
         let tags_json = serde_json::to_string(&tags)?;
 
         tx.execute(
@@ -1511,8 +1441,9 @@ impl MemoryStore {
 
         // Update title + re-embed if requested
         let final_title = if let Some(new_t) = new_title {
-            let embedding = new_embedding
-                .ok_or_else(|| anyhow!("new_embedding required when changing title"))?;
+            let embedding = new_embedding.ok_or_else(|| {
+                TriviaError::validation("new_embedding required when changing title")
+            })?;
 
             // Check for conflict
             let conflict: bool = tx
@@ -1523,7 +1454,10 @@ impl MemoryStore {
                 )
                 .map(|c| c > 0)?;
             if conflict {
-                return Err(anyhow!("title already exists: {}", new_t));
+                return Err(TriviaError::Conflict(format!(
+                    "title already exists: {}",
+                    new_t
+                )));
             }
 
             // Update title + keep mnemonic synced
@@ -1539,11 +1473,13 @@ impl MemoryStore {
             )?;
 
             // Re-embed primary mnemonic
-            let primary_mn_id: Option<i64> = tx.query_row(
-                "SELECT id FROM mnemonics WHERE text = ?1",
-                params![new_t],
-                |row| row.get(0),
-            ).ok();
+            let primary_mn_id: Option<i64> = tx
+                .query_row(
+                    "SELECT id FROM mnemonics WHERE text = ?1",
+                    params![new_t],
+                    |row| row.get(0),
+                )
+                .optional()?;
             if let Some(mn_id) = primary_mn_id {
                 tx.execute(
                     "DELETE FROM mnemonic_vectors WHERE mnemonic_id = ?1",
@@ -1555,16 +1491,6 @@ impl MemoryStore {
                 )?;
             }
 
-            // Also update legacy memory_vectors
-            tx.execute(
-                "DELETE FROM memory_vectors WHERE memory_id = ?1",
-                params![memory_id],
-            )?;
-            tx.execute(
-                "INSERT INTO memory_vectors (memory_id, embedding) VALUES (?1, ?2)",
-                params![memory_id, embedding.as_bytes()],
-            )?;
-
             new_t.to_string()
         } else {
             title.to_string()
@@ -1602,28 +1528,28 @@ impl MemoryStore {
                 |row| row.get(0),
             )?;
             if count <= 1 {
-                return Err(anyhow!("cannot remove the last mnemonic"));
+                return Err(TriviaError::validation("cannot remove the last mnemonic"));
             }
-            let mn_id: Option<i64> = tx.query_row(
-                "SELECT id FROM mnemonics WHERE memory_id = ?1 AND text = ?2",
-                params![memory_id, mn_text],
-                |row| row.get(0),
-            ).ok();
+            let mn_id: Option<i64> = tx
+                .query_row(
+                    "SELECT id FROM mnemonics WHERE memory_id = ?1 AND text = ?2",
+                    params![memory_id, mn_text],
+                    |row| row.get(0),
+                )
+                .optional()?;
             if let Some(mn_id) = mn_id {
                 tx.execute(
                     "DELETE FROM mnemonic_vectors WHERE mnemonic_id = ?1",
                     params![mn_id],
                 )?;
-                tx.execute(
-                    "DELETE FROM mnemonics WHERE id = ?1",
-                    params![mn_id],
-                )?;
+                tx.execute("DELETE FROM mnemonics WHERE id = ?1", params![mn_id])?;
             }
         }
 
         // Collect final mnemonics list
         let mnemonics = Self::get_mnemonics_for_memory(&tx, memory_id)?;
 
+        self.audit_change("memory.edit", memory_id, before)?;
         tx.commit()?;
         Ok(EditResult {
             old_mnemonic: title.to_string(),
@@ -1635,9 +1561,9 @@ impl MemoryStore {
     }
 
     pub fn rename_tag(&self, old_tag: &str, new_tag: &str) -> Result<usize> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, tags FROM memories WHERE tags LIKE ?1",
-        )?;
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, tags FROM memories WHERE tags LIKE ?1")?;
         let pattern = format!("%\"{}\"%", old_tag.replace('"', "\"\""));
         let rows: Vec<(i64, String)> = stmt
             .query_map(params![pattern], |row| {
@@ -1656,10 +1582,12 @@ impl MemoryStore {
                 tags.push(new_tag.to_string());
             }
             let new_json = serde_json::to_string(&tags)?;
+            let before = self.snapshot_by_id(*id)?;
             self.conn.execute(
                 "UPDATE memories SET tags = ?1, updated_at = datetime('now') WHERE id = ?2",
                 params![new_json, id],
             )?;
+            self.audit_change("memory.rename_tag", *id, before)?;
             count += 1;
         }
 
@@ -1667,27 +1595,32 @@ impl MemoryStore {
     }
 
     pub fn delete_memory(&self, title: &str) -> Result<bool> {
+        let before = self.snapshot_by_title(title)?;
         // Must manually delete mnemonic_vectors rows first (vec0 doesn't support FK CASCADE)
-        let memory_id: Option<i64> = self.conn.query_row(
-            "SELECT id FROM memories WHERE title = ?1",
-            params![title],
-            |row| row.get(0),
-        ).ok();
+        let memory_id: Option<i64> = self
+            .conn
+            .query_row(
+                "SELECT id FROM memories WHERE title = ?1",
+                params![title],
+                |row| row.get(0),
+            )
+            .optional()?;
         if let Some(mid) = memory_id {
             self.conn.execute(
                 "DELETE FROM mnemonic_vectors WHERE mnemonic_id IN (SELECT id FROM mnemonics WHERE memory_id = ?1)",
                 params![mid],
             )?;
-            // Also clean up legacy memory_vectors
-            self.conn.execute(
-                "DELETE FROM memory_vectors WHERE memory_id = ?1",
-                params![mid],
+        }
+        let rows = self
+            .conn
+            .execute("DELETE FROM memories WHERE title = ?1", params![title])?;
+        if let Some(before) = before.filter(|_| rows > 0) {
+            self.audit_memory(
+                "memory.delete",
+                &before.uuid,
+                change_detail(Some(&before), None).as_deref(),
             )?;
         }
-        let rows = self.conn.execute(
-            "DELETE FROM memories WHERE title = ?1",
-            params![title],
-        )?;
         Ok(rows > 0)
     }
 
@@ -1736,18 +1669,158 @@ impl MemoryStore {
 }
 
 /// Parse a SQLite datetime string ("YYYY-MM-DD HH:MM:SS") into a chrono DateTime<Utc>.
-fn parse_sqlite_datetime(s: &str) -> DateTime<Utc> {
+pub(crate) fn parse_sqlite_datetime(s: &str) -> DateTime<Utc> {
     NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S")
         .map(|naive| naive.and_utc())
         .unwrap_or_default()
 }
 
+/// Inverse of `parse_sqlite_datetime`: the format `datetime('now')` produces.
+pub(crate) fn format_sqlite_datetime(dt: &DateTime<Utc>) -> String {
+    dt.format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
 /// Return the number of days between two DateTimes.
-fn days_between(earlier: DateTime<Utc>, later: DateTime<Utc>) -> f64 {
+pub(crate) fn days_between(earlier: DateTime<Utc>, later: DateTime<Utc>) -> f64 {
     let duration = later.signed_duration_since(earlier);
     (duration.num_seconds() as f64 / 86400.0).max(0.0)
 }
 
+// The decisions below are the same for every backend; only the SQL that
+// carries them out differs.
+
+/// A memory near one being stored, as found in the vector index.
+pub(crate) struct Neighbor {
+    pub(crate) memory_id: i64,
+    pub(crate) distance: f64,
+    pub(crate) title: String,
+    pub(crate) tags: Vec<String>,
+}
+
+/// What storing a memory does to the memories around it.
+pub(crate) struct NeighborPlan {
+    /// Neighbors reported back to the caller.
+    pub(crate) reported: Vec<MemorizeNeighbor>,
+    /// A neighbor close enough to be absorbed as a duplicate.
+    pub(crate) merge: Option<Neighbor>,
+    /// Neighbors to link as related when nothing is absorbed.
+    pub(crate) link: Vec<i64>,
+}
+
+/// Plan the auto-merge and auto-links for memory `memory_id` from its
+/// nearest mnemonics, closest first. When `skip_merge` is set (shared/ACL
+/// mode), neighbor info is suppressed and nothing is merged, to prevent
+/// probing via distance values; auto-links are still made.
+pub(crate) fn plan_neighbors(
+    candidates: impl IntoIterator<Item = Neighbor>,
+    memory_id: i64,
+    skip_merge: bool,
+) -> NeighborPlan {
+    // Dedup by memory_id, keep best (lowest) distance, exclude self
+    let mut seen = std::collections::HashSet::new();
+    let mut nearby: Vec<Neighbor> = candidates
+        .into_iter()
+        .filter(|n| n.memory_id != memory_id && seen.insert(n.memory_id))
+        .collect();
+
+    let reported = if skip_merge {
+        Vec::new()
+    } else {
+        nearby
+            .iter()
+            .filter(|n| n.distance < AUTO_LINK_THRESHOLD)
+            .map(|n| MemorizeNeighbor {
+                mnemonic: n.title.clone(),
+                distance: n.distance,
+                tags: n.tags.clone(),
+            })
+            .collect()
+    };
+    let merge = match nearby
+        .iter()
+        .position(|n| n.distance < AUTO_MERGE_THRESHOLD)
+    {
+        Some(i) if !skip_merge => Some(nearby.swap_remove(i)),
+        _ => None,
+    };
+    let link = match merge {
+        Some(_) => Vec::new(),
+        None => nearby
+            .iter()
+            .filter(|n| n.distance < AUTO_LINK_THRESHOLD)
+            .map(|n| n.memory_id)
+            .collect(),
+    };
+    NeighborPlan {
+        reported,
+        merge,
+        link,
+    }
+}
+
+/// Content of a memory that absorbed another: its own, then the other's.
+pub(crate) fn merged_content(keep: &str, absorbed: &str) -> String {
+    format!("{keep}\n\n{absorbed}")
+}
+
+/// `tags` followed by those of `more` it lacks.
+pub(crate) fn union_tags(tags: &[String], more: &[String]) -> Vec<String> {
+    let mut merged = tags.to_vec();
+    for t in more {
+        if !merged.contains(t) {
+            merged.push(t.clone());
+        }
+    }
+    merged
+}
+
+/// `tags` after an edit that adds `add` and then removes `remove`.
+pub(crate) fn edited_tags(tags: &[String], add: &[String], remove: &[String]) -> Vec<String> {
+    let mut edited = union_tags(tags, add);
+    edited.retain(|t| !remove.contains(t));
+    edited
+}
+
+/// Whether `mem` has one of `tags` (if given) and none of `exclude_tags`.
+pub(crate) fn passes_tag_filters(
+    mem: &Memory,
+    tags: Option<&[String]>,
+    exclude_tags: Option<&[String]>,
+) -> bool {
+    let included = tags.is_none_or(|wanted| wanted.iter().any(|t| mem.tags.contains(t)));
+    let excluded = exclude_tags.is_some_and(|excl| excl.iter().any(|t| mem.tags.contains(t)));
+    included && !excluded
+}
+
+/// sqlite-vec's limit on `k` in a KNN query.
+const MAX_RECALL_FETCH: usize = 4096;
+
+/// Candidates for a recall that pass its filters, with their memory ids.
+/// `fetch(n)` looks up the `n` nearest mnemonics and returns how many rows
+/// it got and the filtered memories. The search overfetches 5x `limit` for
+/// composite-score reranking (4x more with a tag filter), and widens while
+/// filters leave fewer candidates than that and nearer ones remain.
+pub(crate) fn gather_candidates(
+    limit: usize,
+    tag_filtered: bool,
+    mut fetch: impl FnMut(usize) -> Result<(usize, Vec<(i64, Memory)>)>,
+) -> Result<Vec<(i64, Memory)>> {
+    let wanted = limit * 5;
+    let mut fetch_limit = if tag_filtered { wanted * 4 } else { wanted }.min(MAX_RECALL_FETCH);
+    loop {
+        let (fetched, candidates) = fetch(fetch_limit)?;
+        if candidates.len() >= wanted || fetched < fetch_limit || fetch_limit == MAX_RECALL_FETCH {
+            return Ok(candidates);
+        }
+        fetch_limit = (fetch_limit * 4).min(MAX_RECALL_FETCH);
+    }
+}
+
+/// Audit detail of a link or unlink.
+pub(crate) fn link_detail(link_type: &str, target_title: &str) -> String {
+    format!("{link_type} -> {target_title:?}")
+}
+
 struct MemoryRow {
     memory_id: i64,
     mnemonic: String,
@@ -1760,6 +1833,7 @@ struct MemoryRow {
     last_recalled_at: Option<String>,
     useful_count: i64,
     not_useful_count: i64,
+    owner: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1772,6 +1846,8 @@ pub struct MemorySummary {
     pub recall_count: i64,
     pub useful_count: i64,
     pub not_useful_count: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub owner: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1784,6 +1860,9 @@ pub struct TagCount {
 pub struct MemorizeResult {
     pub merged_with: Option<String>,
     pub neighbors: Vec<MemorizeNeighbor>,
+    /// True if a new memory was stored, rather than an existing one updated
+    /// or merged into.
+    pub created: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -1832,7 +1911,7 @@ mod tests {
             &emb2,
         )?;
 
-        let results = store.recall(&query, 5, None, None, None)?;
+        let results = store.recall(&query, 5, None, None, None)?.memories;
         assert_eq!(results.len(), 2);
 
         // Both should be returned, closest first
@@ -1856,7 +1935,7 @@ mod tests {
         store.memorize("key", "original content", &[], &emb)?;
         store.memorize("key", "updated content", &[], &emb2)?;
 
-        let results = store.recall(&emb2, 5, None, None, None)?;
+        let results = store.recall(&emb2, 5, None, None, None)?.memories;
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].content, "updated content");
 
@@ -1871,18 +1950,18 @@ mod tests {
         store.memorize("tracked::fact", "some content", &[], &emb)?;
 
         // First recall — returned snapshot has count=0 (pre-update value)
-        let results = store.recall(&emb, 5, None, None, None)?;
+        let results = store.recall(&emb, 5, None, None, None)?.memories;
         assert_eq!(results.len(), 1);
         assert_eq!(results[0].recall_count, 0);
         assert!(results[0].last_recalled_at.is_none());
 
         // Second recall — DB was updated by the first recall, so now count=1
-        let results = store.recall(&emb, 5, None, None, None)?;
+        let results = store.recall(&emb, 5, None, None, None)?.memories;
         assert_eq!(results[0].recall_count, 1);
         assert!(results[0].last_recalled_at.is_some());
 
         // Third recall — count should be 2
-        let results = store.recall(&emb, 5, None, None, None)?;
+        let results = store.recall(&emb, 5, None, None, None)?.memories;
         assert_eq!(results[0].recall_count, 2);
         assert!(results[0].last_recalled_at.is_some());
 
@@ -2021,10 +2100,80 @@ mod tests {
         store.memorize("exists", "content", &[], &emb).unwrap();
 
         let result = store.link("exists", "does_not_exist", "related");
-        assert!(result.is_err());
+        assert!(matches!(result, Err(TriviaError::NotFound(_))));
 
         let result = store.link("does_not_exist", "exists", "related");
-        assert!(result.is_err());
+        assert!(matches!(result, Err(TriviaError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_database_failures_are_not_missing_memories() -> Result<()> {
+        let store = MemoryStore::in_memory()?;
+        store.memorize("exists", "content", &[], &[0.1; 384])?;
+        store
+            .conn()
+            .execute_batch("ALTER TABLE memories RENAME TO unreadable")?;
+
+        assert!(matches!(
+            store.link("exists", "exists", "related"),
+            Err(TriviaError::Storage(_))
+        ));
+        assert!(matches!(
+            store.merge("exists", "other", &[0.1; 384]),
+            Err(TriviaError::Storage(_))
+        ));
+        assert!(matches!(
+            store.edit_memory("exists", None, &["t".into()], &[], None, &[], &[], &[]),
+            Err(TriviaError::Storage(_))
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_recall_filtered_skips_unreadable_before_limit() -> Result<()> {
+        let store = MemoryStore::in_memory()?;
+        let axis = |i: usize| {
+            let mut v = vec![0.0; 384];
+            v[i] = 1.0;
+            v
+        };
+        for i in 0..10 {
+            store.memorize(
+                &format!("secret {i}"),
+                "hidden",
+                &["secret".into()],
+                &axis(i),
+            )?;
+        }
+        store.memorize("public", "shown", &["public".into()], &axis(10))?;
+        // Every secret is nearer the query than the public memory
+        let mut query = vec![0.0; 384];
+        query[..10].fill(1.0);
+        query[10] = 0.5;
+
+        let readable = |m: &Memory| !m.tags.iter().any(|t| t == "secret");
+        let recall = store.recall_filtered(&query, 1, None, None, None, &readable)?;
+        let found: Vec<&str> = recall
+            .memories
+            .iter()
+            .map(|m| m.mnemonic.as_str())
+            .collect();
+        assert_eq!(found, ["public"]);
+
+        // Unreadable memories are neither counted as recalled nor logged
+        assert_eq!(
+            store
+                .get_memory_by_mnemonic("secret 0")?
+                .unwrap()
+                .recall_count,
+            0
+        );
+        let logged: i64 =
+            store
+                .conn()
+                .query_row("SELECT COUNT(*) FROM recall_results", [], |row| row.get(0))?;
+        assert_eq!(logged, 1);
+        Ok(())
     }
 
     #[test]
@@ -2037,7 +2186,7 @@ mod tests {
         store.memorize("r2", "other memory", &[], &emb2)?;
         store.link("r1", "r2", "supersedes")?;
 
-        let results = store.recall(&emb1, 5, None, None, None)?;
+        let results = store.recall(&emb1, 5, None, None, None)?.memories;
         let r1 = results.iter().find(|m| m.mnemonic == "r1").unwrap();
         assert!(!r1.links.is_empty(), "recalled memory should include links");
 
@@ -2050,9 +2199,12 @@ mod tests {
         let emb: Vec<f32> = (0..384).map(|i| (i as f32) / 384.0).collect();
         store.memorize("scored", "content", &[], &emb)?;
 
-        let results = store.recall(&emb, 5, None, None, None)?;
+        let results = store.recall(&emb, 5, None, None, None)?.memories;
         assert_eq!(results.len(), 1);
-        assert!(results[0].score > 0.0, "score should be positive for close match");
+        assert!(
+            results[0].score > 0.0,
+            "score should be positive for close match"
+        );
         Ok(())
     }
 
@@ -2072,7 +2224,7 @@ mod tests {
 
         // Query equidistant — freq::a should score higher due to frequency
         let mid: Vec<f32> = (0..384).map(|i| (i as f32) / 384.0 + 0.005).collect();
-        let results = store.recall(&mid, 2, None, None, None)?;
+        let results = store.recall(&mid, 2, None, None, None)?.memories;
         assert_eq!(results.len(), 2);
 
         let a = results.iter().find(|m| m.mnemonic == "freq::a").unwrap();
@@ -2095,11 +2247,14 @@ mod tests {
 
         // Query equidistant
         let mid: Vec<f32> = (0..384).map(|i| (i as f32) / 384.0 + 0.005).collect();
-        let results = store.recall(&mid, 2, None, None, None)?;
+        let results = store.recall(&mid, 2, None, None, None)?.memories;
         let a = results.iter().find(|m| m.mnemonic == "recent::a").unwrap();
         let b = results.iter().find(|m| m.mnemonic == "recent::b").unwrap();
         // a has recency + frequency boost, b has neither
-        assert!(a.score > b.score, "recently recalled memory should score higher");
+        assert!(
+            a.score > b.score,
+            "recently recalled memory should score higher"
+        );
         Ok(())
     }
 
@@ -2119,11 +2274,14 @@ mod tests {
         // Link a and b — both are candidates, so a gets link_boost from b's similarity
         store.link("linked::a", "linked::b", "related")?;
 
-        let results = store.recall(&base, 3, None, None, None)?;
+        let results = store.recall(&base, 3, None, None, None)?.memories;
         let a = results.iter().find(|m| m.mnemonic == "linked::a").unwrap();
         let c = results.iter().find(|m| m.mnemonic == "linked::c").unwrap();
         // a and c have symmetric distances from query, but a has link boost
-        assert!(a.score > c.score, "linked memory should score higher than equidistant unlinked");
+        assert!(
+            a.score > c.score,
+            "linked memory should score higher than equidistant unlinked"
+        );
         Ok(())
     }
 
@@ -2139,7 +2297,7 @@ mod tests {
         store.memorize("merge::new", "new content", &["tag_b".into()], &emb2)?;
 
         // Old should be merged into new
-        let results = store.recall(&emb1, 10, None, None, None)?;
+        let results = store.recall(&emb1, 10, None, None, None)?.memories;
         let mnemonics: Vec<&str> = results.iter().map(|m| m.mnemonic.as_str()).collect();
         assert!(
             !mnemonics.contains(&"merge::old"),
@@ -2159,13 +2317,14 @@ mod tests {
 
     #[test]
     fn test_rate_useful() -> Result<()> {
-        let store = MemoryStore::in_memory()?;
+        let mut store = MemoryStore::in_memory()?;
         let emb: Vec<f32> = vec![0.1; 384];
         store.memorize("rated", "some content", &[], &emb)?;
 
-        store.rate("rated", true)?;
-        store.rate("rated", true)?;
-        store.rate("rated", false)?;
+        for (rater, useful) in [("alice", true), ("bob", true), ("carol", false)] {
+            store.set_actor(Actor::new(Some(rater), crate::audit::Transport::Cli));
+            store.rate("rated", useful, None, None)?;
+        }
 
         let mem = store.get_memory_by_mnemonic("rated")?.unwrap();
         assert_eq!(mem.useful_count, 2);
@@ -2176,8 +2335,8 @@ mod tests {
     #[test]
     fn test_rate_missing_mnemonic() {
         let store = MemoryStore::in_memory().unwrap();
-        let result = store.rate("nonexistent", true);
-        assert!(result.is_err());
+        let result = store.rate("nonexistent", true, None, None);
+        assert!(matches!(result, Err(TriviaError::NotFound(_))));
     }
 
     #[test]
@@ -2191,15 +2350,18 @@ mod tests {
 
         // Rate good up, bad down
         for _ in 0..5 {
-            store.rate("good", true)?;
-            store.rate("bad", false)?;
+            store.rate("good", true, None, None)?;
+            store.rate("bad", false, None, None)?;
         }
 
         let mid: Vec<f32> = (0..384).map(|i| (i as f32) / 384.0 + 0.005).collect();
-        let results = store.recall(&mid, 2, None, None, None)?;
+        let results = store.recall(&mid, 2, None, None, None)?.memories;
         let good = results.iter().find(|m| m.mnemonic == "good").unwrap();
         let bad = results.iter().find(|m| m.mnemonic == "bad").unwrap();
-        assert!(good.score > bad.score, "well-rated memory should score higher");
+        assert!(
+            good.score > bad.score,
+            "well-rated memory should score higher"
+        );
         Ok(())
     }
 
@@ -2220,14 +2382,14 @@ mod tests {
         store.merge("keep", "discard", &emb1)?;
 
         // Discard should be gone
-        let results = store.recall(&emb2, 10, None, None, None)?;
+        let results = store.recall(&emb2, 10, None, None, None)?.memories;
         assert!(
             !results.iter().any(|m| m.mnemonic == "discard"),
             "discard memory should be deleted"
         );
 
         // Keep should have merged content
-        let results = store.recall(&emb1, 10, None, None, None)?;
+        let results = store.recall(&emb1, 10, None, None, None)?.memories;
         let kept = results.iter().find(|m| m.mnemonic == "keep").unwrap();
         assert!(kept.content.contains("keep content"));
         assert!(kept.content.contains("discard content"));
@@ -2237,7 +2399,9 @@ mod tests {
         // Link from discard should have transferred to keep
         let links = store.get_links("keep")?;
         assert!(
-            links.iter().any(|l| l.target_mnemonic == "other" || l.source_mnemonic == "other"),
+            links
+                .iter()
+                .any(|l| l.target_mnemonic == "other" || l.source_mnemonic == "other"),
             "links should transfer from discard to keep"
         );
 
@@ -2277,7 +2441,24 @@ mod tests {
         store.memorize("only", "content", &[], &emb).unwrap();
 
         let result = store.remove_mnemonic("only", "only");
-        assert!(result.is_err(), "should not be able to remove last mnemonic");
+        assert!(
+            matches!(result, Err(TriviaError::Validation(_))),
+            "should not be able to remove last mnemonic"
+        );
+    }
+
+    #[test]
+    fn test_rename_to_existing_title_is_conflict() -> Result<()> {
+        let store = MemoryStore::in_memory()?;
+        store.memorize("first", "a", &[], &vec![0.1; 384])?;
+        store.memorize("second", "b", &[], &vec![-0.1; 384])?;
+
+        let err = store
+            .rename_memory("second", "first", &vec![0.1; 384])
+            .unwrap_err();
+        assert!(matches!(err, TriviaError::Conflict(_)));
+        assert!(!err.is_retryable());
+        Ok(())
     }
 
     #[test]
@@ -2290,7 +2471,7 @@ mod tests {
         store.add_mnemonic("original-name", "alias-name", &alias_emb)?;
 
         // Recall by alias embedding should find the memory
-        let results = store.recall(&alias_emb, 5, None, None, None)?;
+        let results = store.recall(&alias_emb, 5, None, None, None)?.memories;
         assert!(!results.is_empty());
         assert_eq!(results[0].mnemonic, "original-name");
         // Should have both mnemonics
@@ -2313,7 +2494,7 @@ mod tests {
 
         // Recall should return only 1 result (deduped by memory_id)
         let query: Vec<f32> = (0..384).map(|i| (i as f32) / 384.0).collect();
-        let results = store.recall(&query, 5, None, None, None)?;
+        let results = store.recall(&query, 5, None, None, None)?.memories;
         let count = results.iter().filter(|m| m.mnemonic == "multi-mn").count();
         assert_eq!(count, 1, "same memory should appear only once in results");
 
@@ -2334,7 +2515,10 @@ mod tests {
         // Memory should still exist with both mnemonics
         let mem = store.get_memory_by_mnemonic("base-memory")?.unwrap();
         assert_eq!(mem.mnemonics.len(), 2);
-        assert!(mem.content == "content", "content should not be merged with itself");
+        assert!(
+            mem.content == "content",
+            "content should not be merged with itself"
+        );
 
         Ok(())
     }
@@ -2354,8 +2538,11 @@ mod tests {
 
         // keeper should now have goner's mnemonics
         let mem = store.get_memory_by_mnemonic("keeper")?.unwrap();
-        assert!(mem.mnemonics.contains(&"goner".to_string()) || mem.mnemonics.contains(&"goner-alias".to_string()),
-            "merged memory should inherit mnemonics from discard");
+        assert!(
+            mem.mnemonics.contains(&"goner".to_string())
+                || mem.mnemonics.contains(&"goner-alias".to_string()),
+            "merged memory should inherit mnemonics from discard"
+        );
 
         Ok(())
     }
@@ -2370,18 +2557,27 @@ mod tests {
         store.add_mnemonic("deleteme", "deleteme-alias", &alias_emb)?;
 
         // Count mnemonic_vectors before delete
-        let before: i64 = store.conn().query_row(
-            "SELECT COUNT(*) FROM mnemonic_vectors", [], |row| row.get(0),
-        )?;
+        let before: i64 =
+            store
+                .conn()
+                .query_row("SELECT COUNT(*) FROM mnemonic_vectors", [], |row| {
+                    row.get(0)
+                })?;
         assert!(before >= 2);
 
         store.delete_memory("deleteme")?;
 
         // All mnemonic_vectors should be cleaned up
-        let after: i64 = store.conn().query_row(
-            "SELECT COUNT(*) FROM mnemonic_vectors", [], |row| row.get(0),
-        )?;
-        assert_eq!(after, 0, "mnemonic_vectors should be cleaned up after delete");
+        let after: i64 =
+            store
+                .conn()
+                .query_row("SELECT COUNT(*) FROM mnemonic_vectors", [], |row| {
+                    row.get(0)
+                })?;
+        assert_eq!(
+            after, 0,
+            "mnemonic_vectors should be cleaned up after delete"
+        );
 
         Ok(())
     }
@@ -2415,10 +2611,10 @@ mod tests {
 
         let result = store.edit_memory(
             "editable",
-            None,      // no rename
-            &[],       // no add tags
-            &[],       // no remove tags
-            None,      // no re-embed
+            None, // no rename
+            &[],  // no add tags
+            &[],  // no remove tags
+            None, // no re-embed
             &["alias1".into(), "alias2".into()],
             &[],
             &[alias1_emb.clone(), alias2_emb.clone()],
@@ -2431,7 +2627,10 @@ mod tests {
         // Now remove one
         let result = store.edit_memory(
             "editable",
-            None, &[], &[], None,
+            None,
+            &[],
+            &[],
+            None,
             &[],
             &["alias1".into()],
             &[],
@@ -2455,7 +2654,7 @@ mod tests {
         assert_eq!(mem.mnemonics, vec!["single"]);
 
         // recall should also populate mnemonics
-        let results = store.recall(&emb, 5, None, None, None)?;
+        let results = store.recall(&emb, 5, None, None, None)?.memories;
         assert_eq!(results[0].mnemonics, vec!["single"]);
 
         Ok(())
@@ -2471,7 +2670,10 @@ mod tests {
         store.add_mnemonic("summary-test", "summary-alias", &alias_emb)?;
 
         let summaries = store.list_all_summaries()?;
-        let s = summaries.iter().find(|s| s.mnemonic == "summary-test").unwrap();
+        let s = summaries
+            .iter()
+            .find(|s| s.mnemonic == "summary-test")
+            .unwrap();
         assert_eq!(s.mnemonics.len(), 2);
 
         Ok(())
@@ -0,0 +1,359 @@
+//! Fitting the recall scoring weights to the ratings a store has collected.
+//! Each rating given with a recall id is an example: the rated result's
+//! score components, logged when it was recalled, and whether it was found
+//! useful. A logistic regression over most recalls gives the weights; the
+//! rest are held out to check the new weights order results better than
+//! the ones in use.
+
+use std::cmp::Ordering;
+
+use crate::config::ScoringWeights;
+use crate::error::{Result, TriviaError};
+use crate::migrations::RECALL_FEATURE_COLUMNS;
+use crate::ratings::LATEST_PER_RATER_AND_RECALL;
+use crate::store::{MemoryStore, ScoreFeatures, ScoringConfig};
+
+/// Fewest rated results worth fitting weights to.
+pub const MIN_RATED_RESULTS: usize = 20;
+
+const ITERATIONS: usize = 2000;
+const LEARNING_RATE: f64 = 0.1;
+/// Keeps weights small when the ratings barely support them.
+const L2_PENALTY: f64 = 0.01;
+
+/// One rater's latest verdict on a result of a logged recall.
+#[derive(Debug, Clone)]
+pub struct RatedResult {
+    pub recall_id: i64,
+    pub features: ScoreFeatures,
+    pub useful: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct TuneReport {
+    /// Rated results the weights were fitted to.
+    pub fitted: usize,
+    /// Rated results held out to compare the weights on.
+    pub held_out: usize,
+    /// Share of held-out (useful, not useful) pairs from the same recall
+    /// that the current weights score the right way round, ties counting
+    /// half.
+    pub current_accuracy: f64,
+    /// The same share for the tuned weights.
+    pub tuned_accuracy: f64,
+    /// Tuned weights of the score components that varied among the rated
+    /// results. The others keep their current weight.
+    pub weights: ScoringWeights,
+}
+
+impl TuneReport {
+    pub fn improved(&self) -> bool {
+        self.tuned_accuracy > self.current_accuracy
+    }
+}
+
+/// Every fifth recall is held out, so results of one recall are never
+/// split between fitting and checking.
+fn is_held_out(result: &RatedResult) -> bool {
+    result.recall_id % 5 == 0
+}
+
+fn has_both_verdicts(results: &[&RatedResult]) -> bool {
+    results.iter().any(|r| r.useful) && results.iter().any(|r| !r.useful)
+}
+
+/// Every (useful, not useful) pair of `results` from the same recall.
+/// Scores are only ever compared within one recall, so pairs across
+/// recalls say nothing about the ranking.
+fn same_recall_pairs<'a>(results: &[&'a RatedResult]) -> Vec<(&'a RatedResult, &'a RatedResult)> {
+    let mut pairs = Vec::new();
+    for &u in results.iter().filter(|r| r.useful) {
+        for &n in results
+            .iter()
+            .filter(|r| !r.useful && r.recall_id == u.recall_id)
+        {
+            pairs.push((u, n));
+        }
+    }
+    pairs
+}
+
+/// Fit scoring weights to `results` and compare them with `current` on
+/// the held-out recalls.
+pub fn tune(results: &[RatedResult], current: &ScoringConfig) -> Result<TuneReport> {
+    let (held_out, fitted): (Vec<&RatedResult>, Vec<&RatedResult>) =
+        results.iter().partition(|r| is_held_out(r));
+    let held_out_pairs = same_recall_pairs(&held_out);
+    if results.len() < MIN_RATED_RESULTS || !has_both_verdicts(&fitted) || held_out_pairs.is_empty()
+    {
+        return Err(TriviaError::validation(format!(
+            "not enough ratings to tune: {} rated recall results, need at least {MIN_RATED_RESULTS} \
+             with both useful and not useful ones among those fitted, and a held-out recall \
+             with both; rate recalled memories with their recall id",
+            results.len()
+        )));
+    }
+
+    let weights = fit(&fitted)?;
+    let mut tuned = current.clone();
+    tuned.apply_weights(&weights);
+    Ok(TuneReport {
+        fitted: fitted.len(),
+        held_out: held_out.len(),
+        current_accuracy: pairwise_accuracy(&held_out_pairs, current),
+        tuned_accuracy: pairwise_accuracy(&held_out_pairs, &tuned),
+        weights,
+    })
+}
+
+/// Logistic regression of the verdicts on the score components, with the
+/// weights kept non-negative since every component is meant as a boost.
+/// Weights are scaled so similarity keeps a weight of 1, as in the
+/// defaults, which keeps scores comparable with `min_score` settings.
+fn fit(results: &[&RatedResult]) -> Result<ScoringWeights> {
+    let n = results.len() as f64;
+    let rows: Vec<[f64; 8]> = results.iter().map(|r| r.features.to_array()).collect();
+    let mean: [f64; 8] = std::array::from_fn(|j| rows.iter().map(|row| row[j]).sum::<f64>() / n);
+    let spread: [f64; 8] = std::array::from_fn(|j| {
+        (rows
+            .iter()
+            .map(|row| (row[j] - mean[j]).powi(2))
+            .sum::<f64>()
+            / n)
+            .sqrt()
+    });
+    // Standardized, so one learning rate suits every component; components
+    // that never varied carry no information and stay at zero
+    let standardized: Vec<[f64; 8]> = rows
+        .iter()
+        .map(|row| {
+            std::array::from_fn(|j| {
+                if spread[j] > 0.0 {
+                    (row[j] - mean[j]) / spread[j]
+                } else {
+                    0.0
+                }
+            })
+        })
+        .collect();
+    let labels: Vec<f64> = results
+        .iter()
+        .map(|r| if r.useful { 1.0 } else { 0.0 })
+        .collect();
+
+    let mut weights = [0.0_f64; 8];
+    let mut bias = 0.0;
+    for _ in 0..ITERATIONS {
+        let mut gradient = [0.0_f64; 8];
+        let mut bias_gradient = 0.0;
+        for (row, label) in standardized.iter().zip(&labels) {
+            let z = bias + weights.iter().zip(row).map(|(w, x)| w * x).sum::<f64>();
+            let error = 1.0 / (1.0 + (-z).exp()) - label;
+            for (g, x) in gradient.iter_mut().zip(row) {
+                *g += error * x;
+            }
+            bias_gradient += error;
+        }
+        for (w, g) in weights.iter_mut().zip(gradient) {
+            *w = (*w - LEARNING_RATE * (g / n + L2_PENALTY * *w)).max(0.0);
+        }
+        bias -= LEARNING_RATE * bias_gradient / n;
+    }
+
+    let raw: [Option<f64>; 8] = std::array::from_fn(|j| {
+        if spread[j] > 0.0 {
+            Some(weights[j] / spread[j])
+        } else {
+            None
+        }
+    });
+    let scale = match raw[0] {
+        Some(similarity) if similarity > 0.0 => similarity,
+        _ => raw.iter().flatten().copied().fold(0.0, f64::max),
+    };
+    if scale <= 0.0 {
+        return Err(TriviaError::validation(
+            "the ratings so far do not tell useful results from the rest; keep the current weights",
+        ));
+    }
+    let [
+        similarity,
+        recency,
+        frequency,
+        link,
+        rating,
+        query,
+        tag_boost,
+        fts,
+    ] = raw.map(|w| w.map(|w| w / scale));
+    Ok(ScoringWeights {
+        similarity_weight: similarity,
+        recency_weight: recency,
+        frequency_weight: frequency,
+        link_weight: link,
+        rating_weight: rating,
+        query_weight: query,
+        tag_boost_weight: tag_boost,
+        fts_weight: fts,
+    })
+}
+
+/// Share of (useful, not useful) `pairs` that `scoring` scores the right
+/// way round, ties counting half.
+fn pairwise_accuracy(pairs: &[(&RatedResult, &RatedResult)], scoring: &ScoringConfig) -> f64 {
+    let right: f64 = pairs
+        .iter()
+        .map(|(u, n)| {
+            match scoring
+                .score(&u.features)
+                .partial_cmp(&scoring.score(&n.features))
+            {
+                Some(Ordering::Greater) => 1.0,
+                Some(Ordering::Equal) => 0.5,
+                _ => 0.0,
+            }
+        })
+        .sum();
+    right / pairs.len() as f64
+}
+
+impl MemoryStore {
+    /// Each rater's latest verdict on each result of a logged recall, with
+    /// the result's score components. Results logged before those were
+    /// recorded are left out.
+    pub fn rated_results(&self) -> Result<Vec<RatedResult>> {
+        let columns: Vec<String> = RECALL_FEATURE_COLUMNS
+            .iter()
+            .map(|c| format!("rr.{c}"))
+            .collect();
+        let mut stmt = self.conn().prepare(&format!(
+            "SELECT rr.recall_id, r.useful, {}
+             FROM ratings r
+             JOIN recall_results rr ON rr.recall_id = r.recall_id AND rr.memory_id = r.memory_id
+             WHERE rr.similarity IS NOT NULL AND {LATEST_PER_RATER_AND_RECALL}
+             ORDER BY r.id",
+            columns.join(", ")
+        ))?;
+        let results = stmt
+            .query_map([], |row| {
+                let mut features = [0.0; 8];
+                for (j, value) in features.iter_mut().enumerate() {
+                    *value = row.get(j + 2)?;
+                }
+                Ok(RatedResult {
+                    recall_id: row.get(0)?,
+                    useful: row.get(1)?,
+                    features: ScoreFeatures::from_array(features),
+                })
+            })?
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        Ok(results)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Results where usefulness follows the rating signal and not
+    /// similarity, spread over `recalls` recalls.
+    fn rated_by_signal(recalls: i64) -> Vec<RatedResult> {
+        (0..recalls)
+            .flat_map(|recall_id| {
+                [
+                    (0.6, 0.8, true),
+                    (0.7, -0.6, false),
+                    (0.5, 0.4, true),
+                    (0.8, -0.9, false),
+                ]
+                .into_iter()
+                .map(move |(similarity, rating, useful)| RatedResult {
+                    recall_id,
+                    features: ScoreFeatures {
+                        similarity: similarity + (recall_id % 3) as f64 * 0.01,
+                        rating,
+                        ..Default::default()
+                    },
+                    useful,
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn learns_what_the_ratings_reward() -> Result<()> {
+        let current = ScoringConfig::default();
+        let report = tune(&rated_by_signal(10), &current)?;
+        assert_eq!((report.fitted, report.held_out), (32, 8));
+        assert!(report.improved(), "{report:?}");
+        assert_eq!(report.tuned_accuracy, 1.0);
+        let rating = report.weights.rating_weight.unwrap();
+        assert!(rating > current.rating_weight, "{report:?}");
+        // Components that never varied keep their current weight
+        assert_eq!(report.weights.fts_weight, None);
+        Ok(())
+    }
+
+    #[test]
+    fn needs_enough_ratings() {
+        let few = rated_by_signal(3);
+        assert!(matches!(
+            tune(&few, &ScoringConfig::default()),
+            Err(TriviaError::Validation(_))
+        ));
+        let all_useful: Vec<RatedResult> = rated_by_signal(10)
+            .into_iter()
+            .filter(|r| r.useful)
+            .collect();
+        assert!(matches!(
+            tune(&all_useful, &ScoringConfig::default()),
+            Err(TriviaError::Validation(_))
+        ));
+    }
+
+    #[test]
+    fn compares_results_within_a_recall() {
+        let result = |recall_id, similarity, useful| RatedResult {
+            recall_id,
+            features: ScoreFeatures {
+                similarity,
+                ..Default::default()
+            },
+            useful,
+        };
+        // Each recall ranks its useful result first, though the useful one
+        // of the first recall scores below the other recall's results
+        let results = [
+            result(0, 0.3, true),
+            result(0, 0.2, false),
+            result(5, 0.9, true),
+            result(5, 0.8, false),
+        ];
+        let refs: Vec<&RatedResult> = results.iter().collect();
+        let pairs = same_recall_pairs(&refs);
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairwise_accuracy(&pairs, &ScoringConfig::default()), 1.0);
+        // A recall rated only one way has no pairs
+        assert!(same_recall_pairs(&refs[..1]).is_empty());
+    }
+
+    #[test]
+    fn rated_results_come_from_logged_recalls() -> Result<()> {
+        let store = MemoryStore::in_memory()?;
+        let emb: Vec<f32> = (0..384).map(|i| (i as f32) / 384.0).collect();
+        store.memorize("tip", "useful", &[], &emb)?;
+        store.rate("tip", true, None, None)?;
+        let recall = store.recall(&emb, 1, None, None, None)?;
+        store.rate("tip", false, None, Some(&recall.id))?;
+        store.rate("tip", true, None, Some(&recall.id))?;
+
+        let results = store.rated_results()?;
+        assert_eq!(results.len(), 1);
+        assert!(results[0].useful);
+        assert!((results[0].features.similarity - 1.0).abs() < 1e-6);
+        // The logged components reproduce the score the result was ranked by
+        let score = ScoringConfig::default().score(&results[0].features);
+        assert_eq!(score, recall.memories[0].score);
+        Ok(())
+    }
+}